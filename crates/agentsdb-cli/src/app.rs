@@ -1,10 +1,29 @@
-use crate::cli::{AllowlistCommand, Cli, Command, LayerArgs, OptionsCommand, ProposalsCommand};
+use anyhow::Context;
+
+use crate::cli::{
+    AllowlistCommand, Cli, Command, IngestArgs, KindsCommand, LayerArgs, OptionsCommand,
+    ProposalsCommand, SyncCommand, WorkspaceCommand,
+};
+use crate::commands::export::VectorStoreTarget;
+use crate::commands::import::ImportSource;
+use crate::commands::import_markdown::MarkdownImportOptions;
 
 /// Runs the main application logic based on the provided CLI arguments.
 ///
 /// This function dispatches to the appropriate command handler based on the `cli.cmd` value.
-pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
-    let json = cli.json;
+pub(crate) fn run(mut cli: Cli) -> anyhow::Result<()> {
+    let json = cli.json_output()?;
+    crate::util::init_logging(&cli.log_level, cli.log_format)?;
+    agentsdb_ops::hooks::register(std::sync::Arc::new(agentsdb_ops::hooks::PolicyHook));
+    if let Some(root) = cli.project_root.as_deref() {
+        std::env::set_current_dir(root).with_context(|| format!("enter project root {root}"))?;
+    }
+    default_dir_to_project(&mut cli.cmd);
+    // A broken local options chunk must not lock out `agentsdb options`, which repairs it.
+    let identity_dir = crate::util::project_dir().unwrap_or_else(|| ".".into());
+    if let Err(err) = agentsdb_ops::identity::install_for_dir(&identity_dir) {
+        tracing::warn!("ignoring identity settings: {err:#}");
+    }
     match cli.cmd {
         Command::List { root } => crate::commands::list::cmd_list(&root, json),
         Command::Init {
@@ -14,6 +33,10 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
             dim,
             element_type,
             quant_scale,
+            template,
+            wizard,
+            backend,
+            model,
         } => crate::commands::init::cmd_init(
             &root,
             &out,
@@ -21,22 +44,106 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
             dim,
             &element_type,
             quant_scale,
+            template.as_deref(),
+            wizard,
+            backend.as_deref(),
+            model.as_deref(),
             json,
         ),
-        Command::Validate { path } => crate::commands::validate::cmd_validate(&path, json),
+        Command::Validate {
+            path,
+            embedding_drift: true,
+            fix,
+            allow_base,
+        } => crate::commands::validate::cmd_validate_drift(&path, fix, allow_base, json),
+        Command::Validate { path, .. } => crate::commands::validate::cmd_validate(&path, json),
         Command::Inspect { layer, id, path } => {
             crate::commands::inspect::cmd_inspect(layer.as_deref(), path.as_deref(), id, json)
         }
-        Command::Serve { layers } => {
-            if json {
-                anyhow::bail!("--json is not supported for serve");
+        Command::Serve {
+            layers,
+            check,
+            allow_proposal_review,
+            max_in_flight,
+            read_only,
+            no_propose,
+            write_scopes,
+            config_file,
+            audit_log,
+            audit_reads,
+            metrics_file,
+            workspace,
+            search_k,
+            search_layers,
+            search_kinds,
+            min_confidence,
+        } => {
+            // Flags win; anything left unset falls back to the `server.*` settings.
+            let resolved =
+                crate::commands::options::server_settings(&layers, config_file.as_deref())?;
+            let settings = resolved.server();
+            let read_only = read_only || settings.read_only;
+            let no_propose = no_propose || settings.no_propose;
+            let write_scopes = if write_scopes.is_empty() {
+                settings.write_scopes
+            } else {
+                write_scopes
+            };
+            let search_layers = if search_layers.is_empty() {
+                settings.search_layers
+            } else {
+                search_layers
+            };
+            let search_kinds = if search_kinds.is_empty() {
+                settings.search_kinds
+            } else {
+                search_kinds
+            };
+            let min_confidence = min_confidence.or(settings.min_confidence);
+            if min_confidence.is_some_and(|c| !(0.0..=1.0).contains(&c)) {
+                anyhow::bail!("--min-confidence must be between 0.0 and 1.0");
             }
-            agentsdb_mcp::serve_stdio(agentsdb_mcp::ServerConfig {
+            let policy = agentsdb_mcp::ToolPolicy {
+                allow_write: !read_only,
+                allow_propose: !read_only && !no_propose,
+                write_scopes,
+            };
+            let config = agentsdb_mcp::ServerConfig {
                 base: layers.base,
                 user: layers.user,
                 delta: layers.delta,
                 local: layers.local,
-            })
+                allow_proposal_review: allow_proposal_review && !read_only,
+                max_in_flight: max_in_flight.unwrap_or(settings.max_in_flight),
+                policy,
+                config_file,
+                audit_log,
+                audit_reads: audit_reads || settings.audit_reads,
+                metrics_file,
+                session: None,
+                trust: resolved.trust(),
+                workspace,
+                search_k: search_k.unwrap_or(settings.search_k),
+                search_layers,
+                search_kinds,
+                min_confidence,
+            };
+            if check {
+                let report = agentsdb_mcp::check(config);
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                if !report.ok {
+                    anyhow::bail!("startup check failed");
+                }
+                return Ok(());
+            }
+            if json {
+                anyhow::bail!("JSON output (--json, --output json) is not supported for serve");
+            }
+            // SIGINT/SIGTERM stop reading requests; in-flight ones are still answered.
+            let shutdown = agentsdb_mcp::ShutdownTrigger::default();
+            let trigger = shutdown.clone();
+            ctrlc::set_handler(move || trigger.shutdown()).context("install signal handler")?;
+            agentsdb_mcp::serve_stdio_until(config, &shutdown)
         }
         Command::Compile {
             input,
@@ -50,6 +157,10 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
             dim,
             element_type,
             quant_scale,
+            embedding_layout,
+            deterministic,
+            manifest,
+            sign_key,
         } => crate::commands::compile::cmd_compile(
             input.as_deref(),
             &out,
@@ -62,6 +173,47 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
             dim,
             &element_type,
             quant_scale,
+            embedding_layout.as_deref(),
+            deterministic,
+            manifest.as_deref(),
+            sign_key.as_deref(),
+            json,
+        ),
+        Command::Ingest {
+            ingest,
+            incremental,
+        } => crate::commands::ingest::cmd_ingest(&ingest_config(ingest, incremental, false), json),
+        Command::Watch {
+            ingest,
+            interval_ms,
+            debounce_ms,
+        } => crate::commands::watch::cmd_watch(
+            &ingest_config(ingest, true, true),
+            std::time::Duration::from_millis(interval_ms),
+            std::time::Duration::from_millis(debounce_ms),
+            json,
+        ),
+        Command::Write {
+            path,
+            scope,
+            kind,
+            confidence,
+            from_file: Some(from_file),
+            dim,
+            namespace,
+            language,
+            ..
+        } => crate::commands::write::cmd_write_records(
+            &path,
+            &scope,
+            &from_file,
+            &crate::commands::write::RecordDefaults {
+                kind,
+                confidence,
+                namespace,
+                language,
+            },
+            dim,
             json,
         ),
         Command::Write {
@@ -71,51 +223,70 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
             kind,
             content,
             confidence,
+            from_file: None,
             embedding,
             dim,
             sources,
             source_chunks,
+            namespace,
+            language,
         } => crate::commands::write::cmd_write(
             &path,
             &scope,
             id,
-            &kind,
-            &content,
-            confidence,
+            &kind.context("--kind is required")?,
+            &content.context("--content is required")?,
+            confidence.context("--confidence is required")?,
             embedding.as_deref(),
             dim,
             &sources,
             &source_chunks,
+            namespace.as_deref(),
+            language.as_deref(),
             json,
         ),
         Command::Search {
             layers,
             query,
+            query_text,
             query_vec,
             query_vec_file,
             k,
             kinds,
+            namespace,
+            language,
             use_index,
+            disk_cache,
             mode,
+            assemble,
+            budget_tokens,
+            workspace,
         } => crate::commands::search::cmd_search(
-            layerset(layers),
-            query,
+            layerset(layers)?,
+            query.or(query_text),
             query_vec,
             query_vec_file,
             k,
             kinds,
+            namespace,
+            language,
             use_index,
+            disk_cache,
             mode,
+            assemble.then_some(budget_tokens),
+            workspace,
             json,
         ),
         Command::Index {
             layers,
             out_dir,
             store_embeddings_f32,
+            binary,
         } => crate::commands::index::cmd_index(
-            layerset(layers),
+            layerset(layers)?,
             out_dir.as_deref(),
             store_embeddings_f32,
+            binary,
             json,
         ),
         Command::Export {
@@ -124,17 +295,51 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
             layers,
             out,
             redact,
-        } => crate::commands::export::cmd_export(
-            &dir,
-            &format,
-            &layers,
-            out.as_deref(),
-            &redact,
-            json,
-        ),
+            table,
+            qdrant_url,
+            collection,
+            batch_size,
+            namespace,
+        } => match format.as_str() {
+            "pgvector" | "qdrant" if namespace.is_some() => {
+                anyhow::bail!("--namespace is not supported with --format {format}")
+            }
+            "pgvector" | "qdrant" => crate::commands::export::cmd_export_vector_store(
+                &dir,
+                &layers,
+                &match qdrant_url {
+                    Some(url) if format == "qdrant" => {
+                        VectorStoreTarget::Qdrant { url, collection }
+                    }
+                    _ => VectorStoreTarget::Pgvector { table, out },
+                },
+                &redact,
+                batch_size,
+                json,
+            ),
+            _ => crate::commands::export::cmd_export(
+                &dir,
+                &format,
+                &layers,
+                out.as_deref(),
+                &redact,
+                namespace.as_deref(),
+                json,
+            ),
+        },
         Command::Import {
             dir,
             input,
+            path,
+            format,
+            chunk_size,
+            overlap,
+            kind,
+            table,
+            query,
+            collection,
+            tenant,
+            database,
             target,
             out,
             dry_run,
@@ -142,9 +347,24 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
             preserve_ids,
             allow_base,
             dim,
+            price_per_million_tokens,
         } => crate::commands::import::cmd_import(
             &dir,
-            &input,
+            &input.or(path).unwrap_or_default(),
+            &match format.as_deref() {
+                Some("markdown") => ImportSource::Markdown(MarkdownImportOptions {
+                    chunk_size,
+                    overlap,
+                    kind,
+                }),
+                Some("sqlite") => ImportSource::Sqlite { table, query },
+                Some("chroma") => ImportSource::Chroma {
+                    collection: collection.unwrap_or_default(),
+                    tenant,
+                    database,
+                },
+                _ => ImportSource::Export { format },
+            },
             target.as_deref(),
             out.as_deref(),
             dry_run,
@@ -152,6 +372,7 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
             preserve_ids,
             allow_base,
             dim,
+            price_per_million_tokens,
             json,
         ),
         Command::Diff {
@@ -159,52 +380,275 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
             delta,
             target,
             user,
-        } => {
-            crate::commands::diff::cmd_diff(&base, &delta, target.as_deref(), user.as_deref(), json)
-        }
+            semantic,
+            threshold,
+        } => crate::commands::diff::cmd_diff(
+            &base,
+            &delta,
+            target.as_deref(),
+            user.as_deref(),
+            semantic,
+            threshold,
+            json,
+        ),
         Command::Promote {
             from_path,
             to_path,
             ids,
+            namespace,
+            via_pr: true,
+            ..
+        } => crate::commands::promote::cmd_promote_via_pr(
+            &from_path,
+            &to_path,
+            ids.as_deref(),
+            namespace.as_deref(),
+            json,
+        ),
+        Command::Promote {
+            from_path,
+            to_path,
+            ids,
+            namespace,
             skip_existing,
+            strategy,
+            interactive,
             yes,
+            via_pr: false,
         } => crate::commands::promote::cmd_promote(
             &from_path,
             &to_path,
-            &ids,
+            ids.as_deref(),
+            namespace.as_deref(),
             skip_existing,
+            match (strategy, interactive) {
+                (Some(strategy), _) => {
+                    crate::commands::promote::Conflicts::Strategy(strategy.parse()?)
+                }
+                (None, true) => crate::commands::promote::Conflicts::Ask,
+                (None, false) => crate::commands::promote::Conflicts::NewIds,
+            },
             yes,
             json,
         ),
+        Command::Stats { layers } => crate::commands::stats::cmd_stats(&layerset(layers)?, json),
+        Command::Mergetool {
+            base,
+            ours,
+            theirs,
+            out,
+            prefer,
+            install,
+        } => match (install, base, ours, theirs) {
+            (false, Some(base), Some(ours), Some(theirs)) => {
+                crate::commands::mergetool::cmd_mergetool(
+                    &base,
+                    &ours,
+                    &theirs,
+                    out.as_deref(),
+                    prefer.as_deref(),
+                    json,
+                )
+            }
+            _ => crate::commands::mergetool::cmd_mergetool_install(json),
+        },
+        Command::Sync { cmd } => match cmd {
+            SyncCommand::Push {
+                layer,
+                remote,
+                dir,
+                force,
+            } => crate::commands::sync::cmd_sync_push(&dir, &layer, remote.as_deref(), force, json),
+            SyncCommand::Pull {
+                layer,
+                remote,
+                dir,
+                force,
+                merge,
+            } => crate::commands::sync::cmd_sync_pull(
+                &dir,
+                &layer,
+                remote.as_deref(),
+                force,
+                merge,
+                json,
+            ),
+        },
+        Command::Dedupe {
+            layers,
+            threshold,
+            same_layer,
+            apply,
+            interactive,
+            merge,
+            fail_on_duplicates,
+        } => crate::commands::dedupe::cmd_dedupe(
+            &layerset(layers)?,
+            agentsdb_ops::dedupe::DedupeOptions {
+                threshold,
+                same_layer_only: same_layer,
+            },
+            apply,
+            interactive,
+            merge,
+            fail_on_duplicates,
+            json,
+        ),
+        Command::Sign {
+            path,
+            key,
+            generate_key,
+        } => crate::commands::sign::cmd_sign(
+            path.as_deref(),
+            key.as_deref(),
+            generate_key.as_deref(),
+            json,
+        ),
+        Command::Verify { layers, signature } => {
+            crate::commands::verify::cmd_verify(&layerset(layers)?, signature, json)
+        }
+        Command::VerifyProvenance { layers, fix } => {
+            crate::commands::verify_provenance::cmd_verify_provenance(&layerset(layers)?, fix, json)
+        }
+        Command::Check {
+            dir,
+            manifest,
+            max_unreviewed_days,
+        } => {
+            crate::commands::check::cmd_check(&dir, manifest.as_deref(), max_unreviewed_days, json)
+        }
+        Command::Review {
+            layers,
+            stale,
+            threshold,
+            decay,
+            max_age_days,
+            factor,
+            confirm,
+            confidence,
+        } => crate::commands::review::cmd_review(
+            &layerset(layers)?,
+            crate::commands::review::ReviewArgs {
+                stale,
+                threshold,
+                decay: decay
+                    .then(|| agentsdb_ops::review::DecayOptions::from_days(max_age_days, factor)),
+                confirm: &confirm,
+                confidence,
+            },
+            json,
+        ),
+        Command::Show { layers, id } => {
+            crate::commands::show::cmd_show(&layerset(layers)?, id, json)
+        }
+        Command::Top {
+            layers,
+            audit_log,
+            limit,
+            period,
+        } => crate::commands::top::cmd_top(
+            &layerset(layers)?,
+            &audit_log,
+            agentsdb_ops::usage::UsageOptions {
+                limit,
+                period: period.parse()?,
+            },
+            json,
+        ),
+        Command::Undo {
+            op_id,
+            dir,
+            allow_base,
+        } => crate::commands::undo::cmd_undo(&dir, op_id, allow_base, json),
+        Command::Repl { layers, k, history } => {
+            crate::commands::repl::cmd_repl(layerset(layers)?, k, history.as_deref(), json)
+        }
+        Command::Completions { shell } => {
+            crate::commands::completions::cmd_completions(&shell, json)
+        }
+        Command::Compact {
+            apply_policy: true, ..
+        } => crate::commands::compact::cmd_compact_apply_policy(json),
         Command::Compact {
             base,
             user,
             out,
             remove_proposals,
+            embedding_layout,
+            apply_policy: false,
         } => crate::commands::compact::cmd_compact(
             base.as_deref(),
             user.as_deref(),
             out.as_deref(),
             remove_proposals,
+            embedding_layout.as_deref(),
             json,
         ),
         Command::Reembed {
             dir,
             layers,
             allow_base,
-        } => crate::commands::reembed::cmd_reembed(&dir, &layers, allow_base, json),
+            price_per_million_tokens,
+            batch_size,
+            resume,
+            retries,
+        } => crate::commands::reembed::cmd_reembed(
+            &dir,
+            &layers,
+            allow_base,
+            price_per_million_tokens,
+            batch_size,
+            resume,
+            retries,
+            json,
+        ),
         Command::Smash {
             dir,
             layers,
             limit,
             allow_base,
-        } => crate::commands::smash::cmd_smash(&dir, &layers, limit, allow_base, json),
+            only,
+            exclude_layers,
+            dry_run,
+        } => crate::commands::smash::cmd_smash(
+            &dir,
+            &layers,
+            limit,
+            allow_base,
+            only.as_deref(),
+            &exclude_layers,
+            dry_run,
+            json,
+        ),
         Command::Destroy { root, dry_run } => crate::commands::destroy::cmd_destroy(&root, dry_run, json),
-        Command::Web { root, bind } => {
+        Command::Web {
+            root,
+            bind,
+            auth_token,
+            anonymous_read,
+            read_only,
+            users_file,
+            allow_self_review,
+            check,
+        } => {
+            if check {
+                return crate::commands::web::cmd_web_check(&root, &bind);
+            }
             if json {
-                anyhow::bail!("--json is not supported for web");
+                anyhow::bail!("JSON output (--json, --output json) is not supported for web");
             }
-            crate::commands::web::cmd_web(&root, &bind)
+            let users = match users_file.as_deref() {
+                Some(path) => agentsdb_web::read_users_file(std::path::Path::new(path))?,
+                None => Vec::new(),
+            };
+            let auth = agentsdb_web::WebAuth {
+                token: auth_token.filter(|t| !t.is_empty()),
+                anonymous_read,
+                read_only,
+                users,
+                allow_self_review,
+            };
+            crate::commands::web::cmd_web(&root, &bind, auth)
         }
         Command::Options { dir, cmd } => match cmd {
             OptionsCommand::Show { layers } => crate::commands::options::cmd_options_show(
@@ -215,6 +659,18 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
                 layers.local.as_deref(),
                 json,
             ),
+            OptionsCommand::Explain {
+                layers,
+                config_file,
+            } => crate::commands::options::cmd_options_explain(
+                &dir,
+                layers.base.as_deref(),
+                layers.user.as_deref(),
+                layers.delta.as_deref(),
+                layers.local.as_deref(),
+                config_file.as_deref(),
+                json,
+            ),
             OptionsCommand::Set {
                 scope,
                 backend,
@@ -227,6 +683,7 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
                 api_key_env,
                 cache,
                 cache_dir,
+                settings,
             } => crate::commands::options::cmd_options_set(
                 &dir,
                 &scope,
@@ -240,6 +697,7 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
                 api_key_env.as_deref(),
                 cache.map(|t| matches!(t, crate::cli::Toggle::On)),
                 cache_dir.as_deref(),
+                &settings,
                 json,
             ),
             OptionsCommand::Wizard { scope: _ } => {
@@ -286,6 +744,31 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
                 }
             },
         },
+        Command::Kinds { dir, cmd } => match cmd {
+            KindsCommand::List => crate::commands::kinds::cmd_kinds_list(&dir, json),
+            KindsCommand::Add {
+                name,
+                description,
+                scope,
+                validation,
+            } => crate::commands::kinds::cmd_kinds_add(
+                &dir,
+                &name,
+                &description,
+                &scope,
+                validation.as_deref(),
+                json,
+            ),
+        },
+        Command::Workspace { cmd } => match cmd {
+            WorkspaceCommand::Add { root, name } => {
+                crate::commands::workspace::cmd_workspace_add(&root, name.as_deref(), json)
+            }
+            WorkspaceCommand::Remove { project } => {
+                crate::commands::workspace::cmd_workspace_remove(&project, json)
+            }
+            WorkspaceCommand::List => crate::commands::workspace::cmd_workspace_list(json),
+        },
         Command::Proposals {
             dir,
             delta,
@@ -313,6 +796,7 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
                 ids,
                 skip_existing,
                 yes,
+                approver,
             } => crate::commands::proposals::cmd_proposals_accept(
                 &dir,
                 delta.as_deref(),
@@ -321,6 +805,7 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
                 &ids,
                 skip_existing,
                 yes,
+                &approver,
                 json,
             ),
             ProposalsCommand::Reject { ids, reason } => {
@@ -338,9 +823,60 @@ pub(crate) fn run(cli: Cli) -> anyhow::Result<()> {
     }
 }
 
-fn layerset(layers: LayerArgs) -> agentsdb_query::LayerSet {
+fn ingest_config(
+    args: IngestArgs,
+    incremental: bool,
+    tombstone_deleted: bool,
+) -> crate::commands::ingest::IngestConfig {
+    crate::commands::ingest::IngestConfig {
+        root: args.root,
+        out: args.out,
+        includes: args.includes,
+        excludes: args.excludes,
+        chunk_size: args.chunk_size,
+        overlap: args.overlap,
+        kind: args.kind,
+        dim: args.dim,
+        incremental,
+        tombstone_deleted,
+    }
+}
+
+/// Points a `--dir` left at its default (`.`) at the project directory, so commands run from a
+/// subdirectory still find the standard layers.
+fn default_dir_to_project(cmd: &mut Command) {
+    let dir = match cmd {
+        Command::Export { dir, .. }
+        | Command::Import { dir, .. }
+        | Command::Undo { dir, .. }
+        | Command::Reembed { dir, .. }
+        | Command::Smash { dir, .. }
+        | Command::Options { dir, .. }
+        | Command::Kinds { dir, .. }
+        | Command::Proposals { dir, .. }
+        | Command::Sync {
+            cmd: SyncCommand::Push { dir, .. } | SyncCommand::Pull { dir, .. },
+        } => dir,
+        _ => return,
+    };
+    if dir.as_str() == "." {
+        if let Some(project) = crate::util::project_dir() {
+            *dir = project.to_string_lossy().into_owned();
+        }
+    }
+}
+
+/// A standard layer file name, inside the project directory when run from a subdirectory.
+fn in_project(project: Option<&std::path::Path>, file_name: &str) -> String {
+    match project {
+        Some(dir) => dir.join(file_name).to_string_lossy().into_owned(),
+        None => file_name.to_string(),
+    }
+}
+
+fn layerset(layers: LayerArgs) -> anyhow::Result<agentsdb_query::LayerSet> {
     // If all layers are None, auto-discover standard layer files in the current directory
-    if layers.base.is_none()
+    let mut set = if layers.base.is_none()
         && layers.user.is_none()
         && layers.delta.is_none()
         && layers.local.is_none()
@@ -350,9 +886,9 @@ fn layerset(layers: LayerArgs) -> agentsdb_query::LayerSet {
         // Auto-discover AGENTS.db if base is not explicitly provided
         // This ensures embedding options are always available from the base layer
         let base = layers.base.or_else(|| {
-            let path = "AGENTS.db";
-            if std::path::Path::new(path).exists() {
-                Some(path.to_string())
+            let path = in_project(crate::util::project_dir().as_deref(), "AGENTS.db");
+            if std::path::Path::new(&path).exists() {
+                Some(path)
             } else {
                 None
             }
@@ -364,11 +900,15 @@ fn layerset(layers: LayerArgs) -> agentsdb_query::LayerSet {
             delta: layers.delta,
             local: layers.local,
         }
-    }
+    };
+    // `https://` base and user layers are read from a local cache.
+    agentsdb_ops::remote::resolve_remote_layers(&mut set)?;
+    Ok(set)
 }
 
 fn discover_standard_layers() -> agentsdb_query::LayerSet {
-    // Standard layer filenames in the current directory
+    // Standard layer filenames in the current directory (or the project directory above it)
+    let project = crate::util::project_dir();
     let standard_paths = [
         ("AGENTS.db", "base"),
         ("AGENTS.user.db", "user"),
@@ -382,8 +922,8 @@ fn discover_standard_layers() -> agentsdb_query::LayerSet {
     let mut local = None;
 
     for (filename, layer_type) in standard_paths {
-        if std::path::Path::new(filename).exists() {
-            let path_str = filename.to_string();
+        let path_str = in_project(project.as_deref(), filename);
+        if std::path::Path::new(&path_str).exists() {
             match layer_type {
                 "base" => base = Some(path_str),
                 "user" => user = Some(path_str),
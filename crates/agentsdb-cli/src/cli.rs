@@ -1,18 +1,50 @@
 use clap::{Args, Parser, Subcommand};
+use clap_complete::engine::ArgValueCompleter;
+
+use crate::completion::{chunk_kinds, layer_paths};
 
 #[derive(Args, Clone, Debug, Default)]
 /// Arguments for specifying various AGENTS.db layers.
 pub(crate) struct LayerArgs {
-    #[arg(long)]
+    #[arg(long, add = ArgValueCompleter::new(layer_paths))]
     pub(crate) base: Option<String>,
-    #[arg(long)]
+    #[arg(long, add = ArgValueCompleter::new(layer_paths))]
     pub(crate) user: Option<String>,
-    #[arg(long)]
+    #[arg(long, add = ArgValueCompleter::new(layer_paths))]
     pub(crate) delta: Option<String>,
-    #[arg(long)]
+    #[arg(long, add = ArgValueCompleter::new(layer_paths))]
     pub(crate) local: Option<String>,
 }
 
+#[derive(Args, Clone, Debug)]
+/// Arguments shared by `ingest` and `watch`.
+pub(crate) struct IngestArgs {
+    /// Root directory to walk.
+    #[arg(long, default_value = ".")]
+    pub(crate) root: String,
+    /// Output layer path to write.
+    #[arg(long, default_value = "AGENTS.db")]
+    pub(crate) out: String,
+    /// Globs of files to ingest, relative to the root (repeatable; defaults to markdown/rst/txt/adoc).
+    #[arg(long = "include")]
+    pub(crate) includes: Vec<String>,
+    /// Globs of files or directories to skip (repeatable).
+    #[arg(long = "exclude")]
+    pub(crate) excludes: Vec<String>,
+    /// Target chunk size in characters.
+    #[arg(long, default_value_t = 1500)]
+    pub(crate) chunk_size: usize,
+    /// Characters of overlap between consecutive chunks.
+    #[arg(long, default_value_t = 200)]
+    pub(crate) overlap: usize,
+    /// Chunk kind to assign to ingested chunks.
+    #[arg(long, default_value = "canonical")]
+    pub(crate) kind: String,
+    /// Embedding dimension for the emitted schema (defaults to configured options if present, else 128).
+    #[arg(long)]
+    pub(crate) dim: Option<u32>,
+}
+
 #[derive(Parser)]
 #[command(
     name = "agentsdb",
@@ -23,14 +55,65 @@ pub(crate) struct LayerArgs {
 ///
 /// This struct uses `clap` to parse command-line arguments and subcommands.
 pub(crate) struct Cli {
-    /// Emit machine-readable JSON instead of human output.
-    #[arg(long)]
+    /// Emit machine-readable JSON instead of human output (same as `--output json`).
+    #[arg(long, global = true)]
     pub(crate) json: bool,
 
+    /// Output format: human-readable `table` (default) or `json`.
+    #[arg(long, global = true, value_enum)]
+    pub(crate) output: Option<OutputFormat>,
+
+    /// Run as if started in DIR (like `git -C`). Without it, commands that default to the
+    /// standard layers find them in the nearest ancestor directory with an `AGENTS.db`.
+    #[arg(
+        short = 'C',
+        long,
+        global = true,
+        value_name = "DIR",
+        env = "AGENTSDB_ROOT"
+    )]
+    pub(crate) project_root: Option<String>,
+
+    /// Diagnostics written to stderr: a level (`error`, `warn`, `info`, `debug`, `trace`) or a
+    /// filter such as `agentsdb_ops=debug`.
+    #[arg(
+        long,
+        global = true,
+        value_name = "LEVEL",
+        env = "AGENTSDB_LOG",
+        default_value = "warn"
+    )]
+    pub(crate) log_level: String,
+
+    /// Format of the diagnostics on stderr.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        env = "AGENTSDB_LOG_FORMAT",
+        default_value_t = LogFormat::Text
+    )]
+    pub(crate) log_format: LogFormat,
+
     #[command(subcommand)]
     pub(crate) cmd: Command,
 }
 
+impl Cli {
+    /// Whether commands should print JSON, from either `--json` or `--output json`.
+    ///
+    /// Checked here rather than with clap's `conflicts_with`, which does not see global flags
+    /// given on different sides of the subcommand.
+    pub(crate) fn json_output(&self) -> anyhow::Result<bool> {
+        match (self.json, self.output) {
+            (true, Some(OutputFormat::Table)) => {
+                anyhow::bail!("--json conflicts with --output table")
+            }
+            (json, output) => Ok(json || output == Some(OutputFormat::Json)),
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub(crate) enum Command {
     /// List readable `.db` layer files in a directory.
@@ -59,28 +142,96 @@ pub(crate) enum Command {
         /// Quantization scale (only used when `--element-type i8`).
         #[arg(long)]
         quant_scale: Option<f32>,
+        /// Project template: picks the files to compile and seeds starter kinds.
+        #[arg(long, value_parser = ["rust-crate", "monorepo", "docs-site"])]
+        template: Option<String>,
+        /// Prompt for the template and embedder options interactively.
+        #[arg(long, conflicts_with_all = ["template", "backend", "model"])]
+        wizard: bool,
+        /// Embedder backend to record in an options chunk in the new `AGENTS.db`.
+        #[arg(long)]
+        backend: Option<String>,
+        /// Embedding model to record alongside `--backend`.
+        #[arg(long, requires = "backend")]
+        model: Option<String>,
     },
     /// Validate that a layer file is readable and well-formed.
     Validate {
         /// Layer path (e.g. `AGENTS.base.db`).
+        #[arg(add = ArgValueCompleter::new(layer_paths))]
         path: String,
+        /// Compare the embedding profiles of the layers next to PATH and list rows embedded with
+        /// another profile than their layer's.
+        #[arg(long)]
+        embedding_drift: bool,
+        /// Re-embed the rows `--embedding-drift` lists with the configured embedder.
+        #[arg(long, requires = "embedding_drift")]
+        fix: bool,
+        /// Allow `--fix` to rewrite the base layer (AGENTS.db).
+        #[arg(long, requires = "fix")]
+        allow_base: bool,
     },
     /// Inspect a layer file header/sections, or print a chunk by id.
     Inspect {
         /// Layer path to inspect (alternative to providing PATH).
-        #[arg(long)]
+        #[arg(long, add = ArgValueCompleter::new(layer_paths))]
         layer: Option<String>,
         /// Chunk id to print (prints layer metadata if omitted).
         #[arg(long)]
         id: Option<u32>,
         /// Layer path to inspect (positional alternative to `--layer`).
-        #[arg(value_name = "PATH")]
+        #[arg(value_name = "PATH", add = ArgValueCompleter::new(layer_paths))]
         path: Option<String>,
     },
     /// Run the MCP server over stdio.
     Serve {
         #[command(flatten)]
         layers: LayerArgs,
+        /// Run startup initialization, print the effective configuration and step timings as JSON, and exit.
+        #[arg(long)]
+        check: bool,
+        /// Let MCP clients accept and reject proposals (`agents_proposals_accept` / `agents_proposals_reject`).
+        #[arg(long)]
+        allow_proposal_review: bool,
+        /// Maximum number of MCP requests processed concurrently (default: `server.max_in_flight`, 4).
+        #[arg(long)]
+        max_in_flight: Option<usize>,
+        /// Only expose read tools (no writes, retractions, updates or proposals).
+        #[arg(long, env = "AGENTSDB_MCP_READ_ONLY", value_parser = clap::builder::FalseyValueParser::new())]
+        read_only: bool,
+        /// Hide `agents_context_propose`.
+        #[arg(long, env = "AGENTSDB_MCP_NO_PROPOSE", value_parser = clap::builder::FalseyValueParser::new())]
+        no_propose: bool,
+        /// Scopes MCP writes may target (repeatable or comma-separated; default: local and delta).
+        #[arg(long = "write-scope", value_name = "SCOPE", value_delimiter = ',', value_parser = ["local", "delta"], env = "AGENTSDB_MCP_WRITE_SCOPES")]
+        write_scopes: Vec<String>,
+        /// Config file with layer paths, reloaded when it changes (default: `agentsdb.toml` in the current directory, if present).
+        #[arg(long = "config", value_name = "PATH")]
+        config_file: Option<String>,
+        /// Append an ndjson audit record of every write/propose tool call to this file.
+        #[arg(long, value_name = "PATH", env = "AGENTSDB_MCP_AUDIT_LOG")]
+        audit_log: Option<String>,
+        /// Also record searches and chunk reads in the audit log (for `agentsdb top`).
+        #[arg(long, env = "AGENTSDB_MCP_AUDIT_READS", value_parser = clap::builder::FalseyValueParser::new())]
+        audit_reads: bool,
+        /// Rewrite this file with Prometheus text-format metrics after every request (for a node_exporter textfile collector).
+        #[arg(long, value_name = "PATH", env = "AGENTSDB_MCP_METRICS_FILE")]
+        metrics_file: Option<String>,
+        /// Also expose `agents_workspace_search`, which searches every project registered with `agentsdb workspace add`.
+        #[arg(long)]
+        workspace: bool,
+        /// Results `agents_search` returns when a call sets no `k` (default: `server.search_k`, 10).
+        #[arg(long, value_name = "K")]
+        search_k: Option<usize>,
+        /// Layers `agents_search` searches when a call names none (repeatable or comma-separated; default: `server.search_layers`, all).
+        #[arg(long = "search-layer", value_name = "LAYER", value_delimiter = ',', value_parser = ["base", "user", "delta", "local", "session"])]
+        search_layers: Vec<String>,
+        /// Kinds every search is restricted to, on top of the call's own kind filter (repeatable or comma-separated; default: `server.search_kinds`).
+        #[arg(long = "search-kind", value_name = "KIND", value_delimiter = ',')]
+        search_kinds: Vec<String>,
+        /// Leave chunks below this confidence (0.0 to 1.0) out of every search (default: `server.min_confidence`).
+        #[arg(long, value_name = "CONFIDENCE")]
+        min_confidence: Option<f32>,
     },
     /// Compile text and/or files into an on-disk layer file.
     Compile {
@@ -117,10 +268,55 @@ pub(crate) enum Command {
         /// Quantization scale (only used when `--element-type i8`).
         #[arg(long)]
         quant_scale: Option<f32>,
+        /// Embedding matrix layout: `row-major`, or `blocked` for faster brute-force scans of
+        /// large layers. Appends keep the layout of the existing file.
+        #[arg(long, value_parser = ["row-major", "blocked"])]
+        embedding_layout: Option<String>,
+        /// Produce byte-identical output for identical inputs: zero timestamps, derive missing
+        /// ids from source and content, and omit per-request provider metadata.
+        #[arg(long)]
+        deterministic: bool,
+        /// Write a reproducibility manifest (input hashes, embedder profile, tool version,
+        /// output hash) to this path.
+        #[arg(long)]
+        manifest: Option<String>,
+        /// Sign the output with the key in this file (default: `AGENTSDB_SIGNING_KEY` or
+        /// `AGENTSDB_SIGNING_KEY_FILE`, if set). See `agentsdb sign`.
+        #[arg(long, value_name = "FILE")]
+        sign_key: Option<String>,
+    },
+    /// Walk a source tree, chunk docs and code comments with overlap, embed, and write a layer.
+    #[command(
+        after_help = "Examples:\n  agentsdb ingest\n  agentsdb ingest --root . --include 'docs/**' --include '*.md' --include 'src/**/*.rs'\n  agentsdb ingest --exclude 'docs/archive/**' --chunk-size 1000 --overlap 100\n  agentsdb ingest --incremental\n\nCode files (rs, py, ts, go, ...) contribute only their comments. Each chunk records `path:line` and\n`ingest:<path>@<sha256>`; with --incremental, files whose hash is unchanged keep their chunks and embeddings."
+    )]
+    Ingest {
+        #[command(flatten)]
+        ingest: IngestArgs,
+        /// Only re-embed files whose content hash changed since the last ingest.
+        #[arg(long)]
+        incremental: bool,
+    },
+    /// Keep an ingested layer up to date as files change (incremental, debounced; deleted files are tombstoned).
+    #[command(
+        after_help = "Examples:\n  agentsdb watch\n  agentsdb watch --include 'docs/**' --include 'src/**/*.rs' --debounce-ms 2000\n\nThe layer is rewritten atomically, so `agentsdb serve` picks up each update (and notifies clients)."
+    )]
+    Watch {
+        #[command(flatten)]
+        ingest: IngestArgs,
+        /// How often to poll the watched files, in milliseconds.
+        #[arg(long, default_value_t = 1000)]
+        interval_ms: u64,
+        /// Re-ingest once the files have been unchanged for this long, in milliseconds.
+        #[arg(long, default_value_t = 500)]
+        debounce_ms: u64,
     },
-    /// Append a chunk to a writable layer file.
+    /// Append a chunk (or a file of chunks) to a writable layer file.
+    #[command(
+        after_help = "Examples:\n  agentsdb write AGENTS.local.db --scope local --kind note --content \"Use pnpm\" --confidence 0.9\n  agentsdb write AGENTS.local.db --scope local --from-file notes.jsonl --kind note --confidence 0.8\n  generate-notes | agentsdb write AGENTS.delta.db --scope delta --from-file -\n\nEach --from-file line is a JSON object with `content` and optional `kind`, `confidence`, `sources`,\n`source_chunks`, `namespace` and `language`; --kind, --confidence, --namespace and --language fill in\nmissing fields."
+    )]
     Write {
         /// Destination layer path (must be `AGENTS.local.db` or `AGENTS.delta.db`).
+        #[arg(add = ArgValueCompleter::new(layer_paths))]
         path: String,
         /// Target scope for permission checks: `local` or `delta`.
         #[arg(long)]
@@ -128,15 +324,18 @@ pub(crate) enum Command {
         /// Chunk id to write (if omitted, an id is assigned).
         #[arg(long)]
         id: Option<u32>,
-        /// Chunk kind (e.g. `canonical`, `note`, etc).
-        #[arg(long)]
-        kind: String,
+        /// Chunk kind (e.g. `canonical`, `note`, etc); the default kind with `--from-file`.
+        #[arg(long, required_unless_present = "from_file", add = ArgValueCompleter::new(chunk_kinds))]
+        kind: Option<String>,
         /// Chunk content (the text to store).
-        #[arg(long)]
-        content: String,
-        /// Confidence score in [0, 1].
-        #[arg(long)]
-        confidence: f32,
+        #[arg(long, required_unless_present = "from_file")]
+        content: Option<String>,
+        /// Confidence score in [0, 1]; the default confidence with `--from-file`.
+        #[arg(long, required_unless_present = "from_file")]
+        confidence: Option<f32>,
+        /// Append one chunk per JSON line of this file (`-` for stdin) in a single atomic write.
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["id", "content", "embedding", "sources", "source_chunks"])]
+        from_file: Option<String>,
         /// Embedding JSON array (e.g. `[0.1, 0.2, ...]`); if omitted, uses hash embedding.
         #[arg(long)]
         embedding: Option<String>, // JSON array; if omitted, uses hash embed
@@ -149,10 +348,16 @@ pub(crate) enum Command {
         /// Source chunk ids (repeatable).
         #[arg(long = "source-chunk")]
         source_chunks: Vec<u32>,
+        /// Namespace to place the chunk in (e.g. a package of a monorepo).
+        #[arg(long)]
+        namespace: Option<String>,
+        /// Language tag of the content (e.g. `en`, `de-AT`), or `auto` to detect it.
+        #[arg(long)]
+        language: Option<String>,
     },
     /// Search one or more layers using vector similarity.
     #[command(
-        after_help = "Examples:\n  agentsdb search --base AGENTS.base.db --query \"how do I run tests?\"\n  agentsdb search --user AGENTS.user.db --query-vec '[0.1, 0.2, 0.3]' -k 5\n  agentsdb search --base AGENTS.base.db --delta AGENTS.delta.db --query \"rustfmt\" --kind canonical --kind note\n\nQuery modes:\n  - --query: text hashed into a deterministic embedding (fast, but not semantic).\n  - --query-vec/--query-vec-file: provide an explicit embedding as a JSON array of numbers."
+        after_help = "Examples:\n  agentsdb search \"how do I run tests?\"   (standard layers of the enclosing project)\n  agentsdb search --base AGENTS.base.db --query \"how do I run tests?\"\n  agentsdb search --user AGENTS.user.db --query-vec '[0.1, 0.2, 0.3]' -k 5\n  agentsdb search --base AGENTS.base.db --delta AGENTS.delta.db --query \"rustfmt\" --kind canonical --kind note\n\nQuery modes:\n  - --query: text hashed into a deterministic embedding (fast, but not semantic).\n  - --query-vec/--query-vec-file: provide an explicit embedding as a JSON array of numbers."
     )]
     Search {
         #[command(flatten)]
@@ -161,6 +366,9 @@ pub(crate) enum Command {
         /// Text query (hashed into an embedding).
         #[arg(long)]
         query: Option<String>,
+        /// Text query, as a positional alternative to `--query`.
+        #[arg(value_name = "QUERY", conflicts_with = "query")]
+        query_text: Option<String>,
         /// Explicit embedding as a JSON array (e.g. `[0.1, 0.2, ...]`).
         #[arg(long)]
         query_vec: Option<String>,
@@ -168,21 +376,43 @@ pub(crate) enum Command {
         #[arg(long)]
         query_vec_file: Option<String>,
 
-        /// Number of nearest neighbors to return.
-        #[arg(short, long, default_value_t = 5)]
-        k: usize,
+        /// Number of nearest neighbors to return (default: `search.k`, 5).
+        #[arg(short, long)]
+        k: Option<usize>,
 
         /// Filter results by chunk kind (repeatable).
-        #[arg(long = "kind")]
+        #[arg(long = "kind", add = ArgValueCompleter::new(chunk_kinds))]
         kinds: Vec<String>,
+        /// Only return chunks in this namespace.
+        #[arg(long)]
+        namespace: Option<String>,
+        /// Only return chunks in this language (`de` also matches `de-AT`).
+        #[arg(long)]
+        language: Option<String>,
 
         /// Use a rebuildable sidecar index (if present) to accelerate exact search.
         #[arg(long)]
         use_index: bool,
+        /// Reuse the selection pass cached under `.agentsdb/cache/` by an earlier search of the
+        /// same layer contents, and cache it there when missing.
+        #[arg(long)]
+        disk_cache: bool,
 
-        /// Search mode: hybrid (lexical + semantic) or semantic-only.
-        #[arg(long, default_value = "hybrid")]
-        mode: String,
+        /// Search mode: hybrid (lexical + semantic) or semantic-only (default: `search.mode`, hybrid).
+        #[arg(long)]
+        mode: Option<String>,
+
+        /// Print the results that fit `--budget-tokens` as one markdown context block (JSON with `--json`).
+        #[arg(long)]
+        assemble: bool,
+        /// Token budget for `--assemble` (estimated at four characters per token).
+        #[arg(long, default_value_t = 2000, requires = "assemble")]
+        budget_tokens: usize,
+
+        /// Search every project in the workspace registry (see `agentsdb workspace`) instead of
+        /// the layers given.
+        #[arg(long, conflicts_with = "assemble")]
+        workspace: bool,
     },
     /// Build a rebuildable sidecar index for one or more layers.
     Index {
@@ -196,14 +426,21 @@ pub(crate) enum Command {
         /// Store decoded f32 embeddings even when the layer already stores f32 embeddings.
         #[arg(long)]
         store_embeddings_f32: bool,
+
+        /// Store 1-bit binary codes so searches shortlist rows by Hamming distance and rescore
+        /// only the best candidates exactly.
+        #[arg(long)]
+        binary: bool,
     },
-    /// Export one or more layers to a stable JSON/NDJSON format.
+    /// Export one or more layers to a stable JSON/NDJSON format, to Parquet, or into a vector store.
     Export {
         /// Directory to resolve standard layer paths from.
         #[arg(long, default_value = ".")]
         dir: String,
-        /// Output format: `json` or `ndjson`.
-        #[arg(long, default_value = "json", value_parser = ["json", "ndjson"])]
+        /// Output format: `json`, `ndjson`, `parquet` (builds with the `parquet` feature), `pgvector`
+        /// (a `psql` script loading a pgvector table), or `qdrant` (upserts into a Qdrant server;
+        /// builds with the `qdrant` feature).
+        #[arg(long, default_value = "json", value_parser = ["json", "ndjson", "parquet", "pgvector", "qdrant"])]
         format: String,
         /// Comma-separated logical layers: `base,user,delta,local`.
         #[arg(long, default_value = "base,user,delta,local")]
@@ -214,15 +451,65 @@ pub(crate) enum Command {
         /// Redaction mode: `none`, `content`, `embeddings`, or `all`.
         #[arg(long, default_value = "none", value_parser = ["none", "content", "embeddings", "all"])]
         redact: String,
+        /// Table to load with `--format pgvector`.
+        #[arg(long, default_value = agentsdb_ops::export::pgvector::DEFAULT_TABLE)]
+        table: String,
+        /// Qdrant REST URL for `--format qdrant`, e.g. `http://localhost:6333` (API key from `QDRANT_API_KEY`).
+        #[arg(long, required_if_eq("format", "qdrant"))]
+        qdrant_url: Option<String>,
+        /// Qdrant collection for `--format qdrant` (created if missing).
+        #[arg(long, default_value = "agentsdb")]
+        collection: String,
+        /// Records per batch for `--format pgvector`/`qdrant`.
+        #[arg(long, default_value_t = 256)]
+        batch_size: usize,
+        /// Only export chunks in this namespace.
+        #[arg(long)]
+        namespace: Option<String>,
     },
-    /// Import a JSON/NDJSON export and append it to a writable layer.
+    /// Import a JSON/NDJSON/Parquet export, or markdown notes, and append it to a writable layer.
     Import {
         /// Directory to resolve the target layer path from.
         #[arg(long, default_value = ".")]
         dir: String,
-        /// Input file path (JSON or NDJSON).
-        #[arg(long = "in")]
-        input: String,
+        /// Input file path (JSON, NDJSON, or Parquet). With `--format markdown`, a file or
+        /// directory; `sqlite`, a database file; `chroma`, the server URL.
+        #[arg(long = "in", required_unless_present = "path")]
+        input: Option<String>,
+        /// Input path, as an alternative to `--in`.
+        #[arg(conflicts_with = "input", value_name = "PATH")]
+        path: Option<String>,
+        /// Input format; exports are detected from the file when omitted. `markdown` converts
+        /// `.md`/`.mdx`/`.markdown`/`.txt` files into chunks split at headings, taking `kind`,
+        /// `confidence`, `author` and `tags` from YAML front matter. `sqlite` and `chroma` read
+        /// agent memory stores, keeping their embeddings when the dimension matches the target.
+        #[arg(long, value_parser = ["json", "ndjson", "parquet", "markdown", "sqlite", "chroma"])]
+        format: Option<String>,
+        /// Maximum characters per chunk with `--format markdown`.
+        #[arg(long, default_value_t = 1500)]
+        chunk_size: usize,
+        /// Characters of overlap when `--format markdown` splits a long section.
+        #[arg(long, default_value_t = 200)]
+        overlap: usize,
+        /// Chunk kind with `--format markdown`, for files whose front matter sets none.
+        #[arg(long, default_value = "note")]
+        kind: String,
+        /// Table to read with `--format sqlite` (found automatically when only one table has a
+        /// `content`/`text`/`document`/`memory` column).
+        #[arg(long, conflicts_with = "query")]
+        table: Option<String>,
+        /// `SELECT` to read with `--format sqlite`, instead of a table.
+        #[arg(long)]
+        query: Option<String>,
+        /// Collection to read with `--format chroma`.
+        #[arg(long, required_if_eq("format", "chroma"))]
+        collection: Option<String>,
+        /// Chroma tenant.
+        #[arg(long, default_value = "default_tenant")]
+        tenant: String,
+        /// Chroma database.
+        #[arg(long, default_value = "default_database")]
+        database: String,
         /// Optional target logical layer: `local`, `delta`, `user`, or `base`.
         ///
         /// If omitted, the import reads the export bundle and writes each exported layer into the
@@ -247,6 +534,9 @@ pub(crate) enum Command {
         /// Embedding dimension when creating a new layer and embeddings are missing.
         #[arg(long)]
         dim: Option<u32>,
+        /// Price per million input tokens, used to estimate embedding cost in the usage summary.
+        #[arg(long)]
+        price_per_million_tokens: Option<f64>,
     },
     /// Compare a base layer to a delta layer by id.
     Diff {
@@ -262,26 +552,232 @@ pub(crate) enum Command {
         /// Path to the user layer (required when `--target user`).
         #[arg(long)]
         user: Option<String>,
+        /// Pair chunks only in base with chunks only in delta by embedding similarity and
+        /// report them as modified.
+        #[arg(long)]
+        semantic: bool,
+        /// Minimum cosine similarity for `--semantic` to pair two chunks.
+        #[arg(long, default_value_t = 0.85, requires = "semantic")]
+        threshold: f32,
     },
     /// Copy selected chunks from one layer into another.
+    #[command(
+        after_help = "Examples:\n  agentsdb promote --from AGENTS.delta.db --to AGENTS.user.db --ids 1,2\n  agentsdb promote --from AGENTS.delta.db --to AGENTS.user.db --ids 1,2 --strategy theirs\n  agentsdb promote --from AGENTS.delta.db --to AGENTS.user.db --namespace auth --interactive\n  agentsdb promote --from AGENTS.delta.db --to AGENTS.db --ids 1,2 --via-pr\n\nWithout --strategy or --interactive, promoted chunks always get new ids. With them, ids are kept where\nthe destination has them free, and identical chunks are skipped.\n\n--via-pr opens a GitHub pull request or GitLab merge request instead of writing the base layer; the\nprovider and repository come from the `origin` remote, the token from GITHUB_TOKEN or GITLAB_TOKEN."
+    )]
     Promote {
         /// Source layer path.
-        #[arg(long = "from")]
+        #[arg(long = "from", add = ArgValueCompleter::new(layer_paths))]
         from_path: String,
         /// Destination layer path (must be writable).
-        #[arg(long = "to")]
+        #[arg(long = "to", add = ArgValueCompleter::new(layer_paths))]
         to_path: String,
         /// Comma-separated chunk ids to promote (e.g. `1,2,3`).
+        #[arg(long, required_unless_present = "namespace")]
+        ids: Option<String>, // comma-separated
+        /// Promote only chunks in this namespace; without `--ids`, every chunk in it.
         #[arg(long)]
-        ids: String, // comma-separated
+        namespace: Option<String>,
         /// Skip ids already present in the destination layer instead of erroring.
         #[arg(long)]
         skip_existing: bool,
+        /// Keep chunk ids where the destination has them free, and settle ids it holds with different
+        /// content: keep the destination's chunk (`ours`), supersede it (`theirs`) or promote under a new id (`rename`).
+        #[arg(long, value_parser = ["ours", "theirs", "rename"], conflicts_with = "interactive")]
+        strategy: Option<String>,
+        /// Like `--strategy`, but ask for each conflicting id.
+        #[arg(long)]
+        interactive: bool,
         /// Assume \"yes\" for interactive confirmation prompts.
         #[arg(long)]
         yes: bool,
+        /// Open a pull request adding the chunks to the base layer `--to`, keeping their ids,
+        /// instead of writing any layer.
+        #[arg(long, conflicts_with_all = ["strategy", "interactive", "skip_existing"])]
+        via_pr: bool,
+    },
+    /// Show chunk, confidence, age, embedding and size statistics for layers.
+    Stats {
+        #[command(flatten)]
+        layers: LayerArgs,
+    },
+    /// Find exact and near-duplicate chunks within or across layers, and optionally retract them.
+    #[command(
+        after_help = "Examples:\n  agentsdb dedupe\n  agentsdb dedupe --threshold 0.95 --same-layer\n  agentsdb dedupe --local AGENTS.local.db --base AGENTS.db --apply --merge\n  agentsdb --json dedupe --threshold 0.9 --fail-on-duplicates\n\nDuplicates are tombstoned only in writable layers (local/delta); the chunk in the most durable layer is kept."
+    )]
+    Dedupe {
+        #[command(flatten)]
+        layers: LayerArgs,
+        /// Also report near duplicates whose embedding cosine similarity is at least this value.
+        #[arg(long)]
+        threshold: Option<f32>,
+        /// Only compare chunks stored in the same layer.
+        #[arg(long)]
+        same_layer: bool,
+        /// Tombstone every duplicate without asking.
+        #[arg(long, conflicts_with = "interactive")]
+        apply: bool,
+        /// Ask before tombstoning the duplicates of each group.
+        #[arg(long)]
+        interactive: bool,
+        /// Fold the duplicates' sources and confidence into a revision of the kept chunk.
+        #[arg(long)]
+        merge: bool,
+        /// Exit with an error when duplicates are found (e.g. in CI).
+        #[arg(long)]
+        fail_on_duplicates: bool,
+    },
+    /// Sign a layer file with an Ed25519 key, storing the signature in its metadata.
+    #[command(
+        after_help = "Examples:\n  agentsdb sign --generate-key agentsdb-signing.key\n  agentsdb sign AGENTS.db --key agentsdb-signing.key\n  AGENTSDB_SIGNING_KEY_FILE=agentsdb-signing.key agentsdb compile --out AGENTS.db\n\nThe key file holds the 32-byte secret as hex; keep it out of the repository. Readers trust the printed\npublic key through `trust.public_keys` in agentsdb.toml or AGENTSDB_TRUST_PUBLIC_KEYS."
+    )]
+    Sign {
+        /// Layer path to sign.
+        #[arg(required_unless_present = "generate_key", add = ArgValueCompleter::new(layer_paths))]
+        path: Option<String>,
+        /// File holding the signing key (default: `AGENTSDB_SIGNING_KEY` or the file named by
+        /// `AGENTSDB_SIGNING_KEY_FILE`).
+        #[arg(long, value_name = "FILE", conflicts_with = "generate_key")]
+        key: Option<String>,
+        /// Write a new signing key to FILE (which must not exist) and print its public key;
+        /// signs PATH with it if given.
+        #[arg(long, value_name = "FILE")]
+        generate_key: Option<String>,
+    },
+    /// Verify layer files against the trust settings (`--signature`).
+    #[command(
+        after_help = "Examples:\n  agentsdb verify --signature\n  agentsdb --json verify --signature --base AGENTS.db\n\nExits with an error when a signature does not match its layer, or when `trust.mode = \"enforce\"` and a\nlayer in `trust.layers` is not signed by a key in `trust.public_keys`."
+    )]
+    Verify {
+        #[command(flatten)]
+        layers: LayerArgs,
+        /// Check that each layer's signature matches its content and is from a trusted key.
+        #[arg(long)]
+        signature: bool,
+    },
+    /// Check that chunk-id sources resolve: flags dangling, retracted and cyclic provenance.
+    #[command(
+        after_help = "Examples:\n  agentsdb verify-provenance\n  agentsdb verify-provenance --fix\n  agentsdb --json verify-provenance --local AGENTS.local.db --base AGENTS.db\n\nExits with an error while issues remain. --fix only rewrites chunks in writable layers (local/delta)."
+    )]
+    VerifyProvenance {
+        #[command(flatten)]
+        layers: LayerArgs,
+        /// Replace broken chunks with revisions that point retracted sources at their successor
+        /// and record other broken links as `broken-source:<id>` strings.
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Run every repository check, for CI: layers, embedder profile, base manifest, stale deltas.
+    #[command(
+        after_help = "Examples:\n  agentsdb check\n  agentsdb check --max-unreviewed-days 7\n  agentsdb --json check --dir repo --manifest repo/AGENTS.manifest.json\n\nExits with an error when any check reports an error. The manifest check runs when --manifest is given or\nAGENTS.manifest.json exists next to AGENTS.db. Under GitHub Actions, findings are also printed as annotations."
+    )]
+    Check {
+        /// Directory containing `AGENTS*.db` standard layer files.
+        #[arg(long, default_value = ".")]
+        dir: String,
+        /// `compile --manifest` manifest of the base layer (default: AGENTS.manifest.json, if present).
+        #[arg(long)]
+        manifest: Option<String>,
+        /// Fail on delta chunks older than this many days that no accepted or rejected proposal covers.
+        #[arg(long, default_value_t = 14)]
+        max_unreviewed_days: u64,
+    },
+    /// Decay the confidence of old chunks and re-validate the ones that fall below a threshold.
+    #[command(
+        after_help = "Examples:\n  agentsdb review --stale\n  agentsdb review --decay --max-age-days 90 --factor 0.8 --stale\n  agentsdb review --confirm 42 --confirm 43\n\n--decay and --confirm write superseding revisions; only chunks in writable layers (local/delta) are changed."
+    )]
+    Review {
+        #[command(flatten)]
+        layers: LayerArgs,
+        /// List chunks whose confidence is below --threshold, oldest first.
+        #[arg(long)]
+        stale: bool,
+        /// Confidence below which a chunk needs re-validation.
+        #[arg(long, default_value_t = 0.5)]
+        threshold: f32,
+        /// Lower the confidence of chunks older than --max-age-days.
+        #[arg(long)]
+        decay: bool,
+        /// Age in days after which a chunk decays (counted from its latest revision).
+        #[arg(long, default_value_t = 90)]
+        max_age_days: u64,
+        /// Multiplier applied to the confidence of decayed chunks.
+        #[arg(long, default_value_t = 0.8)]
+        factor: f32,
+        /// Chunk id from the review queue to mark as re-validated (repeatable).
+        #[arg(long = "confirm", value_name = "ID")]
+        confirm: Vec<u32>,
+        /// Confidence given to re-validated chunks.
+        #[arg(long, default_value_t = 1.0)]
+        confidence: f32,
+    },
+    /// Show one chunk in full: every layer holding it, which copy wins, tombstones and its provenance trace.
+    #[command(
+        after_help = "Examples:\n  agentsdb show 42\n  agentsdb show 42 --local AGENTS.local.db --base AGENTS.db\n  agentsdb --json show 42"
+    )]
+    Show {
+        #[command(flatten)]
+        layers: LayerArgs,
+        /// Chunk id to show.
+        id: u32,
+    },
+    /// Summarize the MCP audit log: most and never retrieved chunks, top query terms and write volume.
+    #[command(
+        after_help = "Examples:\n  agentsdb top --audit-log agentsdb-audit.ndjson\n  agentsdb top --audit-log agentsdb-audit.ndjson --period month -n 20\n\nRetrievals are only logged when the server runs with `serve --audit-log <PATH> --audit-reads`;\nwithout them, only write volume is reported."
+    )]
+    Top {
+        #[command(flatten)]
+        layers: LayerArgs,
+        /// Audit log written by `agentsdb serve --audit-log`.
+        #[arg(long, value_name = "PATH", env = "AGENTSDB_MCP_AUDIT_LOG")]
+        audit_log: String,
+        /// Entries shown per list.
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+        /// Bucket size for write volume.
+        #[arg(long, default_value = "week", value_parser = ["day", "week", "month"])]
+        period: String,
+    },
+    /// Undo a promotion or import recorded in the operation journal (AGENTS.journal.json).
+    #[command(
+        after_help = "Examples:\n  agentsdb undo\n  agentsdb undo 3\n  agentsdb undo 5 --allow-base\n\nWithout an id, lists the journal. Undo tombstones the chunks the operation introduced; undoing a promotion\nalso writes the moved chunks back to the layer they came from."
+    )]
+    Undo {
+        /// Journal id of the operation to undo (printed by promote and import).
+        op_id: Option<u32>,
+        /// Directory containing the `AGENTS*.db` layers and their journal.
+        #[arg(long, default_value = ".")]
+        dir: String,
+        /// Allow tombstoning chunks in AGENTS.db when undoing an import into base.
+        #[arg(long)]
+        allow_base: bool,
+    },
+    /// Explore layers interactively: search, inspect chunks, follow provenance and write notes.
+    #[command(
+        after_help = "Examples:\n  agentsdb repl\n  agentsdb repl --base AGENTS.db --local AGENTS.local.db -k 5\n  agentsdb repl --history .agentsdb_history\n\nType `help` at the prompt for the command list. Commands are appended to the history file\n(default: ~/.agentsdb_history); rerun one with !<n> or !!."
+    )]
+    Repl {
+        #[command(flatten)]
+        layers: LayerArgs,
+        /// Number of results returned by `search`.
+        #[arg(short, long, default_value_t = 10)]
+        k: usize,
+        /// History file to load and append to (defaults to ~/.agentsdb_history).
+        #[arg(long)]
+        history: Option<String>,
+    },
+    /// Print a shell completion script that also completes layer paths and chunk kinds.
+    #[command(
+        after_help = "Examples:\n  source <(agentsdb completions bash)\n  agentsdb completions zsh > ~/.zfunc/_agentsdb\n  agentsdb completions fish > ~/.config/fish/completions/agentsdb.fish\n\nThe script calls back into agentsdb while completing, so regenerate it after upgrading."
+    )]
+    Completions {
+        /// Shell to generate the script for.
+        #[arg(value_parser = ["bash", "elvish", "fish", "powershell", "zsh"])]
+        shell: String,
     },
     /// Rewrite and deduplicate layer files.
+    #[command(
+        after_help = "Examples:\n  agentsdb compact\n  agentsdb compact --base AGENTS.db --user AGENTS.user.db --out AGENTS.compacted.db\n  agentsdb options set --set retention.max_chunks=5000 --set retention.max_age_days=30\n  agentsdb compact --apply-policy\n\n--apply-policy retracts local chunks over the retention.* limits, and removes chunks it retracted\nmore than retention.gc_after_days ago."
+    )]
     Compact {
         /// Path to a base layer.
         #[arg(long)]
@@ -295,6 +791,13 @@ pub(crate) enum Command {
         /// Remove proposal event chunks during compaction.
         #[arg(long)]
         remove_proposals: bool,
+        /// Rewrite the embedding matrix in this layout: `row-major`, or `blocked` for faster
+        /// brute-force scans (default: keep each layer's layout).
+        #[arg(long, value_parser = ["row-major", "blocked"])]
+        embedding_layout: Option<String>,
+        /// Enforce the `retention.*` policy on AGENTS.local.db instead of compacting.
+        #[arg(long, conflicts_with_all = ["base", "user", "out", "remove_proposals", "embedding_layout"])]
+        apply_policy: bool,
     },
     /// Re-embed content from all layers using the embedding options configured in AGENTS.db.
     Reembed {
@@ -307,6 +810,18 @@ pub(crate) enum Command {
         /// Allow re-embedding the base layer (AGENTS.db). Required to include `base` in --layers.
         #[arg(long)]
         allow_base: bool,
+        /// Price per million input tokens, used to estimate embedding cost in the usage summary.
+        #[arg(long)]
+        price_per_million_tokens: Option<f64>,
+        /// Chunks sent to the embedder per request; progress is checkpointed after each batch.
+        #[arg(long, default_value_t = 64)]
+        batch_size: usize,
+        /// Continue from the checkpoint an interrupted or partially failed run left behind.
+        #[arg(long)]
+        resume: bool,
+        /// Retries per batch when the embedding provider fails, with exponential backoff.
+        #[arg(long, default_value_t = 3)]
+        retries: u32,
     },
     /// Break down large files into smaller chunks and re-compile them into a layer.
     /// This command is ALWAYS destructive and replaces the entire layer.
@@ -323,6 +838,15 @@ pub(crate) enum Command {
         /// Allow writing to `AGENTS.db` (dangerous; bypasses immutability).
         #[arg(long)]
         allow_base: bool,
+        /// Only split chunks matching a filter: `kinds=<kind>[,<kind>...]`.
+        #[arg(long)]
+        only: Option<String>,
+        /// Leave a logical layer out of --layers (repeatable).
+        #[arg(long = "exclude-layer")]
+        exclude_layers: Vec<String>,
+        /// Print the chunks that would be split without embedding or rewriting anything.
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Delete AGENTS*.db files under a root directory.
     Destroy {
@@ -333,14 +857,64 @@ pub(crate) enum Command {
         #[arg(long)]
         dry_run: bool,
     },
+    /// Three-way merge layer files chunk by chunk (used as a git merge driver).
+    #[command(
+        after_help = "Examples:\n  agentsdb mergetool --install\n  agentsdb mergetool --base base.db --ours AGENTS.user.db --theirs theirs.db\n  agentsdb mergetool --base %O --ours %A --theirs %B --prefer theirs\n\nChunks both branches appended under the same id are kept, with theirs renumbered. Edits to the same chunk on both\nbranches keep both versions and exit non-zero (git then marks the file conflicted) unless --prefer picks a side."
+    )]
+    Mergetool {
+        /// Common ancestor layer (git `%O`).
+        #[arg(long, required_unless_present = "install")]
+        base: Option<String>,
+        /// Our layer (git `%A`).
+        #[arg(long, required_unless_present = "install")]
+        ours: Option<String>,
+        /// Their layer (git `%B`).
+        #[arg(long, required_unless_present = "install")]
+        theirs: Option<String>,
+        /// Where to write the merged layer (defaults to `--ours`, as git expects).
+        #[arg(long)]
+        out: Option<String>,
+        /// Settle conflicting edits in favour of one side instead of keeping both versions.
+        #[arg(long, value_parser = ["ours", "theirs"])]
+        prefer: Option<String>,
+        /// Register the merge driver in this repository's git config and `.gitattributes`, then exit.
+        #[arg(long, conflicts_with_all = ["base", "ours", "theirs", "out", "prefer"])]
+        install: bool,
+    },
+    /// Push or pull standard layer files to and from remote storage.
+    #[command(
+        after_help = "Examples:\n  agentsdb sync push user --remote s3://team-bucket/agents\n  agentsdb sync pull user --merge\n  agentsdb sync push delta --remote https://dav.example.com/agents/\n\nRemotes: s3://bucket/prefix (AWS_* credentials; AWS_ENDPOINT_URL for S3-compatible stores), http(s):// URLs\n(plain HTTPS or WebDAV; AGENTSDB_SYNC_TOKEN is sent as a bearer token), or a local directory. The remote used last\nis remembered in AGENTS.sync.json, so --remote can be omitted afterwards."
+    )]
+    Sync {
+        #[command(subcommand)]
+        cmd: SyncCommand,
+    },
     /// Launch a local Web UI for browsing and editing writable layers.
     Web {
-        /// Root directory to scan for `.db` files.
+        /// Root directory to scan for `.db` files. Repeat to serve several roots; `NAME=DIR` sets the name shown in the UI and used in `/api/roots/NAME/` paths.
         #[arg(long, default_value = ".")]
-        root: String,
+        root: Vec<String>,
         /// Bind address, e.g. `127.0.0.1:3030`.
         #[arg(long, default_value = "127.0.0.1:3030")]
         bind: String,
+        /// Require this token as `Authorization: Bearer <token>` on `/api` routes.
+        #[arg(long, env = "AGENTSDB_WEB_AUTH_TOKEN", hide_env_values = true)]
+        auth_token: Option<String>,
+        /// With `--auth-token`, allow GET requests without a token (only changes need it).
+        #[arg(long, requires = "auth_token", env = "AGENTSDB_WEB_ANONYMOUS_READ", value_parser = clap::builder::FalseyValueParser::new())]
+        anonymous_read: bool,
+        /// Refuse every change (add, edit, remove, import, promote, proposal decisions) and hide write controls in the UI.
+        #[arg(long, env = "AGENTSDB_WEB_READ_ONLY", value_parser = clap::builder::FalseyValueParser::new())]
+        read_only: bool,
+        /// Team mode: a JSON file listing users as `[{"name": ..., "token": ...}]`. Changes made with a user's token are attributed to that user.
+        #[arg(long, value_name = "PATH", env = "AGENTSDB_WEB_USERS_FILE")]
+        users_file: Option<String>,
+        /// In team mode, let users accept or reject their own proposals.
+        #[arg(long, requires = "users_file")]
+        allow_self_review: bool,
+        /// Run startup initialization, print the effective configuration and step timings as JSON, and exit.
+        #[arg(long)]
+        check: bool,
     },
     /// Show or update embedding-related options stored in standard layer files.
     Options {
@@ -350,6 +924,17 @@ pub(crate) enum Command {
         #[command(subcommand)]
         cmd: OptionsCommand,
     },
+    /// Show or extend the kind taxonomy stored in standard layer files.
+    #[command(
+        after_help = "Examples:\n  agentsdb kinds list\n  agentsdb kinds add decision --description \"A choice and why it was made\"\n  agentsdb kinds add runbook --validation enforce\n\nThe taxonomy lives in the layer metadata of --scope (default: AGENTS.db). Writes to any layer in the directory\nwarn about kinds it does not list, or reject them with --validation enforce."
+    )]
+    Kinds {
+        /// Directory containing `AGENTS*.db` standard layer files.
+        #[arg(long, default_value = ".")]
+        dir: String,
+        #[command(subcommand)]
+        cmd: KindsCommand,
+    },
     /// Review and manage MCP promotion proposals.
     Proposals {
         /// Directory containing `AGENTS*.db` standard layer files.
@@ -367,6 +952,11 @@ pub(crate) enum Command {
         #[command(subcommand)]
         cmd: ProposalsCommand,
     },
+    /// Manage the registry of projects that `search --workspace` searches together.
+    Workspace {
+        #[command(subcommand)]
+        cmd: WorkspaceCommand,
+    },
 }
 
 #[derive(Subcommand)]
@@ -377,6 +967,17 @@ pub(crate) enum OptionsCommand {
         #[command(flatten)]
         layers: LayerArgs,
     },
+    /// Print every effective setting (embedding, search, server) and the source of each value.
+    #[command(
+        after_help = "Precedence (lowest to highest): defaults, options chunks, agentsdb.toml, AGENTSDB_* environment variables.\nEmbedding keys are only read from the base layer's options chunks."
+    )]
+    Explain {
+        #[command(flatten)]
+        layers: LayerArgs,
+        /// Settings file to read (default: `agentsdb.toml` next to the base layer, if present).
+        #[arg(long = "config", value_name = "PATH")]
+        config_file: Option<String>,
+    },
     /// Append a new options record to a writable standard layer file.
     Set {
         /// Destination scope to write to: `base` (required for consistency).
@@ -412,6 +1013,9 @@ pub(crate) enum OptionsCommand {
         /// Override the embedding cache directory.
         #[arg(long)]
         cache_dir: Option<String>,
+        /// Set any schema key, e.g. `search.k=10` or `server.write_scopes=local` (repeatable; see `options explain`).
+        #[arg(long = "set", value_name = "KEY=VALUE")]
+        settings: Vec<String>,
     },
     /// Interactive prompt for configuring embedding options.
     Wizard {
@@ -469,6 +1073,87 @@ pub(crate) enum AllowlistCommand {
     },
 }
 
+#[derive(Subcommand)]
+/// Subcommands for managing the kind taxonomy.
+pub(crate) enum KindsCommand {
+    /// Print the kinds each standard layer defines and the validation mode in effect.
+    List,
+    /// Add a kind, or update its description, in a layer's taxonomy.
+    Add {
+        /// Kind name (e.g. `decision`).
+        #[arg(add = ArgValueCompleter::new(chunk_kinds))]
+        name: String,
+        /// What chunks of this kind hold; shown to agents in the MCP tool schema.
+        #[arg(long, default_value = "")]
+        description: String,
+        /// Layer whose taxonomy to extend.
+        #[arg(long, default_value = "base", value_parser = ["base", "user", "delta", "local"])]
+        scope: String,
+        /// What writes do with kinds the taxonomy does not list (default: `warn`).
+        #[arg(long, value_parser = ["off", "warn", "enforce"])]
+        validation: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+/// Subcommands for managing the workspace registry.
+pub(crate) enum WorkspaceCommand {
+    /// Register a project directory (one holding `AGENTS.db`).
+    Add {
+        /// Project directory.
+        #[arg(default_value = ".")]
+        root: String,
+        /// Name shown next to the project's results (default: the directory name).
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Unregister a project by name or directory.
+    Remove {
+        /// Project name or directory.
+        project: String,
+    },
+    /// Print the registered projects.
+    List,
+}
+
+#[derive(Subcommand)]
+/// Subcommands for syncing layer files with a remote.
+pub(crate) enum SyncCommand {
+    /// Upload a layer; refuses when the remote changed since the last sync.
+    Push {
+        /// Layer to push.
+        #[arg(value_parser = ["base", "user", "delta", "local"])]
+        layer: String,
+        /// Remote URL (defaults to the remote this layer last synced with).
+        #[arg(long)]
+        remote: Option<String>,
+        /// Directory containing the `AGENTS*.db` standard layer files.
+        #[arg(long, default_value = ".")]
+        dir: String,
+        /// Overwrite the remote even if it changed since the last sync.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Download a layer; refuses when both copies changed since the last sync.
+    Pull {
+        /// Layer to pull.
+        #[arg(value_parser = ["base", "user", "delta", "local"])]
+        layer: String,
+        /// Remote URL (defaults to the remote this layer last synced with).
+        #[arg(long)]
+        remote: Option<String>,
+        /// Directory containing the `AGENTS*.db` standard layer files.
+        #[arg(long, default_value = ".")]
+        dir: String,
+        /// Discard local changes and take the remote version.
+        #[arg(long, conflicts_with = "merge")]
+        force: bool,
+        /// Three-way merge local and remote changes chunk by chunk.
+        #[arg(long)]
+        merge: bool,
+    },
+}
+
 #[derive(Subcommand)]
 /// Subcommands for reviewing and managing MCP promotion proposals.
 pub(crate) enum ProposalsCommand {
@@ -495,6 +1180,10 @@ pub(crate) enum ProposalsCommand {
         /// Assume \"yes\" for interactive confirmation prompts.
         #[arg(long)]
         yes: bool,
+        /// Name recorded as the approver; counts toward the `required_approvals` of
+        /// `AGENTS.policy.json`.
+        #[arg(long, env = "AGENTSDB_APPROVER", default_value = "human")]
+        approver: String,
     },
     /// Reject proposals without promoting them.
     Reject {
@@ -507,6 +1196,21 @@ pub(crate) enum ProposalsCommand {
     },
 }
 
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+/// Output format selected with the global `--output` flag.
+pub(crate) enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+/// Log format selected with the global `--log-format` flag.
+pub(crate) enum LogFormat {
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
 #[derive(clap::ValueEnum, Debug, Clone, Copy)]
 /// Represents a toggle state, either on or off.
 pub(crate) enum Toggle {
@@ -529,6 +1233,7 @@ mod tests {
                 dim,
                 element_type,
                 quant_scale,
+                ..
             } => {
                 assert_eq!(root, ".");
                 assert_eq!(out, "AGENTS.db");
@@ -546,17 +1251,49 @@ mod tests {
         let cli = Cli::try_parse_from(["agentsdb", "search", "--query", "append-only", "-k", "5"])
             .expect("parse should succeed");
         match cli.cmd {
-            Command::Search { k, .. } => assert_eq!(k, 5),
+            Command::Search { k, .. } => assert_eq!(k, Some(5)),
             _ => panic!("expected search command"),
         }
     }
 
+    #[test]
+    fn search_accepts_positional_query_and_global_project_root() {
+        let cli = Cli::try_parse_from(["agentsdb", "search", "how to test", "-C", "proj"])
+            .expect("parse should succeed");
+        assert_eq!(cli.project_root.as_deref(), Some("proj"));
+        match cli.cmd {
+            Command::Search { query_text, .. } => {
+                assert_eq!(query_text.as_deref(), Some("how to test"))
+            }
+            _ => panic!("expected search command"),
+        }
+        assert!(
+            Cli::try_parse_from(["agentsdb", "search", "a", "--query", "b"]).is_err(),
+            "positional query conflicts with --query"
+        );
+    }
+
+    #[test]
+    fn log_flags_are_global() {
+        let cli = Cli::try_parse_from([
+            "agentsdb",
+            "serve",
+            "--log-level",
+            "agentsdb_ops=debug",
+            "--log-format",
+            "json",
+        ])
+        .expect("parse should succeed");
+        assert_eq!(cli.log_level, "agentsdb_ops=debug");
+        assert_eq!(cli.log_format, LogFormat::Json);
+    }
+
     #[test]
     fn search_accepts_long_k() {
         let cli = Cli::try_parse_from(["agentsdb", "search", "--query", "append-only", "--k", "7"])
             .expect("parse should succeed");
         match cli.cmd {
-            Command::Search { k, .. } => assert_eq!(k, 7),
+            Command::Search { k, .. } => assert_eq!(k, Some(7)),
             _ => panic!("expected search command"),
         }
     }
@@ -582,6 +1319,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn output_flag_is_global_and_selects_json() -> anyhow::Result<()> {
+        let cli = Cli::try_parse_from(["agentsdb", "list", "--output", "json"])?;
+        assert!(cli.json_output()?);
+        let cli = Cli::try_parse_from(["agentsdb", "--output", "table", "list"])?;
+        assert!(!cli.json_output()?);
+        let cli = Cli::try_parse_from(["agentsdb", "list", "--json"])?;
+        assert!(cli.json_output()?);
+        let cli = Cli::try_parse_from(["agentsdb", "--json", "list", "--output", "table"])?;
+        assert!(cli.json_output().is_err());
+        Ok(())
+    }
+
     #[test]
     fn index_parses_defaults() {
         let cli = Cli::try_parse_from(["agentsdb", "index", "--base", "AGENTS.db"])
@@ -591,6 +1341,7 @@ mod tests {
                 layers,
                 out_dir,
                 store_embeddings_f32,
+                binary,
             } => {
                 assert_eq!(layers.base, Some("AGENTS.db".to_string()));
                 assert_eq!(layers.user, None);
@@ -598,6 +1349,7 @@ mod tests {
                 assert_eq!(layers.local, None);
                 assert_eq!(out_dir, None);
                 assert!(!store_embeddings_f32);
+                assert!(!binary);
             }
             _ => panic!("expected index command"),
         }
@@ -607,14 +1359,62 @@ mod tests {
     fn web_parses_defaults() {
         let cli = Cli::try_parse_from(["agentsdb", "web"]).expect("parse should succeed");
         match cli.cmd {
-            Command::Web { root, bind } => {
-                assert_eq!(root, ".");
+            Command::Web {
+                root,
+                bind,
+                auth_token,
+                anonymous_read,
+                read_only,
+                users_file,
+                allow_self_review,
+                check,
+            } => {
+                assert_eq!(root, ["."]);
                 assert_eq!(bind, "127.0.0.1:3030");
+                assert!(auth_token.is_none());
+                assert!(!anonymous_read);
+                assert!(!read_only);
+                assert!(users_file.is_none());
+                assert!(!allow_self_review);
+                assert!(!check);
             }
             _ => panic!("expected web command"),
         }
     }
 
+    #[test]
+    fn check_parses_defaults() {
+        let cli = Cli::try_parse_from(["agentsdb", "check"]).expect("parse should succeed");
+        match cli.cmd {
+            Command::Check {
+                dir,
+                manifest,
+                max_unreviewed_days,
+            } => {
+                assert_eq!(dir, ".");
+                assert!(manifest.is_none());
+                assert_eq!(max_unreviewed_days, 14);
+            }
+            _ => panic!("expected check command"),
+        }
+    }
+
+    #[test]
+    fn validate_fix_requires_embedding_drift() {
+        assert!(Cli::try_parse_from(["agentsdb", "validate", "--fix", "."]).is_err());
+        let cli = Cli::try_parse_from(["agentsdb", "validate", "--embedding-drift", "--fix", "."])
+            .expect("parse should succeed");
+        match cli.cmd {
+            Command::Validate {
+                embedding_drift,
+                fix,
+                allow_base,
+                ..
+            } => assert!(embedding_drift && fix && !allow_base),
+            _ => panic!("expected validate command"),
+        }
+    }
+
     #[test]
     fn options_parses_defaults() {
         let cli =
@@ -677,6 +1477,7 @@ mod tests {
                 dim,
                 element_type,
                 quant_scale,
+                ..
             } => {
                 assert_eq!(input, None);
                 assert_eq!(out, "AGENTS.db");
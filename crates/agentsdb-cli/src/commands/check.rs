@@ -0,0 +1,103 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use agentsdb_ops::check::{CheckOptions, CheckReport, Finding, Severity};
+
+pub(crate) fn cmd_check(
+    dir: &str,
+    manifest: Option<&str>,
+    max_unreviewed_days: u64,
+    json: bool,
+) -> anyhow::Result<()> {
+    let options = CheckOptions {
+        manifest: manifest.map(PathBuf::from),
+        max_unreviewed_age_days: max_unreviewed_days,
+    };
+    let report = agentsdb_ops::check::run_checks(Path::new(dir), &options)?;
+    let errors = report
+        .findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            #[serde(flatten)]
+            report: &'a CheckReport,
+            errors: usize,
+        }
+        let out = Out {
+            report: &report,
+            errors,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        for finding in &report.findings {
+            println!("{}", describe(finding));
+        }
+        println!(
+            "Ran {} checks in {}: {errors} errors, {} warnings",
+            report.checks.join(", "),
+            report.dir,
+            report.findings.len() - errors
+        );
+    }
+    // Workflow commands on stdout become annotations on the pull request.
+    if std::env::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true") {
+        for finding in &report.findings {
+            println!("{}", annotation(finding));
+        }
+    }
+
+    if errors > 0 {
+        anyhow::bail!("{errors} check errors");
+    }
+    Ok(())
+}
+
+fn severity_str(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
+
+fn describe(finding: &Finding) -> String {
+    let mut out = format!("{} [{}]", severity_str(finding.severity), finding.check);
+    if let Some(path) = &finding.path {
+        out.push_str(&format!(" {path}"));
+    }
+    if let Some(id) = finding.chunk_id {
+        out.push_str(&format!(" chunk {id}"));
+    }
+    out.push_str(&format!(": {}", finding.message));
+    out
+}
+
+/// A GitHub Actions `::error`/`::warning` workflow command for `finding`.
+fn annotation(finding: &Finding) -> String {
+    let mut out = format!("::{}", severity_str(finding.severity));
+    if let Some(path) = &finding.path {
+        out.push_str(&format!(" file={}", escape_property(path)));
+    }
+    out.push_str(&format!(
+        " title=agentsdb check ({})::{}",
+        finding.check,
+        escape_data(&finding.message)
+    ));
+    if let Some(id) = finding.chunk_id {
+        out.push_str(&format!(" (chunk {id})"));
+    }
+    out
+}
+
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
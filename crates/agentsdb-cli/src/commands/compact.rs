@@ -8,12 +8,19 @@ pub(crate) fn cmd_compact(
     user: Option<&str>,
     out: Option<&str>,
     remove_proposals: bool,
+    embedding_layout: Option<&str>,
     json: bool,
 ) -> anyhow::Result<()> {
     let cwd = std::env::current_dir().context("resolve current directory")?;
+    let layout = embedding_layout
+        .map(|name| {
+            agentsdb_format::EmbeddingLayout::from_name(name)
+                .with_context(|| format!("unknown embedding layout {name:?}"))
+        })
+        .transpose()?;
 
     if base.is_none() && user.is_none() && out.is_none() {
-        let compacted = compact_all_in_dir(&cwd, remove_proposals)
+        let compacted = compact_all_in_dir(&cwd, remove_proposals, layout)
             .context("compact all")?;
         if json {
             #[derive(Serialize)]
@@ -53,12 +60,17 @@ pub(crate) fn cmd_compact(
         );
     }
 
-    agentsdb_format::ensure_writable_layer_path_allow_user(&out)
+    agentsdb_format::WritePolicy::standard()
+        .allow_user()
+        .check(&out)
         .context("refuse to write compacted output to a non-writable layer path")?;
 
-    let (schema, mut chunks) =
+    let (mut schema, mut chunks) =
         compact_layers(base.as_deref(), user.as_deref(), remove_proposals)
             .context("compact")?;
+    if let Some(layout) = layout {
+        schema.layout = layout;
+    }
     agentsdb_format::write_layer_atomic(&out, &schema, &mut chunks, None)
         .context("write compacted layer")?;
 
@@ -88,11 +100,62 @@ pub(crate) fn cmd_compact(
     Ok(())
 }
 
+/// `compact --apply-policy`: enforces the `retention.*` policy on the local layer.
+pub(crate) fn cmd_compact_apply_policy(json: bool) -> anyhow::Result<()> {
+    let cwd = std::env::current_dir().context("resolve current directory")?;
+    let policy =
+        agentsdb_embeddings::settings::retention(&cwd).context("resolve retention settings")?;
+    if !policy.has_limits() {
+        anyhow::bail!(
+            "no retention policy set (set retention.max_chunks, retention.max_age_days or retention.max_bytes)"
+        );
+    }
+    let local = agentsdb_embeddings::config::standard_layer_paths_for_dir(&cwd).local;
+    let outcome = agentsdb_ops::retention::apply_retention(
+        &local,
+        &policy,
+        agentsdb_ops::util::now_unix_ms(),
+        "agentsdb-cli",
+        env!("CARGO_PKG_VERSION"),
+    )?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            layer: &'a str,
+            #[serde(flatten)]
+            outcome: &'a agentsdb_ops::retention::RetentionOutcome,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: true,
+                layer: &local.to_string_lossy(),
+                outcome: &outcome,
+            })?
+        );
+    } else {
+        println!(
+            "{}: retracted {} chunks, removed {} retracted earlier, kept {}",
+            local.display(),
+            outcome.retracted.len(),
+            outcome.collected.len(),
+            outcome.kept
+        );
+    }
+    Ok(())
+}
+
+/// Compacts every writable layer in `dir` in place, rewriting its embedding matrix in `layout`
+/// when given.
 fn compact_all_in_dir(
     dir: &Path,
     remove_proposals: bool,
+    layout: Option<agentsdb_format::EmbeddingLayout>,
 ) -> anyhow::Result<Vec<PathBuf>> {
     let mut compacted = Vec::new();
+    let policy = agentsdb_format::WritePolicy::standard().allow_user();
     for entry in std::fs::read_dir(dir).with_context(|| format!("read_dir {}", dir.display()))? {
         let entry = entry.context("read_dir entry")?;
         let path = entry.path();
@@ -112,7 +175,7 @@ fn compact_all_in_dir(
             continue;
         }
 
-        if agentsdb_format::ensure_writable_layer_path_allow_user(&path).is_err() {
+        if policy.check(&path).is_err() {
             continue;
         }
 
@@ -128,7 +191,10 @@ fn compact_all_in_dir(
             }
         };
 
-        let schema = agentsdb_format::schema_of(&file);
+        let mut schema = agentsdb_format::schema_of(&file);
+        if let Some(layout) = layout {
+            schema.layout = layout;
+        }
         let all_chunks = agentsdb_format::read_all_chunks(&file)
             .with_context(|| format!("read chunks from {}", path.display()))?;
 
@@ -302,29 +368,9 @@ fn path_to_string(p: PathBuf) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{chunk, schema};
     use std::collections::HashSet;
 
-    fn schema() -> agentsdb_format::LayerSchema {
-        agentsdb_format::LayerSchema {
-            dim: 4,
-            element_type: agentsdb_format::EmbeddingElementType::F32,
-            quant_scale: 1.0,
-        }
-    }
-
-    fn chunk(id: u32, kind: &str, content: &str) -> agentsdb_format::ChunkInput {
-        agentsdb_format::ChunkInput {
-            id,
-            kind: kind.to_string(),
-            content: content.to_string(),
-            author: "human".to_string(),
-            confidence: 1.0,
-            created_at_unix_ms: 0,
-            embedding: vec![0.0, 0.0, 0.0, 0.0],
-            sources: Vec::new(),
-        }
-    }
-
     #[test]
     fn compacts_base_plus_user() {
         let dir = crate::util::make_temp_dir();
@@ -354,7 +400,7 @@ mod tests {
 
         let base_s = base_path.to_string_lossy().into_owned();
         let user_s = user_path.to_string_lossy().into_owned();
-        cmd_compact(Some(&base_s), Some(&user_s), None, false, true).unwrap();
+        cmd_compact(Some(&base_s), Some(&user_s), None, false, None, true).unwrap();
 
         let out_file = agentsdb_format::LayerFile::open(&out_path).unwrap();
         let chunks = agentsdb_format::read_all_chunks(&out_file).unwrap();
@@ -422,7 +468,7 @@ mod tests {
         std::fs::write(&junk_path, b"not an agentsdb layer").unwrap();
         std::fs::write(&other_path, b"ignore").unwrap();
 
-        let compacted = compact_all_in_dir(&dir, false).unwrap();
+        let compacted = compact_all_in_dir(&dir, false, None).unwrap();
         let rendered: HashSet<String> = compacted
             .into_iter()
             .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
@@ -430,4 +476,22 @@ mod tests {
 
         assert_eq!(rendered, HashSet::from(["AGENTS.user.db".to_string()]));
     }
+
+    #[test]
+    fn compact_all_in_dir_converts_embedding_layout() {
+        let dir = crate::util::make_temp_dir();
+        let path = dir.join("AGENTS.user.db");
+        let mut chunks = [chunk(1, "note", "a"), chunk(2, "note", "b")];
+        agentsdb_format::write_layer_atomic(&path, &schema(), &mut chunks, None).unwrap();
+
+        compact_all_in_dir(&dir, false, Some(agentsdb_format::EmbeddingLayout::Blocked)).unwrap();
+
+        let file = agentsdb_format::LayerFile::open(&path).unwrap();
+        assert_eq!(
+            file.embedding_matrix.layout,
+            agentsdb_format::EmbeddingLayout::Blocked
+        );
+        let read = agentsdb_format::read_all_chunks(&file).unwrap();
+        assert_eq!(read[1].embedding, chunks[1].embedding);
+    }
 }
@@ -4,11 +4,17 @@ use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 
 use agentsdb_embeddings::config::get_immutable_embedding_options;
+use agentsdb_embeddings::embedder::{EmbedPurpose, Embedder};
+use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
+use agentsdb_embeddings::signing::LayerSigningKey;
 
 use crate::embedding_helpers::{
-    append_with_validated_metadata, create_layer_metadata, create_validated_embedder,
+    append_with_validated_metadata, create_layer_metadata, create_validated_embedder, keep_kinds_of,
+};
+use crate::types::{
+    CompileChunk, CompileInput, CompileManifestInput, CompileManifestJson, CompileManifestOutput,
+    CompileSchema, CompileSource,
 };
-use crate::types::{CompileChunk, CompileInput, CompileSchema, CompileSource};
 use crate::util::{assign_stable_id, collect_files};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,6 +38,10 @@ pub(crate) fn cmd_compile(
     dim: Option<u32>,
     element_type: &str,
     quant_scale: Option<f32>,
+    embedding_layout: Option<&str>,
+    deterministic: bool,
+    manifest: Option<&str>,
+    sign_key: Option<&str>,
     json: bool,
 ) -> anyhow::Result<()> {
     let resolved_dim = match dim {
@@ -49,12 +59,17 @@ pub(crate) fn cmd_compile(
         }
     };
 
+    let mut input_file = None;
     let mut input = if let Some(input_json) = input_json {
         if !paths.is_empty() || !texts.is_empty() {
             anyhow::bail!("--in cannot be combined with PATHs or --text");
         }
         let s =
             std::fs::read_to_string(input_json).with_context(|| format!("read {}", input_json))?;
+        input_file = Some(CompileManifestInput {
+            source: input_json.to_string(),
+            sha256: agentsdb_ops::util::content_sha256_hex(&s),
+        });
         serde_json::from_str(&s).context("parse compile input JSON")?
     } else {
         compile_input_from_sources(
@@ -69,7 +84,41 @@ pub(crate) fn cmd_compile(
         )?
     };
 
-    let (action, chunks) = compile_to_layer(&mut input, out, replace).context("compile")?;
+    if let Some(layout) = embedding_layout {
+        input.schema.layout = Some(layout.to_string());
+    }
+
+    // Hash the inputs before compiling consumes them.
+    let inputs = manifest.map(|_| manifest_inputs(&input.chunks));
+    let schema = CompileSchema {
+        dim: input.schema.dim,
+        element_type: input.schema.element_type.clone(),
+        quant_scale: input.schema.quant_scale,
+        layout: input.schema.layout.clone(),
+    };
+
+    let (action, chunks) =
+        compile_to_layer(&mut input, out, replace, deterministic).context("compile")?;
+
+    // Sign before the manifest hashes the output, so the hash is of the signed layer.
+    let signing_key = match sign_key {
+        Some(path) => Some(LayerSigningKey::from_file(Path::new(path))?),
+        None => LayerSigningKey::from_env()?,
+    };
+    let signature = match &signing_key {
+        Some(key) => Some(crate::commands::sign::sign_path(out, key)?),
+        None => None,
+    };
+
+    if let (Some(manifest_path), Some(inputs)) = (manifest, inputs) {
+        let manifest_json =
+            build_manifest(Path::new(out), deterministic, input_file, inputs, schema)?;
+        std::fs::write(
+            manifest_path,
+            format!("{}\n", serde_json::to_string_pretty(&manifest_json)?),
+        )
+        .with_context(|| format!("write {manifest_path}"))?;
+    }
 
     if json {
         #[derive(Serialize)]
@@ -77,11 +126,17 @@ pub(crate) fn cmd_compile(
             ok: bool,
             out: &'a str,
             chunks: usize,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            manifest: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            signed_by: Option<String>,
         }
         let out = Out {
             ok: true,
             out,
             chunks,
+            manifest,
+            signed_by: signature.map(|s| s.public_key),
         };
         println!("{}", serde_json::to_string_pretty(&out)?);
     } else {
@@ -91,6 +146,9 @@ pub(crate) fn cmd_compile(
                 println!("Wrote {out} ({chunks} chunks)")
             }
         }
+        if let Some(signature) = signature {
+            println!("Signed {out} with key {}", signature.public_key);
+        }
     }
     Ok(())
 }
@@ -117,6 +175,7 @@ fn compile_input_from_sources(
         dim,
         element_type: element_type.to_string(),
         quant_scale: quant_scale.or_else(|| (element_type == "i8").then_some(1.0)),
+        layout: None,
     };
 
     let cwd = std::env::current_dir().ok();
@@ -189,10 +248,90 @@ fn compile_input_from_sources(
     Ok(CompileInput { schema, chunks })
 }
 
+/// Label of a compile chunk in manifests and stable id assignment: its first source string.
+fn chunk_label(chunk: &CompileChunk) -> String {
+    chunk
+        .sources
+        .iter()
+        .find_map(|s| match s {
+            CompileSource::String(v) => Some(v.clone()),
+            CompileSource::Chunk { .. } => None,
+        })
+        .unwrap_or_else(|| format!("chunk:{}", chunk.id))
+}
+
+/// One hashed input per chunk, ordered by source so the manifest is stable.
+fn manifest_inputs(chunks: &[CompileChunk]) -> Vec<CompileManifestInput> {
+    let mut inputs: Vec<CompileManifestInput> = chunks
+        .iter()
+        .map(|c| CompileManifestInput {
+            source: chunk_label(c),
+            sha256: agentsdb_ops::util::content_sha256_hex(&c.content),
+        })
+        .collect();
+    inputs.sort_by(|a, b| (&a.source, &a.sha256).cmp(&(&b.source, &b.sha256)));
+    inputs
+}
+
+/// Describes the layer written at `out`: its hash plus the embedder and tool recorded in it.
+fn build_manifest(
+    out: &Path,
+    deterministic: bool,
+    input_file: Option<CompileManifestInput>,
+    inputs: Vec<CompileManifestInput>,
+    schema: CompileSchema,
+) -> anyhow::Result<CompileManifestJson> {
+    let bytes = std::fs::read(out).with_context(|| format!("read {}", out.display()))?;
+    let file = agentsdb_format::LayerFile::open(out)
+        .with_context(|| format!("open layer {}", out.display()))?;
+    let chunks = agentsdb_format::read_all_chunks(&file)
+        .with_context(|| format!("read chunks from {}", out.display()))?
+        .len();
+    let metadata = file
+        .layer_metadata_bytes()
+        .map(LayerMetadataV1::from_json_bytes)
+        .transpose()?;
+
+    Ok(CompileManifestJson {
+        v: 1,
+        tool_name: "agentsdb-cli".to_string(),
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        deterministic,
+        input_file,
+        inputs,
+        schema,
+        embedding_profile: metadata.as_ref().map(|m| m.embedding_profile.clone()),
+        embedder_metadata: metadata.and_then(|m| m.embedder_metadata),
+        output: CompileManifestOutput {
+            path: out.display().to_string(),
+            sha256: agentsdb_ops::util::hex_lower(&agentsdb_embeddings::cache::sha256(&bytes)),
+            bytes: bytes.len() as u64,
+            chunks,
+        },
+    })
+}
+
+/// Layer metadata without provider response details (request ids, headers), which differ on
+/// every call and would make otherwise identical builds differ.
+fn reproducible_layer_metadata(embedder: &dyn Embedder) -> anyhow::Result<Vec<u8>> {
+    let mut metadata = embedder.metadata();
+    metadata.provider_response = None;
+    metadata.provider_response_headers = None;
+    LayerMetadataV1::new(embedder.profile().clone())
+        .with_embedder_metadata(metadata)
+        .with_tool("agentsdb-cli", env!("CARGO_PKG_VERSION"))
+        .to_json_bytes()
+        .context("serialize layer metadata")
+}
+
+/// Compiles `input` into the layer at `out`. With `deterministic`, timestamps are zeroed, chunks
+/// without an id get one derived from their source and content, and volatile provider metadata
+/// is left out, so identical inputs produce byte-identical layers.
 pub(crate) fn compile_to_layer(
     input: &mut CompileInput,
     out: &str,
     replace: bool,
+    deterministic: bool,
 ) -> anyhow::Result<(LayerWriteAction, usize)> {
     if input.schema.dim == 0 {
         anyhow::bail!("schema.dim must be non-zero");
@@ -207,13 +346,45 @@ pub(crate) fn compile_to_layer(
         agentsdb_format::EmbeddingElementType::F32 => 1.0,
         agentsdb_format::EmbeddingElementType::I8 => input.schema.quant_scale.unwrap_or(1.0),
     };
+    let layout = match input.schema.layout.as_deref() {
+        None => agentsdb_format::EmbeddingLayout::RowMajor,
+        Some(name) => agentsdb_format::EmbeddingLayout::from_name(name).with_context(|| {
+            format!("schema.layout must be 'row-major' or 'blocked' (got {name:?})")
+        })?,
+    };
     let schema = agentsdb_format::LayerSchema {
         dim: input.schema.dim,
         element_type,
         quant_scale,
+        layout,
     };
 
-    input.chunks.sort_by_key(|c| c.id);
+    if deterministic {
+        let mut used_ids: BTreeSet<u32> = input
+            .chunks
+            .iter()
+            .map(|c| c.id)
+            .filter(|&id| id != 0)
+            .collect();
+        for c in &mut input.chunks {
+            c.created_at_unix_ms = 0;
+            if c.id == 0 {
+                c.id = assign_stable_id(Path::new(&chunk_label(c)), &c.content, &mut used_ids);
+            }
+        }
+    }
+
+    // Order by id, breaking ties on the remaining fields, so the string dictionary (interned in
+    // chunk order) does not depend on input order.
+    input.chunks.sort_by(|a, b| {
+        (a.id, &a.kind, &a.author, &a.content, a.created_at_unix_ms).cmp(&(
+            b.id,
+            &b.kind,
+            &b.author,
+            &b.content,
+            b.created_at_unix_ms,
+        ))
+    });
     let dim = schema.dim as usize;
 
     let out_path = Path::new(out);
@@ -227,11 +398,16 @@ pub(crate) fn compile_to_layer(
         .map(|c| c.content.clone())
         .collect();
     let mut embedded_iter = embedder
-        .embed(&to_embed)
+        .embed_for(EmbedPurpose::Document, &to_embed)
         .context("embed chunks")?
         .into_iter();
 
-    let layer_metadata_json = create_layer_metadata(embedder.as_ref())?;
+    let layer_metadata_json = if deterministic {
+        reproducible_layer_metadata(embedder.as_ref())?
+    } else {
+        create_layer_metadata(embedder.as_ref())?
+    };
+    let layer_metadata_json = keep_kinds_of(layer_metadata_json, out_path)?;
     let mut chunks: Vec<agentsdb_format::ChunkInput> = input
         .chunks
         .drain(..)
@@ -282,6 +458,13 @@ pub(crate) fn compile_to_layer(
                 schema.quant_scale
             );
         }
+        if input.schema.layout.is_some() && existing_schema.layout != schema.layout {
+            anyhow::bail!(
+                "output layer uses the {} embedding layout, not {}; use --replace, or `agentsdb compact --embedding-layout` to convert it",
+                existing_schema.layout.name(),
+                schema.layout.name()
+            );
+        }
 
         append_with_validated_metadata(
             out_path,
@@ -317,6 +500,7 @@ mod tests {
                 dim: 8,
                 element_type: "f32".to_string(),
                 quant_scale: None,
+                layout: None,
             },
             chunks: vec![CompileChunk {
                 id: 1,
@@ -329,8 +513,8 @@ mod tests {
                 sources: vec![],
             }],
         };
-        let (action1, chunks1) =
-            compile_to_layer(&mut input1, out.to_str().unwrap(), false).expect("initial compile");
+        let (action1, chunks1) = compile_to_layer(&mut input1, out.to_str().unwrap(), false, false)
+            .expect("initial compile");
         assert_eq!(action1, LayerWriteAction::Created);
         assert_eq!(chunks1, 1);
 
@@ -339,6 +523,7 @@ mod tests {
                 dim: 8,
                 element_type: "f32".to_string(),
                 quant_scale: None,
+                layout: None,
             },
             chunks: vec![CompileChunk {
                 id: 2,
@@ -351,8 +536,8 @@ mod tests {
                 sources: vec![],
             }],
         };
-        let (action2, chunks2) =
-            compile_to_layer(&mut input2, out.to_str().unwrap(), false).expect("append compile");
+        let (action2, chunks2) = compile_to_layer(&mut input2, out.to_str().unwrap(), false, false)
+            .expect("append compile");
         assert_eq!(action2, LayerWriteAction::Appended);
         assert_eq!(chunks2, 1);
 
@@ -0,0 +1,31 @@
+use clap_complete::env::Shells;
+
+/// Environment variable the registration scripts set when calling back into the binary.
+/// `clap_complete::CompleteEnv` in `main` reads the same one.
+const COMPLETE_VAR: &str = "COMPLETE";
+
+pub(crate) fn cmd_completions(shell: &str, json: bool) -> anyhow::Result<()> {
+    if json {
+        anyhow::bail!("JSON output (--json, --output json) is not supported for completions");
+    }
+    let shells = Shells::builtins();
+    let Some(completer) = shells.completer(shell) else {
+        anyhow::bail!(
+            "unsupported shell {shell:?} (expected one of: {})",
+            shells.names().collect::<Vec<_>>().join(", ")
+        );
+    };
+    // Call back into this exact binary so completions keep working when it is not on PATH.
+    let exe = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.to_str().map(str::to_string))
+        .unwrap_or_else(|| "agentsdb".to_string());
+    completer.write_registration(
+        COMPLETE_VAR,
+        "agentsdb",
+        "agentsdb",
+        &exe,
+        &mut std::io::stdout(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+use std::io::{IsTerminal, Write};
+
+use agentsdb_ops::dedupe::{DedupeOptions, DuplicateGroup, GroupResolution, MatchKind};
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn cmd_dedupe(
+    layers: &agentsdb_query::LayerSet,
+    options: DedupeOptions,
+    apply: bool,
+    interactive: bool,
+    merge: bool,
+    fail_on_duplicates: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    if let Some(threshold) = options.threshold {
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            anyhow::bail!("--threshold must be in (0, 1]");
+        }
+    }
+    if merge && !apply && !interactive {
+        anyhow::bail!("--merge requires --apply or --interactive");
+    }
+    if interactive && (json || !std::io::stdin().is_terminal()) {
+        anyhow::bail!("--interactive needs a terminal; use --apply instead");
+    }
+
+    let report = agentsdb_ops::dedupe::find_duplicates(layers, options)?;
+
+    let mut resolutions = Vec::new();
+    for group in &report.groups {
+        if interactive {
+            print_group(group);
+            if !confirm("Tombstone these duplicates?")? {
+                continue;
+            }
+        } else if !apply {
+            continue;
+        }
+        let resolution = agentsdb_ops::dedupe::resolve_group(
+            &report,
+            group,
+            merge,
+            "agentsdb-cli",
+            env!("CARGO_PKG_VERSION"),
+        )?;
+        resolutions.push((group, resolution));
+    }
+
+    if json {
+        #[derive(Serialize)]
+        struct Resolved<'a> {
+            keep_layer: &'a str,
+            keep_id: u32,
+            #[serde(flatten)]
+            resolution: &'a GroupResolution,
+        }
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            #[serde(flatten)]
+            report: &'a agentsdb_ops::dedupe::DedupeReport,
+            duplicate_count: usize,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            resolved: Vec<Resolved<'a>>,
+        }
+        let duplicate_count = report.groups.iter().map(|g| g.duplicates.len()).sum();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: !(fail_on_duplicates && duplicate_count > 0),
+                report: &report,
+                duplicate_count,
+                resolved: resolutions
+                    .iter()
+                    .map(|(group, resolution)| Resolved {
+                        keep_layer: &group.keep.layer,
+                        keep_id: group.keep.id,
+                        resolution,
+                    })
+                    .collect(),
+            })?
+        );
+    } else {
+        println!(
+            "Scanned {} chunks: {} duplicate groups",
+            report.scanned,
+            report.groups.len()
+        );
+        if !interactive {
+            for group in &report.groups {
+                print_group(group);
+            }
+        }
+        for (group, resolution) in &resolutions {
+            for (layer, id) in &resolution.retracted {
+                println!(
+                    "Retracted {layer}:{id} (duplicate of {}:{})",
+                    group.keep.layer, group.keep.id
+                );
+            }
+            for (layer, id, reason) in &resolution.skipped {
+                println!("Skipped {layer}:{id}: {reason}");
+            }
+            if let Some(merged) = resolution.merged_into {
+                println!(
+                    "Merged sources into {}:{merged} (revision of {})",
+                    group.keep.layer, group.keep.id
+                );
+            }
+        }
+    }
+
+    if fail_on_duplicates && !report.groups.is_empty() {
+        anyhow::bail!("found {} duplicate groups", report.groups.len());
+    }
+    Ok(())
+}
+
+fn print_group(group: &DuplicateGroup) {
+    let keep = &group.keep;
+    println!();
+    println!(
+        "keep {}:{} [{}] {}",
+        keep.layer, keep.id, keep.kind, keep.content_preview
+    );
+    for dup in &group.duplicates {
+        let chunk = &dup.chunk;
+        let how = match dup.match_kind {
+            MatchKind::Exact => "exact".to_string(),
+            MatchKind::Near => format!("near {:.3}", dup.similarity),
+        };
+        println!(
+            "  {how:>10}  {}:{} [{}] {}",
+            chunk.layer, chunk.id, chunk.kind, chunk.content_preview
+        );
+    }
+}
+
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    eprint!("{question} [y/N] ");
+    std::io::stderr().flush().ok();
+    let mut s = String::new();
+    std::io::stdin().read_line(&mut s)?;
+    let s = s.trim().to_ascii_lowercase();
+    Ok(s == "y" || s == "yes")
+}
@@ -7,8 +7,17 @@ pub(crate) fn cmd_diff(
     delta: &str,
     target: Option<&str>,
     user: Option<&str>,
+    semantic: bool,
+    threshold: f32,
     json: bool,
 ) -> anyhow::Result<()> {
+    if semantic {
+        if target.is_some() {
+            anyhow::bail!("--semantic cannot be combined with --target");
+        }
+        return cmd_diff_semantic(base, delta, threshold, json);
+    }
+
     let base_file =
         agentsdb_format::LayerFile::open(base).with_context(|| format!("open {base}"))?;
     let delta_file =
@@ -103,3 +112,95 @@ pub(crate) fn cmd_diff(
     }
     Ok(())
 }
+
+/// `diff --semantic`: pairs chunks only in `base` with chunks only in `delta` by embedding
+/// similarity and reports them as modified.
+fn cmd_diff_semantic(base: &str, delta: &str, threshold: f32, json: bool) -> anyhow::Result<()> {
+    if !(0.0..=1.0).contains(&threshold) {
+        anyhow::bail!("--threshold must be between 0 and 1");
+    }
+    for path in [base, delta] {
+        if !std::path::Path::new(path).exists() {
+            anyhow::bail!("layer not found: {path}");
+        }
+    }
+    let semantic = agentsdb_ops::semantic_diff_layers(
+        std::path::Path::new(delta),
+        std::path::Path::new(base),
+        threshold,
+    )?;
+    let ids = |chunks: &[agentsdb_ops::diff::ChunkDiff]| -> Vec<u32> {
+        chunks.iter().map(|c| c.id).collect()
+    };
+    let added = ids(&semantic.diff.added);
+    let removed = ids(&semantic.diff.removed);
+    let changed = ids(&semantic.diff.changed);
+
+    if json {
+        #[derive(Serialize)]
+        struct Modified {
+            base_id: u32,
+            delta_id: u32,
+            similarity: f32,
+        }
+        #[derive(Serialize)]
+        struct Out<'a> {
+            base: &'a str,
+            delta: &'a str,
+            threshold: f32,
+            modified: Vec<Modified>,
+            added: Vec<u32>,
+            removed: Vec<u32>,
+            changed: Vec<u32>,
+            unchanged: usize,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                base,
+                delta,
+                threshold,
+                modified: semantic
+                    .modified
+                    .iter()
+                    .map(|m| Modified {
+                        base_id: m.before.id,
+                        delta_id: m.after.id,
+                        similarity: m.similarity,
+                    })
+                    .collect(),
+                added,
+                removed,
+                changed,
+                unchanged: semantic.diff.unchanged,
+            })?
+        );
+        return Ok(());
+    }
+
+    println!("Semantic diff: {base} -> {delta} (threshold {threshold})");
+    println!(
+        "Modified (paired by embedding similarity): {}",
+        semantic.modified.len()
+    );
+    for m in &semantic.modified {
+        println!(
+            "  ~ {} -> {} ({:.3})",
+            m.before.id, m.after.id, m.similarity
+        );
+    }
+    println!("Added (only in delta): {}", added.len());
+    for id in &added {
+        println!("  + {id}");
+    }
+    println!("Removed (only in base): {}", removed.len());
+    for id in &removed {
+        println!("  - {id}");
+    }
+    println!("Changed in place (same id): {}", changed.len());
+    for id in &changed {
+        println!("  ~ {id}");
+    }
+    println!("Unchanged: {}", semantic.diff.unchanged);
+    Ok(())
+}
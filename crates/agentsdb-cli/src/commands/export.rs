@@ -1,5 +1,10 @@
 use anyhow::Context;
 use std::io::Write;
+use std::path::PathBuf;
+
+use agentsdb_ops::export::vector_store::{
+    export_to_vector_store, VectorStoreExportOptions, VectorStoreExportReport, VectorStoreWriter,
+};
 
 use agentsdb_embeddings::config::standard_layer_paths_for_dir;
 
@@ -21,24 +26,12 @@ fn parse_layers_csv(s: &str) -> anyhow::Result<Vec<String>> {
     Ok(out)
 }
 
-pub(crate) fn cmd_export(
-    dir: &str,
-    format: &str,
-    layers_csv: &str,
-    out_path: Option<&str>,
-    redact: &str,
-    json: bool,
-) -> anyhow::Result<()> {
-    if json {
-        anyhow::bail!("--json is not supported for export (export output is already JSON/NDJSON)");
-    }
-
-    let layers = parse_layers_csv(layers_csv)?;
+/// The existing standard layer files among `layers_csv`, with their logical layer names.
+fn existing_layer_paths(dir: &str, layers_csv: &str) -> anyhow::Result<Vec<(String, PathBuf)>> {
     let siblings = standard_layer_paths_for_dir(std::path::Path::new(dir));
 
-    // Build list of paths to export
-    let mut paths_to_export = Vec::new();
-    for layer in layers {
+    let mut out = Vec::new();
+    for layer in parse_layers_csv(layers_csv)? {
         let path = match layer.as_str() {
             "base" => siblings.base.clone(),
             "user" => siblings.user.clone(),
@@ -47,9 +40,29 @@ pub(crate) fn cmd_export(
             _ => continue,
         };
         if path.exists() {
-            paths_to_export.push(path);
+            out.push((layer, path));
         }
     }
+    Ok(out)
+}
+
+pub(crate) fn cmd_export(
+    dir: &str,
+    format: &str,
+    layers_csv: &str,
+    out_path: Option<&str>,
+    redact: &str,
+    namespace: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    if json {
+        anyhow::bail!("JSON output (--json, --output json) is not supported for export (export output is already JSON/NDJSON)");
+    }
+
+    let paths_to_export: Vec<PathBuf> = existing_layer_paths(dir, layers_csv)?
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
 
     // Build list of (abs_path, rel_path, logical_layer) tuples with proper lifetimes
     let layers_and_paths: Vec<_> = paths_to_export
@@ -69,6 +82,7 @@ pub(crate) fn cmd_export(
         layers_and_paths,
         format,
         redact,
+        namespace,
         "agentsdb-cli",
         env!("CARGO_PKG_VERSION"),
     )?;
@@ -82,3 +96,108 @@ pub(crate) fn cmd_export(
 
     Ok(())
 }
+
+/// Where `export --format pgvector|qdrant` sends the records.
+pub(crate) enum VectorStoreTarget {
+    /// A `psql` script loading `table`, written to `out` (stdout if `None`).
+    Pgvector { table: String, out: Option<String> },
+    /// A Qdrant server's REST API.
+    Qdrant { url: String, collection: String },
+}
+
+pub(crate) fn cmd_export_vector_store(
+    dir: &str,
+    layers_csv: &str,
+    target: &VectorStoreTarget,
+    redact: &str,
+    batch_size: usize,
+    json: bool,
+) -> anyhow::Result<()> {
+    let redact_content = match redact {
+        "none" => false,
+        "content" => true,
+        _ => anyhow::bail!("vector stores need embeddings; use --redact none or content"),
+    };
+    let mut layers = agentsdb_query::LayerSet {
+        base: None,
+        user: None,
+        delta: None,
+        local: None,
+    };
+    for (layer, path) in existing_layer_paths(dir, layers_csv)? {
+        let path = Some(path.to_string_lossy().to_string());
+        match layer.as_str() {
+            "base" => layers.base = path,
+            "user" => layers.user = path,
+            "delta" => layers.delta = path,
+            _ => layers.local = path,
+        }
+    }
+    let options = VectorStoreExportOptions {
+        batch_size,
+        redact_content,
+    };
+
+    let (report, destination) = match target {
+        VectorStoreTarget::Pgvector { table, out } => {
+            if json && out.is_none() {
+                anyhow::bail!(
+                    "--json with --format pgvector needs --out (the script goes to stdout)"
+                );
+            }
+            let sink: Box<dyn std::io::Write> = match out {
+                Some(p) => Box::new(std::io::BufWriter::new(
+                    std::fs::File::create(p).with_context(|| format!("create {p}"))?,
+                )),
+                None => Box::new(std::io::stdout().lock()),
+            };
+            let mut writer = agentsdb_ops::export::pgvector::PgvectorCopyWriter::new(sink, table)?;
+            let report = export_to_vector_store(&layers, &mut writer, options)?;
+            (report, out.clone())
+        }
+        VectorStoreTarget::Qdrant { url, collection } => {
+            let mut writer = qdrant_writer(url, collection)?;
+            let report = export_to_vector_store(&layers, writer.as_mut(), options)?;
+            (report, Some(format!("{url} (collection {collection})")))
+        }
+    };
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            destination: Option<&'a str>,
+            #[serde(flatten)]
+            report: &'a VectorStoreExportReport,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: true,
+                destination: destination.as_deref(),
+                report: &report,
+            })?
+        );
+    } else {
+        // The pgvector script may be on stdout, so the summary goes to stderr.
+        eprintln!(
+            "Exported {} chunks (dim {}) to {}",
+            report.records,
+            report.dim,
+            destination.as_deref().unwrap_or("stdout")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(feature = "qdrant")]
+fn qdrant_writer(url: &str, collection: &str) -> anyhow::Result<Box<dyn VectorStoreWriter>> {
+    Ok(Box::new(agentsdb_ops::export::qdrant::QdrantWriter::new(
+        url, collection,
+    )))
+}
+
+#[cfg(not(feature = "qdrant"))]
+fn qdrant_writer(_url: &str, _collection: &str) -> anyhow::Result<Box<dyn VectorStoreWriter>> {
+    anyhow::bail!("--format qdrant needs a build with the `qdrant` feature")
+}
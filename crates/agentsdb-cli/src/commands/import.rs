@@ -1,7 +1,12 @@
 use anyhow::Context;
 use serde::Serialize;
 
-use agentsdb_embeddings::config::standard_layer_paths_for_dir;
+use agentsdb_core::export::ExportBundleV1;
+use agentsdb_embeddings::config::{get_immutable_embedding_options, standard_layer_paths_for_dir};
+use agentsdb_embeddings::usage::UsageReport;
+
+use crate::commands::import_markdown::{markdown_bundle, MarkdownImportOptions};
+use crate::commands::ingest::configured_dim;
 
 fn resolve_target_path(dir: &str, target: &str, out: Option<&str>) -> anyhow::Result<String> {
     if let Some(p) = out {
@@ -18,9 +23,140 @@ fn resolve_target_path(dir: &str, target: &str, out: Option<&str>) -> anyhow::Re
     Ok(p.to_string_lossy().to_string())
 }
 
+/// What `import` reads, by `--format`.
+pub(crate) enum ImportSource {
+    /// A JSON, NDJSON or Parquet export (`--format` checks the detected kind when given).
+    Export {
+        format: Option<String>,
+    },
+    Markdown(MarkdownImportOptions),
+    Sqlite {
+        table: Option<String>,
+        query: Option<String>,
+    },
+    Chroma {
+        collection: String,
+        tenant: String,
+        database: String,
+    },
+}
+
+impl ImportSource {
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Export { .. } => "export",
+            Self::Markdown(_) => "markdown",
+            Self::Sqlite { .. } => "sqlite",
+            Self::Chroma { .. } => "chroma",
+        }
+    }
+}
+
+/// The input as export bytes, plus the dim for a new target layer. Sources other than exports
+/// default it to the configured dim, so embeddings of another size get re-embedded.
+fn read_input(
+    dir: &str,
+    input: &str,
+    source: &ImportSource,
+    has_target: bool,
+    dim: Option<u32>,
+) -> anyhow::Result<(Vec<u8>, Option<u32>)> {
+    if !has_target && !matches!(source, ImportSource::Export { .. }) {
+        anyhow::bail!(
+            "--format {} needs --target (the layer to write to)",
+            source.name()
+        );
+    }
+    let dir = std::path::Path::new(dir);
+    let bundle = match source {
+        ImportSource::Export { format } => {
+            let bytes = std::fs::read(input).with_context(|| format!("read {}", input))?;
+            if let Some(format) = format {
+                let detected = agentsdb_ops::import::detect_export_format(&bytes);
+                if detected != format {
+                    anyhow::bail!("{input} is not a {format} export (it looks like {detected})");
+                }
+            }
+            return Ok((bytes, dim));
+        }
+        ImportSource::Markdown(options) => {
+            let bundle = markdown_bundle(std::path::Path::new(input), options)?;
+            // No embeddings to infer a dim from.
+            let dim = match dim {
+                Some(d) => d,
+                None => configured_dim(dir)?,
+            };
+            return Ok((
+                serde_json::to_vec(&bundle).context("serialize markdown chunks")?,
+                Some(dim),
+            ));
+        }
+        ImportSource::Sqlite { table, query } => sqlite_bundle(input, table, query)?,
+        ImportSource::Chroma {
+            collection,
+            tenant,
+            database,
+        } => chroma_bundle(input, tenant, database, collection)?,
+    };
+    let configured = get_immutable_embedding_options(dir)
+        .context("get immutable embedding options")?
+        .dim
+        .map(|d| u32::try_from(d).context("configured dim overflows u32"))
+        .transpose()?;
+    Ok((
+        serde_json::to_vec(&bundle).context("serialize imported records")?,
+        dim.or(configured),
+    ))
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_bundle(
+    input: &str,
+    table: &Option<String>,
+    query: &Option<String>,
+) -> anyhow::Result<ExportBundleV1> {
+    agentsdb_ops::import::sqlite::read_bundle(
+        std::path::Path::new(input),
+        agentsdb_ops::import::sqlite::SqliteImportOptions {
+            table: table.as_deref(),
+            query: query.as_deref(),
+        },
+    )
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sqlite_bundle(
+    _input: &str,
+    _table: &Option<String>,
+    _query: &Option<String>,
+) -> anyhow::Result<ExportBundleV1> {
+    anyhow::bail!("--format sqlite needs a build with the `sqlite` feature")
+}
+
+#[cfg(feature = "chroma")]
+fn chroma_bundle(
+    url: &str,
+    tenant: &str,
+    database: &str,
+    collection: &str,
+) -> anyhow::Result<ExportBundleV1> {
+    agentsdb_ops::import::chroma::ChromaSource::new(url, tenant, database, collection).read_bundle()
+}
+
+#[cfg(not(feature = "chroma"))]
+fn chroma_bundle(
+    _url: &str,
+    _tenant: &str,
+    _database: &str,
+    _collection: &str,
+) -> anyhow::Result<ExportBundleV1> {
+    anyhow::bail!("--format chroma needs a build with the `chroma` feature")
+}
+
 pub(crate) fn cmd_import(
     dir: &str,
     input: &str,
+    source: &ImportSource,
     target: Option<&str>,
     out: Option<&str>,
     dry_run: bool,
@@ -28,11 +164,10 @@ pub(crate) fn cmd_import(
     preserve_ids: bool,
     allow_base: bool,
     dim: Option<u32>,
+    price_per_million_tokens: Option<f64>,
     json: bool,
 ) -> anyhow::Result<()> {
-    // Read input file
-    let bytes = std::fs::read(input).with_context(|| format!("read {}", input))?;
-    let data = std::str::from_utf8(&bytes).context("input must be valid UTF-8")?;
+    let (bytes, dim) = read_input(dir, input, source, target.is_some(), dim)?;
 
     if let Some(target) = target {
         let target_path = resolve_target_path(dir, target, out)?;
@@ -40,7 +175,7 @@ pub(crate) fn cmd_import(
         let outcome = agentsdb_ops::import::import_into_layer(
             std::path::Path::new(&target_path),
             target,
-            data,
+            &bytes,
             dry_run,
             dedupe,
             preserve_ids,
@@ -50,6 +185,11 @@ pub(crate) fn cmd_import(
             env!("CARGO_PKG_VERSION"),
         )?;
 
+        let embedding_usage = outcome
+            .embedding_usage
+            .clone()
+            .map(|u| u.with_price(price_per_million_tokens));
+
         #[derive(Serialize)]
         struct Out<'a> {
             ok: bool,
@@ -57,6 +197,10 @@ pub(crate) fn cmd_import(
             imported: usize,
             skipped: usize,
             dry_run: bool,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            embedding_usage: Option<UsageReport>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            operation: Option<u32>,
         }
         let out_struct = Out {
             ok: true,
@@ -64,6 +208,8 @@ pub(crate) fn cmd_import(
             imported: outcome.imported,
             skipped: outcome.skipped,
             dry_run: outcome.dry_run,
+            embedding_usage: embedding_usage.clone(),
+            operation: outcome.operation,
         };
 
         if json {
@@ -94,6 +240,12 @@ pub(crate) fn cmd_import(
                     outcome.imported, target_path, outcome.skipped
                 );
             }
+            if let Some(op) = outcome.operation {
+                println!("Recorded as operation {op} (undo with `agentsdb undo {op}`)");
+            }
+            if let Some(usage) = &embedding_usage {
+                println!("{}", usage.summary());
+            }
         }
 
         return Ok(());
@@ -120,6 +272,14 @@ pub(crate) fn cmd_import(
 
     let total_imported: usize = results.iter().map(|(_, o)| o.imported).sum();
     let total_skipped: usize = results.iter().map(|(_, o)| o.skipped).sum();
+    let mut embedding_usage: Option<UsageReport> = None;
+    for usage in results.iter().filter_map(|(_, o)| o.embedding_usage.as_ref()) {
+        match embedding_usage.as_mut() {
+            Some(total) => total.merge(usage),
+            None => embedding_usage = Some(usage.clone()),
+        }
+    }
+    let embedding_usage = embedding_usage.map(|u| u.with_price(price_per_million_tokens));
 
     #[derive(Serialize)]
     struct LayerOut<'a> {
@@ -136,6 +296,8 @@ pub(crate) fn cmd_import(
         skipped: usize,
         dry_run: bool,
         layers: Vec<LayerOut<'a>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        embedding_usage: Option<UsageReport>,
     }
 
     if json {
@@ -155,6 +317,7 @@ pub(crate) fn cmd_import(
             skipped: total_skipped,
             dry_run,
             layers,
+            embedding_usage,
         };
         println!("{}", serde_json::to_string_pretty(&out_struct)?);
     } else {
@@ -189,6 +352,9 @@ pub(crate) fn cmd_import(
                 total_skipped
             );
         }
+        if let Some(usage) = &embedding_usage {
+            println!("{}", usage.summary());
+        }
     }
 
     Ok(())
@@ -0,0 +1,275 @@
+use anyhow::Context;
+use std::path::Path;
+
+use agentsdb_core::export::{
+    ExportBundleV1, ExportChunkV1, ExportLayerSchemaV1, ExportLayerV1, ExportSourceV1,
+    ExportToolInfo,
+};
+
+use crate::commands::ingest::{chunk_section, Section};
+use crate::util::{collect_files_matching, rel_path_string};
+
+/// Files picked up when importing a directory with `--format markdown`.
+const MARKDOWN_INCLUDES: [&str; 4] = ["*.md", "*.mdx", "*.markdown", "*.txt"];
+
+/// Prefix of the sources recording front-matter tags: `tag:<name>`.
+const TAG_SOURCE_PREFIX: &str = "tag:";
+
+/// How `import --format markdown` turns files into chunks.
+pub(crate) struct MarkdownImportOptions {
+    pub(crate) chunk_size: usize,
+    pub(crate) overlap: usize,
+    /// Kind for files whose front matter does not set one.
+    pub(crate) kind: String,
+}
+
+/// Chunk settings taken from a file's front matter.
+#[derive(Debug, Default, PartialEq)]
+struct FrontMatter {
+    kind: Option<String>,
+    confidence: Option<f32>,
+    author: Option<String>,
+    tags: Vec<String>,
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+/// Splits off a leading `---` front-matter block (a flat YAML subset: `key: value`,
+/// `tags: [a, b]` or `tags:` followed by `- a` items). Returns the front matter, the body,
+/// and the 1-based line the body starts on.
+fn split_front_matter(rel: &str, content: &str) -> anyhow::Result<(FrontMatter, String, usize)> {
+    let mut lines = content.lines();
+    if lines.next().map(str::trim_end) != Some("---") {
+        return Ok((FrontMatter::default(), content.to_string(), 1));
+    }
+    let mut front = FrontMatter::default();
+    let mut in_tags = false;
+    for (i, line) in lines.by_ref().enumerate() {
+        let trimmed = line.trim();
+        if trimmed == "---" || trimmed == "..." {
+            let body: Vec<&str> = content.lines().skip(i + 2).collect();
+            return Ok((front, body.join("\n"), i + 3));
+        }
+        if in_tags {
+            if let Some(tag) = trimmed.strip_prefix("- ") {
+                front.tags.push(unquote(tag).to_string());
+                continue;
+            }
+            in_tags = false;
+        }
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let value = unquote(value);
+        match key.trim() {
+            "kind" | "type" => front.kind = Some(value.to_string()),
+            "confidence" => {
+                let confidence: f32 = value
+                    .parse()
+                    .with_context(|| format!("{rel}: front-matter confidence {value:?}"))?;
+                if !(0.0..=1.0).contains(&confidence) {
+                    anyhow::bail!("{rel}: front-matter confidence must be within 0..=1");
+                }
+                front.confidence = Some(confidence);
+            }
+            "author" => front.author = Some(value.to_string()),
+            "tags" => {
+                let list = value.trim_start_matches('[').trim_end_matches(']');
+                front.tags.extend(
+                    list.split(',')
+                        .map(unquote)
+                        .filter(|t| !t.is_empty())
+                        .map(str::to_string),
+                );
+                in_tags = value.is_empty();
+            }
+            _ => {}
+        }
+    }
+    // No closing delimiter: not front matter after all.
+    Ok((FrontMatter::default(), content.to_string(), 1))
+}
+
+/// Splits markdown at headings (outside code fences); each section keeps its heading line.
+fn heading_sections(body: &str, first_line: usize) -> Vec<Section> {
+    let mut out = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut start = first_line;
+    let mut fence: Option<&str> = None;
+    for (i, line) in body.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+        } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = trimmed.get(..3);
+        } else if is_heading(trimmed) && !current.is_empty() {
+            push_section(&mut out, &mut current, start);
+            start = first_line + i;
+        }
+        current.push(line);
+    }
+    push_section(&mut out, &mut current, start);
+    out
+}
+
+fn is_heading(line: &str) -> bool {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes) && line.chars().nth(hashes).is_none_or(char::is_whitespace)
+}
+
+fn push_section(out: &mut Vec<Section>, lines: &mut Vec<&str>, line: usize) {
+    let text = lines.join("\n");
+    lines.clear();
+    if !text.trim().is_empty() {
+        out.push(Section {
+            line,
+            text: text.trim_end().to_string(),
+            markdown: true,
+        });
+    }
+}
+
+fn file_chunks(
+    rel: &str,
+    content: &str,
+    options: &MarkdownImportOptions,
+    now_ms: u64,
+) -> anyhow::Result<Vec<ExportChunkV1>> {
+    let (front, body, first_line) = split_front_matter(rel, content)?;
+    let sections = if rel.ends_with(".txt") {
+        vec![Section {
+            line: first_line,
+            text: body,
+            markdown: false,
+        }]
+    } else {
+        heading_sections(&body, first_line)
+    };
+
+    let mut out = Vec::new();
+    for section in &sections {
+        for (line, text) in chunk_section(section, options.chunk_size, options.overlap)? {
+            let mut sources = vec![ExportSourceV1::SourceString {
+                value: format!("{rel}:{line}"),
+            }];
+            sources.extend(front.tags.iter().map(|tag| ExportSourceV1::SourceString {
+                value: format!("{TAG_SOURCE_PREFIX}{tag}"),
+            }));
+            out.push(ExportChunkV1 {
+                id: 0,
+                kind: front.kind.clone().unwrap_or_else(|| options.kind.clone()),
+                content: Some(text),
+                author: front.author.clone().unwrap_or_else(|| "human".to_string()),
+                confidence: front.confidence.unwrap_or(1.0),
+                created_at_unix_ms: now_ms,
+                sources,
+                embedding: None,
+                content_sha256: None,
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Converts a markdown/text file, or every such file under a directory, into a one-layer
+/// export bundle without embeddings (the import computes them).
+pub(crate) fn markdown_bundle(
+    input: &Path,
+    options: &MarkdownImportOptions,
+) -> anyhow::Result<ExportBundleV1> {
+    let (root, files) = if input.is_dir() {
+        let includes: Vec<String> = MARKDOWN_INCLUDES.iter().map(|s| (*s).to_string()).collect();
+        (input, collect_files_matching(input, &includes, &[])?)
+    } else {
+        let name = input
+            .file_name()
+            .with_context(|| format!("{} is not a file", input.display()))?;
+        (
+            input.parent().unwrap_or_else(|| Path::new(".")),
+            vec![Path::new(name).to_path_buf()],
+        )
+    };
+
+    let now_ms = agentsdb_ops::util::now_unix_ms();
+    let mut chunks = Vec::new();
+    for rel in &files {
+        let path = root.join(rel);
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        chunks.extend(file_chunks(
+            &rel_path_string(rel),
+            &content,
+            options,
+            now_ms,
+        )?);
+    }
+    Ok(ExportBundleV1 {
+        format: "agentsdb.export.v1".to_string(),
+        tool: ExportToolInfo {
+            name: "agentsdb-cli".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+        layers: vec![ExportLayerV1 {
+            path: input.display().to_string(),
+            layer: None,
+            schema: ExportLayerSchemaV1 {
+                dim: 0,
+                element_type: "f32".to_string(),
+                quant_scale: 1.0,
+            },
+            layer_metadata_json: None,
+            chunks,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn front_matter_sets_chunk_fields_and_headings_split_sections() -> anyhow::Result<()> {
+        let doc = "---\nkind: runbook\nconfidence: 0.8\ntags:\n  - deploy\n  - \"ops\"\n---\n# Deploy\n\nRun the script.\n\n```sh\n# not a heading\n```\n\n## Rollback\n\nRevert the tag.\n";
+        let options = MarkdownImportOptions {
+            chunk_size: 1500,
+            overlap: 0,
+            kind: "note".to_string(),
+        };
+        let chunks = file_chunks("docs/deploy.md", doc, &options, 0)?;
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks
+            .iter()
+            .all(|c| c.kind == "runbook" && (c.confidence - 0.8).abs() < f32::EPSILON));
+        assert_eq!(
+            chunks[0].content.as_deref(),
+            Some("# Deploy\n\nRun the script.\n\n```sh\n# not a heading\n```")
+        );
+        assert_eq!(
+            chunks[1].content.as_deref(),
+            Some("## Rollback\n\nRevert the tag.")
+        );
+        let sources: Vec<&str> = chunks[1]
+            .sources
+            .iter()
+            .filter_map(|s| match s {
+                ExportSourceV1::SourceString { value } => Some(value.as_str()),
+                ExportSourceV1::ChunkId { .. } => None,
+            })
+            .collect();
+        assert_eq!(sources, ["docs/deploy.md:16", "tag:deploy", "tag:ops"]);
+
+        let (front, body, line) = split_front_matter("plain.md", "# Title\n")?;
+        assert_eq!(front, FrontMatter::default());
+        assert_eq!((body.as_str(), line), ("# Title\n", 1));
+        Ok(())
+    }
+}
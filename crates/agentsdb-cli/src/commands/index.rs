@@ -24,6 +24,7 @@ pub(crate) fn cmd_index(
     layers: LayerSet,
     out_dir: Option<&str>,
     store_embeddings_f32: bool,
+    binary: bool,
     json: bool,
 ) -> anyhow::Result<()> {
     let opened = layers.open().context("open layers")?;
@@ -52,6 +53,7 @@ pub(crate) fn cmd_index(
             &index_path,
             IndexBuildOptions {
                 store_embeddings_even_if_f32: store_embeddings_f32,
+                binary_codes: binary,
             },
         )
         .with_context(|| format!("build index for {:?}", layer.path()))?;
@@ -0,0 +1,551 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use agentsdb_embeddings::config::get_immutable_embedding_options;
+use agentsdb_embeddings::embedder::EmbedPurpose;
+use text_splitter::{ChunkConfig, MarkdownSplitter, TextSplitter};
+
+use crate::embedding_helpers::{
+    create_layer_metadata, create_validated_embedder, keep_kinds_of, validate_embedder_profile,
+};
+use crate::util::{assign_stable_id, collect_files_matching, rel_path_string};
+
+/// Globs used when no `--include` is given: prose documentation anywhere in the tree.
+pub(crate) const DEFAULT_INCLUDES: [&str; 5] = ["*.md", "*.mdx", "*.rst", "*.txt", "*.adoc"];
+
+/// Prefix of the source recording which file an ingested chunk came from and the
+/// SHA-256 of that file's content when it was ingested: `ingest:<rel>@<sha256>`.
+pub(crate) const INGEST_SOURCE_PREFIX: &str = "ingest:";
+
+/// Hash recorded in the ingest source of a tombstone for a deleted file.
+const DELETED_HASH: &str = "deleted";
+
+/// Comment blocks shorter than this (in non-whitespace characters) are not worth a chunk.
+const MIN_COMMENT_CHARS: usize = 40;
+
+/// A span of text to chunk, starting at a 1-based line of its file.
+pub(crate) struct Section {
+    pub(crate) line: usize,
+    pub(crate) text: String,
+    pub(crate) markdown: bool,
+}
+
+/// Line and block comment markers for a source file extension.
+fn comment_markers(ext: &str) -> Option<(&'static str, Option<(&'static str, &'static str)>)> {
+    match ext {
+        "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "go" | "java" | "js" | "jsx" | "mjs" | "ts"
+        | "tsx" | "swift" | "kt" | "cs" | "scala" | "dart" => Some(("//", Some(("/*", "*/")))),
+        "py" | "rb" | "sh" | "bash" | "zsh" | "pl" | "r" => Some(("#", None)),
+        _ => None,
+    }
+}
+
+/// Splits a file into sections: code files contribute their comment blocks, anything
+/// else is taken whole.
+fn extract_sections(rel: &str, content: &str) -> Vec<Section> {
+    let ext = rel
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match comment_markers(&ext) {
+        Some((line_marker, block)) => extract_comments(content, line_marker, block),
+        None => vec![Section {
+            line: 1,
+            text: content.to_string(),
+            markdown: matches!(ext.as_str(), "md" | "mdx"),
+        }],
+    }
+}
+
+/// Collects runs of consecutive comment lines (and block comments) with their markers
+/// stripped.
+fn extract_comments(content: &str, line_marker: &str, block: Option<(&str, &str)>) -> Vec<Section> {
+    let marker_char = line_marker.chars().next().unwrap_or('/');
+    let mut out = Vec::new();
+    let mut run: Vec<&str> = Vec::new();
+    let mut run_start = 0;
+    let mut in_block = false;
+
+    let flush = |run: &mut Vec<&str>, start: usize, out: &mut Vec<Section>| {
+        let text = run.join("\n").trim().to_string();
+        run.clear();
+        if text.chars().filter(|c| !c.is_whitespace()).count() >= MIN_COMMENT_CHARS {
+            out.push(Section {
+                line: start + 1,
+                text,
+                markdown: false,
+            });
+        }
+    };
+
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if in_block {
+            let close = block.map_or("*/", |(_, close)| close);
+            let (body, closed) = match trimmed.split_once(close) {
+                Some((body, _)) => (body, true),
+                None => (trimmed, false),
+            };
+            run.push(body.trim_start_matches('*').trim_start());
+            if closed {
+                in_block = false;
+                flush(&mut run, run_start, &mut out);
+            }
+            continue;
+        }
+        if let Some(rest) = trimmed.strip_prefix(line_marker) {
+            if run.is_empty() {
+                run_start = i;
+            }
+            let rest = rest.trim_start_matches([marker_char, '!']);
+            run.push(rest.strip_prefix(' ').unwrap_or(rest));
+            continue;
+        }
+        flush(&mut run, run_start, &mut out);
+        if let Some((open, close)) = block {
+            if let Some(rest) = trimmed.strip_prefix(open) {
+                run_start = i;
+                let rest = rest.trim_start_matches('*');
+                if let Some((body, _)) = rest.split_once(close) {
+                    run.push(body.trim());
+                    flush(&mut run, run_start, &mut out);
+                } else {
+                    run.push(rest.trim());
+                    in_block = true;
+                }
+            }
+        }
+    }
+    flush(&mut run, run_start, &mut out);
+    out
+}
+
+/// Splits a section into overlapping chunks, returning each chunk's starting line.
+pub(crate) fn chunk_section(
+    section: &Section,
+    chunk_size: usize,
+    overlap: usize,
+) -> anyhow::Result<Vec<(usize, String)>> {
+    let config = ChunkConfig::new(chunk_size)
+        .with_overlap(overlap)
+        .context("invalid --chunk-size/--overlap")?;
+    let pieces: Vec<(usize, &str)> = if section.markdown {
+        MarkdownSplitter::new(config)
+            .chunk_indices(&section.text)
+            .collect()
+    } else {
+        TextSplitter::new(config)
+            .chunk_indices(&section.text)
+            .collect()
+    };
+    Ok(pieces
+        .into_iter()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(offset, text)| {
+            let before = section.text.get(..offset).unwrap_or_default();
+            let line = section.line + before.matches('\n').count();
+            (line, text.to_string())
+        })
+        .collect())
+}
+
+/// Splits an ingest source back into the relative path and content hash.
+fn parse_ingest_source(source: &str) -> Option<(&str, &str)> {
+    source.strip_prefix(INGEST_SOURCE_PREFIX)?.rsplit_once('@')
+}
+
+/// Chunks of an existing output layer, grouped by the file they were ingested from.
+#[derive(Default)]
+struct Previous {
+    /// Relative path -> (content hash, chunks).
+    files: HashMap<String, (String, Vec<agentsdb_format::ChunkInput>)>,
+    /// Chunks not produced by `ingest` (e.g. options records); always carried over.
+    kept: Vec<agentsdb_format::ChunkInput>,
+}
+
+fn read_previous(
+    out_path: &Path,
+    embedder: &dyn agentsdb_embeddings::embedder::Embedder,
+    dim: u32,
+) -> anyhow::Result<Previous> {
+    let file = agentsdb_format::LayerFile::open_lenient(out_path)
+        .with_context(|| format!("open existing layer {}", out_path.display()))?;
+    let schema = agentsdb_format::schema_of(&file);
+    if schema.dim != dim || schema.element_type != agentsdb_format::EmbeddingElementType::F32 {
+        anyhow::bail!(
+            "existing layer {} has dim={} ({:?}); ingest writes f32 dim={dim} (remove it or pass a matching --dim)",
+            out_path.display(),
+            schema.dim,
+            schema.element_type
+        );
+    }
+    if let Some(existing) = file.layer_metadata_bytes() {
+        validate_embedder_profile(existing, embedder, out_path)?;
+    }
+
+    let mut latest = BTreeMap::new();
+    for chunk in agentsdb_format::read_all_chunks(&file)? {
+        latest.insert(chunk.id, chunk);
+    }
+    let mut previous = Previous::default();
+    for chunk in latest.into_values() {
+        let origin = chunk.sources.iter().find_map(|s| match s {
+            agentsdb_format::ChunkSource::SourceString(v) => {
+                parse_ingest_source(v).map(|(rel, hash)| (rel.to_string(), hash.to_string()))
+            }
+            agentsdb_format::ChunkSource::ChunkId(_) => None,
+        });
+        match origin {
+            Some((rel, hash)) => {
+                let entry = previous
+                    .files
+                    .entry(rel)
+                    .or_insert_with(|| (hash.clone(), Vec::new()));
+                // A file whose chunks disagree on the hash is treated as changed.
+                if entry.0 != hash {
+                    entry.0.clear();
+                }
+                entry.1.push(chunk);
+            }
+            None => previous.kept.push(chunk),
+        }
+    }
+    Ok(previous)
+}
+
+/// Embedding dimension from rolled-up options, else 128 (as `compile` does).
+pub(crate) fn configured_dim(dir: &Path) -> anyhow::Result<u32> {
+    Ok(get_immutable_embedding_options(dir)
+        .context("get immutable embedding options")?
+        .dim
+        .map(|v| u32::try_from(v).context("configured dim overflows u32"))
+        .transpose()?
+        .unwrap_or(128))
+}
+
+/// Chunks for one changed file, with empty embeddings to be filled in by the caller.
+fn file_chunks(
+    rel: &str,
+    hash: &str,
+    content: &str,
+    config: &IngestConfig,
+    used_ids: &mut BTreeSet<u32>,
+) -> anyhow::Result<Vec<agentsdb_format::ChunkInput>> {
+    let now_ms = agentsdb_ops::util::now_unix_ms();
+    let mut out = Vec::new();
+    for section in extract_sections(rel, content) {
+        for (line, text) in chunk_section(&section, config.chunk_size, config.overlap)? {
+            let location = format!("{rel}:{line}");
+            let id = assign_stable_id(Path::new(&location), &text, used_ids);
+            out.push(agentsdb_format::ChunkInput {
+                id,
+                kind: config.kind.clone(),
+                content: text,
+                author: "human".to_string(),
+                confidence: 1.0,
+                created_at_unix_ms: now_ms,
+                embedding: Vec::new(),
+                sources: vec![
+                    agentsdb_format::ChunkSource::SourceString(location),
+                    agentsdb_format::ChunkSource::SourceString(format!(
+                        "{INGEST_SOURCE_PREFIX}{rel}@{hash}"
+                    )),
+                ],
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Settings shared by `ingest` and `watch`.
+pub(crate) struct IngestConfig {
+    pub(crate) root: String,
+    pub(crate) out: String,
+    /// Globs of files to ingest; empty means [`DEFAULT_INCLUDES`].
+    pub(crate) includes: Vec<String>,
+    pub(crate) excludes: Vec<String>,
+    pub(crate) chunk_size: usize,
+    pub(crate) overlap: usize,
+    pub(crate) kind: String,
+    pub(crate) dim: Option<u32>,
+    /// Keep the chunks and embeddings of files whose content hash is unchanged.
+    pub(crate) incremental: bool,
+    /// Record a tombstone for the chunks of files deleted since the previous run.
+    pub(crate) tombstone_deleted: bool,
+}
+
+impl IngestConfig {
+    pub(crate) fn includes(&self) -> Vec<String> {
+        if self.includes.is_empty() {
+            DEFAULT_INCLUDES.iter().map(|s| (*s).to_string()).collect()
+        } else {
+            self.includes.clone()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct IngestSummary {
+    pub(crate) ok: bool,
+    pub(crate) out: String,
+    pub(crate) files: usize,
+    pub(crate) chunks: usize,
+    pub(crate) embedded_chunks: usize,
+    pub(crate) reused_files: usize,
+    pub(crate) removed_files: usize,
+    /// False when nothing changed since the previous run and the layer was left alone.
+    pub(crate) written: bool,
+}
+
+pub(crate) fn cmd_ingest(config: &IngestConfig, json: bool) -> anyhow::Result<()> {
+    let summary = ingest_layer(config)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        println!(
+            "Ingested {} files into {} ({} chunks, {} embedded)",
+            summary.files, summary.out, summary.chunks, summary.embedded_chunks
+        );
+        if config.incremental {
+            println!(
+                "{} files unchanged, {} files removed",
+                summary.reused_files, summary.removed_files
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Ingests the files selected by `config` into `config.out`, rewriting it atomically.
+pub(crate) fn ingest_layer(config: &IngestConfig) -> anyhow::Result<IngestSummary> {
+    if config.chunk_size == 0 {
+        anyhow::bail!("--chunk-size must be non-zero");
+    }
+    if config.overlap >= config.chunk_size {
+        anyhow::bail!("--overlap must be smaller than --chunk-size");
+    }
+
+    let out_path = Path::new(&config.out);
+    let out_dir = out_path.parent().unwrap_or_else(|| Path::new("."));
+    let dim = config.dim.map_or_else(|| configured_dim(out_dir), Ok)?;
+    if dim == 0 {
+        anyhow::bail!("--dim must be non-zero");
+    }
+    let embedder = create_validated_embedder(out_dir, dim as usize)?;
+    let layer_metadata_json = keep_kinds_of(create_layer_metadata(embedder.as_ref())?, out_path)?;
+
+    let existed = out_path.exists();
+    let mut previous = existed
+        .then(|| read_previous(out_path, embedder.as_ref(), dim))
+        .transpose()?
+        .unwrap_or_default();
+
+    let includes = config.includes();
+    let root_path = Path::new(&config.root);
+    let files = collect_files_matching(root_path, &includes, &config.excludes)?;
+
+    let mut chunks = std::mem::take(&mut previous.kept);
+    let mut reused_files = 0usize;
+    let mut changed = Vec::new();
+    for rel in &files {
+        let (rel, hash, content) = read_file(root_path, rel)?;
+        match previous.files.remove(&rel) {
+            Some((old_hash, old_chunks)) if config.incremental && old_hash == hash => {
+                reused_files += 1;
+                chunks.extend(old_chunks);
+            }
+            _ => changed.push((rel, hash, content)),
+        }
+    }
+
+    // Files that are gone: tombstones recorded by an earlier run are carried over as they
+    // are; the chunks of files deleted since then are dropped.
+    let mut removed = Vec::new();
+    for (rel, (hash, old_chunks)) in std::mem::take(&mut previous.files) {
+        if hash == DELETED_HASH {
+            chunks.extend(old_chunks);
+        } else {
+            removed.push((rel, old_chunks));
+        }
+    }
+    if existed && changed.is_empty() && removed.is_empty() {
+        return Ok(IngestSummary {
+            ok: true,
+            out: config.out.clone(),
+            files: files.len(),
+            chunks: chunks.len(),
+            embedded_chunks: 0,
+            reused_files,
+            removed_files: 0,
+            written: false,
+        });
+    }
+
+    let mut used_ids = reserved_ids(&chunks);
+    let mut pending = Vec::new();
+    for (rel, hash, content) in &changed {
+        pending.extend(file_chunks(rel, hash, content, config, &mut used_ids)?);
+    }
+    if config.tombstone_deleted {
+        for (rel, old_chunks) in &removed {
+            pending.push(deleted_file_tombstone(rel, old_chunks, &mut used_ids));
+        }
+    }
+
+    let to_embed: Vec<String> = pending.iter().map(|c| c.content.clone()).collect();
+    let embeddings = embedder
+        .embed_for(EmbedPurpose::Document, &to_embed)
+        .context("embed chunks")?;
+    let embedded_chunks = pending.len();
+    chunks.extend(
+        pending
+            .into_iter()
+            .zip(embeddings)
+            .map(|(mut chunk, embedding)| {
+                chunk.embedding = embedding;
+                chunk
+            }),
+    );
+
+    if chunks.is_empty() {
+        anyhow::bail!(
+            "no chunks to ingest (no files under {} matched {})",
+            config.root,
+            includes.join(", ")
+        );
+    }
+    write_ingested(out_path, dim, &mut chunks, &layer_metadata_json)?;
+
+    Ok(IngestSummary {
+        ok: true,
+        out: config.out.clone(),
+        files: files.len(),
+        chunks: chunks.len(),
+        embedded_chunks,
+        reused_files,
+        removed_files: removed.len(),
+        written: true,
+    })
+}
+
+fn write_ingested(
+    out_path: &Path,
+    dim: u32,
+    chunks: &mut [agentsdb_format::ChunkInput],
+    layer_metadata_json: &[u8],
+) -> anyhow::Result<()> {
+    chunks.sort_by_key(|c| c.id);
+    let schema = agentsdb_format::LayerSchema {
+        dim,
+        element_type: agentsdb_format::EmbeddingElementType::F32,
+        quant_scale: 1.0,
+        layout: agentsdb_format::EmbeddingLayout::RowMajor,
+    };
+    agentsdb_format::write_layer_atomic(out_path, &schema, chunks, Some(layer_metadata_json))
+        .context("write layer")?;
+    Ok(())
+}
+
+/// Reads a file under `root`, returning its `/`-separated relative path, content hash,
+/// and (lossily decoded) content.
+fn read_file(root: &Path, rel: &Path) -> anyhow::Result<(String, String, String)> {
+    let rel = rel_path_string(rel);
+    let abs = root.join(&rel);
+    let bytes = std::fs::read(&abs).with_context(|| format!("read bytes {}", abs.display()))?;
+    let content = String::from_utf8_lossy(&bytes).to_string();
+    let hash = agentsdb_ops::util::content_sha256_hex(&content);
+    Ok((rel, hash, content))
+}
+
+/// Ids of the carried-over chunks plus the ids their tombstones retract, reserved first so
+/// new chunks never collide with them.
+fn reserved_ids(chunks: &[agentsdb_format::ChunkInput]) -> BTreeSet<u32> {
+    chunks
+        .iter()
+        .flat_map(|c| {
+            let retracted = c.sources.iter().filter_map(|s| match s {
+                agentsdb_format::ChunkSource::ChunkId(id) => Some(*id),
+                agentsdb_format::ChunkSource::SourceString(_) => None,
+            });
+            std::iter::once(c.id).chain(retracted)
+        })
+        .collect()
+}
+
+/// A tombstone retracting the chunks ingested from a file that has been deleted.
+///
+/// It carries `ingest:<rel>@deleted`, so it is dropped again if the file comes back.
+fn deleted_file_tombstone(
+    rel: &str,
+    old_chunks: &[agentsdb_format::ChunkInput],
+    used_ids: &mut BTreeSet<u32>,
+) -> agentsdb_format::ChunkInput {
+    let reason = format!("{rel} was deleted");
+    let mut sources: Vec<agentsdb_format::ChunkSource> = old_chunks
+        .iter()
+        .map(|c| agentsdb_format::ChunkSource::ChunkId(c.id))
+        .collect();
+    sources.push(agentsdb_format::ChunkSource::SourceString(format!(
+        "{INGEST_SOURCE_PREFIX}{rel}@{DELETED_HASH}"
+    )));
+    agentsdb_format::ChunkInput {
+        id: assign_stable_id(Path::new(&format!("{rel}:deleted")), &reason, used_ids),
+        kind: agentsdb_query::KIND_TOMBSTONE.to_string(),
+        content: reason,
+        author: "human".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: agentsdb_ops::util::now_unix_ms(),
+        embedding: Vec::new(),
+        sources,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_comment_blocks_from_code() {
+        let src = "use std::io;\n\n/// Opens the layer file and validates every section header.\n/// Fails on truncated files.\nfn open() {}\n\n// short\nlet x = 1;\n/*\n * Block comments are collected too, with their leading stars removed.\n */\n";
+        let sections = extract_sections("src/lib.rs", src);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].line, 3);
+        assert_eq!(
+            sections[0].text,
+            "Opens the layer file and validates every section header.\nFails on truncated files."
+        );
+        assert_eq!(sections[1].line, 9);
+        assert!(sections[1].text.starts_with("Block comments are collected"));
+
+        let md = extract_sections("docs/guide.md", "# Guide\n");
+        assert_eq!(md.len(), 1);
+        assert!(md[0].markdown);
+    }
+
+    #[test]
+    fn chunks_overlap_and_track_lines() -> anyhow::Result<()> {
+        let text = (1..=40)
+            .map(|i| format!("line number {i} of the document."))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let section = Section {
+            line: 1,
+            text,
+            markdown: false,
+        };
+        let chunks = chunk_section(&section, 200, 50)?;
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].0, 1);
+        assert!(chunks.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(chunks
+            .iter()
+            .all(|(line, text)| text.starts_with(&format!("line number {line} "))));
+        assert_eq!(
+            parse_ingest_source("ingest:docs/a@b.md@abc"),
+            Some(("docs/a@b.md", "abc"))
+        );
+        Ok(())
+    }
+}
@@ -1,13 +1,17 @@
 use anyhow::Context;
 use serde::Serialize;
 use std::collections::BTreeSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use agentsdb_embeddings::config::get_immutable_embedding_options;
+use agentsdb_embeddings::config::{
+    get_immutable_embedding_options, standard_layer_paths_for_dir, EmbeddingOptionsPatch,
+    OptionsRecord, KIND_OPTIONS,
+};
 
 use crate::commands::compile::compile_to_layer;
+use crate::commands::options::{default_dim_for_backend, default_model_for_backend, prompt_line};
 use crate::types::{CompileChunk, CompileInput, CompileSchema, CompileSource};
-use crate::util::{assign_stable_id, collect_files_wide_docs};
+use crate::util::{assign_stable_id, collect_files_matching, collect_files_wide_docs};
 
 const AGENT_NOTES_BLOCK: &str = r#"## Agent-Specific Notes
 
@@ -16,6 +20,157 @@ For context for any task, you MUST use MCP `agents_search` to look up context in
 Treat `AGENTS.db` layers as immutable; avoid in-place mutation utilities unless required by the design.
 "#;
 
+/// A project layout `init --template` knows: which files to compile and which kinds to seed.
+struct InitTemplate {
+    name: &'static str,
+    includes: &'static [&'static str],
+    excludes: &'static [&'static str],
+    /// Starter kinds, each seeded with a chunk describing what it is for.
+    kinds: &'static [(&'static str, &'static str)],
+}
+
+const CORE_KINDS: [(&str, &str); 4] = [
+    (
+        "canonical",
+        "Use kind `canonical` for the authoritative description of how the project works.",
+    ),
+    (
+        "decision",
+        "Use kind `decision` to record an architectural or process decision and why it was made.",
+    ),
+    (
+        "invariant",
+        "Use kind `invariant` for rules the code must never break, such as layers being append-only.",
+    ),
+    (
+        "note",
+        "Use kind `note` for working notes that may be promoted or retired later.",
+    ),
+];
+
+const TEMPLATES: &[InitTemplate] = &[
+    InitTemplate {
+        name: "rust-crate",
+        includes: &["*.md", "docs/**", "examples/**/*.rs"],
+        excludes: &["CHANGELOG.md"],
+        kinds: &[
+            CORE_KINDS[0],
+            CORE_KINDS[1],
+            CORE_KINDS[2],
+            CORE_KINDS[3],
+            (
+                "runbook",
+                "Use kind `runbook` for step-by-step procedures such as cutting a release.",
+            ),
+        ],
+    },
+    InitTemplate {
+        name: "monorepo",
+        includes: &["*.md", "**/docs/**"],
+        excludes: &["**/CHANGELOG.md", "**/fixtures/**"],
+        kinds: &[
+            CORE_KINDS[0],
+            CORE_KINDS[1],
+            CORE_KINDS[2],
+            CORE_KINDS[3],
+            (
+                "ownership",
+                "Use kind `ownership` to record which team or package owns a part of the repository.",
+            ),
+        ],
+    },
+    InitTemplate {
+        name: "docs-site",
+        includes: &["docs/**/*.md", "docs/**/*.mdx", "content/**/*.md", "README.md"],
+        excludes: &[],
+        kinds: &[
+            CORE_KINDS[0],
+            CORE_KINDS[3],
+            (
+                "glossary",
+                "Use kind `glossary` to define a term the documentation relies on.",
+            ),
+            (
+                "style",
+                "Use kind `style` for writing conventions the documentation follows.",
+            ),
+        ],
+    },
+];
+
+fn find_template(name: &str) -> anyhow::Result<&'static InitTemplate> {
+    TEMPLATES.iter().find(|t| t.name == name).with_context(|| {
+        format!("unknown template {name:?} (valid: rust-crate, monorepo, docs-site)")
+    })
+}
+
+/// What the wizard or the `--template`/`--backend` flags chose.
+struct InitSetup {
+    template: Option<&'static InitTemplate>,
+    backend: Option<String>,
+    model: Option<String>,
+    dim: Option<u32>,
+}
+
+fn run_wizard(dim: Option<u32>) -> anyhow::Result<InitSetup> {
+    println!("Project setup wizard (writes AGENTS.db and the empty writable layers).");
+    println!("Note: backends other than `hash` require rebuilding `agentsdb` with the matching Cargo feature.");
+
+    let template = prompt_line(
+        "Template (rust-crate|monorepo|docs-site|none)",
+        Some("none"),
+    )?;
+    let template = match template.as_str() {
+        "none" => None,
+        name => Some(find_template(name)?),
+    };
+
+    let backend = prompt_line(
+        "Backend (hash|ort|candle|openai|voyage|cohere|anthropic|bedrock|gemini)",
+        Some("hash"),
+    )?;
+    let model = match default_model_for_backend(&backend) {
+        Some(default) => {
+            let s = prompt_line("Model (optional)", Some(default))?;
+            (!s.trim().is_empty()).then_some(s)
+        }
+        None => None,
+    };
+    let default_dim = dim.unwrap_or(default_dim_for_backend(&backend));
+    let dim_s = prompt_line("Embedding dim", Some(&default_dim.to_string()))?;
+    let dim: u32 = dim_s.parse().context("parse dim")?;
+
+    Ok(InitSetup {
+        template,
+        backend: Some(backend),
+        model,
+        dim: Some(dim),
+    })
+}
+
+/// `agentsdb serve` registration commands for the supported MCP clients, with absolute paths.
+fn mcp_snippets(dir: &Path) -> Vec<(&'static str, String)> {
+    let dir = std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let paths = standard_layer_paths_for_dir(&dir);
+    let serve = format!(
+        "agentsdb serve --base \"{}\" --local \"{}\" --delta \"{}\"",
+        paths.base.display(),
+        paths.local.display(),
+        paths.delta.display()
+    );
+    vec![
+        ("codex", format!("codex mcp add agentsdb -- {serve}")),
+        (
+            "claude",
+            format!("claude mcp add --transport stdio --scope project agentsdb -- {serve}"),
+        ),
+        (
+            "gemini",
+            format!("gemini mcp add --transport stdio --scope project agentsdb {serve}"),
+        ),
+    ]
+}
+
 /// Ensures that AGENTS.local.db is in .gitignore
 fn ensure_gitignore_entry(root_path: &Path) -> anyhow::Result<()> {
     let gitignore_path = root_path.join(".gitignore");
@@ -88,11 +243,38 @@ pub(crate) fn cmd_init(
     dim: Option<u32>,
     element_type: &str,
     quant_scale: Option<f32>,
+    template: Option<&str>,
+    wizard: bool,
+    backend: Option<&str>,
+    model: Option<&str>,
     json: bool,
 ) -> anyhow::Result<()> {
-    let resolved_dim = match dim {
-        Some(v) => v,
-        None => {
+    if wizard && json {
+        anyhow::bail!("JSON output (--json, --output json) is not supported for init --wizard");
+    }
+    let setup = if wizard {
+        run_wizard(dim)?
+    } else {
+        InitSetup {
+            template: template.map(find_template).transpose()?,
+            backend: backend.map(str::to_string),
+            model: model.map(str::to_string),
+            dim,
+        }
+    };
+    let out_path = Path::new(out);
+    let out_dir = out_path.parent().unwrap_or_else(|| Path::new("."));
+    if setup.backend.is_some() && out_path.file_name().and_then(|s| s.to_str()) != Some("AGENTS.db")
+    {
+        anyhow::bail!(
+            "embedder options can only be recorded in an AGENTS.db base layer (got --out {out})"
+        );
+    }
+
+    let resolved_dim = match (setup.dim, setup.backend.as_deref()) {
+        (Some(v), _) => v,
+        (None, Some(backend)) => default_dim_for_backend(backend),
+        (None, None) => {
             let out_path = Path::new(out);
             let out_dir = out_path.parent().unwrap_or_else(|| Path::new("."));
             let options = get_immutable_embedding_options(out_dir)
@@ -119,7 +301,14 @@ pub(crate) fn cmd_init(
     // Ensure agent notes are present in relevant instruction files.
     ensure_agent_notes(root_path)?;
 
-    let files = collect_files_wide_docs(root_path)?;
+    let files = match setup.template {
+        Some(template) => {
+            let includes: Vec<String> = template.includes.iter().map(|s| s.to_string()).collect();
+            let excludes: Vec<String> = template.excludes.iter().map(|s| s.to_string()).collect();
+            collect_files_matching(root_path, &includes, &excludes)?
+        }
+        None => collect_files_wide_docs(root_path)?,
+    };
 
     let mut used_ids = BTreeSet::new();
     let mut chunks = Vec::with_capacity(files.len());
@@ -140,15 +329,62 @@ pub(crate) fn cmd_init(
         });
     }
 
+    if let Some(template) = setup.template {
+        for (starter_kind, content) in template.kinds {
+            let label = format!("init:{}/{starter_kind}", template.name);
+            let id = assign_stable_id(Path::new(&label), content, &mut used_ids);
+            chunks.push(CompileChunk {
+                id,
+                kind: starter_kind.to_string(),
+                content: content.to_string(),
+                author: "human".to_string(),
+                confidence: 1.0,
+                created_at_unix_ms: 0,
+                embedding: None,
+                sources: vec![CompileSource::String(label)],
+            });
+        }
+    }
+
+    let schema = CompileSchema {
+        dim: resolved_dim,
+        element_type: element_type.to_string(),
+        quant_scale: quant_scale.or_else(|| (element_type == "i8").then_some(1.0)),
+        layout: None,
+    };
+
+    // Record the chosen embedder first so compiling the docs below embeds with it.
+    let replace = match setup.backend.as_deref() {
+        Some(backend) => {
+            write_options_layer(
+                out_path,
+                &schema,
+                backend,
+                setup.model.as_deref(),
+                &mut used_ids,
+            )?;
+            false
+        }
+        None => true,
+    };
+
     let mut input = CompileInput {
         schema: CompileSchema {
-            dim: resolved_dim,
-            element_type: element_type.to_string(),
-            quant_scale: quant_scale.or_else(|| (element_type == "i8").then_some(1.0)),
+            dim: schema.dim,
+            element_type: schema.element_type.clone(),
+            quant_scale: schema.quant_scale,
+            layout: None,
         },
         chunks,
     };
-    let (_action, chunks) = compile_to_layer(&mut input, out, true).context("compile")?;
+    let (_action, chunks) = compile_to_layer(&mut input, out, replace, false).context("compile")?;
+
+    let created_layers = if setup.template.is_some() || setup.backend.is_some() {
+        create_writable_layers(out_dir, out_path)?
+    } else {
+        Vec::new()
+    };
+    let mcp = mcp_snippets(out_dir);
 
     if json {
         #[derive(Serialize)]
@@ -156,6 +392,13 @@ pub(crate) fn cmd_init(
             ok: bool,
             out: &'a str,
             chunks: usize,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            template: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            backend: Option<&'a str>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            created_layers: Vec<String>,
+            mcp: std::collections::BTreeMap<&'a str, String>,
         }
         println!(
             "{}",
@@ -163,14 +406,94 @@ pub(crate) fn cmd_init(
                 ok: true,
                 out,
                 chunks,
+                template: setup.template.map(|t| t.name),
+                backend: setup.backend.as_deref(),
+                created_layers: created_layers
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect(),
+                mcp: mcp.into_iter().collect(),
             })?
         );
     } else {
         println!("Wrote {out} ({chunks} chunks)");
+        for path in &created_layers {
+            println!("Created {}", path.display());
+        }
+        println!();
+        println!("Register the MCP server with your client:");
+        for (client, command) in &mcp {
+            println!("  {client}: {command}");
+        }
     }
     Ok(())
 }
 
+/// Starts the layer at `out` with an options chunk recording the chosen embedder.
+fn write_options_layer(
+    out: &Path,
+    schema: &CompileSchema,
+    backend: &str,
+    model: Option<&str>,
+    used_ids: &mut BTreeSet<u32>,
+) -> anyhow::Result<()> {
+    let record = OptionsRecord {
+        embedding: Some(EmbeddingOptionsPatch {
+            backend: Some(backend.to_string()),
+            model: model.map(str::to_string),
+            dim: Some(schema.dim as usize),
+            ..Default::default()
+        }),
+        search: None,
+        server: None,
+        checksum_allowlist: None,
+    };
+    let content = serde_json::to_string_pretty(&record).context("serialize options")?;
+    let id = assign_stable_id(Path::new("init:options"), &content, used_ids);
+    let layer_schema = agentsdb_format::LayerSchema {
+        dim: schema.dim,
+        element_type: match schema.element_type.as_str() {
+            "i8" => agentsdb_format::EmbeddingElementType::I8,
+            _ => agentsdb_format::EmbeddingElementType::F32,
+        },
+        quant_scale: schema.quant_scale.unwrap_or(1.0),
+        layout: agentsdb_format::EmbeddingLayout::RowMajor,
+    };
+    let mut chunks = [agentsdb_format::ChunkInput {
+        id,
+        kind: KIND_OPTIONS.to_string(),
+        content,
+        author: "human".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: 0,
+        embedding: vec![0.0; schema.dim as usize],
+        sources: Vec::new(),
+    }];
+    agentsdb_format::write_layer_atomic(out, &layer_schema, &mut chunks, None)
+        .with_context(|| format!("write {}", out.display()))?;
+    Ok(())
+}
+
+/// Creates the missing user, delta and local layers next to `base`, empty and with its schema.
+fn create_writable_layers(dir: &Path, base: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let file = agentsdb_format::LayerFile::open(base)
+        .with_context(|| format!("open layer {}", base.display()))?;
+    let schema = agentsdb_format::schema_of(&file);
+    let metadata = file.layer_metadata_bytes().map(|b| b.to_vec());
+
+    let paths = standard_layer_paths_for_dir(dir);
+    let mut created = Vec::new();
+    for path in [paths.user, paths.delta, paths.local] {
+        if path.exists() {
+            continue;
+        }
+        agentsdb_format::write_layer_atomic(&path, &schema, &mut [], metadata.as_deref())
+            .with_context(|| format!("write {}", path.display()))?;
+        created.push(path);
+    }
+    Ok(created)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,7 +511,20 @@ mod tests {
         let out_path = root.join("AGENTS.test.db");
         let root_s = root.to_string_lossy().to_string();
         let out_s = out_path.to_string_lossy().to_string();
-        cmd_init(&root_s, &out_s, "docs", Some(8), "f32", None, true).expect("init should succeed");
+        cmd_init(
+            &root_s,
+            &out_s,
+            "docs",
+            Some(8),
+            "f32",
+            None,
+            None,
+            false,
+            None,
+            None,
+            true,
+        )
+        .expect("init should succeed");
 
         let after = std::fs::read_to_string(&readme_path).expect("read README");
         assert_eq!(after, original);
@@ -212,7 +548,20 @@ mod tests {
         let out_path = root.join("AGENTS.test.db");
         let root_s = root.to_string_lossy().to_string();
         let out_s = out_path.to_string_lossy().to_string();
-        cmd_init(&root_s, &out_s, "docs", Some(8), "f32", None, true).expect("init should succeed");
+        cmd_init(
+            &root_s,
+            &out_s,
+            "docs",
+            Some(8),
+            "f32",
+            None,
+            None,
+            false,
+            None,
+            None,
+            true,
+        )
+        .expect("init should succeed");
 
         let agents_after = std::fs::read_to_string(&agents_path).expect("read AGENTS");
         assert!(agents_after.contains("## Agent-Specific Notes"));
@@ -255,13 +604,17 @@ mod tests {
             None,
             None,
             None,
+            &[],
             true,
         )
         .expect("write options");
 
         let out_path = root.join("AGENTS.test.db");
         let out_s = out_path.to_string_lossy().to_string();
-        cmd_init(&root_s, &out_s, "docs", None, "f32", None, true).expect("init should succeed");
+        cmd_init(
+            &root_s, &out_s, "docs", None, "f32", None, None, false, None, None, true,
+        )
+        .expect("init should succeed");
 
         let file = agentsdb_format::LayerFile::open(&out_path).expect("open out layer");
         let schema = agentsdb_format::schema_of(&file);
@@ -269,4 +622,51 @@ mod tests {
 
         std::fs::remove_dir_all(&root).expect("cleanup");
     }
+
+    #[test]
+    fn init_template_records_options_and_seeds_kinds() {
+        let root = crate::util::make_temp_dir();
+        std::fs::write(root.join("README.md"), "# Title\n").expect("write README");
+        std::fs::create_dir_all(root.join("src")).expect("create src");
+        std::fs::write(root.join("src/lib.rs"), "//! Crate docs\n").expect("write lib.rs");
+
+        let out_path = root.join("AGENTS.db");
+        let root_s = root.to_string_lossy().to_string();
+        let out_s = out_path.to_string_lossy().to_string();
+        cmd_init(
+            &root_s,
+            &out_s,
+            "canonical",
+            Some(8),
+            "f32",
+            None,
+            Some("rust-crate"),
+            false,
+            Some("hash"),
+            None,
+            true,
+        )
+        .expect("init should succeed");
+
+        let file = agentsdb_format::LayerFile::open(&out_path).expect("open out layer");
+        let chunks = agentsdb_format::read_all_chunks(&file).expect("read chunks");
+        let options = chunks
+            .iter()
+            .find(|c| c.kind == KIND_OPTIONS)
+            .expect("options chunk");
+        let record: OptionsRecord = serde_json::from_str(&options.content).expect("parse options");
+        let embedding = record.embedding.expect("embedding options");
+        assert_eq!(embedding.backend.as_deref(), Some("hash"));
+        assert_eq!(embedding.dim, Some(8));
+        assert!(chunks.iter().any(|c| c.kind == "runbook"));
+        assert!(chunks.iter().any(|c| c.content == "# Title\n"));
+        assert!(!chunks.iter().any(|c| c.content.contains("Crate docs")));
+
+        for name in ["AGENTS.user.db", "AGENTS.delta.db", "AGENTS.local.db"] {
+            let file = agentsdb_format::LayerFile::open(root.join(name)).expect("open layer");
+            assert_eq!(agentsdb_format::schema_of(&file).dim, 8);
+        }
+
+        std::fs::remove_dir_all(&root).expect("cleanup");
+    }
 }
@@ -1,7 +1,7 @@
 use anyhow::Context;
 use serde::Serialize;
 
-use crate::types::{EmbeddingJson, HeaderJson, InspectJson, SectionJson};
+use crate::types::{EmbeddingJson, HeaderJson, InspectJson, SectionJson, StatsJson};
 
 pub(crate) fn cmd_inspect(
     layer: Option<&str>,
@@ -75,6 +75,9 @@ pub(crate) fn cmd_inspect(
         return Ok(());
     }
 
+    let stored = file.stored_stats()?.is_some();
+    let stats = agentsdb_format::ChunkStats::of(&file)?;
+
     if json {
         let header = HeaderJson {
             magic: file.header.magic,
@@ -120,6 +123,7 @@ pub(crate) fn cmd_inspect(
             data_offset: file.embedding_matrix.data_offset,
             data_length: file.embedding_matrix.data_length,
             quant_scale: file.embedding_matrix.quant_scale,
+            layout: file.embedding_matrix.layout.name(),
         };
 
         let out = InspectJson {
@@ -130,6 +134,17 @@ pub(crate) fn cmd_inspect(
             chunk_count: file.chunk_count,
             embedding,
             relationships: file.relationship_count,
+            stats: StatsJson {
+                stored,
+                confidence_avg: stats.confidence_mean(),
+                by_kind: stats.by_kind,
+                by_author: stats.by_author,
+                confidence_min: stats.confidence_min,
+                confidence_max: stats.confidence_max,
+                confidence_histogram: stats.confidence_histogram.to_vec(),
+                created_at_min_unix_ms: stats.created_at_min,
+                created_at_max_unix_ms: stats.created_at_max,
+            },
         };
         println!("{}", serde_json::to_string_pretty(&out)?);
     } else {
@@ -184,10 +199,11 @@ pub(crate) fn cmd_inspect(
             print!(" backend={}", backend);
         }
         println!(
-            " data_offset={} data_length={} quant_scale={}",
+            " data_offset={} data_length={} quant_scale={} layout={}",
             file.embedding_matrix.data_offset,
             file.embedding_matrix.data_length,
-            file.embedding_matrix.quant_scale
+            file.embedding_matrix.quant_scale,
+            file.embedding_matrix.layout.name()
         );
         println!(
             "Relationships: {}",
@@ -195,6 +211,28 @@ pub(crate) fn cmd_inspect(
                 .map(|v| v.to_string())
                 .unwrap_or_else(|| "absent".to_string())
         );
+        let counts = |counts: &std::collections::BTreeMap<String, u64>| {
+            counts
+                .iter()
+                .map(|(name, n)| format!("{name}={n}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        println!(
+            "Stats ({}): kinds: {} authors: {}",
+            if stored { "stored" } else { "recomputed" },
+            counts(&stats.by_kind),
+            counts(&stats.by_author)
+        );
+        println!(
+            "  confidence min={:.3} avg={:.3} max={:.3} histogram={:?} created_at={}..{}",
+            stats.confidence_min,
+            stats.confidence_mean(),
+            stats.confidence_max,
+            stats.confidence_histogram,
+            stats.created_at_min,
+            stats.created_at_max
+        );
     }
 
     Ok(())
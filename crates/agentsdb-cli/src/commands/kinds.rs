@@ -0,0 +1,115 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::path::Path;
+
+use agentsdb_embeddings::layer_metadata::{KindDef, KindValidation};
+
+pub(crate) fn cmd_kinds_list(dir: &str, json: bool) -> anyhow::Result<()> {
+    let dir = Path::new(dir);
+    let layers = agentsdb_ops::kinds::list_taxonomies(dir)?;
+    let combined = agentsdb_ops::kinds::load_taxonomy_for_dir(dir)?;
+
+    if json {
+        #[derive(Serialize)]
+        struct LayerOut<'a> {
+            path: String,
+            validation: KindValidation,
+            kinds: &'a [KindDef],
+        }
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            validation: KindValidation,
+            kinds: &'a [KindDef],
+            layers: Vec<LayerOut<'a>>,
+        }
+        let out = Out {
+            ok: true,
+            validation: combined.validation,
+            kinds: &combined.kinds,
+            layers: layers
+                .iter()
+                .map(|(path, taxonomy)| LayerOut {
+                    path: path.display().to_string(),
+                    validation: taxonomy.validation,
+                    kinds: &taxonomy.kinds,
+                })
+                .collect(),
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+
+    if combined.kinds.is_empty() {
+        println!(
+            "No kind taxonomy in {}; any kind is accepted.",
+            dir.display()
+        );
+        return Ok(());
+    }
+    println!("Validation: {}", combined.validation);
+    for (path, taxonomy) in &layers {
+        println!("{} ({})", path.display(), taxonomy.validation);
+        for kind in &taxonomy.kinds {
+            if kind.description.is_empty() {
+                println!("  {}", kind.name);
+            } else {
+                println!("  {}\t{}", kind.name, kind.description);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_kinds_add(
+    dir: &str,
+    name: &str,
+    description: &str,
+    scope: &str,
+    validation: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    // Like options records, the taxonomy is project configuration, so every standard layer
+    // (including AGENTS.db) may hold one.
+    let policy = agentsdb_format::WritePolicy::standard()
+        .allow_user()
+        .allow_base();
+    let file_name = policy
+        .file_name(scope)
+        .with_context(|| format!("unknown scope {scope:?}"))?;
+    let path = Path::new(dir).join(file_name);
+    let validation = validation
+        .map(|v| match v {
+            "off" => Ok(KindValidation::Off),
+            "warn" => Ok(KindValidation::Warn),
+            "enforce" => Ok(KindValidation::Enforce),
+            other => anyhow::bail!("--validation must be off, warn or enforce (got {other:?})"),
+        })
+        .transpose()?;
+    let taxonomy = agentsdb_ops::kinds::add_kind(&path, name, description, validation, &policy)?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            path: String,
+            validation: KindValidation,
+            kinds: &'a [KindDef],
+        }
+        let out = Out {
+            ok: true,
+            path: path.display().to_string(),
+            validation: taxonomy.validation,
+            kinds: &taxonomy.kinds,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        println!(
+            "Added kind {name:?} to {} ({} kinds, validation: {})",
+            path.display(),
+            taxonomy.kinds.len(),
+            taxonomy.validation
+        );
+    }
+    Ok(())
+}
@@ -121,6 +121,7 @@ mod tests {
             dim: 4,
             element_type: EmbeddingElementType::F32,
             quant_scale: 0.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
         };
         let mut chunks: Vec<ChunkInput> = (0..chunk_count)
             .map(|i| ChunkInput {
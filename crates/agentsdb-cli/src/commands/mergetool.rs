@@ -0,0 +1,157 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+use agentsdb_ops::merge::{MergeReport, Prefer};
+
+/// Name of the merge driver in git config (`merge.<name>.driver`).
+const DRIVER_NAME: &str = "agentsdb";
+/// The `.gitattributes` line that routes layer files through the driver.
+const ATTRIBUTES_LINE: &str = "AGENTS*.db merge=agentsdb";
+
+pub(crate) fn cmd_mergetool(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    out: Option<&str>,
+    prefer: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let prefer = match prefer {
+        None => None,
+        Some("ours") => Some(Prefer::Ours),
+        Some("theirs") => Some(Prefer::Theirs),
+        Some(other) => anyhow::bail!("--prefer must be 'ours' or 'theirs' (got {other:?})"),
+    };
+    let out = out.unwrap_or(ours);
+    let report = agentsdb_ops::merge::merge_layers(
+        Path::new(base),
+        Path::new(ours),
+        Path::new(theirs),
+        Path::new(out),
+        prefer,
+    )?;
+    let unresolved = report.conflicts.iter().filter(|c| !c.resolved).count();
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            out: &'a str,
+            #[serde(flatten)]
+            report: &'a MergeReport,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: unresolved == 0,
+                out,
+                report: &report,
+            })?
+        );
+    } else {
+        println!(
+            "Merged into {out}: {} chunks ({} from theirs, {} renumbered, {} deduplicated)",
+            report.chunks,
+            report.taken_from_theirs,
+            report.renumbered.len(),
+            report.deduplicated
+        );
+        for (old, new) in &report.renumbered {
+            println!("  theirs {old} -> {new}");
+        }
+        for conflict in &report.conflicts {
+            let kept = conflict
+                .kept
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let state = if conflict.resolved {
+                "resolved"
+            } else {
+                "CONFLICT"
+            };
+            println!(
+                "  {state} {} on chunk {} (kept: {})",
+                conflict.kind,
+                conflict.id,
+                if kept.is_empty() { "none" } else { &kept }
+            );
+        }
+    }
+
+    if unresolved > 0 {
+        anyhow::bail!(
+            "{unresolved} conflicting chunks were kept in every version; review {out}, retract the unwanted ones, then mark it resolved"
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_mergetool_install(json: bool) -> anyhow::Result<()> {
+    // Implements `mergetool --install`: registers the driver in the current repository's
+    // git config and routes layer files to it via `.gitattributes`.
+    let driver = "agentsdb mergetool --base %O --ours %A --theirs %B";
+    git(&[
+        "config",
+        &format!("merge.{DRIVER_NAME}.name"),
+        "AGENTS.db chunk-level merge",
+    ])?;
+    git(&["config", &format!("merge.{DRIVER_NAME}.driver"), driver])?;
+
+    let top = git(&["rev-parse", "--show-toplevel"])?;
+    let attributes = Path::new(top.trim()).join(".gitattributes");
+    let existing = match std::fs::read_to_string(&attributes) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("read {}", attributes.display())),
+    };
+    let added = !existing.lines().any(|l| l.trim() == ATTRIBUTES_LINE);
+    if added {
+        let mut updated = existing;
+        if !updated.is_empty() && !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated.push_str(ATTRIBUTES_LINE);
+        updated.push('\n');
+        std::fs::write(&attributes, updated)
+            .with_context(|| format!("write {}", attributes.display()))?;
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "ok": true,
+                "driver": driver,
+                "gitattributes": attributes.display().to_string(),
+                "gitattributes_updated": added,
+            }))?
+        );
+    } else {
+        println!("Registered git merge driver `{DRIVER_NAME}`: {driver}");
+        if added {
+            println!("Added `{ATTRIBUTES_LINE}` to {}", attributes.display());
+        } else {
+            println!(
+                "{} already routes layers to the driver",
+                attributes.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn git(args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git").args(args).output().context("run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
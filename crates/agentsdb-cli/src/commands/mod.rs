@@ -1,21 +1,40 @@
 //! This module contains the implementation of the various subcommands for the `agentsdb-cli` tool.
 
 pub(crate) mod destroy;
+pub(crate) mod check;
 pub(crate) mod compact;
 pub(crate) mod compile;
+pub(crate) mod completions;
+pub(crate) mod dedupe;
 pub(crate) mod diff;
 pub(crate) mod export;
 pub(crate) mod import;
+pub(crate) mod import_markdown;
 pub(crate) mod index;
+pub(crate) mod ingest;
 pub(crate) mod init;
 pub(crate) mod inspect;
+pub(crate) mod kinds;
 pub(crate) mod list;
+pub(crate) mod mergetool;
 pub(crate) mod options;
 pub(crate) mod promote;
 pub(crate) mod proposals;
 pub(crate) mod reembed;
+pub(crate) mod repl;
+pub(crate) mod review;
 pub(crate) mod search;
+pub(crate) mod show;
+pub(crate) mod sign;
 pub(crate) mod smash;
+pub(crate) mod stats;
+pub(crate) mod sync;
+pub(crate) mod top;
+pub(crate) mod undo;
 pub(crate) mod validate;
+pub(crate) mod verify;
+pub(crate) mod verify_provenance;
+pub(crate) mod watch;
 pub(crate) mod web;
+pub(crate) mod workspace;
 pub(crate) mod write;
@@ -1,11 +1,15 @@
 use anyhow::Context;
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use agentsdb_embeddings::config::{
     roll_up_embedding_options_from_paths, standard_layer_paths_for_dir, AllowlistOp,
     ChecksumAllowlistRecord, EmbeddingOptionsPatch, ModelChecksumPin, OptionsRecord,
-    ResolvedEmbeddingOptions, DEFAULT_LOCAL_REVISION, KIND_OPTIONS,
+    ResolvedEmbeddingOptions, StandardLayerPaths, DEFAULT_LOCAL_REVISION, KIND_OPTIONS,
+};
+use agentsdb_embeddings::settings::{
+    parse_assignment, resolve_settings, validate_document, DocumentKind, EffectiveSettings,
 };
 
 fn now_unix_ms() -> u64 {
@@ -74,6 +78,10 @@ struct PatchJson {
     api_key_env: Option<String>,
     cache_enabled: Option<bool>,
     cache_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    languages: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language_models: Option<BTreeMap<String, String>>,
 }
 
 impl From<EmbeddingOptionsPatch> for PatchJson {
@@ -89,6 +97,8 @@ impl From<EmbeddingOptionsPatch> for PatchJson {
             api_key_env: v.api_key_env,
             cache_enabled: v.cache_enabled,
             cache_dir: v.cache_dir,
+            languages: v.languages,
+            language_models: v.language_models,
         }
     }
 }
@@ -106,11 +116,16 @@ struct ResolvedJson {
     api_key_env: Option<String>,
     cache_enabled: bool,
     cache_dir: Option<String>,
+    languages: Vec<String>,
+    language_models: BTreeMap<String, String>,
+    /// Model `language_models` selects for `languages`, used instead of `model`.
+    language_model: Option<String>,
     checksum_allowlist: Vec<ModelChecksumPin>,
 }
 
 impl From<ResolvedEmbeddingOptions> for ResolvedJson {
     fn from(v: ResolvedEmbeddingOptions) -> Self {
+        let language_model = v.language_model().map(str::to_string);
         let checksum_allowlist = v
             .checksum_allowlist
             .into_iter()
@@ -131,6 +146,9 @@ impl From<ResolvedEmbeddingOptions> for ResolvedJson {
             api_key_env: v.api_key_env,
             cache_enabled: v.cache_enabled,
             cache_dir: v.cache_dir,
+            languages: v.languages,
+            language_models: v.language_models,
+            language_model,
             checksum_allowlist,
         }
     }
@@ -218,6 +236,13 @@ pub(crate) fn cmd_options_show(
         resolved.cache_enabled,
         resolved.cache_dir
     );
+    if !resolved.languages.is_empty() {
+        println!(
+            "  languages={} language_model={:?}",
+            resolved.languages.join(","),
+            resolved.language_model()
+        );
+    }
     if !resolved.checksum_allowlist.is_empty() {
         println!(
             "  checksum_allowlist={} entries",
@@ -256,6 +281,86 @@ pub(crate) fn cmd_options_show(
     Ok(())
 }
 
+pub(crate) fn cmd_options_explain(
+    dir: &str,
+    base: Option<&str>,
+    user: Option<&str>,
+    delta: Option<&str>,
+    local: Option<&str>,
+    config_file: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let paths = resolve_paths(Path::new(dir), base, user, delta, local);
+    let settings = resolve_settings(&standard_paths(&paths), config_file.map(Path::new))
+        .context("resolve settings")?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            #[serde(flatten)]
+            settings: &'a EffectiveSettings,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: true,
+                settings: &settings
+            })?
+        );
+        return Ok(());
+    }
+
+    println!("Effective settings (default < options chunk < agentsdb.toml < env):");
+    let rows: Vec<(&str, String, String)> = settings
+        .settings
+        .iter()
+        .map(|s| {
+            let value = s
+                .value
+                .as_ref()
+                .map_or_else(|| "(unset)".to_string(), ToString::to_string);
+            (s.key, value, s.source.to_string())
+        })
+        .collect();
+    let key_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+    let value_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0);
+    for (key, value, source) in rows {
+        println!("  {key:<key_width$}  {value:<value_width$}  {source}");
+    }
+    if let Some(file) = &settings.config_file {
+        println!();
+        println!("Config file: {file}");
+    }
+    Ok(())
+}
+
+/// Settings for `agentsdb serve` (`server.*` and `trust.*`), resolved against the layers it was
+/// given.
+pub(crate) fn server_settings(
+    layers: &crate::cli::LayerArgs,
+    config_file: Option<&str>,
+) -> anyhow::Result<EffectiveSettings> {
+    let paths = resolve_paths(
+        Path::new("."),
+        layers.base.as_deref(),
+        layers.user.as_deref(),
+        layers.delta.as_deref(),
+        layers.local.as_deref(),
+    );
+    resolve_settings(&standard_paths(&paths), config_file.map(Path::new))
+        .context("resolve server settings")
+}
+
+fn standard_paths(paths: &ResolvedPaths) -> StandardLayerPaths {
+    StandardLayerPaths {
+        base: paths.base.clone(),
+        user: paths.user.clone(),
+        delta: paths.delta.clone(),
+        local: paths.local.clone(),
+    }
+}
+
 pub(crate) fn cmd_options_allowlist_list(
     dir: &str,
     base: Option<&str>,
@@ -340,15 +445,14 @@ fn write_allowlist_record(
         other => anyhow::bail!("--scope must be 'base' (got {other:?})"),
     };
 
+    let mut policy = agentsdb_format::WritePolicy::standard();
+    if allow_user || allow_base {
+        policy = policy.allow_user();
+    }
     if allow_base {
-        agentsdb_format::ensure_writable_layer_path_allow_base(&target_path)
-            .context("permission check")?;
-    } else if allow_user {
-        agentsdb_format::ensure_writable_layer_path_allow_user(&target_path)
-            .context("permission check")?;
-    } else {
-        agentsdb_format::ensure_writable_layer_path(&target_path).context("permission check")?;
+        policy = policy.allow_base();
     }
+    policy.check(&target_path).context("permission check")?;
 
     let schema = if target_path.exists() {
         let file = agentsdb_format::LayerFile::open(&target_path)
@@ -363,11 +467,14 @@ fn write_allowlist_record(
             dim: 128,
             element_type: agentsdb_format::EmbeddingElementType::F32,
             quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
         }
     };
 
     let record = OptionsRecord {
         embedding: None,
+        search: None,
+        server: None,
         checksum_allowlist: Some(record),
     };
     let content = serde_json::to_string_pretty(&record).context("serialize allowlist record")?;
@@ -480,6 +587,41 @@ pub(crate) fn cmd_options_allowlist_clear(
     write_allowlist_record(dir, scope, record, json)
 }
 
+/// Builds an options record from the embedding flags and `--set KEY=VALUE` assignments,
+/// validated against the settings schema.
+fn build_options_record(
+    embedding: Option<EmbeddingOptionsPatch>,
+    settings: &[String],
+) -> anyhow::Result<OptionsRecord> {
+    let mut doc = serde_json::Map::new();
+    if let Some(patch) = embedding {
+        doc.insert(
+            "embedding".to_string(),
+            serde_json::to_value(patch).context("serialize embedding options")?,
+        );
+    }
+    for assignment in settings {
+        let (spec, value) = parse_assignment(assignment).context("--set")?;
+        // `options set` writes AGENTS.db, which everyone shares.
+        if spec.section() == "identity" {
+            anyhow::bail!(
+                "--set {}: identity is personal; set it in AGENTS.local.db options, agentsdb.toml or {}",
+                spec.key,
+                spec.env
+            );
+        }
+        let section = doc
+            .entry(spec.section().to_string())
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+        if let serde_json::Value::Object(fields) = section {
+            fields.insert(spec.field().to_string(), value);
+        }
+    }
+    let doc = serde_json::Value::Object(doc);
+    validate_document(&doc, DocumentKind::OptionsChunk).context("validate options")?;
+    serde_json::from_value(doc).context("decode options record")
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn cmd_options_set(
     dir: &str,
@@ -494,25 +636,45 @@ pub(crate) fn cmd_options_set(
     api_key_env: Option<&str>,
     cache_enabled: Option<bool>,
     cache_dir: Option<&str>,
+    settings: &[String],
     json: bool,
 ) -> anyhow::Result<()> {
     let dir = Path::new(dir);
     let paths = resolve_paths(dir, None, None, None, None);
 
-    if backend.is_none()
-        && model.is_none()
-        && revision.is_none()
-        && model_path.is_none()
-        && model_sha256.is_none()
-        && dim.is_none()
-        && api_base.is_none()
-        && api_key_env.is_none()
-        && cache_enabled.is_none()
-        && cache_dir.is_none()
-    {
-        anyhow::bail!("no fields provided (use one or more of --backend/--model/--revision/--model-path/--model-sha256/--dim/--api-base/--api-key-env/--cache/--cache-dir)");
+    let has_embedding_flags = backend.is_some()
+        || model.is_some()
+        || revision.is_some()
+        || model_path.is_some()
+        || model_sha256.is_some()
+        || dim.is_some()
+        || api_base.is_some()
+        || api_key_env.is_some()
+        || cache_enabled.is_some()
+        || cache_dir.is_some();
+    if !has_embedding_flags && settings.is_empty() {
+        anyhow::bail!("no fields provided (use one or more of --backend/--model/--revision/--model-path/--model-sha256/--dim/--api-base/--api-key-env/--cache/--cache-dir/--set KEY=VALUE)");
     }
 
+    let patch = EmbeddingOptionsPatch {
+        backend: backend.map(str::to_string),
+        model: model.map(str::to_string),
+        revision: revision.map(str::to_string),
+        model_path: model_path.map(str::to_string),
+        model_sha256: model_sha256.map(str::to_string),
+        dim: dim.map(|d| d as usize),
+        api_base: api_base.map(str::to_string),
+        api_key_env: api_key_env.map(str::to_string),
+        cache_enabled,
+        cache_dir: cache_dir.map(str::to_string),
+        ..Default::default()
+    };
+    let record = build_options_record(has_embedding_flags.then_some(patch), settings)?;
+    let dim = match record.embedding.as_ref().and_then(|e| e.dim) {
+        Some(d) => Some(u32::try_from(d).context("embedding dim out of range")?),
+        None => None,
+    };
+
     // Only AGENTS.db (base layer) should store options documents.
     // This ensures all operations use the same immutable embedding configuration.
     if scope != "base" {
@@ -528,15 +690,14 @@ pub(crate) fn cmd_options_set(
         other => anyhow::bail!("--scope must be 'base' (got {other:?})"),
     };
 
+    let mut policy = agentsdb_format::WritePolicy::standard();
+    if allow_user || allow_base {
+        policy = policy.allow_user();
+    }
     if allow_base {
-        agentsdb_format::ensure_writable_layer_path_allow_base(&target_path)
-            .context("permission check")?;
-    } else if allow_user {
-        agentsdb_format::ensure_writable_layer_path_allow_user(&target_path)
-            .context("permission check")?;
-    } else {
-        agentsdb_format::ensure_writable_layer_path(&target_path).context("permission check")?;
+        policy = policy.allow_base();
     }
+    policy.check(&target_path).context("permission check")?;
 
     let schema = if target_path.exists() {
         let file = agentsdb_format::LayerFile::open(&target_path)
@@ -551,6 +712,7 @@ pub(crate) fn cmd_options_set(
             dim: dim.unwrap_or(128),
             element_type: agentsdb_format::EmbeddingElementType::F32,
             quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
         }
     };
 
@@ -563,22 +725,6 @@ pub(crate) fn cmd_options_set(
         }
     }
 
-    let patch = EmbeddingOptionsPatch {
-        backend: backend.map(str::to_string),
-        model: model.map(str::to_string),
-        revision: revision.map(str::to_string),
-        model_path: model_path.map(str::to_string),
-        model_sha256: model_sha256.map(str::to_string),
-        dim: dim.map(|d| d as usize),
-        api_base: api_base.map(str::to_string),
-        api_key_env: api_key_env.map(str::to_string),
-        cache_enabled,
-        cache_dir: cache_dir.map(str::to_string),
-    };
-    let record = OptionsRecord {
-        embedding: Some(patch),
-        checksum_allowlist: None,
-    };
     let content = serde_json::to_string_pretty(&record).context("serialize options")?;
 
     let chunk_id = if target_path.exists() { 0 } else { 1 };
@@ -636,7 +782,7 @@ pub(crate) fn cmd_options_set(
     Ok(())
 }
 
-fn prompt_line(label: &str, default: Option<&str>) -> anyhow::Result<String> {
+pub(crate) fn prompt_line(label: &str, default: Option<&str>) -> anyhow::Result<String> {
     use std::io::Write;
     let mut stdout = std::io::stdout();
     match default {
@@ -654,9 +800,31 @@ fn prompt_line(label: &str, default: Option<&str>) -> anyhow::Result<String> {
     }
 }
 
+/// Embedding dim suggested for `backend` when no layer fixes it yet.
+pub(crate) fn default_dim_for_backend(backend: &str) -> u32 {
+    match backend {
+        "hash" => 128,
+        _ => 384,
+    }
+}
+
+/// Model suggested for `backend`, if the backend takes one.
+pub(crate) fn default_model_for_backend(backend: &str) -> Option<&'static str> {
+    match backend {
+        "ort" | "candle" => Some(agentsdb_embeddings::config::DEFAULT_LOCAL_MODEL),
+        "openai" => Some("text-embedding-3-small"),
+        "voyage" => Some("voyage-3"),
+        "cohere" => Some("embed-english-v3.0"),
+        "anthropic" => Some("voyage-3"),
+        "bedrock" => Some("amazon.titan-embed-text-v1"),
+        "gemini" => Some("text-embedding-004"),
+        _ => None,
+    }
+}
+
 pub(crate) fn cmd_options_wizard(dir: &str, json: bool) -> anyhow::Result<()> {
     if json {
-        anyhow::bail!("--json is not supported for options wizard");
+        anyhow::bail!("JSON output (--json, --output json) is not supported for options wizard");
     }
     let dir = Path::new(dir);
 
@@ -674,23 +842,11 @@ pub(crate) fn cmd_options_wizard(dir: &str, json: bool) -> anyhow::Result<()> {
         Some("candle"),
     )?;
 
-    let schema_dim = existing_schema_dim.unwrap_or(match backend.as_str() {
-        "hash" => 128,
-        _ => 384,
-    });
+    let schema_dim = existing_schema_dim.unwrap_or(default_dim_for_backend(&backend));
     let dim_s = prompt_line("Embedding dim", Some(&schema_dim.to_string()))?;
     let dim: u32 = dim_s.parse().context("parse dim")?;
 
-    let model_default = match backend.as_str() {
-        "ort" | "candle" => Some(agentsdb_embeddings::config::DEFAULT_LOCAL_MODEL),
-        "openai" => Some("text-embedding-3-small"),
-        "voyage" => Some("voyage-3"),
-        "cohere" => Some("embed-english-v3.0"),
-        "anthropic" => Some("voyage-3"),
-        "bedrock" => Some("amazon.titan-embed-text-v1"),
-        "gemini" => Some("text-embedding-004"),
-        _ => None,
-    };
+    let model_default = default_model_for_backend(&backend);
     let model = if model_default.is_some() {
         let s = prompt_line("Model (optional)", model_default)?;
         (!s.trim().is_empty()).then_some(s)
@@ -774,6 +930,7 @@ pub(crate) fn cmd_options_wizard(dir: &str, json: bool) -> anyhow::Result<()> {
         api_key_env.as_deref(),
         Some(cache_enabled),
         cache_dir.as_deref(),
+        &[],
         false,
     )
 }
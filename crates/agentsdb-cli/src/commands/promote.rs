@@ -1,20 +1,99 @@
 use serde::Serialize;
 use std::io::IsTerminal;
+use std::path::Path;
 
+use agentsdb_ops::promote::{ConflictStrategy, PromoteConflict};
+
+use crate::types::PromoteJson;
 use crate::util::parse_ids_csv;
 
+/// How `agentsdb promote` treats ids the destination already holds.
+#[derive(Clone, Copy)]
+pub(crate) enum Conflicts {
+    /// Give every promoted chunk a new id, so ids never conflict.
+    NewIds,
+    /// Keep ids where free and settle every conflict the same way.
+    Strategy(ConflictStrategy),
+    /// Keep ids where free and ask on stdin for each conflict.
+    Ask,
+}
+
+fn preview(chunk: &agentsdb_format::ChunkInput) -> String {
+    format!(
+        "[{}] {}",
+        chunk.kind,
+        agentsdb_ops::util::truncate_preview(&chunk.content, 200)
+    )
+}
+
+fn ask_strategy(to_path: &str, conflict: &PromoteConflict) -> anyhow::Result<ConflictStrategy> {
+    use std::io::Write;
+    eprintln!(
+        "Conflict: {to_path} already contains id {} with different content",
+        conflict.id
+    );
+    eprintln!("  existing: {}", preview(&conflict.existing));
+    eprintln!("  incoming: {}", preview(&conflict.incoming));
+    loop {
+        eprint!("Keep [o]urs, take [t]heirs (supersede), [r]ename, or [a]bort? ");
+        std::io::stderr().flush().ok();
+        let mut s = String::new();
+        if std::io::stdin().read_line(&mut s)? == 0 {
+            anyhow::bail!("aborted");
+        }
+        match s.trim().to_ascii_lowercase().as_str() {
+            "o" | "ours" => return Ok(ConflictStrategy::Ours),
+            "t" | "theirs" => return Ok(ConflictStrategy::Theirs),
+            "r" | "rename" => return Ok(ConflictStrategy::Rename),
+            "a" | "abort" => anyhow::bail!("aborted"),
+            _ => {}
+        }
+    }
+}
+
+/// The chunk ids `--ids` and `--namespace` select in `from_path`.
+fn wanted_ids(
+    from_path: &str,
+    ids: Option<&str>,
+    namespace: Option<&str>,
+) -> anyhow::Result<Vec<u32>> {
+    let wanted = match (ids, namespace) {
+        (Some(ids), None) => parse_ids_csv(ids)?,
+        (None, Some(namespace)) => {
+            let members = agentsdb_ops::promote::namespace_chunk_ids(from_path, namespace)?;
+            if members.is_empty() {
+                anyhow::bail!("no chunks in namespace {namespace:?} in {from_path}");
+            }
+            members
+        }
+        (Some(ids), Some(namespace)) => {
+            let members = agentsdb_ops::promote::namespace_chunk_ids(from_path, namespace)?;
+            let wanted = parse_ids_csv(ids)?;
+            if let Some(id) = wanted.iter().find(|id| !members.contains(id)) {
+                anyhow::bail!("chunk {id} is not in namespace {namespace:?}");
+            }
+            wanted
+        }
+        (None, None) => anyhow::bail!("pass --ids or --namespace"),
+    };
+    if wanted.is_empty() {
+        anyhow::bail!("--ids must be non-empty");
+    }
+    Ok(wanted)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn cmd_promote(
     from_path: &str,
     to_path: &str,
-    ids: &str,
+    ids: Option<&str>,
+    namespace: Option<&str>,
     skip_existing: bool,
+    conflicts: Conflicts,
     yes: bool,
     json: bool,
 ) -> anyhow::Result<()> {
-    let wanted = parse_ids_csv(ids)?;
-    if wanted.is_empty() {
-        anyhow::bail!("--ids must be non-empty");
-    }
+    let wanted = wanted_ids(from_path, ids, namespace)?;
 
     // Prompt for confirmation if writing to user layer and not in non-interactive mode
     if !yes
@@ -40,31 +119,33 @@ pub(crate) fn cmd_promote(
     }
 
     // Use shared promote operation
-    let out = agentsdb_ops::promote::promote_chunks(
-        from_path,
-        to_path,
-        &wanted,
-        skip_existing,
-    )?;
+    let out = match conflicts {
+        Conflicts::NewIds => {
+            agentsdb_ops::promote::promote_chunks(from_path, to_path, &wanted, skip_existing)?
+        }
+        Conflicts::Strategy(strategy) => {
+            agentsdb_ops::promote::promote_chunks_resolving(from_path, to_path, &wanted, |_| {
+                Ok(strategy)
+            })?
+        }
+        Conflicts::Ask => {
+            agentsdb_ops::promote::promote_chunks_resolving(from_path, to_path, &wanted, |c| {
+                ask_strategy(to_path, c)
+            })?
+        }
+    };
 
     if json {
-        #[derive(Serialize)]
-        struct Out<'a> {
-            ok: bool,
-            from: &'a str,
-            to: &'a str,
-            promoted: Vec<u32>,
-            #[serde(skip_serializing_if = "Vec::is_empty")]
-            skipped: Vec<u32>,
-        }
         println!(
             "{}",
-            serde_json::to_string_pretty(&Out {
+            serde_json::to_string_pretty(&PromoteJson {
                 ok: true,
                 from: from_path,
                 to: to_path,
                 promoted: out.promoted,
                 skipped: out.skipped,
+                operation: out.operation,
+                conflicts: out.conflicts,
             })?
         );
     } else {
@@ -76,13 +157,84 @@ pub(crate) fn cmd_promote(
                 out.promoted.len()
             );
         }
+        if let Some(op) = out.operation {
+            println!("Recorded as operation {op} (undo with `agentsdb undo {op}`)");
+        }
         if !out.skipped.is_empty() {
             println!(
                 "Skipped {} ids already present in destination",
                 out.skipped.len()
             );
         }
+        for c in &out.conflicts {
+            match (c.strategy, c.promoted_id) {
+                (ConflictStrategy::Theirs, Some(new_id)) => {
+                    println!(
+                        "Conflict on id {}: promoted as {new_id}, superseding the existing chunk",
+                        c.id
+                    )
+                }
+                (ConflictStrategy::Rename, Some(new_id)) => {
+                    println!("Conflict on id {}: promoted as {new_id}", c.id)
+                }
+                _ => println!("Conflict on id {}: kept the existing chunk", c.id),
+            }
+        }
     }
 
     Ok(())
 }
+
+/// `agentsdb promote --via-pr`: opens a pull request adding the chunks to the base layer.
+pub(crate) fn cmd_promote_via_pr(
+    from_path: &str,
+    to_path: &str,
+    ids: Option<&str>,
+    namespace: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let wanted = wanted_ids(from_path, ids, namespace)?;
+    let to = Path::new(to_path);
+    let dir = to
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let provider = agentsdb_ops::vcs::provider_for_dir(dir)?;
+    let out =
+        agentsdb_ops::vcs::promote_via_pr(provider.as_ref(), Path::new(from_path), to, &wanted)?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            from: &'a str,
+            to: &'a str,
+            #[serde(flatten)]
+            promotion: &'a agentsdb_ops::vcs::PrPromotion,
+        }
+        let out = Out {
+            ok: true,
+            from: from_path,
+            to: to_path,
+            promotion: &out,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+    match &out.pull_request {
+        Some(pr) => println!(
+            "Opened {} pull request {} promoting {} chunks from {from_path} to {to_path}",
+            pr.provider,
+            pr.url,
+            out.promoted.len()
+        ),
+        None => println!("No chunks to promote (all requested ids already exist in {to_path})"),
+    }
+    if !out.skipped.is_empty() {
+        println!(
+            "Skipped {} ids already present in destination",
+            out.skipped.len()
+        );
+    }
+    Ok(())
+}
@@ -64,9 +64,9 @@ fn resolve_layer_label(dir: &Path, paths: &ResolvedPaths, label: &str) -> PathBu
 /// This struct is deserialized from the `meta.proposal_event` chunk content.
 struct ProposalEvent {
     #[serde(default)]
-    action: Option<String>, // propose | accept | reject
+    action: Option<String>, // propose | approve | accept | reject
     #[serde(default)]
-    proposal_id: Option<u32>, // for accept/reject
+    proposal_id: Option<u32>, // for approve/accept/reject
     context_id: u32,
     #[serde(default)]
     from_path: Option<String>,
@@ -87,6 +87,8 @@ struct ProposalEvent {
     #[serde(default)]
     actor: Option<String>,
     #[serde(default)]
+    identity: Option<String>,
+    #[serde(default)]
     reason: Option<String>,
     #[serde(default)]
     outcome: Option<String>,
@@ -114,10 +116,13 @@ struct ProposalState {
     why: Option<String>,
     what: Option<String>,
     where_: Option<String>,
+    proposed_by_identity: Option<String>,
     decided_at_unix_ms: Option<u64>,
     decided_by: Option<String>,
+    decided_by_identity: Option<String>,
     decision_reason: Option<String>,
     decision_outcome: Option<String>,
+    approvers: Vec<String>,
 }
 
 fn read_proposal_events(path: &Path) -> anyhow::Result<Vec<(u32, ProposalEvent)>> {
@@ -160,18 +165,31 @@ fn apply_event(map: &mut BTreeMap<u32, ProposalState>, event_id: u32, ev: Propos
                     why: ev.why,
                     what: ev.what,
                     where_: ev.where_,
+                    proposed_by_identity: ev.identity,
                     decided_at_unix_ms: None,
                     decided_by: None,
+                    decided_by_identity: None,
                     decision_reason: None,
                     decision_outcome: None,
+                    approvers: Vec::new(),
                 },
             );
         }
-        "accept" | "reject" => {
+        agentsdb_ops::policy::APPROVE_ACTION | "accept" | "reject" => {
             let Some(proposal_id) = ev.proposal_id else {
                 return;
             };
-            if let Some(state) = map.get_mut(&proposal_id) {
+            let Some(state) = map.get_mut(&proposal_id) else {
+                return;
+            };
+            if action != "reject" {
+                if let Some(actor) = &ev.actor {
+                    if !state.approvers.contains(actor) {
+                        state.approvers.push(actor.clone());
+                    }
+                }
+            }
+            if action != agentsdb_ops::policy::APPROVE_ACTION {
                 state.status = if action == "accept" {
                     ProposalStatus::Accepted
                 } else {
@@ -179,6 +197,7 @@ fn apply_event(map: &mut BTreeMap<u32, ProposalState>, event_id: u32, ev: Propos
                 };
                 state.decided_at_unix_ms = ev.created_at_unix_ms;
                 state.decided_by = ev.actor;
+                state.decided_by_identity = ev.identity;
                 state.decision_reason = ev.reason;
                 state.decision_outcome = ev.outcome;
             }
@@ -211,6 +230,7 @@ fn append_decision_event(
     action: &str,
     proposal_id: u32,
     context_id: u32,
+    actor: &str,
     outcome: Option<&str>,
     reason: Option<&str>,
 ) -> anyhow::Result<()> {
@@ -220,7 +240,8 @@ fn append_decision_event(
         "proposal_id": proposal_id,
         "context_id": context_id,
         "created_at_unix_ms": now_ms,
-        "actor": "human",
+        "actor": actor,
+        "identity": agentsdb_ops::identity::current(),
         "outcome": outcome,
         "reason": reason,
     });
@@ -293,6 +314,8 @@ pub(crate) fn cmd_proposals_list(
             what: Option<String>,
             #[serde(rename = "where")]
             where_: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            proposed_by: Option<String>,
             exists_in_source: bool,
             exists_in_target: bool,
         }
@@ -309,6 +332,7 @@ pub(crate) fn cmd_proposals_list(
                 why: s.why,
                 what: s.what,
                 where_: s.where_,
+                proposed_by: s.proposed_by_identity,
                 exists_in_source,
                 exists_in_target,
             })
@@ -332,6 +356,11 @@ pub(crate) fn cmd_proposals_list(
             .as_deref()
             .map(|t| format!(" - {}", one_line(t)))
             .unwrap_or_default();
+        let by = s
+            .proposed_by_identity
+            .as_deref()
+            .map(|who| format!(" [by {who}]"))
+            .unwrap_or_default();
         let mut flags = Vec::new();
         if !exists_in_source {
             flags.push("missing-in-source");
@@ -341,17 +370,18 @@ pub(crate) fn cmd_proposals_list(
         }
         if flags.is_empty() {
             println!(
-                "  - proposal {}: {} {} -> {}{}",
-                s.proposal_id, s.context_id, s.from_path, s.to_path, title
+                "  - proposal {}: {} {} -> {}{}{}",
+                s.proposal_id, s.context_id, s.from_path, s.to_path, title, by
             );
         } else {
             println!(
-                "  - proposal {}: {} {} -> {}{} ({})",
+                "  - proposal {}: {} {} -> {}{}{} ({})",
                 s.proposal_id,
                 s.context_id,
                 s.from_path,
                 s.to_path,
                 title,
+                by,
                 flags.join(", ")
             );
         }
@@ -409,6 +439,15 @@ pub(crate) fn cmd_proposals_show(
     println!("To: {}", state.to_path);
     println!("Status: {:?}", state.status);
     println!("Context id: {}", state.context_id);
+    if let Some(who) = state.proposed_by_identity.as_deref() {
+        println!("Proposed by: {who}");
+    }
+    if let Some(who) = state.decided_by_identity.as_deref() {
+        println!("Decided by: {who}");
+    }
+    if !state.approvers.is_empty() {
+        println!("Approved by: {}", state.approvers.join(", "));
+    }
     if let Some(t) = state.title.as_deref() {
         println!("Title: {}", one_line(t));
     }
@@ -487,10 +526,16 @@ struct ProposalStateJson {
     what: Option<String>,
     #[serde(rename = "where")]
     where_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proposed_by_identity: Option<String>,
     decided_at_unix_ms: Option<u64>,
     decided_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decided_by_identity: Option<String>,
     decision_reason: Option<String>,
     decision_outcome: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    approvers: Vec<String>,
 }
 
 impl From<ProposalState> for ProposalStateJson {
@@ -506,10 +551,13 @@ impl From<ProposalState> for ProposalStateJson {
             why: s.why,
             what: s.what,
             where_: s.where_,
+            proposed_by_identity: s.proposed_by_identity,
             decided_at_unix_ms: s.decided_at_unix_ms,
             decided_by: s.decided_by,
+            decided_by_identity: s.decided_by_identity,
             decision_reason: s.decision_reason,
             decision_outcome: s.decision_outcome,
+            approvers: s.approvers,
         }
     }
 }
@@ -523,6 +571,55 @@ struct PromoteOut {
     promoted: Vec<u32>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     skipped: Vec<u32>,
+    /// Proposals approved but still short of the policy's required approvals.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    awaiting: Vec<AwaitingApproval>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AwaitingApproval {
+    proposal_id: u32,
+    approvers: Vec<String>,
+    missing: usize,
+}
+
+/// Records an approval by `approver` for each proposal in `wanted` that still lacks approvals
+/// under the promotion policy of `dir`, and returns those proposals; the rest can be accepted.
+fn record_approvals(
+    dir: &Path,
+    paths: &ResolvedPaths,
+    states: &BTreeMap<u32, ProposalState>,
+    wanted: &[u32],
+    approver: &str,
+) -> anyhow::Result<Vec<AwaitingApproval>> {
+    let policy = agentsdb_ops::policy::PromotionPolicy::load(dir)?;
+    let mut awaiting = Vec::new();
+    for id in wanted {
+        let s = states.get(id).context("proposal missing")?;
+        let missing = policy.missing_approvals(&s.approvers, approver);
+        if missing == 0 {
+            continue;
+        }
+        append_decision_event(
+            &paths.proposals_layer,
+            agentsdb_ops::policy::APPROVE_ACTION,
+            *id,
+            s.context_id,
+            approver,
+            None,
+            None,
+        )?;
+        let mut approvers = s.approvers.clone();
+        if !approvers.iter().any(|a| a == approver) {
+            approvers.push(approver.to_string());
+        }
+        awaiting.push(AwaitingApproval {
+            proposal_id: *id,
+            approvers,
+            missing,
+        });
+    }
+    Ok(awaiting)
 }
 
 pub(crate) fn cmd_proposals_accept(
@@ -533,13 +630,14 @@ pub(crate) fn cmd_proposals_accept(
     ids: &str,
     skip_existing: bool,
     _yes: bool,
+    approver: &str,
     json: bool,
 ) -> anyhow::Result<()> {
     // Implements the `proposals accept` command, which accepts proposals by promoting
     // their chunks into the user layer.
     //
-    // This function handles validating proposals, performing the promotion, and recording
-    // the acceptance event.
+    // This function handles validating proposals, recording approvals that the promotion
+    // policy still needs, performing the promotion, and recording the acceptance event.
     let dir = Path::new(dir);
     let paths = resolve_paths(dir, delta, user, proposals_layer);
     let states = load_states(&paths.proposals_layer)?;
@@ -561,8 +659,12 @@ pub(crate) fn cmd_proposals_accept(
         }
     }
 
+    let awaiting = record_approvals(dir, &paths, &states, &wanted, approver)?;
     let mut by_pair: BTreeMap<(String, String), Vec<(u32, u32)>> = BTreeMap::new();
     for pid in &wanted {
+        if awaiting.iter().any(|a| a.proposal_id == *pid) {
+            continue;
+        }
         let s = states.get(pid).context("proposal missing")?;
         by_pair
             .entry((s.from_path.clone(), s.to_path.clone()))
@@ -599,6 +701,7 @@ pub(crate) fn cmd_proposals_accept(
                 "accept",
                 proposal_id,
                 context_id,
+                approver,
                 outcome,
                 None,
             )?;
@@ -619,6 +722,7 @@ pub(crate) fn cmd_proposals_accept(
                 to: "varies".to_string(),
                 promoted,
                 skipped,
+                awaiting,
             })?
         );
         return Ok(());
@@ -634,7 +738,18 @@ pub(crate) fn cmd_proposals_accept(
             skipped.len()
         );
     }
-    println!("Recorded {} proposal acceptances", wanted.len());
+    for a in &awaiting {
+        println!(
+            "Proposal {} approved by {}; needs {} more approval(s)",
+            a.proposal_id,
+            a.approvers.join(", "),
+            a.missing
+        );
+    }
+    println!(
+        "Recorded {} proposal acceptances",
+        wanted.len() - awaiting.len()
+    );
     Ok(())
 }
 
@@ -673,6 +788,7 @@ pub(crate) fn cmd_proposals_reject(
             "reject",
             *id,
             s.context_id,
+            "human",
             Some("rejected"),
             reason,
         )?;
@@ -1,20 +1,210 @@
 use anyhow::Context;
-use serde::Serialize;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use agentsdb_embeddings::config::{get_immutable_embedding_options, standard_layer_paths_for_dir};
+use agentsdb_embeddings::embedder::{EmbedPurpose, Embedder};
+use agentsdb_embeddings::usage::UsageReport;
 
 use crate::embedding_helpers::validate_layer_dimension;
 
+/// Delay before the first retry of a failed batch; doubled for each further attempt.
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// First line of a re-embed checkpoint: what the embeddings after it were computed for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CheckpointHeader {
+    backend: String,
+    model: Option<String>,
+    dim: usize,
+    chunk_count: usize,
+    /// SHA-256 over the ids and contents of the layer's chunks, in order.
+    fingerprint: String,
+}
+
+/// One embedded batch: the embeddings of rows `start..start + embeddings.len()`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CheckpointBatch {
+    start: usize,
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Rows the embedder kept failing on after all retries.
+#[derive(Debug, Clone, Serialize)]
+struct FailedRange {
+    layer: String,
+    start: usize,
+    end: usize,
+    error: String,
+}
+
+/// Checkpoint sidecar for `layer_path`, e.g. `AGENTS.user.db.reembed.json`.
+fn checkpoint_path(layer_path: &Path) -> PathBuf {
+    let mut name = layer_path.as_os_str().to_owned();
+    name.push(".reembed.json");
+    PathBuf::from(name)
+}
+
+fn layer_fingerprint(chunks: &[agentsdb_format::ChunkInput]) -> String {
+    let mut buf = Vec::new();
+    for chunk in chunks {
+        buf.extend_from_slice(&chunk.id.to_le_bytes());
+        buf.extend_from_slice(chunk.content.as_bytes());
+        buf.push(0);
+    }
+    agentsdb_ops::util::hex_lower(&agentsdb_embeddings::cache::sha256(&buf))
+}
+
+/// Reads the embeddings a checkpoint already holds, one slot per row. A truncated last line (the
+/// process died mid-write) is ignored; that batch is simply embedded again.
+fn load_checkpoint(
+    path: &Path,
+    expected: &CheckpointHeader,
+) -> anyhow::Result<Vec<Option<Vec<f32>>>> {
+    let file = std::fs::File::open(path).with_context(|| format!("open {}", path.display()))?;
+    let lines = std::io::BufReader::new(file)
+        .lines()
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("read {}", path.display()))?;
+    let Some((first, rest)) = lines.split_first() else {
+        anyhow::bail!(
+            "checkpoint {} is empty; delete it to start over",
+            path.display()
+        );
+    };
+    let header: CheckpointHeader =
+        serde_json::from_str(first).with_context(|| format!("parse {}", path.display()))?;
+    if header != *expected {
+        anyhow::bail!(
+            "checkpoint {} was written for a different layer state or embedding configuration; \
+             delete it to start over",
+            path.display()
+        );
+    }
+
+    let mut embedded = vec![None; expected.chunk_count];
+    for (i, line) in rest.iter().enumerate() {
+        let batch: CheckpointBatch = match serde_json::from_str(line) {
+            Ok(batch) => batch,
+            Err(_) if i + 1 == rest.len() => break,
+            Err(err) => {
+                return Err(err).with_context(|| format!("parse {} line {}", path.display(), i + 2))
+            }
+        };
+        for (offset, embedding) in batch.embeddings.into_iter().enumerate() {
+            let row = batch.start + offset;
+            if row >= embedded.len() || embedding.len() != expected.dim {
+                anyhow::bail!(
+                    "checkpoint {} holds an invalid batch at row {row}",
+                    path.display()
+                );
+            }
+            embedded[row] = Some(embedding);
+        }
+    }
+    Ok(embedded)
+}
+
+/// Embeds every row of `inputs` that has no embedding yet, `batch_size` rows per request,
+/// appending each finished batch to `checkpoint`. A batch that still fails after `retries`
+/// retries is recorded and skipped so the remaining batches are not lost.
+fn embed_pending(
+    embedder: &dyn Embedder,
+    inputs: &[String],
+    embedded: &mut [Option<Vec<f32>>],
+    dim: usize,
+    batch_size: usize,
+    retries: u32,
+    base_delay: Duration,
+    checkpoint: &mut impl Write,
+    progress: bool,
+) -> anyhow::Result<Vec<(usize, usize, String)>> {
+    let mut failed = Vec::new();
+    let mut done = embedded.iter().filter(|e| e.is_some()).count();
+    let mut start = 0usize;
+    while start < inputs.len() {
+        if embedded[start].is_some() {
+            start += 1;
+            continue;
+        }
+        let mut end = start + 1;
+        while end < inputs.len() && end - start < batch_size && embedded[end].is_none() {
+            end += 1;
+        }
+
+        let mut attempt = 0u32;
+        let result = loop {
+            match embedder.embed_for(EmbedPurpose::Document, &inputs[start..end]) {
+                Ok(embeddings) => break Ok(embeddings),
+                Err(err) if attempt < retries => {
+                    let delay = base_delay * 2u32.saturating_pow(attempt);
+                    tracing::warn!(
+                        "embedding rows {start}..{end} failed ({err:#}); retrying in {}ms",
+                        delay.as_millis()
+                    );
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                Err(err) => break Err(err),
+            }
+        };
+
+        match result {
+            Ok(embeddings) => {
+                if embeddings.len() != end - start {
+                    anyhow::bail!(
+                        "embedder returned {} embeddings for {} chunks",
+                        embeddings.len(),
+                        end - start
+                    );
+                }
+                if let Some(bad) = embeddings.iter().find(|e| e.len() != dim) {
+                    anyhow::bail!(
+                        "embedder returned embedding of dim={} but expected dim={dim}",
+                        bad.len()
+                    );
+                }
+                let batch = CheckpointBatch { start, embeddings };
+                let line = serde_json::to_string(&batch)?;
+                writeln!(checkpoint, "{line}").context("write re-embed checkpoint")?;
+                checkpoint.flush().context("flush re-embed checkpoint")?;
+                for (slot, embedding) in embedded[start..end].iter_mut().zip(batch.embeddings) {
+                    *slot = Some(embedding);
+                }
+                done += end - start;
+                if progress {
+                    eprintln!("  {done}/{} chunks embedded", inputs.len());
+                }
+            }
+            Err(err) => {
+                tracing::warn!("giving up on rows {start}..{end}: {err:#}");
+                failed.push((start, end, format!("{err:#}")));
+            }
+        }
+        start = end;
+    }
+    Ok(failed)
+}
+
 pub(crate) fn cmd_reembed(
     dir: &str,
     layers_csv: &str,
     allow_base: bool,
+    price_per_million_tokens: Option<f64>,
+    batch_size: usize,
+    resume: bool,
+    retries: u32,
     json: bool,
 ) -> anyhow::Result<()> {
     let dir_path = Path::new(dir);
     let standard_paths = standard_layer_paths_for_dir(dir_path);
 
+    if batch_size == 0 {
+        anyhow::bail!("--batch-size must be at least 1");
+    }
+
     // Parse which layers to re-embed
     let requested_layers: Vec<&str> = layers_csv.split(',').map(|s| s.trim()).collect();
 
@@ -47,6 +237,13 @@ pub(crate) fn cmd_reembed(
 
     let mut reembedded_layers = Vec::new();
     let mut total_chunks = 0usize;
+    let mut resumed_chunks = 0usize;
+    let mut failed: Vec<FailedRange> = Vec::new();
+
+    let mut policy = agentsdb_format::WritePolicy::standard().allow_user();
+    if allow_base {
+        policy = policy.allow_base();
+    }
 
     // Process each requested layer
     for layer_name in &requested_layers {
@@ -66,23 +263,10 @@ pub(crate) fn cmd_reembed(
             continue;
         }
 
-        // Check writability based on layer type
-        match *layer_name {
-            "base" => {
-                if !allow_base {
-                    // This should have been caught earlier, but double-check
-                    anyhow::bail!("internal error: attempting to re-embed base without --allow-base");
-                }
-                agentsdb_format::ensure_writable_layer_path_allow_base(layer_path)
-                    .with_context(|| format!("verify {} is writable", layer_path.display()))?;
-            }
-            "user" | "delta" | "local" => {
-                // User layer requires allow_user flag, delta/local are always writable
-                agentsdb_format::ensure_writable_layer_path_allow_user(layer_path)
-                    .with_context(|| format!("verify {} is writable", layer_path.display()))?;
-            }
-            _ => unreachable!(),
-        }
+        // Check writability; the policy only lets base through with --allow-base
+        policy
+            .check(layer_path)
+            .with_context(|| format!("verify {} is writable", layer_path.display()))?;
 
         // Open the layer file
         let file = agentsdb_format::LayerFile::open(layer_path)
@@ -104,41 +288,80 @@ pub(crate) fn cmd_reembed(
         // Check embedding dimension matches
         validate_layer_dimension(&schema, options.dim, layer_path)?;
 
+        // Pick up where an earlier run left off, or start a fresh checkpoint
+        let ckpt_path = checkpoint_path(layer_path);
+        let header = CheckpointHeader {
+            backend: options.backend.clone(),
+            model: options.model.clone(),
+            dim: schema.dim as usize,
+            chunk_count: chunks.len(),
+            fingerprint: layer_fingerprint(&chunks),
+        };
+        let mut embedded = if ckpt_path.exists() {
+            if !resume {
+                anyhow::bail!(
+                    "{} holds progress from an earlier re-embed; pass --resume to continue it \
+                     or delete it to start over",
+                    ckpt_path.display()
+                );
+            }
+            load_checkpoint(&ckpt_path, &header)?
+        } else {
+            let line = serde_json::to_string(&header)?;
+            std::fs::write(&ckpt_path, format!("{line}\n"))
+                .with_context(|| format!("write {}", ckpt_path.display()))?;
+            vec![None; chunks.len()]
+        };
+        let already = embedded.iter().filter(|e| e.is_some()).count();
+        resumed_chunks += already;
+
         // Prepare content to embed
         let to_embed: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
 
         if !json {
             println!(
                 "Re-embedding {} chunks in {} using backend={}...",
-                to_embed.len(),
+                to_embed.len() - already,
                 layer_path.display(),
                 options.backend
             );
         }
 
-        // Generate new embeddings
-        let embeddings = embedder
-            .embed(&to_embed)
-            .with_context(|| format!("embed chunks for {}", layer_path.display()))?;
-
-        if embeddings.len() != chunks.len() {
-            anyhow::bail!(
-                "embedder returned {} embeddings for {} chunks",
-                embeddings.len(),
-                chunks.len()
-            );
+        // Generate new embeddings, checkpointing each batch
+        let mut ckpt_file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&ckpt_path)
+            .with_context(|| format!("open {}", ckpt_path.display()))?;
+        let layer_failed = embed_pending(
+            embedder.as_ref(),
+            &to_embed,
+            &mut embedded,
+            schema.dim as usize,
+            batch_size,
+            retries,
+            Duration::from_millis(RETRY_BASE_DELAY_MS),
+            &mut ckpt_file,
+            !json,
+        )
+        .with_context(|| format!("embed chunks for {}", layer_path.display()))?;
+        drop(ckpt_file);
+
+        if !layer_failed.is_empty() {
+            let layer = layer_path.to_string_lossy().into_owned();
+            for (start, end, error) in layer_failed {
+                failed.push(FailedRange {
+                    layer: layer.clone(),
+                    start,
+                    end,
+                    error,
+                });
+            }
+            continue;
         }
 
         // Update chunks with new embeddings
-        for (chunk, embedding) in chunks.iter_mut().zip(embeddings.into_iter()) {
-            if embedding.len() != schema.dim as usize {
-                anyhow::bail!(
-                    "embedder returned embedding of dim={} but expected dim={}",
-                    embedding.len(),
-                    schema.dim
-                );
-            }
-            chunk.embedding = embedding;
+        for (chunk, embedding) in chunks.iter_mut().zip(embedded.into_iter()) {
+            chunk.embedding = embedding.context("internal error: row left without an embedding")?;
         }
 
         // Preserve existing layer metadata if present
@@ -152,28 +375,40 @@ pub(crate) fn cmd_reembed(
             layer_metadata.as_deref(),
         )
         .with_context(|| format!("write re-embedded layer {}", layer_path.display()))?;
+        std::fs::remove_file(&ckpt_path)
+            .with_context(|| format!("remove {}", ckpt_path.display()))?;
 
         reembedded_layers.push(layer_path.to_string_lossy().into_owned());
         total_chunks += chunks.len();
     }
 
+    let embedding_usage =
+        UsageReport::from_embedder(embedder.as_ref()).with_price(price_per_million_tokens);
+
     if json {
         #[derive(Serialize)]
         struct Out {
             ok: bool,
             reembedded_layers: Vec<String>,
             total_chunks: usize,
+            resumed_chunks: usize,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            failed: Vec<FailedRange>,
             backend: String,
             model: Option<String>,
+            embedding_usage: UsageReport,
         }
         println!(
             "{}",
             serde_json::to_string_pretty(&Out {
-                ok: true,
+                ok: failed.is_empty(),
                 reembedded_layers,
                 total_chunks,
+                resumed_chunks,
+                failed: failed.clone(),
                 backend: options.backend.clone(),
                 model: options.model.clone(),
+                embedding_usage,
             })?
         );
     } else {
@@ -187,6 +422,23 @@ pub(crate) fn cmd_reembed(
                 options.backend
             );
         }
+        if resumed_chunks > 0 {
+            println!("{resumed_chunks} chunk(s) were taken from earlier checkpoints");
+        }
+        if !reembedded_layers.is_empty() || !failed.is_empty() {
+            println!("{}", embedding_usage.summary());
+        }
+    }
+
+    if !failed.is_empty() {
+        let ranges: Vec<String> = failed
+            .iter()
+            .map(|f| format!("{} rows {}..{}", f.layer, f.start, f.end))
+            .collect();
+        anyhow::bail!(
+            "embedding failed for {}; finished batches are checkpointed, re-run with --resume",
+            ranges.join(", ")
+        );
     }
 
     Ok(())
@@ -195,27 +447,7 @@ pub(crate) fn cmd_reembed(
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    fn schema() -> agentsdb_format::LayerSchema {
-        agentsdb_format::LayerSchema {
-            dim: 4,
-            element_type: agentsdb_format::EmbeddingElementType::F32,
-            quant_scale: 1.0,
-        }
-    }
-
-    fn chunk(id: u32, kind: &str, content: &str) -> agentsdb_format::ChunkInput {
-        agentsdb_format::ChunkInput {
-            id,
-            kind: kind.to_string(),
-            content: content.to_string(),
-            author: "human".to_string(),
-            confidence: 1.0,
-            created_at_unix_ms: 0,
-            embedding: vec![0.1, 0.2, 0.3, 0.4],
-            sources: Vec::new(),
-        }
-    }
+    use crate::test_support::{chunk, schema};
 
     #[test]
     fn reembed_updates_embeddings() {
@@ -230,6 +462,8 @@ mod tests {
                 dim: Some(4),
                 ..Default::default()
             }),
+            search: None,
+            server: None,
             checksum_allowlist: None,
         };
         let options_chunk = agentsdb_format::ChunkInput {
@@ -265,7 +499,7 @@ mod tests {
 
         // Re-embed user layer only
         let dir_str = dir.to_string_lossy();
-        cmd_reembed(&dir_str, "user", false, false).unwrap();
+        cmd_reembed(&dir_str, "user", false, None, 64, false, 0, false).unwrap();
 
         // Read back and verify embeddings changed
         let user_file_after = agentsdb_format::LayerFile::open(&user_path).unwrap();
@@ -296,6 +530,8 @@ mod tests {
                 dim: Some(4),
                 ..Default::default()
             }),
+            search: None,
+            server: None,
             checksum_allowlist: None,
         };
         let options_chunk = agentsdb_format::ChunkInput {
@@ -313,7 +549,7 @@ mod tests {
             .unwrap();
 
         let dir_str = dir.to_string_lossy();
-        let result = cmd_reembed(&dir_str, "base", false, false);
+        let result = cmd_reembed(&dir_str, "base", false, None, 64, false, 0, false);
 
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("--allow-base"));
@@ -331,6 +567,8 @@ mod tests {
                 dim: Some(4),
                 ..Default::default()
             }),
+            search: None,
+            server: None,
             checksum_allowlist: None,
         };
         let options_chunk = agentsdb_format::ChunkInput {
@@ -348,8 +586,170 @@ mod tests {
             .unwrap();
 
         let dir_str = dir.to_string_lossy();
-        let result = cmd_reembed(&dir_str, "base", true, false);
+        let result = cmd_reembed(&dir_str, "base", true, None, 64, false, 0, false);
 
         assert!(result.is_ok());
     }
+
+    /// Fails every request that contains `"boom"`, counting the inputs it was asked to embed.
+    struct FlakyEmbedder {
+        profile: agentsdb_embeddings::embedder::EmbeddingProfile,
+        embedded_inputs: std::cell::Cell<usize>,
+    }
+
+    impl FlakyEmbedder {
+        fn new() -> Self {
+            Self {
+                profile: agentsdb_embeddings::embedder::EmbeddingProfile {
+                    backend: "test".to_string(),
+                    model: None,
+                    revision: None,
+                    dim: 2,
+                    output_norm: agentsdb_embeddings::embedder::OutputNorm::None,
+                },
+                embedded_inputs: std::cell::Cell::new(0),
+            }
+        }
+    }
+
+    impl Embedder for FlakyEmbedder {
+        fn profile(&self) -> &agentsdb_embeddings::embedder::EmbeddingProfile {
+            &self.profile
+        }
+
+        fn embed(&self, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+            if inputs.iter().any(|s| s.contains("boom")) {
+                anyhow::bail!("provider unavailable");
+            }
+            self.embedded_inputs
+                .set(self.embedded_inputs.get() + inputs.len());
+            Ok(inputs.iter().map(|_| vec![1.0, 0.0]).collect())
+        }
+    }
+
+    #[test]
+    fn embed_pending_checkpoints_batches_and_skips_failures() {
+        let inputs: Vec<String> = ["a", "bb", "boom", "dddd", "eeeee"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let embedder = FlakyEmbedder::new();
+        let mut embedded = vec![None; inputs.len()];
+        let mut checkpoint = Vec::new();
+        let failed = embed_pending(
+            &embedder,
+            &inputs,
+            &mut embedded,
+            2,
+            2,
+            1,
+            Duration::ZERO,
+            &mut checkpoint,
+            false,
+        )
+        .unwrap();
+
+        // Rows 2..4 hold "boom" and fail; the batches around them are kept.
+        assert_eq!(failed.len(), 1);
+        assert_eq!((failed[0].0, failed[0].1), (2, 4));
+        assert_eq!(embedder.embedded_inputs.get(), 3);
+        assert!(embedded[0].is_some() && embedded[4].is_some());
+        assert!(embedded[2].is_none() && embedded[3].is_none());
+        let lines = String::from_utf8(checkpoint).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+
+        // A second pass only asks for the missing rows.
+        let mut inputs = inputs;
+        inputs[2] = "ccc".to_string();
+        let mut checkpoint = Vec::new();
+        let failed = embed_pending(
+            &embedder,
+            &inputs,
+            &mut embedded,
+            2,
+            2,
+            0,
+            Duration::ZERO,
+            &mut checkpoint,
+            false,
+        )
+        .unwrap();
+        assert!(failed.is_empty());
+        assert_eq!(embedder.embedded_inputs.get(), 5);
+        assert!(embedded.iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn reembed_resumes_from_checkpoint() {
+        let dir = crate::util::make_temp_dir();
+        let base_path = dir.join("AGENTS.db");
+        let user_path = dir.join("AGENTS.user.db");
+
+        let options_record = agentsdb_embeddings::config::OptionsRecord {
+            embedding: Some(agentsdb_embeddings::config::EmbeddingOptionsPatch {
+                backend: Some("hash".to_string()),
+                dim: Some(4),
+                ..Default::default()
+            }),
+            search: None,
+            server: None,
+            checksum_allowlist: None,
+        };
+        let options_chunk = agentsdb_format::ChunkInput {
+            id: 1000,
+            kind: agentsdb_embeddings::config::KIND_OPTIONS.to_string(),
+            content: serde_json::to_string(&options_record).unwrap(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 0,
+            embedding: vec![0.0; 4],
+            sources: Vec::new(),
+        };
+        let mut base_chunks = [options_chunk];
+        agentsdb_format::write_layer_atomic(&base_path, &schema(), &mut base_chunks, None)
+            .unwrap();
+        let mut user_chunks = [
+            chunk(100, "note", "user note"),
+            chunk(101, "note", "another note"),
+        ];
+        agentsdb_format::write_layer_atomic(&user_path, &schema(), &mut user_chunks, None)
+            .unwrap();
+
+        // Simulate an interrupted run that already embedded the first row.
+        let file = agentsdb_format::LayerFile::open(&user_path).unwrap();
+        let chunks = agentsdb_format::read_all_chunks(&file).unwrap();
+        let header = CheckpointHeader {
+            backend: "hash".to_string(),
+            model: None,
+            dim: 4,
+            chunk_count: chunks.len(),
+            fingerprint: layer_fingerprint(&chunks),
+        };
+        let batch = CheckpointBatch {
+            start: 0,
+            embeddings: vec![vec![9.0, 9.0, 9.0, 9.0]],
+        };
+        let ckpt_path = checkpoint_path(&user_path);
+        std::fs::write(
+            &ckpt_path,
+            format!(
+                "{}\n{}\n",
+                serde_json::to_string(&header).unwrap(),
+                serde_json::to_string(&batch).unwrap()
+            ),
+        )
+        .unwrap();
+
+        let dir_str = dir.to_string_lossy();
+        let err = cmd_reembed(&dir_str, "user", false, None, 64, false, 0, false).unwrap_err();
+        assert!(err.to_string().contains("--resume"));
+
+        cmd_reembed(&dir_str, "user", false, None, 64, true, 0, false).unwrap();
+        assert!(!ckpt_path.exists());
+
+        let file = agentsdb_format::LayerFile::open(&user_path).unwrap();
+        let after = agentsdb_format::read_all_chunks(&file).unwrap();
+        assert_eq!(after[0].embedding, vec![9.0, 9.0, 9.0, 9.0]);
+        assert_ne!(after[1].embedding, vec![0.1, 0.2, 0.3, 0.4]);
+    }
 }
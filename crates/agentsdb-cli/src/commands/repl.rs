@@ -0,0 +1,434 @@
+use anyhow::Context;
+use std::collections::HashSet;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use agentsdb_core::types::LayerId;
+use agentsdb_ops::{search_layers, SearchConfig};
+use agentsdb_query::{LayerSet, SearchMode};
+
+use crate::util::{layer_to_str, one_line};
+
+/// History file used when `--history` is not given: `~/.agentsdb_history`.
+const HISTORY_FILE: &str = ".agentsdb_history";
+
+/// Deepest chain of chunk-id sources `trace` follows.
+const MAX_TRACE_DEPTH: usize = 8;
+
+const HELP: &str = "\
+Commands:
+  search <text>          search the enabled layers (alias: s)
+  show <id>              print a chunk from the highest-precedence enabled layer
+  trace <id>             follow a chunk's provenance through its chunk-id sources
+  note <text>            append a note to the local layer
+  layers                 list layers and whether they are enabled
+  toggle <layer>         enable or disable base, user, delta or local
+  k <n>                  number of search results
+  history                list previous commands; rerun one with !<n> or !!
+  help                   show this help
+  quit                   leave the REPL (also: exit, Ctrl-D)";
+
+/// A chunk as found by `show` and `trace`.
+struct FoundChunk {
+    layer: LayerId,
+    id: u32,
+    kind: String,
+    author: String,
+    confidence: f32,
+    created_at_unix_ms: u64,
+    sources: Vec<agentsdb_format::ChunkSource>,
+    content: String,
+}
+
+struct Repl {
+    layers: LayerSet,
+    disabled: HashSet<LayerId>,
+    k: usize,
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+}
+
+fn parse_layer(name: &str) -> anyhow::Result<LayerId> {
+    match name {
+        "base" => Ok(LayerId::Base),
+        "user" => Ok(LayerId::User),
+        "delta" => Ok(LayerId::Delta),
+        "local" => Ok(LayerId::Local),
+        other => anyhow::bail!("unknown layer {other:?} (expected base, user, delta or local)"),
+    }
+}
+
+fn parse_id(arg: &str) -> anyhow::Result<u32> {
+    arg.parse()
+        .with_context(|| format!("expected a chunk id, got {arg:?}"))
+}
+
+impl Repl {
+    const fn path_of(&self, layer: LayerId) -> Option<&String> {
+        match layer {
+            LayerId::Base => self.layers.base.as_ref(),
+            LayerId::User => self.layers.user.as_ref(),
+            LayerId::Delta => self.layers.delta.as_ref(),
+            LayerId::Local => self.layers.local.as_ref(),
+            LayerId::Session => None,
+        }
+    }
+
+    /// The layer set with disabled layers removed.
+    fn enabled(&self) -> LayerSet {
+        let keep = |layer: LayerId, path: &Option<String>| {
+            path.clone().filter(|_| !self.disabled.contains(&layer))
+        };
+        LayerSet {
+            base: keep(LayerId::Base, &self.layers.base),
+            user: keep(LayerId::User, &self.layers.user),
+            delta: keep(LayerId::Delta, &self.layers.delta),
+            local: keep(LayerId::Local, &self.layers.local),
+        }
+    }
+
+    /// Resolves `!!` and `!<n>` against the history; other lines are returned unchanged.
+    fn expand(&self, line: &str) -> anyhow::Result<String> {
+        let Some(rest) = line.strip_prefix('!') else {
+            return Ok(line.to_string());
+        };
+        let entry = if rest == "!" {
+            self.history.last()
+        } else {
+            let n: usize = rest
+                .parse()
+                .with_context(|| format!("expected !<n> or !!, got {line:?}"))?;
+            n.checked_sub(1).and_then(|i| self.history.get(i))
+        };
+        entry
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("no history entry {line}"))
+    }
+
+    fn record(&mut self, line: &str) {
+        self.history.push(line.to_string());
+        let Some(path) = &self.history_path else {
+            return;
+        };
+        let appended = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut f| writeln!(f, "{line}"));
+        if let Err(err) = appended {
+            eprintln!("warning: cannot write history to {}: {err}", path.display());
+            self.history_path = None;
+        }
+    }
+
+    /// Runs one command; returns `false` when the REPL should exit.
+    fn execute(&mut self, line: &str, out: &mut dyn Write) -> anyhow::Result<bool> {
+        let (cmd, arg) = line
+            .split_once(char::is_whitespace)
+            .map_or((line, ""), |(c, a)| (c, a.trim()));
+        match cmd {
+            "quit" | "exit" => return Ok(false),
+            "help" | "?" => writeln!(out, "{HELP}")?,
+            "search" | "s" => self.search(arg, out)?,
+            "show" => self.show(parse_id(arg)?, out)?,
+            "trace" => self.trace(parse_id(arg)?, out)?,
+            "note" => self.note(arg, out)?,
+            "layers" => self.list_layers(out)?,
+            "toggle" => {
+                let layer = parse_layer(arg)?;
+                if self.path_of(layer).is_none() {
+                    anyhow::bail!("layer {arg} is not open");
+                }
+                let enabled = self.disabled.remove(&layer);
+                if !enabled {
+                    self.disabled.insert(layer);
+                }
+                writeln!(out, "{arg}: {}", if enabled { "on" } else { "off" })?;
+            }
+            "k" => {
+                self.k = arg
+                    .parse()
+                    .with_context(|| format!("expected a number, got {arg:?}"))?;
+                writeln!(out, "k = {}", self.k)?;
+            }
+            "history" => {
+                for (i, entry) in self.history.iter().enumerate() {
+                    writeln!(out, "{:>4}  {entry}", i + 1)?;
+                }
+            }
+            other => anyhow::bail!("unknown command {other:?}; type `help` for a list"),
+        }
+        Ok(true)
+    }
+
+    fn search(&self, query: &str, out: &mut dyn Write) -> anyhow::Result<()> {
+        if query.is_empty() {
+            anyhow::bail!("usage: search <text>");
+        }
+        let config = SearchConfig {
+            query: Some(query.to_string()),
+            query_vec: None,
+            k: self.k,
+            kinds: Vec::new(),
+            namespace: None,
+            language: None,
+            use_index: false,
+            mode: SearchMode::Hybrid,
+            cache_results: false,
+            disk_cache: false,
+        };
+        let results = search_layers(&self.enabled(), config).context("search")?;
+        if results.is_empty() {
+            writeln!(out, "no results")?;
+        }
+        for r in results {
+            writeln!(
+                out,
+                "[{}] {} {:.3} {}: {}",
+                layer_to_str(r.layer),
+                r.chunk.id.get(),
+                r.score,
+                r.chunk.kind,
+                one_line(&r.chunk.content)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The chunk `id` from the highest-precedence enabled layer holding it, unless retracted.
+    fn find(&self, id: u32) -> anyhow::Result<Option<FoundChunk>> {
+        let opened = self.enabled().open().context("open layers")?;
+        let retracted = agentsdb_query::retracted_chunk_ids(&opened)?;
+        for (layer, file) in &opened {
+            if agentsdb_query::is_retracted(&retracted, *layer, id) {
+                continue;
+            }
+            for chunk in file.chunks() {
+                let chunk = chunk?;
+                if chunk.id != id || chunk.kind == agentsdb_query::KIND_TOMBSTONE {
+                    continue;
+                }
+                let sources = file
+                    .sources_for(chunk.rel_start, chunk.rel_count)?
+                    .into_iter()
+                    .map(|s| match s {
+                        agentsdb_format::SourceRef::ChunkId(v) => {
+                            agentsdb_format::ChunkSource::ChunkId(v)
+                        }
+                        agentsdb_format::SourceRef::String(v) => {
+                            agentsdb_format::ChunkSource::SourceString(v.to_string())
+                        }
+                    })
+                    .collect();
+                return Ok(Some(FoundChunk {
+                    layer: *layer,
+                    id,
+                    kind: chunk.kind.to_string(),
+                    author: chunk.author.to_string(),
+                    confidence: chunk.confidence,
+                    created_at_unix_ms: chunk.created_at_unix_ms,
+                    sources,
+                    content: chunk.content.to_string(),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    fn show(&self, id: u32, out: &mut dyn Write) -> anyhow::Result<()> {
+        let Some(c) = self.find(id)? else {
+            anyhow::bail!("chunk {id} not found in the enabled layers");
+        };
+        writeln!(
+            out,
+            "[{}] id={} kind={} author={} conf={:.3} created_at_unix_ms={}",
+            layer_to_str(c.layer),
+            c.id,
+            c.kind,
+            c.author,
+            c.confidence,
+            c.created_at_unix_ms
+        )?;
+        for s in &c.sources {
+            match s {
+                agentsdb_format::ChunkSource::ChunkId(v) => writeln!(out, "  source: chunk:{v}")?,
+                agentsdb_format::ChunkSource::SourceString(v) => writeln!(out, "  source: {v}")?,
+            }
+        }
+        writeln!(out)?;
+        writeln!(out, "{}", c.content)?;
+        Ok(())
+    }
+
+    fn trace(&self, id: u32, out: &mut dyn Write) -> anyhow::Result<()> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![(id, 0usize)];
+        while let Some((id, depth)) = stack.pop() {
+            let indent = "  ".repeat(depth);
+            if !seen.insert(id) {
+                writeln!(out, "{indent}{id} (cycle)")?;
+                continue;
+            }
+            let Some(c) = self.find(id)? else {
+                writeln!(out, "{indent}{id} (missing)")?;
+                continue;
+            };
+            writeln!(
+                out,
+                "{indent}[{}] {} {}: {}",
+                layer_to_str(c.layer),
+                c.id,
+                c.kind,
+                one_line(&c.content)
+            )?;
+            for s in &c.sources {
+                if let agentsdb_format::ChunkSource::SourceString(v) = s {
+                    writeln!(out, "{indent}  source: {v}")?;
+                }
+            }
+            if depth + 1 >= MAX_TRACE_DEPTH {
+                continue;
+            }
+            for s in c.sources.iter().rev() {
+                if let agentsdb_format::ChunkSource::ChunkId(v) = s {
+                    stack.push((*v, depth + 1));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn note(&mut self, content: &str, out: &mut dyn Write) -> anyhow::Result<()> {
+        if content.is_empty() {
+            anyhow::bail!("usage: note <text>");
+        }
+        let path = self.layers.local.as_ref().map_or_else(
+            || {
+                let dir = [&self.layers.base, &self.layers.user, &self.layers.delta]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|p| Path::new(p).parent().map(Path::to_path_buf))
+                    .unwrap_or_default();
+                dir.join("AGENTS.local.db")
+            },
+            PathBuf::from,
+        );
+        let dim = match self.layers.open() {
+            Ok(opened) => opened
+                .first()
+                .and_then(|(_, f)| u32::try_from(f.embedding_dim()).ok()),
+            Err(_) => None,
+        };
+        let id = agentsdb_ops::append_chunk(
+            &path,
+            "local",
+            None,
+            "note",
+            content,
+            1.0,
+            dim,
+            &[],
+            &[],
+            "agentsdb-cli",
+            env!("CARGO_PKG_VERSION"),
+        )?;
+        self.layers.local = Some(path.display().to_string());
+        writeln!(out, "Appended id={id} to {}", path.display())?;
+        Ok(())
+    }
+
+    fn list_layers(&self, out: &mut dyn Write) -> anyhow::Result<()> {
+        for layer in [LayerId::Local, LayerId::User, LayerId::Delta, LayerId::Base] {
+            let name = layer_to_str(layer);
+            match self.path_of(layer) {
+                Some(path) => writeln!(
+                    out,
+                    "{name:<6} {:<4} {path}",
+                    if self.disabled.contains(&layer) {
+                        "off"
+                    } else {
+                        "on"
+                    }
+                )?,
+                None => writeln!(out, "{name:<6} -")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+fn load_history(path: &Path) -> Vec<String> {
+    std::fs::read_to_string(path)
+        .map(|s| s.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+pub(crate) fn cmd_repl(
+    layers: LayerSet,
+    k: usize,
+    history: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    if json {
+        anyhow::bail!("JSON output (--json, --output json) is not supported for repl");
+    }
+    let history_path = history.map(PathBuf::from).or_else(default_history_path);
+    let mut repl = Repl {
+        layers,
+        disabled: HashSet::new(),
+        k,
+        history: history_path
+            .as_deref()
+            .map(load_history)
+            .unwrap_or_default(),
+        history_path,
+    };
+
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut stdout = std::io::stdout();
+    if interactive {
+        writeln!(stdout, "agentsdb repl; type `help` for commands")?;
+    }
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            write!(stdout, "agentsdb> ")?;
+            stdout.flush()?;
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line.context("read stdin")?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let line = match repl.expand(line) {
+            Ok(expanded) => {
+                if expanded != line {
+                    writeln!(stdout, "{expanded}")?;
+                }
+                expanded
+            }
+            Err(err) => {
+                eprintln!("error: {err:#}");
+                continue;
+            }
+        };
+        if line != "history" {
+            repl.record(&line);
+        }
+        match repl.execute(&line, &mut stdout) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => eprintln!("error: {err:#}"),
+        }
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use serde::Serialize;
+use std::path::Path;
+
+use agentsdb_ops::review::{DecayOptions, DecayReport, StaleChunk};
+
+/// What `agentsdb review` should do; decay runs first, then confirmations, then the listing.
+pub(crate) struct ReviewArgs<'a> {
+    pub(crate) stale: bool,
+    pub(crate) threshold: f32,
+    pub(crate) decay: Option<DecayOptions>,
+    pub(crate) confirm: &'a [u32],
+    pub(crate) confidence: f32,
+}
+
+/// A chunk re-validated with `--confirm`.
+#[derive(Serialize)]
+struct Confirmed {
+    layer: String,
+    id: u32,
+    revision: u32,
+    confidence: f32,
+}
+
+fn confirm_chunks(
+    layers: &agentsdb_query::LayerSet,
+    args: &ReviewArgs<'_>,
+) -> anyhow::Result<Vec<Confirmed>> {
+    let queue = agentsdb_ops::review::stale_queue(layers, args.threshold)?;
+    let mut out = Vec::new();
+    for &id in args.confirm {
+        let chunk = queue
+            .iter()
+            .find(|c| c.id == id && c.writable)
+            .or_else(|| queue.iter().find(|c| c.id == id));
+        let Some(chunk) = chunk else {
+            anyhow::bail!(
+                "chunk {id} is not in the review queue (confidence below {})",
+                args.threshold
+            );
+        };
+        if !chunk.writable {
+            anyhow::bail!(
+                "chunk {id} is in {}, which is not writable (local or delta)",
+                chunk.layer
+            );
+        }
+        let outcome = agentsdb_ops::review::revalidate_chunk(
+            Path::new(&chunk.layer),
+            id,
+            args.confidence,
+            "agentsdb-cli",
+            env!("CARGO_PKG_VERSION"),
+        )?;
+        out.push(Confirmed {
+            layer: chunk.layer.clone(),
+            id,
+            revision: outcome.id,
+            confidence: args.confidence,
+        });
+    }
+    Ok(out)
+}
+
+pub(crate) fn cmd_review(
+    layers: &agentsdb_query::LayerSet,
+    args: ReviewArgs<'_>,
+    json: bool,
+) -> anyhow::Result<()> {
+    if !args.stale && args.decay.is_none() && args.confirm.is_empty() {
+        anyhow::bail!("nothing to do: pass --stale, --decay or --confirm <ID>");
+    }
+    let decayed = args
+        .decay
+        .map(|options| {
+            agentsdb_ops::review::decay_confidence(
+                layers,
+                options,
+                "agentsdb-cli",
+                env!("CARGO_PKG_VERSION"),
+            )
+        })
+        .transpose()?;
+    let confirmed = confirm_chunks(layers, &args)?;
+    let stale = if args.stale {
+        Some(agentsdb_ops::review::stale_queue(layers, args.threshold)?)
+    } else {
+        None
+    };
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            decay: Option<&'a DecayReport>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            confirmed: &'a Vec<Confirmed>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            stale: Option<&'a [StaleChunk]>,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: true,
+                decay: decayed.as_ref(),
+                confirmed: &confirmed,
+                stale: stale.as_deref(),
+            })?
+        );
+        return Ok(());
+    }
+
+    if let Some(report) = &decayed {
+        for chunk in &report.decayed {
+            println!(
+                "Decayed {}:{} as {} (confidence {:.2} -> {:.2})",
+                chunk.layer, chunk.id, chunk.revision, chunk.from, chunk.to
+            );
+        }
+        for (layer, id, reason) in &report.skipped {
+            println!("Skipped {layer}:{id}: {reason}");
+        }
+        println!(
+            "Checked {} chunks: {} decayed, {} skipped",
+            report.scanned,
+            report.decayed.len(),
+            report.skipped.len()
+        );
+    }
+    for chunk in &confirmed {
+        println!(
+            "Confirmed {}:{} as {} (confidence {:.2})",
+            chunk.layer, chunk.id, chunk.revision, chunk.confidence
+        );
+    }
+    if let Some(stale) = &stale {
+        for chunk in stale {
+            println!(
+                "{}:{} [{}] confidence {:.2}{}: {}",
+                chunk.layer,
+                chunk.id,
+                chunk.kind,
+                chunk.confidence,
+                if chunk.writable { "" } else { " (read-only)" },
+                chunk.content_preview.replace('\n', " ")
+            );
+        }
+        println!(
+            "{} chunks below confidence {} need review",
+            stale.len(),
+            args.threshold
+        );
+    }
+    Ok(())
+}
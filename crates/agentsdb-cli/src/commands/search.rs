@@ -1,5 +1,10 @@
 use anyhow::Context;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 
+use agentsdb_embeddings::config::StandardLayerPaths;
+use agentsdb_embeddings::settings::{default_config_file, resolve_settings, SearchSettings};
+use agentsdb_ops::workspace::{ProjectError, Workspace};
 use agentsdb_ops::{search_layers, SearchConfig};
 use agentsdb_query::{LayerSet, SearchMode};
 
@@ -11,10 +16,15 @@ pub(crate) fn cmd_search(
     query: Option<String>,
     query_vec: Option<String>,
     query_vec_file: Option<String>,
-    k: usize,
+    k: Option<usize>,
     kinds: Vec<String>,
+    namespace: Option<String>,
+    language: Option<String>,
     use_index: bool,
-    mode: String,
+    disk_cache: bool,
+    mode: Option<String>,
+    assemble_budget: Option<usize>,
+    workspace: bool,
     json: bool,
 ) -> anyhow::Result<()> {
     // Implements the `search` command, which searches one or more layers using vector similarity.
@@ -22,6 +32,13 @@ pub(crate) fn cmd_search(
     // This function handles parsing query input (text, vector, or vector file), embedding the query,
     // and performing the search across specified layers with optional filtering and index usage.
 
+    // Flags win; otherwise fall back to the `search.*` settings.
+    let defaults = search_settings(&layers)?;
+    let k = k.unwrap_or(defaults.k);
+    let use_index = use_index || defaults.use_index;
+    let disk_cache = disk_cache || defaults.disk_cache;
+    let mode = mode.unwrap_or(defaults.mode);
+
     // Parse query_vec from JSON string or file if provided
     let query_vec_parsed = match (query_vec, query_vec_file) {
         (Some(v), None) => Some(parse_vec_json(&v)?),
@@ -51,12 +68,30 @@ pub(crate) fn cmd_search(
         query_vec: query_vec_parsed,
         k,
         kinds,
+        namespace,
+        language,
         use_index,
         mode: search_mode,
+        cache_results: false,
+        disk_cache,
     };
 
+    if workspace {
+        return search_workspace(&config, json);
+    }
+
     let results = search_layers(&layers, config).context("search")?;
 
+    if let Some(budget_tokens) = assemble_budget {
+        let context = agentsdb_ops::assemble::assemble_context(results, budget_tokens);
+        if json {
+            println!("{}", serde_json::to_string_pretty(&context)?);
+        } else {
+            print!("{}", context.to_markdown());
+        }
+        return Ok(());
+    }
+
     if json {
         // Get dimension from layers for JSON output
         let opened = layers.open().context("open layers for dimension")?;
@@ -85,6 +120,14 @@ pub(crate) fn cmd_search(
             r.chunk.author,
             r.chunk.confidence
         );
+        if let Some(identity) = r.chunk.sources.iter().find_map(|s| match s {
+            agentsdb_core::types::ProvenanceRef::SourceString(v) => {
+                v.strip_prefix(agentsdb_query::IDENTITY_SOURCE_PREFIX)
+            }
+            agentsdb_core::types::ProvenanceRef::ChunkId(_) => None,
+        }) {
+            println!("  by {identity}");
+        }
         if !r.hidden_layers.is_empty() {
             println!("  hidden_layers={:?}", r.hidden_layers);
         }
@@ -93,6 +136,72 @@ pub(crate) fn cmd_search(
     Ok(())
 }
 
+/// Searches every project in the workspace registry and attributes each hit to its project.
+fn search_workspace(config: &SearchConfig, json: bool) -> anyhow::Result<()> {
+    let registry = agentsdb_ops::workspace::registry_path()?;
+    let workspace = Workspace::load(&registry)?;
+    if workspace.projects.is_empty() {
+        anyhow::bail!(
+            "no projects in {}; register one with `agentsdb workspace add`",
+            registry.display()
+        );
+    }
+    let found = agentsdb_ops::workspace::search_workspace(&workspace, config);
+
+    if json {
+        #[derive(Serialize)]
+        struct HitOut {
+            project: String,
+            root: String,
+            #[serde(flatten)]
+            result: SearchResultJson,
+        }
+        #[derive(Serialize)]
+        struct Out {
+            k: usize,
+            results: Vec<HitOut>,
+            errors: Vec<ProjectError>,
+        }
+        let out = Out {
+            k: config.k,
+            results: found
+                .hits
+                .into_iter()
+                .map(|hit| HitOut {
+                    project: hit.project,
+                    root: hit.root.display().to_string(),
+                    result: to_search_json(hit.result),
+                })
+                .collect(),
+            errors: found.errors,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+
+    for error in &found.errors {
+        eprintln!(
+            "warning: skipped project {}: {}",
+            error.project, error.error
+        );
+    }
+    for hit in found.hits {
+        let r = hit.result;
+        println!(
+            "[{}] [{:?}] id={} score={:.6} kind={} author={:?} conf={:.3}",
+            hit.project,
+            r.layer,
+            r.chunk.id.get(),
+            r.score,
+            r.chunk.kind,
+            r.chunk.author,
+            r.chunk.confidence
+        );
+        println!("  {}", one_line(&r.chunk.content));
+    }
+    Ok(())
+}
+
 fn to_search_json(r: agentsdb_core::types::SearchResult) -> SearchResultJson {
     SearchResultJson {
         layer: layer_to_str(r.layer).to_string(),
@@ -111,3 +220,22 @@ fn to_search_json(r: agentsdb_core::types::SearchResult) -> SearchResultJson {
         content: r.chunk.content,
     }
 }
+
+/// `search.*` settings resolved against the searched layers (and `agentsdb.toml` next to them).
+fn search_settings(layers: &LayerSet) -> anyhow::Result<SearchSettings> {
+    let path = |layer: &Option<String>| layer.as_deref().map(PathBuf::from).unwrap_or_default();
+    let paths = StandardLayerPaths {
+        base: path(&layers.base),
+        user: path(&layers.user),
+        delta: path(&layers.delta),
+        local: path(&layers.local),
+    };
+    // Without a base layer there is nothing to sit next to: use the working directory's file.
+    let config_file = match layers.base {
+        Some(_) => None,
+        None => default_config_file(Path::new(".")),
+    };
+    let settings =
+        resolve_settings(&paths, config_file.as_deref()).context("resolve search settings")?;
+    Ok(settings.search())
+}
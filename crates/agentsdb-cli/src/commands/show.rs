@@ -0,0 +1,103 @@
+use serde::Serialize;
+
+use agentsdb_ops::show::{ChunkReport, CopyStatus, TraceProblem};
+
+fn status_str(status: CopyStatus) -> &'static str {
+    match status {
+        CopyStatus::Visible => "visible",
+        CopyStatus::Hidden => "hidden",
+        CopyStatus::Retracted => "retracted",
+    }
+}
+
+fn print_trace(report: &ChunkReport) {
+    println!("\nProvenance trace:");
+    for node in &report.trace {
+        let indent = "  ".repeat(node.depth + 1);
+        let problem = match node.problem {
+            Some(TraceProblem::Missing) => " (missing)",
+            Some(TraceProblem::Retracted) => " (retracted)",
+            Some(TraceProblem::Cycle) => " (cycle)",
+            None => "",
+        };
+        match (&node.layer, &node.kind, &node.content_preview) {
+            (Some(layer), Some(kind), Some(preview)) => {
+                println!("{indent}{layer}:{}{problem}\t[{kind}] {preview}", node.id);
+            }
+            _ => println!("{indent}chunk:{}{problem}", node.id),
+        }
+        for source in &node.sources {
+            println!("{indent}  <- {source}");
+        }
+    }
+}
+
+pub(crate) fn cmd_show(
+    layers: &agentsdb_query::LayerSet,
+    id: u32,
+    json: bool,
+) -> anyhow::Result<()> {
+    let report = agentsdb_ops::show::show_chunk(layers, id)?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            #[serde(flatten)]
+            report: &'a ChunkReport,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: true,
+                report: &report,
+            })?
+        );
+        return Ok(());
+    }
+
+    // The winning copy, or the highest-precedence one when every copy is retracted.
+    let main = report
+        .copies
+        .iter()
+        .find(|c| c.status == CopyStatus::Visible)
+        .or_else(|| report.copies.first());
+    if let Some(c) = main {
+        println!("Chunk {} [{}]", report.id, c.kind);
+        match c.identity.as_deref() {
+            Some(identity) => println!(
+                "  author: {} ({identity})  confidence: {}",
+                c.author, c.confidence
+            ),
+            None => println!("  author: {}  confidence: {}", c.author, c.confidence),
+        }
+        println!("  created_at_unix_ms: {}", c.created_at_unix_ms);
+        println!("  embedding norm: {:.4}", c.embedding_norm);
+        if !c.sources.is_empty() {
+            println!("  sources: {}", c.sources.join(", "));
+        }
+        println!("\n{}\n", c.content.trim_end());
+    }
+
+    match (&report.winner, &report.retracted_by) {
+        (Some(winner), _) => println!("Wins precedence: {winner}"),
+        (None, Some(by)) => println!("Retracted by a tombstone in {by}"),
+        (None, None) => println!("No visible copy"),
+    }
+    if let Some(by) = report.superseded_by {
+        println!("Superseded by chunk {by}");
+    }
+    println!("\nLayers:");
+    for c in &report.copies {
+        println!(
+            "  {}\t{}\t{}\t[{}] {}",
+            c.layer,
+            status_str(c.status),
+            c.path,
+            c.kind,
+            agentsdb_ops::util::truncate_preview(&c.content, 60)
+        );
+    }
+    print_trace(&report);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+use agentsdb_embeddings::signing::LayerSigningKey;
+
+pub(crate) fn cmd_sign(
+    path: Option<&str>,
+    key: Option<&str>,
+    generate_key: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let key = match (key, generate_key) {
+        (_, Some(key_path)) => {
+            let key = LayerSigningKey::generate()?;
+            write_new_key(Path::new(key_path), &key)?;
+            if !json {
+                println!("Wrote signing key to {key_path}");
+                println!("Public key: {}", key.public_key());
+            }
+            key
+        }
+        (Some(key_path), None) => LayerSigningKey::from_file(Path::new(key_path))?,
+        (None, None) => LayerSigningKey::from_env()?.context(
+            "no signing key: pass --key FILE or set AGENTSDB_SIGNING_KEY / AGENTSDB_SIGNING_KEY_FILE",
+        )?,
+    };
+
+    let signature = match path {
+        Some(path) => Some(sign_path(path, &key)?),
+        None => None,
+    };
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            public_key: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            path: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            content_sha256: Option<String>,
+        }
+        let out = Out {
+            ok: true,
+            public_key: key.public_key(),
+            path,
+            content_sha256: signature.map(|s| s.content_sha256),
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else if let (Some(path), Some(signature)) = (path, signature) {
+        println!(
+            "Signed {path} (content sha256 {}, key {})",
+            signature.content_sha256, signature.public_key
+        );
+    }
+    Ok(())
+}
+
+/// Signs the layer at `path` with `key`. Signing is a release step, so any standard layer
+/// (including `AGENTS.db`) may be signed.
+pub(crate) fn sign_path(
+    path: &str,
+    key: &LayerSigningKey,
+) -> anyhow::Result<agentsdb_embeddings::layer_metadata::LayerSignature> {
+    let policy = agentsdb_format::WritePolicy::standard()
+        .allow_user()
+        .allow_base();
+    agentsdb_ops::signing::sign_layer(Path::new(path), key, &policy)
+        .with_context(|| format!("sign {path}"))
+}
+
+/// Writes `key` to a new file at `path`, readable only by its owner where the platform allows.
+fn write_new_key(path: &Path, key: &LayerSigningKey) -> anyhow::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("create {}", path.display()))?;
+    writeln!(file, "{}", key.to_hex()).with_context(|| format!("write {}", path.display()))
+}
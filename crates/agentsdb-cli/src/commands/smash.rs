@@ -4,26 +4,73 @@ use std::path::Path;
 use text_splitter::{ChunkConfig, MarkdownSplitter, TextSplitter};
 
 use agentsdb_embeddings::config::{get_immutable_embedding_options, standard_layer_paths_for_dir};
+use agentsdb_embeddings::embedder::EmbedPurpose;
 use agentsdb_format::{LayerFile, read_all_chunks, schema_of};
 
+/// A chunk over the limit and the pieces it is split into.
+#[derive(Debug, Clone, Serialize)]
+struct SplitChange {
+    id: u32,
+    kind: String,
+    len: usize,
+    piece_lens: Vec<usize>,
+}
+
+/// Parses an `--only` filter. The only supported key is `kinds=<kind>[,<kind>...]`.
+fn parse_only_kinds(only: &str) -> anyhow::Result<Vec<String>> {
+    let Some(kinds) = only.trim().strip_prefix("kinds=") else {
+        anyhow::bail!("invalid --only filter {only:?} (expected kinds=<kind>[,<kind>...])");
+    };
+    let kinds: Vec<String> = kinds
+        .split(',')
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty())
+        .collect();
+    if kinds.is_empty() {
+        anyhow::bail!("--only kinds= needs at least one kind");
+    }
+    Ok(kinds)
+}
+
 /// Execute the smash command: break down large chunks into smaller pieces.
-/// This command is ALWAYS destructive and replaces the entire layer.
+/// This command is ALWAYS destructive and replaces the entire layer, unless `dry_run` is set,
+/// in which case it only reports which chunks would be split.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn cmd_smash(
     dir: &str,
     layers_csv: &str,
     limit: usize,
     allow_base: bool,
+    only: Option<&str>,
+    exclude_layers: &[String],
+    dry_run: bool,
     json: bool,
 ) -> anyhow::Result<()> {
     let dir_path = Path::new(dir);
     let standard_paths = standard_layer_paths_for_dir(dir_path);
 
+    let only_kinds = only.map(parse_only_kinds).transpose()?;
+
+    // Validate excluded layers
+    for layer in exclude_layers {
+        if !["base", "user", "delta", "local"].contains(&layer.as_str()) {
+            anyhow::bail!(
+                "invalid --exclude-layer: {:?} (valid: base, user, delta, local)",
+                layer
+            );
+        }
+    }
+
     // Parse which layers to smash
-    let requested_layers: Vec<&str> = layers_csv.split(',').map(|s| s.trim()).collect();
+    let requested_layers: Vec<&str> = layers_csv
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !exclude_layers.iter().any(|e| e == s))
+        .collect();
 
     // Check if base layer is requested
     let smash_base = requested_layers.contains(&"base");
-    if smash_base && !allow_base {
+    if smash_base && !allow_base && !dry_run {
         anyhow::bail!(
             "refusing to smash base layer (AGENTS.db) without --allow-base flag"
         );
@@ -43,10 +90,15 @@ pub(crate) fn cmd_smash(
     let options = get_immutable_embedding_options(dir_path)
         .context("get immutable embedding options from AGENTS.db")?;
 
-    let mut smashed_layers = Vec::new();
+    let mut smashed_layers: Vec<(String, usize, usize, Vec<SplitChange>)> = Vec::new();
     let mut total_split_count = 0usize;
     let mut total_chunk_count = 0usize;
 
+    let mut policy = agentsdb_format::WritePolicy::standard().allow_user();
+    if allow_base {
+        policy = policy.allow_base();
+    }
+
     // Process each requested layer
     for layer_name in &requested_layers {
         let layer_path = match *layer_name {
@@ -65,21 +117,11 @@ pub(crate) fn cmd_smash(
             continue;
         }
 
-        // Check writability based on layer type
-        match *layer_name {
-            "base" => {
-                if !allow_base {
-                    // This should have been caught earlier, but double-check
-                    anyhow::bail!("internal error: attempting to smash base without --allow-base");
-                }
-                agentsdb_format::ensure_writable_layer_path_allow_base(layer_path)
-                    .with_context(|| format!("verify {} is writable", layer_path.display()))?;
-            }
-            "user" | "delta" | "local" => {
-                agentsdb_format::ensure_writable_layer_path_allow_user(layer_path)
-                    .with_context(|| format!("verify {} is writable", layer_path.display()))?;
-            }
-            _ => unreachable!(),
+        // Check writability; the policy only lets base through with --allow-base
+        if !dry_run {
+            policy
+                .check(layer_path)
+                .with_context(|| format!("verify {} is writable", layer_path.display()))?;
         }
 
         // Open the layer file
@@ -92,34 +134,86 @@ pub(crate) fn cmd_smash(
         let chunks = read_all_chunks(&file)
             .with_context(|| format!("read chunks from {}", layer_path.display()))?;
 
-        let embedder = options
-            .clone()
-            .into_embedder(schema.dim as usize)
-            .context("create embedder from options")?;
-
-        // Process chunks and split large ones
-        let mut new_chunks = Vec::new();
-        let mut split_count = 0;
-
         // Create text splitters
         let markdown_splitter = MarkdownSplitter::new(ChunkConfig::new(limit));
         let text_splitter = TextSplitter::new(ChunkConfig::new(limit));
 
-        for chunk in chunks {
-            if chunk.content.len() > limit {
-                split_count += 1;
-
+        // Plan the splits first so a dry run can report them without embedding anything
+        let mut changes = Vec::new();
+        let planned: Vec<(agentsdb_format::ChunkInput, Option<Vec<String>>)> = chunks
+            .into_iter()
+            .map(|chunk| {
+                let selected = only_kinds
+                    .as_ref()
+                    .is_none_or(|kinds| kinds.iter().any(|k| *k == chunk.kind));
+                if !selected || chunk.content.len() <= limit {
+                    return (chunk, None);
+                }
                 // Split the chunk based on file type
                 let splits: Vec<String> = if is_markdown(&chunk.content) {
                     markdown_splitter.chunks(&chunk.content).map(|s| s.to_string()).collect()
                 } else {
                     text_splitter.chunks(&chunk.content).map(|s| s.to_string()).collect()
                 };
+                changes.push(SplitChange {
+                    id: chunk.id,
+                    kind: chunk.kind.clone(),
+                    len: chunk.content.len(),
+                    piece_lens: splits.iter().map(String::len).collect(),
+                });
+                (chunk, Some(splits))
+            })
+            .collect();
+        let split_count = changes.len();
+
+        if dry_run {
+            let total = planned
+                .iter()
+                .map(|(_, splits)| splits.as_ref().map_or(1, Vec::len))
+                .sum::<usize>();
+            if !json {
+                println!(
+                    "{}: would smash {} large chunks into {} total chunks",
+                    layer_path.display(),
+                    split_count,
+                    total
+                );
+                for change in &changes {
+                    println!(
+                        "  ~ id={} kind={} {} chars -> {} chunks ({})",
+                        change.id,
+                        change.kind,
+                        change.len,
+                        change.piece_lens.len(),
+                        change
+                            .piece_lens
+                            .iter()
+                            .map(|n| n.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+            }
+            smashed_layers.push((layer_path.to_string_lossy().into_owned(), split_count, total, changes));
+            total_split_count += split_count;
+            total_chunk_count += total;
+            continue;
+        }
 
+        let embedder = options
+            .clone()
+            .into_embedder(schema.dim as usize)
+            .context("create embedder from options")?;
+
+        // Process chunks and split large ones
+        let mut new_chunks = Vec::new();
+
+        for (chunk, splits) in planned {
+            if let Some(splits) = splits {
                 // Add each split as a new chunk
                 for (idx, split_content) in splits.into_iter().enumerate() {
                     // Generate embeddings for the split content
-                    let embeddings = embedder.embed(&[split_content.clone()])
+                    let embeddings = embedder.embed_for(EmbedPurpose::Document, std::slice::from_ref(&split_content))
                         .context("embed chunk content")?;
                     let embedding = embeddings.into_iter().next()
                         .ok_or_else(|| anyhow::anyhow!("embedder returned empty results"))?;
@@ -140,7 +234,7 @@ pub(crate) fn cmd_smash(
                 }
             } else {
                 // Keep chunk as-is but still need to create ChunkInput
-                let embeddings = embedder.embed(&[chunk.content.clone()])
+                let embeddings = embedder.embed_for(EmbedPurpose::Document, std::slice::from_ref(&chunk.content))
                     .context("embed chunk content")?;
                 let embedding = embeddings.into_iter().next()
                     .ok_or_else(|| anyhow::anyhow!("embedder returned empty results"))?;
@@ -170,7 +264,7 @@ pub(crate) fn cmd_smash(
         )
         .with_context(|| format!("write layer {}", layer_path.display()))?;
 
-        smashed_layers.push((layer_path.to_string_lossy().into_owned(), split_count, new_chunks.len()));
+        smashed_layers.push((layer_path.to_string_lossy().into_owned(), split_count, new_chunks.len(), changes));
         total_split_count += split_count;
         total_chunk_count += new_chunks.len();
 
@@ -190,10 +284,12 @@ pub(crate) fn cmd_smash(
             layer: String,
             split_count: usize,
             total_chunks: usize,
+            changes: Vec<SplitChange>,
         }
         #[derive(Serialize)]
         struct Out {
             ok: bool,
+            dry_run: bool,
             layers: Vec<LayerResult>,
             total_split_count: usize,
             total_chunk_count: usize,
@@ -202,10 +298,12 @@ pub(crate) fn cmd_smash(
             "{}",
             serde_json::to_string_pretty(&Out {
                 ok: true,
-                layers: smashed_layers.into_iter().map(|(layer, split_count, total_chunks)| LayerResult {
+                dry_run,
+                layers: smashed_layers.into_iter().map(|(layer, split_count, total_chunks, changes)| LayerResult {
                     layer,
                     split_count,
                     total_chunks,
+                    changes,
                 }).collect(),
                 total_split_count,
                 total_chunk_count,
@@ -229,6 +327,16 @@ fn is_markdown(content: &str) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_only_kinds_accepts_kind_lists() {
+        assert_eq!(
+            parse_only_kinds("kinds=note, canonical").unwrap(),
+            vec!["note".to_string(), "canonical".to_string()]
+        );
+        assert!(parse_only_kinds("kinds=").is_err());
+        assert!(parse_only_kinds("authors=human").is_err());
+    }
+
     #[test]
     fn test_is_markdown() {
         assert!(is_markdown("# Heading\nSome text"));
@@ -0,0 +1,74 @@
+use agentsdb_ops::stats::{Bucket, Summary};
+
+pub(crate) fn cmd_stats(layers: &agentsdb_query::LayerSet, json: bool) -> anyhow::Result<()> {
+    // Highest precedence first, like search results.
+    let paths: Vec<String> = [&layers.local, &layers.user, &layers.delta, &layers.base]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    if paths.is_empty() {
+        anyhow::bail!("no layers found (pass --base/--user/--delta/--local)");
+    }
+    let report = agentsdb_ops::stats::collect_stats(&paths)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    for stats in &report.layers {
+        println!("{}", stats.path);
+        println!(
+            "  chunks: {} ({} live, {} tombstones, {} exact duplicates)",
+            stats.chunk_count, stats.live_chunks, stats.tombstones, stats.exact_duplicates
+        );
+        print_counts("kinds", &stats.by_kind);
+        print_counts("authors", &stats.by_author);
+        print_summary("confidence", stats.confidence.as_ref());
+        print_histogram("confidence <", &stats.confidence_histogram);
+        print_histogram("age <", &stats.age_histogram);
+        print_summary("embedding norm", stats.embedding_norm.as_ref());
+        println!(
+            "  embeddings: dim {}, {} zero vectors",
+            stats.embedding_dim, stats.zero_embeddings
+        );
+        println!(
+            "  bytes: {} total = {} content + {} embeddings + {} overhead",
+            stats.file_length_bytes,
+            stats.content_bytes,
+            stats.embedding_bytes,
+            stats.overhead_bytes
+        );
+    }
+    println!(
+        "Cross-layer duplicates (same content in several layers): {}",
+        report.cross_layer_duplicates
+    );
+    Ok(())
+}
+
+fn print_counts(label: &str, counts: &std::collections::BTreeMap<String, u64>) {
+    let mut counts: Vec<_> = counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let line: Vec<String> = counts.iter().map(|(k, n)| format!("{k}={n}")).collect();
+    println!("  {label}: {}", line.join(", "));
+}
+
+fn print_summary(label: &str, summary: Option<&Summary>) {
+    match summary {
+        Some(s) => println!(
+            "  {label}: min {:.3}, mean {:.3}, max {:.3}",
+            s.min, s.mean, s.max
+        ),
+        None => println!("  {label}: -"),
+    }
+}
+
+fn print_histogram(label: &str, buckets: &[Bucket]) {
+    let line: Vec<String> = buckets
+        .iter()
+        .map(|b| format!("{}: {}", b.le, b.count))
+        .collect();
+    println!("  {label} {}", line.join(" | "));
+}
@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use agentsdb_ops::sync::{SyncAction, SyncOutcome};
+
+pub(crate) fn cmd_sync_push(
+    dir: &str,
+    layer: &str,
+    remote: Option<&str>,
+    force: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let outcome = agentsdb_ops::sync::push(Path::new(dir), layer, remote, force)?;
+    report(&outcome, json)
+}
+
+pub(crate) fn cmd_sync_pull(
+    dir: &str,
+    layer: &str,
+    remote: Option<&str>,
+    force: bool,
+    merge: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let outcome = agentsdb_ops::sync::pull(Path::new(dir), layer, remote, force, merge)?;
+    report(&outcome, json)?;
+    if outcome.unresolved_conflicts > 0 {
+        anyhow::bail!(
+            "{} conflicting chunks were kept in both versions; resolve them, then push",
+            outcome.unresolved_conflicts
+        );
+    }
+    Ok(())
+}
+
+fn report(outcome: &SyncOutcome, json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(outcome)?);
+        return Ok(());
+    }
+    let sha = outcome.sha256.get(..12).unwrap_or(&outcome.sha256);
+    match outcome.action {
+        SyncAction::UpToDate => println!(
+            "{} is up to date with {} ({sha})",
+            outcome.layer, outcome.remote
+        ),
+        SyncAction::Pushed => println!(
+            "Pushed {} to {} ({sha}, {} bytes uploaded)",
+            outcome.layer, outcome.remote, outcome.bytes_transferred
+        ),
+        SyncAction::Pulled => println!(
+            "Pulled {} from {} ({sha}, {} bytes downloaded)",
+            outcome.layer, outcome.remote, outcome.bytes_transferred
+        ),
+        SyncAction::Merged => println!(
+            "Merged {} from {} ({sha}, {} bytes downloaded, {} unresolved conflicts)",
+            outcome.layer, outcome.remote, outcome.bytes_transferred, outcome.unresolved_conflicts
+        ),
+    }
+    Ok(())
+}
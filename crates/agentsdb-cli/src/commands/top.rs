@@ -0,0 +1,85 @@
+use serde::Serialize;
+use std::path::Path;
+
+use agentsdb_ops::usage::{ChunkUsage, UsageOptions};
+
+fn print_chunk(chunk: &ChunkUsage, with_count: bool) {
+    let count = if with_count {
+        format!("{}x\t", chunk.retrievals)
+    } else {
+        String::new()
+    };
+    match (&chunk.kind, &chunk.content_preview) {
+        (Some(kind), Some(preview)) => {
+            println!("  {count}{}:{}\t[{kind}] {preview}", chunk.layer, chunk.id)
+        }
+        _ => println!("  {count}{}:{}\t(no longer visible)", chunk.layer, chunk.id),
+    }
+}
+
+pub(crate) fn cmd_top(
+    layers: &agentsdb_query::LayerSet,
+    audit_log: &str,
+    options: UsageOptions,
+    json: bool,
+) -> anyhow::Result<()> {
+    let (records, malformed) = agentsdb_ops::usage::read_audit_log(Path::new(audit_log))?;
+    let report = agentsdb_ops::usage::usage_report(&records, layers, options)?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            audit_log: &'a str,
+            malformed_lines: usize,
+            #[serde(flatten)]
+            report: &'a agentsdb_ops::usage::UsageReport,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: true,
+                audit_log,
+                malformed_lines: malformed,
+                report: &report,
+            })?
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{audit_log}: {} records ({} reads, {} writes)",
+        report.records, report.reads, report.writes
+    );
+    if malformed > 0 {
+        println!("Skipped {malformed} malformed lines");
+    }
+    if report.reads == 0 {
+        println!(
+            "No retrievals logged; run `agentsdb serve --audit-log {audit_log} --audit-reads` to record them."
+        );
+    } else {
+        println!("\nMost retrieved:");
+        for chunk in &report.most_retrieved {
+            print_chunk(chunk, true);
+        }
+        println!(
+            "\nNever retrieved ({} total, oldest first):",
+            report.never_retrieved_total
+        );
+        for chunk in &report.never_retrieved {
+            print_chunk(chunk, false);
+        }
+        println!("\nTop query terms:");
+        for term in &report.top_terms {
+            println!("  {}\t{}", term.count, term.term);
+        }
+    }
+    if !report.writes_by_author.is_empty() {
+        println!("\nWrites per author:");
+        for w in &report.writes_by_author {
+            println!("  {}\t{}\t{}", w.period, w.actor, w.writes);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use serde::Serialize;
+use std::path::Path;
+
+use agentsdb_ops::journal::{Journal, OperationKind};
+
+fn list_operations(dir: &Path, json: bool) -> anyhow::Result<()> {
+    let journal = Journal::load(dir)?;
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            operations: &'a [agentsdb_ops::journal::Operation],
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: true,
+                operations: &journal.operations,
+            })?
+        );
+        return Ok(());
+    }
+    if journal.operations.is_empty() {
+        println!(
+            "No operations recorded in {}",
+            Journal::path_for(dir).display()
+        );
+        return Ok(());
+    }
+    for op in &journal.operations {
+        let what = match (op.kind, &op.from) {
+            (OperationKind::Promote, Some(from)) => format!("promote {from} -> {}", op.layer),
+            (OperationKind::Promote, None) => format!("promote -> {}", op.layer),
+            (OperationKind::Import, _) => format!("import -> {}", op.layer),
+        };
+        println!(
+            "{}\t{what}\t{} chunks{}",
+            op.id,
+            op.chunk_ids.len(),
+            if op.undone_at_unix_ms.is_some() {
+                " (undone)"
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_undo(
+    dir: &str,
+    op_id: Option<u32>,
+    allow_base: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let dir = Path::new(dir);
+    let Some(op_id) = op_id else {
+        return list_operations(dir, json);
+    };
+    let outcome = agentsdb_ops::journal::undo_operation(dir, op_id, allow_base)?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            #[serde(flatten)]
+            outcome: &'a agentsdb_ops::journal::UndoOutcome,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: true,
+                outcome: &outcome,
+            })?
+        );
+        return Ok(());
+    }
+
+    println!(
+        "Undid operation {op_id}: retracted {} chunks in {}",
+        outcome.retracted.len(),
+        outcome.layer
+    );
+    if let Some(to) = &outcome.restored_to {
+        println!("Restored {} chunks to {to}", outcome.restored.len());
+    }
+    Ok(())
+}
@@ -1,10 +1,13 @@
-use crate::types::ValidateJson;
+use crate::types::{DirectoryValidateJson, LayerValidateJson, ValidateJson};
 use anyhow::Context;
+use serde::Serialize;
 use std::path::Path;
 
 use agentsdb_embeddings::config::{
-    roll_up_embedding_options_from_paths, standard_layer_paths_for_dir,
+    get_immutable_embedding_options, roll_up_embedding_options_from_paths,
+    standard_layer_paths_for_dir,
 };
+use agentsdb_ops::drift::DriftReport;
 
 /// Validates a single layer file for format correctness and optionally checks embedding alignment.
 fn validate_single_file(
@@ -119,32 +122,9 @@ fn validate_directory(dir: &Path, json: bool) -> anyhow::Result<()> {
     }
 
     if json {
-        #[derive(serde::Serialize)]
-        struct DirectoryValidateJson {
-            ok: bool,
-            dir: String,
-            expected_dim: Option<usize>,
-            layers: Vec<LayerValidation>,
-        }
-
-        #[derive(serde::Serialize)]
-        struct LayerValidation {
-            layer: String,
-            path: String,
-            ok: bool,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            error: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            warnings: Option<Vec<String>>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            schema_dim: Option<u32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            options_dim: Option<u32>,
-        }
-
         let layer_validations: Vec<_> = results
             .iter()
-            .map(|(name, result)| LayerValidation {
+            .map(|(name, result)| LayerValidateJson {
                 layer: name.to_string(),
                 path: result.path.clone(),
                 ok: result.ok,
@@ -251,3 +231,137 @@ pub(crate) fn cmd_validate(path: &str, json: bool) -> anyhow::Result<()> {
         Ok(())
     }
 }
+
+/// Implements `validate --embedding-drift`: compares the embedding profiles of the layers in the
+/// directory PATH names (or holds PATH) and lists rows embedded with another profile than their
+/// layer's. With `fix`, those rows are re-embedded with the configured embedder first.
+pub(crate) fn cmd_validate_drift(
+    path: &str,
+    fix: bool,
+    allow_base: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let path_obj = Path::new(path);
+    let dir = if path_obj.is_dir() {
+        path_obj
+    } else {
+        path_obj
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."))
+    };
+
+    let mut reembedded = Vec::new();
+    if fix {
+        let report = agentsdb_ops::drift::drift_report(dir)?;
+        let drifted: Vec<_> = report
+            .layers
+            .iter()
+            .filter(|l| !l.mixed_rows.is_empty())
+            .collect();
+        if !allow_base && drifted.iter().any(|l| l.layer == "base") {
+            anyhow::bail!(
+                "refusing to re-embed rows of the base layer (AGENTS.db) without --allow-base"
+            );
+        }
+        if !drifted.is_empty() {
+            let options = get_immutable_embedding_options(dir)
+                .context("get immutable embedding options from AGENTS.db")?;
+            let embedder = options
+                .clone()
+                .into_embedder(options.dim.unwrap_or(128))
+                .context("create embedder from options")?;
+            let mut policy = agentsdb_format::WritePolicy::standard().allow_user();
+            if allow_base {
+                policy = policy.allow_base();
+            }
+            for layer in drifted {
+                let ids = agentsdb_ops::drift::reembed_drifted(
+                    Path::new(&layer.path),
+                    embedder.as_ref(),
+                    &policy,
+                )?;
+                reembedded.push(ReembeddedLayer {
+                    layer: layer.layer.clone(),
+                    ids,
+                });
+            }
+        }
+    }
+
+    let report = agentsdb_ops::drift::drift_report(dir)?;
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            #[serde(flatten)]
+            report: &'a DriftReport,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            reembedded: Vec<ReembeddedLayer>,
+        }
+        let out = Out {
+            report: &report,
+            reembedded,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        if !report.ok {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    for fixed in &reembedded {
+        println!(
+            "Re-embedded {} drifted rows in {}",
+            fixed.ids.len(),
+            fixed.layer
+        );
+    }
+    println!("Embedding profiles in directory: {}", report.dir);
+    println!();
+    for layer in &report.layers {
+        let ok = !layer.differs_from_reference && layer.mixed_rows.is_empty();
+        let profile = match (&layer.profile, &layer.fingerprint) {
+            (Some(profile), Some(fingerprint)) => format!(
+                "{} [{fingerprint}]",
+                agentsdb_ops::drift::describe_profile(profile)
+            ),
+            _ => "no embedding profile recorded".to_string(),
+        };
+        let mark = if ok { "✓ " } else { "⚠ " };
+        println!("{mark} {}: {} ({profile})", layer.layer, layer.path);
+        if layer.differs_from_reference {
+            println!(
+                "   WARNING: profile differs from {}; re-embed the layer with `agentsdb reembed --layers {}`",
+                report.reference.as_deref().unwrap_or("the other layers"),
+                layer.layer
+            );
+        }
+        for (fingerprint, ids) in &layer.mixed_rows {
+            let ids: Vec<String> = ids.iter().map(u32::to_string).collect();
+            println!(
+                "   WARNING: {} rows embedded with profile {fingerprint}: {}",
+                ids.len(),
+                ids.join(", ")
+            );
+        }
+    }
+    println!();
+
+    if report.ok {
+        println!("All rows match their layer's embedding profile");
+        return Ok(());
+    }
+    if report.layers.iter().any(|l| !l.mixed_rows.is_empty()) {
+        println!(
+            "Re-embed the drifted rows with `agentsdb validate --embedding-drift --fix {path}`"
+        );
+    }
+    anyhow::bail!("Embedding drift found")
+}
+
+/// Rows `validate --embedding-drift --fix` re-embedded in one layer.
+#[derive(Serialize)]
+struct ReembeddedLayer {
+    layer: String,
+    ids: Vec<u32>,
+}
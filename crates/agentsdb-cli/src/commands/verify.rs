@@ -0,0 +1,72 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::path::Path;
+
+use agentsdb_embeddings::signing::{SignatureStatus, TrustMode};
+use agentsdb_ops::signing::SignatureReport;
+
+pub(crate) fn cmd_verify(
+    layers: &agentsdb_query::LayerSet,
+    signature: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    if !signature {
+        anyhow::bail!("nothing to verify: pass --signature (or see `agentsdb verify-provenance`)");
+    }
+    let dir = layers
+        .base
+        .as_deref()
+        .or(layers.user.as_deref())
+        .or(layers.delta.as_deref())
+        .or(layers.local.as_deref())
+        .and_then(|p| Path::new(p).parent())
+        .unwrap_or_else(|| Path::new("."));
+    let trust = agentsdb_embeddings::settings::trust_policy(dir).context("read trust settings")?;
+    let reports = agentsdb_ops::signing::verify_signatures(layers, &trust)?;
+    let failed = reports
+        .iter()
+        .filter(|r| r.refused || matches!(r.status, SignatureStatus::Invalid { .. }))
+        .count();
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            trust_mode: TrustMode,
+            layers: &'a [SignatureReport],
+        }
+        let out = Out {
+            ok: failed == 0,
+            trust_mode: trust.mode,
+            layers: &reports,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        for report in &reports {
+            println!("{}", describe(report));
+        }
+        println!(
+            "Checked {} layers (trust mode: {}): {failed} failed",
+            reports.len(),
+            trust.mode
+        );
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{failed} layers failed signature verification");
+    }
+    Ok(())
+}
+
+fn describe(report: &SignatureReport) -> String {
+    let status = match &report.status {
+        SignatureStatus::Unsigned => "unsigned".to_string(),
+        SignatureStatus::Valid { public_key } if report.trusted => {
+            format!("signed by trusted key {public_key}")
+        }
+        SignatureStatus::Valid { public_key } => format!("signed by untrusted key {public_key}"),
+        SignatureStatus::Invalid { reason, .. } => format!("INVALID: {reason}"),
+    };
+    let refused = if report.refused { " (refused)" } else { "" };
+    format!("{} {}: {status}{refused}", report.layer, report.path)
+}
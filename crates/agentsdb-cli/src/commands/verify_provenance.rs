@@ -0,0 +1,103 @@
+use serde::Serialize;
+
+use agentsdb_ops::provenance::{
+    ProvenanceFix, ProvenanceIssue, ProvenanceProblem, ProvenanceReport,
+};
+
+pub(crate) fn cmd_verify_provenance(
+    layers: &agentsdb_query::LayerSet,
+    fix: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let (fixed, report) = if fix {
+        let (fixed, report) = agentsdb_ops::provenance::fix_provenance(
+            layers,
+            "agentsdb-cli",
+            env!("CARGO_PKG_VERSION"),
+        )?;
+        (Some(fixed), report)
+    } else {
+        (None, agentsdb_ops::provenance::verify_provenance(layers)?)
+    };
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            #[serde(flatten)]
+            report: &'a ProvenanceReport,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            fix: Option<&'a ProvenanceFix>,
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Out {
+                ok: report.issues.is_empty(),
+                report: &report,
+                fix: fixed.as_ref(),
+            })?
+        );
+    } else {
+        if let Some(fixed) = &fixed {
+            for chunk in &fixed.relinked {
+                let relinks: Vec<String> = chunk
+                    .relinks
+                    .iter()
+                    .map(|(old, new)| match new {
+                        Some(new) => format!("{old} -> {new}"),
+                        None => format!("{old} -> broken-source:{old}"),
+                    })
+                    .collect();
+                println!(
+                    "Relinked {}:{} as {} ({})",
+                    chunk.layer,
+                    chunk.id,
+                    chunk.revision,
+                    relinks.join(", ")
+                );
+            }
+            for (layer, id, reason) in &fixed.skipped {
+                println!("Skipped {layer}:{id}: {reason}");
+            }
+        }
+        for issue in &report.issues {
+            println!("{}", describe(issue));
+        }
+        println!(
+            "Checked {} chunk-id sources in {} chunks: {} issues",
+            report.references,
+            report.scanned,
+            report.issues.len()
+        );
+    }
+
+    if !report.issues.is_empty() {
+        anyhow::bail!("found {} provenance issues", report.issues.len());
+    }
+    Ok(())
+}
+
+fn describe(issue: &ProvenanceIssue) -> String {
+    let at = format!("{}:{}", issue.layer, issue.id);
+    match issue.problem {
+        ProvenanceProblem::Dangling => {
+            format!("{at}: source {} does not exist", issue.source_id)
+        }
+        ProvenanceProblem::Retracted => match issue.successor {
+            Some(successor) => format!(
+                "{at}: source {} is retracted (superseded by {successor})",
+                issue.source_id
+            ),
+            None => format!("{at}: source {} is retracted", issue.source_id),
+        },
+        ProvenanceProblem::Cycle => {
+            let cycle: Vec<String> = issue.cycle.iter().map(u32::to_string).collect();
+            format!(
+                "{at}: source {} closes a cycle ({} -> {})",
+                issue.source_id,
+                cycle.join(" -> "),
+                issue.source_id
+            )
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::commands::ingest::{ingest_layer, IngestConfig, IngestSummary};
+use crate::util::collect_files_matching;
+
+/// Size and modification time of every watched file.
+type Snapshot = BTreeMap<PathBuf, (u64, Option<SystemTime>)>;
+
+fn snapshot(config: &IngestConfig) -> anyhow::Result<Snapshot> {
+    let root = Path::new(&config.root);
+    let mut out = Snapshot::new();
+    for rel in collect_files_matching(root, &config.includes(), &config.excludes)? {
+        // A file removed between listing and stat simply drops out of this snapshot.
+        if let Ok(meta) = std::fs::metadata(root.join(&rel)) {
+            out.insert(rel, (meta.len(), meta.modified().ok()));
+        }
+    }
+    Ok(out)
+}
+
+pub(crate) fn cmd_watch(
+    config: &IngestConfig,
+    interval: Duration,
+    debounce: Duration,
+    json: bool,
+) -> anyhow::Result<()> {
+    // Implements the `watch` command: re-ingests whenever the watched files change, until
+    // SIGINT/SIGTERM.
+    let (stop_tx, stop_rx) = std::sync::mpsc::channel::<()>();
+    ctrlc::set_handler(move || {
+        let _ = stop_tx.send(());
+    })
+    .context("install signal handler")?;
+    if !json {
+        eprintln!(
+            "Watching {} for changes (writing {}); press Ctrl-C to stop",
+            config.root, config.out
+        );
+    }
+    watch_until(
+        config,
+        interval,
+        debounce,
+        &stop_rx,
+        |result| match result {
+            Ok(summary) if json => match serde_json::to_string(summary) {
+                Ok(line) => println!("{line}"),
+                Err(e) => tracing::error!("watch: {e}"),
+            },
+            Ok(summary) if summary.written => println!(
+                "Updated {} ({} files, {} chunks: {} embedded, {} files removed)",
+                summary.out,
+                summary.files,
+                summary.chunks,
+                summary.embedded_chunks,
+                summary.removed_files
+            ),
+            Ok(summary) => println!("{} is up to date", summary.out),
+            Err(e) => tracing::error!("watch: ingest failed: {e:#}"),
+        },
+    );
+    if !json {
+        eprintln!("Watch: stopped");
+    }
+    Ok(())
+}
+
+/// Ingests once, then polls the watched files every `interval` and re-ingests once they
+/// have been quiet for `debounce` after a change. Runs until `stop` receives a message or
+/// is disconnected; a failed run is reported and retried on the next change.
+pub(crate) fn watch_until(
+    config: &IngestConfig,
+    interval: Duration,
+    debounce: Duration,
+    stop: &Receiver<()>,
+    mut on_run: impl FnMut(&anyhow::Result<IngestSummary>),
+) {
+    // Snapshot before the first run so edits made while it ingests are picked up.
+    let mut last = snapshot(config).unwrap_or_default();
+    on_run(&ingest_layer(config));
+    let mut changed_at: Option<Instant> = None;
+    while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(interval) {
+        let current = match snapshot(config) {
+            Ok(current) => current,
+            Err(e) => {
+                on_run(&Err(e));
+                continue;
+            }
+        };
+        if current != last {
+            last = current;
+            changed_at = Some(Instant::now());
+            continue;
+        }
+        if changed_at.is_some_and(|at| at.elapsed() >= debounce) {
+            changed_at = None;
+            on_run(&ingest_layer(config));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reingests_after_changes_settle_and_tombstones_deleted_files() -> anyhow::Result<()> {
+        let root = crate::util::make_temp_dir();
+        std::fs::write(root.join("keep.md"), "# Keep\n\nStays around.\n")?;
+        std::fs::write(root.join("gone.md"), "# Gone\n\nWill be deleted.\n")?;
+        let config = IngestConfig {
+            root: root.display().to_string(),
+            out: root.join("AGENTS.db").display().to_string(),
+            includes: Vec::new(),
+            excludes: Vec::new(),
+            chunk_size: 1500,
+            overlap: 200,
+            kind: "canonical".to_string(),
+            dim: Some(8),
+            incremental: true,
+            tombstone_deleted: true,
+        };
+
+        let (stop_tx, stop_rx) = std::sync::mpsc::channel::<()>();
+        let (runs_tx, runs_rx) = std::sync::mpsc::channel();
+        let watcher = std::thread::spawn(move || {
+            watch_until(
+                &config,
+                Duration::from_millis(10),
+                Duration::from_millis(30),
+                &stop_rx,
+                |result| {
+                    let _ = runs_tx.send(result.as_ref().map(|s| s.removed_files).ok());
+                },
+            );
+        });
+        let first = runs_rx.recv_timeout(Duration::from_secs(10))?;
+        assert_eq!(first, Some(0));
+
+        std::fs::remove_file(root.join("gone.md"))?;
+        let second = runs_rx.recv_timeout(Duration::from_secs(10))?;
+        assert_eq!(second, Some(1));
+        drop(stop_tx);
+        watcher
+            .join()
+            .map_err(|_| anyhow::anyhow!("watcher panicked"))?;
+
+        let file = agentsdb_format::LayerFile::open(root.join("AGENTS.db"))?;
+        let chunks = agentsdb_format::read_all_chunks(&file)?;
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().any(
+            |c| c.kind == agentsdb_query::KIND_TOMBSTONE && c.content == "gone.md was deleted"
+        ));
+
+        std::fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}
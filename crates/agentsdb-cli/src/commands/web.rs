@@ -1,6 +1,35 @@
-pub(crate) fn cmd_web(root: &str, bind: &str) -> anyhow::Result<()> {
+use anyhow::Context;
+
+pub(crate) fn cmd_web(
+    roots: &[String],
+    bind: &str,
+    auth: agentsdb_web::WebAuth,
+) -> anyhow::Result<()> {
     // Implements the `web` command, which launches a local Web UI for browsing and editing writable layers.
     //
-    // This function delegates to the `agentsdb_web::serve` function to start the web server.
-    agentsdb_web::serve(root, bind)
+    // The server runs until SIGINT/SIGTERM, then stops accepting connections, lets in-flight
+    // requests finish, saves its state, and returns.
+    let server = agentsdb_web::start_roots(roots, bind, auth)?;
+    let trigger = server.shutdown_trigger();
+    ctrlc::set_handler(move || trigger.shutdown()).context("install signal handler")?;
+    server.wait()?;
+    eprintln!("Web: shut down");
+    Ok(())
+}
+
+pub(crate) fn cmd_web_check(roots: &[String], bind: &str) -> anyhow::Result<()> {
+    // Implements `web --check`: runs the server's startup initialization, prints the JSON
+    // report (effective configuration plus per-step timings), and exits without serving.
+    // With several roots, each one is checked and reported in turn.
+    let mut ok = true;
+    for spec in roots {
+        let root = spec.split_once('=').map_or(spec.as_str(), |(_, dir)| dir);
+        let report = agentsdb_web::check(root, bind);
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        ok &= report.ok;
+    }
+    if !ok {
+        anyhow::bail!("startup check failed");
+    }
+    Ok(())
 }
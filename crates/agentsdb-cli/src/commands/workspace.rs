@@ -0,0 +1,94 @@
+use serde::Serialize;
+use std::path::Path;
+
+use agentsdb_ops::workspace::{registry_path, Workspace, WorkspaceProject};
+
+pub(crate) fn cmd_workspace_add(root: &str, name: Option<&str>, json: bool) -> anyhow::Result<()> {
+    let registry = registry_path()?;
+    let mut workspace = Workspace::load(&registry)?;
+    let project = workspace.add(Path::new(root), name)?;
+    workspace.save(&registry)?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            registry: String,
+            project: &'a WorkspaceProject,
+        }
+        let out = Out {
+            ok: true,
+            registry: registry.display().to_string(),
+            project: &project,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        println!(
+            "Registered {} ({}) in {}",
+            project.name,
+            project.root.display(),
+            registry.display()
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_workspace_remove(project: &str, json: bool) -> anyhow::Result<()> {
+    let registry = registry_path()?;
+    let mut workspace = Workspace::load(&registry)?;
+    let Some(removed) = workspace.remove(project) else {
+        anyhow::bail!("no project {project:?} in {}", registry.display());
+    };
+    workspace.save(&registry)?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            project: &'a WorkspaceProject,
+        }
+        let out = Out {
+            ok: true,
+            project: &removed,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        println!("Removed {} ({})", removed.name, removed.root.display());
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_workspace_list(json: bool) -> anyhow::Result<()> {
+    let registry = registry_path()?;
+    let workspace = Workspace::load(&registry)?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            registry: String,
+            projects: &'a [WorkspaceProject],
+        }
+        let out = Out {
+            ok: true,
+            registry: registry.display().to_string(),
+            projects: &workspace.projects,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+
+    if workspace.projects.is_empty() {
+        println!("No projects in {}.", registry.display());
+        return Ok(());
+    }
+    for project in &workspace.projects {
+        let missing = if project.root.join("AGENTS.db").exists() {
+            ""
+        } else {
+            " (missing)"
+        };
+        println!("{}\t{}{missing}", project.name, project.root.display());
+    }
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use anyhow::Context;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
 
+use agentsdb_embeddings::embedder::EmbedPurpose;
 use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
 use crate::embedding_helpers::{create_layer_metadata, create_validated_embedder};
 use crate::util::parse_vec_json;
@@ -17,6 +19,8 @@ pub(crate) fn cmd_write(
     dim: Option<u32>,
     sources: &[String],
     source_chunks: &[u32],
+    namespace: Option<&str>,
+    language: Option<&str>,
     json: bool,
 ) -> anyhow::Result<()> {
     // Implements the `write` command, which appends a chunk to a writable layer file.
@@ -24,23 +28,14 @@ pub(crate) fn cmd_write(
     // This function handles creating a new chunk with specified content, kind, confidence,
     // and optional embedding/sources, then appending it to the designated layer. It also
     // handles schema validation and embedding generation if an embedding is not provided.
-    if scope != "local" && scope != "delta" {
+    let policy = agentsdb_format::WritePolicy::standard();
+    if !policy.is_writable_scope(scope) {
         anyhow::bail!("--scope must be 'local' or 'delta'");
     }
-    let expected_name = match scope {
-        "local" => "AGENTS.local.db",
-        "delta" => "AGENTS.delta.db",
-        _ => unreachable!(),
-    };
-    if std::path::Path::new(path)
-        .file_name()
-        .and_then(|s| s.to_str())
-        .is_some_and(|n| n != expected_name)
-    {
-        anyhow::bail!("scope {scope:?} expects file named {expected_name}");
-    }
-
-    agentsdb_format::ensure_writable_layer_path(path).context("permission check")?;
+    policy
+        .check_scope(scope, path)
+        .context("permission check")?;
+    agentsdb_ops::kinds::check_kind_for_layer(std::path::Path::new(path), kind)?;
 
     let embedding = match embedding_json {
         Some(v) => parse_vec_json(v)?,
@@ -71,8 +66,19 @@ pub(crate) fn cmd_write(
             )
             .collect(),
     };
-
+    if let Some(namespace) = namespace {
+        chunk
+            .sources
+            .push(agentsdb_ops::util::namespace_source(namespace)?);
+    }
+    if let Some(language) = language {
+        chunk
+            .sources
+            .extend(agentsdb_ops::util::language_source(language, content)?);
+    }
     let p = std::path::Path::new(path);
+    agentsdb_ops::hooks::run_on_input(&agentsdb_ops::hooks::WriteTarget::of(p), &mut chunk)?;
+
     let dir = p.parent().unwrap_or_else(|| std::path::Path::new("."));
     let mut layer_metadata_json: Option<Vec<u8>> = None;
     let assigned = if p.exists() {
@@ -81,7 +87,7 @@ pub(crate) fn cmd_write(
             let dim = file.embedding_dim();
             let embedder = create_validated_embedder(dir, dim)?;
             chunk.embedding = embedder
-                .embed(&[chunk.content.clone()])?
+                .embed_for(EmbedPurpose::Document, &[chunk.content.clone()])?
                 .into_iter()
                 .next()
                 .unwrap_or_else(|| vec![0.0; dim]);
@@ -126,7 +132,7 @@ pub(crate) fn cmd_write(
         if chunk.embedding.is_empty() {
             let embedder = create_validated_embedder(dir, dim)?;
             chunk.embedding = embedder
-                .embed(&[chunk.content.clone()])?
+                .embed_for(EmbedPurpose::Document, &[chunk.content.clone()])?
                 .into_iter()
                 .next()
                 .unwrap_or_else(|| vec![0.0; dim]);
@@ -139,6 +145,7 @@ pub(crate) fn cmd_write(
             dim: dim as u32,
             element_type: agentsdb_format::EmbeddingElementType::F32,
             quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
         };
         let mut chunks = [chunk];
         agentsdb_format::write_layer_atomic(
@@ -170,3 +177,149 @@ pub(crate) fn cmd_write(
 
     Ok(())
 }
+
+/// Field values `--from-file` records fall back to, from `--kind`, `--confidence`,
+/// `--namespace` and `--language`.
+pub(crate) struct RecordDefaults {
+    pub(crate) kind: Option<String>,
+    pub(crate) confidence: Option<f32>,
+    pub(crate) namespace: Option<String>,
+    pub(crate) language: Option<String>,
+}
+
+/// One line of a `write --from-file` input.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WriteRecord {
+    content: String,
+    kind: Option<String>,
+    confidence: Option<f32>,
+    #[serde(default)]
+    sources: Vec<String>,
+    #[serde(default)]
+    source_chunks: Vec<u32>,
+    namespace: Option<String>,
+    language: Option<String>,
+}
+
+fn parse_records(
+    text: &str,
+    defaults: &RecordDefaults,
+) -> anyhow::Result<Vec<agentsdb_ops::write::NewChunk>> {
+    let mut out = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line_no = i + 1;
+        let record: WriteRecord =
+            serde_json::from_str(line).with_context(|| format!("line {line_no}"))?;
+        if record.content.trim().is_empty() {
+            anyhow::bail!("line {line_no}: content must be non-empty");
+        }
+        let Some(kind) = record.kind.or_else(|| defaults.kind.clone()) else {
+            anyhow::bail!("line {line_no}: no kind (set \"kind\" or pass --kind)");
+        };
+        let Some(confidence) = record.confidence.or(defaults.confidence) else {
+            anyhow::bail!(
+                "line {line_no}: no confidence (set \"confidence\" or pass --confidence)"
+            );
+        };
+        if !(0.0..=1.0).contains(&confidence) {
+            anyhow::bail!("line {line_no}: confidence must be in [0, 1]");
+        }
+        let mut sources: Vec<agentsdb_format::ChunkSource> = record
+            .sources
+            .into_iter()
+            .map(agentsdb_format::ChunkSource::SourceString)
+            .chain(
+                record
+                    .source_chunks
+                    .into_iter()
+                    .map(agentsdb_format::ChunkSource::ChunkId),
+            )
+            .collect();
+        if let Some(namespace) = record.namespace.as_ref().or(defaults.namespace.as_ref()) {
+            sources.push(
+                agentsdb_ops::util::namespace_source(namespace)
+                    .with_context(|| format!("line {line_no}"))?,
+            );
+        }
+        if let Some(language) = record.language.as_ref().or(defaults.language.as_ref()) {
+            sources.extend(
+                agentsdb_ops::util::language_source(language, &record.content)
+                    .with_context(|| format!("line {line_no}"))?,
+            );
+        }
+        out.push(agentsdb_ops::write::NewChunk {
+            kind,
+            content: record.content,
+            confidence,
+            sources,
+        });
+    }
+    Ok(out)
+}
+
+/// Implements `write --from-file`: appends every record of a JSON-lines file (or stdin) in one
+/// atomic write, embedding all contents in one batch.
+pub(crate) fn cmd_write_records(
+    path: &str,
+    scope: &str,
+    from_file: &str,
+    defaults: &RecordDefaults,
+    dim: Option<u32>,
+    json: bool,
+) -> anyhow::Result<()> {
+    agentsdb_format::WritePolicy::standard()
+        .check(path)
+        .context("permission check")?;
+    let text = if from_file == "-" {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .context("read stdin")?;
+        text
+    } else {
+        std::fs::read_to_string(from_file).with_context(|| format!("read {from_file}"))?
+    };
+    let chunks = parse_records(&text, defaults)?;
+    if chunks.is_empty() {
+        anyhow::bail!("no records in {from_file}");
+    }
+    let p = std::path::Path::new(path);
+    if !p.exists() && dim.is_none() {
+        anyhow::bail!("creating a new layer requires --dim");
+    }
+    let ids = agentsdb_ops::write::append_chunks(
+        p,
+        scope,
+        chunks,
+        dim,
+        "agentsdb-cli",
+        env!("CARGO_PKG_VERSION"),
+    )?;
+
+    if json {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            ok: bool,
+            path: &'a str,
+            ids: &'a [u32],
+        }
+        let out = Out {
+            ok: true,
+            path,
+            ids: &ids,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        let ids: Vec<String> = ids.iter().map(u32::to_string).collect();
+        println!(
+            "Appended {} chunks to {path} (ids={})",
+            ids.len(),
+            ids.join(",")
+        );
+    }
+    Ok(())
+}
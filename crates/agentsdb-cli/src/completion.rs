@@ -0,0 +1,145 @@
+//! Dynamic shell completion for layer paths and chunk kinds.
+//!
+//! The shell scripts printed by `agentsdb completions <shell>` call back into the binary with
+//! `COMPLETE=<shell>` set; `main` hands those calls to `clap_complete` before parsing, which
+//! runs the completers below for the arguments that use them.
+
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::path::Path;
+
+use clap_complete::engine::CompletionCandidate;
+
+/// Kinds offered even when no layer in the current directory uses them yet.
+const COMMON_KINDS: &[&str] = &["canonical", "decision", "invariant", "note"];
+
+/// Standard layer files whose kinds are offered for completion.
+const STANDARD_LAYERS: &[&str] = &[
+    "AGENTS.db",
+    "AGENTS.user.db",
+    "AGENTS.delta.db",
+    "AGENTS.local.db",
+];
+
+/// Completes `.db` files (and directories leading to them) relative to the current directory.
+pub(crate) fn layer_paths(current: &OsStr) -> Vec<CompletionCandidate> {
+    layer_paths_in(Path::new("."), &current.to_string_lossy())
+}
+
+/// Completes chunk kinds: the common ones, the kind taxonomy and every kind stored in the standard
+/// layers of the current directory.
+pub(crate) fn chunk_kinds(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    kinds_in(Path::new("."))
+        .into_iter()
+        .filter(|kind| kind.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+fn layer_paths_in(root: &Path, current: &str) -> Vec<CompletionCandidate> {
+    let (dir, prefix) = current
+        .rsplit_once('/')
+        .map_or((String::new(), current), |(dir, prefix)| {
+            (format!("{dir}/"), prefix)
+        });
+    let Ok(entries) = std::fs::read_dir(root.join(&dir)) else {
+        return Vec::new();
+    };
+    let mut out: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.file_type().ok()?.is_dir();
+            if is_dir {
+                Some(format!("{dir}{name}/"))
+            } else if name.ends_with(".db") {
+                Some(format!("{dir}{name}"))
+            } else {
+                None
+            }
+        })
+        .collect();
+    out.sort();
+    out.into_iter().map(CompletionCandidate::new).collect()
+}
+
+fn kinds_in(root: &Path) -> BTreeSet<String> {
+    let mut kinds: BTreeSet<String> = COMMON_KINDS.iter().map(|k| k.to_string()).collect();
+    if let Ok(taxonomy) = agentsdb_ops::kinds::load_taxonomy_for_dir(root) {
+        kinds.extend(taxonomy.kinds.into_iter().map(|k| k.name));
+    }
+    for name in STANDARD_LAYERS {
+        let Ok(file) = agentsdb_format::LayerFile::open_lenient(root.join(name)) else {
+            continue;
+        };
+        for chunk in file.chunks().flatten() {
+            if chunk.kind != agentsdb_query::KIND_TOMBSTONE {
+                kinds.insert(chunk.kind.to_string());
+            }
+        }
+    }
+    kinds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(candidates: &[CompletionCandidate]) -> Vec<String> {
+        candidates
+            .iter()
+            .map(|c| c.get_value().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn completes_layer_files_and_kinds() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir(dir.path().join("nested"))?;
+        std::fs::write(dir.path().join("notes.txt"), "")?;
+        std::fs::write(dir.path().join("nested").join("AGENTS.user.db"), "")?;
+        let schema = agentsdb_format::LayerSchema {
+            dim: 2,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let mut chunks = [agentsdb_format::ChunkInput {
+            id: 1,
+            kind: "runbook".to_string(),
+            content: "restart the worker".to_string(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 0,
+            embedding: vec![1.0, 0.0],
+            sources: Vec::new(),
+        }];
+        agentsdb_format::write_layer_atomic(
+            dir.path().join("AGENTS.db"),
+            &schema,
+            &mut chunks,
+            None,
+        )?;
+
+        assert_eq!(
+            values(&layer_paths_in(dir.path(), "")),
+            ["AGENTS.db", "nested/"]
+        );
+        assert_eq!(
+            values(&layer_paths_in(dir.path(), "nested/AG")),
+            ["nested/AGENTS.user.db"]
+        );
+        assert!(layer_paths_in(dir.path(), "missing/").is_empty());
+
+        let kinds: Vec<String> = kinds_in(dir.path()).into_iter().collect();
+        assert_eq!(
+            kinds,
+            ["canonical", "decision", "invariant", "note", "runbook"]
+        );
+        Ok(())
+    }
+}
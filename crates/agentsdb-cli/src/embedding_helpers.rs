@@ -3,7 +3,7 @@ use std::path::Path;
 
 use agentsdb_embeddings::config::get_immutable_embedding_options;
 use agentsdb_embeddings::embedder::Embedder;
-use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
+use agentsdb_embeddings::layer_metadata::{carry_kinds, LayerMetadataV1};
 
 /// Creates an embedder from directory options, validating dimension compatibility.
 ///
@@ -57,6 +57,17 @@ pub(crate) fn create_layer_metadata(embedder: &dyn Embedder) -> anyhow::Result<V
         .context("serialize layer metadata")
 }
 
+/// `metadata_json` with the kind taxonomy of the layer at `path` (if it exists) carried over, so
+/// rewriting the layer does not drop it.
+pub(crate) fn keep_kinds_of(metadata_json: Vec<u8>, path: &Path) -> anyhow::Result<Vec<u8>> {
+    if !path.exists() {
+        return Ok(metadata_json);
+    }
+    let file = agentsdb_format::LayerFile::open_lenient(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    carry_kinds(metadata_json, file.layer_metadata_bytes())
+}
+
 /// Validates that an embedder's profile matches existing layer metadata.
 ///
 /// This function:
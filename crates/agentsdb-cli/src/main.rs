@@ -1,16 +1,21 @@
 mod app;
 mod cli;
 mod commands;
+mod completion;
 mod embedding_helpers;
+#[cfg(test)]
+mod test_support;
 mod types;
 mod util;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 
 /// Main entry point for the AGENTS.db CLI application.
 ///
-/// Parses command-line arguments and dispatches to the main application logic.
+/// Answers shell completion requests (`COMPLETE=<shell>`), otherwise parses command-line
+/// arguments and dispatches to the main application logic.
 fn main() -> anyhow::Result<()> {
+    clap_complete::CompleteEnv::with_factory(cli::Cli::command).complete();
     let cli = cli::Cli::parse();
     app::run(cli)
 }
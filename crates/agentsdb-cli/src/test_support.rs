@@ -0,0 +1,25 @@
+//! Fixtures shared by the unit tests of the commands.
+
+/// The schema of the fixture layers: four f32 dimensions, row-major.
+pub(crate) fn schema() -> agentsdb_format::LayerSchema {
+    agentsdb_format::LayerSchema {
+        dim: 4,
+        element_type: agentsdb_format::EmbeddingElementType::F32,
+        quant_scale: 1.0,
+        layout: agentsdb_format::EmbeddingLayout::RowMajor,
+    }
+}
+
+/// A human chunk with full confidence, embedding `[0.1, 0.2, 0.3, 0.4]` and no sources.
+pub(crate) fn chunk(id: u32, kind: &str, content: &str) -> agentsdb_format::ChunkInput {
+    agentsdb_format::ChunkInput {
+        id,
+        kind: kind.to_string(),
+        content: content.to_string(),
+        author: "human".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: 0,
+        embedding: vec![0.1, 0.2, 0.3, 0.4],
+        sources: Vec::new(),
+    }
+}
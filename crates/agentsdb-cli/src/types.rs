@@ -15,6 +15,46 @@ pub(crate) struct ValidateJson {
     pub(crate) options_dim: Option<u32>,
 }
 
+#[derive(Serialize)]
+/// Represents the JSON output structure for `validate` on a directory of standard layers.
+pub(crate) struct DirectoryValidateJson {
+    pub(crate) ok: bool,
+    pub(crate) dir: String,
+    pub(crate) expected_dim: Option<usize>,
+    pub(crate) layers: Vec<LayerValidateJson>,
+}
+
+#[derive(Serialize)]
+/// Represents the validation result of one layer in [`DirectoryValidateJson`].
+pub(crate) struct LayerValidateJson {
+    pub(crate) layer: String,
+    pub(crate) path: String,
+    pub(crate) ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) warnings: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) schema_dim: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) options_dim: Option<u32>,
+}
+
+#[derive(Serialize)]
+/// Represents the JSON output structure for the `promote` command.
+pub(crate) struct PromoteJson<'a> {
+    pub(crate) ok: bool,
+    pub(crate) from: &'a str,
+    pub(crate) to: &'a str,
+    pub(crate) promoted: Vec<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) skipped: Vec<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) operation: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) conflicts: Vec<agentsdb_ops::promote::ResolvedConflict>,
+}
+
 #[derive(Serialize)]
 /// Represents the JSON output structure for the `destroy` command.
 pub(crate) struct DestroyJson<'a> {
@@ -41,6 +81,7 @@ pub(crate) struct InspectJson<'a> {
     pub(crate) chunk_count: u64,
     pub(crate) embedding: EmbeddingJson,
     pub(crate) relationships: Option<u64>,
+    pub(crate) stats: StatsJson,
 }
 
 #[derive(Serialize)]
@@ -63,6 +104,22 @@ pub(crate) struct SectionJson {
     pub(crate) length: u64,
 }
 
+#[derive(Serialize)]
+/// Represents the chunk statistics of an AGENTS.db layer in JSON format.
+pub(crate) struct StatsJson {
+    /// Whether the statistics were read from the layer's stats section rather than recomputed.
+    pub(crate) stored: bool,
+    pub(crate) by_kind: std::collections::BTreeMap<String, u64>,
+    pub(crate) by_author: std::collections::BTreeMap<String, u64>,
+    pub(crate) confidence_min: f32,
+    pub(crate) confidence_max: f32,
+    pub(crate) confidence_avg: f32,
+    /// Chunk counts with confidence below 0.2, 0.4, 0.6, 0.8 and up to 1.0.
+    pub(crate) confidence_histogram: Vec<u64>,
+    pub(crate) created_at_min_unix_ms: u64,
+    pub(crate) created_at_max_unix_ms: u64,
+}
+
 #[derive(Serialize)]
 /// Represents embedding-related metadata within an AGENTS.db layer in JSON format.
 pub(crate) struct EmbeddingJson {
@@ -73,6 +130,7 @@ pub(crate) struct EmbeddingJson {
     pub(crate) data_offset: u64,
     pub(crate) data_length: u64,
     pub(crate) quant_scale: f32,
+    pub(crate) layout: &'static str, // "row-major" | "blocked"
 }
 
 #[derive(Serialize)]
@@ -105,12 +163,14 @@ pub(crate) struct CompileInput {
     pub(crate) chunks: Vec<CompileChunk>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 /// Represents the schema information within the `compile` command's input JSON.
 pub(crate) struct CompileSchema {
     pub(crate) dim: u32,
     pub(crate) element_type: String, // "f32" | "i8"
     pub(crate) quant_scale: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) layout: Option<String>, // "row-major" | "blocked"
 }
 
 #[derive(Deserialize)]
@@ -135,3 +195,37 @@ pub(crate) enum CompileSource {
     String(String),
     Chunk { chunk_id: u32 },
 }
+
+#[derive(Serialize)]
+/// Represents the reproducibility manifest written by `compile --manifest`.
+pub(crate) struct CompileManifestJson {
+    pub(crate) v: u32,
+    pub(crate) tool_name: String,
+    pub(crate) tool_version: String,
+    pub(crate) deterministic: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) input_file: Option<CompileManifestInput>,
+    pub(crate) inputs: Vec<CompileManifestInput>,
+    pub(crate) schema: CompileSchema,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) embedding_profile: Option<agentsdb_embeddings::embedder::EmbeddingProfile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) embedder_metadata: Option<agentsdb_embeddings::embedder::EmbedderMetadata>,
+    pub(crate) output: CompileManifestOutput,
+}
+
+#[derive(Serialize)]
+/// Represents one hashed input in [`CompileManifestJson`].
+pub(crate) struct CompileManifestInput {
+    pub(crate) source: String,
+    pub(crate) sha256: String,
+}
+
+#[derive(Serialize)]
+/// Represents the written layer in [`CompileManifestJson`].
+pub(crate) struct CompileManifestOutput {
+    pub(crate) path: String,
+    pub(crate) sha256: String,
+    pub(crate) bytes: u64,
+    pub(crate) chunks: usize,
+}
@@ -17,6 +17,7 @@ pub(crate) fn layer_to_str(layer: LayerId) -> &'static str {
         LayerId::User => "user",
         LayerId::Delta => "delta",
         LayerId::Local => "local",
+        LayerId::Session => "session",
     }
 }
 
@@ -236,6 +237,101 @@ fn visit_dir(
     Ok(())
 }
 
+/// Collects files under `root` whose `/`-separated relative path matches one of
+/// `includes` and none of `excludes` (see [`glob_match`]).
+///
+/// Build/dependency directories skipped by `init` are skipped here too, as are
+/// empty and binary files.
+pub(crate) fn collect_files_matching(
+    root: &Path,
+    includes: &[String],
+    excludes: &[String],
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    visit_dir_matching(root, root, includes, excludes, &mut out)?;
+    out.sort();
+    Ok(out)
+}
+
+fn visit_dir_matching(
+    root: &Path,
+    dir: &Path,
+    includes: &[String],
+    excludes: &[String],
+    out: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| format!("read dir {}", dir.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        let ty = entry.file_type()?;
+        let rel = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+        let rel_str = rel_path_string(&rel);
+        if ty.is_dir() {
+            if should_skip_init_dir(&entry.file_name())
+                || excludes.iter().any(|g| glob_match(g, &rel_str))
+            {
+                continue;
+            }
+            visit_dir_matching(root, &path, includes, excludes, out)?;
+        } else if ty.is_file()
+            && includes.iter().any(|g| glob_match(g, &rel_str))
+            && !excludes.iter().any(|g| glob_match(g, &rel_str))
+        {
+            if entry.metadata().map(|m| m.len() == 0).unwrap_or(true) || is_likely_binary(&path) {
+                continue;
+            }
+            out.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// Renders a relative path with `/` separators, regardless of platform.
+pub(crate) fn rel_path_string(rel: &Path) -> String {
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Matches a `/`-separated relative path against a glob pattern.
+///
+/// `*` matches within a path segment, `?` matches one character, and `**` matches
+/// any number of segments. Like `.gitignore`, a pattern without a `/` matches the
+/// file name at any depth.
+pub(crate) fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./");
+    if !pattern.contains('/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return segment_match(pattern.as_bytes(), name.as_bytes());
+    }
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    segments_match(&pattern, &path)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                segment_match(first.as_bytes(), name.as_bytes()) && segments_match(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn segment_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| segment_match(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && segment_match(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && segment_match(rest, &name[1..]),
+    }
+}
+
 pub(crate) fn assign_stable_id(path: &Path, content: &str, used: &mut BTreeSet<u32>) -> u32 {
     // Assigns a stable, unique ID to a chunk based on its path and content.
     //
@@ -320,6 +416,43 @@ pub(crate) fn fmt_bytes_human(bytes: u64) -> String {
     }
 }
 
+/// The nearest strict ancestor of `start` holding an `AGENTS.db`, or `None` when `start` has
+/// one itself (or no ancestor does).
+pub(crate) fn find_project_dir(start: &Path) -> Option<PathBuf> {
+    if start.join("AGENTS.db").exists() {
+        return None;
+    }
+    start
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("AGENTS.db").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Where commands look for the standard layers when no path is given: the working directory,
+/// or the nearest ancestor with an `AGENTS.db` when run from a project subdirectory.
+pub(crate) fn project_dir() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    find_project_dir(&cwd)
+}
+
+/// Sends `tracing` events at or above `filter` to stderr, as text or one JSON object per line.
+pub(crate) fn init_logging(filter: &str, format: crate::cli::LogFormat) -> anyhow::Result<()> {
+    let filter = tracing_subscriber::EnvFilter::try_new(filter)
+        .with_context(|| format!("invalid --log-level {filter:?}"))?;
+    // Closing a span logs its duration, so `debug` shows search, append and embed latency.
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_writer(std::io::stderr);
+    // Fails only when a subscriber is already installed (commands run in-process by tests).
+    let _ = match format {
+        crate::cli::LogFormat::Text => builder.compact().without_time().try_init(),
+        crate::cli::LogFormat::Json => builder.json().try_init(),
+    };
+    Ok(())
+}
+
 #[cfg(test)]
 pub(crate) fn make_temp_dir() -> PathBuf {
     static CTR: AtomicUsize = AtomicUsize::new(0);
@@ -399,4 +532,32 @@ mod tests {
 
         std::fs::remove_dir_all(&root).expect("cleanup");
     }
+
+    #[test]
+    fn glob_match_supports_stars_and_double_stars() {
+        assert!(glob_match("*.md", "README.md"));
+        assert!(glob_match("*.md", "docs/guide/intro.md"));
+        assert!(!glob_match("*.md", "docs/notes.txt"));
+        assert!(glob_match("docs/**", "docs/guide/intro.md"));
+        assert!(glob_match("docs/**/*.md", "docs/intro.md"));
+        assert!(glob_match("docs/**/*.md", "docs/a/b/intro.md"));
+        assert!(!glob_match("docs/**/*.md", "src/intro.md"));
+        assert!(glob_match("src/*.r?", "src/lib.rs"));
+        assert!(!glob_match("src/*.rs", "src/cli/app.rs"));
+        assert!(glob_match("./src/**/*.rs", "src/cli/app.rs"));
+    }
+
+    #[test]
+    fn find_project_dir_walks_up_to_agents_db() {
+        let root = make_temp_dir();
+        let nested = root.join("src").join("deep");
+        std::fs::create_dir_all(&nested).expect("create nested dirs");
+        assert_eq!(find_project_dir(&nested), None);
+
+        std::fs::write(root.join("AGENTS.db"), b"").expect("write AGENTS.db");
+        assert_eq!(find_project_dir(&nested), Some(root.clone()));
+        assert_eq!(find_project_dir(&root), None);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
@@ -85,6 +85,7 @@ fn write_layer_with_custom_profile(path: &Path, dim: u32, output_norm: &str) {
         dim,
         element_type: agentsdb_format::EmbeddingElementType::F32,
         quant_scale: 1.0,
+        layout: agentsdb_format::EmbeddingLayout::RowMajor,
     };
     let profile = EmbeddingProfile {
         backend: "hash".to_string(),
@@ -117,6 +118,7 @@ fn write_layer_two_chunks(path: &Path) {
         dim: 2,
         element_type: agentsdb_format::EmbeddingElementType::F32,
         quant_scale: 1.0,
+        layout: agentsdb_format::EmbeddingLayout::RowMajor,
     };
     let chunks = [
         agentsdb_format::ChunkInput {
@@ -183,6 +185,35 @@ fn index_builds_and_search_can_use_it() {
     assert_eq!(v["results"][0]["id"].as_u64().unwrap(), 1);
 }
 
+#[test]
+fn search_finds_project_layers_from_subdirectory() {
+    let dir = TempDir::new("agentsdb_e2e_project_root");
+    write_layer_two_chunks(&dir.path().join("AGENTS.db"));
+    let nested = dir.path().join("src").join("deep");
+    std::fs::create_dir_all(&nested).expect("create nested dirs");
+
+    let args = ["--json", "search", "--query-vec", "[1.0,0.0]", "-k", "1"];
+    let v = run_ok_json(&nested, &args);
+    assert_eq!(v["results"][0]["id"].as_u64().unwrap(), 1);
+
+    let elsewhere = TempDir::new("agentsdb_e2e_project_root_elsewhere");
+    let root = dir.path().to_string_lossy();
+    let v = run_ok_json(
+        elsewhere.path(),
+        &[
+            "-C",
+            &root,
+            "--json",
+            "search",
+            "--query-vec",
+            "[0.0,1.0]",
+            "-k",
+            "1",
+        ],
+    );
+    assert_eq!(v["results"][0]["id"].as_u64().unwrap(), 2);
+}
+
 #[test]
 fn compile_validate_inspect_roundtrip() {
     let dir = TempDir::new("agentsdb_e2e_compile");
@@ -249,6 +280,51 @@ fn compile_is_deterministic_for_hash_backend() {
     assert_eq!(b1, b2);
 }
 
+#[test]
+fn compile_manifest_records_inputs_and_output_hash() {
+    let dir = TempDir::new("agentsdb_e2e_compile_manifest");
+    std::fs::write(
+        dir.path().join("NOTES.md"),
+        "# Notes\n\nLayers are append-only.\n",
+    )
+    .expect("write notes");
+
+    let mut manifests = Vec::new();
+    for name in ["AGENTS.1.db", "AGENTS.2.db"] {
+        let manifest = dir.path().join(format!("{name}.manifest.json"));
+        run_ok(
+            dir.path(),
+            &[
+                "compile",
+                "--out",
+                name,
+                "--dim",
+                "8",
+                "--deterministic",
+                "--manifest",
+                &manifest.to_string_lossy(),
+                "NOTES.md",
+                "--text",
+                "hello world",
+            ],
+        );
+        let bytes = std::fs::read(&manifest).expect("read manifest");
+        manifests.push(serde_json::from_slice::<Value>(&bytes).expect("parse manifest"));
+    }
+
+    let (m1, m2) = (&manifests[0], &manifests[1]);
+    assert_eq!(m1["deterministic"], true);
+    assert_eq!(m1["inputs"].as_array().expect("inputs").len(), 2);
+    assert_eq!(m1["inputs"], m2["inputs"]);
+    assert_eq!(m1["embedding_profile"]["backend"], "hash");
+    assert_eq!(m1["output"]["chunks"], 2);
+    assert_eq!(m1["output"]["sha256"], m2["output"]["sha256"]);
+    assert_eq!(
+        std::fs::read(dir.path().join("AGENTS.1.db")).expect("read out1"),
+        std::fs::read(dir.path().join("AGENTS.2.db")).expect("read out2")
+    );
+}
+
 #[test]
 fn options_set_show_roundtrip() {
     let dir = TempDir::new("agentsdb_e2e_options");
@@ -280,6 +356,67 @@ fn options_set_show_roundtrip() {
     assert_eq!(out["base"]["patch"]["dim"], 8);
 }
 
+#[test]
+fn options_explain_reports_source_per_key() {
+    let dir = TempDir::new("agentsdb_e2e_options_explain");
+
+    run_ok(
+        dir.path(),
+        &[
+            "options",
+            "set",
+            "--backend",
+            "hash",
+            "--dim",
+            "8",
+            "--set",
+            "search.k=7",
+        ],
+    );
+    std::fs::write(
+        dir.path().join("agentsdb.toml"),
+        "[server]\nread_only = true\n",
+    )
+    .expect("write agentsdb.toml");
+
+    let out = agentsdb()
+        .current_dir(dir.path())
+        .env("AGENTSDB_SEARCH_MODE", "semantic")
+        .args(["--json", "options", "explain"])
+        .output()
+        .expect("run agentsdb");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let out: Value = serde_json::from_slice(&out.stdout).expect("stdout is valid JSON");
+    let setting = |key: &str| {
+        out["settings"]
+            .as_array()
+            .expect("settings")
+            .iter()
+            .find(|s| s["key"] == key)
+            .cloned()
+            .expect("setting present")
+    };
+    assert_eq!(setting("embedding.dim")["value"], 8);
+    assert_eq!(setting("search.k")["value"], 7);
+    assert_eq!(setting("search.k")["source"]["kind"], "layer");
+    assert_eq!(setting("search.mode")["value"], "semantic");
+    assert_eq!(
+        setting("search.mode")["source"]["var"],
+        "AGENTSDB_SEARCH_MODE"
+    );
+    assert_eq!(setting("server.read_only")["value"], true);
+    assert_eq!(setting("server.read_only")["source"]["kind"], "file");
+    assert_eq!(setting("server.max_in_flight")["source"]["kind"], "default");
+
+    let out = run_err(dir.path(), &["options", "set", "--set", "search.top_k=3"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("did you mean `search.k`?"), "{stderr}");
+}
+
 #[test]
 fn write_fails_on_embedder_profile_mismatch_vs_layer_metadata() {
     let dir = TempDir::new("agentsdb_e2e_profile_mismatch_write");
@@ -412,6 +549,639 @@ fn write_search_inspect_flow() {
     assert!(stdout.contains("source: README.md:1"), "stdout={stdout}");
 }
 
+#[test]
+fn namespaces_scope_search_export_and_promote() {
+    let dir = TempDir::new("agentsdb_e2e_namespace");
+    for (content, namespace) in [
+        ("api handlers", "api"),
+        ("api errors", "api"),
+        ("web routes", "web"),
+    ] {
+        run_ok(
+            dir.path(),
+            &[
+                "write",
+                "AGENTS.local.db",
+                "--scope",
+                "local",
+                "--kind",
+                "note",
+                "--content",
+                content,
+                "--confidence",
+                "0.9",
+                "--dim",
+                "8",
+                "--namespace",
+                namespace,
+            ],
+        );
+    }
+
+    let found = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "search",
+            "--local",
+            "AGENTS.local.db",
+            "--query",
+            "routes",
+            "-k",
+            "5",
+            "--namespace",
+            "api",
+        ],
+    );
+    assert_eq!(found["results"].as_array().map(Vec::len), Some(2));
+
+    let out = run_ok(
+        dir.path(),
+        &["export", "--layers", "local", "--namespace", "web"],
+    );
+    let bundle: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap_or_default();
+    assert_eq!(
+        bundle["layers"][0]["chunks"].as_array().map(Vec::len),
+        Some(1)
+    );
+    assert_eq!(bundle["layers"][0]["chunks"][0]["content"], "web routes");
+
+    run_err(
+        dir.path(),
+        &[
+            "promote",
+            "--from",
+            "AGENTS.local.db",
+            "--to",
+            "AGENTS.delta.db",
+            "--ids",
+            "3",
+            "--namespace",
+            "api",
+        ],
+    );
+    let promoted = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "promote",
+            "--from",
+            "AGENTS.local.db",
+            "--to",
+            "AGENTS.delta.db",
+            "--namespace",
+            "api",
+        ],
+    );
+    assert_eq!(promoted["promoted"].as_array().map(Vec::len), Some(2));
+}
+
+#[test]
+fn languages_are_tagged_on_write_and_filter_search() {
+    let dir = TempDir::new("agentsdb_e2e_language");
+    for (content, language) in [
+        (
+            "Always run the formatter before you commit the code.",
+            "auto",
+        ),
+        (
+            "Wir verwenden immer pnpm und nicht npm, wenn die Pakete sich ändern.",
+            "auto",
+        ),
+        ("Formatter vor dem Commit ausführen.", "de-AT"),
+    ] {
+        run_ok(
+            dir.path(),
+            &[
+                "write",
+                "AGENTS.local.db",
+                "--scope",
+                "local",
+                "--kind",
+                "note",
+                "--content",
+                content,
+                "--confidence",
+                "0.9",
+                "--dim",
+                "8",
+                "--language",
+                language,
+            ],
+        );
+    }
+    run_err(
+        dir.path(),
+        &[
+            "write",
+            "AGENTS.local.db",
+            "--scope",
+            "local",
+            "--kind",
+            "note",
+            "--content",
+            "x",
+            "--confidence",
+            "0.9",
+            "--language",
+            "german",
+        ],
+    );
+
+    let search = |language: &str| {
+        run_ok_json(
+            dir.path(),
+            &[
+                "--json",
+                "search",
+                "--local",
+                "AGENTS.local.db",
+                "--query",
+                "formatter",
+                "-k",
+                "5",
+                "--language",
+                language,
+            ],
+        )["results"]
+            .as_array()
+            .map(Vec::len)
+    };
+    assert_eq!(search("de"), Some(2));
+    assert_eq!(search("de-AT"), Some(1));
+    assert_eq!(search("EN"), Some(1));
+    assert_eq!(search("fr"), Some(0));
+}
+
+#[test]
+fn dedupe_reports_and_retracts_exact_duplicates() {
+    let dir = TempDir::new("agentsdb_e2e_dedupe");
+    let layer = dir.path().join("AGENTS.local.db");
+    let layer_s = layer.to_string_lossy();
+    for content in ["run cargo test", "run cargo test", "something else"] {
+        run_ok(
+            dir.path(),
+            &[
+                "write",
+                &layer_s,
+                "--scope",
+                "local",
+                "--kind",
+                "note",
+                "--content",
+                content,
+                "--confidence",
+                "0.9",
+                "--dim",
+                "8",
+            ],
+        );
+    }
+
+    let report = run_ok_json(dir.path(), &["--json", "dedupe", "--local", &layer_s]);
+    assert_eq!(report["scanned"], 3);
+    assert_eq!(report["duplicate_count"], 1);
+    assert_eq!(report["groups"][0]["duplicates"][0]["match_kind"], "exact");
+    run_err(
+        dir.path(),
+        &[
+            "--json",
+            "dedupe",
+            "--local",
+            &layer_s,
+            "--fail-on-duplicates",
+        ],
+    );
+
+    let applied = run_ok_json(
+        dir.path(),
+        &["--json", "dedupe", "--local", &layer_s, "--apply"],
+    );
+    assert_eq!(
+        applied["resolved"][0]["retracted"].as_array().map(Vec::len),
+        Some(1)
+    );
+    let after = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "dedupe",
+            "--local",
+            &layer_s,
+            "--fail-on-duplicates",
+        ],
+    );
+    assert_eq!(after["duplicate_count"], 0);
+    assert_eq!(after["scanned"], 2);
+}
+
+#[test]
+fn ingest_incremental_reembeds_only_changed_files() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_ingest");
+    std::fs::create_dir_all(dir.path().join("docs"))?;
+    std::fs::create_dir_all(dir.path().join("src"))?;
+    std::fs::write(
+        dir.path().join("docs/guide.md"),
+        "# Guide\n\nRun the tests.\n",
+    )?;
+    std::fs::write(dir.path().join("docs/old.md"), "# Old\n\nDeprecated.\n")?;
+    std::fs::write(
+        dir.path().join("src/lib.rs"),
+        "/// Layers are append-only; writes always add new chunks.\npub fn f() {}\n",
+    )?;
+
+    let args = [
+        "--json",
+        "ingest",
+        "--include",
+        "docs/**/*.md",
+        "--include",
+        "src/**/*.rs",
+        "--dim",
+        "8",
+        "--incremental",
+    ];
+    let first = run_ok_json(dir.path(), &args);
+    assert_eq!(first["files"], 3);
+    assert_eq!(first["embedded_chunks"], 3);
+    assert_eq!(first["reused_files"], 0);
+
+    std::fs::write(
+        dir.path().join("docs/guide.md"),
+        "# Guide\n\nRun cargo test.\n",
+    )?;
+    std::fs::remove_file(dir.path().join("docs/old.md"))?;
+    let second = run_ok_json(dir.path(), &args);
+    assert_eq!(second["files"], 2);
+    assert_eq!(second["chunks"], 2);
+    assert_eq!(second["embedded_chunks"], 1);
+    assert_eq!(second["reused_files"], 1);
+    assert_eq!(second["removed_files"], 1);
+
+    run_ok(dir.path(), &["validate", "AGENTS.db"]);
+    Ok(())
+}
+
+#[test]
+fn mergetool_keeps_chunks_appended_on_both_branches() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_mergetool");
+    let write = |id: &str, content: &str| {
+        run_ok(
+            dir.path(),
+            &[
+                "write",
+                "AGENTS.local.db",
+                "--scope",
+                "local",
+                "--id",
+                id,
+                "--kind",
+                "note",
+                "--content",
+                content,
+                "--confidence",
+                "0.9",
+                "--dim",
+                "8",
+            ],
+        );
+    };
+    write("1", "shared note");
+    std::fs::copy(
+        dir.path().join("AGENTS.local.db"),
+        dir.path().join("base.db"),
+    )?;
+    std::fs::copy(
+        dir.path().join("AGENTS.local.db"),
+        dir.path().join("theirs.db"),
+    )?;
+    write("2", "ours note");
+    std::fs::rename(
+        dir.path().join("AGENTS.local.db"),
+        dir.path().join("ours.db"),
+    )?;
+    std::fs::rename(
+        dir.path().join("theirs.db"),
+        dir.path().join("AGENTS.local.db"),
+    )?;
+    write("2", "theirs note");
+    std::fs::rename(
+        dir.path().join("AGENTS.local.db"),
+        dir.path().join("theirs.db"),
+    )?;
+
+    let report = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "mergetool",
+            "--base",
+            "base.db",
+            "--ours",
+            "ours.db",
+            "--theirs",
+            "theirs.db",
+        ],
+    );
+    assert_eq!(report["chunks"], 3);
+    assert_eq!(report["renumbered"][0], serde_json::json!([2, 3]));
+    run_ok(dir.path(), &["validate", "ours.db"]);
+    Ok(())
+}
+
+#[test]
+fn sync_push_pull_through_directory_remote() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_sync");
+    let remote = dir.path().join("remote");
+    let remote_s = remote.to_string_lossy().to_string();
+    let alice = dir.path().join("alice");
+    let bob = dir.path().join("bob");
+    std::fs::create_dir_all(&alice)?;
+    std::fs::create_dir_all(&bob)?;
+    let write = |cwd: &Path, id: &str, content: &str| {
+        run_ok(
+            cwd,
+            &[
+                "write",
+                "AGENTS.local.db",
+                "--scope",
+                "local",
+                "--id",
+                id,
+                "--kind",
+                "note",
+                "--content",
+                content,
+                "--confidence",
+                "0.9",
+                "--dim",
+                "8",
+            ],
+        );
+    };
+
+    write(&alice, "1", "shared note");
+    let pushed = run_ok_json(
+        &alice,
+        &["--json", "sync", "push", "local", "--remote", &remote_s],
+    );
+    assert_eq!(pushed["action"], "pushed");
+    let pulled = run_ok_json(
+        &bob,
+        &["--json", "sync", "pull", "local", "--remote", &remote_s],
+    );
+    assert_eq!(pulled["action"], "pulled");
+
+    // Both sides append; Bob pushes first, so Alice's push must merge.
+    write(&bob, "2", "bob note");
+    run_ok(&bob, &["sync", "push", "local"]);
+    write(&alice, "2", "alice note");
+    run_err(&alice, &["sync", "push", "local"]);
+    let merged = run_ok_json(&alice, &["--json", "sync", "pull", "local", "--merge"]);
+    assert_eq!(merged["action"], "merged");
+    run_ok(&alice, &["sync", "push", "local"]);
+    run_ok(&bob, &["sync", "pull", "local"]);
+    assert_eq!(
+        std::fs::read(alice.join("AGENTS.local.db"))?,
+        std::fs::read(bob.join("AGENTS.local.db"))?
+    );
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn export_import_parquet_round_trip() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_parquet");
+    write_layer_two_chunks(&dir.path().join("AGENTS.local.db"));
+    run_ok(
+        dir.path(),
+        &[
+            "export",
+            "--format",
+            "parquet",
+            "--layers",
+            "local",
+            "--out",
+            "local.parquet",
+        ],
+    );
+    assert!(std::fs::read(dir.path().join("local.parquet"))?.starts_with(b"PAR1"));
+
+    let restored = dir.path().join("restored");
+    std::fs::create_dir_all(&restored)?;
+    let restored_s = restored.to_string_lossy().to_string();
+    run_err(
+        dir.path(),
+        &["import", "--in", "local.parquet", "--format", "json"],
+    );
+    let out = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "import",
+            "--dir",
+            &restored_s,
+            "--in",
+            "local.parquet",
+            "--format",
+            "parquet",
+            "--preserve-ids",
+        ],
+    );
+    assert_eq!(out["imported"], 2);
+    run_ok(&restored, &["validate", "AGENTS.local.db"]);
+    Ok(())
+}
+
+#[test]
+fn import_markdown_directory_splits_at_headings() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_import_markdown");
+    let notes = dir.path().join("notes");
+    std::fs::create_dir_all(notes.join("ops"))?;
+    std::fs::write(
+        notes.join("AGENTS.md"),
+        "# Build\n\nRun cargo build.\n\n## Test\n\nRun cargo test.\n",
+    )?;
+    std::fs::write(
+        notes.join("ops").join("deploy.md"),
+        "---\nkind: runbook\nconfidence: 0.7\ntags: [deploy]\n---\n# Deploy\n\nPush the tag.\n",
+    )?;
+
+    run_err(dir.path(), &["import", "--format", "markdown", "notes"]);
+    let out = run_ok_json(
+        dir.path(),
+        &[
+            "--json", "import", "--format", "markdown", "notes", "--target", "local", "--dim", "8",
+        ],
+    );
+    assert_eq!(out["imported"], 3);
+
+    // Files are read in path order: AGENTS.md gives ids 1 and 2, ops/deploy.md id 3.
+    let deploy = run_ok_json(
+        dir.path(),
+        &["--json", "inspect", "AGENTS.local.db", "--id", "3"],
+    );
+    assert_eq!(deploy["kind"], "runbook");
+    assert_eq!(deploy["content"], "# Deploy\n\nPush the tag.");
+    Ok(())
+}
+
+#[test]
+fn verify_provenance_flags_and_fixes_dangling_sources() {
+    let dir = TempDir::new("agentsdb_e2e_verify_provenance");
+    write_layer_two_chunks(&dir.path().join("AGENTS.local.db"));
+    run_ok(
+        dir.path(),
+        &[
+            "write",
+            "AGENTS.local.db",
+            "--scope",
+            "local",
+            "--kind",
+            "note",
+            "--content",
+            "derived from a lost chunk",
+            "--confidence",
+            "1.0",
+            "--dim",
+            "2",
+            "--source-chunk",
+            "1",
+            "--source-chunk",
+            "99",
+        ],
+    );
+
+    run_err(
+        dir.path(),
+        &["verify-provenance", "--local", "AGENTS.local.db"],
+    );
+    let fixed = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "verify-provenance",
+            "--local",
+            "AGENTS.local.db",
+            "--fix",
+        ],
+    );
+    assert_eq!(fixed["ok"], true);
+    assert_eq!(fixed["fix"]["relinked"][0]["relinks"][0][0], 99);
+    let report = run_ok_json(
+        dir.path(),
+        &["--json", "verify-provenance", "--local", "AGENTS.local.db"],
+    );
+    assert_eq!(report["references"], 1);
+}
+
+#[test]
+fn review_decays_old_chunks_and_confirms_them() {
+    let dir = TempDir::new("agentsdb_e2e_review");
+    write_layer_two_chunks(&dir.path().join("AGENTS.local.db"));
+
+    run_err(dir.path(), &["review", "--local", "AGENTS.local.db"]);
+    let decayed = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "review",
+            "--local",
+            "AGENTS.local.db",
+            "--decay",
+            "--factor",
+            "0.4",
+            "--stale",
+        ],
+    );
+    assert_eq!(
+        decayed["decay"]["decayed"].as_array().map(Vec::len),
+        Some(2)
+    );
+    let stale = decayed["stale"].as_array().cloned().unwrap_or_default();
+    assert_eq!(stale.len(), 2);
+    let revision = stale[0]["id"].as_u64().unwrap_or_default().to_string();
+
+    let confirmed = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "review",
+            "--local",
+            "AGENTS.local.db",
+            "--confirm",
+            &revision,
+            "--stale",
+        ],
+    );
+    assert_eq!(confirmed["confirmed"][0]["confidence"], 1.0);
+    assert_eq!(confirmed["stale"].as_array().map(Vec::len), Some(1));
+}
+
+#[test]
+fn undo_reverts_a_promotion() {
+    let dir = TempDir::new("agentsdb_e2e_undo");
+    write_layer_two_chunks(&dir.path().join("AGENTS.local.db"));
+
+    let promoted = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "promote",
+            "--from",
+            "AGENTS.local.db",
+            "--to",
+            "AGENTS.delta.db",
+            "--ids",
+            "1",
+        ],
+    );
+    let op = promoted["operation"]
+        .as_u64()
+        .unwrap_or_default()
+        .to_string();
+    let journal = run_ok_json(dir.path(), &["--json", "undo"]);
+    assert_eq!(journal["operations"][0]["kind"], "promote");
+    assert_eq!(journal["operations"][0]["from"], "AGENTS.local.db");
+
+    let undone = run_ok_json(dir.path(), &["--json", "undo", &op]);
+    assert_eq!(undone["retracted"], promoted["promoted"]);
+    assert_eq!(undone["restored_to"], "AGENTS.local.db");
+    assert_eq!(undone["restored"][0], 1);
+    run_err(dir.path(), &["undo", &op]);
+}
+
+#[test]
+fn export_pgvector_writes_copy_script() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_pgvector");
+    write_layer_two_chunks(&dir.path().join("AGENTS.local.db"));
+    let report = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "export",
+            "--format",
+            "pgvector",
+            "--layers",
+            "local",
+            "--table",
+            "rag.chunks",
+            "--out",
+            "chunks.sql",
+        ],
+    );
+    assert_eq!(report["records"], 2);
+    assert_eq!(report["dim"], 2);
+    let script = std::fs::read_to_string(dir.path().join("chunks.sql"))?;
+    assert!(script.contains("CREATE TABLE IF NOT EXISTS rag.chunks ("));
+    assert!(script.contains("local\t2\tnote\tb\thuman\t1\t0\t{}\t{}\t[0,1]\n"));
+    run_err(
+        dir.path(),
+        &["export", "--format", "pgvector", "--redact", "embeddings"],
+    );
+    Ok(())
+}
+
 #[test]
 fn validate_json_reports_missing_file() {
     let dir = TempDir::new("agentsdb_e2e_validate_json");
@@ -956,3 +1726,503 @@ fn destroy_json_dry_run_and_delete() {
     assert!(!dir.path().join("nested").join("AGENTS.local.db").exists());
     assert!(dir.path().join("nested").join("AGENTS.db.sig").exists());
 }
+
+#[test]
+fn repl_searches_traces_toggles_and_writes_notes() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_repl");
+    write_layer_two_chunks(&dir.path().join("AGENTS.db"));
+    run_ok(
+        dir.path(),
+        &[
+            "write",
+            "AGENTS.local.db",
+            "--scope",
+            "local",
+            "--id",
+            "7",
+            "--kind",
+            "note",
+            "--content",
+            "derived from a",
+            "--confidence",
+            "1.0",
+            "--dim",
+            "2",
+            "--source-chunk",
+            "1",
+        ],
+    );
+
+    let mut child = agentsdb()
+        .current_dir(dir.path())
+        .args(["repl", "--history", "history.txt"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    {
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| std::io::Error::other("child stdin"))?;
+        for line in [
+            "layers",
+            "k 1",
+            "search derived",
+            "trace 7",
+            "toggle local",
+            "show 7",
+            "toggle local",
+            "show 7",
+            "note remember the repl",
+            "!1",
+            "bogus",
+            "quit",
+        ] {
+            writeln!(stdin, "{line}")?;
+        }
+    }
+    let out = child.wait_with_output()?;
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+
+    assert!(stdout.contains("base   on   AGENTS.db"), "{stdout}");
+    assert!(stdout.contains("k = 1\n[local] 7 "), "{stdout}");
+    assert!(stdout.contains("[local] 7 note: derived from a\n  [base] 1 note: a"));
+    assert!(stdout.contains("local: off"));
+    assert!(stderr.contains("chunk 7 not found in the enabled layers"));
+    assert!(stdout.contains("local: on"));
+    assert!(stdout.contains("Appended id="));
+    assert!(stderr.contains("unknown command \"bogus\""));
+
+    let history = std::fs::read_to_string(dir.path().join("history.txt"))?;
+    assert_eq!(history.lines().count(), 12);
+    assert!(history.starts_with("layers\nk 1\n"));
+    assert!(history.ends_with("layers\nbogus\nquit\n"));
+    Ok(())
+}
+
+#[test]
+fn output_json_flag_works_after_the_subcommand() {
+    let dir = TempDir::new("agentsdb_e2e_output_json");
+    write_layer_two_chunks(&dir.path().join("AGENTS.db"));
+
+    let list = run_ok_json(dir.path(), &["list", "--output", "json"]);
+    assert_eq!(list[0]["path"], "AGENTS.db");
+    assert_eq!(list[0]["chunk_count"], 2);
+
+    let search = run_ok_json(
+        dir.path(),
+        &[
+            "search",
+            "--base",
+            "AGENTS.db",
+            "--query-vec",
+            "[1.0, 0.0]",
+            "-k",
+            "1",
+            "--output",
+            "json",
+        ],
+    );
+    assert_eq!(search["results"][0]["id"], 1);
+
+    let out = run_ok(dir.path(), &["list", "--output", "table"]);
+    assert!(serde_json::from_slice::<Value>(&out.stdout).is_err());
+    run_err(dir.path(), &["--json", "list", "--output", "table"]);
+}
+
+#[test]
+fn completions_script_and_dynamic_layer_and_kind_candidates() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_completions");
+    write_layer_two_chunks(&dir.path().join("AGENTS.db"));
+    std::fs::write(dir.path().join("README.md"), "# readme\n")?;
+
+    let out = run_ok(dir.path(), &["completions", "bash"]);
+    let script = String::from_utf8_lossy(&out.stdout);
+    assert!(script.contains("COMPLETE=\"bash\""), "{script}");
+    assert!(script.contains("-F _clap_complete_agentsdb agentsdb"));
+    run_err(dir.path(), &["completions", "tcsh"]);
+
+    let complete = |args: &[&str]| -> std::io::Result<String> {
+        let out = agentsdb()
+            .current_dir(dir.path())
+            .env("COMPLETE", "bash")
+            .env("_CLAP_COMPLETE_INDEX", (args.len() - 1).to_string())
+            .env("_CLAP_IFS", "\n")
+            .arg("--")
+            .args(args)
+            .output()?;
+        Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+    };
+    assert_eq!(complete(&["agentsdb", "search", "--base", ""])?, "AGENTS.db");
+    assert_eq!(
+        complete(&["agentsdb", "search", "--kind", ""])?,
+        "canonical\ndecision\ninvariant\nnote"
+    );
+    Ok(())
+}
+
+#[test]
+fn top_reports_retrievals_terms_and_writes_from_audit_log() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_top");
+    write_layer_two_chunks(&dir.path().join("AGENTS.db"));
+    let log = [
+        r#"{"ts_unix_ms":0,"tool":"agents_search","actor":"agent/1.0","ok":true,"query":"deploy steps","retrieved":[{"layer":"base","id":1}]}"#,
+        r#"{"ts_unix_ms":1,"tool":"agents_search","actor":"agent/1.0","ok":true,"query":"deploy","retrieved":[{"layer":"base","id":1}]}"#,
+        r#"{"ts_unix_ms":2,"tool":"agents_context_write","actor":"agent/1.0","ok":true}"#,
+        "not json",
+    ];
+    std::fs::write(dir.path().join("audit.ndjson"), log.join("\n"))?;
+
+    let v = run_ok_json(
+        dir.path(),
+        &[
+            "top",
+            "--audit-log",
+            "audit.ndjson",
+            "--period",
+            "day",
+            "--json",
+        ],
+    );
+    assert_eq!(v["reads"], 2);
+    assert_eq!(v["writes"], 1);
+    assert_eq!(v["malformed_lines"], 1);
+    assert_eq!(v["most_retrieved"][0]["id"], 1);
+    assert_eq!(v["most_retrieved"][0]["retrievals"], 2);
+    assert_eq!(v["never_retrieved_total"], 1);
+    assert_eq!(v["never_retrieved"][0]["id"], 2);
+    assert_eq!(v["top_terms"][0]["term"], "deploy");
+    assert_eq!(v["top_terms"][0]["count"], 2);
+    assert_eq!(v["writes_by_author"][0]["period"], "1970-01-01");
+    assert_eq!(v["writes_by_author"][0]["actor"], "agent/1.0");
+
+    let out = run_ok(dir.path(), &["top", "--audit-log", "audit.ndjson"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("2x\tbase:1\t[note] a"), "{stdout}");
+    assert!(stdout.contains("1969-12-29\tagent/1.0\t1"), "{stdout}");
+    Ok(())
+}
+
+#[test]
+fn promote_resolves_id_conflicts_by_strategy_or_prompt() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_promote_conflicts");
+    let write = |scope: &str, id: &str, content: &str| {
+        run_ok(
+            dir.path(),
+            &[
+                "write",
+                &format!("AGENTS.{scope}.db"),
+                "--scope",
+                scope,
+                "--id",
+                id,
+                "--kind",
+                "note",
+                "--content",
+                content,
+                "--confidence",
+                "1.0",
+                "--dim",
+                "2",
+            ],
+        );
+    };
+    write("local", "3", "incoming three");
+    write("local", "4", "incoming four");
+    write("local", "5", "fresh five");
+    write("delta", "3", "existing three");
+    write("delta", "4", "existing four");
+
+    let v = run_ok_json(
+        dir.path(),
+        &[
+            "promote",
+            "--from",
+            "AGENTS.local.db",
+            "--to",
+            "AGENTS.delta.db",
+            "--ids",
+            "3,5",
+            "--strategy",
+            "theirs",
+            "--json",
+        ],
+    );
+    assert_eq!(v["promoted"][1], 5);
+    assert_eq!(v["conflicts"][0]["id"], 3);
+    assert_eq!(v["conflicts"][0]["strategy"], "theirs");
+    assert!(v["conflicts"][0]["tombstone_id"].is_u64());
+
+    let mut child = agentsdb()
+        .current_dir(dir.path())
+        .args([
+            "promote",
+            "--from",
+            "AGENTS.local.db",
+            "--to",
+            "AGENTS.delta.db",
+            "--ids",
+            "4",
+            "--interactive",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| std::io::Error::other("child stdin"))?
+        .write_all(b"maybe\no\n")?;
+    let out = child.wait_with_output()?;
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("existing: [note] existing four"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("incoming: [note] incoming four"),
+        "{stderr}"
+    );
+    assert!(
+        stdout.contains("Conflict on id 4: kept the existing chunk"),
+        "{stdout}"
+    );
+
+    for (layer, content) in [("delta", "existing four"), ("local", "incoming four")] {
+        let layer = format!("AGENTS.{layer}.db");
+        let shown = run_ok_json(dir.path(), &["inspect", &layer, "--id", "4", "--json"]);
+        assert_eq!(shown["content"], content);
+    }
+    Ok(())
+}
+
+#[test]
+fn write_from_file_appends_records_in_one_batch() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_write_from_file");
+    std::fs::write(
+        dir.path().join("notes.jsonl"),
+        concat!(
+            r#"{"content": "use pnpm", "sources": ["README.md:3"]}"#,
+            "\n\n",
+            r#"{"content": "tests need docker", "kind": "invariant", "confidence": 1.0, "namespace": "api"}"#,
+            "\n",
+        ),
+    )?;
+    let v = run_ok_json(
+        dir.path(),
+        &[
+            "write",
+            "AGENTS.local.db",
+            "--scope",
+            "local",
+            "--from-file",
+            "notes.jsonl",
+            "--kind",
+            "note",
+            "--confidence",
+            "0.7",
+            "--dim",
+            "8",
+            "--json",
+        ],
+    );
+    assert_eq!(v["ids"], serde_json::json!([1, 2]));
+    let second = run_ok_json(
+        dir.path(),
+        &["inspect", "AGENTS.local.db", "--id", "2", "--json"],
+    );
+    assert_eq!(second["kind"], "invariant");
+
+    let mut child = agentsdb()
+        .current_dir(dir.path())
+        .args([
+            "write",
+            "AGENTS.local.db",
+            "--scope",
+            "local",
+            "--from-file",
+            "-",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| std::io::Error::other("child stdin"))?
+        .write_all(b"{\"content\": \"from stdin\", \"kind\": \"note\", \"confidence\": 0.5}\n")?;
+    let out = child.wait_with_output()?;
+    assert!(out.status.success());
+    assert!(
+        String::from_utf8_lossy(&out.stdout).starts_with("Appended 1 chunks to AGENTS.local.db")
+    );
+
+    std::fs::write(dir.path().join("bad.jsonl"), "{\"content\": \"x\"}\n")?;
+    let err = run_err(
+        dir.path(),
+        &[
+            "write",
+            "AGENTS.local.db",
+            "--scope",
+            "local",
+            "--from-file",
+            "bad.jsonl",
+        ],
+    );
+    assert!(String::from_utf8_lossy(&err.stderr).contains("line 1: no kind"));
+    Ok(())
+}
+
+#[test]
+fn search_assemble_emits_context_block_under_budget() {
+    let dir = TempDir::new("agentsdb_e2e_search_assemble");
+    write_layer_two_chunks(&dir.path().join("AGENTS.db"));
+
+    let out = run_ok(
+        dir.path(),
+        &["search", "--query-vec", "[1.0, 0.0]", "--assemble"],
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.starts_with("## Project context (AGENTS.db)\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("### note (base:1)\n\na\n"), "{stdout}");
+    assert!(stdout.contains("### note (base:2)\n\nb\n"), "{stdout}");
+
+    let v = run_ok_json(
+        dir.path(),
+        &[
+            "search",
+            "--query-vec",
+            "[1.0, 0.0]",
+            "--assemble",
+            "--budget-tokens",
+            "1",
+            "--json",
+        ],
+    );
+    assert_eq!(v["budget_tokens"], 1);
+    assert_eq!(v["used_tokens"], 1);
+    assert_eq!(v["chunks"].as_array().map(Vec::len), Some(1));
+    assert_eq!(v["dropped_over_budget"], 1);
+}
+
+#[test]
+fn show_reports_precedence_hidden_layers_and_trace() -> std::io::Result<()> {
+    let dir = TempDir::new("agentsdb_e2e_show");
+    write_layer_two_chunks(&dir.path().join("AGENTS.db"));
+    let schema = agentsdb_format::LayerSchema {
+        dim: 2,
+        element_type: agentsdb_format::EmbeddingElementType::F32,
+        quant_scale: 1.0,
+        layout: agentsdb_format::EmbeddingLayout::RowMajor,
+    };
+    let mut local = [agentsdb_format::ChunkInput {
+        id: 2,
+        kind: "decision".to_string(),
+        content: "local b".to_string(),
+        author: "human".to_string(),
+        confidence: 0.9,
+        created_at_unix_ms: 0,
+        embedding: vec![3.0, 4.0],
+        sources: vec![agentsdb_format::ChunkSource::ChunkId(1)],
+    }];
+    agentsdb_format::write_layer_atomic(
+        dir.path().join("AGENTS.local.db"),
+        &schema,
+        &mut local,
+        None,
+    )
+    .map_err(std::io::Error::other)?;
+
+    let v = run_ok_json(dir.path(), &["show", "2", "--json"]);
+    assert_eq!(v["winner"], "local");
+    assert_eq!(v["copies"][0]["status"], "visible");
+    assert_eq!(v["copies"][0]["embedding_norm"], 5.0);
+    assert_eq!(v["copies"][1]["layer"], "base");
+    assert_eq!(v["copies"][1]["status"], "hidden");
+    assert_eq!(v["trace"][1]["id"], 1);
+    assert_eq!(v["trace"][1]["layer"], "base");
+
+    let out = run_ok(dir.path(), &["show", "2"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Chunk 2 [decision]"), "{stdout}");
+    assert!(stdout.contains("Wins precedence: local"), "{stdout}");
+    assert!(stdout.contains("  base\thidden\t"), "{stdout}");
+
+    run_err(dir.path(), &["show", "7"]);
+    Ok(())
+}
+
+#[test]
+fn smash_dry_run_reports_splits_without_writing() {
+    let dir = TempDir::new("agentsdb_e2e_smash_dry_run");
+    let long = "Layers are append-only. ".repeat(20);
+    run_ok(
+        dir.path(),
+        &[
+            "compile",
+            "--out",
+            "AGENTS.local.db",
+            "--dim",
+            "8",
+            "--kind",
+            "note",
+            "--text",
+            &long,
+            "--text",
+            "short",
+        ],
+    );
+    let before = std::fs::read(dir.path().join("AGENTS.local.db")).expect("read layer");
+
+    let v = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "smash",
+            "--layers",
+            "local,user",
+            "--exclude-layer",
+            "user",
+            "--only",
+            "kinds=note",
+            "--limit",
+            "100",
+            "--dry-run",
+        ],
+    );
+    assert_eq!(v["dry_run"], true);
+    assert_eq!(v["total_split_count"], 1);
+    let changes = v["layers"][0]["changes"].as_array().expect("changes");
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0]["kind"], "note");
+    assert!(changes[0]["piece_lens"].as_array().expect("pieces").len() > 1);
+
+    let v = run_ok_json(
+        dir.path(),
+        &[
+            "--json",
+            "smash",
+            "--layers",
+            "local",
+            "--only",
+            "kinds=canonical",
+            "--limit",
+            "100",
+            "--dry-run",
+        ],
+    );
+    assert_eq!(v["total_split_count"], 0);
+
+    let after = std::fs::read(dir.path().join("AGENTS.local.db")).expect("read layer");
+    assert_eq!(before, after);
+}
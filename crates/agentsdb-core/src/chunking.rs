@@ -0,0 +1,445 @@
+//! Splitting long text into parts small enough to embed and retrieve well.
+//!
+//! [`split`] cuts text at the coarsest boundary that makes the parts fit: markdown headings,
+//! then fenced code blocks and paragraphs, then sentences (lines, inside code), then words.
+//! Sizes are measured with [`estimate_tokens`], which does not know any particular model's
+//! tokenizer, so leave some headroom below the model's real limit.
+
+use std::ops::Range;
+
+/// Source string prefix marking a chunk as one part of a longer text; see [`PartOf`].
+pub const PART_OF_SOURCE_PREFIX: &str = "part-of:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingOptions {
+    /// Largest part, in estimated tokens.
+    pub max_tokens: usize,
+    /// Tokens from the end of a part repeated at the start of the next one, so text near a cut
+    /// is found with its context. Capped at half of `max_tokens`.
+    pub overlap_tokens: usize,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            max_tokens: 512,
+            overlap_tokens: 32,
+        }
+    }
+}
+
+/// Where a part sits in the text it was split from, recorded as a source string
+/// `part-of:<first>#<index>/<count>` on every part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartOf {
+    /// Chunk id of the first part.
+    pub first: u32,
+    /// 1-based position of this part.
+    pub index: u32,
+    pub count: u32,
+}
+
+impl PartOf {
+    pub fn source(&self) -> String {
+        format!(
+            "{PART_OF_SOURCE_PREFIX}{}#{}/{}",
+            self.first, self.index, self.count
+        )
+    }
+
+    /// Parses a source string written by [`source`](Self::source).
+    pub fn parse(source: &str) -> Option<Self> {
+        let rest = source.strip_prefix(PART_OF_SOURCE_PREFIX)?;
+        let (first, rest) = rest.split_once('#')?;
+        let (index, count) = rest.split_once('/')?;
+        Some(Self {
+            first: first.parse().ok()?,
+            index: index.parse().ok()?,
+            count: count.parse().ok()?,
+        })
+    }
+}
+
+/// Rough token count of `text`: one token per four characters of each word, at least one per
+/// word.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.split_whitespace()
+        .map(|word| word.chars().count().div_ceil(4))
+        .sum()
+}
+
+/// A fenced code block, or the prose between two of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block<'a> {
+    pub text: &'a str,
+    pub code: bool,
+}
+
+/// Splits `text` before every markdown heading outside a code block.
+pub fn split_headings(text: &str) -> Vec<&str> {
+    heading_ranges(text)
+        .into_iter()
+        .map(|r| at(text, r))
+        .collect()
+}
+
+/// Splits `text` into fenced code blocks (fences included) and the prose around them.
+pub fn split_code_blocks(text: &str) -> Vec<Block<'_>> {
+    block_ranges(text)
+        .into_iter()
+        .map(|(r, code)| Block {
+            text: at(text, r),
+            code,
+        })
+        .collect()
+}
+
+/// Splits `text` after sentence-ending punctuation followed by whitespace, and after line
+/// breaks. Each sentence keeps its trailing whitespace, so the parts concatenate to `text`.
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    sentence_ranges(text)
+        .into_iter()
+        .map(|r| at(text, r))
+        .collect()
+}
+
+/// Splits `text` into trimmed parts of at most `options.max_tokens` estimated tokens, each
+/// starting with up to `options.overlap_tokens` of the end of the previous one. Text that
+/// already fits comes back as a single part; blank text as none.
+pub fn split(text: &str, options: &ChunkingOptions) -> Vec<String> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    let max = options.max_tokens.max(1);
+    if estimate_tokens(text) <= max {
+        return vec![text.trim().to_string()];
+    }
+
+    let mut units = Vec::new();
+    refine(text, 0..text.len(), Level::Headings, max, &mut units);
+    pack(text, &units, max, options.overlap_tokens.min(max / 2))
+        .into_iter()
+        .map(|r| at(text, r).trim().to_string())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Level {
+    Headings,
+    Blocks,
+    Paragraphs,
+    Sentences,
+    Lines,
+    Words,
+}
+
+/// Appends to `out` ranges of `text` within `range` that each fit in `max` tokens, splitting at
+/// `level` and then at finer levels where a piece is still too long.
+fn refine(text: &str, range: Range<usize>, level: Level, max: usize, out: &mut Vec<Range<usize>>) {
+    let piece = at(text, range.clone());
+    if estimate_tokens(piece) <= max {
+        out.push(range);
+        return;
+    }
+    let offset = |r: Range<usize>| r.start + range.start..r.end + range.start;
+    match level {
+        Level::Headings => {
+            for r in heading_ranges(piece) {
+                refine(text, offset(r), Level::Blocks, max, out);
+            }
+        }
+        Level::Blocks => {
+            for (r, code) in block_ranges(piece) {
+                let next = if code {
+                    Level::Lines
+                } else {
+                    Level::Paragraphs
+                };
+                refine(text, offset(r), next, max, out);
+            }
+        }
+        Level::Paragraphs => {
+            for r in paragraph_ranges(piece) {
+                refine(text, offset(r), Level::Sentences, max, out);
+            }
+        }
+        Level::Sentences => {
+            for r in sentence_ranges(piece) {
+                refine(text, offset(r), Level::Words, max, out);
+            }
+        }
+        Level::Lines => {
+            for r in line_ranges(piece) {
+                refine(text, offset(r), Level::Words, max, out);
+            }
+        }
+        Level::Words => out.extend(word_ranges(piece, max).into_iter().map(offset)),
+    }
+}
+
+/// Greedily joins consecutive `units` into ranges of at most `max` tokens, starting each range
+/// after the first with up to `overlap` tokens of units from the previous one.
+fn pack(text: &str, units: &[Range<usize>], max: usize, overlap: usize) -> Vec<Range<usize>> {
+    let tokens: Vec<usize> = units
+        .iter()
+        .map(|r| estimate_tokens(at(text, r.clone())))
+        .collect();
+    let mut parts = Vec::new();
+    let mut previous_first = 0;
+    let mut next = 0;
+    while next < units.len() {
+        let mut first = next;
+        let mut used = 0;
+        while first > previous_first + 1
+            && used + tokens[first - 1] <= overlap
+            && used + tokens[first - 1] + tokens[next] <= max
+        {
+            first -= 1;
+            used += tokens[first];
+        }
+        let mut end = next;
+        while end < units.len() && (end == next || used + tokens[end] <= max) {
+            used += tokens[end];
+            end += 1;
+        }
+        parts.push(units[first].start..units[end - 1].end);
+        previous_first = first;
+        next = end;
+    }
+    parts
+}
+
+/// `text[range]`; the ranges here always fall on character boundaries.
+fn at(text: &str, range: Range<usize>) -> &str {
+    text.get(range).unwrap_or_default()
+}
+
+fn line_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut start = 0;
+    text.split_inclusive('\n')
+        .map(|line| {
+            let range = start..start + line.len();
+            start = range.end;
+            range
+        })
+        .collect()
+}
+
+/// The fence character of a line opening or closing a fenced code block.
+fn fence(line: &str) -> Option<char> {
+    let trimmed = line.trim_start();
+    ['`', '~']
+        .into_iter()
+        .find(|&c| trimmed.starts_with(&[c; 3][..]))
+}
+
+/// Tracks whether a line is inside a fenced code block.
+#[derive(Debug, Default)]
+struct Fences {
+    open: Option<char>,
+}
+
+impl Fences {
+    /// Feeds `line`; returns whether it opens or closes a block.
+    fn toggle(&mut self, line: &str) -> bool {
+        match (self.open, fence(line)) {
+            (None, Some(c)) => {
+                self.open = Some(c);
+                true
+            }
+            (Some(open), Some(c)) if open == c && line.trim().chars().all(|d| d == c) => {
+                self.open = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn is_heading(line: &str) -> bool {
+    let rest = line.trim_start_matches('#');
+    let level = line.len() - rest.len();
+    (1..=6).contains(&level) && (rest.starts_with([' ', '\t']) || rest.trim().is_empty())
+}
+
+fn heading_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut fences = Fences::default();
+    let mut start = 0;
+    for line in line_ranges(text) {
+        let s = at(text, line.clone());
+        if fences.toggle(s) || fences.open.is_some() {
+            continue;
+        }
+        if is_heading(s) && line.start > start {
+            ranges.push(start..line.start);
+            start = line.start;
+        }
+    }
+    ranges.push(start..text.len());
+    ranges
+}
+
+fn block_ranges(text: &str) -> Vec<(Range<usize>, bool)> {
+    let mut blocks = Vec::new();
+    let mut fences = Fences::default();
+    let mut start = 0;
+    for line in line_ranges(text) {
+        let opening = fences.open.is_none();
+        if !fences.toggle(at(text, line.clone())) {
+            continue;
+        }
+        if opening {
+            if line.start > start {
+                blocks.push((start..line.start, false));
+            }
+            start = line.start;
+        } else {
+            blocks.push((start..line.end, true));
+            start = line.end;
+        }
+    }
+    if start < text.len() {
+        blocks.push((start..text.len(), fences.open.is_some()));
+    }
+    blocks
+}
+
+fn paragraph_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut previous_blank = false;
+    for line in line_ranges(text) {
+        let blank = at(text, line.clone()).trim().is_empty();
+        if !blank && previous_blank && line.start > start {
+            ranges.push(start..line.start);
+            start = line.start;
+        }
+        previous_blank = blank;
+    }
+    ranges.push(start..text.len());
+    ranges
+}
+
+fn sentence_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if !matches!(c, '.' | '!' | '?' | '\n') {
+            continue;
+        }
+        let mut end = i + c.len_utf8();
+        if c != '\n' {
+            while let Some((j, d)) = chars.next_if(|&(_, d)| matches!(d, '"' | '\'' | ')' | ']')) {
+                end = j + d.len_utf8();
+            }
+            if !chars.peek().is_some_and(|&(_, d)| d.is_whitespace()) {
+                continue;
+            }
+        }
+        while let Some((j, d)) = chars.next_if(|&(_, d)| d.is_whitespace()) {
+            end = j + d.len_utf8();
+        }
+        ranges.push(start..end);
+        start = end;
+    }
+    if start < text.len() {
+        ranges.push(start..text.len());
+    }
+    ranges
+}
+
+/// Words with their trailing whitespace, with words longer than `max` tokens cut into pieces.
+fn word_ranges(text: &str, max: usize) -> Vec<Range<usize>> {
+    let max_chars = max.saturating_mul(4);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for word in text.split_inclusive(char::is_whitespace) {
+        let mut piece_start = start;
+        for (count, (i, _)) in word.char_indices().enumerate() {
+            if count > 0 && count % max_chars == 0 {
+                ranges.push(piece_start..start + i);
+                piece_start = start + i;
+            }
+        }
+        start += word.len();
+        ranges.push(piece_start..start);
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splitters_cover_the_text() {
+        let text = "# One\nIntro. More text!\n\n```sh\n# not a heading\n```\n## Two\nEnd";
+        assert_eq!(
+            split_headings(text),
+            vec![
+                "# One\nIntro. More text!\n\n```sh\n# not a heading\n```\n",
+                "## Two\nEnd"
+            ]
+        );
+        let blocks = split_code_blocks(text);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[1].text, "```sh\n# not a heading\n```\n");
+        assert!(blocks[1].code && !blocks[0].code && !blocks[2].code);
+        assert_eq!(
+            split_sentences("Hi there. \"Quoted.\" e.g.x ok\nlast"),
+            vec!["Hi there. ", "\"Quoted.\" ", "e.g.x ok\n", "last"]
+        );
+    }
+
+    #[test]
+    fn long_text_is_split_into_overlapping_parts_that_fit() {
+        let sentence = "The release branch is cut from main every second Tuesday. ";
+        let text = format!(
+            "# Releases\n{}\n# Hotfixes\n{}",
+            sentence.repeat(20),
+            sentence.repeat(5)
+        );
+        let options = ChunkingOptions {
+            max_tokens: 60,
+            overlap_tokens: 15,
+        };
+        let parts = split(&text, &options);
+        assert!(parts.len() > 2);
+        assert!(parts.iter().all(|p| estimate_tokens(p) <= 60));
+        assert!(parts[0].starts_with("# Releases"));
+        assert!(
+            parts[1].starts_with("The release branch"),
+            "the next part repeats the last sentence"
+        );
+        assert!(parts
+            .iter()
+            .any(|p| p.starts_with("# Hotfixes") || p.contains("\n# Hotfixes")));
+
+        let word = "x".repeat(100);
+        let parts = split(
+            &word,
+            &ChunkingOptions {
+                max_tokens: 10,
+                overlap_tokens: 0,
+            },
+        );
+        assert_eq!(parts.len(), 3);
+        assert_eq!(split("  short  ", &options), vec!["short"]);
+        assert!(split(" \n ", &options).is_empty());
+    }
+
+    #[test]
+    fn part_of_round_trips() {
+        let part = PartOf {
+            first: 7,
+            index: 2,
+            count: 3,
+        };
+        assert_eq!(part.source(), "part-of:7#2/3");
+        assert_eq!(PartOf::parse(&part.source()), Some(part));
+        assert_eq!(PartOf::parse("part-of:7"), None);
+    }
+}
@@ -17,6 +17,9 @@ pub enum Error {
 
     #[error(transparent)]
     Permission(#[from] PermissionError),
+
+    #[error(transparent)]
+    Trust(#[from] TrustError),
 }
 
 #[derive(Debug, Error)]
@@ -96,4 +99,19 @@ pub enum PermissionError {
     /// This error occurs when an attempt is made to write to a layer that is not designated as writable.
     #[error("writes are not permitted to {path:?}")]
     WriteNotPermitted { path: PathBuf },
+
+    /// The path is not the layer file the write policy assigns to `scope`, or the scope is
+    /// unknown or not writable.
+    #[error("scope {scope:?} does not write to {path:?}")]
+    ScopeNotPermitted { scope: String, path: PathBuf },
+}
+
+#[derive(Debug, Error)]
+pub enum TrustError {
+    /// Represents a layer refused by the trust policy.
+    ///
+    /// This error occurs when a layer the policy covers is unsigned, fails signature
+    /// verification, or is signed by a key the policy does not trust.
+    #[error("untrusted layer {path:?}: {reason}")]
+    Untrusted { path: PathBuf, reason: String },
 }
@@ -1,5 +1,15 @@
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// `format` of a JSON bundle with [`ExportBundleV1`] layout.
+pub const EXPORT_FORMAT_V1: &str = "agentsdb.export.v1";
+/// `format` of the header record of an NDJSON export made of [`ExportNdjsonRecordV1`]s.
+pub const EXPORT_NDJSON_FORMAT_V1: &str = "agentsdb.export.ndjson.v1";
+/// `format` of a JSON bundle with [`ExportBundleV2`] layout.
+pub const EXPORT_FORMAT_V2: &str = "agentsdb.export.v2";
+/// `format` of the header record of an NDJSON export made of [`ExportNdjsonRecordV2`]s.
+pub const EXPORT_NDJSON_FORMAT_V2: &str = "agentsdb.export.ndjson.v2";
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
@@ -110,3 +120,146 @@ pub enum ExportNdjsonRecordV1 {
         chunk: ExportChunkV1,
     },
 }
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ExportBundleV2 {
+    /// A complete AGENTS.db export bundle (version 2).
+    ///
+    /// Unlike version 1, which copies each chunk's sources verbatim, version 2 separates them
+    /// into plain source strings, typed relationships between chunks and chunk metadata, and
+    /// records a hash of every exported layer file.
+    pub format: String, // "agentsdb.export.v2"
+    pub tool: ExportToolInfo,
+    pub layers: Vec<ExportLayerV2>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ExportLayerV2 {
+    /// Layer path (as referenced by the caller; typically relative to a root).
+    pub path: String,
+    /// Optional logical layer id: "base" | "user" | "delta" | "local".
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub layer: Option<String>,
+    pub schema: ExportLayerSchemaV1,
+    /// Raw JSON string (if present in the layer file).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub layer_metadata_json: Option<String>,
+    /// Where the chunks came from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub provenance: Option<ExportLayerProvenanceV2>,
+    pub chunks: Vec<ExportChunkV2>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportLayerProvenanceV2 {
+    /// SHA-256 of the layer file as it was exported (64 lowercase hex chars).
+    pub file_sha256: String,
+    /// Chunk records in that file, including any not exported (e.g. filtered by namespace).
+    pub chunk_count: u64,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ExportChunkV2 {
+    pub id: u32,
+    pub kind: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub content: Option<String>,
+    pub author: String, // "human" | "mcp"
+    pub confidence: f32,
+    pub created_at_unix_ms: u64,
+    /// Free-form source strings, e.g. `path/to/file:12`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sources: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub relationships: Vec<ExportRelationshipV2>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: ExportChunkMetadataV2,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub embedding: Option<Vec<f32>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub content_sha256: Option<String>, // 64 lowercase hex chars
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum ExportRelationshipV2 {
+    /// The chunk was derived from `chunk_id` (a chunk-id source).
+    DerivedFrom { chunk_id: u32 },
+    /// The chunk is a tombstone retracting `chunk_id`.
+    Retracts { chunk_id: u32 },
+    /// The chunk is a revision of `chunk_id`.
+    Supersedes { chunk_id: u32 },
+    /// The chunk cited `chunk_id`, which no longer resolves.
+    BrokenSource { chunk_id: u32 },
+    /// The chunk is part `index` of `count` of a text whose first part is `chunk_id`.
+    PartOf {
+        chunk_id: u32,
+        index: u32,
+        count: u32,
+    },
+}
+
+impl ExportRelationshipV2 {
+    /// The other chunk of the relationship.
+    pub const fn chunk_id(&self) -> u32 {
+        match *self {
+            Self::DerivedFrom { chunk_id }
+            | Self::Retracts { chunk_id }
+            | Self::Supersedes { chunk_id }
+            | Self::BrokenSource { chunk_id }
+            | Self::PartOf { chunk_id, .. } => chunk_id,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportChunkMetadataV2 {
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub namespace: Option<String>,
+    /// Language tag, e.g. `de-AT`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub language: Option<String>,
+    /// Metadata keys this version does not model; each is stored as a `<key>:<value>` source.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub other: BTreeMap<String, String>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum ExportNdjsonRecordV2 {
+    /// A single record in the NDJSON export format (version 2); see [`ExportNdjsonRecordV1`].
+    #[cfg_attr(feature = "serde", serde(rename = "header"))]
+    Header {
+        format: String, // "agentsdb.export.ndjson.v2"
+        tool: ExportToolInfo,
+    },
+    #[cfg_attr(feature = "serde", serde(rename = "layer"))]
+    Layer {
+        path: String,
+        #[cfg_attr(feature = "serde", serde(default))]
+        layer: Option<String>,
+        schema: ExportLayerSchemaV1,
+        #[cfg_attr(feature = "serde", serde(default))]
+        layer_metadata_json: Option<String>,
+        #[cfg_attr(feature = "serde", serde(default))]
+        provenance: Option<ExportLayerProvenanceV2>,
+    },
+    #[cfg_attr(feature = "serde", serde(rename = "chunk"))]
+    Chunk {
+        layer_path: String,
+        chunk: ExportChunkV2,
+    },
+}
@@ -0,0 +1,231 @@
+//! Language tags on chunks.
+//!
+//! A chunk's language is recorded as a source string `lang:<tag>`, where the tag is a BCP 47
+//! language tag such as `en`, `de-AT` or `zh-Hant`. [`detect`] guesses the language of a text
+//! without any model: by script for non-Latin text, and by common function words for the Latin
+//! languages it knows. It returns `None` rather than guess on short or mixed text.
+
+/// Source string prefix (followed by a language tag) recording the language of a chunk.
+pub const LANGUAGE_SOURCE_PREFIX: &str = "lang:";
+
+/// `tag` in canonical case (`de-at` becomes `de-AT`, `ZH_hant` becomes `zh-Hant`), or `None` if
+/// it is not a well-formed language tag.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    let mut out = String::with_capacity(tag.len());
+    for (i, subtag) in tag.split(['-', '_']).enumerate() {
+        let len = subtag.chars().count();
+        if !(1..=8).contains(&len) || !subtag.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        if i == 0 {
+            if !(2..=3).contains(&len) || !subtag.chars().all(|c| c.is_ascii_alphabetic()) {
+                return None;
+            }
+            out.push_str(&subtag.to_ascii_lowercase());
+            continue;
+        }
+        out.push('-');
+        match len {
+            // Region, e.g. `AT`.
+            2 => out.push_str(&subtag.to_ascii_uppercase()),
+            // Script, e.g. `Hant`.
+            4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                let mut chars = subtag.chars();
+                out.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+                out.push_str(&chars.as_str().to_ascii_lowercase());
+            }
+            _ => out.push_str(&subtag.to_ascii_lowercase()),
+        }
+    }
+    Some(out)
+}
+
+/// The primary language subtag of `tag` (`de` for `de-AT`).
+pub fn primary_subtag(tag: &str) -> &str {
+    tag.split(['-', '_']).next().unwrap_or(tag)
+}
+
+/// Whether a chunk tagged `tag` matches the language filter `wanted`: the same tag, or a more
+/// specific one (`de` matches `de-AT`, but `de-AT` does not match `de`). Case is ignored.
+pub fn matches(tag: &str, wanted: &str) -> bool {
+    let tag = tag.to_ascii_lowercase().replace('_', "-");
+    let wanted = wanted.trim().to_ascii_lowercase().replace('_', "-");
+    tag == wanted
+        || tag
+            .strip_prefix(&wanted)
+            .is_some_and(|rest| rest.starts_with('-'))
+}
+
+/// Fewest letters [`detect`] will look at.
+const MIN_LETTERS: usize = 12;
+
+/// Function words of the Latin-script languages [`detect`] tells apart.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "of", "to", "in", "that", "it", "for", "with", "this",
+            "not", "be", "on", "use", "when", "should",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "mit", "ein", "eine", "für", "auf", "wir",
+            "wenn", "sich", "auch", "werden", "immer",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "est", "des", "une", "pour", "pas", "dans", "avec", "que",
+            "qui", "sur", "nous", "toujours",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "y", "es", "del", "una", "para", "con", "que", "por", "no",
+            "siempre", "usar", "cuando", "como",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "gli", "della", "che", "è", "per", "non", "una", "con", "sono", "sempre",
+            "quando", "come", "anche",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "as", "e", "é", "do", "da", "uma", "para", "com", "não", "que", "sempre",
+            "quando", "usar",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "is", "niet", "van", "voor", "met", "op", "wij", "altijd",
+            "wanneer", "ook",
+        ],
+    ),
+];
+
+/// Best guess at the language of `text`, as a language tag.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let mut latin = 0usize;
+    let mut letters = 0usize;
+    let mut scripts: Vec<(&'static str, usize)> = Vec::new();
+    let mut kana = false;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let script = match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' => {
+                latin += 1;
+                continue;
+            }
+            '\u{3040}'..='\u{30FF}' => {
+                kana = true;
+                "ja"
+            }
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => "zh",
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => "ko",
+            '\u{0400}'..='\u{04FF}' => "ru",
+            '\u{0370}'..='\u{03FF}' => "el",
+            '\u{0590}'..='\u{05FF}' => "he",
+            '\u{0600}'..='\u{06FF}' => "ar",
+            '\u{0900}'..='\u{097F}' => "hi",
+            '\u{0E00}'..='\u{0E7F}' => "th",
+            _ => continue,
+        };
+        match scripts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, n)) => *n += 1,
+            None => scripts.push((script, 1)),
+        }
+    }
+    if letters < MIN_LETTERS {
+        return None;
+    }
+
+    // Japanese mixes kanji with kana; kanji alone reads as Chinese.
+    let non_latin: usize = scripts.iter().map(|(_, n)| n).sum();
+    if non_latin * 2 > letters {
+        if kana {
+            return Some("ja");
+        }
+        return scripts.iter().max_by_key(|(_, n)| *n).map(|(s, _)| *s);
+    }
+    if latin * 2 <= letters {
+        return None;
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let mut scores: Vec<(&'static str, usize)> = STOPWORDS
+        .iter()
+        .map(|(lang, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|w| stopwords.contains(&w.as_str()))
+                .count();
+            (*lang, hits)
+        })
+        .collect();
+    scores.sort_by_key(|(_, hits)| std::cmp::Reverse(*hits));
+    match scores.as_slice() {
+        [(lang, best), (_, second), ..] if *best >= 2 && *best > *second => Some(lang),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_normalized_and_matched_by_prefix() {
+        assert_eq!(normalize_tag(" de_at ").as_deref(), Some("de-AT"));
+        assert_eq!(normalize_tag("ZH-hant-tw").as_deref(), Some("zh-Hant-TW"));
+        assert_eq!(normalize_tag("english"), None);
+        assert_eq!(normalize_tag("en us"), None);
+        assert_eq!(normalize_tag(""), None);
+
+        assert_eq!(primary_subtag("de-AT"), "de");
+        assert!(matches("de-AT", "de"));
+        assert!(matches("de", "DE"));
+        assert!(!matches("de", "de-AT"));
+        assert!(!matches("deu", "de"));
+    }
+
+    #[test]
+    fn detects_common_languages() {
+        assert_eq!(
+            detect("Always run the formatter before you commit, and use pnpm for installs."),
+            Some("en")
+        );
+        assert_eq!(
+            detect("Wir verwenden immer pnpm und nicht npm, wenn die Abhängigkeiten sich ändern."),
+            Some("de")
+        );
+        assert_eq!(
+            detect("Nous utilisons toujours pnpm pour les dépendances et pas npm dans le dépôt."),
+            Some("fr")
+        );
+        assert_eq!(
+            detect("依存関係のインストールには常にpnpmを使います。"),
+            Some("ja")
+        );
+        assert_eq!(detect("安装依赖时请始终使用而不是其他工具。"), Some("zh"));
+        assert_eq!(
+            detect("Всегда используйте pnpm для установки зависимостей."),
+            Some("ru")
+        );
+        assert_eq!(detect("pnpm"), None);
+        assert_eq!(detect("cargo build --workspace --release"), None);
+    }
+}
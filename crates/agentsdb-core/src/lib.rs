@@ -3,7 +3,9 @@
 //! This crate defines the fundamental types, errors, and embedding mechanisms
 //! used throughout the AGENTS.db ecosystem.
 
+pub mod chunking;
 pub mod embed;
 pub mod error;
 pub mod export;
+pub mod language;
 pub mod types;
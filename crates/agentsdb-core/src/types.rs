@@ -18,8 +18,10 @@ impl ChunkId {
 pub enum LayerId {
     /// Represents the identifier for different types of AGENTS.db layers.
     ///
-    /// The variants are ordered by precedence, with `Local` having the highest precedence.
+    /// The variants are ordered by precedence, with `Session` having the highest precedence.
     // Ord is used for deterministic tie-breaks; variants are in precedence order.
+    /// Ephemeral per-session working memory (see the MCP `agents_session_note` tool).
+    Session,
     Local,
     User,
     Delta,
@@ -65,8 +67,27 @@ pub struct Chunk {
 pub struct SearchFilters {
     /// Represents criteria for filtering search results.
     ///
-    /// Currently, this includes filtering by chunk `kind`.
+    /// Currently, this includes filtering by chunk `kind`, namespace, language and confidence.
     pub kinds: Vec<String>,
+    /// Only chunks in this namespace (carrying a `namespace:<name>` source string).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub namespace: Option<String>,
+    /// Only chunks in this language (carrying a `lang:<tag>` source string). A primary tag such
+    /// as `de` also matches regional variants such as `de-AT`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub language: Option<String>,
+    /// Only chunks with at least this confidence.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub min_confidence: Option<f32>,
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
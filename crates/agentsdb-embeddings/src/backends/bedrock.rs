@@ -2,7 +2,7 @@
 
 use anyhow::Context;
 use std::collections::BTreeMap;
-use crate::embedder::{Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm};
+use crate::embedder::{EmbedPurpose, Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm};
 use super::common::{ensure_dim, collect_headers};
 
 pub fn bedrock_embedder(
@@ -221,6 +221,25 @@ impl Embedder for BedrockEmbedder {
     }
 
     fn embed(&self, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        self.request_embeddings(inputs, None)
+    }
+
+    fn embed_for(&self, purpose: EmbedPurpose, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let input_type = match purpose {
+            EmbedPurpose::Query => "search_query",
+            EmbedPurpose::Document => "search_document",
+        };
+        self.request_embeddings(inputs, Some(input_type))
+    }
+}
+
+impl BedrockEmbedder {
+    /// Calls the embeddings API, passing `input_type` when embedding for a specific purpose.
+    fn request_embeddings(
+        &self,
+        inputs: &[String],
+        input_type: Option<&str>,
+    ) -> anyhow::Result<Vec<Vec<f32>>> {
         let model = self
             .profile
             .model
@@ -235,6 +254,7 @@ impl Embedder for BedrockEmbedder {
                 "endpoint": format!("/model/{}/invoke", model),
                 "model": model,
                 "input_count": inputs.len(),
+                "input_type": input_type,
             }));
         }
 
@@ -248,7 +268,7 @@ impl Embedder for BedrockEmbedder {
             // Cohere Embeddings format
             serde_json::json!({
                 "texts": inputs,
-                "input_type": "search_document"
+                "input_type": input_type.unwrap_or("search_document")
             })
         } else {
             // Generic format - try inputText
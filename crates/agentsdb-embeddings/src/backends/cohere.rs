@@ -2,7 +2,7 @@
 
 use anyhow::Context;
 use std::collections::BTreeMap;
-use crate::embedder::{Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm};
+use crate::embedder::{EmbedPurpose, Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm};
 use super::common::{ensure_dim, require_env, collect_headers};
 
 pub fn cohere_embedder(
@@ -71,6 +71,25 @@ impl Embedder for CohereEmbedder {
     }
 
     fn embed(&self, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        self.request_embeddings(inputs, None)
+    }
+
+    fn embed_for(&self, purpose: EmbedPurpose, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let input_type = match purpose {
+            EmbedPurpose::Query => "search_query",
+            EmbedPurpose::Document => "search_document",
+        };
+        self.request_embeddings(inputs, Some(input_type))
+    }
+}
+
+impl CohereEmbedder {
+    /// Calls the embeddings API, passing `input_type` when embedding for a specific purpose.
+    fn request_embeddings(
+        &self,
+        inputs: &[String],
+        input_type: Option<&str>,
+    ) -> anyhow::Result<Vec<Vec<f32>>> {
         let model = self
             .profile
             .model
@@ -83,13 +102,19 @@ impl Embedder for CohereEmbedder {
                 "endpoint": "/v1/embed",
                 "model": model,
                 "input_count": inputs.len(),
+                "input_type": input_type,
             }));
         }
 
+        let mut body = serde_json::json!({ "model": model, "texts": inputs });
+        if let Some(input_type) = input_type {
+            body["input_type"] = serde_json::Value::from(input_type);
+        }
+
         let response = ureq::post(&url)
             .set("authorization", &format!("Bearer {}", self.api_key))
             .set("content-type", "application/json")
-            .send_json(serde_json::json!({ "model": model, "texts": inputs }))
+            .send_json(body)
             .context("cohere embeddings request")?;
 
         let headers = collect_headers(
@@ -117,7 +142,7 @@ impl Embedder for CohereEmbedder {
         }
         if let Some(obj) = raw.as_object() {
             let mut meta = serde_json::Map::new();
-            for k in ["model"] {
+            for k in ["model", "meta"] {
                 if let Some(v) = obj.get(k) {
                     meta.insert(k.to_string(), v.clone());
                 }
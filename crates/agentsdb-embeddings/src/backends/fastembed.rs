@@ -1,7 +1,9 @@
 //! FastEmbed (ONNX Runtime) local embedding backend.
 
 use anyhow::Context;
-use crate::embedder::{Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm};
+use crate::embedder::{
+    with_instruction_prefix, EmbedPurpose, Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm,
+};
 use super::common::{ensure_dim, hex_lower};
 
 pub fn local_fastembed_embedder(
@@ -131,6 +133,14 @@ impl Embedder for FastembedEmbedder {
         }
         Ok(out)
     }
+
+    fn embed_for(&self, purpose: EmbedPurpose, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        // User-supplied ONNX models may be instruction-tuned (E5/BGE) and expect prefixes.
+        match with_instruction_prefix(self.profile.model.as_deref(), purpose, inputs) {
+            Some(prefixed) => self.embed(&prefixed),
+            None => self.embed(inputs),
+        }
+    }
 }
 
 fn parse_fastembed_model(model: &str) -> anyhow::Result<fastembed::EmbeddingModel> {
@@ -2,7 +2,7 @@
 
 use anyhow::Context;
 use std::collections::BTreeMap;
-use crate::embedder::{Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm};
+use crate::embedder::{EmbedPurpose, Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm};
 use super::common::{ensure_dim, require_env, collect_headers};
 
 pub fn gemini_embedder(
@@ -71,6 +71,25 @@ impl Embedder for GeminiEmbedder {
     }
 
     fn embed(&self, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        self.request_embeddings(inputs, None)
+    }
+
+    fn embed_for(&self, purpose: EmbedPurpose, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let task_type = match purpose {
+            EmbedPurpose::Query => "RETRIEVAL_QUERY",
+            EmbedPurpose::Document => "RETRIEVAL_DOCUMENT",
+        };
+        self.request_embeddings(inputs, Some(task_type))
+    }
+}
+
+impl GeminiEmbedder {
+    /// Calls the embeddings API, passing `task_type` when embedding for a specific purpose.
+    fn request_embeddings(
+        &self,
+        inputs: &[String],
+        task_type: Option<&str>,
+    ) -> anyhow::Result<Vec<Vec<f32>>> {
         let model = self
             .profile
             .model
@@ -85,19 +104,23 @@ impl Embedder for GeminiEmbedder {
                 "endpoint": format!("/v1/models/{}:embedContent", model),
                 "model": model,
                 "input_count": inputs.len(),
+                "task_type": task_type,
             }));
         }
 
         // Gemini expects requests with content array
         let mut embeddings = Vec::new();
         for input in inputs {
-            let request_body = serde_json::json!({
+            let mut request_body = serde_json::json!({
                 "content": {
                     "parts": [{
                         "text": input
                     }]
                 }
             });
+            if let Some(task_type) = task_type {
+                request_body["taskType"] = serde_json::Value::from(task_type);
+            }
 
             let response = ureq::post(&url)
                 .set("content-type", "application/json")
@@ -2,7 +2,7 @@
 
 use anyhow::Context;
 use std::collections::BTreeMap;
-use crate::embedder::{Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm};
+use crate::embedder::{EmbedPurpose, Embedder, EmbedderMetadata, EmbeddingProfile, OutputNorm};
 use super::common::{ensure_dim, require_env, collect_headers};
 
 pub fn voyage_embedder(
@@ -71,6 +71,25 @@ impl Embedder for VoyageEmbedder {
     }
 
     fn embed(&self, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        self.request_embeddings(inputs, None)
+    }
+
+    fn embed_for(&self, purpose: EmbedPurpose, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let input_type = match purpose {
+            EmbedPurpose::Query => "query",
+            EmbedPurpose::Document => "document",
+        };
+        self.request_embeddings(inputs, Some(input_type))
+    }
+}
+
+impl VoyageEmbedder {
+    /// Calls the embeddings API, passing `input_type` when embedding for a specific purpose.
+    fn request_embeddings(
+        &self,
+        inputs: &[String],
+        input_type: Option<&str>,
+    ) -> anyhow::Result<Vec<Vec<f32>>> {
         let model = self
             .profile
             .model
@@ -83,13 +102,19 @@ impl Embedder for VoyageEmbedder {
                 "endpoint": "/v1/embeddings",
                 "model": model,
                 "input_count": inputs.len(),
+                "input_type": input_type,
             }));
         }
 
+        let mut body = serde_json::json!({ "model": model, "input": inputs });
+        if let Some(input_type) = input_type {
+            body["input_type"] = serde_json::Value::from(input_type);
+        }
+
         let response = ureq::post(&url)
             .set("authorization", &format!("Bearer {}", self.api_key))
             .set("content-type", "application/json")
-            .send_json(serde_json::json!({ "model": model, "input": inputs }))
+            .send_json(body)
             .context("voyage embeddings request")?;
 
         let headers = collect_headers(
@@ -83,6 +83,23 @@ pub fn cache_key_hex(profile: &EmbeddingProfile, content_utf8: &str) -> anyhow::
     Ok(hex_lower(&digest))
 }
 
+/// Cache key for an embedding computed for `purpose`.
+///
+/// Document embeddings share the plain key so caches populated before purposes existed stay
+/// valid; query embeddings get their own key because asymmetric models embed them differently.
+pub fn cache_key_hex_for(
+    profile: &EmbeddingProfile,
+    purpose: crate::embedder::EmbedPurpose,
+    content_utf8: &str,
+) -> anyhow::Result<String> {
+    match purpose {
+        crate::embedder::EmbedPurpose::Document => cache_key_hex(profile, content_utf8),
+        crate::embedder::EmbedPurpose::Query => {
+            cache_key_hex(profile, &format!("query\0{content_utf8}"))
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DiskEmbeddingCache {
     dir: PathBuf,
@@ -232,7 +249,7 @@ fn atomic_write(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
     }
 }
 
-fn hex_lower(bytes: &[u8]) -> String {
+pub(crate) fn hex_lower(bytes: &[u8]) -> String {
     const HEX: &[u8; 16] = b"0123456789abcdef";
     let mut out = vec![0u8; bytes.len() * 2];
     for (i, b) in bytes.iter().enumerate() {
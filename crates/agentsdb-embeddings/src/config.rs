@@ -2,9 +2,13 @@ use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+use agentsdb_core::language::primary_subtag;
+
 use crate::cache::DiskEmbeddingCache;
-use crate::embedder::Embedder;
+use crate::embedder::{EmbedPurpose, Embedder};
 use crate::hash::HashEmbedder;
+use crate::usage::EmbeddingUsage;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 pub const KIND_OPTIONS: &str = "options";
 
@@ -70,12 +74,41 @@ pub struct EmbeddingOptionsPatch {
     pub api_key_env: Option<String>,
     pub cache_enabled: Option<bool>,
     pub cache_dir: Option<String>,
+    /// Language tags of the project's content; picks the model from `language_models`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<String>>,
+    /// Model per language group: a comma-separated list of language tags (e.g. `"de,fr"`), or
+    /// `"*"` for any other mix of languages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_models: Option<BTreeMap<String, String>>,
+}
+
+/// Defaults for `agentsdb search`; see `crate::settings` for the schema.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchOptionsPatch {
+    pub k: Option<usize>,
+    pub mode: Option<String>,
+    pub use_index: Option<bool>,
+}
+
+/// Defaults for `agentsdb serve`; see `crate::settings` for the schema.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerOptionsPatch {
+    pub max_in_flight: Option<usize>,
+    pub read_only: Option<bool>,
+    pub no_propose: Option<bool>,
+    pub write_scopes: Option<Vec<String>>,
+    pub audit_reads: Option<bool>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct OptionsRecord {
     pub embedding: Option<EmbeddingOptionsPatch>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<SearchOptionsPatch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<ServerOptionsPatch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub checksum_allowlist: Option<ChecksumAllowlistRecord>,
 }
 
@@ -91,14 +124,81 @@ pub struct ResolvedEmbeddingOptions {
     pub api_key_env: Option<String>,
     pub cache_enabled: bool,
     pub cache_dir: Option<String>,
+    pub languages: Vec<String>,
+    pub language_models: BTreeMap<String, String>,
     pub checksum_allowlist: BTreeMap<ModelRevision, String>,
 }
 
 impl ResolvedEmbeddingOptions {
+    /// Overwrites every field the patch sets.
+    pub fn apply_patch(&mut self, patch: EmbeddingOptionsPatch) {
+        if let Some(backend) = patch.backend {
+            self.backend = backend;
+        }
+        if patch.model.is_some() {
+            self.model = patch.model;
+        }
+        if patch.revision.is_some() {
+            self.revision = patch.revision;
+        }
+        if patch.model_path.is_some() {
+            self.model_path = patch.model_path;
+        }
+        if patch.model_sha256.is_some() {
+            self.model_sha256 = patch.model_sha256;
+        }
+        if patch.dim.is_some() {
+            self.dim = patch.dim;
+        }
+        if patch.api_base.is_some() {
+            self.api_base = patch.api_base;
+        }
+        if patch.api_key_env.is_some() {
+            self.api_key_env = patch.api_key_env;
+        }
+        if patch.cache_enabled.is_some() {
+            self.cache_enabled = patch.cache_enabled.unwrap_or(false);
+        }
+        if patch.cache_dir.is_some() {
+            self.cache_dir = patch.cache_dir;
+        }
+        if let Some(languages) = patch.languages {
+            self.languages = languages;
+        }
+        if let Some(language_models) = patch.language_models {
+            self.language_models = language_models;
+        }
+    }
+
+    /// The model `language_models` selects for `languages`: the first group listing every
+    /// configured language (by primary subtag), else the `*` group. `None` when no languages
+    /// are configured or no group applies, in which case `model` is used.
+    pub fn language_model(&self) -> Option<&str> {
+        if self.languages.is_empty() {
+            return None;
+        }
+        let covers = |group: &str| {
+            self.languages.iter().all(|lang| {
+                let lang = primary_subtag(lang);
+                group
+                    .split(',')
+                    .any(|g| primary_subtag(g.trim()).eq_ignore_ascii_case(lang))
+            })
+        };
+        self.language_models
+            .iter()
+            .find(|(group, _)| group.as_str() != "*" && covers(group))
+            .or_else(|| self.language_models.get_key_value("*"))
+            .map(|(_, model)| model.as_str())
+    }
+
     pub fn into_embedder(
-        self,
+        mut self,
         fallback_dim: usize,
     ) -> anyhow::Result<Box<dyn Embedder + Send + Sync>> {
+        if let Some(model) = self.language_model() {
+            self.model = Some(model.to_string());
+        }
         let dim = self.dim.unwrap_or(fallback_dim);
         let inner: Box<dyn Embedder + Send + Sync> = match self.backend.as_str() {
             "hash" => Box::new(HashEmbedder::new(dim)),
@@ -299,6 +399,11 @@ impl ResolvedEmbeddingOptions {
             ),
         };
 
+        let inner: Box<dyn Embedder + Send + Sync> = Box::new(MeteredEmbedder {
+            inner,
+            usage: std::sync::Mutex::new(EmbeddingUsage::default()),
+        });
+
         if !self.cache_enabled {
             return Ok(inner);
         }
@@ -308,13 +413,79 @@ impl ResolvedEmbeddingOptions {
             None => DiskEmbeddingCache::default_dir().context("resolve default cache dir")?,
         };
         let cache = DiskEmbeddingCache::new(cache_dir).context("init embedding cache")?;
-        Ok(Box::new(CachedEmbedder { inner, cache }))
+        Ok(Box::new(CachedEmbedder {
+            inner,
+            cache,
+            cache_hits: AtomicU64::new(0),
+        }))
+    }
+}
+
+/// Counts backend calls and provider-reported tokens for the wrapped embedder.
+struct MeteredEmbedder {
+    inner: Box<dyn Embedder + Send + Sync>,
+    usage: std::sync::Mutex<EmbeddingUsage>,
+}
+
+impl Embedder for MeteredEmbedder {
+    fn profile(&self) -> &crate::embedder::EmbeddingProfile {
+        self.inner.profile()
+    }
+
+    fn metadata(&self) -> crate::embedder::EmbedderMetadata {
+        self.inner.metadata()
+    }
+
+    fn usage(&self) -> EmbeddingUsage {
+        self.usage.lock().map(|g| *g).unwrap_or_default()
+    }
+
+    fn embed(&self, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let _span = self.span(None, inputs.len()).entered();
+        let started = std::time::Instant::now();
+        let out = self.inner.embed(inputs)?;
+        self.record(inputs.len(), started.elapsed());
+        Ok(out)
+    }
+
+    fn embed_for(&self, purpose: EmbedPurpose, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let _span = self.span(Some(purpose), inputs.len()).entered();
+        let started = std::time::Instant::now();
+        let out = self.inner.embed_for(purpose, inputs)?;
+        self.record(inputs.len(), started.elapsed());
+        Ok(out)
+    }
+}
+
+impl MeteredEmbedder {
+    fn span(&self, purpose: Option<EmbedPurpose>, inputs: usize) -> tracing::Span {
+        let profile = self.inner.profile();
+        tracing::debug_span!(
+            "embed",
+            backend = %profile.backend,
+            model = profile.model.as_deref(),
+            purpose = ?purpose,
+            inputs
+        )
+    }
+
+    fn record(&self, inputs: usize, elapsed: std::time::Duration) {
+        tracing::debug!(
+            latency_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            "provider call done"
+        );
+        // Backends record the metadata of their most recent response, so read it right away.
+        let response = self.inner.metadata().provider_response;
+        if let Ok(mut g) = self.usage.lock() {
+            g.record_request(inputs, response.as_ref());
+        }
     }
 }
 
 struct CachedEmbedder {
     inner: Box<dyn Embedder + Send + Sync>,
     cache: DiskEmbeddingCache,
+    cache_hits: AtomicU64,
 }
 
 impl Embedder for CachedEmbedder {
@@ -326,16 +497,46 @@ impl Embedder for CachedEmbedder {
         self.inner.metadata()
     }
 
+    fn usage(&self) -> EmbeddingUsage {
+        let mut usage = self.inner.usage();
+        usage.cached_inputs += self.cache_hits.load(Ordering::Relaxed);
+        usage
+    }
+
     fn embed(&self, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        self.embed_cached(None, inputs)
+    }
+
+    fn embed_for(&self, purpose: EmbedPurpose, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        self.embed_cached(Some(purpose), inputs)
+    }
+}
+
+impl CachedEmbedder {
+    /// Serves cache hits and embeds misses; `purpose` of `None` is the plain `embed` path.
+    fn embed_cached(
+        &self,
+        purpose: Option<EmbedPurpose>,
+        inputs: &[String],
+    ) -> anyhow::Result<Vec<Vec<f32>>> {
+        let key_for = |s: &str| {
+            crate::cache::cache_key_hex_for(
+                self.profile(),
+                purpose.unwrap_or(EmbedPurpose::Document),
+                s,
+            )
+            .context("cache key")
+        };
         let dim = self.profile().dim;
         let mut out: Vec<Option<Vec<f32>>> = vec![None; inputs.len()];
         let mut misses: Vec<(usize, &str)> = Vec::new();
 
         for (i, s) in inputs.iter().enumerate() {
-            let key = crate::cache::cache_key_hex(self.profile(), s).context("cache key")?;
+            let key = key_for(s)?;
             if let Some(v) = self.cache.load_f32(&key).context("cache read")? {
                 if v.len() == dim {
                     out[i] = Some(v);
+                    self.cache_hits.fetch_add(1, Ordering::Relaxed);
                     continue;
                 }
             }
@@ -344,10 +545,11 @@ impl Embedder for CachedEmbedder {
 
         if !misses.is_empty() {
             let miss_inputs: Vec<String> = misses.iter().map(|(_, s)| (*s).to_string()).collect();
-            let miss_embeds = self
-                .inner
-                .embed(&miss_inputs)
-                .context("embed cache misses")?;
+            let miss_embeds = match purpose {
+                Some(purpose) => self.inner.embed_for(purpose, &miss_inputs),
+                None => self.inner.embed(&miss_inputs),
+            }
+            .context("embed cache misses")?;
             if miss_embeds.len() != misses.len() {
                 anyhow::bail!(
                     "embedder returned {} embeddings for {} inputs",
@@ -363,7 +565,7 @@ impl Embedder for CachedEmbedder {
                         dim
                     );
                 }
-                let key = crate::cache::cache_key_hex(self.profile(), s).context("cache key")?;
+                let key = key_for(s)?;
                 self.cache
                     .store_f32(&key, self.profile(), &emb)
                     .context("cache write")?;
@@ -392,6 +594,8 @@ pub fn roll_up_embedding_options(
         api_key_env: None,
         cache_enabled: false,
         cache_dir: None,
+        languages: Vec::new(),
+        language_models: BTreeMap::new(),
         checksum_allowlist: BTreeMap::new(),
     };
 
@@ -448,36 +652,7 @@ pub fn roll_up_embedding_options(
         let Some(layer) = layer_opt else { continue };
         if let Some(patch) = last_options_patch_in_layer(layer)? {
             found_any_options = true;
-            if let Some(backend) = patch.backend {
-                out.backend = backend;
-            }
-            if patch.model.is_some() {
-                out.model = patch.model;
-            }
-            if patch.revision.is_some() {
-                out.revision = patch.revision;
-            }
-            if patch.model_path.is_some() {
-                out.model_path = patch.model_path;
-            }
-            if patch.model_sha256.is_some() {
-                out.model_sha256 = patch.model_sha256;
-            }
-            if patch.dim.is_some() {
-                out.dim = patch.dim;
-            }
-            if patch.api_base.is_some() {
-                out.api_base = patch.api_base;
-            }
-            if patch.api_key_env.is_some() {
-                out.api_key_env = patch.api_key_env;
-            }
-            if patch.cache_enabled.is_some() {
-                out.cache_enabled = patch.cache_enabled.unwrap_or(false);
-            }
-            if patch.cache_dir.is_some() {
-                out.cache_dir = patch.cache_dir;
-            }
+            out.apply_patch(patch);
         }
     }
 
@@ -529,17 +704,20 @@ pub fn roll_up_embedding_options_from_paths(
 /// * `dir` - Directory containing the AGENTS.db file
 ///
 /// # Returns
-/// Resolved embedding options read only from AGENTS.db (base layer)
+/// Resolved embedding options read only from AGENTS.db (base layer), with any `[embedding]`
+/// keys from `agentsdb.toml` and `AGENTSDB_EMBEDDING_*` environment variables applied on top
 pub fn get_immutable_embedding_options(
     dir: &std::path::Path,
 ) -> anyhow::Result<ResolvedEmbeddingOptions> {
     let standard = standard_layer_paths_for_dir(dir);
-    roll_up_embedding_options_from_paths(
+    let mut out = roll_up_embedding_options_from_paths(
         None,  // local - not read
         None,  // user - not read
         None,  // delta - not read
         Some(standard.base.as_path()),  // base only
-    )
+    )?;
+    out.apply_patch(crate::settings::embedding_overrides(dir).context("resolve overrides")?);
+    Ok(out)
 }
 
 fn open_if_exists(
@@ -624,6 +802,7 @@ mod tests {
         let cached = CachedEmbedder {
             inner: Box::new(inner),
             cache,
+            cache_hits: AtomicU64::new(0),
         };
 
         let out1 = cached.embed(&["hello".to_string()]).unwrap();
@@ -633,6 +812,40 @@ mod tests {
         let out2 = cached.embed(&["hello".to_string()]).unwrap();
         assert_eq!(out2, vec![vec![1.0; 4]]);
         assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cached.usage().cached_inputs, 1);
+    }
+
+    #[test]
+    fn cached_embedder_keeps_query_and_document_entries_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskEmbeddingCache::new(dir.path().to_path_buf()).unwrap();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let inner = CountingEmbedder {
+            profile: crate::embedder::EmbeddingProfile {
+                backend: "hash".to_string(),
+                model: None,
+                revision: None,
+                dim: 4,
+                output_norm: crate::embedder::OutputNorm::None,
+            },
+            calls: calls.clone(),
+        };
+        let cached = CachedEmbedder {
+            inner: Box::new(inner),
+            cache,
+            cache_hits: AtomicU64::new(0),
+        };
+        let input = ["hello".to_string()];
+
+        cached.embed_for(EmbedPurpose::Document, &input).unwrap();
+        cached.embed(&input).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        cached.embed_for(EmbedPurpose::Query, &input).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        cached.embed_for(EmbedPurpose::Query, &input).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
     }
 
     #[test]
@@ -645,10 +858,13 @@ mod tests {
             dim: 4,
             element_type: agentsdb_format::EmbeddingElementType::F32,
             quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
         };
 
         let base_record = OptionsRecord {
             embedding: None,
+            search: None,
+            server: None,
             checksum_allowlist: Some(ChecksumAllowlistRecord {
                 op: AllowlistOp::Add,
                 entries: vec![ModelChecksumPin {
@@ -676,6 +892,8 @@ mod tests {
 
         let local_record_remove = OptionsRecord {
             embedding: None,
+            search: None,
+            server: None,
             checksum_allowlist: Some(ChecksumAllowlistRecord {
                 op: AllowlistOp::Remove,
                 entries: vec![ModelChecksumPin {
@@ -687,6 +905,8 @@ mod tests {
         };
         let local_record_add = OptionsRecord {
             embedding: None,
+            search: None,
+            server: None,
             checksum_allowlist: Some(ChecksumAllowlistRecord {
                 op: AllowlistOp::Add,
                 entries: vec![ModelChecksumPin {
@@ -751,4 +971,28 @@ mod tests {
             Some("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff")
         );
     }
+
+    #[test]
+    fn language_models_pick_the_group_covering_every_language() {
+        let mut resolved = roll_up_embedding_options(&[]).unwrap();
+        resolved.model = Some("all-minilm-l6-v2".to_string());
+        assert_eq!(resolved.language_model(), None);
+
+        resolved.apply_patch(EmbeddingOptionsPatch {
+            languages: Some(vec!["de-AT".to_string(), "fr".to_string()]),
+            language_models: Some(BTreeMap::from([
+                ("en".to_string(), "english-model".to_string()),
+                ("de,fr,es".to_string(), "european-model".to_string()),
+                ("*".to_string(), "multilingual-model".to_string()),
+            ])),
+            ..Default::default()
+        });
+        assert_eq!(resolved.language_model(), Some("european-model"));
+
+        resolved.languages.push("ja".to_string());
+        assert_eq!(resolved.language_model(), Some("multilingual-model"));
+
+        resolved.language_models.remove("*");
+        assert_eq!(resolved.language_model(), None);
+    }
 }
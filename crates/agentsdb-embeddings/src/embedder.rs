@@ -19,6 +19,15 @@ pub enum OutputNorm {
     L2,
 }
 
+impl EmbeddingProfile {
+    /// Short stable id of the profile: the first 16 hex digits of the SHA-256 of its JSON.
+    pub fn fingerprint(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        let digest = crate::cache::sha256(&json);
+        crate::cache::hex_lower(digest.get(..8).unwrap_or(&digest))
+    }
+}
+
 impl Default for OutputNorm {
     fn default() -> Self {
         Self::None
@@ -57,10 +66,82 @@ pub struct EmbedderMetadata {
     pub notes: Option<String>,
 }
 
+/// What an embedding is for. Asymmetric models embed search queries and stored documents
+/// differently (instruction prefixes, provider `input_type` modes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedPurpose {
+    Query,
+    Document,
+}
+
+/// Instruction prefix expected by instruction-tuned models (E5, BGE) for `purpose`, if any.
+///
+/// Matches on the model name (ignoring any `org/` prefix), so user-supplied local models named
+/// e.g. `multilingual-e5-small` or `bge-small-en-v1.5` get the prefixes their model cards require.
+pub fn instruction_prefix(model: Option<&str>, purpose: EmbedPurpose) -> Option<&'static str> {
+    let name = model?.rsplit('/').next()?.to_ascii_lowercase();
+    if name.starts_with("e5-") || name.contains("-e5-") {
+        return Some(match purpose {
+            EmbedPurpose::Query => "query: ",
+            EmbedPurpose::Document => "passage: ",
+        });
+    }
+    if name.starts_with("bge-") && !name.starts_with("bge-m3") && purpose == EmbedPurpose::Query {
+        return Some("Represent this sentence for searching relevant passages: ");
+    }
+    None
+}
+
 pub trait Embedder {
     fn profile(&self) -> &EmbeddingProfile;
     fn metadata(&self) -> EmbedderMetadata {
         EmbedderMetadata::default()
     }
+    /// Usage accumulated by this embedder since it was created.
+    fn usage(&self) -> crate::usage::EmbeddingUsage {
+        crate::usage::EmbeddingUsage::default()
+    }
     fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>>;
+    /// Embeds `inputs` for a specific purpose. Symmetric backends ignore `purpose`.
+    fn embed_for(&self, _purpose: EmbedPurpose, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.embed(inputs)
+    }
+}
+
+/// Prepends the instruction prefix for `purpose` (see [`instruction_prefix`]) to each input.
+pub fn with_instruction_prefix(
+    model: Option<&str>,
+    purpose: EmbedPurpose,
+    inputs: &[String],
+) -> Option<Vec<String>> {
+    let prefix = instruction_prefix(model, purpose)?;
+    Some(inputs.iter().map(|s| format!("{prefix}{s}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instruction_prefixes_follow_model_family() {
+        assert_eq!(
+            instruction_prefix(Some("intfloat/multilingual-e5-small"), EmbedPurpose::Query),
+            Some("query: ")
+        );
+        assert_eq!(
+            instruction_prefix(Some("e5-base-v2"), EmbedPurpose::Document),
+            Some("passage: ")
+        );
+        assert!(instruction_prefix(Some("BAAI/bge-small-en-v1.5"), EmbedPurpose::Query).is_some());
+        assert_eq!(
+            instruction_prefix(Some("bge-small-en-v1.5"), EmbedPurpose::Document),
+            None
+        );
+        assert_eq!(
+            instruction_prefix(Some("all-minilm-l6-v2"), EmbedPurpose::Query),
+            None
+        );
+        assert_eq!(instruction_prefix(None, EmbedPurpose::Query), None);
+    }
 }
@@ -13,6 +13,10 @@ pub struct LayerMetadataV1 {
     pub embedder_metadata: Option<EmbedderMetadata>,
     pub tool_name: Option<String>,
     pub tool_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kinds: Option<KindTaxonomy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<LayerSignature>,
 }
 
 impl LayerMetadataV1 {
@@ -24,6 +28,8 @@ impl LayerMetadataV1 {
             embedder_metadata: None,
             tool_name: None,
             tool_version: None,
+            kinds: None,
+            signature: None,
         }
     }
 
@@ -38,6 +44,16 @@ impl LayerMetadataV1 {
         self
     }
 
+    pub fn with_kinds(mut self, kinds: Option<KindTaxonomy>) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    pub fn with_signature(mut self, signature: Option<LayerSignature>) -> Self {
+        self.signature = signature;
+        self
+    }
+
     pub fn to_json_bytes(&self) -> anyhow::Result<Vec<u8>> {
         serde_json::to_vec(self).context("serialize layer metadata")
     }
@@ -47,6 +63,97 @@ impl LayerMetadataV1 {
     }
 }
 
+/// The kinds chunks in a layer are expected to use, kept in the layer metadata so the taxonomy
+/// travels with the file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KindTaxonomy {
+    #[serde(default)]
+    pub validation: KindValidation,
+    #[serde(default)]
+    pub kinds: Vec<KindDef>,
+}
+
+impl KindTaxonomy {
+    pub fn get(&self, name: &str) -> Option<&KindDef> {
+        self.kinds.iter().find(|k| k.name == name)
+    }
+
+    /// Adds `name`, or replaces the description of an existing entry.
+    pub fn add(&mut self, name: impl Into<String>, description: impl Into<String>) {
+        let def = KindDef {
+            name: name.into(),
+            description: description.into(),
+        };
+        match self.kinds.iter_mut().find(|k| k.name == def.name) {
+            Some(existing) => *existing = def,
+            None => self.kinds.push(def),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KindDef {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+/// What a write does with a kind the taxonomy does not list.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum KindValidation {
+    /// Accept it silently.
+    Off,
+    /// Accept it with a warning.
+    #[default]
+    Warn,
+    /// Reject the write.
+    Enforce,
+}
+
+impl std::fmt::Display for KindValidation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Off => "off",
+            Self::Warn => "warn",
+            Self::Enforce => "enforce",
+        })
+    }
+}
+
+/// An Ed25519 signature over a layer's content (see [`crate::signing`]).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LayerSignature {
+    /// Signature algorithm; always `ed25519`.
+    pub alg: String,
+    /// Signer's public key (64 lowercase hex chars).
+    pub public_key: String,
+    /// Digest that was signed (see [`crate::signing::content_sha256`]).
+    pub content_sha256: String,
+    /// Signature over the digest (128 lowercase hex chars).
+    pub signature: String,
+}
+
+/// `metadata_json` with the kind taxonomy of `existing_json` (if any) carried over, so rewriting
+/// a layer's metadata keeps its taxonomy.
+pub fn carry_kinds(
+    metadata_json: Vec<u8>,
+    existing_json: Option<&[u8]>,
+) -> anyhow::Result<Vec<u8>> {
+    let Some(existing) = existing_json else {
+        return Ok(metadata_json);
+    };
+    let kinds = LayerMetadataV1::from_json_bytes(existing)
+        .context("parse existing layer metadata")?
+        .kinds;
+    if kinds.is_none() {
+        return Ok(metadata_json);
+    }
+    LayerMetadataV1::from_json_bytes(&metadata_json)?
+        .with_kinds(kinds)
+        .to_json_bytes()
+}
+
 pub fn ensure_layer_metadata_compatible_with_embedder(
     file: &agentsdb_format::LayerFile,
     embedder: &dyn Embedder,
@@ -57,12 +164,34 @@ pub fn ensure_layer_metadata_compatible_with_embedder(
     let existing =
         LayerMetadataV1::from_json_bytes(existing).context("parse existing layer metadata")?;
     if existing.embedding_profile != *embedder.profile() {
-        anyhow::bail!(
-            "embedder profile mismatch vs layer metadata (layer={}, existing={:?}, current={:?})",
-            file.path().display(),
-            existing.embedding_profile,
-            embedder.profile()
-        );
+        return Err(ProfileMismatchError {
+            layer: file.path().to_path_buf(),
+            existing: existing.embedding_profile,
+            current: embedder.profile().clone(),
+        }
+        .into());
     }
     Ok(())
 }
+
+/// A layer was written with a different embedding profile than the current embedder's.
+#[derive(Debug, Clone)]
+pub struct ProfileMismatchError {
+    pub layer: std::path::PathBuf,
+    pub existing: EmbeddingProfile,
+    pub current: EmbeddingProfile,
+}
+
+impl std::fmt::Display for ProfileMismatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "embedder profile mismatch vs layer metadata (layer={}, existing={:?}, current={:?})",
+            self.layer.display(),
+            self.existing,
+            self.current
+        )
+    }
+}
+
+impl std::error::Error for ProfileMismatchError {}
@@ -5,4 +5,7 @@ pub mod config;
 pub mod embedder;
 pub mod hash;
 pub mod layer_metadata;
+pub mod settings;
+pub mod signing;
+pub mod usage;
 pub mod verification;
@@ -0,0 +1,1360 @@
+//! Settings schema shared by options chunks, `agentsdb.toml` and `AGENTSDB_*` environment
+//! variables.
+//!
+//! Precedence, lowest to highest: built-in defaults, options chunks (`embedding` keys from the
+//! base layer only, `search`, `server` and `retention` keys rolled up base < delta < user <
+//! local),
+//! `agentsdb.toml` next to the layers, then environment variables. `trust` keys decide which
+//! layers readers accept, so options chunks cannot set them. `identity` keys name whoever is
+//! writing, so only the local layer's options chunks may set them.
+
+use anyhow::Context;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use crate::config::{EmbeddingOptionsPatch, StandardLayerPaths, KIND_OPTIONS};
+use crate::signing::{TrustMode, TrustPolicy};
+
+pub const CONFIG_FILE_NAME: &str = "agentsdb.toml";
+
+pub const SUPPORTED_BACKENDS: &[&str] = &[
+    "hash",
+    "candle",
+    "ort",
+    "openai",
+    "voyage",
+    "cohere",
+    "anthropic",
+    "bedrock",
+    "gemini",
+];
+
+/// Sections holding settings; options chunks may also carry `checksum_allowlist` and
+/// `agentsdb.toml` may also carry `[layers]`.
+pub const SECTIONS: &[&str] = &[
+    "embedding",
+    "search",
+    "server",
+    "retention",
+    "trust",
+    "identity",
+];
+
+/// Section only `agentsdb.toml` and the environment may set.
+const TRUST_SECTION: &str = "trust";
+
+/// Section only the local layer's options chunks, `agentsdb.toml` and the environment may set.
+const IDENTITY_SECTION: &str = "identity";
+
+const LAYER_KEYS: &[&str] = &["base", "user", "delta", "local"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingType {
+    String,
+    Bool,
+    PositiveInt,
+    Sha256,
+    OneOf(&'static [&'static str]),
+    ListOf(&'static [&'static str]),
+    /// A list of arbitrary non-empty strings.
+    Strings,
+    /// A number from 0.0 to 1.0.
+    Fraction,
+    /// A list of language tags.
+    LanguageTags,
+    /// A table from a language group (comma-separated language tags, or `*`) to a model;
+    /// written as text `GROUP=MODEL;GROUP=MODEL`.
+    ModelsByLanguage,
+    /// A list of Ed25519 public keys, 64 hex chars each.
+    PublicKeys,
+}
+
+impl SettingType {
+    fn describe(self) -> String {
+        match self {
+            Self::String => "a string".to_string(),
+            Self::Bool => "a boolean".to_string(),
+            Self::PositiveInt => "a positive integer".to_string(),
+            Self::Sha256 => "a lowercase sha256 hex string".to_string(),
+            Self::OneOf(allowed) => format!("one of: {}", allowed.join(", ")),
+            Self::ListOf(allowed) => format!("a list of: {}", allowed.join(", ")),
+            Self::Strings => "a list of strings".to_string(),
+            Self::Fraction => "a number from 0.0 to 1.0".to_string(),
+            Self::LanguageTags => "a list of language tags (e.g. en, de-AT)".to_string(),
+            Self::ModelsByLanguage => {
+                "a table of language group (e.g. \"de,fr\" or \"*\") to model".to_string()
+            }
+            Self::PublicKeys => "a list of Ed25519 public keys (64 hex chars each)".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SettingSpec {
+    /// Dotted key, `<section>.<field>`.
+    pub key: &'static str,
+    pub ty: SettingType,
+    /// Environment variable overriding every other source.
+    pub env: &'static str,
+    /// Default, written the way an environment variable would be (`None`: unset).
+    pub default: Option<&'static str>,
+    pub doc: &'static str,
+}
+
+pub const SETTINGS: &[SettingSpec] = &[
+    SettingSpec {
+        key: "embedding.backend",
+        ty: SettingType::OneOf(SUPPORTED_BACKENDS),
+        env: "AGENTSDB_EMBEDDING_BACKEND",
+        default: Some("hash"),
+        doc: "Embedder backend.",
+    },
+    SettingSpec {
+        key: "embedding.model",
+        ty: SettingType::String,
+        env: "AGENTSDB_EMBEDDING_MODEL",
+        default: None,
+        doc: "Embedding model identifier (provider-specific).",
+    },
+    SettingSpec {
+        key: "embedding.revision",
+        ty: SettingType::String,
+        env: "AGENTSDB_EMBEDDING_REVISION",
+        default: None,
+        doc: "Embedding model revision/version.",
+    },
+    SettingSpec {
+        key: "embedding.model_path",
+        ty: SettingType::String,
+        env: "AGENTSDB_EMBEDDING_MODEL_PATH",
+        default: None,
+        doc: "Local model path for offline backends.",
+    },
+    SettingSpec {
+        key: "embedding.model_sha256",
+        ty: SettingType::Sha256,
+        env: "AGENTSDB_EMBEDDING_MODEL_SHA256",
+        default: None,
+        doc: "Expected SHA-256 of local model bytes.",
+    },
+    SettingSpec {
+        key: "embedding.dim",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_EMBEDDING_DIM",
+        default: None,
+        doc: "Embedding dimension (must match the layer schema).",
+    },
+    SettingSpec {
+        key: "embedding.api_base",
+        ty: SettingType::String,
+        env: "AGENTSDB_EMBEDDING_API_BASE",
+        default: None,
+        doc: "API base URL for remote providers.",
+    },
+    SettingSpec {
+        key: "embedding.api_key_env",
+        ty: SettingType::String,
+        env: "AGENTSDB_EMBEDDING_API_KEY_ENV",
+        default: None,
+        doc: "Environment variable holding the provider API key.",
+    },
+    SettingSpec {
+        key: "embedding.cache_enabled",
+        ty: SettingType::Bool,
+        env: "AGENTSDB_EMBEDDING_CACHE_ENABLED",
+        default: Some("false"),
+        doc: "Cache embeddings on disk.",
+    },
+    SettingSpec {
+        key: "embedding.cache_dir",
+        ty: SettingType::String,
+        env: "AGENTSDB_EMBEDDING_CACHE_DIR",
+        default: None,
+        doc: "Embedding cache directory.",
+    },
+    SettingSpec {
+        key: "embedding.languages",
+        ty: SettingType::LanguageTags,
+        env: "AGENTSDB_EMBEDDING_LANGUAGES",
+        default: None,
+        doc: "Languages of the project's content; selects a model from `embedding.language_models`.",
+    },
+    SettingSpec {
+        key: "embedding.language_models",
+        ty: SettingType::ModelsByLanguage,
+        env: "AGENTSDB_EMBEDDING_LANGUAGE_MODELS",
+        default: None,
+        doc: "Model per language group, used instead of `embedding.model` when `embedding.languages` matches a group.",
+    },
+    SettingSpec {
+        key: "search.k",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_SEARCH_K",
+        default: Some("5"),
+        doc: "Results returned by `search` when -k is not given.",
+    },
+    SettingSpec {
+        key: "search.mode",
+        ty: SettingType::OneOf(&["hybrid", "semantic"]),
+        env: "AGENTSDB_SEARCH_MODE",
+        default: Some("hybrid"),
+        doc: "Search mode when --mode is not given.",
+    },
+    SettingSpec {
+        key: "search.use_index",
+        ty: SettingType::Bool,
+        env: "AGENTSDB_SEARCH_USE_INDEX",
+        default: Some("false"),
+        doc: "Use sidecar indexes when present.",
+    },
+    SettingSpec {
+        key: "search.disk_cache",
+        ty: SettingType::Bool,
+        env: "AGENTSDB_SEARCH_DISK_CACHE",
+        default: Some("false"),
+        doc: "Cache the selection pass of searches under `.agentsdb/cache/` for later processes.",
+    },
+    SettingSpec {
+        key: "server.max_in_flight",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_MCP_MAX_IN_FLIGHT",
+        default: Some("4"),
+        doc: "MCP requests processed concurrently.",
+    },
+    SettingSpec {
+        key: "server.read_only",
+        ty: SettingType::Bool,
+        env: "AGENTSDB_MCP_READ_ONLY",
+        default: Some("false"),
+        doc: "Only expose read tools.",
+    },
+    SettingSpec {
+        key: "server.no_propose",
+        ty: SettingType::Bool,
+        env: "AGENTSDB_MCP_NO_PROPOSE",
+        default: Some("false"),
+        doc: "Hide `agents_context_propose`.",
+    },
+    SettingSpec {
+        key: "server.write_scopes",
+        ty: SettingType::ListOf(&["local", "delta"]),
+        env: "AGENTSDB_MCP_WRITE_SCOPES",
+        default: Some("local,delta"),
+        doc: "Scopes MCP writes may target.",
+    },
+    SettingSpec {
+        key: "server.audit_reads",
+        ty: SettingType::Bool,
+        env: "AGENTSDB_MCP_AUDIT_READS",
+        default: Some("false"),
+        doc: "Record searches and chunk reads in the audit log.",
+    },
+    SettingSpec {
+        key: "server.query_cache_size",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_QUERY_CACHE_SIZE",
+        default: Some("256"),
+        doc: "Query embeddings the MCP and web servers keep in memory; 0 disables the cache.",
+    },
+    SettingSpec {
+        key: "server.query_cache_ttl_secs",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_QUERY_CACHE_TTL_SECS",
+        default: Some("3600"),
+        doc: "Seconds a cached query embedding is reused for.",
+    },
+    SettingSpec {
+        key: "server.maintain_indexes",
+        ty: SettingType::Bool,
+        env: "AGENTSDB_MAINTAIN_INDEXES",
+        default: Some("false"),
+        doc: "Build `.agix` indexes on startup and rebuild them in the background after writes.",
+    },
+    SettingSpec {
+        key: "server.index_poll_secs",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_INDEX_POLL_SECS",
+        default: Some("2"),
+        doc: "Seconds between checks for changed layers when maintaining indexes.",
+    },
+    SettingSpec {
+        key: "server.search_k",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_MCP_SEARCH_K",
+        default: Some("10"),
+        doc: "Results `agents_search` returns when a call sets no `k`.",
+    },
+    SettingSpec {
+        key: "server.search_layers",
+        ty: SettingType::ListOf(&["base", "user", "delta", "local", "session"]),
+        env: "AGENTSDB_MCP_SEARCH_LAYERS",
+        default: None,
+        doc: "Layers `agents_search` searches when a call names none (unset: all of them).",
+    },
+    SettingSpec {
+        key: "server.search_kinds",
+        ty: SettingType::Strings,
+        env: "AGENTSDB_MCP_SEARCH_KINDS",
+        default: None,
+        doc: "Kinds every MCP search is restricted to, on top of the call's own kind filter.",
+    },
+    SettingSpec {
+        key: "server.min_confidence",
+        ty: SettingType::Fraction,
+        env: "AGENTSDB_MCP_MIN_CONFIDENCE",
+        default: None,
+        doc: "Leave chunks below this confidence out of every MCP search.",
+    },
+    SettingSpec {
+        key: "retention.max_chunks",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_RETENTION_MAX_CHUNKS",
+        default: None,
+        doc: "Chunks the local layer keeps; the oldest beyond it are retracted.",
+    },
+    SettingSpec {
+        key: "retention.max_age_days",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_RETENTION_MAX_AGE_DAYS",
+        default: None,
+        doc: "Days a local chunk is kept before it is retracted.",
+    },
+    SettingSpec {
+        key: "retention.max_bytes",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_RETENTION_MAX_BYTES",
+        default: None,
+        doc: "Approximate bytes of content and embeddings the local layer keeps; the oldest chunks beyond it are retracted.",
+    },
+    SettingSpec {
+        key: "retention.gc_after_days",
+        ty: SettingType::PositiveInt,
+        env: "AGENTSDB_RETENTION_GC_AFTER_DAYS",
+        default: Some("7"),
+        doc: "Days a chunk retracted by the retention policy stays in the file before it is removed.",
+    },
+    SettingSpec {
+        key: "retention.on_startup",
+        ty: SettingType::Bool,
+        env: "AGENTSDB_RETENTION_ON_STARTUP",
+        default: Some("false"),
+        doc: "Apply the retention policy when `serve` or `web` starts.",
+    },
+    SettingSpec {
+        key: "trust.mode",
+        ty: SettingType::OneOf(&["off", "warn", "enforce"]),
+        env: "AGENTSDB_TRUST_MODE",
+        default: Some("off"),
+        doc: "What readers do with a covered layer not signed by a trusted key: open it, warn, or refuse it.",
+    },
+    SettingSpec {
+        key: "trust.public_keys",
+        ty: SettingType::PublicKeys,
+        env: "AGENTSDB_TRUST_PUBLIC_KEYS",
+        default: None,
+        doc: "Public keys whose layer signatures are trusted.",
+    },
+    SettingSpec {
+        key: "trust.layers",
+        ty: SettingType::ListOf(&["base", "user", "delta", "local"]),
+        env: "AGENTSDB_TRUST_LAYERS",
+        default: Some("base"),
+        doc: "Layers that must be signed by a trusted key.",
+    },
+    SettingSpec {
+        key: "identity.name",
+        ty: SettingType::String,
+        env: "AGENTSDB_IDENTITY_NAME",
+        default: None,
+        doc: "Name recorded with the chunks and proposal events you write.",
+    },
+    SettingSpec {
+        key: "identity.email",
+        ty: SettingType::String,
+        env: "AGENTSDB_IDENTITY_EMAIL",
+        default: None,
+        doc: "Email recorded with the chunks and proposal events you write.",
+    },
+    SettingSpec {
+        key: "identity.actor",
+        ty: SettingType::String,
+        env: "AGENTSDB_IDENTITY_ACTOR",
+        default: None,
+        doc: "Machine actor id (e.g. ci/nightly) recorded when no name or email is set.",
+    },
+];
+
+pub fn spec(key: &str) -> Option<&'static SettingSpec> {
+    SETTINGS.iter().find(|s| s.key == key)
+}
+
+impl SettingSpec {
+    pub fn section(&self) -> &'static str {
+        self.key
+            .split_once('.')
+            .map_or(self.key, |(section, _)| section)
+    }
+
+    pub fn field(&self) -> &'static str {
+        self.key
+            .split_once('.')
+            .map_or(self.key, |(_, field)| field)
+    }
+
+    /// Parses a value written as text (`--set KEY=VALUE`, environment variables, defaults).
+    pub fn parse_text(&self, text: &str) -> anyhow::Result<Value> {
+        let text = text.trim();
+        let value = match self.ty {
+            SettingType::String | SettingType::Sha256 | SettingType::OneOf(_) => {
+                Value::String(text.to_string())
+            }
+            SettingType::Bool => match text.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Value::Bool(true),
+                "0" | "false" | "no" | "off" | "" => Value::Bool(false),
+                _ => anyhow::bail!("{}: expected a boolean, got {text:?}", self.key),
+            },
+            SettingType::PositiveInt => {
+                let n: u64 = text.parse().map_err(|_| {
+                    anyhow::anyhow!("{}: expected a positive integer, got {text:?}", self.key)
+                })?;
+                Value::from(n)
+            }
+            SettingType::Fraction => {
+                let n: f64 = text.parse().map_err(|_| {
+                    anyhow::anyhow!("{}: expected a number, got {text:?}", self.key)
+                })?;
+                Value::from(n)
+            }
+            SettingType::ListOf(_)
+            | SettingType::Strings
+            | SettingType::LanguageTags
+            | SettingType::PublicKeys => Value::Array(
+                text.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| Value::String(s.to_string()))
+                    .collect(),
+            ),
+            SettingType::ModelsByLanguage => {
+                let mut table = Map::new();
+                for entry in text.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+                    let (group, model) = entry.split_once('=').ok_or_else(|| {
+                        anyhow::anyhow!("{}: expected GROUP=MODEL, got {entry:?}", self.key)
+                    })?;
+                    table.insert(
+                        group.trim().to_string(),
+                        Value::String(model.trim().to_string()),
+                    );
+                }
+                Value::Object(table)
+            }
+        };
+        self.check(&value)?;
+        Ok(value)
+    }
+
+    /// Checks a JSON value against this key's type.
+    pub fn check(&self, value: &Value) -> anyhow::Result<()> {
+        let ok = match (self.ty, value) {
+            (SettingType::String, Value::String(_)) | (SettingType::Bool, Value::Bool(_)) => true,
+            (SettingType::PositiveInt, Value::Number(n)) => n.as_u64().is_some_and(|n| n > 0),
+            (SettingType::Fraction, Value::Number(n)) => {
+                n.as_f64().is_some_and(|n| (0.0..=1.0).contains(&n))
+            }
+            (SettingType::Strings, Value::Array(items)) => items
+                .iter()
+                .all(|item| item.as_str().is_some_and(|s| !s.trim().is_empty())),
+            (SettingType::Sha256, Value::String(s)) => {
+                crate::verification::ensure_sha256_hex(s).with_context(|| self.key.to_string())?;
+                true
+            }
+            (SettingType::OneOf(allowed), Value::String(s)) => {
+                if !allowed.contains(&s.as_str()) {
+                    anyhow::bail!(
+                        "{}: unknown value {s:?}{} (expected {})",
+                        self.key,
+                        did_you_mean(s, allowed.iter().copied()),
+                        self.ty.describe()
+                    );
+                }
+                true
+            }
+            (SettingType::ListOf(allowed), Value::Array(items)) => {
+                for item in items {
+                    let Some(s) = item.as_str() else {
+                        anyhow::bail!("{}: expected {}, got {value}", self.key, self.ty.describe());
+                    };
+                    if !allowed.contains(&s) {
+                        anyhow::bail!(
+                            "{}: unknown value {s:?}{} (expected {})",
+                            self.key,
+                            did_you_mean(s, allowed.iter().copied()),
+                            self.ty.describe()
+                        );
+                    }
+                }
+                true
+            }
+            (SettingType::LanguageTags, Value::Array(items)) => {
+                for item in items {
+                    if !item.as_str().is_some_and(is_language_tag) {
+                        anyhow::bail!("{}: expected {}, got {item}", self.key, self.ty.describe());
+                    }
+                }
+                true
+            }
+            (SettingType::PublicKeys, Value::Array(items)) => {
+                for item in items {
+                    let Some(key) = item.as_str() else {
+                        anyhow::bail!("{}: expected {}, got {item}", self.key, self.ty.describe());
+                    };
+                    crate::signing::parse_public_key(key)
+                        .with_context(|| format!("{}: invalid public key {key:?}", self.key))?;
+                }
+                true
+            }
+            (SettingType::ModelsByLanguage, Value::Object(table)) => {
+                for (group, model) in table {
+                    let group_ok =
+                        group == "*" || group.split(',').map(str::trim).all(is_language_tag);
+                    if !group_ok {
+                        anyhow::bail!(
+                            "{}: language group {group:?} must be comma-separated language tags or \"*\"",
+                            self.key
+                        );
+                    }
+                    if !model.as_str().is_some_and(|m| !m.trim().is_empty()) {
+                        anyhow::bail!(
+                            "{}: model for {group:?} must be a non-empty string",
+                            self.key
+                        );
+                    }
+                }
+                true
+            }
+            _ => false,
+        };
+        if !ok {
+            anyhow::bail!("{}: expected {}, got {value}", self.key, self.ty.describe());
+        }
+        Ok(())
+    }
+}
+
+fn is_language_tag(tag: &str) -> bool {
+    agentsdb_core::language::normalize_tag(tag).is_some()
+}
+
+/// What a settings document is; decides which extra sections it may hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    /// The JSON content of an options chunk (may also carry `checksum_allowlist`).
+    OptionsChunk,
+    /// `agentsdb.toml` (may also carry `[layers]`).
+    ConfigFile,
+}
+
+/// Validates a whole settings document, reporting every unknown key and bad value at once.
+pub fn validate_document(doc: &Value, kind: DocumentKind) -> anyhow::Result<()> {
+    let Value::Object(sections) = doc else {
+        anyhow::bail!("expected a table of sections, got {doc}");
+    };
+    let extra = match kind {
+        DocumentKind::OptionsChunk => "checksum_allowlist",
+        DocumentKind::ConfigFile => "layers",
+    };
+    let mut errors = Vec::new();
+    for (section, body) in sections {
+        if section == extra {
+            if kind == DocumentKind::ConfigFile {
+                validate_layers_section(body, &mut errors);
+            }
+            continue;
+        }
+        if !SECTIONS.contains(&section.as_str()) {
+            errors.push(format!(
+                "unknown section `{section}`{}",
+                did_you_mean(section, SECTIONS.iter().copied().chain([extra]))
+            ));
+            continue;
+        }
+        if section == TRUST_SECTION && kind == DocumentKind::OptionsChunk {
+            errors.push(format!(
+                "`{TRUST_SECTION}` can only be set in {CONFIG_FILE_NAME} or the environment"
+            ));
+            continue;
+        }
+        let fields = match body {
+            Value::Null => continue,
+            Value::Object(fields) => fields,
+            other => {
+                errors.push(format!("`{section}` must be a table, got {other}"));
+                continue;
+            }
+        };
+        for (field, value) in fields {
+            let key = format!("{section}.{field}");
+            match spec(&key) {
+                None => errors.push(format!(
+                    "unknown key `{key}`{}",
+                    did_you_mean(&key, SETTINGS.iter().map(|s| s.key))
+                )),
+                Some(_) if value.is_null() => {}
+                Some(spec) => {
+                    if let Err(err) = spec.check(value) {
+                        errors.push(format!("{err:#}"));
+                    }
+                }
+            }
+        }
+    }
+    if !errors.is_empty() {
+        anyhow::bail!("{}", errors.join("\n"));
+    }
+    Ok(())
+}
+
+fn validate_layers_section(body: &Value, errors: &mut Vec<String>) {
+    let Value::Object(fields) = body else {
+        errors.push(format!("`layers` must be a table, got {body}"));
+        return;
+    };
+    for (field, value) in fields {
+        if !LAYER_KEYS.contains(&field.as_str()) {
+            errors.push(format!(
+                "unknown key `layers.{field}`{}",
+                did_you_mean(field, LAYER_KEYS.iter().copied())
+            ));
+        } else if !value.is_string() {
+            errors.push(format!(
+                "layers.{field}: expected a path string, got {value}"
+            ));
+        }
+    }
+}
+
+/// Parses `KEY=VALUE` (as given to `agentsdb options set --set`) against the schema.
+pub fn parse_assignment(text: &str) -> anyhow::Result<(&'static SettingSpec, Value)> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("expected KEY=VALUE, got {text:?}"))?;
+    let key = key.trim();
+    let spec = spec(key).ok_or_else(|| {
+        anyhow::anyhow!(
+            "unknown key `{key}`{}",
+            did_you_mean(key, SETTINGS.iter().map(|s| s.key))
+        )
+    })?;
+    Ok((spec, spec.parse_text(value)?))
+}
+
+fn did_you_mean<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> String {
+    let best = candidates
+        .into_iter()
+        .map(|c| (edit_distance(input, c), c))
+        .min_by_key(|(d, _)| *d);
+    match best {
+        Some((d, c)) if d <= (input.len() / 3).max(2) => format!(" (did you mean `{c}`?)"),
+        _ => String::new(),
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Where the effective value of a setting came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SettingSource {
+    Default,
+    /// Embedding profile recorded in the base layer's metadata (no options chunk sets it).
+    LayerMetadata {
+        path: String,
+    },
+    /// The last options chunk of a layer.
+    Layer {
+        layer: &'static str,
+        path: String,
+    },
+    File {
+        path: String,
+    },
+    Env {
+        var: &'static str,
+    },
+}
+
+impl std::fmt::Display for SettingSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::LayerMetadata { path } => write!(f, "base layer metadata ({path})"),
+            Self::Layer { layer, path } => write!(f, "{layer} layer ({path})"),
+            Self::File { path } => write!(f, "{path}"),
+            Self::Env { var } => write!(f, "env {var}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveSetting {
+    pub key: &'static str,
+    pub value: Option<Value>,
+    pub source: SettingSource,
+    pub env: &'static str,
+}
+
+/// The merged settings, one entry per schema key in schema order.
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveSettings {
+    /// `agentsdb.toml` that was read, if any.
+    pub config_file: Option<String>,
+    pub settings: Vec<EffectiveSetting>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchSettings {
+    pub k: usize,
+    pub mode: String,
+    pub use_index: bool,
+    pub disk_cache: bool,
+}
+
+/// Who is writing, from the `identity.*` settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Identity {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub actor: Option<String>,
+}
+
+impl Identity {
+    /// `Name <email>`, the name or `<email>` alone, or else the actor id; `None` when nothing is
+    /// set.
+    pub fn label(&self) -> Option<String> {
+        match (&self.name, &self.email) {
+            (Some(name), Some(email)) => Some(format!("{name} <{email}>")),
+            (Some(name), None) => Some(name.clone()),
+            (None, Some(email)) => Some(format!("<{email}>")),
+            (None, None) => self.actor.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerSettings {
+    pub max_in_flight: usize,
+    pub read_only: bool,
+    pub no_propose: bool,
+    pub write_scopes: Vec<String>,
+    pub audit_reads: bool,
+    pub query_cache_size: usize,
+    pub query_cache_ttl_secs: u64,
+    pub maintain_indexes: bool,
+    pub index_poll_secs: u64,
+    pub search_k: usize,
+    pub search_layers: Vec<String>,
+    pub search_kinds: Vec<String>,
+    pub min_confidence: Option<f32>,
+}
+
+/// The local layer's retention policy, from the `retention.*` settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionSettings {
+    pub max_chunks: Option<usize>,
+    pub max_age_days: Option<u64>,
+    pub max_bytes: Option<u64>,
+    pub gc_after_days: u64,
+    pub on_startup: bool,
+}
+
+impl RetentionSettings {
+    /// Whether any limit is set.
+    pub const fn has_limits(&self) -> bool {
+        self.max_chunks.is_some() || self.max_age_days.is_some() || self.max_bytes.is_some()
+    }
+}
+
+impl EffectiveSettings {
+    fn defaults() -> anyhow::Result<Self> {
+        let settings = SETTINGS
+            .iter()
+            .map(|spec| {
+                Ok(EffectiveSetting {
+                    key: spec.key,
+                    value: spec.default.map(|d| spec.parse_text(d)).transpose()?,
+                    source: SettingSource::Default,
+                    env: spec.env,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            config_file: None,
+            settings,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.settings
+            .iter()
+            .find(|s| s.key == key)
+            .and_then(|s| s.value.as_ref())
+    }
+
+    fn get_bool(&self, key: &str) -> bool {
+        self.get(key).and_then(Value::as_bool).unwrap_or(false)
+    }
+
+    fn get_usize(&self, key: &str, fallback: usize) -> usize {
+        self.get(key)
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(fallback)
+    }
+
+    pub fn search(&self) -> SearchSettings {
+        SearchSettings {
+            k: self.get_usize("search.k", 5),
+            mode: self
+                .get("search.mode")
+                .and_then(Value::as_str)
+                .unwrap_or("hybrid")
+                .to_string(),
+            use_index: self.get_bool("search.use_index"),
+            disk_cache: self.get_bool("search.disk_cache"),
+        }
+    }
+
+    fn get_strings(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn server(&self) -> ServerSettings {
+        ServerSettings {
+            max_in_flight: self.get_usize("server.max_in_flight", 4),
+            read_only: self.get_bool("server.read_only"),
+            no_propose: self.get_bool("server.no_propose"),
+            write_scopes: self.get_strings("server.write_scopes"),
+            audit_reads: self.get_bool("server.audit_reads"),
+            query_cache_size: self.get_usize("server.query_cache_size", 256),
+            query_cache_ttl_secs: self
+                .get("server.query_cache_ttl_secs")
+                .and_then(Value::as_u64)
+                .unwrap_or(3600),
+            maintain_indexes: self.get_bool("server.maintain_indexes"),
+            index_poll_secs: self
+                .get("server.index_poll_secs")
+                .and_then(Value::as_u64)
+                .unwrap_or(2),
+            search_k: self.get_usize("server.search_k", 10),
+            search_layers: self.get_strings("server.search_layers"),
+            search_kinds: self.get_strings("server.search_kinds"),
+            // Checked to be in 0.0..=1.0, so narrowing to f32 only rounds.
+            min_confidence: self
+                .get("server.min_confidence")
+                .and_then(Value::as_f64)
+                .map(|n| n as f32),
+        }
+    }
+
+    pub fn retention(&self) -> RetentionSettings {
+        let number = |key: &str| self.get(key).and_then(Value::as_u64);
+        RetentionSettings {
+            max_chunks: number("retention.max_chunks").and_then(|n| usize::try_from(n).ok()),
+            max_age_days: number("retention.max_age_days"),
+            max_bytes: number("retention.max_bytes"),
+            gc_after_days: number("retention.gc_after_days").unwrap_or(7),
+            on_startup: self.get_bool("retention.on_startup"),
+        }
+    }
+
+    pub fn trust(&self) -> TrustPolicy {
+        let mode = match self.get("trust.mode").and_then(Value::as_str) {
+            Some("warn") => TrustMode::Warn,
+            Some("enforce") => TrustMode::Enforce,
+            _ => TrustMode::Off,
+        };
+        TrustPolicy {
+            mode,
+            public_keys: self
+                .get_strings("trust.public_keys")
+                .into_iter()
+                .map(|k| k.trim().to_ascii_lowercase())
+                .collect(),
+            layers: self.get_strings("trust.layers"),
+        }
+    }
+
+    pub fn identity(&self) -> Identity {
+        let text = |key: &str| {
+            self.get(key)
+                .and_then(Value::as_str)
+                .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|s| !s.is_empty())
+        };
+        Identity {
+            name: text("identity.name"),
+            email: text("identity.email"),
+            actor: text("identity.actor"),
+        }
+    }
+
+    /// Embedding keys set by `agentsdb.toml` or the environment, as a patch over the options
+    /// rolled up from the base layer.
+    pub fn embedding_overrides(&self) -> anyhow::Result<EmbeddingOptionsPatch> {
+        let mut fields = Map::new();
+        for setting in &self.settings {
+            let spec = spec(setting.key).context("unknown setting")?;
+            if spec.section() != "embedding" {
+                continue;
+            }
+            if !matches!(
+                setting.source,
+                SettingSource::File { .. } | SettingSource::Env { .. }
+            ) {
+                continue;
+            }
+            if let Some(value) = &setting.value {
+                fields.insert(spec.field().to_string(), value.clone());
+            }
+        }
+        serde_json::from_value(Value::Object(fields)).context("decode embedding overrides")
+    }
+
+    fn apply_section(&mut self, section: &str, body: &Value, source: &SettingSource) {
+        let Value::Object(fields) = body else { return };
+        for (field, value) in fields {
+            if value.is_null() {
+                continue;
+            }
+            let key = format!("{section}.{field}");
+            if let Some(setting) = self.settings.iter_mut().find(|s| s.key == key) {
+                setting.value = Some(value.clone());
+                setting.source = source.clone();
+            }
+        }
+    }
+
+    fn apply_config_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        let doc = read_config_file(path)?;
+        let source = SettingSource::File {
+            path: path.display().to_string(),
+        };
+        if let Value::Object(sections) = &doc {
+            for section in SECTIONS {
+                if let Some(body) = sections.get(*section) {
+                    self.apply_section(section, body, &source);
+                }
+            }
+        }
+        self.config_file = Some(path.display().to_string());
+        Ok(())
+    }
+
+    fn apply_env(&mut self, env: &dyn Fn(&str) -> Option<String>) -> anyhow::Result<()> {
+        for setting in &mut self.settings {
+            let Some(text) = env(setting.env) else {
+                continue;
+            };
+            let spec = spec(setting.key).context("unknown setting")?;
+            let value = spec
+                .parse_text(&text)
+                .with_context(|| format!("invalid {}", setting.env))?;
+            setting.value = Some(value);
+            setting.source = SettingSource::Env { var: setting.env };
+        }
+        Ok(())
+    }
+}
+
+/// Reads and validates `agentsdb.toml`, returning it as JSON.
+pub fn read_config_file(path: &Path) -> anyhow::Result<Value> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let parsed: toml::Value =
+        toml::from_str(&text).with_context(|| format!("parse {}", path.display()))?;
+    let doc = serde_json::to_value(parsed).context("convert TOML")?;
+    validate_document(&doc, DocumentKind::ConfigFile)
+        .with_context(|| format!("invalid {}", path.display()))?;
+    Ok(doc)
+}
+
+/// `agentsdb.toml` in `dir`, if present.
+pub fn default_config_file(dir: &Path) -> Option<PathBuf> {
+    Some(dir.join(CONFIG_FILE_NAME)).filter(|p| p.exists())
+}
+
+/// Embedding keys set by `dir/agentsdb.toml` or `AGENTSDB_EMBEDDING_*` variables.
+pub fn embedding_overrides(dir: &Path) -> anyhow::Result<EmbeddingOptionsPatch> {
+    let mut settings = EffectiveSettings::defaults()?;
+    if let Some(file) = default_config_file(dir) {
+        settings.apply_config_file(&file)?;
+    }
+    settings.apply_env(&|var| std::env::var(var).ok())?;
+    settings.embedding_overrides()
+}
+
+/// The trust policy set by `dir/agentsdb.toml` or `AGENTSDB_TRUST_*` variables.
+pub fn trust_policy(dir: &Path) -> anyhow::Result<TrustPolicy> {
+    let mut settings = EffectiveSettings::defaults()?;
+    if let Some(file) = default_config_file(dir) {
+        settings.apply_config_file(&file)?;
+    }
+    settings.apply_env(&|var| std::env::var(var).ok())?;
+    Ok(settings.trust())
+}
+
+/// The identity set by the options of the local layer in `dir`, `dir/agentsdb.toml` or
+/// `AGENTSDB_IDENTITY_*` variables.
+pub fn identity(dir: &Path) -> anyhow::Result<Identity> {
+    let mut settings = EffectiveSettings::defaults()?;
+    let local = crate::config::standard_layer_paths_for_dir(dir).local;
+    let source = SettingSource::Layer {
+        layer: "local",
+        path: local.display().to_string(),
+    };
+    for (section, body) in last_sections_in_layer(&local)? {
+        if section == IDENTITY_SECTION {
+            settings.apply_section(&section, &body, &source);
+        }
+    }
+    if let Some(file) = default_config_file(dir) {
+        settings.apply_config_file(&file)?;
+    }
+    settings.apply_env(&|var| std::env::var(var).ok())?;
+    Ok(settings.identity())
+}
+
+/// The retention policy for the layers in `dir`, from their options, `dir/agentsdb.toml` or
+/// `AGENTSDB_RETENTION_*` variables.
+pub fn retention(dir: &Path) -> anyhow::Result<RetentionSettings> {
+    let paths = crate::config::standard_layer_paths_for_dir(dir);
+    Ok(resolve_settings(&paths, None)?.retention())
+}
+
+/// Merges defaults, options chunks, `agentsdb.toml` (`config_file`, or the one next to the
+/// base layer) and environment variables.
+pub fn resolve_settings(
+    paths: &StandardLayerPaths,
+    config_file: Option<&Path>,
+) -> anyhow::Result<EffectiveSettings> {
+    resolve_settings_with_env(paths, config_file, &|var| std::env::var(var).ok())
+}
+
+pub fn resolve_settings_with_env(
+    paths: &StandardLayerPaths,
+    config_file: Option<&Path>,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> anyhow::Result<EffectiveSettings> {
+    let mut settings = EffectiveSettings::defaults()?;
+
+    for (layer, path) in [
+        ("base", &paths.base),
+        ("delta", &paths.delta),
+        ("user", &paths.user),
+        ("local", &paths.local),
+    ] {
+        let source = SettingSource::Layer {
+            layer,
+            path: path.display().to_string(),
+        };
+        for (section, body) in last_sections_in_layer(path)? {
+            // Embedding options are immutable and only read from the base layer.
+            if section == "embedding" && layer != "base" {
+                continue;
+            }
+            if section == TRUST_SECTION {
+                continue;
+            }
+            // Everyone shares the other layers; an identity there would name all writers.
+            if section == IDENTITY_SECTION && layer != "local" {
+                continue;
+            }
+            settings.apply_section(&section, &body, &source);
+        }
+    }
+
+    let from_base_options = settings.settings.iter().any(|s| {
+        s.key.starts_with("embedding.") && matches!(s.source, SettingSource::Layer { .. })
+    });
+    if !from_base_options {
+        apply_base_metadata(&mut settings, &paths.base)?;
+    }
+
+    let config_file = match config_file {
+        Some(path) => Some(path.to_path_buf()),
+        None => paths.base.parent().and_then(default_config_file),
+    };
+    if let Some(file) = config_file {
+        settings.apply_config_file(&file)?;
+    }
+
+    settings.apply_env(env)?;
+    Ok(settings)
+}
+
+/// The last non-null value of each section across a layer's options chunks.
+fn last_sections_in_layer(path: &Path) -> anyhow::Result<Vec<(String, Value)>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = agentsdb_format::LayerFile::open(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    let mut last: Vec<(String, Value)> = Vec::new();
+    for chunk in file.chunks() {
+        let chunk = chunk.context("read chunk")?;
+        if chunk.kind != KIND_OPTIONS {
+            continue;
+        }
+        let doc: Value = serde_json::from_str(chunk.content).context("parse options JSON")?;
+        validate_document(&doc, DocumentKind::OptionsChunk).with_context(|| {
+            format!(
+                "invalid options chunk id={} in {}",
+                chunk.id,
+                path.display()
+            )
+        })?;
+        let Value::Object(sections) = doc else {
+            continue;
+        };
+        for (section, body) in sections {
+            if !SECTIONS.contains(&section.as_str()) || body.is_null() {
+                continue;
+            }
+            last.retain(|(s, _)| *s != section);
+            last.push((section, body));
+        }
+    }
+    Ok(last)
+}
+
+fn apply_base_metadata(settings: &mut EffectiveSettings, base: &Path) -> anyhow::Result<()> {
+    if !base.exists() {
+        return Ok(());
+    }
+    let file = agentsdb_format::LayerFile::open(base)
+        .with_context(|| format!("open {}", base.display()))?;
+    let Some(bytes) = file.layer_metadata_bytes() else {
+        return Ok(());
+    };
+    let Ok(metadata) = crate::layer_metadata::LayerMetadataV1::from_json_bytes(bytes) else {
+        return Ok(());
+    };
+    let profile = metadata.embedding_profile;
+    let body = serde_json::json!({
+        "backend": profile.backend,
+        "model": profile.model,
+        "revision": profile.revision,
+        "dim": profile.dim,
+    });
+    let source = SettingSource::LayerMetadata {
+        path: base.display().to_string(),
+    };
+    settings.apply_section("embedding", &body, &source);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::standard_layer_paths_for_dir;
+
+    fn write_options(path: &Path, content: &str) {
+        let schema = agentsdb_format::LayerSchema {
+            dim: 4,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let mut chunks = [agentsdb_format::ChunkInput {
+            id: 1,
+            kind: KIND_OPTIONS.to_string(),
+            content: content.to_string(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 0,
+            embedding: vec![0.0; 4],
+            sources: Vec::new(),
+        }];
+        agentsdb_format::write_layer_atomic(path, &schema, &mut chunks, None).unwrap();
+    }
+
+    #[test]
+    fn validate_reports_unknown_keys_with_suggestions() {
+        let doc = serde_json::json!({
+            "search": { "top_k": 3, "mode": "semantc" },
+            "server": { "max_in_flight": 0 },
+            "embeding": {}
+        });
+        let err = validate_document(&doc, DocumentKind::ConfigFile)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown key `search.top_k`"), "{err}");
+        assert!(err.contains("did you mean `semantic`?"), "{err}");
+        assert!(
+            err.contains("server.max_in_flight: expected a positive integer"),
+            "{err}"
+        );
+        assert!(err.contains("did you mean `embedding`?"), "{err}");
+    }
+
+    #[test]
+    fn resolve_tracks_source_per_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = standard_layer_paths_for_dir(dir.path());
+        write_options(
+            &paths.base,
+            r#"{"embedding":{"backend":"hash","dim":4},"search":{"k":7}}"#,
+        );
+        write_options(&paths.local, r#"{"search":{"mode":"semantic"}}"#);
+        std::fs::write(
+            dir.path().join(CONFIG_FILE_NAME),
+            "[layers]\nbase = \"AGENTS.db\"\n\n[server]\nread_only = true\n",
+        )
+        .unwrap();
+
+        let env = |var: &str| (var == "AGENTSDB_SEARCH_K").then(|| "9".to_string());
+        let settings = resolve_settings_with_env(&paths, None, &env).unwrap();
+        let source = |key: &str| {
+            settings
+                .settings
+                .iter()
+                .find(|s| s.key == key)
+                .unwrap()
+                .source
+                .clone()
+        };
+
+        assert_eq!(settings.search().k, 9);
+        assert_eq!(
+            source("search.k"),
+            SettingSource::Env {
+                var: "AGENTSDB_SEARCH_K"
+            }
+        );
+        assert_eq!(settings.search().mode, "semantic");
+        assert!(matches!(
+            source("search.mode"),
+            SettingSource::Layer { layer: "local", .. }
+        ));
+        assert!(settings.server().read_only);
+        assert!(matches!(
+            source("server.read_only"),
+            SettingSource::File { .. }
+        ));
+        assert_eq!(settings.get("embedding.dim"), Some(&Value::from(4)));
+        assert_eq!(source("server.max_in_flight"), SettingSource::Default);
+    }
+
+    #[test]
+    fn parse_assignment_checks_types() {
+        let (spec, value) = parse_assignment("server.write_scopes=local").unwrap();
+        assert_eq!(spec.key, "server.write_scopes");
+        assert_eq!(value, serde_json::json!(["local"]));
+        assert!(parse_assignment("search.k=zero").is_err());
+        assert!(parse_assignment("search.kk=1")
+            .unwrap_err()
+            .to_string()
+            .contains("did you mean `search.k`?"));
+    }
+
+    #[test]
+    fn language_settings_parse_groups_and_tags() {
+        let (_, value) = parse_assignment(
+            "embedding.language_models=en=all-minilm-l6-v2; de,fr=multilingual-e5-small",
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"en": "all-minilm-l6-v2", "de,fr": "multilingual-e5-small"})
+        );
+        assert!(parse_assignment("embedding.language_models=german=m").is_err());
+        assert!(parse_assignment("embedding.language_models=*=").is_err());
+        assert!(parse_assignment("embedding.languages=de,fr-CA").is_ok());
+        assert!(parse_assignment("embedding.languages=deutsch").is_err());
+    }
+
+    #[test]
+    fn trust_comes_from_the_config_file_and_env_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = standard_layer_paths_for_dir(dir.path());
+        let key = crate::signing::LayerSigningKey::generate()
+            .unwrap()
+            .public_key();
+
+        let doc = serde_json::json!({ "trust": { "mode": "off" } });
+        let err = validate_document(&doc, DocumentKind::OptionsChunk)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("can only be set in agentsdb.toml"), "{err}");
+        assert!(parse_assignment("trust.public_keys=abc").is_err());
+
+        std::fs::write(
+            dir.path().join(CONFIG_FILE_NAME),
+            format!(
+                "[trust]\nmode = \"warn\"\npublic_keys = [\"{}\"]\n",
+                key.to_uppercase()
+            ),
+        )
+        .unwrap();
+        let env = |var: &str| (var == "AGENTSDB_TRUST_LAYERS").then(|| "base,user".to_string());
+        let trust =
+            resolve_settings_with_env(&paths, Some(&dir.path().join(CONFIG_FILE_NAME)), &env)
+                .unwrap()
+                .trust();
+        assert_eq!(trust.mode, TrustMode::Warn);
+        assert_eq!(trust.public_keys, [key]);
+        assert_eq!(trust.layers, ["base", "user"]);
+        assert_eq!(
+            EffectiveSettings::defaults().unwrap().trust().layers,
+            ["base"]
+        );
+    }
+
+    #[test]
+    fn identity_is_only_read_from_the_local_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = standard_layer_paths_for_dir(dir.path());
+        write_options(&paths.delta, r#"{"identity":{"name":"Everyone"}}"#);
+        write_options(
+            &paths.local,
+            r#"{"identity":{"name":"Ada  Lovelace","email":"ada@example.com"}}"#,
+        );
+        let env = |var: &str| (var == "AGENTSDB_IDENTITY_ACTOR").then(|| "ci/nightly".to_string());
+        let identity = resolve_settings_with_env(&paths, None, &env)
+            .unwrap()
+            .identity();
+        assert_eq!(
+            identity.label().as_deref(),
+            Some("Ada Lovelace <ada@example.com>")
+        );
+        assert_eq!(identity.actor.as_deref(), Some("ci/nightly"));
+
+        std::fs::remove_file(&paths.local).unwrap();
+        let identity = resolve_settings_with_env(&paths, None, &env)
+            .unwrap()
+            .identity();
+        assert_eq!(identity.label().as_deref(), Some("ci/nightly"));
+    }
+}
@@ -0,0 +1,453 @@
+//! Ed25519 signatures over layer content.
+//!
+//! A signed layer carries a [`LayerSignature`] in its metadata. The signature covers
+//! [`content_sha256`]: the schema, the rest of the layer metadata and every chunk record with its
+//! embedding and sources, so any change to what readers see invalidates it. Signing keys are 32
+//! byte Ed25519 seeds written as hex, read from a file or from [`SIGNING_KEY_ENV`].
+//!
+//! Readers decide what to accept with a [`TrustPolicy`] (the `trust.*` settings). Trust is only
+//! configured in `agentsdb.toml` or the environment, never by options chunks: a layer must not
+//! be able to vouch for itself.
+
+use anyhow::Context;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use agentsdb_core::error::TrustError;
+use agentsdb_format::{ChunkSource, EmbeddingElementType, LayerFile};
+
+use crate::cache::{hex_lower, sha256};
+use crate::layer_metadata::{LayerMetadataV1, LayerSignature};
+
+/// Signature algorithm recorded in [`LayerSignature::alg`].
+pub const SIGNATURE_ALG: &str = "ed25519";
+
+/// Environment variable holding a signing key (64 hex chars).
+pub const SIGNING_KEY_ENV: &str = "AGENTSDB_SIGNING_KEY";
+
+/// Environment variable naming a file that holds a signing key.
+pub const SIGNING_KEY_FILE_ENV: &str = "AGENTSDB_SIGNING_KEY_FILE";
+
+/// Prefix of the digest input, so a layer digest cannot be mistaken for any other signed data.
+const DIGEST_DOMAIN: &[u8] = b"agentsdb.layer-signature.v1\n";
+
+/// An Ed25519 key that signs layers.
+pub struct LayerSigningKey(SigningKey);
+
+impl std::fmt::Debug for LayerSigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LayerSigningKey")
+            .field("public_key", &self.public_key())
+            .finish_non_exhaustive()
+    }
+}
+
+impl LayerSigningKey {
+    /// A new random key.
+    pub fn generate() -> anyhow::Result<Self> {
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed)
+            .map_err(|e| anyhow::anyhow!("generate signing key: {e}"))?;
+        Ok(Self(SigningKey::from_bytes(&seed)))
+    }
+
+    /// A key written as 64 hex chars (surrounding whitespace is ignored).
+    pub fn from_hex(text: &str) -> anyhow::Result<Self> {
+        let seed = decode_hex::<32>(text.trim()).context("signing key must be 64 hex chars")?;
+        Ok(Self(SigningKey::from_bytes(&seed)))
+    }
+
+    /// The key stored in the file at `path`.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("read signing key {}", path.display()))?;
+        Self::from_hex(&text).with_context(|| format!("parse signing key {}", path.display()))
+    }
+
+    /// The key in [`SIGNING_KEY_ENV`], or else in the file named by [`SIGNING_KEY_FILE_ENV`];
+    /// `None` if neither is set.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        if let Ok(text) = std::env::var(SIGNING_KEY_ENV) {
+            return Self::from_hex(&text)
+                .with_context(|| format!("invalid {SIGNING_KEY_ENV}"))
+                .map(Some);
+        }
+        match std::env::var(SIGNING_KEY_FILE_ENV) {
+            Ok(path) => Self::from_file(Path::new(&path)).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// The secret key as 64 lowercase hex chars.
+    pub fn to_hex(&self) -> String {
+        hex_lower(self.0.as_bytes())
+    }
+
+    /// The public key as 64 lowercase hex chars.
+    pub fn public_key(&self) -> String {
+        hex_lower(self.0.verifying_key().as_bytes())
+    }
+
+    /// Signs `content_sha256` (see [`content_sha256`]).
+    pub fn sign_digest(&self, content_sha256: &[u8; 32]) -> LayerSignature {
+        LayerSignature {
+            alg: SIGNATURE_ALG.to_string(),
+            public_key: self.public_key(),
+            content_sha256: hex_lower(content_sha256),
+            signature: hex_lower(&self.0.sign(content_sha256).to_bytes()),
+        }
+    }
+}
+
+/// Parses a public key written as 64 hex chars.
+pub fn parse_public_key(text: &str) -> anyhow::Result<VerifyingKey> {
+    let bytes = decode_hex::<32>(text.trim()).context("public key must be 64 hex chars")?;
+    VerifyingKey::from_bytes(&bytes).context("not an Ed25519 public key")
+}
+
+/// The digest a signature covers: the schema, the metadata without its signature (`None` for a
+/// layer without metadata) and `chunks` in file order.
+pub fn content_sha256_of(
+    schema: &agentsdb_format::LayerSchema,
+    metadata: Option<&LayerMetadataV1>,
+    chunks: &[agentsdb_format::ChunkInput],
+) -> anyhow::Result<[u8; 32]> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(DIGEST_DOMAIN);
+    buf.extend_from_slice(&schema.dim.to_le_bytes());
+    buf.push(match schema.element_type {
+        EmbeddingElementType::F32 => 1,
+        EmbeddingElementType::I8 => 2,
+    });
+    buf.extend_from_slice(&schema.quant_scale.to_bits().to_le_bytes());
+    let metadata = metadata
+        .map(|m| m.clone().with_signature(None).to_json_bytes())
+        .transpose()?
+        .unwrap_or_default();
+    put_bytes(&mut buf, &metadata);
+
+    buf.extend_from_slice(&(chunks.len() as u64).to_le_bytes());
+    for chunk in chunks {
+        buf.extend_from_slice(&chunk.id.to_le_bytes());
+        put_bytes(&mut buf, chunk.kind.as_bytes());
+        put_bytes(&mut buf, chunk.content.as_bytes());
+        put_bytes(&mut buf, chunk.author.as_bytes());
+        buf.extend_from_slice(&chunk.confidence.to_bits().to_le_bytes());
+        buf.extend_from_slice(&chunk.created_at_unix_ms.to_le_bytes());
+        buf.extend_from_slice(&(chunk.embedding.len() as u64).to_le_bytes());
+        for x in &chunk.embedding {
+            buf.extend_from_slice(&x.to_bits().to_le_bytes());
+        }
+        buf.extend_from_slice(&(chunk.sources.len() as u64).to_le_bytes());
+        for source in &chunk.sources {
+            match source {
+                ChunkSource::ChunkId(id) => {
+                    buf.push(1);
+                    buf.extend_from_slice(&id.to_le_bytes());
+                }
+                ChunkSource::SourceString(value) => {
+                    buf.push(2);
+                    put_bytes(&mut buf, value.as_bytes());
+                }
+            }
+        }
+    }
+    Ok(sha256(&buf))
+}
+
+/// [`content_sha256_of`] the layer `file`.
+pub fn content_sha256(file: &LayerFile) -> anyhow::Result<[u8; 32]> {
+    let metadata = layer_metadata(file)?;
+    let chunks = agentsdb_format::read_all_chunks(file).context("read chunks")?;
+    content_sha256_of(
+        &agentsdb_format::schema_of(file),
+        metadata.as_ref(),
+        &chunks,
+    )
+}
+
+fn layer_metadata(file: &LayerFile) -> anyhow::Result<Option<LayerMetadataV1>> {
+    file.layer_metadata_bytes()
+        .map(LayerMetadataV1::from_json_bytes)
+        .transpose()
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+/// What the signature of a layer says about it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// The layer carries no signature.
+    Unsigned,
+    /// The signature matches the layer's content.
+    Valid { public_key: String },
+    /// The layer carries a signature that does not match its content, or cannot be checked.
+    Invalid {
+        public_key: Option<String>,
+        reason: String,
+    },
+}
+
+/// Checks the signature in `file`'s metadata against its content.
+pub fn verify_layer(file: &LayerFile) -> SignatureStatus {
+    let metadata = match layer_metadata(file) {
+        Ok(Some(metadata)) => metadata,
+        Ok(None) => return SignatureStatus::Unsigned,
+        Err(err) => {
+            return SignatureStatus::Invalid {
+                public_key: None,
+                reason: format!("{err:#}"),
+            }
+        }
+    };
+    let Some(signature) = metadata.signature.clone() else {
+        return SignatureStatus::Unsigned;
+    };
+    let invalid = |reason: String| SignatureStatus::Invalid {
+        public_key: Some(signature.public_key.clone()),
+        reason,
+    };
+    if signature.alg != SIGNATURE_ALG {
+        return invalid(format!("unsupported algorithm {:?}", signature.alg));
+    }
+    let key = match parse_public_key(&signature.public_key) {
+        Ok(key) => key,
+        Err(err) => return invalid(format!("{err:#}")),
+    };
+    let digest = match agentsdb_format::read_all_chunks(file) {
+        Ok(chunks) => {
+            content_sha256_of(&agentsdb_format::schema_of(file), Some(&metadata), &chunks)
+        }
+        Err(err) => return invalid(format!("read chunks: {err}")),
+    };
+    let digest = match digest {
+        Ok(digest) => digest,
+        Err(err) => return invalid(format!("{err:#}")),
+    };
+    if hex_lower(&digest) != signature.content_sha256 {
+        return invalid("content changed since it was signed".to_string());
+    }
+    let Ok(bytes) = decode_hex::<64>(&signature.signature) else {
+        return invalid("signature must be 128 hex chars".to_string());
+    };
+    if key
+        .verify_strict(&digest, &Signature::from_bytes(&bytes))
+        .is_err()
+    {
+        return invalid("signature does not match the public key".to_string());
+    }
+    SignatureStatus::Valid {
+        public_key: signature.public_key,
+    }
+}
+
+/// What readers do with a layer the [`TrustPolicy`] covers that is not signed by a trusted key.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustMode {
+    /// Read it without checking.
+    #[default]
+    Off,
+    /// Read it with a warning.
+    Warn,
+    /// Refuse to open it.
+    Enforce,
+}
+
+impl std::fmt::Display for TrustMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Off => "off",
+            Self::Warn => "warn",
+            Self::Enforce => "enforce",
+        })
+    }
+}
+
+/// Which layers must be signed, and by whom.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustPolicy {
+    pub mode: TrustMode,
+    /// Trusted public keys (64 lowercase hex chars each).
+    pub public_keys: Vec<String>,
+    /// Logical layers (`base`, `user`, `delta`, `local`) the policy covers.
+    pub layers: Vec<String>,
+}
+
+impl TrustPolicy {
+    /// Whether opening `layer` needs a check.
+    pub fn covers(&self, layer: &str) -> bool {
+        self.mode != TrustMode::Off && self.layers.iter().any(|l| l == layer)
+    }
+
+    pub fn trusts_key(&self, public_key: &str) -> bool {
+        self.public_keys
+            .iter()
+            .any(|k| k.eq_ignore_ascii_case(public_key))
+    }
+
+    /// Checks the signature `status` of `layer` (at `path`). A covered layer that is not validly
+    /// signed by a trusted key is an error under [`TrustMode::Enforce`]; under
+    /// [`TrustMode::Warn`] the warning is returned (and logged).
+    pub fn check(
+        &self,
+        layer: &str,
+        path: &Path,
+        status: &SignatureStatus,
+    ) -> Result<Option<String>, TrustError> {
+        if !self.covers(layer) {
+            return Ok(None);
+        }
+        let reason = match status {
+            SignatureStatus::Valid { public_key } if self.trusts_key(public_key) => {
+                return Ok(None)
+            }
+            SignatureStatus::Valid { public_key } => {
+                format!("signed by untrusted key {public_key}")
+            }
+            SignatureStatus::Unsigned => "not signed".to_string(),
+            SignatureStatus::Invalid { reason, .. } => format!("invalid signature: {reason}"),
+        };
+        let err = TrustError::Untrusted {
+            path: path.to_path_buf(),
+            reason,
+        };
+        if self.mode == TrustMode::Enforce {
+            return Err(err);
+        }
+        let warning = err.to_string();
+        tracing::warn!("{warning}");
+        Ok(Some(warning))
+    }
+}
+
+fn decode_hex<const N: usize>(text: &str) -> anyhow::Result<[u8; N]> {
+    let bytes = text.as_bytes();
+    if bytes.len() != N * 2 {
+        anyhow::bail!("expected {} hex chars, got {}", N * 2, bytes.len());
+    }
+    let nibble = |b: u8| match b {
+        b'0'..=b'9' => Ok(b - b'0'),
+        b'a'..=b'f' => Ok(b - b'a' + 10),
+        b'A'..=b'F' => Ok(b - b'A' + 10),
+        _ => Err(anyhow::anyhow!("invalid hex char {:?}", char::from(b))),
+    };
+    let mut out = [0u8; N];
+    for (slot, pair) in out.iter_mut().zip(bytes.chunks_exact(2)) {
+        *slot = (nibble(pair[0])? << 4) | nibble(pair[1])?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedder::EmbeddingProfile;
+
+    fn write_layer(path: &Path, content: &str, signing: Option<&LayerSigningKey>) {
+        let schema = agentsdb_format::LayerSchema {
+            dim: 2,
+            element_type: EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let mut chunks = vec![agentsdb_format::ChunkInput {
+            id: 1,
+            kind: "note".to_string(),
+            content: content.to_string(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 1,
+            embedding: vec![0.5, -0.5],
+            sources: vec![ChunkSource::SourceString("README.md:1".to_string())],
+        }];
+        let mut metadata = LayerMetadataV1::new(EmbeddingProfile {
+            backend: "hash".to_string(),
+            model: None,
+            revision: None,
+            dim: 2,
+            output_norm: crate::embedder::OutputNorm::None,
+        });
+        if let Some(key) = signing {
+            let digest = content_sha256_of(&schema, Some(&metadata), &chunks).unwrap();
+            metadata = metadata.with_signature(Some(key.sign_digest(&digest)));
+        }
+        let json = metadata.to_json_bytes().unwrap();
+        agentsdb_format::write_layer_atomic(path, &schema, &mut chunks, Some(&json)).unwrap();
+    }
+
+    #[test]
+    fn signatures_detect_changed_content_and_untrusted_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("AGENTS.db");
+        let key =
+            LayerSigningKey::from_hex(&LayerSigningKey::generate().unwrap().to_hex()).unwrap();
+
+        write_layer(&path, "use pnpm", Some(&key));
+        let status = verify_layer(&LayerFile::open(&path).unwrap());
+        assert_eq!(
+            status,
+            SignatureStatus::Valid {
+                public_key: key.public_key()
+            }
+        );
+
+        let mut policy = TrustPolicy {
+            mode: TrustMode::Enforce,
+            public_keys: Vec::new(),
+            layers: vec!["base".to_string()],
+        };
+        assert!(policy.check("base", &path, &status).is_err());
+        assert_eq!(policy.check("local", &path, &status).ok(), Some(None));
+        policy.public_keys.push(key.public_key().to_uppercase());
+        assert_eq!(policy.check("base", &path, &status).ok(), Some(None));
+
+        // Same signature, different content.
+        let signed = LayerFile::open(&path).unwrap();
+        let metadata =
+            LayerMetadataV1::from_json_bytes(signed.layer_metadata_bytes().unwrap()).unwrap();
+        drop(signed);
+        write_layer(&path, "use npm", None);
+        let file = LayerFile::open(&path).unwrap();
+        let json = LayerMetadataV1::from_json_bytes(file.layer_metadata_bytes().unwrap())
+            .unwrap()
+            .with_signature(metadata.signature)
+            .to_json_bytes()
+            .unwrap();
+        let schema = agentsdb_format::schema_of(&file);
+        let mut chunks = agentsdb_format::read_all_chunks(&file).unwrap();
+        drop(file);
+        agentsdb_format::write_layer_atomic(&path, &schema, &mut chunks, Some(&json)).unwrap();
+        let status = verify_layer(&LayerFile::open(&path).unwrap());
+        assert!(
+            matches!(status, SignatureStatus::Invalid { ref reason, .. } if reason.contains("content changed")),
+            "{status:?}"
+        );
+        assert!(policy.check("base", &path, &status).is_err());
+        policy.mode = TrustMode::Warn;
+        assert!(policy
+            .check("base", &path, &status)
+            .is_ok_and(|w| w.is_some()));
+
+        write_layer(&path, "use npm", None);
+        assert_eq!(
+            verify_layer(&LayerFile::open(&path).unwrap()),
+            SignatureStatus::Unsigned
+        );
+    }
+
+    #[test]
+    fn keys_round_trip_through_hex() {
+        assert!(LayerSigningKey::from_hex("abc").is_err());
+        assert!(parse_public_key(&"zz".repeat(32)).is_err());
+        let key = LayerSigningKey::generate().unwrap();
+        let again = LayerSigningKey::from_hex(&format!("  {}\n", key.to_hex())).unwrap();
+        assert_eq!(again.public_key(), key.public_key());
+        assert!(parse_public_key(&key.public_key()).is_ok());
+    }
+}
@@ -0,0 +1,168 @@
+//! Embedding provider usage (request/token) accounting.
+
+use serde::{Deserialize, Serialize};
+
+use crate::embedder::Embedder;
+
+/// Usage accumulated across `Embedder::embed` calls.
+///
+/// Token counts are taken from provider responses when the provider reports them
+/// (OpenAI/Voyage `usage`, Cohere `meta.billed_units`, Bedrock `inputTextTokenCount`);
+/// local and hash backends only report request/input counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingUsage {
+    /// Calls that reached the backend (cache hits excluded).
+    pub requests: u64,
+    /// Inputs sent to the backend.
+    pub inputs: u64,
+    /// Inputs served from the embedding cache without a backend call.
+    pub cached_inputs: u64,
+    /// Input tokens reported by the provider.
+    pub input_tokens: u64,
+}
+
+impl EmbeddingUsage {
+    pub const fn add(&mut self, other: &Self) {
+        self.requests += other.requests;
+        self.inputs += other.inputs;
+        self.cached_inputs += other.cached_inputs;
+        self.input_tokens += other.input_tokens;
+    }
+
+    /// Records one backend call for `inputs` inputs, taking the token count from the
+    /// provider response metadata captured for that call (if any).
+    pub fn record_request(&mut self, inputs: usize, provider_response: Option<&serde_json::Value>) {
+        self.requests += 1;
+        self.inputs += inputs as u64;
+        self.input_tokens += provider_response
+            .and_then(input_tokens_from_provider_response)
+            .unwrap_or(0);
+    }
+}
+
+/// Extracts the input token count from captured provider response metadata.
+pub fn input_tokens_from_provider_response(response: &serde_json::Value) -> Option<u64> {
+    let usage = response.get("usage");
+    usage
+        .and_then(|u| u.get("prompt_tokens"))
+        .or_else(|| usage.and_then(|u| u.get("total_tokens")))
+        .or_else(|| {
+            response
+                .get("meta")
+                .and_then(|m| m.get("billed_units"))
+                .and_then(|b| b.get("input_tokens"))
+        })
+        .or_else(|| response.get("inputTextTokenCount"))
+        .and_then(serde_json::Value::as_u64)
+}
+
+/// Usage summary for one operation, suitable for JSON reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageReport {
+    pub backend: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(flatten)]
+    pub usage: EmbeddingUsage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_per_million_tokens: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_cost: Option<f64>,
+}
+
+impl UsageReport {
+    pub fn from_embedder(embedder: &dyn Embedder) -> Self {
+        let profile = embedder.profile();
+        Self {
+            backend: profile.backend.clone(),
+            model: profile.model.clone(),
+            usage: embedder.usage(),
+            price_per_million_tokens: None,
+            estimated_cost: None,
+        }
+    }
+
+    /// Sets the price used to estimate cost (in the provider's billing currency).
+    pub fn with_price(mut self, price_per_million_tokens: Option<f64>) -> Self {
+        self.price_per_million_tokens = price_per_million_tokens;
+        self.estimate_cost();
+        self
+    }
+
+    /// Combines reports from several operations (e.g. one per imported layer).
+    pub fn merge(&mut self, other: &Self) {
+        self.usage.add(&other.usage);
+        if self.model != other.model {
+            self.model = None;
+        }
+        self.estimate_cost();
+    }
+
+    // Token counts stay far below 2^52, so the f64 conversion is exact.
+    #[allow(clippy::cast_precision_loss)]
+    fn estimate_cost(&mut self) {
+        self.estimated_cost = self
+            .price_per_million_tokens
+            .map(|p| self.usage.input_tokens as f64 * p / 1_000_000.0);
+    }
+
+    /// One-line human-readable summary.
+    pub fn summary(&self) -> String {
+        let mut s = format!(
+            "Embedding usage: backend={} requests={} inputs={} cached={} tokens={}",
+            self.backend,
+            self.usage.requests,
+            self.usage.inputs,
+            self.usage.cached_inputs,
+            self.usage.input_tokens
+        );
+        if let Some(cost) = self.estimated_cost {
+            s.push_str(&format!(" est_cost={cost:.6}"));
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_tokens_from_known_provider_shapes() {
+        let openai = serde_json::json!({"usage": {"prompt_tokens": 7, "total_tokens": 7}});
+        let voyage = serde_json::json!({"usage": {"total_tokens": 5}});
+        let cohere = serde_json::json!({"meta": {"billed_units": {"input_tokens": 3}}});
+        let bedrock = serde_json::json!({"inputTextTokenCount": 2});
+        assert_eq!(input_tokens_from_provider_response(&openai), Some(7));
+        assert_eq!(input_tokens_from_provider_response(&voyage), Some(5));
+        assert_eq!(input_tokens_from_provider_response(&cohere), Some(3));
+        assert_eq!(input_tokens_from_provider_response(&bedrock), Some(2));
+        assert_eq!(
+            input_tokens_from_provider_response(&serde_json::json!({})),
+            None
+        );
+    }
+
+    #[test]
+    fn report_estimates_cost_from_price() {
+        let mut report = UsageReport {
+            backend: "openai".to_string(),
+            model: Some("text-embedding-3-small".to_string()),
+            usage: EmbeddingUsage {
+                requests: 1,
+                inputs: 2,
+                cached_inputs: 0,
+                input_tokens: 500_000,
+            },
+            price_per_million_tokens: None,
+            estimated_cost: None,
+        }
+        .with_price(Some(0.02));
+        assert!((report.estimated_cost.unwrap_or_default() - 0.01).abs() < 1e-9);
+
+        let other = report.clone();
+        report.merge(&other);
+        assert_eq!(report.usage.input_tokens, 1_000_000);
+        assert!((report.estimated_cost.unwrap_or_default() - 0.02).abs() < 1e-9);
+    }
+}
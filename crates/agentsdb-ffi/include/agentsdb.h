@@ -0,0 +1,53 @@
+/* C interface for reading AGENTS.db layer files. Link against libagentsdb_ffi. */
+#ifndef AGENTSDB_H
+#define AGENTSDB_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct AgentsdbLayer AgentsdbLayer;
+
+typedef struct AgentsdbHit {
+  uint32_t id;
+  float score;
+} AgentsdbHit;
+
+typedef struct AgentsdbChunk {
+  uint32_t id;
+  char *kind;
+  char *content;
+  char *author;
+  float confidence;
+  uint64_t created_at_unix_ms;
+} AgentsdbChunk;
+
+/* Message of the last failed call on this thread, or NULL. */
+const char *agentsdb_last_error(void);
+
+/* Opens a layer file, including one with edited chunks; NULL on failure. Release with
+ * agentsdb_close. */
+AgentsdbLayer *agentsdb_open(const char *path);
+void agentsdb_close(AgentsdbLayer *layer);
+
+/* Length of the query vectors agentsdb_search expects. */
+size_t agentsdb_embedding_dim(const AgentsdbLayer *layer);
+
+/* Writes up to out_len hits, best first; returns the number written or -1. */
+ssize_t agentsdb_search(const AgentsdbLayer *layer, const float *query, size_t query_len,
+                        AgentsdbHit *out, size_t out_len);
+
+/* Latest version of chunk id, or NULL if absent/retracted or on failure.
+ * Release with agentsdb_chunk_free. */
+AgentsdbChunk *agentsdb_chunk_get(const AgentsdbLayer *layer, uint32_t id);
+void agentsdb_chunk_free(AgentsdbChunk *chunk);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* AGENTSDB_H */
@@ -0,0 +1,365 @@
+//! C ABI for reading AGENTS.db layer files.
+//!
+//! The functions here are declared in `include/agentsdb.h`. A layer opened with
+//! [`agentsdb_open`] is released with [`agentsdb_close`]; a chunk returned by
+//! [`agentsdb_chunk_get`] is released with [`agentsdb_chunk_free`]. Failing calls return NULL or
+//! -1 and leave a message for [`agentsdb_last_error`] on the calling thread.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+
+use agentsdb_core::types::{LayerId, SearchFilters};
+use agentsdb_format::LayerFile;
+use agentsdb_query::{retracted_chunk_ids, search_layers, SearchQuery, KIND_TOMBSTONE};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = None);
+}
+
+fn c_string(field: &str, s: &str) -> Result<CString, String> {
+    CString::new(s).map_err(|_| format!("chunk {field} contains a NUL byte"))
+}
+
+/// An open layer file. Opaque to C callers.
+pub struct AgentsdbLayer {
+    // `search_layers` takes a layer set; a handle is a set of one.
+    layers: Vec<(LayerId, LayerFile)>,
+}
+
+impl AgentsdbLayer {
+    fn file(&self) -> &LayerFile {
+        &self.layers[0].1
+    }
+}
+
+/// One search result: a chunk id and its similarity to the query.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AgentsdbHit {
+    pub id: u32,
+    pub score: f32,
+}
+
+/// A chunk returned by [`agentsdb_chunk_get`]. Strings are NUL-terminated UTF-8.
+#[repr(C)]
+#[derive(Debug)]
+pub struct AgentsdbChunk {
+    pub id: u32,
+    pub kind: *mut c_char,
+    pub content: *mut c_char,
+    pub author: *mut c_char,
+    pub confidence: f32,
+    pub created_at_unix_ms: u64,
+}
+
+/// Message of the last failed call on this thread, or NULL. Valid until the next call into this
+/// library on the same thread.
+#[no_mangle]
+pub extern "C" fn agentsdb_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |s| s.as_ptr())
+    })
+}
+
+/// Opens the layer file at `path`, including one whose chunks were edited (several records under
+/// one id). Returns NULL on failure.
+///
+/// # Safety
+/// `path` must be NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn agentsdb_open(path: *const c_char) -> *mut AgentsdbLayer {
+    clear_last_error();
+    if path.is_null() {
+        set_last_error("path is NULL".to_string());
+        return std::ptr::null_mut();
+    }
+    let Ok(path) = CStr::from_ptr(path).to_str() else {
+        set_last_error("path is not valid UTF-8".to_string());
+        return std::ptr::null_mut();
+    };
+    match LayerFile::open_lenient(path) {
+        Ok(file) => Box::into_raw(Box::new(AgentsdbLayer {
+            layers: vec![(LayerId::Base, file)],
+        })),
+        Err(e) => {
+            set_last_error(format!("open {path}: {e}"));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Closes a layer returned by [`agentsdb_open`]. NULL is ignored.
+///
+/// # Safety
+/// `layer` must be NULL or a handle from [`agentsdb_open`] that was not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn agentsdb_close(layer: *mut AgentsdbLayer) {
+    if !layer.is_null() {
+        drop(Box::from_raw(layer));
+    }
+}
+
+/// Embedding dimension of the layer; query vectors passed to [`agentsdb_search`] must have this
+/// length. Returns 0 for NULL.
+///
+/// # Safety
+/// `layer` must be NULL or a live handle from [`agentsdb_open`].
+#[no_mangle]
+pub unsafe extern "C" fn agentsdb_embedding_dim(layer: *const AgentsdbLayer) -> usize {
+    layer.as_ref().map_or(0, |l| l.file().embedding_dim())
+}
+
+/// Ranks the layer's chunks by cosine similarity to `query` and writes up to `out_len` hits,
+/// best first, to `out`. Tombstoned, options and `meta.*` chunks are skipped.
+///
+/// Returns the number of hits written, or -1 on failure.
+///
+/// # Safety
+/// `layer` must be a live handle from [`agentsdb_open`], `query` must point to `query_len`
+/// floats and `out` to `out_len` writable [`AgentsdbHit`]s.
+#[no_mangle]
+pub unsafe extern "C" fn agentsdb_search(
+    layer: *const AgentsdbLayer,
+    query: *const f32,
+    query_len: usize,
+    out: *mut AgentsdbHit,
+    out_len: usize,
+) -> isize {
+    clear_last_error();
+    let Some(layer) = layer.as_ref() else {
+        set_last_error("layer is NULL".to_string());
+        return -1;
+    };
+    if query.is_null() || (out.is_null() && out_len > 0) {
+        set_last_error("query or out is NULL".to_string());
+        return -1;
+    }
+    if out_len == 0 {
+        return 0;
+    }
+    let query = SearchQuery {
+        embedding: std::slice::from_raw_parts(query, query_len).to_vec(),
+        k: out_len,
+        filters: SearchFilters::default(),
+        query_text: None,
+    };
+    let results = match search_layers(&layer.layers, &query) {
+        Ok(results) => results,
+        Err(e) => {
+            set_last_error(format!("search: {e}"));
+            return -1;
+        }
+    };
+    let out = std::slice::from_raw_parts_mut(out, out_len);
+    for (slot, result) in out.iter_mut().zip(&results) {
+        *slot = AgentsdbHit {
+            id: result.chunk.id.get(),
+            score: result.score,
+        };
+    }
+    isize::try_from(results.len().min(out_len)).unwrap_or(isize::MAX)
+}
+
+fn chunk_get(layer: &AgentsdbLayer, id: u32) -> Result<Option<AgentsdbChunk>, String> {
+    let retracted = retracted_chunk_ids(&layer.layers).map_err(|e| e.to_string())?;
+    if retracted.contains_key(&id) {
+        return Ok(None);
+    }
+    let mut latest = None;
+    for chunk in layer.file().chunks() {
+        let chunk = chunk.map_err(|e| e.to_string())?;
+        if chunk.id == id && chunk.kind != KIND_TOMBSTONE {
+            latest = Some(chunk);
+        }
+    }
+    let Some(chunk) = latest else {
+        return Ok(None);
+    };
+    // Convert every field before handing any out, so a NUL byte in a later one leaks nothing.
+    let kind = c_string("kind", chunk.kind)?;
+    let content = c_string("content", chunk.content)?;
+    let author = c_string("author", chunk.author)?;
+    Ok(Some(AgentsdbChunk {
+        id: chunk.id,
+        kind: kind.into_raw(),
+        content: content.into_raw(),
+        author: author.into_raw(),
+        confidence: chunk.confidence,
+        created_at_unix_ms: chunk.created_at_unix_ms,
+    }))
+}
+
+/// Latest version of chunk `id`. Returns NULL if the layer does not hold it (or retracted it),
+/// and on failure, in which case [`agentsdb_last_error`] is set.
+///
+/// # Safety
+/// `layer` must be a live handle from [`agentsdb_open`].
+#[no_mangle]
+pub unsafe extern "C" fn agentsdb_chunk_get(
+    layer: *const AgentsdbLayer,
+    id: u32,
+) -> *mut AgentsdbChunk {
+    clear_last_error();
+    let Some(layer) = layer.as_ref() else {
+        set_last_error("layer is NULL".to_string());
+        return std::ptr::null_mut();
+    };
+    match chunk_get(layer, id) {
+        Ok(Some(chunk)) => Box::into_raw(Box::new(chunk)),
+        Ok(None) => std::ptr::null_mut(),
+        Err(e) => {
+            set_last_error(format!("read chunk {id}: {e}"));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a chunk returned by [`agentsdb_chunk_get`]. NULL is ignored.
+///
+/// # Safety
+/// `chunk` must be NULL or a pointer from [`agentsdb_chunk_get`] that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn agentsdb_chunk_free(chunk: *mut AgentsdbChunk) {
+    if chunk.is_null() {
+        return;
+    }
+    let chunk = Box::from_raw(chunk);
+    for s in [chunk.kind, chunk.content, chunk.author] {
+        if !s.is_null() {
+            drop(CString::from_raw(s));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentsdb_format::{ChunkInput, ChunkSource};
+
+    fn chunk(id: u32, kind: &str, content: &str, embedding: Vec<f32>) -> ChunkInput {
+        ChunkInput {
+            id,
+            kind: kind.to_string(),
+            content: content.to_string(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 0,
+            embedding,
+            sources: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn open_search_and_get_through_the_c_abi() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("AGENTS.db");
+        let schema = agentsdb_format::LayerSchema {
+            dim: 2,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let mut tombstone = chunk(3, KIND_TOMBSTONE, "", vec![0.0, 0.0]);
+        tombstone.sources = vec![ChunkSource::ChunkId(2)];
+        agentsdb_format::write_layer_atomic(
+            &path,
+            &schema,
+            &mut [
+                chunk(1, "note", "east", vec![1.0, 0.0]),
+                chunk(2, "note", "north", vec![0.0, 1.0]),
+                tombstone,
+            ],
+            None,
+        )?;
+
+        let c_path = CString::new(path.to_str().ok_or("path")?)?;
+        unsafe {
+            let layer = agentsdb_open(c_path.as_ptr());
+            assert!(!layer.is_null());
+            assert_eq!(agentsdb_embedding_dim(layer), 2);
+
+            let query = [1.0_f32, 0.0];
+            let mut hits = [AgentsdbHit::default(); 4];
+            let n = agentsdb_search(layer, query.as_ptr(), 2, hits.as_mut_ptr(), hits.len());
+            assert_eq!(n, 1, "retracted chunk 2 is not returned");
+            assert_eq!(hits[0].id, 1);
+
+            let got = agentsdb_chunk_get(layer, 1);
+            assert!(!got.is_null());
+            assert_eq!(CStr::from_ptr((*got).content).to_str()?, "east");
+            agentsdb_chunk_free(got);
+            assert!(agentsdb_chunk_get(layer, 2).is_null());
+            assert!(agentsdb_last_error().is_null());
+
+            agentsdb_close(layer);
+
+            let missing = CString::new("/nonexistent/AGENTS.db")?;
+            assert!(agentsdb_open(missing.as_ptr()).is_null());
+            assert!(!agentsdb_last_error().is_null());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn edited_layers_serve_the_latest_version() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("AGENTS.local.db");
+        let schema = agentsdb_format::LayerSchema {
+            dim: 2,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        agentsdb_format::write_layer_atomic(
+            &path,
+            &schema,
+            &mut [
+                chunk(1, "note", "east", vec![1.0, 0.0]),
+                chunk(2, "note", "north", vec![0.0, 1.0]),
+            ],
+            None,
+        )?;
+        agentsdb_format::append_layer_atomic(
+            &path,
+            &mut [
+                chunk(1, "note", "east, edited", vec![1.0, 0.0]),
+                chunk(2, "note", "north\0", vec![0.0, 1.0]),
+            ],
+            None,
+        )?;
+
+        let c_path = CString::new(path.to_str().ok_or("path")?)?;
+        unsafe {
+            let layer = agentsdb_open(c_path.as_ptr());
+            assert!(!layer.is_null(), "edited layers open");
+
+            let query = [1.0_f32, 0.0];
+            let mut hits = [AgentsdbHit::default(); 4];
+            let n = agentsdb_search(layer, query.as_ptr(), 2, hits.as_mut_ptr(), hits.len());
+            assert_eq!(n, 2, "each id is returned once");
+
+            let got = agentsdb_chunk_get(layer, 1);
+            assert!(!got.is_null());
+            assert_eq!(CStr::from_ptr((*got).content).to_str()?, "east, edited");
+            agentsdb_chunk_free(got);
+
+            assert!(agentsdb_chunk_get(layer, 2).is_null());
+            let error = CStr::from_ptr(agentsdb_last_error()).to_str()?;
+            assert!(error.contains("NUL"), "{error}");
+
+            agentsdb_close(layer);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+//! `append_layer_atomic` cost as the layer grows: each iteration appends a batch of chunks to a
+//! fresh copy of a layer already holding N chunks, so the numbers track how much of the file an
+//! append rewrites.
+#![allow(clippy::expect_used)]
+
+use agentsdb_format::{append_layer_atomic, write_layer_atomic, ChunkInput, EmbeddingElementType};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+const DIM: u32 = 64;
+
+fn chunks(ids: std::ops::Range<u32>) -> Vec<ChunkInput> {
+    ids.map(|id| ChunkInput {
+        id,
+        kind: "note".to_string(),
+        content: format!("chunk {id} about subsystem {}", id % 97),
+        author: "mcp".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: u64::from(id),
+        embedding: vec![0.125; DIM as usize],
+        sources: Vec::new(),
+    })
+    .collect()
+}
+
+fn bench_append(c: &mut Criterion) {
+    let mut group = c.benchmark_group("append_layer_atomic");
+    group.sample_size(10);
+    let dir = tempfile::tempdir().expect("tempdir");
+
+    for n in [1_000u32, 10_000, 100_000] {
+        let seed = dir.path().join(format!("seed-{n}.db"));
+        let schema = agentsdb_format::LayerSchema {
+            dim: DIM,
+            element_type: EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        write_layer_atomic(&seed, &schema, &mut chunks(1..n + 1), None).expect("write seed");
+        let target = dir.path().join(format!("AGENTS.delta-{n}.db"));
+
+        for batch in [1u32, 100] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("batch-{batch}"), n),
+                &batch,
+                |b, &batch| {
+                    b.iter_batched(
+                        || {
+                            std::fs::copy(&seed, &target).expect("copy seed");
+                            chunks(n + 1..n + 1 + batch)
+                        },
+                        |mut new_chunks| {
+                            append_layer_atomic(&target, &mut new_chunks, None).expect("append")
+                        },
+                        BatchSize::PerIteration,
+                    );
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_append);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+//! Opens arbitrary bytes as a layer file and reads everything it exposes. Malformed input must
+//! be rejected with an error, never a panic or an out-of-bounds read.
+#![no_main]
+
+use agentsdb_format::{read_all_chunks, LayerFile};
+use libfuzzer_sys::fuzz_target;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// `LayerFile` maps a path, so each input goes through one scratch file per process.
+fn scratch_path() -> &'static PathBuf {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| {
+        std::env::temp_dir().join(format!("agentsdb-fuzz-{}.db", std::process::id()))
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let path = scratch_path();
+    if std::fs::write(path, data).is_err() {
+        return;
+    }
+    for file in [LayerFile::open(path), LayerFile::open_lenient(path)]
+        .into_iter()
+        .flatten()
+    {
+        let _ = file.layer_metadata_json();
+        let mut row = Vec::new();
+        for chunk in file.chunks() {
+            let Ok(chunk) = chunk else { continue };
+            row.resize(file.embedding_dim(), 0.0);
+            let _ = file.read_embedding_row_f32(chunk.embedding_row, &mut row);
+            let _ = file.sources_for(chunk.rel_start, chunk.rel_count);
+        }
+        let _ = read_all_chunks(&file);
+    }
+});
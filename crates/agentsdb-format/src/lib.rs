@@ -1,13 +1,20 @@
+mod policy;
 mod reader;
+mod stats;
 pub mod writer;
 
+#[cfg(test)]
+mod test_support;
+
 pub use reader::{
-    ChunkView, EmbeddingElementType, EmbeddingMatrixHeaderV1, FileHeaderV1, LayerFile,
-    RelationshipKind, SectionEntry, SectionKind, SourceRef, StringDictionaryHeaderV1,
+    ChunkView, EmbeddingElementType, EmbeddingLayout, EmbeddingMatrixHeaderV1, FileHeaderV1,
+    FileStamp, LayerFile, RelationshipKind, SectionEntry, SectionKind, SourceRef, StringDictionaryHeaderV1,
+    EMBEDDING_BLOCK_ROWS,
 };
 
+pub use policy::WritePolicy;
+pub use stats::{ChunkStats, CONFIDENCE_BUCKET_BOUNDS};
 pub use writer::{
-    append_layer_atomic, ensure_writable_layer_path, ensure_writable_layer_path_allow_base,
-    ensure_writable_layer_path_allow_user, read_all_chunks, schema_of, write_layer_atomic,
-    ChunkInput, ChunkSource, LayerSchema,
+    append_layer_atomic, prepare_append_layer, prepare_write_layer, read_all_chunks, schema_of,
+    stage_layer_bytes, write_layer_atomic, ChunkInput, ChunkSource, LayerSchema,
 };
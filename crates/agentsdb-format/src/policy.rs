@@ -0,0 +1,254 @@
+use crate::writer::{append_layer_atomic, write_layer_atomic, ChunkInput, LayerSchema};
+use agentsdb_core::error::{Error, PermissionError};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ScopeRule {
+    scope: String,
+    file_name: String,
+    writable: bool,
+}
+
+/// Which layer files a writer may touch.
+///
+/// A policy maps write scopes (`"local"`, `"delta"`, ...) to layer file names and marks each one
+/// writable or protected. [`check`](Self::check) refuses a path whose file name belongs to a
+/// protected scope; file names the policy does not know are allowed.
+/// [`check_scope`](Self::check_scope) also requires the path to be the file of a writable scope.
+///
+/// Build one policy up front and pass it to whatever writes:
+///
+/// ```
+/// use agentsdb_format::WritePolicy;
+///
+/// let policy = WritePolicy::standard()
+///     .allow_user()
+///     .with_scope("scratch", "NOTES.scratch.db", true);
+/// assert!(policy.check("AGENTS.user.db").is_ok());
+/// assert!(policy.check("AGENTS.db").is_err());
+/// assert!(policy.check_scope("scratch", "NOTES.scratch.db").is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WritePolicy {
+    rules: Vec<ScopeRule>,
+}
+
+impl Default for WritePolicy {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl WritePolicy {
+    /// The standard layers: `local` (`AGENTS.local.db`) and `delta` (`AGENTS.delta.db`) are
+    /// writable, `user` (`AGENTS.user.db`) and `base` (`AGENTS.db`) are protected.
+    pub fn standard() -> Self {
+        Self { rules: Vec::new() }
+            .with_scope("local", "AGENTS.local.db", true)
+            .with_scope("delta", "AGENTS.delta.db", true)
+            .with_scope("user", "AGENTS.user.db", false)
+            .with_scope("base", "AGENTS.db", false)
+    }
+
+    /// The standard layers, all protected.
+    pub fn read_only() -> Self {
+        let mut policy = Self::standard();
+        for rule in &mut policy.rules {
+            rule.writable = false;
+        }
+        policy
+    }
+
+    /// Makes the `user` layer writable (promotions, compaction).
+    pub fn allow_user(self) -> Self {
+        self.set_writable("user", true)
+    }
+
+    /// Makes the `base` layer writable. An escape hatch for commands run with `--allow-base`.
+    pub fn allow_base(self) -> Self {
+        self.set_writable("base", true)
+    }
+
+    /// Sets the file name and writability of `scope`, adding the scope if it is new. Use it to
+    /// rename a standard layer or to add layers of your own.
+    pub fn with_scope(
+        mut self,
+        scope: impl Into<String>,
+        file_name: impl Into<String>,
+        writable: bool,
+    ) -> Self {
+        let rule = ScopeRule {
+            scope: scope.into(),
+            file_name: file_name.into(),
+            writable,
+        };
+        match self.rules.iter_mut().find(|r| r.scope == rule.scope) {
+            Some(existing) => *existing = rule,
+            None => self.rules.push(rule),
+        }
+        self
+    }
+
+    fn set_writable(mut self, scope: &str, writable: bool) -> Self {
+        if let Some(rule) = self.rules.iter_mut().find(|r| r.scope == scope) {
+            rule.writable = writable;
+        }
+        self
+    }
+
+    /// The layer file name of `scope`, writable or not.
+    pub fn file_name(&self, scope: &str) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|r| r.scope == scope)
+            .map(|r| r.file_name.as_str())
+    }
+
+    /// The layer file name of `scope`, if the scope is writable.
+    pub fn writable_file_name(&self, scope: &str) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|r| r.scope == scope && r.writable)
+            .map(|r| r.file_name.as_str())
+    }
+
+    /// The scope whose layer file is `path`, if the policy knows it.
+    pub fn scope_of(&self, path: impl AsRef<Path>) -> Option<&str> {
+        let name = file_name_of(path.as_ref());
+        self.rules
+            .iter()
+            .find(|r| r.file_name == name)
+            .map(|r| r.scope.as_str())
+    }
+
+    /// The writable scopes, in the order they were added.
+    pub fn writable_scopes(&self) -> impl Iterator<Item = &str> {
+        self.rules
+            .iter()
+            .filter(|r| r.writable)
+            .map(|r| r.scope.as_str())
+    }
+
+    /// Whether `scope` is known and writable.
+    pub fn is_writable_scope(&self, scope: &str) -> bool {
+        self.rules.iter().any(|r| r.scope == scope && r.writable)
+    }
+
+    /// Refuses `path` if its file name belongs to a protected scope.
+    pub fn check(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let name = file_name_of(path);
+        if self
+            .rules
+            .iter()
+            .any(|r| !r.writable && r.file_name == name)
+        {
+            return Err(PermissionError::WriteNotPermitted {
+                path: path.to_path_buf(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Requires `path` to be the layer file of `scope`, and `scope` to be writable.
+    pub fn check_scope(&self, scope: &str, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let Some(rule) = self.rules.iter().find(|r| r.scope == scope) else {
+            return Err(scope_not_permitted(scope, path));
+        };
+        if rule.file_name != file_name_of(path) {
+            return Err(scope_not_permitted(scope, path));
+        }
+        if !rule.writable {
+            return Err(PermissionError::WriteNotPermitted {
+                path: path.to_path_buf(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// [`write_layer_atomic`] after [`check`](Self::check).
+    pub fn write_layer_atomic(
+        &self,
+        path: impl AsRef<Path>,
+        schema: &LayerSchema,
+        chunks: &mut [ChunkInput],
+        layer_metadata_json: Option<&[u8]>,
+    ) -> Result<Vec<u32>, Error> {
+        self.check(path.as_ref())?;
+        write_layer_atomic(path, schema, chunks, layer_metadata_json)
+    }
+
+    /// [`append_layer_atomic`] after [`check`](Self::check).
+    pub fn append_layer_atomic(
+        &self,
+        path: impl AsRef<Path>,
+        new_chunks: &mut [ChunkInput],
+        layer_metadata_json: Option<&[u8]>,
+    ) -> Result<Vec<u32>, Error> {
+        self.check(path.as_ref())?;
+        append_layer_atomic(path, new_chunks, layer_metadata_json)
+    }
+}
+
+fn file_name_of(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+}
+
+fn scope_not_permitted(scope: &str, path: &Path) -> Error {
+    PermissionError::ScopeNotPermitted {
+        scope: scope.to_string(),
+        path: path.to_path_buf(),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_policy_matches_the_layer_conventions() {
+        let policy = WritePolicy::standard();
+        assert!(policy.check("dir/AGENTS.local.db").is_ok());
+        assert!(policy.check("AGENTS.delta.db").is_ok());
+        assert!(policy.check("AGENTS.user.db").is_err());
+        assert!(policy.check("AGENTS.db").is_err());
+        assert!(policy.check("other.db").is_ok());
+
+        let policy = policy.allow_user();
+        assert!(policy.check("AGENTS.user.db").is_ok());
+        assert!(policy.check("AGENTS.db").is_err());
+        assert!(policy.allow_base().check("AGENTS.db").is_ok());
+
+        assert!(WritePolicy::read_only().check("AGENTS.local.db").is_err());
+    }
+
+    #[test]
+    fn scopes_can_be_renamed_and_added() {
+        let policy = WritePolicy::standard()
+            .with_scope("local", "mine.db", true)
+            .with_scope("team", "TEAM.db", false);
+        assert_eq!(policy.file_name("local"), Some("mine.db"));
+        assert_eq!(policy.writable_file_name("team"), None);
+        assert_eq!(policy.scope_of("x/TEAM.db"), Some("team"));
+        assert!(policy.check_scope("local", "mine.db").is_ok());
+        assert!(matches!(
+            policy.check_scope("local", "AGENTS.local.db"),
+            Err(Error::Permission(PermissionError::ScopeNotPermitted { .. }))
+        ));
+        assert!(matches!(
+            policy.check_scope("team", "TEAM.db"),
+            Err(Error::Permission(PermissionError::WriteNotPermitted { .. }))
+        ));
+        assert!(policy.check_scope("nope", "mine.db").is_err());
+        assert_eq!(
+            policy.writable_scopes().collect::<Vec<_>>(),
+            vec!["local", "delta"]
+        );
+    }
+}
@@ -1,11 +1,17 @@
 use agentsdb_core::error::FormatError;
 use memmap2::Mmap;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
 
 const MAGIC_AGDB: u32 = 0x4244_4741; // 'A' 'G' 'D' 'B'
 
+/// Source of [`LayerFile::generation`] numbers.
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SectionKind {
     StringDictionary,
@@ -13,6 +19,8 @@ pub enum SectionKind {
     EmbeddingMatrix,
     Relationships,
     LayerMetadata,
+    ChunkIds,
+    LayerStats,
     Unknown(u32),
 }
 
@@ -24,6 +32,8 @@ impl SectionKind {
             3 => Self::EmbeddingMatrix,
             4 => Self::Relationships,
             5 => Self::LayerMetadata,
+            6 => Self::ChunkIds,
+            7 => Self::LayerStats,
             other => Self::Unknown(other),
         }
     }
@@ -35,6 +45,8 @@ impl SectionKind {
             Self::EmbeddingMatrix => "SECTION_EMBEDDING_MATRIX",
             Self::Relationships => "SECTION_RELATIONSHIPS",
             Self::LayerMetadata => "SECTION_LAYER_METADATA",
+            Self::ChunkIds => "SECTION_CHUNK_IDS",
+            Self::LayerStats => "SECTION_LAYER_STATS",
             Self::Unknown(_) => "SECTION_UNKNOWN",
         }
     }
@@ -78,6 +90,23 @@ struct ChunkTableHeaderV1 {
     records_offset: u64,
 }
 
+/// Sorted, distinct ids of the chunk table, so membership checks need not scan the records.
+#[derive(Debug, Clone, Copy)]
+struct ChunkIdsHeaderV1 {
+    id_count: u64,
+    ids_offset: u64,
+}
+
+/// Fixed part of `SECTION_LAYER_STATS`; `kind_count` then `author_count` count entries follow.
+#[derive(Debug, Clone, Copy)]
+struct LayerStatsHeaderV1 {
+    section: SectionEntry,
+    kind_count: u64,
+    author_count: u64,
+}
+
+const LAYER_STATS_HEADER_LEN: u64 = 104;
+
 #[derive(Debug, Clone, Copy)]
 struct ChunkRecord {
     id: u32,
@@ -119,6 +148,65 @@ impl EmbeddingElementType {
     }
 }
 
+/// Rows per block of [`EmbeddingLayout::Blocked`] matrices.
+pub const EMBEDDING_BLOCK_ROWS: u64 = 16;
+
+/// How the embedding matrix orders its values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingLayout {
+    /// Each row's `dim` values are contiguous.
+    RowMajor,
+    /// Rows are grouped in blocks of [`EMBEDDING_BLOCK_ROWS`]; within a block the values of one
+    /// dimension are contiguous, so a full scan reads every cache line once for 16 rows.
+    Blocked,
+}
+
+impl EmbeddingLayout {
+    fn from_u32(v: u32) -> Result<Self, FormatError> {
+        match v {
+            0 => Ok(Self::RowMajor),
+            1 => Ok(Self::Blocked),
+            _ => Err(FormatError::InvalidValue {
+                field: "EmbeddingMatrixHeaderV1.layout",
+                reason: "unknown embedding layout",
+            }),
+        }
+    }
+
+    pub(crate) const fn to_u32(self) -> u32 {
+        match self {
+            Self::RowMajor => 0,
+            Self::Blocked => 1,
+        }
+    }
+
+    /// `row-major` or `blocked`, as accepted by [`EmbeddingLayout::from_name`].
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::RowMajor => "row-major",
+            Self::Blocked => "blocked",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "row-major" => Some(Self::RowMajor),
+            "blocked" => Some(Self::Blocked),
+            _ => None,
+        }
+    }
+
+    /// Rows stored for `row_count` rows: blocked matrices pad the last block with zeros.
+    pub(crate) const fn stored_rows(self, row_count: u64) -> u64 {
+        match self {
+            Self::RowMajor => row_count,
+            Self::Blocked => row_count
+                .div_ceil(EMBEDDING_BLOCK_ROWS)
+                .saturating_mul(EMBEDDING_BLOCK_ROWS),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct EmbeddingMatrixHeaderV1 {
     pub row_count: u64,
@@ -127,7 +215,7 @@ pub struct EmbeddingMatrixHeaderV1 {
     pub data_offset: u64,
     pub data_length: u64,
     pub quant_scale: f32,
-    pub reserved0: f32,
+    pub layout: EmbeddingLayout,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -163,10 +251,41 @@ struct LayerMetadataHeaderV1 {
     blob_length: u64,
 }
 
-#[derive(Debug)]
+/// What a layer file looked like on disk. Writers replace layers by renaming a new file over
+/// the old one, so a changed inode (or size, or mtime) means another version of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+    pub inode: u64,
+}
+
+impl FileStamp {
+    /// The stamp of the file at `path` now.
+    pub fn of(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::from_metadata(&std::fs::metadata(path)?))
+    }
+
+    fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+        Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            inode,
+        }
+    }
+}
+
+/// An open, validated layer file. Cloning shares the mapping, so it is cheap.
+#[derive(Debug, Clone)]
 pub struct LayerFile {
     path: PathBuf,
-    mmap: Mmap,
+    mmap: Arc<Mmap>,
+    generation: u64,
+    stamp: FileStamp,
     pub header: FileHeaderV1,
     pub sections: Vec<SectionEntry>,
     pub string_dictionary: StringDictionaryHeaderV1,
@@ -176,6 +295,8 @@ pub struct LayerFile {
     pub relationship_count: Option<u64>,
     relationships_records_offset: Option<u64>,
     layer_metadata: Option<LayerMetadataHeaderV1>,
+    chunk_ids: Option<ChunkIdsHeaderV1>,
+    stats: Option<LayerStatsHeaderV1>,
 }
 
 impl LayerFile {
@@ -183,8 +304,12 @@ impl LayerFile {
         Self::open_with_options(path, false)
     }
 
-    /// Open a layer file without validating chunk ID uniqueness.
-    /// This is intended for recovery/repair tools like `agentsdb compact`.
+    /// Open a layer that may hold several versions of a chunk id.
+    ///
+    /// Editing a chunk appends a new record under its id instead of rewriting the layer, so
+    /// [`LayerFile::open`] rejects any layer that has been edited. The last record of each id is
+    /// the version a search sees; [`LayerFile::latest_chunks`] lists just those. Recovery tools
+    /// like `agentsdb compact` open layers this way too.
     pub fn open_lenient(path: impl AsRef<Path>) -> Result<Self, agentsdb_core::error::Error> {
         Self::open_with_options(path, true)
     }
@@ -228,6 +353,8 @@ impl LayerFile {
         let embed_section = required_section(&sections, SectionKind::EmbeddingMatrix)?;
         let rel_section = optional_section(&sections, SectionKind::Relationships)?;
         let metadata_section = optional_section(&sections, SectionKind::LayerMetadata)?;
+        let chunk_ids_section = optional_section(&sections, SectionKind::ChunkIds)?;
+        let stats_section = optional_section(&sections, SectionKind::LayerStats)?;
 
         let string_dictionary = parse_string_dictionary_header(bytes, string_section)?;
         validate_string_dictionary(bytes, string_section, &string_dictionary)?;
@@ -258,6 +385,25 @@ impl LayerFile {
             None
         };
 
+        let chunk_ids = if let Some(section) = chunk_ids_section {
+            let hdr = parse_chunk_ids_header(bytes, section)?;
+            validate_chunk_ids(bytes, section, &hdr, chunk_count)?;
+            Some(hdr)
+        } else {
+            None
+        };
+
+        let stats = if let Some(section) = stats_section {
+            Some(parse_layer_stats_header(
+                bytes,
+                section,
+                chunk_count,
+                &string_dictionary,
+            )?)
+        } else {
+            None
+        };
+
         validate_chunk_records(
             bytes,
             chunk_section,
@@ -265,12 +411,15 @@ impl LayerFile {
             &string_dictionary,
             &embedding_matrix,
             relationship_count,
+            chunk_ids.as_ref(),
             allow_duplicate_ids,
         )?;
 
         Ok(Self {
             path,
-            mmap,
+            mmap: Arc::new(mmap),
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+            stamp: FileStamp::from_metadata(&metadata),
             header,
             sections,
             string_dictionary,
@@ -280,6 +429,8 @@ impl LayerFile {
             relationship_count,
             relationships_records_offset,
             layer_metadata,
+            chunk_ids,
+            stats,
         })
     }
 
@@ -287,8 +438,22 @@ impl LayerFile {
         &self.path
     }
 
+    /// Number of this opening of the file, unique within the process. Clones share it, and every
+    /// open gets a new one, so a pooled handle keeps its generation until the file changes.
+    /// Caches of data derived from a layer can key on it.
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The [`FileStamp`] of the file this handle maps, taken from the open file rather than the
+    /// path, so it stays true to the mapping if the path is replaced later. Unlike
+    /// [`generation`](Self::generation) it is the same in every process.
+    pub const fn stamp(&self) -> FileStamp {
+        self.stamp
+    }
+
     pub fn file_bytes(&self) -> &[u8] {
-        self.mmap.as_ref()
+        &self.mmap[..]
     }
 
     pub fn embedding_dim(&self) -> usize {
@@ -315,6 +480,69 @@ impl LayerFile {
         })?))
     }
 
+    /// The statistics stored in `SECTION_LAYER_STATS`, if the file has that section. See
+    /// [`crate::ChunkStats::of`] for statistics of any file.
+    pub fn stored_stats(&self) -> Result<Option<crate::ChunkStats>, agentsdb_core::error::Error> {
+        let Some(hdr) = &self.stats else {
+            return Ok(None);
+        };
+        let bytes = self.file_bytes();
+        let base = hdr.section.offset;
+        let mut confidence_histogram = [0u64; crate::CONFIDENCE_BUCKET_BOUNDS.len()];
+        for (i, slot) in (0u64..).zip(confidence_histogram.iter_mut()) {
+            *slot = read_u64(bytes, base + 32 + i * 8)?;
+        }
+        let mut stats = crate::ChunkStats {
+            chunk_count: read_u64(bytes, base + 8)?,
+            by_kind: std::collections::BTreeMap::new(),
+            by_author: std::collections::BTreeMap::new(),
+            confidence_min: read_f32(bytes, base + 16)?,
+            confidence_max: read_f32(bytes, base + 20)?,
+            confidence_sum: f64::from_le_bytes(read_exact::<8>(bytes, base + 24)?),
+            confidence_histogram,
+            created_at_min: read_u64(bytes, base + 72)?,
+            created_at_max: read_u64(bytes, base + 80)?,
+        };
+        for i in 0..hdr.kind_count + hdr.author_count {
+            let off = base + LAYER_STATS_HEADER_LEN + i * 16;
+            let name = get_string(
+                bytes,
+                &self.string_dictionary,
+                u64::from(read_u32(bytes, off)?),
+            )?;
+            let count = read_u64(bytes, off + 8)?;
+            let counts = if i < hdr.kind_count {
+                &mut stats.by_kind
+            } else {
+                &mut stats.by_author
+            };
+            counts.insert(name.to_string(), count);
+        }
+        Ok(Some(stats))
+    }
+
+    /// Whether the file has `SECTION_CHUNK_IDS`, making [`LayerFile::contains_chunk_id`] a
+    /// binary search rather than a scan.
+    pub const fn has_chunk_ids_section(&self) -> bool {
+        self.chunk_ids.is_some()
+    }
+
+    /// Whether any chunk record has id `id`: a binary search of `SECTION_CHUNK_IDS`, or a scan
+    /// of the chunk table for files written before that section existed.
+    pub fn contains_chunk_id(&self, id: u32) -> Result<bool, agentsdb_core::error::Error> {
+        match &self.chunk_ids {
+            Some(hdr) => Ok(find_chunk_id(self.file_bytes(), hdr, id)?),
+            None => {
+                for chunk in self.chunks() {
+                    if chunk?.id == id {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
     pub fn chunks(&self) -> ChunkIter<'_> {
         ChunkIter {
             file: self,
@@ -322,6 +550,16 @@ impl LayerFile {
         }
     }
 
+    /// The last record of each chunk id, in id order (see [`LayerFile::open_lenient`]).
+    pub fn latest_chunks(&self) -> Result<Vec<ChunkView<'_>>, agentsdb_core::error::Error> {
+        let mut latest = BTreeMap::new();
+        for chunk in self.chunks() {
+            let chunk = chunk?;
+            latest.insert(chunk.id, chunk);
+        }
+        Ok(latest.into_values().collect())
+    }
+
     pub fn read_embedding_row_f32(
         &self,
         embedding_row: u32,
@@ -343,38 +581,35 @@ impl LayerFile {
         }
 
         let bytes = self.file_bytes();
-        let dim = self.embedding_matrix.dim as u64;
+        let matrix = &self.embedding_matrix;
+        let dim = matrix.dim as u64;
         let idx0 = (embedding_row as u64) - 1;
-        let elem_size = self.embedding_matrix.element_type.size_bytes();
-        let row_bytes = dim
-            .checked_mul(elem_size)
-            .ok_or(FormatError::InvalidRange {
-                field: "embedding row size",
-            })?;
-        let start = self
-            .embedding_matrix
-            .data_offset
-            .checked_add(
-                idx0.checked_mul(row_bytes)
-                    .ok_or(FormatError::InvalidRange {
-                        field: "embedding row offset",
-                    })?,
-            )
-            .ok_or(FormatError::InvalidRange {
-                field: "embedding row offset",
-            })?;
+        let elem_size = matrix.element_type.size_bytes();
+        // Validation checked that the stored rows fit the file, so these offsets cannot overflow.
+        let (first, stride) = match matrix.layout {
+            EmbeddingLayout::RowMajor => (idx0 * dim, 1),
+            EmbeddingLayout::Blocked => {
+                let block = idx0 / EMBEDDING_BLOCK_ROWS;
+                let lane = idx0 % EMBEDDING_BLOCK_ROWS;
+                (
+                    block * EMBEDDING_BLOCK_ROWS * dim + lane,
+                    EMBEDDING_BLOCK_ROWS,
+                )
+            }
+        };
+        let offset = |i: usize| matrix.data_offset + (first + (i as u64) * stride) * elem_size;
 
-        match self.embedding_matrix.element_type {
+        match matrix.element_type {
             EmbeddingElementType::F32 => {
                 for (i, slot) in out.iter_mut().enumerate() {
-                    *slot = read_f32(bytes, start + (i as u64) * 4)?;
+                    *slot = read_f32(bytes, offset(i))?;
                 }
             }
             EmbeddingElementType::I8 => {
-                let scale = self.embedding_matrix.quant_scale;
-                let slice = slice_range(bytes, start, start + row_bytes)?;
-                for (i, b) in slice.iter().enumerate() {
-                    out[i] = (*b as i8) as f32 * scale;
+                let scale = matrix.quant_scale;
+                for (i, slot) in out.iter_mut().enumerate() {
+                    let [b] = read_exact::<1>(bytes, offset(i))?;
+                    *slot = (b as i8) as f32 * scale;
                 }
             }
         }
@@ -382,6 +617,74 @@ impl LayerFile {
         Ok(())
     }
 
+    /// Dot product with `query` and L2 norm of every embedding row, in row order. Reads the
+    /// matrix front to back; with [`EmbeddingLayout::Blocked`] each pass scores a whole block.
+    pub fn dot_and_norm_rows(
+        &self,
+        query: &[f32],
+    ) -> Result<Vec<(f32, f32)>, agentsdb_core::error::Error> {
+        const LANES: usize = EMBEDDING_BLOCK_ROWS as usize;
+        if query.len() != self.embedding_dim() {
+            return Err(FormatError::InvalidValue {
+                field: "embedding",
+                reason: "query length must equal embedding dim",
+            }
+            .into());
+        }
+        let matrix = &self.embedding_matrix;
+        let data = slice_range(
+            self.file_bytes(),
+            matrix.data_offset,
+            matrix.data_offset + matrix.data_length,
+        )?;
+        let rows = usize::try_from(matrix.row_count).map_err(|_| FormatError::InvalidRange {
+            field: "EmbeddingMatrixHeaderV1.row_count",
+        })?;
+        let elem_size = matrix.element_type.size_bytes() as usize;
+        let decode = |b: &[u8]| match matrix.element_type {
+            EmbeddingElementType::F32 => f32::from_le_bytes(b.try_into().unwrap_or([0; 4])),
+            EmbeddingElementType::I8 => {
+                f32::from(i8::from_le_bytes(b.try_into().unwrap_or([0]))) * matrix.quant_scale
+            }
+        };
+
+        let mut out = Vec::with_capacity(rows);
+        match matrix.layout {
+            EmbeddingLayout::RowMajor => {
+                for row in data.chunks_exact(query.len() * elem_size) {
+                    let (mut dot, mut sum) = (0.0f32, 0.0f32);
+                    for (q, b) in query.iter().zip(row.chunks_exact(elem_size)) {
+                        let v = decode(b);
+                        dot += q * v;
+                        sum += v * v;
+                    }
+                    out.push((dot, sum.sqrt()));
+                }
+            }
+            EmbeddingLayout::Blocked => {
+                for block in data.chunks_exact(LANES * query.len() * elem_size) {
+                    let mut dot = [0.0f32; LANES];
+                    let mut sum = [0.0f32; LANES];
+                    for (q, values) in query.iter().zip(block.chunks_exact(LANES * elem_size)) {
+                        for ((d, s), b) in dot
+                            .iter_mut()
+                            .zip(sum.iter_mut())
+                            .zip(values.chunks_exact(elem_size))
+                        {
+                            let v = decode(b);
+                            *d += q * v;
+                            *s += v * v;
+                        }
+                    }
+                    out.extend(dot.into_iter().zip(sum).map(|(d, s)| (d, s.sqrt())));
+                }
+                // Drop the zero rows padding the last block.
+                out.truncate(rows);
+            }
+        }
+        Ok(out)
+    }
+
     pub fn sources_for(
         &self,
         rel_start: u64,
@@ -475,7 +778,11 @@ impl<'a> Iterator for ChunkIter<'a> {
 }
 
 impl LayerFile {
-    fn chunk_at<'a>(&'a self, index: u64) -> Result<ChunkView<'a>, agentsdb_core::error::Error> {
+    /// The chunk record at `index` of the chunk table (0-based, in append order).
+    pub fn chunk_at<'a>(
+        &'a self,
+        index: u64,
+    ) -> Result<ChunkView<'a>, agentsdb_core::error::Error> {
         const RECORD_SIZE: u64 = 52;
         if index >= self.chunk_count {
             return Err(FormatError::InvalidRange {
@@ -560,7 +867,7 @@ fn parse_section_table(
     }
 
     let mut sections = Vec::with_capacity(count_usize);
-    let mut required_seen = (false, false, false, false, false); // string, chunk, embed, rel, metadata
+    let mut required_seen = (false, false, false, false, false, false, false); // string, chunk, embed, rel, metadata, ids, stats
     for i in 0..count {
         let off = table_offset + i * ENTRY_SIZE;
         let kind_u32 = read_u32(bytes, off)?;
@@ -614,6 +921,18 @@ fn parse_section_table(
                 }
                 required_seen.4 = true;
             }
+            SectionKind::ChunkIds => {
+                if required_seen.5 {
+                    return Err(FormatError::DuplicateSection("chunk_ids"));
+                }
+                required_seen.5 = true;
+            }
+            SectionKind::LayerStats => {
+                if required_seen.6 {
+                    return Err(FormatError::DuplicateSection("layer_stats"));
+                }
+                required_seen.6 = true;
+            }
             SectionKind::Unknown(_) => {}
         }
 
@@ -651,6 +970,8 @@ fn required_section(
             SectionKind::EmbeddingMatrix => FormatError::MissingSection("embedding_matrix"),
             SectionKind::Relationships => FormatError::MissingSection("relationships"),
             SectionKind::LayerMetadata => FormatError::MissingSection("layer_metadata"),
+            SectionKind::ChunkIds => FormatError::MissingSection("chunk_ids"),
+            SectionKind::LayerStats => FormatError::MissingSection("layer_stats"),
             SectionKind::Unknown(_) => FormatError::MissingSection("unknown"),
         })
 }
@@ -731,6 +1052,141 @@ fn validate_layer_metadata(
     Ok(())
 }
 
+fn parse_chunk_ids_header(
+    bytes: &[u8],
+    section: SectionEntry,
+) -> Result<ChunkIdsHeaderV1, FormatError> {
+    let base = section.offset;
+    Ok(ChunkIdsHeaderV1 {
+        id_count: read_u64(bytes, base)?,
+        ids_offset: read_u64(bytes, base + 8)?,
+    })
+}
+
+fn validate_chunk_ids(
+    bytes: &[u8],
+    section: SectionEntry,
+    hdr: &ChunkIdsHeaderV1,
+    chunk_count: u64,
+) -> Result<(), FormatError> {
+    let header_len = 16u64;
+    if section.length < header_len {
+        return Err(FormatError::InvalidRange {
+            field: "SECTION_CHUNK_IDS length",
+        });
+    }
+    if hdr.ids_offset != section.offset + header_len {
+        return Err(FormatError::InvalidValue {
+            field: "ChunkIdsHeaderV1.ids_offset",
+            reason: "must equal section.offset + header_len",
+        });
+    }
+    if hdr.id_count > chunk_count {
+        return Err(FormatError::InvalidValue {
+            field: "ChunkIdsHeaderV1.id_count",
+            reason: "must not exceed chunk_count",
+        });
+    }
+    if hdr.id_count.checked_mul(4) != Some(section.length - header_len) {
+        return Err(FormatError::InvalidValue {
+            field: "ChunkIdsHeaderV1.id_count",
+            reason: "must equal (section.length - header_len) / 4",
+        });
+    }
+    let mut prev = 0u32;
+    for i in 0..hdr.id_count {
+        let id = read_u32(bytes, hdr.ids_offset + i * 4)?;
+        if id <= prev {
+            return Err(FormatError::InvalidValue {
+                field: "ChunkIdsHeaderV1.ids",
+                reason: "must be non-zero and strictly ascending",
+            });
+        }
+        prev = id;
+    }
+    Ok(())
+}
+
+/// Binary search for `id` in a validated chunk id section.
+fn find_chunk_id(bytes: &[u8], hdr: &ChunkIdsHeaderV1, id: u32) -> Result<bool, FormatError> {
+    let (mut lo, mut hi) = (0u64, hdr.id_count);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let value = read_u32(bytes, hdr.ids_offset + mid * 4)?;
+        match value.cmp(&id) {
+            std::cmp::Ordering::Equal => return Ok(true),
+            std::cmp::Ordering::Less => lo = mid + 1,
+            std::cmp::Ordering::Greater => hi = mid,
+        }
+    }
+    Ok(false)
+}
+
+fn parse_layer_stats_header(
+    bytes: &[u8],
+    section: SectionEntry,
+    chunk_count: u64,
+    dict: &StringDictionaryHeaderV1,
+) -> Result<LayerStatsHeaderV1, FormatError> {
+    let base = section.offset;
+    if section.length < LAYER_STATS_HEADER_LEN {
+        return Err(FormatError::InvalidRange {
+            field: "SECTION_LAYER_STATS length",
+        });
+    }
+    if read_u32(bytes, base)? != 1 {
+        return Err(FormatError::InvalidValue {
+            field: "LayerStatsHeaderV1.version",
+            reason: "unsupported stats version",
+        });
+    }
+    if read_u32(bytes, base + 4)? != 0 {
+        return Err(FormatError::NonZeroReserved {
+            field: "LayerStatsHeaderV1.reserved",
+        });
+    }
+    if read_u64(bytes, base + 8)? != chunk_count {
+        return Err(FormatError::InvalidValue {
+            field: "LayerStatsHeaderV1.chunk_count",
+            reason: "must equal the chunk table's chunk_count",
+        });
+    }
+    let kind_count = read_u64(bytes, base + 88)?;
+    let author_count = read_u64(bytes, base + 96)?;
+    let entries_len = kind_count
+        .checked_add(author_count)
+        .and_then(|n| n.checked_mul(16))
+        .ok_or(FormatError::InvalidRange {
+            field: "LayerStatsHeaderV1.kind_count/author_count",
+        })?;
+    if section.length - LAYER_STATS_HEADER_LEN != entries_len {
+        return Err(FormatError::InvalidValue {
+            field: "LayerStatsHeaderV1.kind_count/author_count",
+            reason: "must match section.length",
+        });
+    }
+    for i in 0..kind_count + author_count {
+        let off = base + LAYER_STATS_HEADER_LEN + i * 16;
+        let str_id = u64::from(read_u32(bytes, off)?);
+        if str_id == 0 || str_id > dict.string_count {
+            return Err(FormatError::InvalidStringId {
+                id: str_id,
+                count: dict.string_count,
+            });
+        }
+        if read_u32(bytes, off + 4)? != 0 {
+            return Err(FormatError::NonZeroReserved {
+                field: "StatsCountEntry.reserved",
+            });
+        }
+    }
+    Ok(LayerStatsHeaderV1 {
+        section,
+        kind_count,
+        author_count,
+    })
+}
+
 fn parse_string_dictionary_header(
     bytes: &[u8],
     section: SectionEntry,
@@ -890,6 +1346,7 @@ fn validate_chunk_records(
     dict: &StringDictionaryHeaderV1,
     embed: &EmbeddingMatrixHeaderV1,
     relationship_count: Option<u64>,
+    chunk_ids: Option<&ChunkIdsHeaderV1>,
     allow_duplicate_ids: bool,
 ) -> Result<(), FormatError> {
     const RECORD_SIZE: u64 = 52;
@@ -940,6 +1397,14 @@ fn validate_chunk_records(
                 return Err(FormatError::DuplicateChunkId(record.id));
             }
         }
+        if let Some(hdr) = chunk_ids {
+            if !find_chunk_id(bytes, hdr, record.id)? {
+                return Err(FormatError::InvalidValue {
+                    field: "ChunkIdsHeaderV1.ids",
+                    reason: "must list every chunk id",
+                });
+            }
+        }
 
         let kind_id = record.kind_str_id as u64;
         let content_id = record.content_str_id as u64;
@@ -1041,7 +1506,7 @@ fn parse_embedding_matrix_header(
         data_offset: read_u64(bytes, base + 16)?,
         data_length: read_u64(bytes, base + 24)?,
         quant_scale: read_f32(bytes, base + 32)?,
-        reserved0: read_f32(bytes, base + 36)?,
+        layout: EmbeddingLayout::from_u32(read_u32(bytes, base + 36)?)?,
     })
 }
 
@@ -1078,12 +1543,6 @@ fn validate_embedding_matrix(
         });
     }
 
-    if header.reserved0 != 0.0 {
-        return Err(FormatError::NonZeroReserved {
-            field: "EmbeddingMatrixHeaderV1.reserved0",
-        });
-    }
-
     match header.element_type {
         EmbeddingElementType::F32 => {
             if header.quant_scale != 1.0 {
@@ -1104,7 +1563,8 @@ fn validate_embedding_matrix(
     }
 
     let expected = header
-        .row_count
+        .layout
+        .stored_rows(header.row_count)
         .checked_mul(header.dim as u64)
         .and_then(|v| v.checked_mul(header.element_type.size_bytes()))
         .ok_or(FormatError::InvalidRange {
@@ -1113,7 +1573,7 @@ fn validate_embedding_matrix(
     if header.data_length != expected {
         return Err(FormatError::InvalidValue {
             field: "EmbeddingMatrixHeaderV1.data_length",
-            reason: "does not match row_count * dim * element_size",
+            reason: "does not match the stored rows * dim * element_size",
         });
     }
 
@@ -1411,6 +1871,33 @@ mod tests {
         assert_eq!(file.relationship_count, None);
     }
 
+    #[test]
+    fn contains_chunk_id_scans_files_without_id_section() {
+        let data = build_minimal_valid_file();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("AGENTS.db");
+        std::fs::write(&path, &data).unwrap();
+        let file = LayerFile::open(&path).unwrap();
+        assert!(file.chunk_ids.is_none());
+        assert!(file.contains_chunk_id(1).unwrap());
+        assert!(!file.contains_chunk_id(2).unwrap());
+    }
+
+    #[test]
+    fn chunk_stats_are_recomputed_without_stats_section() {
+        let data = build_minimal_valid_file();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("AGENTS.db");
+        std::fs::write(&path, &data).unwrap();
+        let file = LayerFile::open(&path).unwrap();
+        assert!(file.stored_stats().unwrap().is_none());
+        let stats = crate::ChunkStats::of(&file).unwrap();
+        assert_eq!(stats.chunk_count, 1);
+        assert_eq!(stats.by_kind["note"], 1);
+        assert_eq!(stats.by_author["human"], 1);
+        assert_eq!(stats.confidence_mean(), 1.0);
+    }
+
     #[test]
     fn rejects_bad_magic() {
         let mut data = build_minimal_valid_file();
@@ -0,0 +1,92 @@
+//! Counts and ranges over a layer's chunk records. The writer stores them in
+//! `SECTION_LAYER_STATS`, so readers such as `agentsdb inspect` and the web UI's layer metadata
+//! need not scan the chunk table for them.
+
+use crate::LayerFile;
+use agentsdb_core::error::Error;
+use std::collections::BTreeMap;
+
+/// Upper bounds of the confidence histogram buckets; the last bucket also holds 1.0.
+pub const CONFIDENCE_BUCKET_BOUNDS: [f32; 5] = [0.2, 0.4, 0.6, 0.8, 1.0];
+
+/// Statistics over every chunk record of a layer, tombstones and superseded versions included.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChunkStats {
+    pub chunk_count: u64,
+    pub by_kind: BTreeMap<String, u64>,
+    pub by_author: BTreeMap<String, u64>,
+    /// Smallest and largest confidence; 0.0 without chunks.
+    pub confidence_min: f32,
+    pub confidence_max: f32,
+    /// Sum of all confidences, see [`ChunkStats::confidence_mean`].
+    pub confidence_sum: f64,
+    /// Chunks per bucket of [`CONFIDENCE_BUCKET_BOUNDS`].
+    pub confidence_histogram: [u64; CONFIDENCE_BUCKET_BOUNDS.len()],
+    /// Range of `created_at_unix_ms`; 0 without chunks.
+    pub created_at_min: u64,
+    pub created_at_max: u64,
+}
+
+impl ChunkStats {
+    /// Adds one chunk record.
+    pub fn push(&mut self, kind: &str, author: &str, confidence: f32, created_at_unix_ms: u64) {
+        if self.chunk_count == 0 {
+            self.confidence_min = confidence;
+            self.confidence_max = confidence;
+            self.created_at_min = created_at_unix_ms;
+            self.created_at_max = created_at_unix_ms;
+        } else {
+            self.confidence_min = self.confidence_min.min(confidence);
+            self.confidence_max = self.confidence_max.max(confidence);
+            self.created_at_min = self.created_at_min.min(created_at_unix_ms);
+            self.created_at_max = self.created_at_max.max(created_at_unix_ms);
+        }
+        self.chunk_count += 1;
+        self.confidence_sum += f64::from(confidence);
+        let bucket = CONFIDENCE_BUCKET_BOUNDS
+            .iter()
+            .position(|le| confidence < *le)
+            .unwrap_or(CONFIDENCE_BUCKET_BOUNDS.len() - 1);
+        self.confidence_histogram[bucket] += 1;
+        count(&mut self.by_kind, kind);
+        count(&mut self.by_author, author);
+    }
+
+    /// Mean confidence; 0.0 without chunks.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn confidence_mean(&self) -> f32 {
+        if self.chunk_count == 0 {
+            return 0.0;
+        }
+        // Means of f32 values fit back into f32; precision loss on the count is harmless.
+        (self.confidence_sum / self.chunk_count as f64) as f32
+    }
+
+    /// The statistics stored in `file`, or recomputed from its chunk table when the file was
+    /// written without them.
+    pub fn of(file: &LayerFile) -> Result<Self, Error> {
+        if let Some(stats) = file.stored_stats()? {
+            return Ok(stats);
+        }
+        let mut stats = Self::default();
+        for chunk in file.chunks() {
+            let chunk = chunk?;
+            stats.push(
+                chunk.kind,
+                chunk.author,
+                chunk.confidence,
+                chunk.created_at_unix_ms,
+            );
+        }
+        Ok(stats)
+    }
+}
+
+fn count(counts: &mut BTreeMap<String, u64>, key: &str) {
+    match counts.get_mut(key) {
+        Some(n) => *n += 1,
+        None => {
+            counts.insert(key.to_string(), 1);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Fixtures shared by the unit tests of this crate.
+
+use crate::{ChunkInput, EmbeddingElementType, EmbeddingLayout, LayerSchema};
+
+/// A one-dimensional f32 schema, row-major; tests set any other field with struct update syntax.
+pub(crate) fn schema() -> LayerSchema {
+    LayerSchema {
+        dim: 1,
+        element_type: EmbeddingElementType::F32,
+        quant_scale: 1.0,
+        layout: EmbeddingLayout::RowMajor,
+    }
+}
+
+/// A human note with full confidence and embedding `[1]`, for layers with [`schema`].
+pub(crate) fn chunk(id: u32, content: &str) -> ChunkInput {
+    ChunkInput {
+        id,
+        kind: "note".to_string(),
+        content: content.to_string(),
+        author: "human".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: 0,
+        embedding: vec![1.0],
+        sources: Vec::new(),
+    }
+}
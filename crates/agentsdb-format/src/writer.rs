@@ -1,10 +1,10 @@
-use crate::{EmbeddingElementType, LayerFile};
-use agentsdb_core::error::{Error, FormatError, PermissionError};
+use crate::{ChunkStats, EmbeddingElementType, EmbeddingLayout, LayerFile, EMBEDDING_BLOCK_ROWS};
+use agentsdb_core::error::{Error, FormatError};
 use std::collections::{HashMap, HashSet};
 use std::fs::OpenOptions;
 use std::hash::{BuildHasher, Hasher, RandomState};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Generate a random non-zero u32 using std's RandomState (no external crate needed).
 fn random_chunk_id(used: &HashSet<u32>) -> u32 {
@@ -28,6 +28,8 @@ const SECTION_CHUNK_TABLE: u32 = 2;
 const SECTION_EMBEDDING_MATRIX: u32 = 3;
 const SECTION_RELATIONSHIPS: u32 = 4;
 const SECTION_LAYER_METADATA: u32 = 5;
+const SECTION_CHUNK_IDS: u32 = 6;
+const SECTION_LAYER_STATS: u32 = 7;
 
 const LAYER_METADATA_FORMAT_JSON: u32 = 1;
 
@@ -39,6 +41,8 @@ pub struct LayerSchema {
     pub dim: u32,
     pub element_type: EmbeddingElementType,
     pub quant_scale: f32,
+    /// Order of the embedding matrix values; appends keep the layout of the file.
+    pub layout: EmbeddingLayout,
 }
 
 #[derive(Debug, Clone)]
@@ -64,6 +68,7 @@ pub fn schema_of(file: &LayerFile) -> LayerSchema {
         dim: file.embedding_matrix.dim,
         element_type: file.embedding_matrix.element_type,
         quant_scale: file.embedding_matrix.quant_scale,
+        layout: file.embedding_matrix.layout,
     }
 }
 
@@ -73,6 +78,18 @@ pub fn write_layer_atomic(
     chunks: &mut [ChunkInput],
     layer_metadata_json: Option<&[u8]>,
 ) -> Result<Vec<u32>, Error> {
+    let (bytes, assigned) = prepare_write_layer(schema, chunks, layer_metadata_json)?;
+    atomic_write(path.as_ref(), &bytes)?;
+    Ok(assigned)
+}
+
+/// The bytes [`write_layer_atomic`] would write, and the ids it would assign, without touching
+/// the file system.
+pub fn prepare_write_layer(
+    schema: &LayerSchema,
+    chunks: &mut [ChunkInput],
+    layer_metadata_json: Option<&[u8]>,
+) -> Result<(Vec<u8>, Vec<u32>), Error> {
     // Auto-assign randomized IDs for chunks with id=0
     let mut used_ids: HashSet<u32> = chunks.iter().filter(|c| c.id != 0).map(|c| c.id).collect();
 
@@ -86,8 +103,7 @@ pub fn write_layer_atomic(
     }
 
     let bytes = encode_layer(schema, chunks, layer_metadata_json)?;
-    atomic_write(path.as_ref(), &bytes)?;
-    Ok(assigned)
+    Ok((bytes, assigned))
 }
 
 pub fn append_layer_atomic(
@@ -95,6 +111,19 @@ pub fn append_layer_atomic(
     new_chunks: &mut [ChunkInput],
     layer_metadata_json: Option<&[u8]>,
 ) -> Result<Vec<u32>, Error> {
+    let path = path.as_ref();
+    let (bytes, assigned) = prepare_append_layer(path, new_chunks, layer_metadata_json)?;
+    atomic_write(path, &bytes)?;
+    Ok(assigned)
+}
+
+/// The bytes [`append_layer_atomic`] would write to `path`, and the ids it would assign, without
+/// replacing the file.
+pub fn prepare_append_layer(
+    path: impl AsRef<Path>,
+    new_chunks: &mut [ChunkInput],
+    layer_metadata_json: Option<&[u8]>,
+) -> Result<(Vec<u8>, Vec<u32>), Error> {
     let path = path.as_ref();
     // Use lenient opening to allow appending to files with duplicate IDs from previous edits.
     // This is similar to the fix for the compact command.
@@ -128,59 +157,21 @@ pub fn append_layer_atomic(
     }
 
     let bytes = encode_layer(&schema, &all_chunks, metadata_to_write.as_deref())?;
-    atomic_write(path, &bytes)?;
-    Ok(assigned)
-}
-
-pub fn ensure_writable_layer_path(path: impl AsRef<Path>) -> Result<(), Error> {
-    ensure_writable_layer_path_inner(path.as_ref(), false, false)
-}
-
-pub fn ensure_writable_layer_path_allow_user(path: impl AsRef<Path>) -> Result<(), Error> {
-    ensure_writable_layer_path_inner(path.as_ref(), true, false)
-}
-
-pub fn ensure_writable_layer_path_allow_base(path: impl AsRef<Path>) -> Result<(), Error> {
-    // Escape hatch: allow writing to AGENTS.db when explicitly requested by the caller.
-    ensure_writable_layer_path_inner(path.as_ref(), true, true)
+    Ok((bytes, assigned))
 }
 
 pub fn read_all_chunks(file: &LayerFile) -> Result<Vec<ChunkInput>, Error> {
     decode_all_chunks(file)
 }
 
-fn ensure_writable_layer_path_inner(
-    path: &Path,
-    allow_user: bool,
-    allow_base: bool,
-) -> Result<(), Error> {
-    let name = path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or_default();
-    let forbidden = match (allow_user, allow_base) {
-        // Default: only local/delta allowed.
-        (false, _) => ["AGENTS.db", "AGENTS.user.db"].as_slice(),
-        // User allowed, but base still protected.
-        (true, false) => ["AGENTS.db"].as_slice(),
-        // Escape hatch: allow base + user.
-        (true, true) => [].as_slice(),
-    };
-    if forbidden.contains(&name) {
-        return Err(PermissionError::WriteNotPermitted {
-            path: path.to_path_buf(),
-        }
-        .into());
-    }
-    Ok(())
-}
-
 fn decode_all_chunks(file: &LayerFile) -> Result<Vec<ChunkInput>, Error> {
     let dim = file.embedding_dim();
-    let mut tmp = vec![0.0f32; dim];
+    // Sized on the first chunk: a layer without rows may declare any dim.
+    let mut tmp = Vec::new();
     let mut out = Vec::with_capacity(file.chunk_count as usize);
     for c in file.chunks() {
         let c = c?;
+        tmp.resize(dim, 0.0f32);
         file.read_embedding_row_f32(c.embedding_row, &mut tmp)?;
         let sources = file
             .sources_for(c.rel_start, c.rel_count)?
@@ -313,9 +304,19 @@ fn encode_layer(
         }
     }
 
+    // Distinct chunk ids, ascending, for `LayerFile::contains_chunk_id`.
+    let mut sorted_ids: Vec<u32> = chunks.iter().map(|c| c.id).collect();
+    sorted_ids.sort_unstable();
+    sorted_ids.dedup();
+
+    let mut stats = ChunkStats::default();
+    for c in chunks {
+        stats.push(&c.kind, &c.author, c.confidence, c.created_at_unix_ms);
+    }
+
     // Layout.
     let header_len = 40u64;
-    let mut section_count = 3u64;
+    let mut section_count = 5u64;
     if include_relationships {
         section_count += 1;
     }
@@ -332,13 +333,22 @@ fn encode_layer(
     let chunk_records_size = (chunks.len() as u64) * 52u64;
     let chunk_section_len = chunk_header_size + chunk_records_size;
 
+    let chunk_ids_header_size = 16u64;
+    let chunk_ids_section_len = chunk_ids_header_size + (sorted_ids.len() as u64) * 4u64;
+
+    let stats_header_size = 104u64;
+    let stats_entry_count = (stats.by_kind.len() + stats.by_author.len()) as u64;
+    let stats_section_len = stats_header_size + stats_entry_count * 16u64;
+
     let embed_header_size = 40u64;
     let elem_size = match schema.element_type {
         EmbeddingElementType::F32 => 4u64,
         EmbeddingElementType::I8 => 1u64,
     };
     let row_count = chunks.len() as u64;
-    let embed_data_len = row_count
+    let embed_data_len = schema
+        .layout
+        .stored_rows(row_count)
         .checked_mul(schema.dim as u64)
         .and_then(|v| v.checked_mul(elem_size))
         .ok_or(FormatError::InvalidRange {
@@ -356,14 +366,17 @@ fn encode_layer(
 
     let string_section_off = header_len + section_table_len;
     let chunk_section_off = string_section_off + string_section_len;
+    let chunk_ids_section_off = chunk_section_off + chunk_section_len;
+    let stats_section_off = chunk_ids_section_off + chunk_ids_section_len;
+    let after_stats = stats_section_off + stats_section_len;
     let layer_metadata_section_off = if include_layer_metadata {
-        Some(chunk_section_off + chunk_section_len)
+        Some(after_stats)
     } else {
         None
     };
     let after_meta = layer_metadata_section_off
         .map(|off| off + layer_metadata_section_len)
-        .unwrap_or(chunk_section_off + chunk_section_len);
+        .unwrap_or(after_stats);
     let rel_section_off = if include_relationships {
         Some(after_meta)
     } else {
@@ -400,6 +413,18 @@ fn encode_layer(
     put_u64(&mut buf, sec + 8, chunk_section_off);
     put_u64(&mut buf, sec + 16, chunk_section_len);
     sec += 24;
+    // chunk ids
+    put_u32(&mut buf, sec, SECTION_CHUNK_IDS);
+    put_u32(&mut buf, sec + 4, 0);
+    put_u64(&mut buf, sec + 8, chunk_ids_section_off);
+    put_u64(&mut buf, sec + 16, chunk_ids_section_len);
+    sec += 24;
+    // layer stats
+    put_u32(&mut buf, sec, SECTION_LAYER_STATS);
+    put_u32(&mut buf, sec + 4, 0);
+    put_u64(&mut buf, sec + 8, stats_section_off);
+    put_u64(&mut buf, sec + 16, stats_section_len);
+    sec += 24;
     if let Some(meta_off) = layer_metadata_section_off {
         put_u32(&mut buf, sec, SECTION_LAYER_METADATA);
         put_u32(&mut buf, sec + 4, 0);
@@ -497,6 +522,45 @@ fn encode_layer(
         put_u32(&mut buf, rec_off + 48, 0);
     }
 
+    // Chunk ids
+    let chunk_ids_off = chunk_ids_section_off + chunk_ids_header_size;
+    put_u64(
+        &mut buf,
+        chunk_ids_section_off as usize,
+        sorted_ids.len() as u64,
+    );
+    put_u64(&mut buf, chunk_ids_section_off as usize + 8, chunk_ids_off);
+    for (i, id) in sorted_ids.iter().enumerate() {
+        put_u32(&mut buf, chunk_ids_off as usize + i * 4, *id);
+    }
+
+    // Layer stats
+    let stats_off = stats_section_off as usize;
+    put_u32(&mut buf, stats_off, 1);
+    put_u32(&mut buf, stats_off + 4, 0);
+    put_u64(&mut buf, stats_off + 8, stats.chunk_count);
+    put_f32(&mut buf, stats_off + 16, stats.confidence_min);
+    put_f32(&mut buf, stats_off + 20, stats.confidence_max);
+    buf[stats_off + 24..stats_off + 32].copy_from_slice(&stats.confidence_sum.to_le_bytes());
+    for (i, count) in stats.confidence_histogram.iter().enumerate() {
+        put_u64(&mut buf, stats_off + 32 + i * 8, *count);
+    }
+    put_u64(&mut buf, stats_off + 72, stats.created_at_min);
+    put_u64(&mut buf, stats_off + 80, stats.created_at_max);
+    put_u64(&mut buf, stats_off + 88, stats.by_kind.len() as u64);
+    put_u64(&mut buf, stats_off + 96, stats.by_author.len() as u64);
+    let entries = stats.by_kind.iter().chain(&stats.by_author);
+    for (i, (name, count)) in entries.enumerate() {
+        let entry_off = stats_off + stats_header_size as usize + i * 16;
+        put_u32(
+            &mut buf,
+            entry_off,
+            *string_ids.get(name).expect("interned"),
+        );
+        put_u32(&mut buf, entry_off + 4, 0);
+        put_u64(&mut buf, entry_off + 8, *count);
+    }
+
     // Embedding matrix
     put_u64(&mut buf, embed_section_off as usize, row_count);
     put_u32(&mut buf, embed_section_off as usize + 8, schema.dim);
@@ -519,15 +583,31 @@ fn encode_layer(
             EmbeddingElementType::I8 => schema.quant_scale,
         },
     );
-    put_f32(&mut buf, embed_section_off as usize + 36, 0.0);
+    put_u32(
+        &mut buf,
+        embed_section_off as usize + 36,
+        schema.layout.to_u32(),
+    );
+
+    // Index of value `d` of row `r` (0-based) in the matrix; the buffer is zeroed, so the rows
+    // padding a blocked matrix need no writes.
+    let block_rows = EMBEDDING_BLOCK_ROWS as usize;
+    let value_index = |r: usize, d: usize| match schema.layout {
+        EmbeddingLayout::RowMajor => r * dim + d,
+        EmbeddingLayout::Blocked => {
+            (r / block_rows) * block_rows * dim + d * block_rows + r % block_rows
+        }
+    };
 
     match schema.element_type {
         EmbeddingElementType::F32 => {
-            let mut at = embed_data_off as usize;
-            for c in chunks {
-                for x in &c.embedding {
-                    put_f32(&mut buf, at, *x);
-                    at += 4;
+            for (r, c) in chunks.iter().enumerate() {
+                for (d, x) in c.embedding.iter().enumerate() {
+                    put_f32(
+                        &mut buf,
+                        embed_data_off as usize + value_index(r, d) * 4,
+                        *x,
+                    );
                 }
             }
         }
@@ -540,13 +620,11 @@ fn encode_layer(
                 }
                 .into());
             }
-            let mut at = embed_data_off as usize;
-            for c in chunks {
-                for x in &c.embedding {
+            for (r, c) in chunks.iter().enumerate() {
+                for (d, x) in c.embedding.iter().enumerate() {
                     let q = (*x / scale).round();
                     let clamped = q.clamp(-128.0, 127.0) as i32;
-                    buf[at] = (clamped as i8) as u8;
-                    at += 1;
+                    buf[embed_data_off as usize + value_index(r, d)] = (clamped as i8) as u8;
                 }
             }
         }
@@ -556,6 +634,17 @@ fn encode_layer(
 }
 
 fn atomic_write(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    let tmp_path = stage_layer_bytes(path, bytes)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Writes `bytes` to a new temp file next to `path` and syncs it, leaving `path` untouched.
+///
+/// # Returns
+/// The temp file's path, to rename over `path` (or remove) once the caller is ready
+pub fn stage_layer_bytes(path: impl AsRef<Path>, bytes: &[u8]) -> Result<PathBuf, Error> {
+    let path = path.as_ref();
     let dir = path.parent().unwrap_or_else(|| Path::new("."));
     let base = path
         .file_name()
@@ -576,10 +665,11 @@ fn atomic_write(path: &Path, bytes: &[u8]) -> Result<(), Error> {
             .open(&tmp_path)
         {
             Ok(mut f) => {
-                f.write_all(bytes)?;
-                f.sync_all()?;
-                std::fs::rename(&tmp_path, path)?;
-                return Ok(());
+                if let Err(e) = f.write_all(bytes).and_then(|()| f.sync_all()) {
+                    let _ = std::fs::remove_file(&tmp_path);
+                    return Err(e.into());
+                }
+                return Ok(tmp_path);
             }
             Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
                 i = i.saturating_add(1);
@@ -606,6 +696,7 @@ fn put_f32(buf: &mut [u8], off: usize, v: f32) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{chunk, schema};
     use crate::LayerFile;
 
     #[test]
@@ -617,6 +708,7 @@ mod tests {
             dim: 2,
             element_type: EmbeddingElementType::F32,
             quant_scale: 1.0,
+            layout: EmbeddingLayout::RowMajor,
         };
         let mut chunks = vec![ChunkInput {
             id: 1,
@@ -636,6 +728,122 @@ mod tests {
         assert_eq!(opened.relationship_count, Some(1));
     }
 
+    #[test]
+    fn chunk_id_section_lists_every_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("AGENTS.delta.db");
+
+        let mut chunks = [chunk(7, "seven"), chunk(3, "three"), chunk(9, "nine")];
+        write_layer_atomic(&path, &schema(), &mut chunks, None).unwrap();
+        let assigned = append_layer_atomic(&path, &mut [chunk(0, "new")], None).unwrap();
+
+        let opened = LayerFile::open(&path).unwrap();
+        assert!(opened
+            .sections
+            .iter()
+            .any(|s| s.kind == crate::SectionKind::ChunkIds));
+        for id in [3, 7, 9].into_iter().chain(assigned) {
+            assert!(opened.contains_chunk_id(id).unwrap());
+        }
+        assert!(!opened.contains_chunk_id(8).unwrap());
+    }
+
+    #[test]
+    fn latest_chunks_keeps_the_last_record_of_each_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("AGENTS.delta.db");
+
+        write_layer_atomic(&path, &schema(), &mut [chunk(5, "a"), chunk(2, "b")], None).unwrap();
+        append_layer_atomic(&path, &mut [chunk(5, "a, edited")], None).unwrap();
+
+        assert!(LayerFile::open(&path).is_err());
+        let opened = LayerFile::open_lenient(&path).unwrap();
+        let latest: Vec<_> = opened
+            .latest_chunks()
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.id, c.content))
+            .collect();
+        assert_eq!(latest, vec![(2, "b"), (5, "a, edited")]);
+    }
+
+    #[test]
+    fn layer_stats_section_matches_the_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("AGENTS.delta.db");
+
+        let mut chunks = vec![
+            ChunkInput {
+                confidence: 0.1,
+                created_at_unix_ms: 300,
+                ..chunk(1, "one")
+            },
+            ChunkInput {
+                author: "mcp".to_string(),
+                confidence: 0.5,
+                created_at_unix_ms: 100,
+                ..chunk(2, "two")
+            },
+            ChunkInput {
+                kind: "tombstone".to_string(),
+                created_at_unix_ms: 200,
+                ..chunk(3, "three")
+            },
+        ];
+        write_layer_atomic(&path, &schema(), &mut chunks, None).unwrap();
+
+        let opened = LayerFile::open(&path).unwrap();
+        let stats = opened.stored_stats().unwrap().expect("stats section");
+        assert_eq!(stats.chunk_count, 3);
+        assert_eq!(stats.by_kind["note"], 2);
+        assert_eq!(stats.by_kind["tombstone"], 1);
+        assert_eq!(stats.by_author["human"], 2);
+        assert_eq!(stats.by_author["mcp"], 1);
+        assert_eq!((stats.confidence_min, stats.confidence_max), (0.1, 1.0));
+        assert_eq!(stats.confidence_histogram, [1, 0, 1, 0, 1]);
+        assert_eq!((stats.created_at_min, stats.created_at_max), (100, 300));
+
+        let mut scanned = ChunkStats::default();
+        for c in &chunks {
+            scanned.push(&c.kind, &c.author, c.confidence, c.created_at_unix_ms);
+        }
+        assert_eq!(stats, scanned);
+        assert_eq!(ChunkStats::of(&opened).unwrap(), scanned);
+    }
+
+    #[test]
+    fn blocked_layout_pads_blocks_and_survives_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("AGENTS.delta.db");
+
+        let schema = LayerSchema {
+            dim: 3,
+            element_type: EmbeddingElementType::I8,
+            layout: EmbeddingLayout::Blocked,
+            ..schema()
+        };
+        let note = |id: u8| ChunkInput {
+            embedding: vec![f32::from(id), -f32::from(id), 1.0],
+            ..chunk(u32::from(id), &format!("note {id}"))
+        };
+        let mut chunks: Vec<ChunkInput> = (1..=17).map(note).collect();
+        write_layer_atomic(&path, &schema, &mut chunks, None).unwrap();
+        append_layer_atomic(&path, &mut [note(18)], None).unwrap();
+
+        let opened = LayerFile::open(&path).unwrap();
+        assert_eq!(opened.embedding_matrix.layout, EmbeddingLayout::Blocked);
+        assert_eq!(opened.embedding_matrix.row_count, 18);
+        // Two blocks of 16 rows, 3 one-byte values each.
+        assert_eq!(opened.embedding_matrix.data_length, 2 * 16 * 3);
+        let mut row = vec![0.0; 3];
+        opened.read_embedding_row_f32(17, &mut row).unwrap();
+        assert_eq!(row, vec![17.0, -17.0, 1.0]);
+
+        let scanned = opened.dot_and_norm_rows(&[1.0, 0.0, 0.0]).unwrap();
+        assert_eq!(scanned.len(), 18);
+        assert_eq!(scanned[17].0, 18.0);
+    }
+
     #[test]
     fn layer_metadata_roundtrips_and_is_preserved_on_append() {
         let dir = tempfile::tempdir().unwrap();
@@ -645,6 +853,7 @@ mod tests {
             dim: 2,
             element_type: EmbeddingElementType::F32,
             quant_scale: 1.0,
+            layout: EmbeddingLayout::RowMajor,
         };
         let mut chunks = vec![ChunkInput {
             id: 1,
@@ -0,0 +1,245 @@
+//! Property tests for the layer format: generated chunk sets survive a write/read round trip,
+//! and mutated files are rejected or read without panicking. The runners use a fixed seed, so a
+//! failure reproduces on every run.
+
+use agentsdb_format::{
+    read_all_chunks, write_layer_atomic, ChunkInput, ChunkSource, EmbeddingElementType,
+    EmbeddingLayout, LayerFile, LayerSchema,
+};
+use proptest::prelude::*;
+use proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+
+const I8_SCALE: f32 = 1.0 / 127.0;
+
+fn runner(cases: u32) -> TestRunner {
+    TestRunner::new_with_rng(
+        Config {
+            cases,
+            failure_persistence: None,
+            ..Config::default()
+        },
+        TestRng::deterministic_rng(RngAlgorithm::ChaCha),
+    )
+}
+
+fn source() -> impl Strategy<Value = ChunkSource> {
+    prop_oneof![
+        (1u32..=u32::MAX).prop_map(ChunkSource::ChunkId),
+        any::<String>().prop_map(ChunkSource::SourceString),
+    ]
+}
+
+fn chunk(dim: usize) -> impl Strategy<Value = ChunkInput> {
+    (
+        "[a-z.]{1,12}",
+        any::<String>(),
+        any::<bool>(),
+        0.0f32..=1.0,
+        any::<u64>(),
+        prop::collection::vec(-1.0f32..=1.0, dim),
+        prop::collection::vec(source(), 0..4),
+    )
+        .prop_map(
+            |(kind, content, mcp, confidence, created_at_unix_ms, embedding, sources)| ChunkInput {
+                id: 0,
+                kind,
+                content,
+                author: if mcp { "mcp" } else { "human" }.to_string(),
+                confidence,
+                created_at_unix_ms,
+                embedding,
+                sources,
+            },
+        )
+}
+
+#[derive(Debug, Clone)]
+struct Layer {
+    schema: LayerSchema,
+    chunks: Vec<ChunkInput>,
+    metadata: Option<String>,
+}
+
+fn layer() -> impl Strategy<Value = Layer> {
+    (1u32..=16, any::<bool>(), any::<bool>())
+        .prop_flat_map(|(dim, quantized, blocked)| {
+            (
+                Just(dim),
+                Just(quantized),
+                Just(blocked),
+                // Enough rows to span more than one block of a blocked matrix.
+                prop::collection::vec(chunk(dim as usize), 0..40),
+                proptest::option::of("\\{[a-z\":, 0-9]{0,24}\\}"),
+            )
+        })
+        .prop_map(|(dim, quantized, blocked, mut chunks, metadata)| {
+            for (id, c) in (1u32..).zip(chunks.iter_mut()) {
+                c.id = id;
+            }
+            let (element_type, quant_scale) = if quantized {
+                (EmbeddingElementType::I8, I8_SCALE)
+            } else {
+                (EmbeddingElementType::F32, 1.0)
+            };
+            Layer {
+                schema: LayerSchema {
+                    dim,
+                    element_type,
+                    quant_scale,
+                    layout: if blocked {
+                        EmbeddingLayout::Blocked
+                    } else {
+                        EmbeddingLayout::RowMajor
+                    },
+                },
+                chunks,
+                metadata,
+            }
+        })
+}
+
+fn same_source(a: &ChunkSource, b: &ChunkSource) -> bool {
+    match (a, b) {
+        (ChunkSource::ChunkId(x), ChunkSource::ChunkId(y)) => x == y,
+        (ChunkSource::SourceString(x), ChunkSource::SourceString(y)) => x == y,
+        _ => false,
+    }
+}
+
+#[test]
+fn written_chunks_read_back_unchanged() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let path = dir.path().join("AGENTS.db");
+    runner(128)
+        .run(&layer(), |layer| {
+            let mut chunks = layer.chunks.clone();
+            write_layer_atomic(
+                &path,
+                &layer.schema,
+                &mut chunks,
+                layer.metadata.as_deref().map(str::as_bytes),
+            )
+            .map_err(|e| TestCaseError::fail(format!("write: {e}")))?;
+
+            let file =
+                LayerFile::open(&path).map_err(|e| TestCaseError::fail(format!("open: {e}")))?;
+            prop_assert_eq!(file.embedding_dim(), layer.schema.dim as usize);
+            prop_assert_eq!(
+                file.layer_metadata_json().ok().flatten(),
+                layer.metadata.as_deref()
+            );
+            let read =
+                read_all_chunks(&file).map_err(|e| TestCaseError::fail(format!("read: {e}")))?;
+            prop_assert_eq!(read.len(), layer.chunks.len());
+
+            let tolerance = match layer.schema.element_type {
+                EmbeddingElementType::F32 => 0.0,
+                EmbeddingElementType::I8 => I8_SCALE / 2.0 + f32::EPSILON,
+            };
+            for (got, want) in read.iter().zip(&layer.chunks) {
+                prop_assert_eq!(got.id, want.id);
+                prop_assert_eq!(&got.kind, &want.kind);
+                prop_assert_eq!(&got.content, &want.content);
+                prop_assert_eq!(&got.author, &want.author);
+                prop_assert_eq!(got.confidence.to_bits(), want.confidence.to_bits());
+                prop_assert_eq!(got.created_at_unix_ms, want.created_at_unix_ms);
+                prop_assert_eq!(got.sources.len(), want.sources.len());
+                for (a, b) in got.sources.iter().zip(&want.sources) {
+                    prop_assert!(same_source(a, b), "source {:?} != {:?}", a, b);
+                }
+                for (a, b) in got.embedding.iter().zip(&want.embedding) {
+                    prop_assert!((a - b).abs() <= tolerance, "embedding {} != {}", a, b);
+                }
+            }
+
+            // The sequential scan sees the same rows as per-row reads.
+            let ones = vec![1.0f32; file.embedding_dim()];
+            let scanned = file
+                .dot_and_norm_rows(&ones)
+                .map_err(|e| TestCaseError::fail(format!("scan: {e}")))?;
+            prop_assert_eq!(scanned.len(), read.len());
+            for ((dot, _), got) in scanned.iter().zip(&read) {
+                let sum: f32 = got.embedding.iter().sum();
+                prop_assert!((dot - sum).abs() <= 1e-4, "scanned dot {} != {}", dot, sum);
+            }
+            Ok(())
+        })
+        .map_err(|e| e.to_string())
+}
+
+/// Reads everything a layer exposes, the way search and export do.
+fn walk(file: &LayerFile) {
+    let _ = file.layer_metadata_json();
+    let mut row = Vec::new();
+    for chunk in file.chunks() {
+        let Ok(chunk) = chunk else { continue };
+        row.resize(file.embedding_dim(), 0.0);
+        let _ = file.read_embedding_row_f32(chunk.embedding_row, &mut row);
+        let _ = file.sources_for(chunk.rel_start, chunk.rel_count);
+    }
+    let _ = file.dot_and_norm_rows(&vec![1.0; file.embedding_dim()]);
+    let _ = read_all_chunks(file);
+}
+
+fn seed_file(path: &std::path::Path) -> Result<Vec<u8>, String> {
+    let schema = LayerSchema {
+        dim: 4,
+        element_type: EmbeddingElementType::F32,
+        quant_scale: 1.0,
+        layout: EmbeddingLayout::RowMajor,
+    };
+    let mut chunks: Vec<ChunkInput> = (1..=3)
+        .map(|id| ChunkInput {
+            id,
+            kind: "note".to_string(),
+            content: format!("chunk {id}"),
+            author: "human".to_string(),
+            confidence: 0.5,
+            created_at_unix_ms: 1,
+            embedding: vec![0.25; 4],
+            sources: vec![
+                ChunkSource::ChunkId(id),
+                ChunkSource::SourceString(format!("src/{id}.rs:1")),
+            ],
+        })
+        .collect();
+    write_layer_atomic(path, &schema, &mut chunks, Some(br#"{"v":1}"#))
+        .map_err(|e| e.to_string())?;
+    std::fs::read(path).map_err(|e| e.to_string())
+}
+
+#[test]
+fn mutated_files_never_panic() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let seed = seed_file(&dir.path().join("seed.db"))?;
+    let path = dir.path().join("AGENTS.db");
+    let mutation = (
+        prop::collection::vec((0..seed.len(), any::<u8>()), 1..8),
+        proptest::option::of(0..seed.len()),
+        any::<bool>(),
+    );
+    runner(512)
+        .run(&mutation, |(writes, truncate, fix_length)| {
+            let mut bytes = seed.clone();
+            for (at, value) in writes {
+                bytes[at] = value;
+            }
+            if let Some(len) = truncate {
+                bytes.truncate(len);
+            }
+            // Keep the header's file length in step so parsing gets past the first check.
+            if fix_length && bytes.len() >= 16 {
+                let len = bytes.len() as u64;
+                bytes[8..16].copy_from_slice(&len.to_le_bytes());
+            }
+            std::fs::write(&path, &bytes).map_err(|e| TestCaseError::fail(e.to_string()))?;
+            for file in [LayerFile::open(&path), LayerFile::open_lenient(&path)]
+                .into_iter()
+                .flatten()
+            {
+                walk(&file);
+            }
+            Ok(())
+        })
+        .map_err(|e| e.to_string())
+}
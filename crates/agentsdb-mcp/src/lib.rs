@@ -1,31 +1,185 @@
+use agentsdb_core::chunking::{ChunkingOptions, PartOf};
 use agentsdb_core::types::{LayerId, SearchFilters};
 use agentsdb_embeddings::config::{
     get_immutable_embedding_options, roll_up_embedding_options,
 };
+use agentsdb_embeddings::embedder::EmbedPurpose;
 use agentsdb_embeddings::layer_metadata::ensure_layer_metadata_compatible_with_embedder;
 use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
-use agentsdb_query::{LayerSet, SearchQuery};
+use agentsdb_embeddings::layer_metadata::ProfileMismatchError;
+use agentsdb_embeddings::layer_metadata::{KindTaxonomy, KindValidation};
+use agentsdb_query::{LayerPool, LayerSet, SearchQuery};
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+pub use agentsdb_ops::startup_check::{CheckReport, CheckStep};
+
 const TOOL_AGENTS_SEARCH: &str = "agents_search";
 const TOOL_AGENTS_CONTEXT_WRITE: &str = "agents_context_write";
 const TOOL_AGENTS_CONTEXT_PROPOSE: &str = "agents_context_propose";
+const TOOL_AGENTS_CONTEXT_GET: &str = "agents_context_get";
+const TOOL_AGENTS_CONTEXT_LIST: &str = "agents_context_list";
+const TOOL_AGENTS_CONTEXT_RETRACT: &str = "agents_context_retract";
+const TOOL_AGENTS_CONTEXT_UPDATE: &str = "agents_context_update";
+const TOOL_AGENTS_REFLECT: &str = "agents_reflect";
+const TOOL_AGENTS_PROPOSALS_LIST: &str = "agents_proposals_list";
+const TOOL_AGENTS_PROPOSALS_ACCEPT: &str = "agents_proposals_accept";
+const TOOL_AGENTS_PROPOSALS_REJECT: &str = "agents_proposals_reject";
+const TOOL_AGENTS_SESSION_NOTE: &str = "agents_session_note";
+const TOOL_AGENTS_SESSION_END: &str = "agents_session_end";
+const TOOL_AGENTS_WORKSPACE_SEARCH: &str = "agents_workspace_search";
+const TOOL_AGENTS_STATUS: &str = "agents_status";
 
 // Legacy dot-separated names kept for backward compatibility with older clients.
 const TOOL_AGENTS_SEARCH_LEGACY: &str = "agents.search";
 const TOOL_AGENTS_CONTEXT_WRITE_LEGACY: &str = "agents.context.write";
 const TOOL_AGENTS_CONTEXT_PROPOSE_LEGACY: &str = "agents.context.propose";
+
+const PROMPT_AGENTS_RECALL: &str = "agents_recall";
+const PROMPT_AGENTS_REMEMBER: &str = "agents_remember";
+const PROMPT_DEFAULT_K: usize = 8;
+const LIST_DEFAULT_LIMIT: usize = 20;
 use std::io::{BufRead, Write};
 use std::path::{Path, PathBuf};
 
+/// Kinds `agents_reflect` files the items under a summary's headings as, with the confidence
+/// they are written with: decisions were made explicitly, gotchas observed, preferences inferred.
+const REFLECT_KINDS: [(&str, f32); 3] = [("decision", 0.9), ("gotcha", 0.8), ("preference", 0.7)];
+/// Kind and confidence of the summary `agents_reflect` writes; other item kinds get the same
+/// confidence unless they set one.
+const REFLECT_SUMMARY_KIND: &str = "note";
+const REFLECT_SUMMARY_CONFIDENCE: f32 = 0.6;
+
 #[derive(Debug, Clone, Default)]
 pub struct ServerConfig {
     pub base: Option<String>,
     pub user: Option<String>,
     pub delta: Option<String>,
     pub local: Option<String>,
+    /// Expose `agents_proposals_accept` / `agents_proposals_reject` to clients.
+    pub allow_proposal_review: bool,
+    /// Requests processed concurrently by `serve_stdio` (0 = default).
+    pub max_in_flight: usize,
+    /// Which mutating tools clients may call.
+    pub policy: ToolPolicy,
+    /// `agentsdb.toml` with layer paths, reloaded by `serve_stdio` when it changes
+    /// (default: `agentsdb.toml` in the working directory, if present).
+    pub config_file: Option<String>,
+    /// Append an ndjson record of every write/propose tool call to this file.
+    pub audit_log: Option<String>,
+    /// Also record searches and chunk reads (query text and retrieved chunk ids) in the audit
+    /// log, for usage analytics such as `agentsdb top`.
+    pub audit_reads: bool,
+    /// Rewrite this file with Prometheus text-format metrics after every request.
+    pub metrics_file: Option<String>,
+    /// Ephemeral layer written by `agents_session_note` (`serve_stdio` creates one per session).
+    pub session: Option<String>,
+    /// Signatures required of the layers read (the `trust.*` settings).
+    pub trust: agentsdb_embeddings::signing::TrustPolicy,
+    /// Expose `agents_workspace_search`, which searches every project in the workspace registry.
+    pub workspace: bool,
+    /// Results `agents_search` returns when a call sets no `k` (0 = 10).
+    pub search_k: usize,
+    /// Layers `agents_search` searches when a call names none (empty = all of them).
+    pub search_layers: Vec<String>,
+    /// Kinds every search is restricted to, on top of the call's own `kind` filter (empty = any).
+    pub search_kinds: Vec<String>,
+    /// Chunks below this confidence are left out of every search.
+    pub min_confidence: Option<f32>,
+}
+
+/// Tool-level permissions. Disallowed tools are left out of `tools/list` and calls to them
+/// are rejected with a permission error.
+#[derive(Debug, Clone)]
+pub struct ToolPolicy {
+    /// Allow `agents_context_write`, `agents_context_retract`, `agents_context_update` and
+    /// `agents_reflect`.
+    pub allow_write: bool,
+    /// Allow `agents_context_propose`.
+    pub allow_propose: bool,
+    /// Scopes (`local`, `delta`) writes may target; empty allows all of them.
+    pub write_scopes: Vec<String>,
+}
+
+impl Default for ToolPolicy {
+    fn default() -> Self {
+        Self {
+            allow_write: true,
+            allow_propose: true,
+            write_scopes: Vec::new(),
+        }
+    }
+}
+
+impl ToolPolicy {
+    /// A policy that only exposes read tools.
+    pub const fn read_only() -> Self {
+        Self {
+            allow_write: false,
+            allow_propose: false,
+            write_scopes: Vec::new(),
+        }
+    }
+
+    fn allows_scope(&self, scope: &str) -> bool {
+        self.write_scopes.is_empty() || self.write_scopes.iter().any(|s| s == scope)
+    }
+
+    fn allows_tool(&self, name: &str) -> bool {
+        match name {
+            TOOL_AGENTS_CONTEXT_WRITE
+            | TOOL_AGENTS_CONTEXT_WRITE_LEGACY
+            | TOOL_AGENTS_CONTEXT_RETRACT
+            | TOOL_AGENTS_CONTEXT_UPDATE
+            | TOOL_AGENTS_REFLECT => {
+                self.allow_write && ["local", "delta"].iter().any(|s| self.allows_scope(s))
+            }
+            TOOL_AGENTS_SESSION_NOTE | TOOL_AGENTS_SESSION_END => self.allow_write,
+            TOOL_AGENTS_CONTEXT_PROPOSE | TOOL_AGENTS_CONTEXT_PROPOSE_LEGACY => self.allow_propose,
+            _ => true,
+        }
+    }
+
+    /// Rejects a call to `name` with `args` that this policy does not allow.
+    fn check(&self, name: &str, args: &Value) -> Result<(), RpcError> {
+        if !self.allows_tool(name) {
+            return Err(RpcError::permission_denied(
+                format!("{name} is not allowed by the server's tool policy"),
+                serde_json::json!({ "code": "readonly", "tool": name }),
+            ));
+        }
+        let writes = matches!(
+            name,
+            TOOL_AGENTS_CONTEXT_WRITE
+                | TOOL_AGENTS_CONTEXT_WRITE_LEGACY
+                | TOOL_AGENTS_CONTEXT_RETRACT
+                | TOOL_AGENTS_CONTEXT_UPDATE
+                | TOOL_AGENTS_REFLECT
+        );
+        let scope = if name == TOOL_AGENTS_SESSION_END {
+            // Promotion writes the session notes to the local layer.
+            (args.get("action").and_then(Value::as_str) == Some("promote")).then_some("local")
+        } else {
+            args.get("scope").and_then(Value::as_str)
+        };
+        let writes = writes || name == TOOL_AGENTS_SESSION_END;
+        match scope {
+            Some(scope) if writes && !self.allows_scope(scope) => Err(RpcError::permission_denied(
+                format!(
+                    "{name} may not write to scope {scope:?} (allowed: {})",
+                    self.write_scopes.join(", ")
+                ),
+                serde_json::json!({
+                    "code": "readonly",
+                    "tool": name,
+                    "scope": scope,
+                    "allowed_scopes": self.write_scopes,
+                }),
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 fn expand_path_vars(path: &str, cwd: &Path) -> anyhow::Result<String> {
@@ -162,39 +316,158 @@ struct Response {
 struct ErrorObj {
     code: i64,
     message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
 }
 
 #[derive(Debug)]
 struct RpcError {
     code: i64,
     message: String,
+    /// Machine-readable details: `{"code": "<stable code>", ...context}`.
+    data: Option<Value>,
 }
 
 impl RpcError {
-    fn parse_error(message: impl Into<String>) -> Self {
+    const fn new(code: i64, message: String) -> Self {
         Self {
-            code: -32700,
-            message: message.into(),
+            code,
+            message,
+            data: None,
         }
     }
+    fn parse_error(message: impl Into<String>) -> Self {
+        Self::new(-32700, message.into())
+    }
     fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(-32602, message.into())
+    }
+    fn method_not_found(message: impl Into<String>) -> Self {
+        Self::new(-32601, message.into())
+    }
+    fn internal_error(message: impl Into<String>) -> Self {
+        Self::new(-32603, message.into())
+    }
+    fn permission_denied(message: impl Into<String>, data: Value) -> Self {
         Self {
-            code: -32602,
-            message: message.into(),
+            data: Some(data),
+            ..Self::new(-32001, message.into())
         }
     }
-    fn method_not_found(message: impl Into<String>) -> Self {
+    /// A failed tool call, carrying `error_data` for failures clients can act on. Permission
+    /// failures use the policy's `-32001` and invalid arguments `-32602`.
+    fn tool_failure(e: &anyhow::Error) -> Self {
+        let data = error_data(e);
+        let code = match data.as_ref().and_then(|d| d["code"].as_str()) {
+            Some("readonly" | "not_writable" | "base_not_allowed") => -32001,
+            Some(
+                "invalid_scope" | "scope_mismatch" | "dim_required" | "invalid_ids"
+                | "invalid_input" | "empty_import" | "missing_content" | "unknown_kind"
+                | "policy_violation",
+            ) => -32602,
+            _ => -32603,
+        };
         Self {
-            code: -32601,
-            message: message.into(),
+            data,
+            ..Self::new(code, format!("{e:#}"))
         }
     }
-    fn internal_error(message: impl Into<String>) -> Self {
+}
+
+/// A tool failure with a stable code that is reported to clients in `error.data`.
+#[derive(Debug)]
+struct ToolError {
+    code: &'static str,
+    message: String,
+    context: Value,
+}
+
+impl ToolError {
+    fn dim_mismatch(message: String, expected: usize, actual: usize) -> Self {
         Self {
-            code: -32603,
-            message: message.into(),
+            code: "dim_mismatch",
+            message,
+            context: serde_json::json!({ "expected": expected, "actual": actual }),
         }
     }
+
+    fn layer_missing(message: String, layer: &str, path: Option<&str>) -> Self {
+        Self {
+            code: "layer_missing",
+            message,
+            context: serde_json::json!({ "layer": layer, "path": path }),
+        }
+    }
+}
+
+impl std::fmt::Display for ToolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ToolError {}
+
+/// The error inside a transparent `agentsdb_ops` error variant. `chain()` skips it, because a
+/// transparent variant reports the wrapped error's source as its own.
+fn ops_error_inner<'a>(
+    cause: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a (dyn std::error::Error + 'static)> {
+    use agentsdb_ops::{ImportError, PromoteError, WriteError};
+    match cause.downcast_ref::<WriteError>() {
+        Some(WriteError::ProfileMismatch(p)) => return Some(p),
+        Some(WriteError::Core(c)) => return Some(c),
+        _ => {}
+    }
+    match cause.downcast_ref::<ImportError>() {
+        Some(ImportError::ProfileMismatch(p)) => return Some(p),
+        Some(ImportError::Core(c)) => return Some(c),
+        _ => {}
+    }
+    match cause.downcast_ref::<PromoteError>() {
+        Some(PromoteError::Core(c)) => Some(c),
+        _ => None,
+    }
+}
+
+/// Maps the first recognized error in `e`'s chain to `{"code": ..., ...context}`.
+fn error_data(e: &anyhow::Error) -> Option<Value> {
+    e.chain().find_map(|cause| {
+        let cause = ops_error_inner(cause).unwrap_or(cause);
+        if let Some(err) = cause.downcast_ref::<ToolError>() {
+            let mut data = err.context.clone();
+            data["code"] = Value::from(err.code);
+            return Some(data);
+        }
+        if let Some(err) = cause.downcast_ref::<ProfileMismatchError>() {
+            return Some(serde_json::json!({
+                "code": "profile_mismatch",
+                "layer": err.layer,
+                "existing": err.existing,
+                "current": err.current,
+            }));
+        }
+        let permission = match cause.downcast_ref::<agentsdb_core::error::Error>() {
+            Some(agentsdb_core::error::Error::Permission(p)) => Some(p),
+            _ => cause.downcast_ref::<agentsdb_core::error::PermissionError>(),
+        };
+        if let Some(agentsdb_core::error::PermissionError::WriteNotPermitted { path }) = permission
+        {
+            return Some(serde_json::json!({ "code": "readonly", "path": path }));
+        }
+        let trust = match cause.downcast_ref::<agentsdb_core::error::Error>() {
+            Some(agentsdb_core::error::Error::Trust(t)) => Some(t),
+            _ => cause.downcast_ref::<agentsdb_core::error::TrustError>(),
+        };
+        if let Some(agentsdb_core::error::TrustError::Untrusted { path, reason }) = trust {
+            return Some(serde_json::json!({
+                "code": "untrusted_layer",
+                "path": path,
+                "reason": reason,
+            }));
+        }
+        agentsdb_ops::error::cause_code(cause).map(|code| serde_json::json!({ "code": code }))
+    })
 }
 
 #[derive(Debug, Deserialize)]
@@ -208,12 +481,32 @@ struct SearchParams {
     filters: Option<SearchFiltersParams>,
     #[serde(default)]
     layers: Option<Vec<String>>,
+    #[serde(default)]
+    include_sources_content: bool,
+    /// Return the hits of a `tools/call` as content blocks, see [`search_content_blocks`].
+    #[serde(default)]
+    content_blocks: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceSearchParams {
+    query: String,
+    #[serde(default)]
+    query_vec: Option<Vec<f32>>,
+    #[serde(default)]
+    k: Option<usize>,
+    #[serde(default)]
+    filters: Option<SearchFiltersParams>,
 }
 
 #[derive(Debug, Deserialize)]
 struct SearchFiltersParams {
     #[serde(default)]
     kind: Vec<String>,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -224,6 +517,34 @@ struct WriteParams {
     #[serde(default)]
     sources: Vec<WriteSource>,
     scope: String, // local | delta
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionNoteParams {
+    content: String,
+    #[serde(default = "default_session_note_kind")]
+    kind: String,
+    #[serde(default = "default_session_note_confidence")]
+    confidence: f32,
+    #[serde(default)]
+    sources: Vec<WriteSource>,
+}
+
+fn default_session_note_kind() -> String {
+    "note".to_string()
+}
+
+const fn default_session_note_confidence() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionEndParams {
+    action: String, // promote | discard
 }
 
 #[derive(Debug, Deserialize)]
@@ -247,6 +568,93 @@ struct ProposeParams {
     where_: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct GetParams {
+    id: u32,
+    #[serde(default)]
+    layers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListParams {
+    #[serde(default)]
+    kind: Vec<String>,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    layers: Option<Vec<String>>,
+    #[serde(default)]
+    since_unix_ms: Option<u64>,
+    #[serde(default)]
+    until_unix_ms: Option<u64>,
+    #[serde(default)]
+    offset: Option<usize>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RetractParams {
+    id: u32,
+    reason: String,
+    scope: String, // local | delta
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateParams {
+    id: u32,
+    content: String,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    confidence: Option<f32>,
+    #[serde(default)]
+    reason: Option<String>,
+    scope: String, // local | delta
+}
+
+#[derive(Debug, Deserialize)]
+struct ReflectParams {
+    /// Markdown summary of the session, see [`split_reflection`].
+    summary: String,
+    #[serde(default)]
+    items: Vec<ReflectItem>,
+    #[serde(default)]
+    sources: Vec<WriteSource>,
+    scope: String, // local | delta
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReflectItem {
+    kind: String,
+    content: String,
+    #[serde(default)]
+    confidence: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProposalsListParams {
+    #[serde(default)]
+    all: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProposalsDecideParams {
+    ids: Vec<u32>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PromptGetParams {
+    name: String,
+    // MCP prompt arguments are always string-valued.
+    #[serde(default)]
+    arguments: std::collections::BTreeMap<String, String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ToolCallParams {
     name: String,
@@ -254,111 +662,1258 @@ struct ToolCallParams {
     arguments: Value,
 }
 
+/// Stops a running [`serve_stdio_until`] loop from another thread (e.g. a signal handler).
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownTrigger(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl ShutdownTrigger {
+    pub fn shutdown(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    fn is_set(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+/// How often the stdin loop checks for shutdown while waiting for input.
+const SHUTDOWN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
 pub fn serve_stdio(config: ServerConfig) -> anyhow::Result<()> {
+    serve_stdio_until(config, &ShutdownTrigger::default())
+}
+
+/// Reads stdin on a detached thread, since a blocking read cannot be interrupted on shutdown.
+fn spawn_stdin_reader() -> std::sync::mpsc::Receiver<std::io::Result<String>> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Next stdin line, or `None` at EOF or once `shutdown` fires.
+fn next_line(
+    lines: &std::sync::mpsc::Receiver<std::io::Result<String>>,
+    shutdown: &ShutdownTrigger,
+) -> Option<std::io::Result<String>> {
+    while !shutdown.is_set() {
+        match lines.recv_timeout(SHUTDOWN_POLL_INTERVAL) {
+            Ok(line) => return Some(line),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+    None
+}
+
+/// Enforces the local layer's retention policy when `retention.on_startup` is set. Failures
+/// are logged, not fatal: the server is still useful with an oversized local layer.
+fn apply_retention_on_startup(config: &ServerConfig) {
+    if !config.policy.allow_write || !config.policy.allows_scope("local") {
+        return;
+    }
+    let Some(local) = config.local.as_deref() else {
+        return;
+    };
+    let applied = agentsdb_ops::retention::apply_on_startup(
+        Path::new(local),
+        "agentsdb-mcp",
+        env!("CARGO_PKG_VERSION"),
+    );
+    match applied {
+        Ok(Some(outcome)) => tracing::info!(
+            retracted = outcome.retracted.len(),
+            collected = outcome.collected.len(),
+            kept = outcome.kept,
+            "applied the retention policy to {local}"
+        ),
+        Ok(None) => {}
+        Err(err) => tracing::warn!("retention policy not applied to {local}: {err:#}"),
+    }
+}
+
+/// Like [`serve_stdio`], but returns once `shutdown` fires: no new requests are read, queued
+/// and in-flight ones are answered, and the session layer is cleaned up.
+pub fn serve_stdio_until(config: ServerConfig, shutdown: &ShutdownTrigger) -> anyhow::Result<()> {
     let cwd = std::env::current_dir().context("get current working directory")?;
-    let config = normalize_config_with_cwd(config, &cwd).context("normalize layer paths")?;
+    agentsdb_ops::hooks::register(std::sync::Arc::new(agentsdb_ops::hooks::PolicyHook));
+    agentsdb_ops::identity::install_for_dir(&cwd).context("resolve identity")?;
+    agentsdb_ops::query_cache::install_for_dir(&cwd).context("resolve query cache settings")?;
+    let session = config
+        .session
+        .clone()
+        .unwrap_or_else(new_session_layer_path);
+    let live = LiveConfig::load(
+        ServerConfig {
+            session: Some(session.clone()),
+            ..config
+        },
+        cwd.clone(),
+    )?;
+    let config = live.current();
+    apply_retention_on_startup(&config);
+    let layers = [&config.base, &config.user, &config.delta, &config.local]
+        .into_iter()
+        .flatten()
+        .map(std::path::PathBuf::from)
+        .collect();
+    let _indexes = agentsdb_ops::index_maintenance::start_for_layers(&cwd, layers)
+        .context("start index maintenance")?;
+    let max_in_flight = if config.max_in_flight == 0 {
+        DEFAULT_MAX_IN_FLIGHT
+    } else {
+        config.max_in_flight
+    };
 
-    let stdin = std::io::stdin();
-    let mut stdout = std::io::stdout();
+    let lines = spawn_stdin_reader();
+    let stdout = std::sync::Mutex::new(std::io::stdout());
+    let write_order = WriteOrder::default();
+    let write_error: std::sync::Mutex<Option<std::io::Error>> = std::sync::Mutex::new(None);
+    // Bounded so the reader does not run far ahead of the workers.
+    let (tx, rx) = std::sync::mpsc::sync_channel::<Job>(max_in_flight);
+    let rx = std::sync::Mutex::new(rx);
+    let (stop_watch, watch_stopped) = std::sync::mpsc::channel::<()>();
+    let roots = Roots::default();
+    let audit = AuditLog::new(
+        config.audit_log.as_deref().map(|p| cwd.join(p)),
+        config.audit_reads,
+    );
+    let metrics = MetricsFile::new(config.metrics_file.as_deref().map(|p| cwd.join(p)));
 
-    for line in stdin.lock().lines() {
-        let line = line?;
-        if line.trim().is_empty() {
-            continue;
+    std::thread::scope(|scope| -> anyhow::Result<()> {
+        let (live_ref, roots_ref, stdout_ref) = (&live, &roots, &stdout);
+        scope.spawn(move || {
+            watch_files(live_ref, roots_ref, &watch_stopped, |change| {
+                let _ = write_message(stdout_ref, change);
+            });
+        });
+        for _ in 0..max_in_flight {
+            scope.spawn(|| {
+                while let Some(job) = rx.lock().ok().and_then(|rx| rx.recv().ok()) {
+                    let out = write_order.run(&job.tickets, || {
+                        handle_job(&live.current(), &roots, &audit, &metrics, job.req)
+                    });
+                    if let Err(e) = write_message(&stdout, &out) {
+                        if let Ok(mut slot) = write_error.lock() {
+                            slot.get_or_insert(e);
+                        }
+                    }
+                }
+            });
         }
-        let req: Result<Request, _> = serde_json::from_str(&line);
-        let (req, parse_error) = match req {
-            Ok(req) => (Some(req), None),
-            Err(e) => (
-                None,
-                Some(RpcError::parse_error(format!("parse error: {e}"))),
-            ),
-        };
 
-        // JSON-RPC notifications have no id; do not respond.
-        let id = req.as_ref().and_then(|r| r.id.as_ref());
-        if id.is_none() && parse_error.is_none() {
-            continue;
+        while let Some(line) = next_line(&lines, shutdown) {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let msg: Result<Value, _> = serde_json::from_str(&line);
+            if let Ok(msg) = &msg {
+                if is_client_response(msg) {
+                    roots.handle_response(&live.raw(), msg);
+                    continue;
+                }
+            }
+            let req = msg.and_then(serde_json::from_value::<Request>);
+            if let Ok(req) = &req {
+                audit.observe(req);
+                if roots.observe(req) {
+                    write_message(&stdout, &roots_list_request())?;
+                }
+            }
+            // JSON-RPC notifications have no id; do not respond.
+            if req.as_ref().is_ok_and(|r| r.id.is_none()) {
+                continue;
+            }
+            let tickets = req
+                .as_ref()
+                .map(|r| write_order.tickets(&written_layers(r)))
+                .unwrap_or_default();
+            let job = Job {
+                req: req.map_err(|e| RpcError::parse_error(format!("parse error: {e}"))),
+                tickets,
+            };
+            if tx.send(job).is_err() {
+                break;
+            }
+            if write_error.lock().is_ok_and(|e| e.is_some()) {
+                break;
+            }
         }
+        drop(tx);
+        drop(stop_watch);
+        Ok(())
+    })?;
+
+    // Notes not promoted with `agents_session_end` are discarded with the session.
+    let _ = std::fs::remove_file(&session);
+
+    match write_error.into_inner() {
+        Ok(Some(e)) => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// A fresh `AGENTS.session.<id>.db` path in the system temp directory.
+fn new_session_layer_path() -> String {
+    let id = format!(
+        "{}-{:x}",
+        std::process::id(),
+        agentsdb_ops::util::now_unix_ms()
+    );
+    std::env::temp_dir()
+        .join("agentsdb-sessions")
+        .join(format!("AGENTS.session.{id}.db"))
+        .to_string_lossy()
+        .into_owned()
+}
 
-        let out = if let Some(parse_error) = parse_error {
-            Response {
+const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+
+struct Job {
+    req: Result<Request, RpcError>,
+    tickets: Vec<(&'static str, u64)>,
+}
+
+fn handle_job(
+    config: &ServerConfig,
+    roots: &Roots,
+    audit: &AuditLog,
+    metrics: &MetricsFile,
+    req: Result<Request, RpcError>,
+) -> Response {
+    let req = match req {
+        Ok(req) => req,
+        Err(parse_error) => {
+            return Response {
                 jsonrpc: "2.0",
                 id: None,
                 result: None,
                 error: Some(ErrorObj {
                     code: parse_error.code,
                     message: parse_error.message,
+                    data: parse_error.data,
                 }),
             }
-        } else {
-            let req = req.expect("req must exist when no parse_error");
-            let res = handle_request(&config, &req);
-            match res {
-                Ok(result) => Response {
-                    jsonrpc: "2.0",
-                    id: req.id.clone(),
-                    result: Some(result),
-                    error: None,
-                },
-                Err(e) => Response {
-                    jsonrpc: "2.0",
-                    id: req.id.clone(),
-                    result: None,
-                    error: Some(ErrorObj {
-                        code: e.code,
-                        message: e.message,
-                    }),
-                },
-            }
-        };
-
-        writeln!(stdout, "{}", serde_json::to_string(&out)?)?;
-        stdout.flush()?;
+        }
+    };
+    let _span = tracing::info_span!("rpc", method = %req.method).entered();
+    let started = std::time::Instant::now();
+    let result = roots
+        .select(config, &req)
+        .and_then(|config| handle_request(&config, &req));
+    tracing::debug!(
+        ok = result.is_ok(),
+        latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        "rpc handled"
+    );
+    if let Err(e) = audit.record(&req, &result) {
+        tracing::error!("audit log: {e:#}");
+    }
+    if let Err(e) = metrics.record(config, &req, result.is_ok(), started.elapsed()) {
+        tracing::error!("metrics: {e:#}");
+    }
+    match result {
+        Ok(result) => Response {
+            jsonrpc: "2.0",
+            id: req.id,
+            result: Some(result),
+            error: None,
+        },
+        Err(e) => Response {
+            jsonrpc: "2.0",
+            id: req.id,
+            result: None,
+            error: Some(ErrorObj {
+                code: e.code,
+                message: e.message,
+                data: e.data,
+            }),
+        },
     }
+}
 
-    Ok(())
+fn write_message(
+    stdout: &std::sync::Mutex<std::io::Stdout>,
+    out: &impl Serialize,
+) -> std::io::Result<()> {
+    let line = serde_json::to_string(out)?;
+    let mut stdout = stdout
+        .lock()
+        .map_err(|_| std::io::Error::other("stdout lock poisoned"))?;
+    writeln!(stdout, "{line}")?;
+    stdout.flush()
 }
 
-fn handle_request(config: &ServerConfig, req: &Request) -> Result<Value, RpcError> {
-    match req.method.as_str() {
-        // MCP/JSON-RPC handshake
-        "initialize" => Ok(handle_initialize(req.params.clone())),
-        "tools/list" => Ok(handle_tools_list()),
-        "tools/call" => {
-            let params: ToolCallParams = serde_json::from_value(req.params.clone())
-                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
-            handle_tools_call(config, params)
-        }
-        "resources/list" => Ok(serde_json::json!({ "resources": [] })),
-        "prompts/list" => Ok(serde_json::json!({ "prompts": [] })),
-        "ping" => Ok(serde_json::json!({})),
-        "shutdown" => Ok(Value::Null),
+/// Id of the `roots/list` request the server sends to clients that declare `roots`.
+const ROOTS_REQUEST_ID: &str = "agentsdb-roots";
 
-        // Allow calling these as raw methods, in addition to `tools/call`.
-        TOOL_AGENTS_SEARCH | TOOL_AGENTS_SEARCH_LEGACY => {
-            let params: SearchParams = serde_json::from_value(req.params.clone())
-                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
-            handle_search(config, params).map_err(|e| RpcError::internal_error(format!("{e:#}")))
-        }
-        TOOL_AGENTS_CONTEXT_WRITE | TOOL_AGENTS_CONTEXT_WRITE_LEGACY => {
-            let params: WriteParams = serde_json::from_value(req.params.clone())
-                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
-            handle_write(config, params).map_err(|e| RpcError::internal_error(format!("{e:#}")))
-        }
-        TOOL_AGENTS_CONTEXT_PROPOSE | TOOL_AGENTS_CONTEXT_PROPOSE_LEGACY => {
-            let params: ProposeParams = serde_json::from_value(req.params.clone())
-                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
-            handle_propose(config, params).map_err(|e| RpcError::internal_error(format!("{e:#}")))
-        }
-        other => Err(RpcError::method_not_found(format!(
-            "unknown method: {other}"
-        ))),
-    }
+fn roots_list_request() -> Value {
+    serde_json::json!({ "jsonrpc": "2.0", "id": ROOTS_REQUEST_ID, "method": "roots/list" })
 }
 
-fn handle_initialize(_params: Value) -> Value {
-    // Minimal MCP initialize response; clients use this to verify transport and discover tools.
+/// Whether `msg` is a client's response to a server-initiated request.
+fn is_client_response(msg: &Value) -> bool {
+    msg.get("method").is_none() && (msg.get("result").is_some() || msg.get("error").is_some())
+}
+
+/// The layer set for one client workspace root.
+#[derive(Debug, Clone)]
+struct RootLayers {
+    name: String,
+    path: PathBuf,
+    config: ServerConfig,
+}
+
+/// Workspace roots declared by the client. Once the client has answered `roots/list`, each
+/// request runs against the layers of the root named by its `root` argument (default: the
+/// first root) instead of the layers resolved from the server's working directory.
+#[derive(Default)]
+struct Roots {
+    supported: std::sync::atomic::AtomicBool,
+    declared: std::sync::RwLock<Vec<(String, PathBuf)>>,
+    sets: std::sync::RwLock<Vec<RootLayers>>,
+}
+
+impl Roots {
+    /// Tracks the client's `roots` capability. Returns true when roots should be (re)fetched.
+    fn observe(&self, req: &Request) -> bool {
+        use std::sync::atomic::Ordering;
+        match req.method.as_str() {
+            "initialize" => {
+                let declared = req.params.pointer("/capabilities/roots").is_some();
+                self.supported.store(declared, Ordering::Relaxed);
+                false
+            }
+            "notifications/initialized" | "notifications/roots/list_changed" => {
+                self.supported.load(Ordering::Relaxed)
+            }
+            _ => false,
+        }
+    }
+
+    fn handle_response(&self, raw_config: &ServerConfig, msg: &Value) {
+        if msg.get("id").and_then(Value::as_str) != Some(ROOTS_REQUEST_ID) {
+            return;
+        }
+        let Some(roots) = msg.pointer("/result/roots").and_then(Value::as_array) else {
+            return;
+        };
+        let declared = roots
+            .iter()
+            .filter_map(|root| {
+                let path = root_path_from_uri(root.get("uri")?.as_str()?)?;
+                let name = root
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .or_else(|| Some(path.file_name()?.to_string_lossy().into_owned()))
+                    .unwrap_or_else(|| path.to_string_lossy().into_owned());
+                Some((name, path))
+            })
+            .collect();
+        if let Ok(mut slot) = self.declared.write() {
+            *slot = declared;
+        }
+        self.rebuild(raw_config);
+    }
+
+    /// Re-resolves the layer set of every declared root against `raw_config`.
+    fn rebuild(&self, raw_config: &ServerConfig) {
+        let Ok(declared) = self.declared.read() else {
+            return;
+        };
+        let sets = declared
+            .iter()
+            .filter_map(|(name, path)| discover_root_layers(raw_config, name.clone(), path.clone()))
+            .collect();
+        if let Ok(mut slot) = self.sets.write() {
+            *slot = sets;
+        }
+    }
+
+    /// The configuration `req` runs against.
+    fn select<'a>(
+        &self,
+        config: &'a ServerConfig,
+        req: &Request,
+    ) -> Result<std::borrow::Cow<'a, ServerConfig>, RpcError> {
+        let sets = self
+            .sets
+            .read()
+            .map_err(|_| RpcError::internal_error("roots lock poisoned"))?;
+        let wanted = request_root(req);
+        if sets.is_empty() {
+            return match wanted {
+                Some(root) => Err(RpcError::invalid_params(format!(
+                    "unknown root {root:?} (the client has not declared any workspace roots)"
+                ))),
+                None => Ok(std::borrow::Cow::Borrowed(config)),
+            };
+        }
+        let found = match wanted {
+            Some(root) => sets
+                .iter()
+                .find(|set| set.name == root || set.path == Path::new(root)),
+            None => sets.first(),
+        };
+        found
+            .map(|set| std::borrow::Cow::Owned(set.config.clone()))
+            .ok_or_else(|| {
+                let names: Vec<&str> = sets.iter().map(|set| set.name.as_str()).collect();
+                RpcError::invalid_params(format!(
+                    "unknown root {:?} (available: {})",
+                    wanted.unwrap_or_default(),
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// The `root` argument of a tool call or raw method call.
+fn request_root(req: &Request) -> Option<&str> {
+    tool_call(req).1.get("root").and_then(Value::as_str)
+}
+
+/// Tool name and arguments of a `tools/call` request, or method and params of a raw call.
+fn tool_call(req: &Request) -> (&str, &Value) {
+    if req.method == "tools/call" {
+        (
+            req.params
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default(),
+            req.params.get("arguments").unwrap_or(&Value::Null),
+        )
+    } else {
+        (req.method.as_str(), &req.params)
+    }
+}
+
+/// Resolves the configured layer paths (or the standard file names, for layers not configured)
+/// against `path`. Roots without a base layer are skipped.
+fn discover_root_layers(
+    raw_config: &ServerConfig,
+    name: String,
+    path: PathBuf,
+) -> Option<RootLayers> {
+    let mut config = raw_config.clone();
+    for (slot, default) in [
+        (&mut config.base, "AGENTS.db"),
+        (&mut config.user, "AGENTS.user.db"),
+        (&mut config.delta, "AGENTS.delta.db"),
+        (&mut config.local, "AGENTS.local.db"),
+    ] {
+        let expanded = expand_path_vars(slot.as_deref().unwrap_or(default), &path).ok()?;
+        *slot = Some(path.join(expanded).to_string_lossy().into_owned());
+    }
+    if !config
+        .base
+        .as_deref()
+        .is_some_and(|base| Path::new(base).exists())
+    {
+        return None;
+    }
+    Some(RootLayers { name, path, config })
+}
+
+/// Converts a `file://` root URI into a local path.
+fn root_path_from_uri(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    // Drop an optional host (`file://localhost/...`).
+    let rest = rest.get(rest.find('/')?..)?;
+    let mut bytes = Vec::with_capacity(rest.len());
+    let mut iter = rest.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    let mut decoded = String::from_utf8(bytes).ok()?;
+    // `file:///C:/work` -> `C:/work`
+    let decoded = match decoded.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => decoded.split_off(1),
+        _ => decoded,
+    };
+    Some(PathBuf::from(decoded))
+}
+
+/// Tools that change layers; calls to them are recorded in the audit log.
+const AUDITED_TOOLS: [&str; 11] = [
+    TOOL_AGENTS_CONTEXT_WRITE,
+    TOOL_AGENTS_CONTEXT_WRITE_LEGACY,
+    TOOL_AGENTS_CONTEXT_PROPOSE,
+    TOOL_AGENTS_CONTEXT_PROPOSE_LEGACY,
+    TOOL_AGENTS_CONTEXT_RETRACT,
+    TOOL_AGENTS_CONTEXT_UPDATE,
+    TOOL_AGENTS_REFLECT,
+    TOOL_AGENTS_PROPOSALS_ACCEPT,
+    TOOL_AGENTS_PROPOSALS_REJECT,
+    TOOL_AGENTS_SESSION_NOTE,
+    TOOL_AGENTS_SESSION_END,
+];
+
+/// Read-only tools recorded in the audit log when [`ServerConfig::audit_reads`] is set.
+const AUDITED_READ_TOOLS: [&str; 3] = [
+    TOOL_AGENTS_SEARCH,
+    TOOL_AGENTS_SEARCH_LEGACY,
+    TOOL_AGENTS_CONTEXT_GET,
+];
+
+/// Opt-in ndjson audit trail of mutating tool calls (see [`ServerConfig::audit_log`]).
+struct AuditLog {
+    path: Option<PathBuf>,
+    /// Also record [`AUDITED_READ_TOOLS`] calls.
+    reads: bool,
+    /// `clientInfo` from `initialize`, e.g. `claude-code/1.0.0`.
+    actor: std::sync::RwLock<String>,
+    file: std::sync::Mutex<()>,
+}
+
+impl AuditLog {
+    fn new(path: Option<PathBuf>, reads: bool) -> Self {
+        Self {
+            path,
+            reads,
+            actor: std::sync::RwLock::new("mcp".to_string()),
+            file: std::sync::Mutex::new(()),
+        }
+    }
+
+    /// Picks up the client's name and version from `initialize`.
+    fn observe(&self, req: &Request) {
+        if req.method != "initialize" {
+            return;
+        }
+        let info = req.params.get("clientInfo");
+        let Some(name) = info.and_then(|i| i.get("name")).and_then(Value::as_str) else {
+            return;
+        };
+        let actor = match info.and_then(|i| i.get("version")).and_then(Value::as_str) {
+            Some(version) => format!("{name}/{version}"),
+            None => name.to_string(),
+        };
+        if let Ok(mut slot) = self.actor.write() {
+            *slot = actor;
+        }
+    }
+
+    /// Appends a record for `req` if it is a mutating tool call.
+    fn record(&self, req: &Request, result: &Result<Value, RpcError>) -> anyhow::Result<()> {
+        let Some(path) = self.path.as_deref() else {
+            return Ok(());
+        };
+        let (tool, args) = tool_call(req);
+        let read = self.reads && AUDITED_READ_TOOLS.contains(&tool);
+        if !read && !AUDITED_TOOLS.contains(&tool) {
+            return Ok(());
+        }
+        let actor = self.actor.read().map(|a| a.clone()).unwrap_or_default();
+        let mut entry = serde_json::json!({
+            "ts_unix_ms": agentsdb_ops::util::now_unix_ms(),
+            "tool": tool,
+            "args_sha256": agentsdb_ops::util::content_sha256_hex(&args.to_string()),
+            "actor": actor,
+            "ok": result.is_ok(),
+        });
+        match result {
+            Ok(result) if read => {
+                if let Some(query) = args.get("query") {
+                    entry["query"] = query.clone();
+                }
+                entry["retrieved"] = retrieved_chunks(&tool_payload(req, result));
+            }
+            Ok(result) => {
+                let payload = tool_payload(req, result);
+                entry["chunk_id"] = payload.get("context_id").cloned().unwrap_or(Value::Null);
+                entry["result"] = payload;
+            }
+            Err(e) => entry["error"] = Value::from(e.message.as_str()),
+        }
+
+        let _guard = self
+            .file
+            .lock()
+            .map_err(|_| anyhow::anyhow!("audit log lock poisoned"))?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open audit log {}", path.display()))?;
+        writeln!(file, "{entry}").with_context(|| format!("write audit log {}", path.display()))
+    }
+}
+
+/// `{layer, id}` of the chunks in a search (array) or `agents_context_get` (object) payload.
+fn retrieved_chunks(payload: &Value) -> Value {
+    let hits: Vec<&Value> = match payload {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(_) => vec![payload],
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => Vec::new(),
+    };
+    hits.into_iter()
+        .filter_map(|hit| {
+            let layer = hit.get("layer")?.as_str()?.to_ascii_lowercase();
+            let id = hit.pointer("/chunk/id")?.as_u64()?;
+            Some(serde_json::json!({ "layer": layer, "id": id }))
+        })
+        .collect()
+}
+
+/// Opt-in Prometheus textfile (see [`ServerConfig::metrics_file`]). Requests are counted in
+/// the process-wide registry either way; the file is only written when a path is set.
+struct MetricsFile {
+    path: Option<PathBuf>,
+    file: std::sync::Mutex<()>,
+}
+
+impl MetricsFile {
+    const fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            file: std::sync::Mutex::new(()),
+        }
+    }
+
+    /// Counts `req`, then rewrites the file with the registry and the current layer sizes.
+    fn record(
+        &self,
+        config: &ServerConfig,
+        req: &Request,
+        ok: bool,
+        elapsed: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        let (tool, _) = tool_call(req);
+        let metrics = agentsdb_ops::metrics::global();
+        metrics.inc_counter(
+            "agentsdb_mcp_requests_total",
+            "MCP requests answered, by method or tool name.",
+            &[("tool", tool), ("ok", if ok { "true" } else { "false" })],
+            1,
+        );
+        metrics.observe(
+            "agentsdb_mcp_request_duration_seconds",
+            "Time spent answering MCP requests.",
+            &[("tool", tool)],
+            elapsed.as_secs_f64(),
+        );
+        let Some(path) = self.path.as_deref() else {
+            return Ok(());
+        };
+
+        let layers = agentsdb_ops::metrics::Metrics::default();
+        for (scope, layer) in [
+            ("base", &config.base),
+            ("user", &config.user),
+            ("delta", &config.delta),
+            ("local", &config.local),
+        ] {
+            let Some(size) = layer
+                .as_deref()
+                .and_then(|p| std::fs::metadata(p).ok())
+                .map(|m| m.len())
+            else {
+                continue;
+            };
+            // Layer sizes stay far below 2^52, so the f64 conversion is exact.
+            #[allow(clippy::cast_precision_loss)]
+            layers.set_gauge(
+                "agentsdb_layer_size_bytes",
+                "Size of each layer file.",
+                &[("scope", scope)],
+                size as f64,
+            );
+        }
+        let mut text = metrics.render();
+        text.push_str(&layers.render());
+
+        let _guard = self
+            .file
+            .lock()
+            .map_err(|_| anyhow::anyhow!("metrics file lock poisoned"))?;
+        // Written beside the target and renamed so collectors never read a partial file.
+        let tmp = path.with_extension("prom.tmp");
+        std::fs::write(&tmp, text).with_context(|| format!("write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("replace {}", path.display()))
+    }
+}
+
+/// The tool's own result: `tools/call` wraps it as JSON text in the first content block.
+fn tool_payload(req: &Request, result: &Value) -> Value {
+    if req.method != "tools/call" {
+        return result.clone();
+    }
+    result
+        .pointer("/content/0/text")
+        .and_then(Value::as_str)
+        .and_then(|text| serde_json::from_str(text).ok())
+        .unwrap_or(Value::Null)
+}
+
+/// Layers a request appends to. Requests writing the same layer run in arrival order.
+fn written_layers(req: &Request) -> Vec<&'static str> {
+    let (name, args) = tool_call(req);
+    match name {
+        TOOL_AGENTS_CONTEXT_WRITE
+        | TOOL_AGENTS_CONTEXT_WRITE_LEGACY
+        | TOOL_AGENTS_CONTEXT_RETRACT
+        | TOOL_AGENTS_CONTEXT_UPDATE
+        | TOOL_AGENTS_REFLECT => match args.get("scope").and_then(Value::as_str) {
+            Some("local") => vec!["local"],
+            Some("delta") => vec!["delta"],
+            _ => Vec::new(),
+        },
+        TOOL_AGENTS_CONTEXT_PROPOSE
+        | TOOL_AGENTS_CONTEXT_PROPOSE_LEGACY
+        | TOOL_AGENTS_PROPOSALS_REJECT => vec!["delta"],
+        // Promotion moves chunks between any of the writable layers.
+        TOOL_AGENTS_PROPOSALS_ACCEPT => vec!["local", "user", "delta"],
+        TOOL_AGENTS_SESSION_NOTE => vec!["session"],
+        TOOL_AGENTS_SESSION_END => vec!["session", "local"],
+        _ => Vec::new(),
+    }
+}
+
+/// Per-layer ticket queues. Tickets are handed out on the reader thread in arrival order, and a
+/// job only runs once every layer it writes is serving its ticket. Jobs are taken from the queue
+/// in the same order, so an earlier ticket is always already running and waiting cannot deadlock.
+#[derive(Default)]
+struct WriteOrder {
+    // layer -> (next ticket to hand out, ticket now allowed to run)
+    lanes: std::sync::Mutex<std::collections::HashMap<&'static str, (u64, u64)>>,
+    turn: std::sync::Condvar,
+}
+
+impl WriteOrder {
+    fn tickets(&self, layers: &[&'static str]) -> Vec<(&'static str, u64)> {
+        let Ok(mut lanes) = self.lanes.lock() else {
+            return Vec::new();
+        };
+        layers
+            .iter()
+            .map(|layer| {
+                let lane = lanes.entry(layer).or_insert((0, 0));
+                lane.0 += 1;
+                (*layer, lane.0 - 1)
+            })
+            .collect()
+    }
+
+    fn run<T>(&self, tickets: &[(&'static str, u64)], f: impl FnOnce() -> T) -> T {
+        if tickets.is_empty() {
+            return f();
+        }
+        if let Ok(lanes) = self.lanes.lock() {
+            let _turn = self.turn.wait_while(lanes, |lanes| {
+                tickets
+                    .iter()
+                    .any(|(layer, ticket)| lanes.get(layer).is_some_and(|l| l.1 != *ticket))
+            });
+        }
+        let _release = TicketRelease {
+            order: self,
+            tickets,
+        };
+        f()
+    }
+}
+
+/// Passes the lanes of `tickets` on to their next tickets when dropped, so a job that panics
+/// still lets the writes queued behind it run.
+struct TicketRelease<'a> {
+    order: &'a WriteOrder,
+    tickets: &'a [(&'static str, u64)],
+}
+
+impl Drop for TicketRelease<'_> {
+    fn drop(&mut self) {
+        let mut lanes = self
+            .order
+            .lanes
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        for (layer, _) in self.tickets {
+            if let Some(lane) = lanes.get_mut(layer) {
+                lane.1 += 1;
+            }
+        }
+        drop(lanes);
+        self.order.turn.notify_all();
+    }
+}
+
+const LAYER_CHANGED_METHOD: &str = "agentsdb/layerChanged";
+const LAYER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Debug, Serialize)]
+struct Notification {
+    jsonrpc: &'static str,
+    method: &'static str,
+    params: Value,
+}
+
+/// Size and modification time of a layer file; `None` while the file does not exist.
+type LayerStamp = Option<(u64, std::time::SystemTime)>;
+
+fn layer_stamp(path: &Path) -> LayerStamp {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.len(), meta.modified().ok()?))
+}
+
+/// Layer files being watched, with their stamps from the last poll.
+struct LayerWatcher {
+    layers: Vec<(&'static str, String, LayerStamp)>,
+}
+
+impl LayerWatcher {
+    fn new(config: &ServerConfig) -> Self {
+        let layers = [
+            ("local", &config.local),
+            ("user", &config.user),
+            ("delta", &config.delta),
+            ("base", &config.base),
+        ]
+        .into_iter()
+        .filter_map(|(name, path)| {
+            let path = path.clone()?;
+            let stamp = layer_stamp(Path::new(&path));
+            Some((name, path, stamp))
+        })
+        .collect();
+        Self { layers }
+    }
+
+    /// `agentsdb/layerChanged` notifications for the layers created, modified, or removed
+    /// since the previous poll.
+    fn poll(&mut self) -> Vec<Notification> {
+        let mut out = Vec::new();
+        for (name, path, stamp) in &mut self.layers {
+            let current = layer_stamp(Path::new(path.as_str()));
+            if current == *stamp {
+                continue;
+            }
+            *stamp = current;
+            out.push(Notification {
+                jsonrpc: "2.0",
+                method: LAYER_CHANGED_METHOD,
+                params: serde_json::json!({
+                    "layer": name,
+                    "path": path,
+                    "exists": current.is_some(),
+                }),
+            });
+        }
+        out
+    }
+}
+
+/// Polls the layer files and the config file until `stop` is disconnected. Layer changes are
+/// reported as `agentsdb/layerChanged`; a changed config file is reloaded (see
+/// [`LiveConfig::reload`]) and reported as `agentsdb/configReloaded`.
+fn watch_files(
+    live: &LiveConfig,
+    roots: &Roots,
+    stop: &std::sync::mpsc::Receiver<()>,
+    mut notify: impl FnMut(&Notification),
+) {
+    let mut layers = LayerWatcher::new(&live.current());
+    let mut config_stamp = live.file_stamp();
+    while let Err(std::sync::mpsc::RecvTimeoutError::Timeout) =
+        stop.recv_timeout(LAYER_POLL_INTERVAL)
+    {
+        let stamp = live.file_stamp();
+        if stamp != config_stamp {
+            config_stamp = stamp;
+            let params = match live.reload() {
+                Ok(config) => {
+                    roots.rebuild(&live.raw());
+                    layers = LayerWatcher::new(&config);
+                    serde_json::json!({
+                        "ok": true,
+                        "base": config.base,
+                        "user": config.user,
+                        "delta": config.delta,
+                        "local": config.local,
+                    })
+                }
+                Err(e) => serde_json::json!({ "ok": false, "error": format!("{e:#}") }),
+            };
+            notify(&Notification {
+                jsonrpc: "2.0",
+                method: CONFIG_RELOADED_METHOD,
+                params,
+            });
+        }
+        for change in layers.poll() {
+            notify(&change);
+        }
+    }
+}
+
+const CONFIG_FILE_NAME: &str = agentsdb_embeddings::settings::CONFIG_FILE_NAME;
+const CONFIG_RELOADED_METHOD: &str = "agentsdb/configReloaded";
+
+/// The `[layers]` section of `agentsdb.toml`; the other sections are validated (and
+/// unknown keys rejected) by `agentsdb_embeddings::settings::read_config_file`.
+#[derive(Debug, Default, Deserialize)]
+struct FileConfig {
+    #[serde(default)]
+    layers: FileLayers,
+}
+
+/// Layer paths; relative paths are resolved against the config file's directory.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileLayers {
+    base: Option<String>,
+    user: Option<String>,
+    delta: Option<String>,
+    local: Option<String>,
+}
+
+/// The configuration in effect: the startup configuration with the layer paths from the
+/// config file (if any) applied.
+struct LiveConfig {
+    startup: ServerConfig,
+    cwd: PathBuf,
+    file: Option<PathBuf>,
+    /// `(raw, resolved)`: layer paths before and after `normalize_config_with_cwd`.
+    state: std::sync::RwLock<(ServerConfig, ServerConfig)>,
+}
+
+impl LiveConfig {
+    fn load(startup: ServerConfig, cwd: PathBuf) -> anyhow::Result<Self> {
+        let file = match startup.config_file.as_deref() {
+            Some(path) => Some(cwd.join(expand_path_vars(path, &cwd)?)),
+            None => Some(cwd.join(CONFIG_FILE_NAME)).filter(|p| p.exists()),
+        };
+        let mut live = Self {
+            startup,
+            cwd,
+            file,
+            state: std::sync::RwLock::new(Default::default()),
+        };
+        let state = live.resolve()?;
+        live.state = std::sync::RwLock::new(state);
+        Ok(live)
+    }
+
+    fn resolve(&self) -> anyhow::Result<(ServerConfig, ServerConfig)> {
+        let mut raw = self.startup.clone();
+        if let Some(file) = self.file.as_deref().filter(|p| p.exists()) {
+            let doc = agentsdb_embeddings::settings::read_config_file(file)?;
+            let parsed: FileConfig =
+                serde_json::from_value(doc).with_context(|| format!("parse {}", file.display()))?;
+            let dir = file.parent().unwrap_or(&self.cwd);
+            for (slot, path) in [
+                (&mut raw.base, parsed.layers.base),
+                (&mut raw.user, parsed.layers.user),
+                (&mut raw.delta, parsed.layers.delta),
+                (&mut raw.local, parsed.layers.local),
+            ] {
+                if let Some(path) = path {
+                    let path = expand_path_vars(&path, dir)?;
+                    *slot = Some(dir.join(path).to_string_lossy().into_owned());
+                }
+            }
+        }
+        let mut resolved =
+            normalize_config_with_cwd(raw.clone(), &self.cwd).context("normalize layer paths")?;
+        // `https://` base and user layers are fetched into the layer cache and read from there.
+        let mut layers = LayerSet {
+            base: resolved.base.take(),
+            user: resolved.user.take(),
+            delta: resolved.delta.take(),
+            local: resolved.local.take(),
+        };
+        agentsdb_ops::remote::resolve_remote_layers(&mut layers).context("fetch remote layers")?;
+        resolved.base = layers.base;
+        resolved.user = layers.user;
+        resolved.delta = layers.delta;
+        resolved.local = layers.local;
+        Ok((raw, resolved))
+    }
+
+    fn current(&self) -> ServerConfig {
+        self.state
+            .read()
+            .map(|state| state.1.clone())
+            .unwrap_or_else(|e| e.into_inner().1.clone())
+    }
+
+    fn raw(&self) -> ServerConfig {
+        self.state
+            .read()
+            .map(|state| state.0.clone())
+            .unwrap_or_else(|e| e.into_inner().0.clone())
+    }
+
+    fn file_stamp(&self) -> LayerStamp {
+        self.file.as_deref().and_then(layer_stamp)
+    }
+
+    /// Re-reads the config file and switches to it if the new layers pass the same checks as
+    /// `agentsdb serve --check` (layers open, embedder resolves, layer profiles match).
+    /// On failure the previous configuration stays in effect.
+    fn reload(&self) -> anyhow::Result<ServerConfig> {
+        let (raw, resolved) = self.resolve()?;
+        let report = check(resolved.clone());
+        if let Some(failed) = report.steps.iter().find(|step| !step.ok) {
+            anyhow::bail!(
+                "{} failed: {}",
+                failed.step,
+                failed.error.as_deref().unwrap_or("unknown error")
+            );
+        }
+        if let Ok(mut state) = self.state.write() {
+            *state = (raw, resolved.clone());
+        }
+        Ok(resolved)
+    }
+}
+
+/// Performs the same initialization `serve_stdio` and the search tool rely on (resolve layer
+/// paths, open layer files, resolve the embedder, validate layer metadata) without serving.
+pub fn check(config: ServerConfig) -> CheckReport {
+    let mut report = CheckReport::new("mcp");
+
+    let Some(config) = report.step("normalize_config", || {
+        let cwd = std::env::current_dir().context("get current working directory")?;
+        let live = LiveConfig::load(config, cwd.clone())?;
+        let config = live.current();
+        let detail = serde_json::json!({
+            "config_file": live.file,
+            "cwd": cwd.to_string_lossy(),
+            "base": config.base,
+            "user": config.user,
+            "delta": config.delta,
+            "local": config.local,
+            "search": {
+                "k": config.search_k,
+                "layers": config.search_layers,
+                "kinds": config.search_kinds,
+                "min_confidence": config.min_confidence,
+            },
+        });
+        Ok((config, detail))
+    }) else {
+        return report;
+    };
+
+    let Some(opened) = report.step("open_layers", || check_open_layers(&config)) else {
+        return report;
+    };
+
+    let Some(embedder) = report.step("resolve_embedder", || {
+        let dim = opened[0].1.embedding_dim();
+        let find = |want: LayerId| opened.iter().find(|(id, _)| *id == want).map(|(_, f)| f);
+        let options = roll_up_embedding_options(&[
+            find(LayerId::Local),
+            find(LayerId::User),
+            find(LayerId::Delta),
+            find(LayerId::Base),
+        ])
+        .context("roll up options")?;
+        if let Some(cfg_dim) = options.dim {
+            if cfg_dim != dim {
+                anyhow::bail!(
+                    "embedding dim mismatch (layers are dim={dim}, options specify dim={cfg_dim})"
+                );
+            }
+        }
+        let cache_enabled = options.cache_enabled;
+        let embedder = options
+            .into_embedder(dim)
+            .context("resolve embedder from options")?;
+        let detail = serde_json::json!({
+            "profile": embedder.profile(),
+            "cache_enabled": cache_enabled,
+        });
+        Ok((embedder, detail))
+    }) else {
+        return report;
+    };
+
+    report.step("validate_layer_metadata", || {
+        for (layer_id, file) in &opened {
+            let name = layer_id_name(*layer_id);
+            ensure_layer_metadata_compatible_with_embedder(file, embedder.as_ref())
+                .with_context(|| format!("validate {name} layer metadata vs embedder"))?;
+        }
+        Ok(((), Value::Null))
+    });
+
+    report
+}
+
+/// Opens the configured layers the way `agents_search` does (missing optional layers are
+/// skipped, a missing base is an error) and describes each one.
+fn check_open_layers(
+    config: &ServerConfig,
+) -> anyhow::Result<(Vec<(LayerId, agentsdb_format::LayerFile)>, Value)> {
+    let mut layers = LayerSet {
+        base: config.base.clone(),
+        user: config.user.clone(),
+        delta: config.delta.clone(),
+        local: config.local.clone(),
+    };
+    let mut rows = Vec::new();
+    for (name, slot) in [
+        ("local", &mut layers.local),
+        ("user", &mut layers.user),
+        ("delta", &mut layers.delta),
+        ("base", &mut layers.base),
+    ] {
+        let status = match slot.as_deref() {
+            None => "unconfigured",
+            Some(p) if Path::new(p).exists() => "present",
+            Some(p) if name == "base" => anyhow::bail!(
+                "base layer not found at {p:?} (configure an absolute path, or run the server with CWD set to your project root)"
+            ),
+            Some(_) => {
+                *slot = None;
+                "missing"
+            }
+        };
+        rows.push((name, status));
+    }
+    let opened = layers.open().context("open layers")?;
+    if opened.is_empty() {
+        anyhow::bail!("no layers configured");
+    }
+    let detail = rows
+        .into_iter()
+        .map(|(name, status)| {
+            let file = opened
+                .iter()
+                .find(|(id, _)| layer_id_name(*id) == name)
+                .map(|(_, f)| f);
+            serde_json::json!({
+                "layer": name,
+                "status": status,
+                "chunk_count": file.map(|f| f.chunk_count),
+                "embedding_dim": file.map(agentsdb_format::LayerFile::embedding_dim),
+                "file_length_bytes": file.map(|f| f.header.file_length_bytes),
+            })
+        })
+        .collect();
+    Ok((opened, Value::Array(detail)))
+}
+
+const fn layer_id_name(id: LayerId) -> &'static str {
+    match id {
+        LayerId::Base => "base",
+        LayerId::User => "user",
+        LayerId::Delta => "delta",
+        LayerId::Local => "local",
+        LayerId::Session => "session",
+    }
+}
+
+fn handle_request(config: &ServerConfig, req: &Request) -> Result<Value, RpcError> {
+    config.policy.check(&req.method, &req.params)?;
+    match req.method.as_str() {
+        // MCP/JSON-RPC handshake
+        "initialize" => Ok(handle_initialize(req.params.clone())),
+        "tools/list" => Ok(handle_tools_list(config)),
+        "tools/call" => {
+            let params: ToolCallParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_tools_call(config, params)
+        }
+        "resources/list" => Ok(serde_json::json!({ "resources": [] })),
+        "prompts/list" => Ok(handle_prompts_list()),
+        "prompts/get" => {
+            let params: PromptGetParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_prompts_get(config, &params)
+        }
+        "ping" => Ok(serde_json::json!({})),
+        "shutdown" => Ok(Value::Null),
+
+        // Allow calling these as raw methods, in addition to `tools/call`.
+        TOOL_AGENTS_SEARCH | TOOL_AGENTS_SEARCH_LEGACY => {
+            let params: SearchParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_search(config, params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_CONTEXT_WRITE | TOOL_AGENTS_CONTEXT_WRITE_LEGACY => {
+            let params: WriteParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_write(config, params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_CONTEXT_PROPOSE | TOOL_AGENTS_CONTEXT_PROPOSE_LEGACY => {
+            let params: ProposeParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_propose(config, params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_STATUS => handle_status(config).map_err(|e| RpcError::tool_failure(&e)),
+        TOOL_AGENTS_CONTEXT_GET => {
+            let params: GetParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_get(config, params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_CONTEXT_LIST => {
+            let params: ListParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_list(config, params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_CONTEXT_RETRACT => {
+            let params: RetractParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_retract(config, params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_CONTEXT_UPDATE => {
+            let params: UpdateParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_update(config, params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_REFLECT => {
+            let params: ReflectParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_reflect(config, params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_PROPOSALS_LIST => {
+            let params: ProposalsListParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_proposals_list(config, &params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_PROPOSALS_ACCEPT | TOOL_AGENTS_PROPOSALS_REJECT => {
+            let params: ProposalsDecideParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_proposals_decide(config, &req.method, &params)
+        }
+        TOOL_AGENTS_SESSION_NOTE => {
+            let params: SessionNoteParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_session_note(config, params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_SESSION_END => {
+            let params: SessionEndParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_session_end(config, &params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        TOOL_AGENTS_WORKSPACE_SEARCH if config.workspace => {
+            let params: WorkspaceSearchParams = serde_json::from_value(req.params.clone())
+                .map_err(|e| RpcError::invalid_params(format!("parse params: {e}")))?;
+            handle_workspace_search(params).map_err(|e| RpcError::tool_failure(&e))
+        }
+        other => Err(RpcError::method_not_found(format!(
+            "unknown method: {other}"
+        ))),
+    }
+}
+
+fn handle_initialize(_params: Value) -> Value {
+    // Minimal MCP initialize response; clients use this to verify transport and discover tools.
     serde_json::json!({
         "protocolVersion": "2024-11-05",
         "serverInfo": {
@@ -373,9 +1928,9 @@ fn handle_initialize(_params: Value) -> Value {
     })
 }
 
-fn handle_tools_list() -> Value {
+fn handle_tools_list(config: &ServerConfig) -> Value {
     // Tool schemas are intentionally minimal; the server validates params at runtime.
-    serde_json::json!({
+    let mut list = serde_json::json!({
         "tools": [
             {
                 "name": TOOL_AGENTS_SEARCH,
@@ -388,16 +1943,28 @@ fn handle_tools_list() -> Value {
                         "k": { "type": "integer", "minimum": 1 },
                         "filters": {
                             "type": "object",
-                            "properties": { "kind": { "type": "array", "items": { "type": "string" } } }
+                            "properties": {
+                                "kind": { "type": "array", "items": { "type": "string" } },
+                                "namespace": { "type": "string", "description": "Only chunks in this namespace (e.g. one package of a monorepo)." },
+                                "language": { "type": "string", "description": "Only chunks in this language, as a language tag (`de` also matches `de-AT`)." }
+                            }
+                        },
+                        "layers": { "type": "array", "items": { "type": "string" } },
+                        "include_sources_content": {
+                            "type": "boolean",
+                            "description": "Inline the content of each result's source chunks (one hop) as `source_chunks`."
                         },
-                        "layers": { "type": "array", "items": { "type": "string" } }
+                        "content_blocks": {
+                            "type": "boolean",
+                            "description": "Return one embedded `agentsdb://chunk/<layer>/<id>` resource per hit, with the rest of the hit in `_meta`, instead of a single JSON text block."
+                        }
                     },
                     "required": ["query"]
                 }
             },
             {
                 "name": TOOL_AGENTS_CONTEXT_WRITE,
-                "description": "Append a new chunk to the local or delta knowledge base layer.",
+                "description": "Append a new chunk to the local or delta knowledge base layer. Content longer than about 512 tokens is split into linked parts (each with a `part-of:<first id>#<n>/<count>` source); `parts` lists their ids.",
                 "inputSchema": {
                     "type": "object",
                     "properties": {
@@ -413,7 +1980,9 @@ fn handle_tools_list() -> Value {
                                 ]
                             }
                         },
-                        "scope": { "type": "string", "enum": ["local", "delta"] }
+                        "scope": { "type": "string", "enum": ["local", "delta"] },
+                        "namespace": { "type": "string", "description": "Namespace to place the chunk in (e.g. one package of a monorepo)." },
+                        "language": { "type": "string", "description": "Language tag of the content (e.g. `en`, `de-AT`), or `auto` to detect it." }
                     },
                     "required": ["content", "kind", "confidence", "scope"]
                 }
@@ -435,25 +2004,371 @@ fn handle_tools_list() -> Value {
                 }
             }
         ]
+    });
+    if let Some(tools) = list["tools"].as_array_mut() {
+        tools.extend(context_tools());
+        if config.session.is_some() {
+            tools.extend(session_tools());
+        }
+        tools.push(serde_json::json!({
+            "name": TOOL_AGENTS_PROPOSALS_LIST,
+            "description": "List pending promotion proposals (or all of them) with the proposed chunk content, for review.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "all": { "type": "boolean" }
+                }
+            }
+        }));
+        if config.allow_proposal_review {
+            tools.extend(proposal_review_tools());
+        }
+        if config.workspace {
+            tools.push(workspace_search_tool());
+        }
+        tools.push(status_tool());
+        apply_tool_policy(&config.policy, tools);
+        match kind_taxonomy(config) {
+            Ok(taxonomy) => apply_kind_taxonomy(&taxonomy, tools),
+            Err(err) => tracing::warn!("kind taxonomy unavailable: {err:#}"),
+        }
+        // Workspace searches span every registered project, not one client root.
+        for tool in tools
+            .iter_mut()
+            .filter(|t| t["name"] != TOOL_AGENTS_WORKSPACE_SEARCH)
+        {
+            if let Some(props) = tool
+                .pointer_mut("/inputSchema/properties")
+                .and_then(Value::as_object_mut)
+            {
+                props.insert(
+                    "root".to_string(),
+                    serde_json::json!({
+                        "type": "string",
+                        "description": "Workspace root (name or path) whose layers to use; defaults to the first root."
+                    }),
+                );
+            }
+        }
+    }
+    list
+}
+
+/// Drops tools the policy disallows and narrows write scopes to the allowed ones.
+fn apply_tool_policy(policy: &ToolPolicy, tools: &mut Vec<Value>) {
+    tools.retain(|tool| policy.allows_tool(tool["name"].as_str().unwrap_or_default()));
+    for tool in tools {
+        if let Some(scopes) = tool
+            .pointer_mut("/inputSchema/properties/scope/enum")
+            .and_then(Value::as_array_mut)
+        {
+            scopes.retain(|s| policy.allows_scope(s.as_str().unwrap_or_default()));
+        }
+    }
+}
+
+/// Describes the taxonomy's kinds on the `kind` parameter of the write tools, and restricts it
+/// to them with an `enum` when the taxonomy is enforced.
+fn apply_kind_taxonomy(taxonomy: &KindTaxonomy, tools: &mut [Value]) {
+    if taxonomy.kinds.is_empty() {
+        return;
+    }
+    let listed: Vec<String> = taxonomy
+        .kinds
+        .iter()
+        .map(|k| {
+            if k.description.is_empty() {
+                k.name.clone()
+            } else {
+                format!("{} ({})", k.name, k.description)
+            }
+        })
+        .collect();
+    let description = format!("One of the project's kinds: {}.", listed.join("; "));
+    let names: Vec<&str> = taxonomy.kinds.iter().map(|k| k.name.as_str()).collect();
+    for tool in tools {
+        if !matches!(
+            tool["name"].as_str(),
+            Some(TOOL_AGENTS_CONTEXT_WRITE | TOOL_AGENTS_CONTEXT_UPDATE)
+        ) {
+            continue;
+        }
+        let Some(kind) = tool.pointer_mut("/inputSchema/properties/kind") else {
+            continue;
+        };
+        kind["description"] = Value::from(description.as_str());
+        if taxonomy.validation == KindValidation::Enforce {
+            kind["enum"] = serde_json::json!(names);
+        }
+    }
+}
+
+/// Search across all projects in the workspace registry (see [`ServerConfig::workspace`]).
+fn workspace_search_tool() -> Value {
+    serde_json::json!({
+        "name": TOOL_AGENTS_WORKSPACE_SEARCH,
+        "description": "Search the knowledge bases of every project registered with `agentsdb workspace add`. Each result names the project it came from.",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "query_vec": { "type": "array", "items": { "type": "number" } },
+                "k": { "type": "integer", "minimum": 1 },
+                "filters": {
+                    "type": "object",
+                    "properties": {
+                        "kind": { "type": "array", "items": { "type": "string" } },
+                        "namespace": { "type": "string" },
+                        "language": { "type": "string" }
+                    }
+                }
+            },
+            "required": ["query"]
+        }
     })
 }
 
+/// Health of the memory stack, see [`handle_status`].
+fn status_tool() -> Value {
+    serde_json::json!({
+        "name": TOOL_AGENTS_STATUS,
+        "description": "Describe the knowledge base: each layer's path, chunk count, newest chunk and last modification, whether its search index is fresh, the embedder profile and the number of pending proposals.",
+        "inputSchema": { "type": "object", "properties": {} }
+    })
+}
+
+/// Tools for the ephemeral per-session layer.
+fn session_tools() -> [Value; 2] {
+    [
+        serde_json::json!({
+            "name": TOOL_AGENTS_SESSION_NOTE,
+            "description": "Jot down working memory for this session. Session notes are searched ahead of all other layers and are dropped when the session ends unless promoted.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "content": { "type": "string" },
+                    "kind": { "type": "string" },
+                    "confidence": { "type": "number" },
+                    "sources": {
+                        "type": "array",
+                        "items": {
+                            "oneOf": [
+                                { "type": "string" },
+                                { "type": "object", "properties": { "chunk_id": { "type": "integer" } }, "required": ["chunk_id"] }
+                            ]
+                        }
+                    }
+                },
+                "required": ["content"]
+            }
+        }),
+        serde_json::json!({
+            "name": TOOL_AGENTS_SESSION_END,
+            "description": "End the session: promote the session notes into the local layer, or discard them.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["promote", "discard"] }
+                },
+                "required": ["action"]
+            }
+        }),
+    ]
+}
+
+/// Tools for reading and correcting individual chunks.
+fn context_tools() -> [Value; 5] {
+    [
+        serde_json::json!({
+            "name": TOOL_AGENTS_CONTEXT_GET,
+            "description": "Fetch a chunk by id from the highest-precedence layer that has it, including its sources.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "minimum": 1 },
+                    "layers": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["id"]
+            }
+        }),
+        serde_json::json!({
+            "name": TOOL_AGENTS_CONTEXT_LIST,
+            "description": "List chunks, most recent first, optionally filtered by kind, namespace, layer and creation time.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "kind": { "type": "array", "items": { "type": "string" } },
+                    "namespace": { "type": "string" },
+                    "layers": { "type": "array", "items": { "type": "string" } },
+                    "since_unix_ms": { "type": "integer", "minimum": 0 },
+                    "until_unix_ms": { "type": "integer", "minimum": 0 },
+                    "offset": { "type": "integer", "minimum": 0 },
+                    "limit": { "type": "integer", "minimum": 1 }
+                }
+            }
+        }),
+        serde_json::json!({
+            "name": TOOL_AGENTS_CONTEXT_RETRACT,
+            "description": "Retract a chunk that is wrong or stale by appending a tombstone with a reason to the local or delta layer; it stops appearing in search and list results.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "minimum": 1 },
+                    "reason": { "type": "string" },
+                    "scope": { "type": "string", "enum": ["local", "delta"] }
+                },
+                "required": ["id", "reason", "scope"]
+            }
+        }),
+        serde_json::json!({
+            "name": TOOL_AGENTS_CONTEXT_UPDATE,
+            "description": "Correct a chunk: write a revised chunk that supersedes it and tombstone the original in the same local or delta layer, in one atomic write.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "minimum": 1 },
+                    "content": { "type": "string" },
+                    "kind": { "type": "string" },
+                    "confidence": { "type": "number" },
+                    "reason": { "type": "string" },
+                    "scope": { "type": "string", "enum": ["local", "delta"] }
+                },
+                "required": ["id", "content", "scope"]
+            }
+        }),
+        serde_json::json!({
+            "name": TOOL_AGENTS_REFLECT,
+            "description": "Store what was learned in a session in one call. The markdown `summary` is written as a note; each list item or paragraph under a `Decisions`, `Gotchas` or `Preferences` heading becomes its own decision, gotcha or preference chunk citing the summary. Returns every id written.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "summary": { "type": "string" },
+                    "items": {
+                        "type": "array",
+                        "description": "Further learnings to store next to the ones found in the summary.",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "kind": { "type": "string" },
+                                "content": { "type": "string" },
+                                "confidence": { "type": "number" }
+                            },
+                            "required": ["kind", "content"]
+                        }
+                    },
+                    "sources": { "type": "array", "items": { "type": "string" } },
+                    "scope": { "type": "string", "enum": ["local", "delta"] },
+                    "namespace": { "type": "string" }
+                },
+                "required": ["summary", "scope"]
+            }
+        }),
+    ]
+}
+
+fn proposal_review_tools() -> [Value; 2] {
+    [
+        serde_json::json!({
+            "name": TOOL_AGENTS_PROPOSALS_ACCEPT,
+            "description": "Accept pending proposals, promoting their chunks into the target layer.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "ids": { "type": "array", "items": { "type": "integer" }, "minItems": 1 }
+                },
+                "required": ["ids"]
+            }
+        }),
+        serde_json::json!({
+            "name": TOOL_AGENTS_PROPOSALS_REJECT,
+            "description": "Reject pending proposals without promoting them.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "ids": { "type": "array", "items": { "type": "integer" }, "minItems": 1 },
+                    "reason": { "type": "string" }
+                },
+                "required": ["ids"]
+            }
+        }),
+    ]
+}
+
 fn handle_tools_call(config: &ServerConfig, params: ToolCallParams) -> Result<Value, RpcError> {
+    config.policy.check(&params.name, &params.arguments)?;
     let result = match params.name.as_str() {
         TOOL_AGENTS_SEARCH | TOOL_AGENTS_SEARCH_LEGACY => {
             let args: SearchParams = serde_json::from_value(params.arguments)
                 .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
-            handle_search(config, args).map_err(|e| RpcError::internal_error(format!("{e:#}")))?
+            let content_blocks = args.content_blocks;
+            let results = handle_search(config, args).map_err(|e| RpcError::tool_failure(&e))?;
+            if content_blocks {
+                let content =
+                    search_content_blocks(results).map_err(|e| RpcError::tool_failure(&e))?;
+                return Ok(serde_json::json!({ "content": content }));
+            }
+            results
+        }
+        TOOL_AGENTS_CONTEXT_WRITE | TOOL_AGENTS_CONTEXT_WRITE_LEGACY => {
+            let args: WriteParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_write(config, args).map_err(|e| RpcError::tool_failure(&e))?
+        }
+        TOOL_AGENTS_CONTEXT_PROPOSE | TOOL_AGENTS_CONTEXT_PROPOSE_LEGACY => {
+            let args: ProposeParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_propose(config, args).map_err(|e| RpcError::tool_failure(&e))?
+        }
+        TOOL_AGENTS_STATUS => handle_status(config).map_err(|e| RpcError::tool_failure(&e))?,
+        TOOL_AGENTS_CONTEXT_GET => {
+            let args: GetParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_get(config, args).map_err(|e| RpcError::tool_failure(&e))?
+        }
+        TOOL_AGENTS_CONTEXT_LIST => {
+            let args: ListParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_list(config, args).map_err(|e| RpcError::tool_failure(&e))?
+        }
+        TOOL_AGENTS_CONTEXT_RETRACT => {
+            let args: RetractParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_retract(config, args).map_err(|e| RpcError::tool_failure(&e))?
+        }
+        TOOL_AGENTS_CONTEXT_UPDATE => {
+            let args: UpdateParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_update(config, args).map_err(|e| RpcError::tool_failure(&e))?
+        }
+        TOOL_AGENTS_REFLECT => {
+            let args: ReflectParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_reflect(config, args).map_err(|e| RpcError::tool_failure(&e))?
+        }
+        TOOL_AGENTS_PROPOSALS_LIST => {
+            let args: ProposalsListParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_proposals_list(config, &args).map_err(|e| RpcError::tool_failure(&e))?
+        }
+        TOOL_AGENTS_PROPOSALS_ACCEPT | TOOL_AGENTS_PROPOSALS_REJECT => {
+            let args: ProposalsDecideParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_proposals_decide(config, &params.name, &args)?
         }
-        TOOL_AGENTS_CONTEXT_WRITE | TOOL_AGENTS_CONTEXT_WRITE_LEGACY => {
-            let args: WriteParams = serde_json::from_value(params.arguments)
+        TOOL_AGENTS_SESSION_NOTE => {
+            let args: SessionNoteParams = serde_json::from_value(params.arguments)
                 .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
-            handle_write(config, args).map_err(|e| RpcError::internal_error(format!("{e:#}")))?
+            handle_session_note(config, args).map_err(|e| RpcError::tool_failure(&e))?
         }
-        TOOL_AGENTS_CONTEXT_PROPOSE | TOOL_AGENTS_CONTEXT_PROPOSE_LEGACY => {
-            let args: ProposeParams = serde_json::from_value(params.arguments)
+        TOOL_AGENTS_SESSION_END => {
+            let args: SessionEndParams = serde_json::from_value(params.arguments)
                 .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
-            handle_propose(config, args).map_err(|e| RpcError::internal_error(format!("{e:#}")))?
+            handle_session_end(config, &args).map_err(|e| RpcError::tool_failure(&e))?
+        }
+        TOOL_AGENTS_WORKSPACE_SEARCH if config.workspace => {
+            let args: WorkspaceSearchParams = serde_json::from_value(params.arguments)
+                .map_err(|e| RpcError::invalid_params(format!("parse arguments: {e}")))?;
+            handle_workspace_search(args).map_err(|e| RpcError::tool_failure(&e))?
         }
         other => return Err(RpcError::method_not_found(format!("unknown tool: {other}"))),
     };
@@ -468,24 +2383,321 @@ fn handle_tools_call(config: &ServerConfig, params: ToolCallParams) -> Result<Va
     }))
 }
 
+fn handle_prompts_list() -> Value {
+    serde_json::json!({
+        "prompts": [
+            {
+                "name": PROMPT_AGENTS_RECALL,
+                "description": "Recall relevant project context for a task, pre-filled with search results from the knowledge base.",
+                "arguments": [
+                    { "name": "task", "description": "What you are about to work on.", "required": true },
+                    { "name": "k", "description": "Maximum number of chunks to include (default 8).", "required": false }
+                ]
+            },
+            {
+                "name": PROMPT_AGENTS_REMEMBER,
+                "description": "Summarize the learnings from this session and store them with agents_context_write.",
+                "arguments": [
+                    { "name": "topic", "description": "What the session was about; used to show already-stored context so it is not duplicated.", "required": false },
+                    { "name": "scope", "description": "Layer to write to: local (default) or delta.", "required": false }
+                ]
+            }
+        ]
+    })
+}
+
+fn handle_prompts_get(config: &ServerConfig, params: &PromptGetParams) -> Result<Value, RpcError> {
+    let k = match params.arguments.get("k") {
+        Some(v) => v
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|k| *k > 0)
+            .ok_or_else(|| {
+                RpcError::invalid_params(format!("k must be a positive integer, got {v:?}"))
+            })?,
+        None => PROMPT_DEFAULT_K,
+    };
+    let search = |query: &str| {
+        run_search(
+            config,
+            SearchParams {
+                query: query.to_string(),
+                query_vec: None,
+                k: Some(k),
+                filters: None,
+                layers: None,
+                include_sources_content: false,
+                content_blocks: false,
+            },
+        )
+        .map_err(|e| RpcError::tool_failure(&e))
+    };
+
+    let (description, text) = match params.name.as_str() {
+        PROMPT_AGENTS_RECALL => {
+            let task = params
+                .arguments
+                .get("task")
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .ok_or_else(|| RpcError::invalid_params("missing required argument: task"))?;
+            let results = search(task)?;
+            let text = format!(
+                "I am about to work on the following task:\n\n{task}\n\n\
+                 Relevant context from the project knowledge base (AGENTS.db), most relevant first:\n\n{}\n\
+                 Use this context while working. If it is not enough, call `{TOOL_AGENTS_SEARCH}` with a more specific query.",
+                format_prompt_results(&results)
+            );
+            ("Project context recalled for the task", text)
+        }
+        PROMPT_AGENTS_REMEMBER => {
+            let scope = params.arguments.get("scope").map_or("local", |s| s.trim());
+            if scope != "local" && scope != "delta" {
+                return Err(RpcError::invalid_params("scope must be 'local' or 'delta'"));
+            }
+            let existing = match params
+                .arguments
+                .get("topic")
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+            {
+                Some(topic) => format!(
+                    "\n\nContext already stored about \"{topic}\" (do not store it again):\n\n{}",
+                    format_prompt_results(&search(topic)?)
+                ),
+                None => String::new(),
+            };
+            let text = format!(
+                "Summarize what was learned in this session that would help a future agent working on this project: \
+                 decisions made, conventions discovered, pitfalls, and useful commands or file locations.\n\n\
+                 Store each learning as a separate, self-contained chunk by calling `{TOOL_AGENTS_CONTEXT_WRITE}` with \
+                 `scope` \"{scope}\", a short `kind` (for example \"note\", \"decision\" or \"pitfall\"), and a `confidence` \
+                 between 0 and 1. Skip anything temporary or specific to this session only.{existing}"
+            );
+            ("Summarize and store session learnings", text)
+        }
+        other => {
+            return Err(RpcError::invalid_params(format!("unknown prompt: {other}")));
+        }
+    };
+
+    Ok(serde_json::json!({
+        "description": description,
+        "messages": [
+            {
+                "role": "user",
+                "content": { "type": "text", "text": text }
+            }
+        ]
+    }))
+}
+
+fn format_prompt_results(results: &[agentsdb_core::types::SearchResult]) -> String {
+    if results.is_empty() {
+        return "(no matching context found)\n".to_string();
+    }
+    let mut out = String::new();
+    for r in results {
+        out.push_str(&format!(
+            "- [{} #{} {}, confidence {:.2}] {}\n",
+            layer_id_name(r.layer),
+            r.chunk.id.get(),
+            r.chunk.kind,
+            r.chunk.confidence,
+            r.chunk.content.trim()
+        ));
+    }
+    out
+}
+
 fn handle_search(config: &ServerConfig, params: SearchParams) -> anyhow::Result<Value> {
+    let include_sources_content = params.include_sources_content;
+    let started = std::time::Instant::now();
+    let results = run_search(config, params)?;
+    agentsdb_ops::metrics::global().observe(
+        "agentsdb_search_duration_seconds",
+        "Time spent in semantic searches.",
+        &[("server", "mcp")],
+        started.elapsed().as_secs_f64(),
+    );
+    if !include_sources_content {
+        return Ok(serde_json::to_value(results)?);
+    }
+
+    let wanted: std::collections::BTreeSet<u32> = results
+        .iter()
+        .flat_map(|r| source_chunk_ids(&r.chunk))
+        .collect();
+    let sources = resolve_source_chunks(config, &wanted)?;
+    let mut out = Vec::with_capacity(results.len());
+    for r in results {
+        let source_chunks: Vec<Value> = source_chunk_ids(&r.chunk)
+            .map(|id| match sources.get(&id) {
+                Some((layer, chunk)) => serde_json::json!({
+                    "chunk_id": id,
+                    "layer": layer,
+                    "kind": chunk.kind,
+                    "content": chunk.content,
+                }),
+                None => serde_json::json!({ "chunk_id": id, "missing": true }),
+            })
+            .collect();
+        let mut v = serde_json::to_value(r)?;
+        if let Some(obj) = v.as_object_mut() {
+            obj.insert("source_chunks".to_string(), Value::Array(source_chunks));
+        }
+        out.push(v);
+    }
+    Ok(Value::Array(out))
+}
+
+/// Searches every project in the workspace registry; each result carries its `project` and
+/// `root`, and projects that could not be searched are listed under `errors`.
+fn handle_workspace_search(params: WorkspaceSearchParams) -> anyhow::Result<Value> {
     if params.query.trim().is_empty() {
         anyhow::bail!("query must be non-empty");
     }
+    let registry = agentsdb_ops::workspace::registry_path()?;
+    let workspace = agentsdb_ops::workspace::Workspace::load(&registry)?;
+    let filters = params.filters;
+    let config = agentsdb_ops::SearchConfig {
+        query: Some(params.query),
+        query_vec: params.query_vec,
+        k: params.k.unwrap_or(10),
+        kinds: filters.as_ref().map(|f| f.kind.clone()).unwrap_or_default(),
+        namespace: filters.as_ref().and_then(|f| f.namespace.clone()),
+        language: filters.and_then(|f| f.language),
+        use_index: false,
+        mode: agentsdb_query::SearchMode::Hybrid,
+        cache_results: false,
+        disk_cache: false,
+    };
+    let found = agentsdb_ops::workspace::search_workspace(&workspace, &config);
+    let mut results = Vec::with_capacity(found.hits.len());
+    for hit in found.hits {
+        let mut v = serde_json::to_value(hit.result)?;
+        if let Some(obj) = v.as_object_mut() {
+            obj.insert("project".to_string(), Value::String(hit.project));
+            obj.insert(
+                "root".to_string(),
+                Value::String(hit.root.display().to_string()),
+            );
+        }
+        results.push(v);
+    }
+    Ok(serde_json::json!({ "results": results, "errors": found.errors }))
+}
 
-    let filters = SearchFilters {
-        kinds: params.filters.map(|f| f.kind).unwrap_or_default(),
+/// `agents_search` results as MCP content: one embedded `agentsdb://chunk/<layer>/<id>`
+/// resource per hit, holding the chunk content as text and the rest of the hit (score, kind,
+/// hidden layers, source chunks) in `_meta`, so clients can render hits natively.
+fn search_content_blocks(results: Value) -> anyhow::Result<Vec<Value>> {
+    let Value::Array(hits) = results else {
+        anyhow::bail!("search results must be an array");
     };
-    let k = params.k.unwrap_or(10);
+    if hits.is_empty() {
+        return Ok(vec![
+            serde_json::json!({ "type": "text", "text": "No results." }),
+        ]);
+    }
+    hits.into_iter()
+        .map(|mut hit| {
+            let layer: LayerId =
+                serde_json::from_value(hit["layer"].clone()).context("hit layer")?;
+            let id = hit["chunk"]["id"].as_u64().context("hit chunk id")?;
+            let content = hit["chunk"]
+                .as_object_mut()
+                .and_then(|chunk| chunk.remove("content"))
+                .unwrap_or_default();
+            Ok(serde_json::json!({
+                "type": "resource",
+                "resource": {
+                    "uri": format!("agentsdb://chunk/{}/{id}", layer_id_name(layer)),
+                    "mimeType": "text/plain",
+                    "text": content,
+                },
+                "_meta": hit,
+            }))
+        })
+        .collect()
+}
+
+fn source_chunk_ids(chunk: &agentsdb_core::types::Chunk) -> impl Iterator<Item = u32> + '_ {
+    chunk.sources.iter().filter_map(|s| match s {
+        agentsdb_core::types::ProvenanceRef::ChunkId(id) => Some(id.get()),
+        agentsdb_core::types::ProvenanceRef::SourceString(_) => None,
+    })
+}
+
+/// Looks up the chunks with the given ids across all configured layers, using the same
+/// precedence as `agents_context_get` (the highest-precedence layer wins).
+fn resolve_source_chunks(
+    config: &ServerConfig,
+    ids: &std::collections::BTreeSet<u32>,
+) -> anyhow::Result<std::collections::HashMap<u32, (LayerId, agentsdb_core::types::Chunk)>> {
+    let mut found = std::collections::HashMap::new();
+    if ids.is_empty() {
+        return Ok(found);
+    }
+    let opened = select_layers(config, None)?.open().context("open layers")?;
+    for (layer_id, file) in &opened {
+        let mut last = std::collections::HashMap::new();
+        for chunk in file.chunks() {
+            let chunk = chunk.context("read chunk")?;
+            if ids.contains(&chunk.id) && !found.contains_key(&chunk.id) {
+                last.insert(chunk.id, chunk);
+            }
+        }
+        for (id, chunk) in last {
+            found.insert(id, (*layer_id, chunk_from_view(file, &chunk)?));
+        }
+    }
+    Ok(found)
+}
+
+/// Layers chosen for a read: the configured layers plus the session layer, if it has notes.
+struct SelectedLayers {
+    set: LayerSet,
+    session: Option<String>,
+    trust: agentsdb_embeddings::signing::TrustPolicy,
+}
+
+impl SelectedLayers {
+    fn open(&self) -> anyhow::Result<Vec<(LayerId, agentsdb_format::LayerFile)>> {
+        let pool = LayerPool::global();
+        let mut opened = self.set.open_trusted(pool, &self.trust)?;
+        if let Some(session) = self.session.as_deref() {
+            let file = pool.get(session).context("open session layer")?;
+            opened.insert(0, (LayerId::Session, file));
+            agentsdb_query::validate_schema_compatible(&opened)?;
+        }
+        Ok(opened)
+    }
+}
 
-    // Select configured layer paths; `params.layers` filters by layer id.
+/// Configured layer paths to read, restricted to `selected` layer ids and to files that exist.
+fn select_layers(
+    config: &ServerConfig,
+    selected: Option<Vec<String>>,
+) -> anyhow::Result<SelectedLayers> {
+    // `selected` filters by layer id.
+    let session = config
+        .session
+        .clone()
+        .filter(|p| Path::new(p).exists())
+        .filter(|_| {
+            selected
+                .as_ref()
+                .is_none_or(|s| s.iter().any(|v| v == "session"))
+        });
     let mut layers = LayerSet {
         base: config.base.clone(),
         user: config.user.clone(),
         delta: config.delta.clone(),
         local: config.local.clone(),
     };
-    if let Some(selected) = params.layers {
+    if let Some(selected) = selected {
         let keep = |name: &str| selected.iter().any(|v| v == name);
         if !keep("base") {
             layers.base = None;
@@ -504,9 +2716,12 @@ fn handle_search(config: &ServerConfig, params: SearchParams) -> anyhow::Result<
     // Treat missing optional layers as absent. Base is expected to exist if configured.
     if let Some(base) = layers.base.as_deref() {
         if !Path::new(base).exists() {
-            anyhow::bail!(
-                "base layer not found at {base:?} (configure an absolute path, or run the server with CWD set to your project root)"
-            );
+            return Err(ToolError::layer_missing(
+                format!("base layer not found at {base:?} (configure an absolute path, or run the server with CWD set to your project root)"),
+                "base",
+                Some(base),
+            )
+            .into());
         }
     }
     if let Some(user) = layers.user.as_deref() {
@@ -525,6 +2740,53 @@ fn handle_search(config: &ServerConfig, params: SearchParams) -> anyhow::Result<
         }
     }
 
+    Ok(SelectedLayers {
+        set: layers,
+        session,
+        trust: config.trust.clone(),
+    })
+}
+
+fn run_search(
+    config: &ServerConfig,
+    params: SearchParams,
+) -> anyhow::Result<Vec<agentsdb_core::types::SearchResult>> {
+    if params.query.trim().is_empty() {
+        anyhow::bail!("query must be non-empty");
+    }
+
+    let mut filters = params
+        .filters
+        .map(|f| SearchFilters {
+            kinds: f.kind,
+            namespace: f.namespace,
+            language: f.language,
+            min_confidence: None,
+        })
+        .unwrap_or_default();
+    filters.min_confidence = config.min_confidence;
+    if !config.search_kinds.is_empty() {
+        if filters.kinds.is_empty() {
+            filters.kinds.clone_from(&config.search_kinds);
+        } else {
+            filters
+                .kinds
+                .retain(|kind| config.search_kinds.contains(kind));
+            if filters.kinds.is_empty() {
+                // None of the requested kinds may be searched here.
+                return Ok(Vec::new());
+            }
+        }
+    }
+    let k = params.k.unwrap_or(match config.search_k {
+        0 => 10,
+        k => k,
+    });
+    let selected = params
+        .layers
+        .or_else(|| (!config.search_layers.is_empty()).then(|| config.search_layers.clone()));
+
+    let layers = select_layers(config, selected)?;
     let opened = layers.open().context("open layers")?;
     if opened.is_empty() {
         anyhow::bail!("no layers configured");
@@ -540,15 +2802,22 @@ fn handle_search(config: &ServerConfig, params: SearchParams) -> anyhow::Result<
             LayerId::User => user = Some(file),
             LayerId::Delta => delta = Some(file),
             LayerId::Base => base = Some(file),
+            // Session layers carry no options.
+            LayerId::Session => {}
         }
     }
     let options =
         roll_up_embedding_options(&[local, user, delta, base]).context("roll up options")?;
     if let Some(cfg_dim) = options.dim {
         if cfg_dim != dim {
-            anyhow::bail!(
-                "embedding dim mismatch (layers are dim={dim}, options specify dim={cfg_dim})"
-            );
+            return Err(ToolError::dim_mismatch(
+                format!(
+                    "embedding dim mismatch (layers are dim={dim}, options specify dim={cfg_dim})"
+                ),
+                dim,
+                cfg_dim,
+            )
+            .into());
         }
     }
     let embedder = options
@@ -557,24 +2826,25 @@ fn handle_search(config: &ServerConfig, params: SearchParams) -> anyhow::Result<
     let embedding = match params.query_vec {
         Some(v) => {
             if v.len() != dim {
-                anyhow::bail!(
-                    "query_vec dimension mismatch (expected {dim}, got {})",
-                    v.len()
-                );
+                return Err(ToolError::dim_mismatch(
+                    format!(
+                        "query_vec dimension mismatch (expected {dim}, got {})",
+                        v.len()
+                    ),
+                    dim,
+                    v.len(),
+                )
+                .into());
             }
             v
         }
-        None => embedder
-            .embed({
-                for (_, file) in &opened {
-                    ensure_layer_metadata_compatible_with_embedder(file, embedder.as_ref())
-                        .context("validate layer metadata vs embedder")?;
-                }
-                std::slice::from_ref(&params.query)
-            })?
-            .into_iter()
-            .next()
-            .unwrap_or_else(|| vec![0.0; dim]),
+        None => {
+            for (_, file) in &opened {
+                ensure_layer_metadata_compatible_with_embedder(file, embedder.as_ref())
+                    .context("validate layer metadata vs embedder")?;
+            }
+            agentsdb_ops::query_cache::embed_query_cached(embedder.as_ref(), &params.query)?
+        }
     };
     let query = SearchQuery {
         embedding,
@@ -582,156 +2852,779 @@ fn handle_search(config: &ServerConfig, params: SearchParams) -> anyhow::Result<
         filters,
         query_text: Some(params.query),
     };
-    let results = agentsdb_query::search_layers_with_options(
+    agentsdb_query::search_layers_with_options(
         &opened,
         &query,
         agentsdb_query::SearchOptions {
             use_index: true,
             mode: agentsdb_query::SearchMode::Hybrid,
+            cache_results: true,
+            disk_cache: false,
         },
     )
-    .context("search")?;
-    Ok(serde_json::to_value(results)?)
+    .context("search")
+}
+
+/// Current version of each chunk id: within a layer the last appended record wins, and a
+/// higher-precedence layer hides the same id in lower layers.
+fn effective_chunks<'a>(
+    opened: &'a [(LayerId, agentsdb_format::LayerFile)],
+) -> anyhow::Result<
+    Vec<(
+        LayerId,
+        &'a agentsdb_format::LayerFile,
+        agentsdb_format::ChunkView<'a>,
+    )>,
+> {
+    let mut seen = std::collections::HashSet::new();
+    let mut out = Vec::new();
+    for (layer_id, file) in opened {
+        let mut last_by_id = std::collections::BTreeMap::new();
+        for chunk in file.chunks() {
+            let chunk = chunk.context("read chunk")?;
+            last_by_id.insert(chunk.id, chunk);
+        }
+        for (id, chunk) in last_by_id {
+            if seen.insert(id) {
+                out.push((*layer_id, file, chunk));
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn chunk_from_view(
+    file: &agentsdb_format::LayerFile,
+    chunk: &agentsdb_format::ChunkView<'_>,
+) -> anyhow::Result<agentsdb_core::types::Chunk> {
+    use agentsdb_core::types::{Author, Chunk, ChunkId, ProvenanceRef};
+
+    let sources = file
+        .sources_for(chunk.rel_start, chunk.rel_count)
+        .with_context(|| format!("read sources for chunk {}", chunk.id))?
+        .into_iter()
+        .map(|s| match s {
+            agentsdb_format::SourceRef::ChunkId(id) => ProvenanceRef::ChunkId(ChunkId(id)),
+            agentsdb_format::SourceRef::String(v) => ProvenanceRef::SourceString(v.to_string()),
+        })
+        .collect();
+    let author = match chunk.author {
+        "human" => Author::Human,
+        "mcp" => Author::Mcp,
+        other => anyhow::bail!("chunk {} has unknown author {other:?}", chunk.id),
+    };
+    Ok(Chunk {
+        id: ChunkId(chunk.id),
+        kind: chunk.kind.to_string(),
+        content: chunk.content.to_string(),
+        author,
+        confidence: chunk.confidence,
+        created_at_unix_ms: chunk.created_at_unix_ms,
+        sources,
+    })
+}
+
+fn handle_get(config: &ServerConfig, params: GetParams) -> anyhow::Result<Value> {
+    let opened = select_layers(config, params.layers)?
+        .open()
+        .context("open layers")?;
+
+    let mut found = None;
+    let mut hidden_layers = Vec::new();
+    for (layer_id, file) in &opened {
+        let mut last = None;
+        for chunk in file.chunks() {
+            let chunk = chunk.context("read chunk")?;
+            if chunk.id == params.id {
+                last = Some(chunk);
+            }
+        }
+        match (last, &found) {
+            (Some(chunk), None) => found = Some((*layer_id, chunk_from_view(file, &chunk)?)),
+            (Some(_), Some(_)) => hidden_layers.push(*layer_id),
+            (None, _) => {}
+        }
+    }
+    let (layer, chunk) =
+        found.ok_or_else(|| anyhow::anyhow!("chunk id {} not found", params.id))?;
+    let retracted = agentsdb_query::retracted_chunk_ids(&opened).context("read tombstones")?;
+
+    Ok(serde_json::json!({
+        "layer": layer,
+        "chunk": chunk,
+        "hidden_layers": hidden_layers,
+        "retracted": agentsdb_query::is_retracted(&retracted, layer, params.id),
+    }))
+}
+
+fn handle_list(config: &ServerConfig, params: ListParams) -> anyhow::Result<Value> {
+    let opened = select_layers(config, params.layers)?
+        .open()
+        .context("open layers")?;
+    let offset = params.offset.unwrap_or(0);
+    let limit = params.limit.unwrap_or(LIST_DEFAULT_LIMIT);
+    if limit == 0 {
+        anyhow::bail!("limit must be positive");
+    }
+
+    let retracted = agentsdb_query::retracted_chunk_ids(&opened).context("read tombstones")?;
+    let mut matching: Vec<_> = effective_chunks(&opened)?
+        .into_iter()
+        .filter(|(layer_id, _, c)| !agentsdb_query::is_retracted(&retracted, *layer_id, c.id))
+        .filter(|(_, _, c)| {
+            if params.kind.is_empty() {
+                // Like search, hide configuration records and tombstones unless asked for explicitly.
+                c.kind != agentsdb_embeddings::config::KIND_OPTIONS
+                    && c.kind != agentsdb_query::KIND_TOMBSTONE
+                    && !c.kind.starts_with("meta.")
+            } else {
+                params.kind.iter().any(|k| k == c.kind)
+            }
+        })
+        .filter(|(_, _, c)| {
+            params
+                .since_unix_ms
+                .is_none_or(|t| c.created_at_unix_ms >= t)
+        })
+        .filter(|(_, _, c)| {
+            params
+                .until_unix_ms
+                .is_none_or(|t| c.created_at_unix_ms <= t)
+        })
+        .collect();
+    if let Some(namespace) = params.namespace.as_deref() {
+        let mut kept = Vec::with_capacity(matching.len());
+        for entry in matching {
+            let sources = entry.1.sources_for(entry.2.rel_start, entry.2.rel_count)?;
+            if agentsdb_query::namespace_of(&sources) == Some(namespace) {
+                kept.push(entry);
+            }
+        }
+        matching = kept;
+    }
+    matching.sort_by(|a, b| {
+        b.2.created_at_unix_ms
+            .cmp(&a.2.created_at_unix_ms)
+            .then(b.2.id.cmp(&a.2.id))
+    });
+
+    let total = matching.len();
+    let items = matching
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(layer_id, file, c)| {
+            Ok(serde_json::json!({ "layer": layer_id, "chunk": chunk_from_view(file, c)? }))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(serde_json::json!({
+        "total": total,
+        "offset": offset,
+        "limit": limit,
+        "items": items,
+    }))
+}
+
+/// Resolves the current version of chunk `id` and checks that a tombstone written to `scope`
+/// can retract it.
+fn retractable_chunk(
+    config: &ServerConfig,
+    id: u32,
+    scope: &str,
+) -> anyhow::Result<(LayerId, agentsdb_core::types::Chunk)> {
+    let scope_layer = match scope {
+        "local" => LayerId::Local,
+        "delta" => LayerId::Delta,
+        _ => anyhow::bail!("scope must be 'local' or 'delta'"),
+    };
+    let opened = select_layers(config, None)?.open().context("open layers")?;
+    let retracted = agentsdb_query::retracted_chunk_ids(&opened).context("read tombstones")?;
+    let (layer, file, chunk) = effective_chunks(&opened)?
+        .into_iter()
+        .find(|(_, _, c)| c.id == id)
+        .ok_or_else(|| anyhow::anyhow!("chunk id {id} not found"))?;
+    if chunk.kind == agentsdb_query::KIND_TOMBSTONE {
+        anyhow::bail!("chunk id {id} is a tombstone");
+    }
+    if agentsdb_query::is_retracted(&retracted, layer, id) {
+        anyhow::bail!("chunk id {id} is already retracted");
+    }
+    // A tombstone only hides its own and lower-precedence layers.
+    if layer < scope_layer {
+        anyhow::bail!(
+            "chunk id {id} lives in the {} layer, which a {scope} tombstone cannot retract",
+            layer_id_name(layer)
+        );
+    }
+    Ok((layer, chunk_from_view(file, &chunk)?))
+}
+
+fn tombstone_chunk(id: u32, reason: String) -> anyhow::Result<agentsdb_format::ChunkInput> {
+    if reason.trim().is_empty() {
+        anyhow::bail!("reason must be non-empty");
+    }
+    mcp_chunk(
+        agentsdb_query::KIND_TOMBSTONE.to_string(),
+        reason,
+        1.0,
+        vec![agentsdb_format::ChunkSource::ChunkId(id)],
+    )
+}
+
+fn handle_retract(config: &ServerConfig, params: RetractParams) -> anyhow::Result<Value> {
+    let path = scope_layer_path(config, &params.scope)?;
+    let tombstone = tombstone_chunk(params.id, params.reason)?;
+    retractable_chunk(config, params.id, &params.scope)?;
+
+    let ids = append_chunks(config, path, vec![tombstone])?;
+    Ok(serde_json::json!({
+        "context_id": ids[0],
+        "retracted_id": params.id,
+    }))
+}
+
+fn handle_update(config: &ServerConfig, params: UpdateParams) -> anyhow::Result<Value> {
+    let path = scope_layer_path(config, &params.scope)?;
+    if params.content.trim().is_empty() {
+        anyhow::bail!("content must be non-empty");
+    }
+    let tombstone = tombstone_chunk(
+        params.id,
+        params
+            .reason
+            .unwrap_or_else(|| "superseded by a revised chunk".to_string()),
+    )?;
+    let warning = params
+        .kind
+        .as_deref()
+        .map(|kind| check_kind(config, kind))
+        .transpose()?
+        .flatten();
+    let (_, original) = retractable_chunk(config, params.id, &params.scope)?;
+
+    // The revision keeps the original's provenance and records what it supersedes.
+    let mut sources = vec![agentsdb_format::ChunkSource::SourceString(format!(
+        "{}{}",
+        agentsdb_query::SUPERSEDES_SOURCE_PREFIX,
+        params.id
+    ))];
+    sources.extend(original.sources.into_iter().map(|s| match s {
+        agentsdb_core::types::ProvenanceRef::ChunkId(id) => {
+            agentsdb_format::ChunkSource::ChunkId(id.get())
+        }
+        agentsdb_core::types::ProvenanceRef::SourceString(v) => {
+            agentsdb_format::ChunkSource::SourceString(v)
+        }
+    }));
+    let revised = mcp_chunk(
+        params.kind.unwrap_or(original.kind),
+        params.content,
+        params.confidence.unwrap_or(original.confidence),
+        sources,
+    )?;
+
+    let ids = append_chunks(config, path, vec![revised, tombstone])?;
+    let mut result = serde_json::json!({
+        "context_id": ids[0],
+        "superseded_id": params.id,
+        "tombstone_id": ids[1],
+    });
+    if let Some(warning) = warning {
+        result["warnings"] = serde_json::json!([warning]);
+    }
+    Ok(result)
+}
+
+/// Writes a session summary and the learnings in it as linked chunks in one append: the summary
+/// first (split into parts when long), then one chunk per item with a source citing the summary.
+fn handle_reflect(config: &ServerConfig, params: ReflectParams) -> anyhow::Result<Value> {
+    let path = scope_layer_path(config, &params.scope)?;
+    let (summary, found) = split_reflection(&params.summary);
+    let mut items: Vec<(String, String, f32)> = found
+        .into_iter()
+        .map(|(kind, content)| (kind.to_string(), content, reflect_confidence(kind)))
+        .collect();
+    for item in params.items {
+        if item.content.trim().is_empty() {
+            anyhow::bail!("item content must be non-empty");
+        }
+        let confidence = item
+            .confidence
+            .unwrap_or_else(|| reflect_confidence(&item.kind));
+        items.push((item.kind, item.content, confidence));
+    }
+    if summary.is_empty() && items.is_empty() {
+        anyhow::bail!("summary must be non-empty");
+    }
+
+    let mut sources = parse_sources(params.sources)?;
+    if let Some(namespace) = params.namespace.as_deref() {
+        sources.push(agentsdb_ops::util::namespace_source(namespace)?);
+    }
+    let taxonomy = kind_taxonomy(config)?;
+    let mut warnings = Vec::new();
+    let summary_kind = (!summary.is_empty()).then_some(REFLECT_SUMMARY_KIND);
+    for kind in summary_kind
+        .into_iter()
+        .chain(items.iter().map(|(kind, _, _)| kind.as_str()))
+    {
+        if let Some(warning) = agentsdb_ops::kinds::check_kind(&taxonomy, kind)? {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+    }
+
+    let first = next_free_id(path)?;
+    let mut chunks = Vec::new();
+    if !summary.is_empty() {
+        let parts = agentsdb_core::chunking::split(&summary, &ChunkingOptions::default());
+        let count = u32::try_from(parts.len()).context("too many parts")?;
+        for (index, content) in (1..=count).zip(parts) {
+            let mut sources = sources.clone();
+            if count > 1 {
+                let part = PartOf {
+                    first,
+                    index,
+                    count,
+                };
+                sources.push(agentsdb_format::ChunkSource::SourceString(part.source()));
+            }
+            chunks.push(mcp_chunk(
+                REFLECT_SUMMARY_KIND.to_string(),
+                content,
+                REFLECT_SUMMARY_CONFIDENCE,
+                sources,
+            )?);
+        }
+    }
+    let summary_count = chunks.len();
+    let kinds: Vec<String> = items.iter().map(|(kind, _, _)| kind.clone()).collect();
+    for (kind, content, confidence) in items {
+        let mut sources = sources.clone();
+        if summary_count > 0 {
+            sources.push(agentsdb_format::ChunkSource::ChunkId(first));
+        }
+        chunks.push(mcp_chunk(kind, content, confidence, sources)?);
+    }
+    for (offset, chunk) in (0u32..).zip(&mut chunks) {
+        chunk.id = first
+            .checked_add(offset)
+            .context("no free chunk ids left")?;
+    }
+
+    let ids = append_chunks(config, path, chunks)?;
+    let (summary_ids, item_ids) = ids.split_at(summary_count);
+    let items: Vec<Value> = item_ids
+        .iter()
+        .zip(kinds)
+        .map(|(id, kind)| serde_json::json!({ "id": id, "kind": kind }))
+        .collect();
+    let mut result = serde_json::json!({
+        "context_id": ids[0],
+        "summary": summary_ids,
+        "items": items,
+        "ids": ids,
+    });
+    if !warnings.is_empty() {
+        result["warnings"] = serde_json::json!(warnings);
+    }
+    Ok(result)
+}
+
+/// Splits a session summary into the items under its `Decisions`, `Gotchas` and `Preferences`
+/// headings (any level, singular or plural, case-insensitive) and the rest of the text. Each
+/// list item or paragraph of such a section is one item; list markers are dropped.
+fn split_reflection(summary: &str) -> (String, Vec<(&'static str, String)>) {
+    fn flush(
+        kind: Option<&'static str>,
+        item: &mut String,
+        items: &mut Vec<(&'static str, String)>,
+    ) {
+        if let Some(kind) = kind.filter(|_| !item.trim().is_empty()) {
+            items.push((kind, item.trim().to_string()));
+        }
+        item.clear();
+    }
+
+    let mut rest = String::new();
+    let mut items = Vec::new();
+    let mut section: Option<&'static str> = None;
+    let mut item = String::new();
+    for line in summary.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            flush(section, &mut item, &mut items);
+            let title = trimmed
+                .trim_start_matches('#')
+                .trim()
+                .trim_end_matches(':')
+                .to_lowercase();
+            section = REFLECT_KINDS
+                .iter()
+                .map(|(kind, _)| *kind)
+                .find(|kind| title.strip_suffix('s').unwrap_or(&title) == *kind);
+            if section.is_some() {
+                continue;
+            }
+        }
+        if section.is_none() {
+            rest.push_str(line);
+            rest.push('\n');
+        } else if trimmed.is_empty() {
+            flush(section, &mut item, &mut items);
+        } else if let Some(text) = strip_list_marker(trimmed) {
+            flush(section, &mut item, &mut items);
+            item.push_str(text);
+        } else {
+            if !item.is_empty() {
+                item.push('\n');
+            }
+            item.push_str(trimmed);
+        }
+    }
+    flush(section, &mut item, &mut items);
+    (rest.trim().to_string(), items)
+}
+
+/// `line` without its leading `-`, `*`, `+`, `1.` or `1)` list marker, if it has one.
+fn strip_list_marker(line: &str) -> Option<&str> {
+    let after_digits = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    let rest = if after_digits.len() < line.len() {
+        after_digits.strip_prefix(['.', ')'])?
+    } else {
+        line.strip_prefix(['-', '*', '+'])?
+    };
+    rest.strip_prefix(' ').map(str::trim_start)
+}
+
+/// Confidence of an `agents_reflect` item of `kind` that does not set one.
+fn reflect_confidence(kind: &str) -> f32 {
+    REFLECT_KINDS
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map_or(REFLECT_SUMMARY_CONFIDENCE, |(_, confidence)| *confidence)
+}
+
+fn parse_sources(sources: Vec<WriteSource>) -> anyhow::Result<Vec<agentsdb_format::ChunkSource>> {
+    sources
+        .into_iter()
+        .map(|s| match s {
+            WriteSource::String(v) => Ok(agentsdb_format::ChunkSource::SourceString(v)),
+            WriteSource::ChunkId { chunk_id } => {
+                if chunk_id == 0 {
+                    anyhow::bail!("source chunk_id must be non-zero");
+                }
+                Ok(agentsdb_format::ChunkSource::ChunkId(chunk_id))
+            }
+        })
+        .collect()
+}
+
+fn handle_write(config: &ServerConfig, params: WriteParams) -> anyhow::Result<Value> {
+    let path = scope_layer_path(config, &params.scope)?;
+    let mut sources = parse_sources(params.sources)?;
+    if let Some(namespace) = params.namespace.as_deref() {
+        sources.push(agentsdb_ops::util::namespace_source(namespace)?);
+    }
+    if let Some(language) = params.language.as_deref() {
+        sources.extend(agentsdb_ops::util::language_source(
+            language,
+            &params.content,
+        )?);
+    }
+
+    let warning = check_kind(config, &params.kind)?;
+
+    let parts = agentsdb_core::chunking::split(&params.content, &ChunkingOptions::default());
+    let mut result = if parts.len() > 1 {
+        write_parts(
+            config,
+            path,
+            &params.kind,
+            parts,
+            params.confidence,
+            &sources,
+        )?
+    } else {
+        let mut chunk = mcp_chunk(params.kind, params.content, params.confidence, sources)?;
+        if !std::path::Path::new(path).exists() {
+            // The first chunk written to a new layer gets id 1.
+            chunk.id = 1;
+        }
+        let ids = append_chunks(config, path, vec![chunk])?;
+        serde_json::json!({ "context_id": ids[0] })
+    };
+    if let Some(warning) = warning {
+        result["warnings"] = serde_json::json!([warning]);
+    }
+    Ok(result)
+}
+
+/// The kind taxonomy of the configured layers.
+fn kind_taxonomy(config: &ServerConfig) -> anyhow::Result<KindTaxonomy> {
+    agentsdb_ops::kinds::load_taxonomy(
+        [&config.base, &config.user, &config.delta, &config.local]
+            .into_iter()
+            .flatten(),
+    )
+}
+
+/// Checks `kind` against the kind taxonomy; returns the warning for an unlisted kind when the
+/// taxonomy only warns.
+fn check_kind(config: &ServerConfig, kind: &str) -> anyhow::Result<Option<String>> {
+    Ok(agentsdb_ops::kinds::check_kind(
+        &kind_taxonomy(config)?,
+        kind,
+    )?)
+}
+
+/// Writes content too long for one chunk as consecutive chunks, each tagged with a `part-of:`
+/// source naming the first, in a single append.
+fn write_parts(
+    config: &ServerConfig,
+    path: &str,
+    kind: &str,
+    parts: Vec<String>,
+    confidence: f32,
+    sources: &[agentsdb_format::ChunkSource],
+) -> anyhow::Result<Value> {
+    let first = next_free_id(path)?;
+    let count = u32::try_from(parts.len()).context("too many parts")?;
+    let chunks = (1..=count)
+        .zip(parts)
+        .map(|(index, content)| {
+            let mut sources = sources.to_vec();
+            sources.push(agentsdb_format::ChunkSource::SourceString(
+                PartOf {
+                    first,
+                    index,
+                    count,
+                }
+                .source(),
+            ));
+            let mut chunk = mcp_chunk(kind.to_string(), content, confidence, sources)?;
+            chunk.id = first
+                .checked_add(index - 1)
+                .context("no free chunk ids left")?;
+            Ok(chunk)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let ids = append_chunks(config, path, chunks)?;
+    Ok(serde_json::json!({ "context_id": ids[0], "parts": ids }))
+}
+
+/// One past the largest chunk id in the layer at `path` (1 for a new layer).
+fn next_free_id(path: &str) -> anyhow::Result<u32> {
+    if !Path::new(path).exists() {
+        return Ok(1);
+    }
+    let file = LayerPool::global().get(path).context("open layer")?;
+    let mut max = 0;
+    for chunk in file.chunks() {
+        max = max.max(chunk?.id);
+    }
+    max.checked_add(1).context("no free chunk ids left")
+}
+
+fn session_layer_path(config: &ServerConfig) -> anyhow::Result<&str> {
+    config
+        .session
+        .as_deref()
+        .ok_or_else(|| layer_not_configured("session"))
+}
+
+fn handle_session_note(config: &ServerConfig, params: SessionNoteParams) -> anyhow::Result<Value> {
+    let path = session_layer_path(config)?;
+    if params.content.trim().is_empty() {
+        anyhow::bail!("content must be non-empty");
+    }
+    if let Some(dir) = Path::new(path).parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    }
+    let sources = parse_sources(params.sources)?;
+    let chunk = mcp_chunk(params.kind, params.content, params.confidence, sources)?;
+    let ids = append_chunks(config, path, vec![chunk])?;
+    Ok(serde_json::json!({ "context_id": ids[0], "layer": "session" }))
 }
 
-fn handle_write(config: &ServerConfig, params: WriteParams) -> anyhow::Result<Value> {
-    if params.scope != "local" && params.scope != "delta" {
-        anyhow::bail!("scope must be 'local' or 'delta'");
+/// Ends the session: `promote` copies the session notes into the local layer, `discard` drops
+/// them. Either way the session layer is emptied.
+fn handle_session_end(config: &ServerConfig, params: &SessionEndParams) -> anyhow::Result<Value> {
+    let path = session_layer_path(config)?;
+    let chunks = if Path::new(path).exists() {
+        let file = agentsdb_format::LayerFile::open(path).context("open session layer")?;
+        agentsdb_format::read_all_chunks(&file).context("read session layer")?
+    } else {
+        Vec::new()
+    };
+    let result = match params.action.as_str() {
+        "promote" => {
+            let local = scope_layer_path(config, "local")?;
+            let chunks: Vec<_> = chunks
+                .into_iter()
+                .map(|mut c| {
+                    c.id = 0;
+                    c
+                })
+                .collect();
+            let ids = if chunks.is_empty() {
+                Vec::new()
+            } else {
+                append_chunks(config, local, chunks)?
+            };
+            serde_json::json!({ "promoted": ids })
+        }
+        "discard" => serde_json::json!({ "discarded": chunks.len() }),
+        other => anyhow::bail!("action must be 'promote' or 'discard' (got {other:?})"),
+    };
+    if Path::new(path).exists() {
+        std::fs::remove_file(path).context("remove session layer")?;
     }
-    let path = match params.scope.as_str() {
+    Ok(result)
+}
+
+/// Writable layer path for a write `scope` (`local` or `delta`).
+fn scope_layer_path<'a>(config: &'a ServerConfig, scope: &str) -> anyhow::Result<&'a str> {
+    let path = match scope {
         "local" => config
             .local
             .as_deref()
-            .ok_or_else(|| anyhow::anyhow!("local layer path not configured"))?,
+            .ok_or_else(|| layer_not_configured("local"))?,
         "delta" => config
             .delta
             .as_deref()
-            .ok_or_else(|| anyhow::anyhow!("delta layer path not configured"))?,
-        _ => unreachable!(),
+            .ok_or_else(|| layer_not_configured("delta"))?,
+        _ => anyhow::bail!("scope must be 'local' or 'delta'"),
     };
+    agentsdb_format::WritePolicy::standard().check(path)?;
+    Ok(path)
+}
 
-    agentsdb_format::ensure_writable_layer_path(path)?;
-
+fn mcp_chunk(
+    kind: String,
+    content: String,
+    confidence: f32,
+    sources: Vec<agentsdb_format::ChunkSource>,
+) -> anyhow::Result<agentsdb_format::ChunkInput> {
+    if !(0.0..=1.0).contains(&confidence) || !confidence.is_finite() {
+        anyhow::bail!("confidence must be finite and in range 0.0..=1.0");
+    }
     let now_ms = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
-        .as_millis() as u64;
-
-    let sources = params
-        .sources
-        .into_iter()
-        .map(|s| match s {
-            WriteSource::String(v) => Ok(agentsdb_format::ChunkSource::SourceString(v)),
-            WriteSource::ChunkId { chunk_id } => {
-                if chunk_id == 0 {
-                    anyhow::bail!("source chunk_id must be non-zero");
-                }
-                Ok(agentsdb_format::ChunkSource::ChunkId(chunk_id))
-            }
-        })
-        .collect::<anyhow::Result<Vec<_>>>()?;
-
-    let mut chunk = agentsdb_format::ChunkInput {
+        .as_millis();
+    Ok(agentsdb_format::ChunkInput {
         id: 0,
-        kind: params.kind,
-        content: params.content,
+        kind,
+        content,
         author: "mcp".to_string(),
-        confidence: params.confidence,
-        created_at_unix_ms: now_ms,
+        confidence,
+        created_at_unix_ms: u64::try_from(now_ms).unwrap_or(u64::MAX),
         embedding: Vec::new(),
         sources,
-    };
+    })
+}
 
-    if !(0.0..=1.0).contains(&chunk.confidence) || !chunk.confidence.is_finite() {
-        anyhow::bail!("confidence must be finite and in range 0.0..=1.0");
+/// Embeds `chunks` and appends them to the layer at `path` in a single atomic write,
+/// creating the layer if needed. Returns the assigned ids in order.
+fn append_chunks(
+    config: &ServerConfig,
+    path: &str,
+    mut chunks: Vec<agentsdb_format::ChunkInput>,
+) -> anyhow::Result<Vec<u32>> {
+    let layer_path = std::path::Path::new(path);
+    let target = agentsdb_ops::hooks::WriteTarget::of(layer_path);
+    for chunk in &mut chunks {
+        agentsdb_ops::hooks::run_on_input(&target, chunk)?;
     }
+    let existing = if layer_path.exists() {
+        Some(agentsdb_format::LayerFile::open(path).context("open layer")?)
+    } else {
+        None
+    };
+    let schema = match &existing {
+        Some(file) => agentsdb_format::schema_of(file),
+        None => infer_schema_from_config(config).context("infer schema")?,
+    };
+    let dim = schema.dim as usize;
+    let what = if existing.is_some() {
+        "layer"
+    } else {
+        "schema"
+    };
 
-    let assigned = if std::path::Path::new(path).exists() {
-        let file = agentsdb_format::LayerFile::open(path).context("open layer")?;
-        let dim = file.embedding_dim();
-        let dir = std::path::Path::new(path)
-            .parent()
-            .unwrap_or_else(|| std::path::Path::new("."));
-        let options = get_immutable_embedding_options(dir)
-            .context("get immutable embedding options")?;
-        if let Some(cfg_dim) = options.dim {
-            if cfg_dim != dim {
-                anyhow::bail!(
-                    "embedding dim mismatch (layer is dim={dim}, options specify dim={cfg_dim})"
-                );
-            }
+    // Session layers live in a temp dir; they use the project's options.
+    let options_layer = if config.session.as_deref() == Some(path) {
+        config.base.as_deref().map_or(layer_path, Path::new)
+    } else {
+        layer_path
+    };
+    let dir = options_layer
+        .parent()
+        .unwrap_or_else(|| std::path::Path::new("."));
+    let options =
+        get_immutable_embedding_options(dir).context("get immutable embedding options")?;
+    if let Some(cfg_dim) = options.dim {
+        if cfg_dim != dim {
+            return Err(ToolError::dim_mismatch(
+                format!(
+                    "embedding dim mismatch ({what} is dim={dim}, options specify dim={cfg_dim})"
+                ),
+                dim,
+                cfg_dim,
+            )
+            .into());
         }
-        let embedder = options
-            .into_embedder(dim)
-            .context("resolve embedder from options")?;
-        chunk.embedding = embedder
-            .embed(&[chunk.content.clone()])?
-            .into_iter()
-            .next()
-            .unwrap_or_else(|| vec![0.0; dim]);
-        let layer_metadata = LayerMetadataV1::new(embedder.profile().clone())
-            .with_embedder_metadata(embedder.metadata())
-            .with_tool("agentsdb-mcp", env!("CARGO_PKG_VERSION"));
-        let layer_metadata_json = layer_metadata
-            .to_json_bytes()
-            .context("serialize layer metadata")?;
-        let mut chunks = vec![chunk];
-        if let Some(existing) = file.layer_metadata_bytes() {
-            let existing = LayerMetadataV1::from_json_bytes(existing)
-                .context("parse existing layer metadata")?;
-            if existing.embedding_profile != *embedder.profile() {
-                anyhow::bail!(
-                    "embedder profile mismatch vs existing layer metadata (existing={:?}, current={:?})",
-                    existing.embedding_profile,
-                    embedder.profile()
-                );
+    }
+    let embedder = options
+        .into_embedder(dim)
+        .context("resolve embedder from options")?;
+    let contents: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+    let mut embeddings = embedder
+        .embed_for(EmbedPurpose::Document, &contents)?
+        .into_iter();
+    for chunk in &mut chunks {
+        chunk.embedding = embeddings.next().unwrap_or_else(|| vec![0.0; dim]);
+    }
+    let layer_metadata = LayerMetadataV1::new(embedder.profile().clone())
+        .with_embedder_metadata(embedder.metadata())
+        .with_tool("agentsdb-mcp", env!("CARGO_PKG_VERSION"));
+    let layer_metadata_json = layer_metadata
+        .to_json_bytes()
+        .context("serialize layer metadata")?;
+
+    let recorded = |ids: &Vec<u32>| agentsdb_ops::metrics::record_append(layer_path, ids.len());
+    let Some(file) = existing else {
+        return agentsdb_format::write_layer_atomic(
+            path,
+            &schema,
+            &mut chunks,
+            Some(&layer_metadata_json),
+        )
+        .inspect(recorded)
+        .context("create layer");
+    };
+    if let Some(existing) = file.layer_metadata_bytes() {
+        let existing =
+            LayerMetadataV1::from_json_bytes(existing).context("parse existing layer metadata")?;
+        if existing.embedding_profile != *embedder.profile() {
+            return Err(ProfileMismatchError {
+                layer: PathBuf::from(path),
+                existing: existing.embedding_profile,
+                current: embedder.profile().clone(),
             }
-            let ids =
-                agentsdb_format::append_layer_atomic(path, &mut chunks, None).context("append")?;
-            ids[0]
-        } else {
-            let ids =
-                agentsdb_format::append_layer_atomic(path, &mut chunks, Some(&layer_metadata_json))
-                    .context("append")?;
-            ids[0]
+            .into());
         }
+        agentsdb_format::append_layer_atomic(path, &mut chunks, None)
+            .inspect(recorded)
+            .context("append")
     } else {
-        chunk.id = 1;
-        let schema = infer_schema_from_config(config).context("infer schema")?;
-        let dim = schema.dim as usize;
-        let dir = std::path::Path::new(path)
-            .parent()
-            .unwrap_or_else(|| std::path::Path::new("."));
-        let options = get_immutable_embedding_options(dir)
-            .context("get immutable embedding options")?;
-        if let Some(cfg_dim) = options.dim {
-            if cfg_dim != dim {
-                anyhow::bail!(
-                    "embedding dim mismatch (schema is dim={dim}, options specify dim={cfg_dim})"
-                );
-            }
-        }
-        let embedder = options
-            .into_embedder(dim)
-            .context("resolve embedder from options")?;
-        chunk.embedding = embedder
-            .embed(&[chunk.content.clone()])?
-            .into_iter()
-            .next()
-            .unwrap_or_else(|| vec![0.0; dim]);
-        let layer_metadata = LayerMetadataV1::new(embedder.profile().clone())
-            .with_embedder_metadata(embedder.metadata())
-            .with_tool("agentsdb-mcp", env!("CARGO_PKG_VERSION"));
-        let layer_metadata_json = layer_metadata
-            .to_json_bytes()
-            .context("serialize layer metadata")?;
-        let mut chunks = [chunk];
-        agentsdb_format::write_layer_atomic(path, &schema, &mut chunks, Some(&layer_metadata_json))
-            .context("create layer")?;
-        1
-    };
-
-    Ok(serde_json::json!({ "context_id": assigned }))
+        agentsdb_format::append_layer_atomic(path, &mut chunks, Some(&layer_metadata_json))
+            .inspect(recorded)
+            .context("append")
+    }
 }
 
 fn infer_schema_from_config(config: &ServerConfig) -> anyhow::Result<agentsdb_format::LayerSchema> {
@@ -751,6 +3644,7 @@ fn infer_schema_from_config(config: &ServerConfig) -> anyhow::Result<agentsdb_fo
                 dim: s.dim,
                 element_type: s.element_type,
                 quant_scale: s.quant_scale,
+                layout: s.layout,
             });
         }
     }
@@ -758,80 +3652,334 @@ fn infer_schema_from_config(config: &ServerConfig) -> anyhow::Result<agentsdb_fo
         dim: 128,
         element_type: agentsdb_format::EmbeddingElementType::F32,
         quant_scale: 1.0,
+        layout: agentsdb_format::EmbeddingLayout::RowMajor,
     })
 }
 
+/// Configured path for a proposal's `from_path`/`to_path` layer label.
+fn layer_path_for_label<'a>(config: &'a ServerConfig, label: &str) -> anyhow::Result<&'a str> {
+    let (path, name) = match label {
+        "AGENTS.local.db" => (config.local.as_deref(), "local"),
+        "AGENTS.user.db" => (config.user.as_deref(), "user"),
+        "AGENTS.delta.db" => (config.delta.as_deref(), "delta"),
+        "AGENTS.db" => (config.base.as_deref(), "base"),
+        other => anyhow::bail!("unknown proposal layer {other:?}"),
+    };
+    path.ok_or_else(|| layer_not_configured(name))
+}
+
+fn layer_not_configured(layer: &str) -> anyhow::Error {
+    ToolError::layer_missing(format!("{layer} layer path not configured"), layer, None).into()
+}
+
+fn proposal_events_path(config: &ServerConfig) -> anyhow::Result<&Path> {
+    config
+        .delta
+        .as_deref()
+        .map(Path::new)
+        .ok_or_else(|| layer_not_configured("delta"))
+}
+
+/// Describes every configured layer (highest precedence first), the embedder they resolve to
+/// and the pending proposals. A part that cannot be read carries an `error` instead of failing
+/// the whole call, since the tool is most useful when something is wrong.
+fn handle_status(config: &ServerConfig) -> anyhow::Result<Value> {
+    let mut opened = Vec::new();
+    let mut layers = Vec::new();
+    for (id, path) in [
+        (LayerId::Session, config.session.as_deref()),
+        (LayerId::Local, config.local.as_deref()),
+        (LayerId::User, config.user.as_deref()),
+        (LayerId::Delta, config.delta.as_deref()),
+        (LayerId::Base, config.base.as_deref()),
+    ] {
+        let name = layer_id_name(id);
+        let Some(path) = path else {
+            layers.push(serde_json::json!({ "layer": name, "status": "unconfigured" }));
+            continue;
+        };
+        if !Path::new(path).exists() {
+            layers.push(serde_json::json!({ "layer": name, "path": path, "status": "missing" }));
+            continue;
+        }
+        let mut row = serde_json::json!({ "layer": name, "path": path, "status": "present" });
+        match layer_status(path) {
+            Ok((file, detail)) => {
+                if let (Some(row), Value::Object(detail)) = (row.as_object_mut(), detail) {
+                    row.extend(detail);
+                }
+                opened.push((id, file));
+            }
+            Err(e) => row["error"] = Value::String(format!("{e:#}")),
+        }
+        layers.push(row);
+    }
+
+    let embedder = match embedder_profile(&opened) {
+        Ok(profile) => serde_json::json!({ "profile": profile }),
+        Err(e) => serde_json::json!({ "error": format!("{e:#}") }),
+    };
+    let proposals = match config.delta.as_deref() {
+        None => Value::Null,
+        Some(delta) => match agentsdb_ops::proposals::load_proposal_states(Path::new(delta)) {
+            Ok(states) => serde_json::json!({
+                "pending": states
+                    .values()
+                    .filter(|s| s.status == agentsdb_ops::proposals::ProposalStatus::Pending)
+                    .count(),
+            }),
+            Err(e) => serde_json::json!({ "error": format!("{e:#}") }),
+        },
+    };
+    Ok(serde_json::json!({
+        "layers": layers,
+        "embedder": embedder,
+        "proposals": proposals,
+    }))
+}
+
+/// Opens the layer at `path` and describes it for `agents_status`.
+fn layer_status(path: &str) -> anyhow::Result<(agentsdb_format::LayerFile, Value)> {
+    let file = LayerPool::global().get(path).context("open layer")?;
+    let stats = agentsdb_format::ChunkStats::of(&file).context("read chunk stats")?;
+    let index_path = agentsdb_query::default_index_path_for_layer(path);
+    let index = if !index_path.exists() {
+        "missing"
+    } else if agentsdb_query::layer_index_is_fresh(&file, &index_path).context("read index")? {
+        "fresh"
+    } else {
+        "stale"
+    };
+    let modified_unix_ms = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .and_then(|d| u64::try_from(d.as_millis()).ok());
+    let detail = serde_json::json!({
+        "chunk_count": stats.chunk_count,
+        "embedding_dim": file.embedding_dim(),
+        "file_length_bytes": file.header.file_length_bytes,
+        "newest_chunk_unix_ms": (stats.chunk_count > 0).then_some(stats.created_at_max),
+        "modified_unix_ms": modified_unix_ms,
+        "index": index,
+    });
+    Ok((file, detail))
+}
+
+/// Profile of the embedder the options of `opened` resolve to, as `agents_search` would use.
+fn embedder_profile(opened: &[(LayerId, agentsdb_format::LayerFile)]) -> anyhow::Result<Value> {
+    let find = |want: LayerId| opened.iter().find(|(id, _)| *id == want).map(|(_, f)| f);
+    let Some(first) = opened
+        .iter()
+        .find(|(id, _)| *id != LayerId::Session)
+        .map(|(_, f)| f)
+    else {
+        anyhow::bail!("no layers configured");
+    };
+    let dim = first.embedding_dim();
+    let options = roll_up_embedding_options(&[
+        find(LayerId::Local),
+        find(LayerId::User),
+        find(LayerId::Delta),
+        find(LayerId::Base),
+    ])
+    .context("roll up options")?;
+    if let Some(cfg_dim) = options.dim {
+        if cfg_dim != dim {
+            anyhow::bail!(
+                "embedding dim mismatch (layers are dim={dim}, options specify dim={cfg_dim})"
+            );
+        }
+    }
+    let embedder = options
+        .into_embedder(dim)
+        .context("resolve embedder from options")?;
+    Ok(serde_json::to_value(embedder.profile())?)
+}
+
+fn handle_proposals_list(
+    config: &ServerConfig,
+    params: &ProposalsListParams,
+) -> anyhow::Result<Value> {
+    let states = agentsdb_ops::proposals::load_proposal_states(proposal_events_path(config)?)
+        .context("load proposals")?;
+    let mut items = Vec::new();
+    for state in states.values() {
+        if !params.all && state.status != agentsdb_ops::proposals::ProposalStatus::Pending {
+            continue;
+        }
+        // Best-effort: show the proposed chunk so the reviewer does not need another call.
+        let content = layer_path_for_label(config, &state.from_path)
+            .ok()
+            .filter(|p| Path::new(p).exists())
+            .and_then(|p| agentsdb_format::LayerFile::open(p).ok())
+            .and_then(|file| {
+                file.chunks()
+                    .filter_map(Result::ok)
+                    .filter(|c| c.id == state.context_id)
+                    .last()
+                    .map(|c| c.content.to_string())
+            });
+        let mut item = serde_json::to_value(state)?;
+        item["content"] = content.map_or(Value::Null, Value::String);
+        items.push(item);
+    }
+    Ok(serde_json::json!({ "proposals": items }))
+}
+
+fn handle_proposals_decide(
+    config: &ServerConfig,
+    tool: &str,
+    params: &ProposalsDecideParams,
+) -> Result<Value, RpcError> {
+    if !config.allow_proposal_review {
+        return Err(RpcError::method_not_found(format!(
+            "{tool} is disabled (start the server with --allow-proposal-review)"
+        )));
+    }
+    let result = if tool == TOOL_AGENTS_PROPOSALS_ACCEPT {
+        accept_proposals(config, &params.ids)
+    } else {
+        reject_proposals(config, &params.ids, params.reason.as_deref())
+    };
+    result.map_err(|e| RpcError::tool_failure(&e))
+}
+
+fn pending_proposals(
+    config: &ServerConfig,
+    ids: &[u32],
+) -> anyhow::Result<Vec<agentsdb_ops::proposals::ProposalState>> {
+    if ids.is_empty() {
+        anyhow::bail!("ids must be non-empty");
+    }
+    let states = agentsdb_ops::proposals::load_proposal_states(proposal_events_path(config)?)
+        .context("load proposals")?;
+    ids.iter()
+        .map(|id| {
+            let state = states
+                .get(id)
+                .ok_or_else(|| anyhow::anyhow!("proposal {id} not found"))?;
+            if state.status != agentsdb_ops::proposals::ProposalStatus::Pending {
+                anyhow::bail!("proposal {id} is not pending");
+            }
+            Ok(state.clone())
+        })
+        .collect()
+}
+
+fn accept_proposals(config: &ServerConfig, ids: &[u32]) -> anyhow::Result<Value> {
+    let states = pending_proposals(config, ids)?;
+    for s in &states {
+        if s.to_path == "AGENTS.db" {
+            anyhow::bail!(
+                "proposal {} targets base; use `agentsdb compact` to rebuild base",
+                s.proposal_id
+            );
+        }
+    }
+
+    let events_path = proposal_events_path(config)?;
+    let policy = agentsdb_ops::policy::PromotionPolicy::for_layer(events_path)?;
+    let mut promoted = Vec::new();
+    let mut awaiting = Vec::new();
+    for s in &states {
+        // Under a policy requiring several approvers, the agent's accept counts as one.
+        if policy.missing_approvals(&s.approvers, "mcp") > 0 {
+            agentsdb_ops::proposals::append_decision_event(
+                events_path,
+                agentsdb_ops::policy::APPROVE_ACTION,
+                s,
+                "mcp",
+                None,
+                None,
+            )?;
+            awaiting.push(s.proposal_id);
+            continue;
+        }
+        let from = layer_path_for_label(config, &s.from_path)?;
+        let to = layer_path_for_label(config, &s.to_path)?;
+        let out = agentsdb_ops::promote_chunks(from, to, &[s.context_id], false)
+            .with_context(|| format!("promote chunk for proposal {}", s.proposal_id))?;
+        promoted.extend(out.promoted);
+        agentsdb_ops::proposals::append_decision_event(
+            events_path,
+            "accept",
+            s,
+            "mcp",
+            Some("promoted"),
+            None,
+        )?;
+    }
+    let accepted: Vec<u32> = ids
+        .iter()
+        .copied()
+        .filter(|id| !awaiting.contains(id))
+        .collect();
+    Ok(serde_json::json!({
+        "ok": true,
+        "accepted": accepted,
+        "promoted": promoted,
+        "awaiting_approval": awaiting,
+    }))
+}
+
+fn reject_proposals(
+    config: &ServerConfig,
+    ids: &[u32],
+    reason: Option<&str>,
+) -> anyhow::Result<Value> {
+    let states = pending_proposals(config, ids)?;
+    let events_path = proposal_events_path(config)?;
+    for s in &states {
+        agentsdb_ops::proposals::append_decision_event(
+            events_path,
+            "reject",
+            s,
+            "mcp",
+            Some("rejected"),
+            reason,
+        )?;
+    }
+    Ok(serde_json::json!({ "ok": true, "rejected": ids }))
+}
+
 fn handle_propose(config: &ServerConfig, params: ProposeParams) -> anyhow::Result<Value> {
     if params.target != "user" {
         anyhow::bail!("target must be 'user'");
     }
-    const PROPOSAL_EVENT_KIND: &str = "meta.proposal_event";
-
     let Some(delta_path) = &config.delta else {
-        anyhow::bail!("delta layer path not configured");
+        return Err(layer_not_configured("delta"));
     };
 
     let delta_p = std::path::Path::new(delta_path);
     if !delta_p.exists() {
-        anyhow::bail!("delta layer file not found at {delta_path}");
+        return Err(ToolError::layer_missing(
+            format!("delta layer file not found at {delta_path}"),
+            "delta",
+            Some(delta_path),
+        )
+        .into());
     }
-    agentsdb_format::ensure_writable_layer_path(delta_p).context("permission check")?;
-
-    let now_ms = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64;
-
-    let delta_file = agentsdb_format::LayerFile::open(delta_p).context("open delta layer")?;
-    let delta_chunks =
-        agentsdb_format::read_all_chunks(&delta_file).context("read delta chunks")?;
-    let Some(src) = delta_chunks.into_iter().find(|c| c.id == params.context_id) else {
-        anyhow::bail!(
-            "context_id {} not found in delta layer {}",
-            params.context_id,
-            delta_path
-        );
-    };
+    agentsdb_format::WritePolicy::standard()
+        .check(delta_p)
+        .context("permission check")?;
 
-    let from_label = delta_p
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or(delta_path)
-        .to_string();
     let to_label = config
         .user
         .as_deref()
         .and_then(|p| std::path::Path::new(p).file_name().and_then(|s| s.to_str()))
-        .unwrap_or("AGENTS.user.db")
-        .to_string();
-
-    let record = serde_json::json!({
-        "action": "propose",
-        "context_id": params.context_id,
-        "from_path": from_label,
-        "to_path": to_label,
-        "created_at_unix_ms": now_ms,
-        "actor": "mcp",
-        "title": params.title,
-        "why": params.why,
-        "what": params.what,
-        "where": params.where_
-    });
-
-    let mut event_chunk = agentsdb_format::ChunkInput {
-        id: 0,
-        kind: PROPOSAL_EVENT_KIND.to_string(),
-        content: serde_json::to_string(&record).context("serialize proposal record")?,
-        author: "mcp".to_string(),
-        confidence: 1.0,
-        created_at_unix_ms: now_ms,
-        embedding: src.embedding.clone(),
-        sources: vec![agentsdb_format::ChunkSource::ChunkId(params.context_id)],
+        .unwrap_or("AGENTS.user.db");
+    let proposal = agentsdb_ops::proposals::NewProposal {
+        context_id: params.context_id,
+        title: params.title,
+        why: params.why,
+        what: params.what,
+        where_: params.where_,
     };
-    agentsdb_format::append_layer_atomic(delta_p, std::slice::from_mut(&mut event_chunk), None)
-        .context("append proposal event")?;
+    let proposal_id =
+        agentsdb_ops::proposals::append_propose_event(delta_p, to_label, &proposal, "mcp")?;
 
-    Ok(serde_json::json!({ "ok": true }))
+    Ok(serde_json::json!({ "ok": true, "proposal_id": proposal_id }))
 }
 
 #[cfg(test)]
@@ -862,24 +4010,412 @@ mod tests {
         dir
     }
 
-    #[test]
-    fn tool_names_are_openai_compatible() {
-        let list = handle_tools_list();
-        let tools = list
-            .get("tools")
-            .and_then(|v| v.as_array())
-            .expect("tools must be an array");
+    #[test]
+    fn tool_names_are_openai_compatible() {
+        let config = ServerConfig {
+            allow_proposal_review: true,
+            workspace: true,
+            ..ServerConfig::default()
+        };
+        let list = handle_tools_list(&config);
+        let tools = list
+            .get("tools")
+            .and_then(|v| v.as_array())
+            .expect("tools must be an array");
+
+        for tool in tools {
+            let name = tool
+                .get("name")
+                .and_then(|v| v.as_str())
+                .expect("tool name must be a string");
+            assert!(
+                is_openai_tool_name_compatible(name),
+                "tool name not OpenAI-compatible: {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn prompts_are_listed_and_validate_arguments() {
+        let list = handle_prompts_list();
+        let names: Vec<&str> = list["prompts"]
+            .as_array()
+            .expect("prompts must be an array")
+            .iter()
+            .filter_map(|p| p["name"].as_str())
+            .collect();
+        assert_eq!(names, [PROMPT_AGENTS_RECALL, PROMPT_AGENTS_REMEMBER]);
+
+        let cfg = ServerConfig::default();
+        let get = |name: &str, arguments: Value| {
+            let params =
+                serde_json::from_value(serde_json::json!({ "name": name, "arguments": arguments }))
+                    .expect("parse prompt params");
+            handle_prompts_get(&cfg, &params)
+        };
+
+        let remember = get(
+            PROMPT_AGENTS_REMEMBER,
+            serde_json::json!({ "scope": "delta" }),
+        )
+        .expect("remember prompt without topic needs no layers");
+        let text = remember["messages"][0]["content"]["text"]
+            .as_str()
+            .expect("text content");
+        assert!(text.contains(TOOL_AGENTS_CONTEXT_WRITE));
+        assert!(text.contains("\"delta\""));
+
+        let err = get(PROMPT_AGENTS_RECALL, serde_json::json!({})).expect_err("task is required");
+        assert_eq!(err.code, -32602);
+        let err = get("nope", serde_json::json!({})).expect_err("unknown prompt");
+        assert_eq!(err.code, -32602);
+    }
+
+    fn write_test_layer(path: &Path, chunks: &[(u32, &str, &str, u64)]) {
+        let schema = agentsdb_format::LayerSchema {
+            dim: 4,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let mut chunks: Vec<agentsdb_format::ChunkInput> = chunks
+            .iter()
+            .map(|(id, kind, content, created)| agentsdb_format::ChunkInput {
+                id: *id,
+                kind: (*kind).to_string(),
+                content: (*content).to_string(),
+                author: "human".to_string(),
+                confidence: 0.5,
+                created_at_unix_ms: *created,
+                embedding: vec![0.0; 4],
+                sources: vec![agentsdb_format::ChunkSource::SourceString(
+                    "notes.md:1".to_string(),
+                )],
+            })
+            .collect();
+        agentsdb_format::write_layer_atomic(path, &schema, &mut chunks, None).expect("write layer");
+    }
+
+    #[test]
+    fn get_and_list_follow_layer_precedence() {
+        let root = make_temp_dir("get-list");
+        let base = root.join("AGENTS.db");
+        let local = root.join("AGENTS.local.db");
+        write_test_layer(
+            &base,
+            &[(1, "note", "old", 10), (2, "decision", "keep", 20)],
+        );
+        write_test_layer(&local, &[(1, "note", "new", 30), (3, "note", "latest", 40)]);
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            user: None,
+            delta: None,
+            local: Some(local.to_string_lossy().into_owned()),
+            allow_proposal_review: false,
+            max_in_flight: 0,
+            policy: ToolPolicy::default(),
+            config_file: None,
+            audit_log: None,
+            audit_reads: false,
+            metrics_file: None,
+            session: None,
+            trust: Default::default(),
+            workspace: false,
+            search_k: 0,
+            search_layers: Vec::new(),
+            search_kinds: Vec::new(),
+            min_confidence: None,
+        };
+
+        let got = handle_get(
+            &cfg,
+            GetParams {
+                id: 1,
+                layers: None,
+            },
+        )
+        .expect("get");
+        assert_eq!(got["layer"], "Local");
+        assert_eq!(got["chunk"]["content"], "new");
+        assert_eq!(got["chunk"]["sources"].as_array().map(Vec::len), Some(1));
+        assert_eq!(got["hidden_layers"], serde_json::json!(["Base"]));
+        assert!(handle_get(
+            &cfg,
+            GetParams {
+                id: 9,
+                layers: None
+            }
+        )
+        .is_err());
+
+        let list = |params: Value| {
+            handle_list(&cfg, serde_json::from_value(params).expect("list params")).expect("list")
+        };
+        let all = list(serde_json::json!({}));
+        assert_eq!(all["total"], 3);
+        let ids: Vec<u64> = all["items"]
+            .as_array()
+            .expect("items")
+            .iter()
+            .filter_map(|i| i["chunk"]["id"].as_u64())
+            .collect();
+        assert_eq!(ids, [3, 1, 2]);
+
+        let page = list(serde_json::json!({ "kind": ["note"], "since_unix_ms": 35 }));
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["chunk"]["content"], "latest");
+        let page = list(serde_json::json!({ "layers": ["base"], "offset": 1, "limit": 1 }));
+        assert_eq!(page["total"], 2);
+        assert_eq!(page["items"][0]["chunk"]["content"], "old");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn retract_hides_chunk_from_list_and_search() {
+        let root = make_temp_dir("retract");
+        let base = root.join("AGENTS.db");
+        let local = root.join("AGENTS.local.db");
+        write_test_layer(
+            &base,
+            &[(1, "note", "stale advice", 10), (2, "note", "keep", 20)],
+        );
+        write_test_layer(&local, &[(3, "note", "local only", 30)]);
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            user: None,
+            delta: Some(root.join("AGENTS.delta.db").to_string_lossy().into_owned()),
+            local: Some(local.to_string_lossy().into_owned()),
+            allow_proposal_review: false,
+            max_in_flight: 0,
+            policy: ToolPolicy::default(),
+            config_file: None,
+            audit_log: None,
+            audit_reads: false,
+            metrics_file: None,
+            session: None,
+            trust: Default::default(),
+            workspace: false,
+            search_k: 0,
+            search_layers: Vec::new(),
+            search_kinds: Vec::new(),
+            min_confidence: None,
+        };
+        let retract = |id: u32, scope: &str| {
+            handle_retract(
+                &cfg,
+                RetractParams {
+                    id,
+                    reason: "no longer true".to_string(),
+                    scope: scope.to_string(),
+                },
+            )
+        };
+
+        let out = retract(1, "delta").expect("retract base chunk from delta");
+        assert_eq!(out["retracted_id"], 1);
+        let err = retract(3, "delta").expect_err("delta cannot retract local");
+        assert!(err.to_string().contains("cannot retract"), "{err:#}");
+        assert!(retract(1, "delta").is_err(), "already retracted");
+
+        let got = handle_get(
+            &cfg,
+            GetParams {
+                id: 1,
+                layers: None,
+            },
+        )
+        .expect("get");
+        assert_eq!(got["retracted"], true);
+        let list = handle_list(
+            &cfg,
+            serde_json::from_value(serde_json::json!({})).expect("params"),
+        )
+        .expect("list");
+        let contents: Vec<&str> = list["items"]
+            .as_array()
+            .expect("items")
+            .iter()
+            .filter_map(|i| i["chunk"]["content"].as_str())
+            .collect();
+        assert_eq!(contents, ["local only", "keep"]);
+
+        let results = run_search(
+            &cfg,
+            SearchParams {
+                query: "stale advice".to_string(),
+                query_vec: None,
+                k: Some(10),
+                filters: None,
+                layers: None,
+                include_sources_content: false,
+                content_blocks: false,
+            },
+        )
+        .expect("search");
+        assert!(results.iter().all(|r| r.chunk.id.get() != 1));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn update_supersedes_and_tombstones_original() {
+        let root = make_temp_dir("update");
+        let base = root.join("AGENTS.db");
+        write_test_layer(&base, &[(1, "decision", "use tabs", 10)]);
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            user: None,
+            delta: Some(root.join("AGENTS.delta.db").to_string_lossy().into_owned()),
+            local: None,
+            allow_proposal_review: false,
+            max_in_flight: 0,
+            policy: ToolPolicy::default(),
+            config_file: None,
+            audit_log: None,
+            audit_reads: false,
+            metrics_file: None,
+            session: None,
+            trust: Default::default(),
+            workspace: false,
+            search_k: 0,
+            search_layers: Vec::new(),
+            search_kinds: Vec::new(),
+            min_confidence: None,
+        };
+        let params = serde_json::json!({ "id": 1, "content": "use spaces", "scope": "delta" });
+
+        let out = handle_update(
+            &cfg,
+            serde_json::from_value(params.clone()).expect("params"),
+        )
+        .expect("update");
+        assert_eq!(out["superseded_id"], 1);
+        let new_id = out["context_id"].as_u64().expect("new id") as u32;
+
+        let got = handle_get(
+            &cfg,
+            GetParams {
+                id: new_id,
+                layers: None,
+            },
+        )
+        .expect("get revision");
+        assert_eq!(got["layer"], "Delta");
+        assert_eq!(got["chunk"]["kind"], "decision");
+        assert_eq!(got["chunk"]["content"], "use spaces");
+        let sources = got["chunk"]["sources"].to_string();
+        assert!(
+            sources.contains("supersedes:1") && sources.contains("notes.md:1"),
+            "{sources}"
+        );
+
+        let list = handle_list(
+            &cfg,
+            serde_json::from_value(serde_json::json!({})).expect("params"),
+        )
+        .expect("list");
+        assert_eq!(list["total"], 1);
+        assert_eq!(list["items"][0]["chunk"]["content"], "use spaces");
+
+        assert!(
+            handle_update(&cfg, serde_json::from_value(params).expect("params")).is_err(),
+            "original is already superseded"
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn proposals_can_be_reviewed_when_enabled() {
+        let root = make_temp_dir("proposals");
+        let delta = root.join("AGENTS.delta.db");
+        let user = root.join("AGENTS.user.db");
+        write_test_layer(
+            &delta,
+            &[(5, "note", "promote me", 10), (6, "note", "not me", 20)],
+        );
+        let mut cfg = ServerConfig {
+            base: None,
+            user: Some(user.to_string_lossy().into_owned()),
+            delta: Some(delta.to_string_lossy().into_owned()),
+            local: None,
+            allow_proposal_review: false,
+            max_in_flight: 0,
+            policy: ToolPolicy::default(),
+            config_file: None,
+            audit_log: None,
+            audit_reads: false,
+            metrics_file: None,
+            session: None,
+            trust: Default::default(),
+            workspace: false,
+            search_k: 0,
+            search_layers: Vec::new(),
+            search_kinds: Vec::new(),
+            min_confidence: None,
+        };
+        for context_id in [5, 6] {
+            let params = serde_json::json!({ "context_id": context_id, "target": "user" });
+            handle_propose(&cfg, serde_json::from_value(params).expect("params")).expect("propose");
+        }
+
+        let pending =
+            handle_proposals_list(&cfg, &ProposalsListParams { all: false }).expect("list");
+        let proposals = pending["proposals"].as_array().expect("proposals");
+        assert_eq!(proposals.len(), 2);
+        let id_for = |content: &str| {
+            proposals
+                .iter()
+                .find(|p| p["content"] == content)
+                .and_then(|p| p["proposal_id"].as_u64())
+                .expect("proposal for content") as u32
+        };
+        let (accept_id, reject_id) = (id_for("promote me"), id_for("not me"));
+
+        let decide = |cfg: &ServerConfig, tool: &str, id: u32| {
+            let params = ProposalsDecideParams {
+                ids: vec![id],
+                reason: Some("duplicate".to_string()),
+            };
+            handle_proposals_decide(cfg, tool, &params)
+        };
+        let err = decide(&cfg, TOOL_AGENTS_PROPOSALS_ACCEPT, accept_id).expect_err("disabled");
+        assert_eq!(err.code, -32601);
+        assert_eq!(
+            handle_tools_list(&cfg)["tools"].as_array().map(Vec::len),
+            Some(10)
+        );
+
+        cfg.allow_proposal_review = true;
+        decide(&cfg, TOOL_AGENTS_PROPOSALS_ACCEPT, accept_id).expect("accept");
+        decide(&cfg, TOOL_AGENTS_PROPOSALS_REJECT, reject_id).expect("reject");
+        assert!(
+            decide(&cfg, TOOL_AGENTS_PROPOSALS_REJECT, reject_id).is_err(),
+            "not pending"
+        );
 
-        for tool in tools {
-            let name = tool
-                .get("name")
-                .and_then(|v| v.as_str())
-                .expect("tool name must be a string");
-            assert!(
-                is_openai_tool_name_compatible(name),
-                "tool name not OpenAI-compatible: {name}"
-            );
-        }
+        let user_file = agentsdb_format::LayerFile::open(&user).expect("user layer");
+        let promoted: Vec<String> = user_file
+            .chunks()
+            .map(|c| c.expect("chunk").content.to_string())
+            .collect();
+        assert_eq!(promoted, ["promote me"]);
+
+        let pending =
+            handle_proposals_list(&cfg, &ProposalsListParams { all: false }).expect("list");
+        assert_eq!(pending["proposals"].as_array().map(Vec::len), Some(0));
+        let all =
+            handle_proposals_list(&cfg, &ProposalsListParams { all: true }).expect("list all");
+        let statuses: Vec<&str> = all["proposals"]
+            .as_array()
+            .expect("proposals")
+            .iter()
+            .filter_map(|p| p["status"].as_str())
+            .collect();
+        assert!(statuses.contains(&"accepted") && statuses.contains(&"rejected"));
+
+        let _ = std::fs::remove_dir_all(&root);
     }
 
     #[test]
@@ -896,6 +4432,20 @@ mod tests {
             user: None,
             delta: None,
             local: Some("AGENTS.local.db".to_string()),
+            allow_proposal_review: false,
+            max_in_flight: 0,
+            policy: ToolPolicy::default(),
+            config_file: None,
+            audit_log: None,
+            audit_reads: false,
+            metrics_file: None,
+            session: None,
+            trust: Default::default(),
+            workspace: false,
+            search_k: 0,
+            search_layers: Vec::new(),
+            search_kinds: Vec::new(),
+            min_confidence: None,
         };
         let normalized = normalize_config_with_cwd(cfg, &nested).expect("normalize config");
 
@@ -922,6 +4472,20 @@ mod tests {
             user: None,
             delta: None,
             local: None,
+            allow_proposal_review: false,
+            max_in_flight: 0,
+            policy: ToolPolicy::default(),
+            config_file: None,
+            audit_log: None,
+            audit_reads: false,
+            metrics_file: None,
+            session: None,
+            trust: Default::default(),
+            workspace: false,
+            search_k: 0,
+            search_layers: Vec::new(),
+            search_kinds: Vec::new(),
+            min_confidence: None,
         };
         let normalized = normalize_config_with_cwd(cfg, &root).expect("normalize config");
         assert_eq!(
@@ -931,4 +4495,882 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&root);
     }
+
+    #[test]
+    fn check_stops_at_missing_base_layer() {
+        let root = make_temp_dir("check");
+        let cfg = ServerConfig {
+            base: Some(root.join("AGENTS.db").to_string_lossy().into_owned()),
+            user: None,
+            delta: None,
+            local: None,
+            allow_proposal_review: false,
+            max_in_flight: 0,
+            policy: ToolPolicy::default(),
+            config_file: None,
+            audit_log: None,
+            audit_reads: false,
+            metrics_file: None,
+            session: None,
+            trust: Default::default(),
+            workspace: false,
+            search_k: 0,
+            search_layers: Vec::new(),
+            search_kinds: Vec::new(),
+            min_confidence: None,
+        };
+        let report = check(cfg);
+        assert!(!report.ok);
+        let last = report.steps.last().expect("at least one step");
+        assert_eq!(last.step, "open_layers");
+        assert!(last
+            .error
+            .as_deref()
+            .is_some_and(|e| e.contains("base layer not found")));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn writes_to_the_same_layer_run_in_arrival_order() {
+        let req = |json: Value| -> Request { serde_json::from_value(json).expect("request") };
+        let write = req(serde_json::json!({
+            "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+            "params": { "name": TOOL_AGENTS_CONTEXT_WRITE, "arguments": { "scope": "delta" } }
+        }));
+        let search = req(serde_json::json!({
+            "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+            "params": { "name": TOOL_AGENTS_SEARCH, "arguments": { "query": "q" } }
+        }));
+        assert_eq!(written_layers(&write), vec!["delta"]);
+        assert!(written_layers(&search).is_empty());
+
+        let order = WriteOrder::default();
+        let tickets: Vec<_> = (0..8).map(|_| order.tickets(&["delta"])).collect();
+        let ran = std::sync::Mutex::new(Vec::new());
+        std::thread::scope(|scope| {
+            // Start the jobs in reverse so later tickets have to wait for earlier ones.
+            for (i, t) in tickets.iter().enumerate().rev() {
+                let (order, ran) = (&order, &ran);
+                scope.spawn(move || {
+                    order.run(t, || ran.lock().expect("lock").push(i));
+                });
+            }
+        });
+        assert_eq!(ran.into_inner().expect("lock"), (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn a_panicking_write_releases_its_layer() {
+        let order = std::sync::Arc::new(WriteOrder::default());
+        let first = order.tickets(&["delta"]);
+        let second = order.tickets(&["delta"]);
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            order.run(&first, || {
+                std::panic::resume_unwind(Box::new("write failed"))
+            });
+        }));
+        assert!(panicked.is_err());
+
+        let (done, finished) = std::sync::mpsc::channel();
+        let next = std::sync::Arc::clone(&order);
+        std::thread::spawn(move || next.run(&second, || done.send(()).expect("send")));
+        assert!(
+            finished
+                .recv_timeout(std::time::Duration::from_secs(5))
+                .is_ok(),
+            "the write queued behind the panic runs"
+        );
+    }
+
+    #[test]
+    fn layer_changes_are_notified() {
+        let root = make_temp_dir("watch");
+        let local = root.join("AGENTS.local.db");
+        let cfg = ServerConfig {
+            local: Some(local.to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let mut watcher = LayerWatcher::new(&cfg);
+        assert!(watcher.poll().is_empty());
+        write_test_layer(&local, &[(1, "note", "created", 10)]);
+        let seen = watcher.poll();
+
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].method, LAYER_CHANGED_METHOD);
+        assert_eq!(seen[0].params["layer"], "local");
+        assert_eq!(seen[0].params["exists"], true);
+        assert!(watcher.poll().is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn config_file_reload_switches_layers_only_when_valid() {
+        let root = make_temp_dir("reload");
+        for dir in ["a", "b"] {
+            std::fs::create_dir_all(root.join(dir)).expect("create layer dir");
+            write_test_layer(&root.join(dir).join("AGENTS.db"), &[(1, "note", dir, 10)]);
+        }
+        let config_file = root.join(CONFIG_FILE_NAME);
+        std::fs::write(&config_file, "[layers]\nbase = \"a/AGENTS.db\"\n").expect("write config");
+
+        let live = LiveConfig::load(
+            ServerConfig {
+                config_file: Some(config_file.to_string_lossy().into_owned()),
+                ..ServerConfig::default()
+            },
+            root.clone(),
+        )
+        .expect("load config");
+        let base = |cfg: ServerConfig| cfg.base.map(PathBuf::from).expect("base configured");
+        assert_eq!(base(live.current()), root.join("a").join("AGENTS.db"));
+
+        std::fs::write(&config_file, "[layers]\nbase = \"b/AGENTS.db\"\n").expect("write config");
+        live.reload().expect("reload");
+        assert_eq!(base(live.current()), root.join("b").join("AGENTS.db"));
+
+        std::fs::write(&config_file, "[layers]\nbase = \"missing/AGENTS.db\"\n")
+            .expect("write config");
+        assert!(live.reload().is_err());
+        std::fs::write(&config_file, "[layers]\nbasse = \"a/AGENTS.db\"\n").expect("write config");
+        assert!(live.reload().is_err());
+        assert_eq!(base(live.current()), root.join("b").join("AGENTS.db"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn tool_policy_filters_list_and_rejects_calls() {
+        let tool_names = |cfg: &ServerConfig| -> Vec<String> {
+            handle_tools_list(cfg)["tools"]
+                .as_array()
+                .expect("tools")
+                .iter()
+                .filter_map(|t| t["name"].as_str().map(str::to_string))
+                .collect()
+        };
+
+        let read_only = ServerConfig {
+            policy: ToolPolicy::read_only(),
+            ..ServerConfig::default()
+        };
+        let names = tool_names(&read_only);
+        assert!(names.iter().any(|n| n == TOOL_AGENTS_SEARCH));
+        assert!(!names.iter().any(|n| n == TOOL_AGENTS_CONTEXT_WRITE));
+        assert!(!names.iter().any(|n| n == TOOL_AGENTS_CONTEXT_PROPOSE));
+        let err = handle_tools_call(
+            &read_only,
+            ToolCallParams {
+                name: TOOL_AGENTS_CONTEXT_WRITE.to_string(),
+                arguments: serde_json::json!({ "scope": "local" }),
+            },
+        )
+        .expect_err("write rejected");
+        assert_eq!(err.code, -32001);
+        assert_eq!(err.data.expect("error data")["code"], "readonly");
+
+        let local_only = ServerConfig {
+            policy: ToolPolicy {
+                write_scopes: vec!["local".to_string()],
+                ..ToolPolicy::default()
+            },
+            ..ServerConfig::default()
+        };
+        let list = handle_tools_list(&local_only);
+        let write = list["tools"]
+            .as_array()
+            .and_then(|tools| {
+                tools
+                    .iter()
+                    .find(|t| t["name"] == TOOL_AGENTS_CONTEXT_WRITE)
+            })
+            .expect("write tool");
+        assert_eq!(
+            write["inputSchema"]["properties"]["scope"]["enum"],
+            serde_json::json!(["local"])
+        );
+        let req = Request {
+            jsonrpc: None,
+            id: Some(serde_json::json!(1)),
+            method: TOOL_AGENTS_CONTEXT_WRITE.to_string(),
+            params: serde_json::json!({ "scope": "delta" }),
+        };
+        let err = handle_request(&local_only, &req).expect_err("delta write rejected");
+        assert_eq!(err.code, -32001);
+        assert!(err.message.contains("delta"));
+    }
+
+    #[test]
+    fn tool_errors_carry_machine_readable_codes() {
+        let root = make_temp_dir("error-data");
+        let base = root.join("AGENTS.db");
+        let search = |cfg: &ServerConfig, args: Value| {
+            handle_tools_call(
+                cfg,
+                ToolCallParams {
+                    name: TOOL_AGENTS_SEARCH.to_string(),
+                    arguments: args,
+                },
+            )
+            .expect_err("search fails")
+        };
+
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let err = search(&cfg, serde_json::json!({ "query": "q" }));
+        let data = err.data.expect("error data");
+        assert_eq!(data["code"], "layer_missing");
+        assert_eq!(data["layer"], "base");
+
+        write_test_layer(&base, &[(1, "note", "hello", 10)]);
+        let err = search(
+            &cfg,
+            serde_json::json!({ "query": "q", "query_vec": [1.0] }),
+        );
+        let data = err.data.expect("error data");
+        assert_eq!(data["code"], "dim_mismatch");
+        assert_eq!(data["expected"], 4);
+        assert_eq!(data["actual"], 1);
+
+        let err = handle_tools_call(
+            &cfg,
+            ToolCallParams {
+                name: TOOL_AGENTS_CONTEXT_WRITE.to_string(),
+                arguments: serde_json::json!({
+                    "content": "c", "kind": "note", "confidence": 1.0, "scope": "local"
+                }),
+            },
+        )
+        .expect_err("local layer not configured");
+        assert_eq!(err.data.expect("error data")["code"], "layer_missing");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn kind_taxonomy_is_enforced_and_listed_in_the_write_schema() {
+        let root = make_temp_dir("kinds");
+        let base = root.join("AGENTS.db");
+        write_test_layer(&base, &[(1, "decision", "use sqlite", 10)]);
+        let policy = agentsdb_format::WritePolicy::standard().allow_base();
+        agentsdb_ops::kinds::add_kind(
+            &base,
+            "decision",
+            "A choice and why it was made",
+            Some(KindValidation::Enforce),
+            &policy,
+        )
+        .expect("add kind");
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            local: Some(root.join("AGENTS.local.db").to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+
+        let list = handle_tools_list(&cfg);
+        let write = list["tools"]
+            .as_array()
+            .and_then(|tools| {
+                tools
+                    .iter()
+                    .find(|t| t["name"] == TOOL_AGENTS_CONTEXT_WRITE)
+            })
+            .expect("write tool");
+        let kind = &write["inputSchema"]["properties"]["kind"];
+        assert_eq!(kind["enum"], serde_json::json!(["decision"]));
+        assert!(kind["description"]
+            .as_str()
+            .is_some_and(|d| d.contains("A choice and why it was made")));
+
+        let write = |kind: &str| {
+            handle_tools_call(
+                &cfg,
+                ToolCallParams {
+                    name: TOOL_AGENTS_CONTEXT_WRITE.to_string(),
+                    arguments: serde_json::json!({
+                        "content": "c", "kind": kind, "confidence": 1.0, "scope": "local"
+                    }),
+                },
+            )
+        };
+        let err = write("Decisions").expect_err("unknown kind rejected");
+        assert_eq!(err.data.expect("error data")["code"], "unknown_kind");
+        assert!(err.message.contains("did you mean \"decision\""));
+        write("decision").expect("listed kind accepted");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn requests_use_layers_of_the_selected_root() {
+        assert_eq!(
+            root_path_from_uri("file:///home/me/My%20Project"),
+            Some(PathBuf::from("/home/me/My Project"))
+        );
+        assert_eq!(
+            root_path_from_uri("file:///C:/work"),
+            Some(PathBuf::from("C:/work"))
+        );
+        assert_eq!(root_path_from_uri("https://example.com/x"), None);
+
+        let root = make_temp_dir("roots");
+        let (app, lib, empty) = (root.join("app"), root.join("lib"), root.join("empty"));
+        for dir in [&app, &lib, &empty] {
+            std::fs::create_dir_all(dir).expect("create root dir");
+        }
+        write_test_layer(&app.join("AGENTS.db"), &[(1, "note", "app note", 10)]);
+        write_test_layer(&lib.join("AGENTS.db"), &[(1, "note", "lib note", 10)]);
+
+        let raw = ServerConfig::default();
+        let req = |json: Value| -> Request { serde_json::from_value(json).expect("request") };
+        let roots = Roots::default();
+        assert!(!roots.observe(&req(serde_json::json!({
+            "method": "initialize", "id": 1, "params": { "capabilities": { "roots": {} } }
+        }))));
+        assert!(roots.observe(&req(
+            serde_json::json!({ "method": "notifications/initialized" })
+        )));
+        let uri = |dir: &Path| format!("file://{}", dir.display());
+        roots.handle_response(
+            &raw,
+            &serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": ROOTS_REQUEST_ID,
+                "result": { "roots": [
+                    { "uri": uri(&app), "name": "app" },
+                    { "uri": uri(&lib) },
+                    { "uri": uri(&empty) }
+                ] }
+            }),
+        );
+
+        let get = |root: Option<&str>| -> String {
+            let mut args = serde_json::json!({ "id": 1 });
+            if let Some(root) = root {
+                args["root"] = Value::from(root);
+            }
+            let r = req(serde_json::json!({
+                "method": TOOL_AGENTS_CONTEXT_GET, "id": 2, "params": args
+            }));
+            let cfg = roots.select(&raw, &r).expect("select root");
+            let out = handle_request(&cfg, &r).expect("get");
+            out["chunk"]["content"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+        };
+        assert_eq!(get(None), "app note");
+        assert_eq!(get(Some("lib")), "lib note");
+
+        let missing = req(serde_json::json!({
+            "method": TOOL_AGENTS_CONTEXT_GET, "id": 3, "params": { "id": 1, "root": "empty" }
+        }));
+        assert_eq!(
+            roots.select(&raw, &missing).expect_err("no layers").code,
+            -32602
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn audit_log_records_mutating_calls() {
+        let root = make_temp_dir("audit");
+        let base = root.join("AGENTS.db");
+        write_test_layer(&base, &[(1, "note", "base", 10)]);
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            local: Some(root.join("AGENTS.local.db").to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let log_path = root.join("audit.ndjson");
+        let audit = AuditLog::new(Some(log_path.clone()), false);
+        let req = |json: Value| -> Request { serde_json::from_value(json).expect("request") };
+        audit.observe(&req(serde_json::json!({
+            "method": "initialize", "id": 1,
+            "params": { "clientInfo": { "name": "test-agent", "version": "1.2" } }
+        })));
+
+        let call = |name: &str, args: Value| {
+            let r = req(serde_json::json!({
+                "method": "tools/call", "id": 2, "params": { "name": name, "arguments": args }
+            }));
+            let result = handle_request(&cfg, &r);
+            audit.record(&r, &result).expect("record");
+        };
+        call(
+            TOOL_AGENTS_CONTEXT_WRITE,
+            serde_json::json!({ "content": "c", "kind": "note", "confidence": 1.0, "scope": "local" }),
+        );
+        call(TOOL_AGENTS_SEARCH, serde_json::json!({ "query": "c" }));
+        call(
+            TOOL_AGENTS_CONTEXT_WRITE,
+            serde_json::json!({ "content": "c", "kind": "note", "confidence": 1.0, "scope": "delta" }),
+        );
+
+        let text = std::fs::read_to_string(&log_path).expect("read audit log");
+        let records: Vec<Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("ndjson record"))
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["tool"], TOOL_AGENTS_CONTEXT_WRITE);
+        assert_eq!(records[0]["actor"], "test-agent/1.2");
+        assert_eq!(records[0]["ok"], true);
+        assert!(records[0]["chunk_id"].as_u64().is_some());
+        assert_eq!(records[0]["args_sha256"].as_str().map(str::len), Some(64));
+        assert_eq!(records[1]["ok"], false);
+        assert!(records[1]["error"].is_string());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn audit_log_records_reads_when_enabled() {
+        let root = make_temp_dir("audit_reads");
+        let base = root.join("AGENTS.db");
+        write_test_layer(&base, &[(1, "note", "base", 10)]);
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let log_path = root.join("audit.ndjson");
+        let audit = AuditLog::new(Some(log_path.clone()), true);
+        for (name, args) in [
+            (TOOL_AGENTS_SEARCH, serde_json::json!({ "query": "base" })),
+            (TOOL_AGENTS_CONTEXT_GET, serde_json::json!({ "id": 1 })),
+            (TOOL_AGENTS_CONTEXT_LIST, serde_json::json!({})),
+        ] {
+            let r: Request = serde_json::from_value(serde_json::json!({
+                "method": "tools/call", "id": 2, "params": { "name": name, "arguments": args }
+            }))
+            .expect("request");
+            let result = handle_request(&cfg, &r);
+            audit.record(&r, &result).expect("record");
+        }
+
+        let text = std::fs::read_to_string(&log_path).expect("read audit log");
+        let records: Vec<Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("ndjson record"))
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["tool"], TOOL_AGENTS_SEARCH);
+        assert_eq!(records[0]["query"], "base");
+        assert_eq!(
+            records[0]["retrieved"],
+            serde_json::json!([{ "layer": "base", "id": 1 }])
+        );
+        assert_eq!(records[1]["tool"], TOOL_AGENTS_CONTEXT_GET);
+        assert!(records[1].get("query").is_none());
+        assert_eq!(
+            records[1]["retrieved"],
+            serde_json::json!([{ "layer": "base", "id": 1 }])
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn metrics_file_is_rewritten_after_each_request() {
+        let root = make_temp_dir("metrics");
+        let base = root.join("AGENTS.db");
+        write_test_layer(&base, &[(1, "note", "base", 10)]);
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            local: Some(root.join("AGENTS.local.db").to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let path = root.join("agentsdb.prom");
+        let metrics = MetricsFile::new(Some(path.clone()));
+        let req: Request = serde_json::from_value(serde_json::json!({
+            "method": "tools/call", "id": 1,
+            "params": {
+                "name": TOOL_AGENTS_CONTEXT_WRITE,
+                "arguments": { "content": "c", "kind": "note", "confidence": 1.0, "scope": "local" }
+            }
+        }))
+        .expect("request");
+        let out = handle_job(
+            &cfg,
+            &Roots::default(),
+            &AuditLog::new(None, false),
+            &metrics,
+            Ok(req),
+        );
+        assert!(out.error.is_none());
+
+        let text = std::fs::read_to_string(&path).expect("read metrics file");
+        assert!(text.contains(&format!(
+            "agentsdb_mcp_requests_total{{tool=\"{TOOL_AGENTS_CONTEXT_WRITE}\",ok=\"true\"}}"
+        )));
+        assert!(text.contains("agentsdb_appended_chunks_total{layer=\"AGENTS.local.db\"}"));
+        assert!(text.contains("agentsdb_layer_size_bytes{scope=\"base\"}"));
+        assert!(!root.join("agentsdb.prom.tmp").exists());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn next_line_stops_on_shutdown_or_eof() {
+        let shutdown = ShutdownTrigger::default();
+        let (tx, rx) = std::sync::mpsc::channel();
+        tx.send(Ok("{}".to_string())).expect("send");
+        assert_eq!(
+            next_line(&rx, &shutdown).and_then(Result::ok).as_deref(),
+            Some("{}")
+        );
+
+        // Pending input is not read once shutdown has been requested.
+        tx.send(Ok("{}".to_string())).expect("send");
+        shutdown.clone().shutdown();
+        assert!(next_line(&rx, &shutdown).is_none());
+
+        let (tx, rx) = std::sync::mpsc::channel::<std::io::Result<String>>();
+        drop(tx);
+        assert!(next_line(&rx, &ShutdownTrigger::default()).is_none());
+    }
+
+    #[test]
+    fn session_notes_are_searched_first_and_promoted_to_local() {
+        let root = make_temp_dir("session");
+        let base = root.join("AGENTS.db");
+        let local = root.join("AGENTS.local.db");
+        let session = root.join("sessions").join("AGENTS.session.test.db");
+        write_test_layer(&base, &[(1, "note", "base", 10)]);
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            local: Some(local.to_string_lossy().into_owned()),
+            session: Some(session.to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let call = |name: &str, args: Value| {
+            let r: Request = serde_json::from_value(serde_json::json!({
+                "method": "tools/call", "id": 1, "params": { "name": name, "arguments": args }
+            }))
+            .expect("request");
+            handle_request(&cfg, &r)
+        };
+
+        call(
+            TOOL_AGENTS_SESSION_NOTE,
+            serde_json::json!({ "content": "scratch hypothesis" }),
+        )
+        .expect("session note");
+        let search = |layers: Option<Vec<String>>| {
+            run_search(
+                &cfg,
+                SearchParams {
+                    query: "scratch hypothesis".to_string(),
+                    query_vec: Some(vec![1.0, 0.0, 0.0, 0.0]),
+                    k: Some(10),
+                    filters: None,
+                    layers,
+                    include_sources_content: false,
+                    content_blocks: false,
+                },
+            )
+            .expect("search")
+        };
+        let results = search(None);
+        assert!(results.iter().any(|r| r.layer == LayerId::Session));
+        assert!(search(Some(vec!["base".to_string()]))
+            .iter()
+            .all(|r| r.layer == LayerId::Base));
+
+        call(
+            TOOL_AGENTS_SESSION_END,
+            serde_json::json!({ "action": "promote" }),
+        )
+        .expect("session end");
+        assert!(!session.exists());
+        let file = agentsdb_format::LayerFile::open(&local).expect("open local");
+        let chunks = agentsdb_format::read_all_chunks(&file).expect("read local");
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].content, "scratch hypothesis");
+        assert!(search(None).iter().all(|r| r.layer != LayerId::Session));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn deployment_search_defaults_apply_to_every_search() {
+        let root = make_temp_dir("search_defaults");
+        let base = root.join("AGENTS.db");
+        let local = root.join("AGENTS.local.db");
+        write_test_layer(
+            &base,
+            &[
+                (1, "note", "base note", 10),
+                (2, "decision", "base decision", 20),
+                (3, "decision", "other decision", 30),
+            ],
+        );
+        write_test_layer(&local, &[(4, "decision", "local decision", 40)]);
+        let mut cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            local: Some(local.to_string_lossy().into_owned()),
+            search_k: 1,
+            search_layers: vec!["base".to_string()],
+            search_kinds: vec!["decision".to_string()],
+            ..ServerConfig::default()
+        };
+        let search = |cfg: &ServerConfig, args: Value| {
+            let mut args = args;
+            args["query"] = "decision".into();
+            args["query_vec"] = serde_json::json!([1.0, 0.0, 0.0, 0.0]);
+            let params: SearchParams = serde_json::from_value(args).expect("search params");
+            run_search(cfg, params).expect("search")
+        };
+
+        let results = search(&cfg, serde_json::json!({}));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].layer, LayerId::Base);
+        assert_eq!(results[0].chunk.kind, "decision");
+
+        let results = search(&cfg, serde_json::json!({ "k": 10, "layers": ["local"] }));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk.id.get(), 4);
+        let results = search(&cfg, serde_json::json!({ "filters": { "kind": ["note"] } }));
+        assert!(results.is_empty());
+
+        // The test layers are written with confidence 0.5.
+        cfg.min_confidence = Some(0.6);
+        assert!(search(&cfg, serde_json::json!({ "k": 10 })).is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn search_can_inline_source_chunk_content() {
+        let root = make_temp_dir("sources_content");
+        let base = root.join("AGENTS.db");
+        write_test_layer(&base, &[(5, "note", "the original decision", 10)]);
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            local: Some(root.join("AGENTS.local.db").to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let params: WriteParams = serde_json::from_value(serde_json::json!({
+            "content": "derived note", "kind": "note", "confidence": 1.0, "scope": "local",
+            "sources": [{ "chunk_id": 5 }, { "chunk_id": 99 }, "docs/a.md"]
+        }))
+        .expect("write params");
+        handle_write(&cfg, params).expect("write");
+
+        let search = |include: bool| {
+            let params: SearchParams = serde_json::from_value(serde_json::json!({
+                "query": "derived note", "layers": ["local"], "include_sources_content": include
+            }))
+            .expect("search params");
+            handle_search(&cfg, params).expect("search")
+        };
+        let plain = search(false);
+        assert!(plain[0].get("source_chunks").is_none());
+
+        let expanded = search(true);
+        let sources = expanded[0]["source_chunks"]
+            .as_array()
+            .expect("source_chunks");
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0]["chunk_id"], 5);
+        assert_eq!(sources[0]["layer"], "Base");
+        assert_eq!(sources[0]["content"], "the original decision");
+        assert_eq!(sources[1]["chunk_id"], 99);
+        assert_eq!(sources[1]["missing"], true);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn search_can_return_resource_content_blocks() {
+        let root = make_temp_dir("search_blocks");
+        let base = root.join("AGENTS.db");
+        write_test_layer(
+            &base,
+            &[
+                (5, "note", "deploys run nightly", 10),
+                (7, "note", "other", 20),
+            ],
+        );
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let call = |args: Value| {
+            handle_tools_call(
+                &cfg,
+                serde_json::from_value(serde_json::json!({
+                    "name": TOOL_AGENTS_SEARCH, "arguments": args
+                }))
+                .expect("call params"),
+            )
+            .expect("search")
+        };
+
+        let plain = call(serde_json::json!({ "query": "deploys run nightly", "k": 1 }));
+        assert_eq!(plain["content"][0]["type"], "text");
+
+        let blocks = call(serde_json::json!({
+            "query": "deploys run nightly", "k": 2, "content_blocks": true
+        }));
+        let content = blocks["content"].as_array().expect("content");
+        assert_eq!(content.len(), 2);
+        assert!(content.iter().all(|block| block["type"] == "resource"));
+        let hit = content
+            .iter()
+            .find(|block| block["resource"]["uri"] == "agentsdb://chunk/base/5")
+            .expect("chunk 5");
+        assert_eq!(hit["resource"]["text"], "deploys run nightly");
+        assert_eq!(hit["_meta"]["chunk"]["kind"], "note");
+        assert!(hit["_meta"]["chunk"].get("content").is_none());
+        assert!(hit["_meta"]["score"].is_number());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn status_describes_every_layer() {
+        let root = make_temp_dir("status");
+        let base = root.join("AGENTS.db");
+        write_test_layer(
+            &base,
+            &[(1, "note", "first", 10), (2, "decision", "second", 20)],
+        );
+        let cfg = ServerConfig {
+            base: Some(base.to_string_lossy().into_owned()),
+            local: Some(root.join("AGENTS.local.db").to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let status = handle_status(&cfg).expect("status");
+        let layers = status["layers"].as_array().expect("layers");
+        let row = |name: &str| {
+            layers
+                .iter()
+                .find(|l| l["layer"] == name)
+                .unwrap_or_else(|| panic!("{name} row"))
+        };
+        assert_eq!(row("session")["status"], "unconfigured");
+        assert_eq!(row("local")["status"], "missing");
+        let base_row = row("base");
+        assert_eq!(base_row["status"], "present");
+        assert_eq!(base_row["chunk_count"], 2);
+        assert_eq!(base_row["newest_chunk_unix_ms"], 20);
+        assert_eq!(base_row["index"], "missing");
+        assert!(base_row["modified_unix_ms"].is_u64());
+        assert!(status["embedder"]["profile"].is_object());
+        assert!(status["proposals"].is_null());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn long_writes_are_split_into_linked_parts() {
+        let root = make_temp_dir("write_parts");
+        let local = root.join("AGENTS.local.db");
+        write_test_layer(&local, &[(3, "note", "existing", 10)]);
+        let cfg = ServerConfig {
+            local: Some(local.to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let params: WriteParams = serde_json::from_value(serde_json::json!({
+            "content": "Deploys run from the release branch. ".repeat(300),
+            "kind": "note", "confidence": 1.0, "scope": "local"
+        }))
+        .expect("write params");
+        let written = handle_write(&cfg, params).expect("write");
+        let parts = written["parts"].as_array().expect("parts");
+        assert!(parts.len() > 1);
+        assert_eq!(written["context_id"], 4);
+        assert_eq!(parts[0], 4);
+
+        let file = agentsdb_format::LayerFile::open(&local).expect("open local");
+        let chunks = agentsdb_format::read_all_chunks(&file).expect("read chunks");
+        let last = chunks.last().expect("last part");
+        assert!(matches!(
+            last.sources.last(),
+            Some(agentsdb_format::ChunkSource::SourceString(s))
+                if PartOf::parse(s).is_some_and(|p| p.first == 4 && p.index == p.count)
+        ));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn reflection_summaries_are_split_into_kinds() {
+        let summary = "Moved the build to cargo-nextest.\n\n\
+            ## Decisions\n\
+            - Run tests with nextest\n  in CI too\n\
+            - Keep doctests on cargo test\n\n\
+            ### Gotcha:\n\
+            Nextest skips doctests.\n\n\
+            # Preferences\n\
+            1. Short test names\n\n\
+            ## Follow-ups\n\
+            Drop the old runner.";
+        let (rest, items) = split_reflection(summary);
+        assert_eq!(
+            rest,
+            "Moved the build to cargo-nextest.\n\n## Follow-ups\nDrop the old runner."
+        );
+        assert_eq!(
+            items,
+            vec![
+                ("decision", "Run tests with nextest\nin CI too".to_string()),
+                ("decision", "Keep doctests on cargo test".to_string()),
+                ("gotcha", "Nextest skips doctests.".to_string()),
+                ("preference", "Short test names".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reflect_writes_linked_chunks() {
+        let root = make_temp_dir("reflect");
+        let local = root.join("AGENTS.local.db");
+        write_test_layer(&local, &[(3, "note", "existing", 10)]);
+        let cfg = ServerConfig {
+            local: Some(local.to_string_lossy().into_owned()),
+            ..ServerConfig::default()
+        };
+        let params: ReflectParams = serde_json::from_value(serde_json::json!({
+            "summary": "Fixed the flaky upload test.\n\n## Gotchas\n- Uploads retry twice",
+            "items": [{ "kind": "preference", "content": "Prefer small PRs", "confidence": 0.5 }],
+            "scope": "local"
+        }))
+        .expect("reflect params");
+        let written = handle_reflect(&cfg, params).expect("reflect");
+        assert_eq!(written["context_id"], 4);
+        assert_eq!(written["summary"], serde_json::json!([4]));
+        assert_eq!(
+            written["items"],
+            serde_json::json!([
+                { "id": 5, "kind": "gotcha" },
+                { "id": 6, "kind": "preference" }
+            ])
+        );
+        assert_eq!(written["ids"], serde_json::json!([4, 5, 6]));
+
+        let file = agentsdb_format::LayerFile::open(&local).expect("open local");
+        let chunks = agentsdb_format::read_all_chunks(&file).expect("read chunks");
+        let summary = chunks.iter().find(|c| c.id == 4).expect("summary");
+        assert_eq!(summary.kind, REFLECT_SUMMARY_KIND);
+        assert_eq!(summary.content, "Fixed the flaky upload test.");
+        let gotcha = chunks.iter().find(|c| c.id == 5).expect("gotcha");
+        assert_eq!(gotcha.content, "Uploads retry twice");
+        assert!((gotcha.confidence - 0.8).abs() < f32::EPSILON);
+        let preference = chunks.iter().find(|c| c.id == 6).expect("preference");
+        assert!((preference.confidence - 0.5).abs() < f32::EPSILON);
+        for item in [gotcha, preference] {
+            assert!(item
+                .sources
+                .iter()
+                .any(|s| matches!(s, agentsdb_format::ChunkSource::ChunkId(4))));
+        }
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
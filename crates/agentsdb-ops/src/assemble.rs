@@ -0,0 +1,182 @@
+//! Context assembly for `agentsdb search --assemble`: turns search results into a prompt-ready
+//! block that fits a token budget.
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+use agentsdb_core::types::{ProvenanceRef, SearchResult};
+
+/// Share of a chunk's words that must also appear in an already selected chunk for it to be
+/// dropped as overlapping.
+const OVERLAP_THRESHOLD: f32 = 0.8;
+
+/// Rough token count of `text`: one token per four characters, rounded up. Good enough for
+/// budgeting without depending on a model's tokenizer.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssembledChunk {
+    /// Logical layer name (`local`, `user`, `delta`, `base` or `session`).
+    pub layer: String,
+    pub id: u32,
+    pub kind: String,
+    pub score: f32,
+    pub tokens: usize,
+    pub sources: Vec<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssembledContext {
+    pub budget_tokens: usize,
+    /// Estimated tokens of the selected chunks' content.
+    pub used_tokens: usize,
+    /// Selected chunks, by layer precedence and then score.
+    pub chunks: Vec<AssembledChunk>,
+    /// Results left out because their words mostly repeat a selected chunk.
+    pub dropped_duplicates: usize,
+    /// Results left out because they did not fit the remaining budget.
+    pub dropped_over_budget: usize,
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Whether most of `candidate`'s words already appear in `selected`.
+fn overlaps(candidate: &HashSet<String>, selected: &HashSet<String>) -> bool {
+    if candidate.is_empty() {
+        return true;
+    }
+    let shared = candidate.intersection(selected).count();
+    #[allow(clippy::cast_precision_loss)]
+    let share = shared as f32 / candidate.len() as f32;
+    share >= OVERLAP_THRESHOLD
+}
+
+/// Picks results (best score first) that fit `budget_tokens`, skipping ones whose content
+/// overlaps an already picked chunk, and orders the picks by layer precedence, then score.
+pub fn assemble_context(mut results: Vec<SearchResult>, budget_tokens: usize) -> AssembledContext {
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let mut picked: Vec<(SearchResult, usize)> = Vec::new();
+    let mut picked_words: Vec<HashSet<String>> = Vec::new();
+    let mut used_tokens = 0;
+    let mut dropped_duplicates = 0;
+    let mut dropped_over_budget = 0;
+    for result in results {
+        let content_words = words(&result.chunk.content);
+        if picked_words.iter().any(|w| overlaps(&content_words, w)) {
+            dropped_duplicates += 1;
+            continue;
+        }
+        let tokens = estimate_tokens(&result.chunk.content);
+        if used_tokens + tokens > budget_tokens {
+            dropped_over_budget += 1;
+            continue;
+        }
+        used_tokens += tokens;
+        picked_words.push(content_words);
+        picked.push((result, tokens));
+    }
+
+    picked.sort_by(|(a, _), (b, _)| {
+        a.layer
+            .cmp(&b.layer)
+            .then_with(|| b.score.total_cmp(&a.score))
+    });
+    let chunks = picked
+        .into_iter()
+        .map(|(r, tokens)| AssembledChunk {
+            layer: format!("{:?}", r.layer).to_ascii_lowercase(),
+            id: r.chunk.id.get(),
+            kind: r.chunk.kind,
+            score: r.score,
+            tokens,
+            sources: r
+                .chunk
+                .sources
+                .into_iter()
+                .map(|s| match s {
+                    ProvenanceRef::ChunkId(id) => format!("chunk:{}", id.get()),
+                    ProvenanceRef::SourceString(v) => v,
+                })
+                .collect(),
+            content: r.chunk.content,
+        })
+        .collect();
+    AssembledContext {
+        budget_tokens,
+        used_tokens,
+        chunks,
+        dropped_duplicates,
+        dropped_over_budget,
+    }
+}
+
+impl AssembledContext {
+    /// Markdown block with one section per chunk, ready to paste into a prompt.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("## Project context (AGENTS.db)\n");
+        for c in &self.chunks {
+            out.push_str(&format!("\n### {} ({}:{})\n\n", c.kind, c.layer, c.id));
+            out.push_str(c.content.trim());
+            out.push('\n');
+            if !c.sources.is_empty() {
+                out.push_str(&format!("\nSources: {}\n", c.sources.join(", ")));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentsdb_core::types::{Author, Chunk, ChunkId, LayerId};
+
+    fn result(layer: LayerId, id: u32, score: f32, content: &str) -> SearchResult {
+        SearchResult {
+            layer,
+            score,
+            chunk: Chunk {
+                id: ChunkId(id),
+                kind: "note".to_string(),
+                content: content.to_string(),
+                author: Author::Human,
+                confidence: 1.0,
+                created_at_unix_ms: 0,
+                sources: vec![ProvenanceRef::SourceString("README.md:1".to_string())],
+            },
+            hidden_layers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn selects_under_budget_dedupes_and_orders_by_layer() {
+        let results = vec![
+            result(LayerId::Base, 1, 0.9, "Releases are tagged from main."),
+            result(LayerId::Local, 2, 0.5, "Run tests with cargo test."),
+            result(LayerId::Delta, 3, 0.8, "releases are TAGGED from main"),
+            result(LayerId::User, 4, 0.7, &"x".repeat(400)),
+            result(LayerId::Local, 5, 0.6, "Use pnpm, not npm."),
+        ];
+        let context = assemble_context(results, 30);
+        let picked: Vec<u32> = context.chunks.iter().map(|c| c.id).collect();
+        assert_eq!(picked, [5, 2, 1]);
+        assert_eq!(context.dropped_duplicates, 1);
+        assert_eq!(context.dropped_over_budget, 1);
+        assert_eq!(context.used_tokens, 5 + 7 + 8);
+        assert!(context.used_tokens <= context.budget_tokens);
+
+        let markdown = context.to_markdown();
+        assert!(markdown
+            .starts_with("## Project context (AGENTS.db)\n\n### note (local:5)\n\nUse pnpm"));
+        assert!(markdown.contains("Sources: README.md:1\n"));
+    }
+}
@@ -0,0 +1,500 @@
+//! The checks `agentsdb check` runs on the layers of a repository, e.g. as a CI gate on pull
+//! requests.
+//!
+//! [`run_checks`] never stops at the first problem: every check runs and reports its
+//! [`Finding`]s, so one CI run shows everything that needs fixing.
+//!
+//! - `layers`: every standard layer present opens and all its chunks read.
+//! - `embedder_profile`: the layers record the same embedding profile, and it matches the
+//!   configured embedder (backend, model and dimension).
+//! - `manifest`: the base layer is the output recorded in its `compile --manifest` manifest, and
+//!   still holds every chunk compiled from the inputs recorded there, so compiling them again
+//!   reproduces it.
+//! - `unreviewed`: no chunk has waited in the delta layer longer than the allowed age without
+//!   being reviewed (a proposal for it accepted or rejected).
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use agentsdb_embeddings::embedder::EmbeddingProfile;
+use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
+
+use crate::proposals::ProposalStatus;
+use crate::util::{content_sha256_hex, hex_lower, now_unix_ms};
+
+/// Manifest file name looked for next to the base layer when none is given.
+pub const DEFAULT_MANIFEST: &str = "AGENTS.manifest.json";
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// What [`run_checks`] checks.
+#[derive(Debug, Clone)]
+pub struct CheckOptions {
+    /// Manifest of the base layer; `None` uses [`DEFAULT_MANIFEST`] next to it, if present.
+    pub manifest: Option<PathBuf>,
+    /// Delta chunks older than this many days must have been reviewed.
+    pub max_unreviewed_age_days: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Fails the check.
+    Error,
+    /// Reported, but does not fail the check.
+    Warning,
+}
+
+/// One problem a check found.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// Name of the check (`layers`, `embedder_profile`, `manifest`, `unreviewed`).
+    pub check: &'static str,
+    pub severity: Severity,
+    /// File the finding is about.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_id: Option<u32>,
+    pub message: String,
+}
+
+/// Result of [`run_checks`].
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckReport {
+    /// No finding is an error.
+    pub ok: bool,
+    pub dir: String,
+    /// Names of the checks that ran (`manifest` only runs when there is one).
+    pub checks: Vec<&'static str>,
+    pub findings: Vec<Finding>,
+}
+
+struct Findings {
+    check: &'static str,
+    out: Vec<Finding>,
+}
+
+impl Findings {
+    fn push(&mut self, severity: Severity, path: Option<&Path>, message: String) {
+        self.out.push(Finding {
+            check: self.check,
+            severity,
+            path: path.map(|p| p.display().to_string()),
+            chunk_id: None,
+            message,
+        });
+    }
+
+    fn error(&mut self, path: &Path, message: String) {
+        self.push(Severity::Error, Some(path), message);
+    }
+}
+
+/// Runs every check on the standard layers in `dir`.
+pub fn run_checks(dir: &Path, options: &CheckOptions) -> anyhow::Result<CheckReport> {
+    let paths = agentsdb_embeddings::config::standard_layer_paths_for_dir(dir);
+    let layers: Vec<PathBuf> = [paths.base, paths.user, paths.delta, paths.local]
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect();
+
+    let mut checks = vec!["layers", "embedder_profile"];
+    let mut findings = check_layers(&layers);
+    findings.extend(check_profiles(dir, &layers));
+    let base = dir.join("AGENTS.db");
+    let manifest = options
+        .manifest
+        .clone()
+        .or_else(|| Some(dir.join(DEFAULT_MANIFEST)).filter(|p| p.exists()));
+    if let Some(manifest) = manifest {
+        checks.push("manifest");
+        findings.extend(check_manifest(&manifest, &base));
+    }
+    checks.push("unreviewed");
+    let delta = dir.join("AGENTS.delta.db");
+    findings.extend(check_unreviewed(&delta, options.max_unreviewed_age_days)?);
+
+    Ok(CheckReport {
+        ok: !findings.iter().any(|f| f.severity == Severity::Error),
+        dir: dir.display().to_string(),
+        checks,
+        findings,
+    })
+}
+
+fn check_layers(layers: &[PathBuf]) -> Vec<Finding> {
+    let mut findings = Findings {
+        check: "layers",
+        out: Vec::new(),
+    };
+    if layers.is_empty() {
+        findings.push(Severity::Warning, None, "no layers found".to_string());
+    }
+    for path in layers {
+        let read = agentsdb_format::LayerFile::open(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(agentsdb_format::read_all_chunks(&file)?));
+        if let Err(err) = read {
+            findings.error(path, format!("unreadable layer: {err:#}"));
+        }
+    }
+    findings.out
+}
+
+/// The embedding profile recorded in the layer at `path`, if it opens and has one.
+fn layer_profile(path: &Path) -> Option<anyhow::Result<EmbeddingProfile>> {
+    let file = agentsdb_format::LayerFile::open_lenient(path).ok()?;
+    let bytes = file.layer_metadata_bytes()?;
+    Some(LayerMetadataV1::from_json_bytes(bytes).map(|m| m.embedding_profile))
+}
+
+fn check_profiles(dir: &Path, layers: &[PathBuf]) -> Vec<Finding> {
+    let mut findings = Findings {
+        check: "embedder_profile",
+        out: Vec::new(),
+    };
+    let mut profiles = Vec::new();
+    for path in layers {
+        match layer_profile(path) {
+            Some(Ok(profile)) => profiles.push((path.as_path(), profile)),
+            Some(Err(err)) => findings.error(path, format!("unreadable layer metadata: {err:#}")),
+            None => findings.push(
+                Severity::Warning,
+                Some(path),
+                "layer records no embedding profile".to_string(),
+            ),
+        }
+    }
+    let Some(((path, profile), others)) = profiles.split_first() else {
+        return findings.out;
+    };
+    for (other, other_profile) in others {
+        if other_profile != profile {
+            findings.error(
+                other,
+                format!(
+                    "embedding profile {} differs from {} in {}",
+                    crate::drift::describe_profile(other_profile),
+                    crate::drift::describe_profile(profile),
+                    path.display()
+                ),
+            );
+        }
+    }
+
+    match agentsdb_embeddings::config::get_immutable_embedding_options(dir) {
+        Ok(configured) => {
+            let model = configured.language_model().or(configured.model.as_deref());
+            let mismatch = configured.backend != profile.backend
+                || model.is_some_and(|m| Some(m) != profile.model.as_deref())
+                || configured.dim.is_some_and(|d| d != profile.dim);
+            if mismatch {
+                findings.error(
+                    path,
+                    format!(
+                        "layers were embedded with {} but the configured embedder is {}{}{}",
+                        crate::drift::describe_profile(profile),
+                        configured.backend,
+                        model.map(|m| format!("/{m}")).unwrap_or_default(),
+                        configured
+                            .dim
+                            .map(|d| format!(" (dim {d})"))
+                            .unwrap_or_default()
+                    ),
+                );
+            }
+        }
+        Err(err) => findings.error(
+            path,
+            format!("unable to resolve embedding options: {err:#}"),
+        ),
+    }
+    findings.out
+}
+
+/// The parts of a `compile --manifest` manifest the check reads.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    input_file: Option<ManifestInput>,
+    #[serde(default)]
+    inputs: Vec<ManifestInput>,
+    #[serde(default)]
+    embedding_profile: Option<EmbeddingProfile>,
+    output: ManifestOutput,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestInput {
+    source: String,
+    sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestOutput {
+    sha256: String,
+}
+
+/// Checks `base` against the manifest at `path`. Input sources are resolved relative to the
+/// manifest's directory; inline texts (`inline:<n>`) cannot be checked and are skipped.
+fn check_manifest(path: &Path, base: &Path) -> Vec<Finding> {
+    let mut findings = Findings {
+        check: "manifest",
+        out: Vec::new(),
+    };
+    let manifest = std::fs::read(path)
+        .with_context(|| format!("read {}", path.display()))
+        .and_then(|bytes| {
+            serde_json::from_slice::<Manifest>(&bytes)
+                .with_context(|| format!("parse {}", path.display()))
+        });
+    let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            findings.error(path, format!("{err:#}"));
+            return findings.out;
+        }
+    };
+
+    match std::fs::read(base) {
+        Ok(bytes) => {
+            let sha256 = hex_lower(&agentsdb_embeddings::cache::sha256(&bytes));
+            if sha256 != manifest.output.sha256 {
+                findings.error(
+                    base,
+                    format!(
+                        "layer hash {sha256} is not the {} recorded in {} (changed since it was compiled?)",
+                        manifest.output.sha256,
+                        path.display()
+                    ),
+                );
+            }
+        }
+        Err(err) => findings.error(base, format!("unable to read the base layer: {err}")),
+    }
+    if let (Some(expected), Some(Ok(profile))) = (&manifest.embedding_profile, layer_profile(base))
+    {
+        if *expected != profile {
+            findings.error(
+                base,
+                format!(
+                    "layer was embedded with {}, but {} records {}",
+                    crate::drift::describe_profile(&profile),
+                    path.display(),
+                    crate::drift::describe_profile(expected)
+                ),
+            );
+        }
+    }
+
+    let root = path.parent().unwrap_or_else(|| Path::new("."));
+    if let Some(input) = &manifest.input_file {
+        // Chunks compiled from an input JSON file: its hash covers them all.
+        let file = root.join(&input.source);
+        match std::fs::read_to_string(&file) {
+            Ok(s) if content_sha256_hex(&s) == input.sha256 => {}
+            Ok(_) => findings.error(
+                &file,
+                format!(
+                    "input changed since the base layer was compiled; recompile it and update {}",
+                    path.display()
+                ),
+            ),
+            Err(err) => findings.error(
+                &file,
+                format!("input recorded in {} is unreadable: {err}", path.display()),
+            ),
+        }
+        return findings.out;
+    }
+
+    // Each input is one compiled chunk, labelled with its first source string.
+    let compiled: HashSet<(String, String)> = match agentsdb_format::LayerFile::open_lenient(base)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| Ok(agentsdb_format::read_all_chunks(&file)?))
+    {
+        Ok(chunks) => chunks
+            .into_iter()
+            .map(|c| (chunk_label(&c), content_sha256_hex(&c.content)))
+            .collect(),
+        // Reported by the `layers` check.
+        Err(_) => return findings.out,
+    };
+    let mut missing_files = BTreeMap::new();
+    for input in &manifest.inputs {
+        if !compiled.contains(&(input.source.clone(), input.sha256.clone())) {
+            findings.error(
+                base,
+                format!(
+                    "compiled chunk {} recorded in {} is missing or changed",
+                    input.source,
+                    path.display()
+                ),
+            );
+        }
+        if !input.source.starts_with("inline:") {
+            let file = root.join(input_file_of(&input.source));
+            if !file.exists() {
+                missing_files.entry(file).or_insert(&input.source);
+            }
+        }
+    }
+    for (file, source) in missing_files {
+        findings.push(
+            Severity::Warning,
+            Some(&file),
+            format!(
+                "source of {source} no longer exists; the base layer cannot be recompiled from it"
+            ),
+        );
+    }
+    findings.out
+}
+
+/// The label `compile` records a chunk's input under: its first source string.
+fn chunk_label(chunk: &agentsdb_format::ChunkInput) -> String {
+    chunk
+        .sources
+        .iter()
+        .find_map(|s| match s {
+            agentsdb_format::ChunkSource::SourceString(v) => Some(v.clone()),
+            agentsdb_format::ChunkSource::ChunkId(_) => None,
+        })
+        .unwrap_or_else(|| format!("chunk:{}", chunk.id))
+}
+
+/// The file a manifest input label (`<path>:<line>`) names.
+fn input_file_of(source: &str) -> &str {
+    match source.rsplit_once(':') {
+        Some((file, line)) if !line.is_empty() && line.bytes().all(|b| b.is_ascii_digit()) => file,
+        _ => source,
+    }
+}
+
+fn check_unreviewed(delta: &Path, max_age_days: u64) -> anyhow::Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    if !delta.exists() {
+        return Ok(findings);
+    }
+    let Ok(file) = agentsdb_format::LayerFile::open_lenient(delta) else {
+        // Reported by the `layers` check.
+        return Ok(findings);
+    };
+    let chunks = agentsdb_format::read_all_chunks(&file)
+        .with_context(|| format!("read {}", delta.display()))?;
+    let mut retracted = HashSet::new();
+    let mut latest = BTreeMap::new();
+    for chunk in chunks {
+        if chunk.kind == agentsdb_query::KIND_TOMBSTONE {
+            retracted.extend(chunk.sources.iter().filter_map(|s| match s {
+                agentsdb_format::ChunkSource::ChunkId(id) => Some(*id),
+                agentsdb_format::ChunkSource::SourceString(_) => None,
+            }));
+        } else if !chunk.kind.starts_with("meta.")
+            && chunk.kind != agentsdb_embeddings::config::KIND_OPTIONS
+        {
+            latest.insert(chunk.id, chunk);
+        }
+    }
+    let reviewed: HashSet<u32> = crate::proposals::load_proposal_states(delta)?
+        .into_values()
+        .filter(|s| s.status != ProposalStatus::Pending)
+        .map(|s| s.context_id)
+        .collect();
+
+    let cutoff = now_unix_ms().saturating_sub(max_age_days.saturating_mul(DAY_MS));
+    for chunk in latest.into_values() {
+        // Timestamps of 0 come from deterministic builds, not from agents.
+        if retracted.contains(&chunk.id)
+            || reviewed.contains(&chunk.id)
+            || chunk.created_at_unix_ms == 0
+            || chunk.created_at_unix_ms > cutoff
+        {
+            continue;
+        }
+        let age_days = now_unix_ms().saturating_sub(chunk.created_at_unix_ms) / DAY_MS;
+        findings.push(Finding {
+            check: "unreviewed",
+            severity: Severity::Error,
+            path: Some(delta.display().to_string()),
+            chunk_id: Some(chunk.id),
+            message: format!(
+                "{} chunk has waited {age_days} days without review (limit {max_age_days}): {}",
+                chunk.kind,
+                crate::util::truncate_preview(&chunk.content, 80)
+            ),
+        });
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+    use agentsdb_format::{ChunkInput, ChunkSource};
+
+    #[test]
+    fn checks_report_stale_deltas_and_manifest_drift() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let by_mcp = |id: u32, content: &str, created_at_unix_ms: u64| ChunkInput {
+            author: "mcp".to_string(),
+            created_at_unix_ms,
+            ..chunk(id, content)
+        };
+        let base = dir.path().join("AGENTS.db");
+        let mut rules = by_mcp(1, "rules", 0);
+        rules.sources = vec![ChunkSource::SourceString("AGENTS.md:1".to_string())];
+        write_layer(&base, vec![rules])?;
+        let old = now_unix_ms() - 30 * DAY_MS;
+        write_layer(
+            &dir.path().join("AGENTS.delta.db"),
+            vec![by_mcp(1, "stale", old), by_mcp(2, "fresh", now_unix_ms())],
+        )?;
+        std::fs::write(dir.path().join("AGENTS.md"), "rules")?;
+        let base_sha256 = hex_lower(&agentsdb_embeddings::cache::sha256(&std::fs::read(&base)?));
+        let manifest = serde_json::json!({
+            "v": 1,
+            "inputs": [{ "source": "AGENTS.md:1", "sha256": content_sha256_hex("rules") }],
+            "output": { "sha256": base_sha256 },
+        });
+        std::fs::write(dir.path().join(DEFAULT_MANIFEST), manifest.to_string())?;
+
+        let options = CheckOptions {
+            manifest: None,
+            max_unreviewed_age_days: 14,
+        };
+        let report = run_checks(dir.path(), &options)?;
+        assert_eq!(
+            report.checks,
+            ["layers", "embedder_profile", "manifest", "unreviewed"]
+        );
+        let errors: Vec<(&str, Option<u32>)> = report
+            .findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .map(|f| (f.check, f.chunk_id))
+            .collect();
+        assert_eq!(errors, [("unreviewed", Some(1))]);
+        assert!(!report.ok);
+
+        // Rewritten by hand after compiling: both the hash and the chunk drift.
+        write_layer(&base, vec![by_mcp(1, "edited", 0)])?;
+        let relaxed = CheckOptions {
+            manifest: None,
+            max_unreviewed_age_days: 60,
+        };
+        let report = run_checks(dir.path(), &relaxed)?;
+        let errors: Vec<&str> = report
+            .findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .map(|f| f.check)
+            .collect();
+        assert_eq!(errors, ["manifest", "manifest"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,440 @@
+//! Exact and near-duplicate detection across layers, used by `agentsdb dedupe`.
+
+use agentsdb_core::types::LayerId;
+use agentsdb_format::{LayerFile, SourceRef};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::util::{content_sha256_hex, truncate_preview};
+
+/// A visible chunk that is part of a [`DuplicateGroup`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupeChunk {
+    /// Path of the layer holding the chunk, as given in the [`agentsdb_query::LayerSet`].
+    pub layer: String,
+    pub id: u32,
+    pub kind: String,
+    pub confidence: f32,
+    pub created_at_unix_ms: u64,
+    pub content_preview: String,
+    #[serde(skip)]
+    pub layer_id: LayerId,
+    #[serde(skip)]
+    pub content: String,
+    /// String sources (`file:line` style provenance), carried over when merging.
+    #[serde(skip)]
+    pub sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// Same content hash.
+    Exact,
+    /// Cosine similarity of the embeddings at or above the threshold.
+    Near,
+}
+
+/// A chunk found to duplicate the kept chunk of its group.
+#[derive(Debug, Clone, Serialize)]
+pub struct Duplicate {
+    #[serde(flatten)]
+    pub chunk: DedupeChunk,
+    pub match_kind: MatchKind,
+    pub similarity: f32,
+}
+
+/// Chunks that duplicate each other. `keep` is the one to leave in place: the chunk in the most
+/// durable layer (base, then delta, user, local), then the oldest, then the lowest id.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub keep: DedupeChunk,
+    pub duplicates: Vec<Duplicate>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DedupeOptions {
+    /// Near-duplicate cosine similarity threshold; `None` only reports exact duplicates.
+    pub threshold: Option<f32>,
+    /// Only compare chunks stored in the same layer.
+    pub same_layer_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupeReport {
+    /// Number of visible chunks compared.
+    pub scanned: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f32>,
+    pub groups: Vec<DuplicateGroup>,
+    /// Every visible (layer, id), to tell whether a tombstone would hide more than its target.
+    #[serde(skip)]
+    pub visible: BTreeSet<(LayerId, u32)>,
+}
+
+/// What [`resolve_group`] did with one group.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GroupResolution {
+    /// `(layer, id)` of each duplicate that was tombstoned.
+    pub retracted: Vec<(String, u32)>,
+    /// Duplicates left in place, with the reason.
+    pub skipped: Vec<(String, u32, String)>,
+    /// Id of the revision of the kept chunk that absorbed the duplicates' sources.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_into: Option<u32>,
+}
+
+/// Scope accepted by [`crate::retract_chunks`] for the layer at `path`, if it is writable.
+fn writable_scope(path: &str) -> Option<&'static str> {
+    match Path::new(path).file_name().and_then(|s| s.to_str()) {
+        Some("AGENTS.local.db") => Some("local"),
+        Some("AGENTS.delta.db") => Some("delta"),
+        _ => None,
+    }
+}
+
+/// Tombstones the duplicates of `group` that sit in a writable layer (local or delta), each in
+/// its own layer. A duplicate is skipped when its tombstone would also hide another visible
+/// chunk with the same id in a lower-precedence layer.
+///
+/// With `merge`, the kept chunk (when writable) is then superseded by a revision carrying the
+/// string sources and highest confidence of the retracted duplicates.
+pub fn resolve_group(
+    report: &DedupeReport,
+    group: &DuplicateGroup,
+    merge: bool,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<GroupResolution> {
+    let mut resolution = GroupResolution::default();
+    let mut by_layer: BTreeMap<&str, Vec<&DedupeChunk>> = BTreeMap::new();
+    for dup in &group.duplicates {
+        let chunk = &dup.chunk;
+        let reason = if writable_scope(&chunk.layer).is_none() {
+            Some("not in a writable layer (local or delta)".to_string())
+        } else {
+            report
+                .visible
+                .iter()
+                .filter(|(layer_id, id)| *id == chunk.id && *layer_id > chunk.layer_id)
+                .find(|(layer_id, id)| {
+                    !group
+                        .duplicates
+                        .iter()
+                        .any(|d| d.chunk.layer_id == *layer_id && d.chunk.id == *id)
+                })
+                .map(|_| {
+                    format!(
+                        "a tombstone for id {} would also hide it in a lower-precedence layer",
+                        chunk.id
+                    )
+                })
+        };
+        match reason {
+            Some(reason) => resolution
+                .skipped
+                .push((chunk.layer.clone(), chunk.id, reason)),
+            None => by_layer.entry(&chunk.layer).or_default().push(chunk),
+        }
+    }
+
+    let keep = &group.keep;
+    let reason = format!("duplicate of {}:{}", keep.layer, keep.id);
+    for (layer, chunks) in &by_layer {
+        let path = Path::new(layer);
+        let scope = writable_scope(layer).unwrap_or_default();
+        let ids: Vec<u32> = chunks.iter().map(|c| c.id).collect();
+        crate::retract_chunks(path, path, scope, &ids, &reason, tool_name, tool_version)
+            .with_context(|| format!("retract duplicates in {layer}"))?;
+        resolution
+            .retracted
+            .extend(ids.into_iter().map(|id| ((*layer).to_string(), id)));
+    }
+
+    if merge && writable_scope(&keep.layer).is_some() {
+        let retracted: Vec<&DedupeChunk> = by_layer.values().flatten().copied().collect();
+        let mut extra_sources = Vec::new();
+        for source in retracted.iter().flat_map(|c| &c.sources) {
+            if !keep.sources.contains(source) && !extra_sources.contains(source) {
+                extra_sources.push(source.clone());
+            }
+        }
+        let confidence = retracted
+            .iter()
+            .map(|c| c.confidence)
+            .fold(keep.confidence, f32::max);
+        if !extra_sources.is_empty() || confidence > keep.confidence {
+            let path = Path::new(&keep.layer);
+            let outcome = crate::supersede_chunk(
+                path,
+                path,
+                keep.id,
+                None,
+                &keep.content,
+                Some(confidence),
+                &extra_sources,
+                Some("merged with its duplicates"),
+                tool_name,
+                tool_version,
+            )
+            .with_context(|| format!("merge duplicates into {}:{}", keep.layer, keep.id))?;
+            resolution.merged_into = Some(outcome.id);
+        }
+    }
+    Ok(resolution)
+}
+
+/// Groups the visible chunks of `layers` (not retracted, not tombstones, options or `meta.*`
+/// records) into exact and near duplicates.
+pub fn find_duplicates(
+    layers: &agentsdb_query::LayerSet,
+    options: DedupeOptions,
+) -> anyhow::Result<DedupeReport> {
+    let mut opened = Vec::new();
+    let mut paths = BTreeMap::new();
+    for (layer_id, path) in [
+        (LayerId::Local, &layers.local),
+        (LayerId::User, &layers.user),
+        (LayerId::Delta, &layers.delta),
+        (LayerId::Base, &layers.base),
+    ] {
+        if let Some(path) = path {
+            let file = LayerFile::open_lenient(path).with_context(|| format!("open {path}"))?;
+            opened.push((layer_id, file));
+            paths.insert(layer_id, path.clone());
+        }
+    }
+    agentsdb_query::validate_schema_compatible(&opened).context("layers are not compatible")?;
+    let (mut candidates, visible) = read_candidates(&opened, &paths)?;
+
+    // Keep-preference order: every group is led by its first member in this order.
+    candidates.sort_by(|a, b| {
+        b.chunk
+            .layer_id
+            .cmp(&a.chunk.layer_id)
+            .then(a.chunk.created_at_unix_ms.cmp(&b.chunk.created_at_unix_ms))
+            .then(a.chunk.id.cmp(&b.chunk.id))
+    });
+
+    let mut grouped = vec![false; candidates.len()];
+    let mut groups = Vec::new();
+    for i in 0..candidates.len() {
+        if grouped[i] {
+            continue;
+        }
+        let keep = &candidates[i];
+        let mut duplicates = Vec::new();
+        for j in i + 1..candidates.len() {
+            let other = &candidates[j];
+            if grouped[j]
+                || (options.same_layer_only && other.chunk.layer_id != keep.chunk.layer_id)
+            {
+                continue;
+            }
+            let (match_kind, similarity) = if other.hash == keep.hash {
+                (MatchKind::Exact, 1.0)
+            } else {
+                let similarity = dot(&keep.embedding, &other.embedding);
+                match options.threshold {
+                    Some(threshold) if similarity >= threshold => (MatchKind::Near, similarity),
+                    _ => continue,
+                }
+            };
+            grouped[j] = true;
+            duplicates.push(Duplicate {
+                chunk: other.chunk.clone(),
+                match_kind,
+                similarity,
+            });
+        }
+        if !duplicates.is_empty() {
+            groups.push(DuplicateGroup {
+                keep: keep.chunk.clone(),
+                duplicates,
+            });
+        }
+    }
+
+    Ok(DedupeReport {
+        scanned: candidates.len(),
+        threshold: options.threshold,
+        groups,
+        visible,
+    })
+}
+
+/// The visible chunks of `opened`, with every visible (layer, id).
+fn read_candidates(
+    opened: &[(LayerId, LayerFile)],
+    paths: &BTreeMap<LayerId, String>,
+) -> anyhow::Result<(Vec<Candidate>, BTreeSet<(LayerId, u32)>)> {
+    let retracted = agentsdb_query::retracted_chunk_ids(opened)?;
+
+    let mut candidates = Vec::new();
+    let mut visible = BTreeSet::new();
+    for (layer_id, file) in opened {
+        for chunk in file.latest_chunks()? {
+            if chunk.kind == agentsdb_query::KIND_TOMBSTONE
+                || chunk.kind == agentsdb_embeddings::config::KIND_OPTIONS
+                || chunk.kind.starts_with("meta.")
+                || agentsdb_query::is_retracted(&retracted, *layer_id, chunk.id)
+            {
+                continue;
+            }
+            visible.insert((*layer_id, chunk.id));
+            let mut embedding = vec![0.0; file.embedding_dim()];
+            file.read_embedding_row_f32(chunk.embedding_row, &mut embedding)?;
+            let sources = file
+                .sources_for(chunk.rel_start, chunk.rel_count)?
+                .into_iter()
+                .filter_map(|s| match s {
+                    SourceRef::String(v) => Some(v.to_string()),
+                    SourceRef::ChunkId(_) => None,
+                })
+                .collect();
+            candidates.push(Candidate {
+                hash: content_sha256_hex(chunk.content),
+                embedding: normalized(embedding),
+                chunk: DedupeChunk {
+                    layer: paths.get(layer_id).cloned().unwrap_or_default(),
+                    id: chunk.id,
+                    kind: chunk.kind.to_string(),
+                    confidence: chunk.confidence,
+                    created_at_unix_ms: chunk.created_at_unix_ms,
+                    content_preview: truncate_preview(chunk.content, 120),
+                    layer_id: *layer_id,
+                    content: chunk.content.to_string(),
+                    sources,
+                },
+            });
+        }
+    }
+    Ok((candidates, visible))
+}
+
+struct Candidate {
+    hash: String,
+    /// Unit-length embedding (all zeros for a zero vector), so the dot product is the cosine.
+    embedding: Vec<f32>,
+    chunk: DedupeChunk,
+}
+
+fn normalized(mut v: Vec<f32>) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in &mut v {
+            *x /= norm;
+        }
+    }
+    v
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{embedded, write_layer};
+
+    #[test]
+    fn groups_exact_and_near_duplicates_keeping_the_base_chunk() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let base = dir.path().join("AGENTS.db");
+        let local = dir.path().join("AGENTS.local.db");
+        write_layer(
+            &base,
+            vec![
+                embedded(1, "run cargo test", [1.0, 0.0]),
+                embedded(2, "other", [0.0, 1.0]),
+            ],
+        )
+        .expect("write layer");
+        write_layer(
+            &local,
+            vec![
+                embedded(7, "run cargo test", [1.0, 0.0]),
+                embedded(8, "use cargo test", [0.99, 0.1]),
+            ],
+        )
+        .expect("write layer");
+        let layers = agentsdb_query::LayerSet {
+            base: Some(base.display().to_string()),
+            user: None,
+            delta: None,
+            local: Some(local.display().to_string()),
+        };
+
+        let exact = find_duplicates(&layers, DedupeOptions::default()).expect("dedupe");
+        assert_eq!(exact.scanned, 4);
+        assert_eq!(exact.groups.len(), 1);
+        assert_eq!(exact.groups[0].keep.id, 1);
+        assert_eq!(exact.groups[0].duplicates[0].chunk.id, 7);
+        assert_eq!(exact.groups[0].duplicates[0].match_kind, MatchKind::Exact);
+
+        let near = DedupeOptions {
+            threshold: Some(0.95),
+            same_layer_only: false,
+        };
+        let report = find_duplicates(&layers, near).expect("dedupe");
+        let ids: Vec<u32> = report.groups[0]
+            .duplicates
+            .iter()
+            .map(|d| d.chunk.id)
+            .collect();
+        assert_eq!(ids, [7, 8]);
+        assert_eq!(report.groups[0].duplicates[1].match_kind, MatchKind::Near);
+
+        let same_layer = DedupeOptions {
+            threshold: Some(0.95),
+            same_layer_only: true,
+        };
+        let report = find_duplicates(&layers, same_layer).expect("dedupe");
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.groups[0].keep.id, 7);
+    }
+
+    #[test]
+    fn resolving_tombstones_writable_duplicates_only() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let base = dir.path().join("AGENTS.db");
+        let local = dir.path().join("AGENTS.local.db");
+        write_layer(
+            &base,
+            vec![
+                embedded(1, "same", [1.0, 0.0]),
+                embedded(2, "same", [1.0, 0.0]),
+            ],
+        )
+        .expect("write layer");
+        write_layer(
+            &local,
+            vec![
+                embedded(7, "same", [1.0, 0.0]),
+                embedded(2, "other", [0.0, 1.0]),
+            ],
+        )
+        .expect("write layer");
+        let layers = agentsdb_query::LayerSet {
+            base: Some(base.display().to_string()),
+            user: None,
+            delta: None,
+            local: Some(local.display().to_string()),
+        };
+
+        let report = find_duplicates(&layers, DedupeOptions::default()).expect("dedupe");
+        let group = &report.groups[0];
+        let resolution = resolve_group(&report, group, false, "test", "0").expect("resolve");
+        assert_eq!(resolution.retracted, [(local.display().to_string(), 7)]);
+        // Base chunk 2 cannot be retracted from a writable layer.
+        assert_eq!(resolution.skipped.len(), 1);
+        assert_eq!(resolution.skipped[0].1, 2);
+
+        let after = find_duplicates(&layers, DedupeOptions::default()).expect("dedupe");
+        assert_eq!(after.groups[0].duplicates.len(), 1);
+        assert_eq!(after.scanned, 3);
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::util::content_sha256_hex;
+
+/// One side of a [`ChunkDiff`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffSide {
+    pub kind: String,
+    pub content: String,
+    pub content_sha256: String,
+    pub confidence: f32,
+    pub author: String,
+    pub created_at_unix_ms: u64,
+}
+
+/// A chunk id present in one or both layers, with its latest version on each side.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChunkDiff {
+    pub id: u32,
+    pub from: Option<DiffSide>,
+    pub to: Option<DiffSide>,
+}
+
+/// Chunk-level difference between two layers, matched by id and compared by content hash.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LayerDiff {
+    /// Ids only in `from` (what promoting them would add to `to`).
+    pub added: Vec<ChunkDiff>,
+    /// Ids only in `to`.
+    pub removed: Vec<ChunkDiff>,
+    /// Ids in both layers whose kind or content differ.
+    pub changed: Vec<ChunkDiff>,
+    /// Number of ids in both layers with identical kind and content.
+    pub unchanged: usize,
+}
+
+/// Latest version of each chunk id in the layer at `path`. A missing layer has no chunks.
+fn latest_chunks(path: &Path) -> anyhow::Result<BTreeMap<u32, DiffSide>> {
+    let mut out = BTreeMap::new();
+    if !path.exists() {
+        return Ok(out);
+    }
+    let file = agentsdb_format::LayerFile::open_lenient(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    for chunk in file.chunks() {
+        let chunk = chunk.with_context(|| format!("read chunk from {}", path.display()))?;
+        out.insert(
+            chunk.id,
+            DiffSide {
+                kind: chunk.kind.to_string(),
+                content: chunk.content.to_string(),
+                content_sha256: content_sha256_hex(chunk.content),
+                confidence: chunk.confidence,
+                author: chunk.author.to_string(),
+                created_at_unix_ms: chunk.created_at_unix_ms,
+            },
+        );
+    }
+    Ok(out)
+}
+
+/// Compares the chunks of two layers by id (e.g. delta against user, to review a promotion).
+pub fn diff_layers(from: &Path, to: &Path) -> anyhow::Result<LayerDiff> {
+    let from_chunks = latest_chunks(from)?;
+    let mut to_chunks = latest_chunks(to)?;
+
+    let mut diff = LayerDiff::default();
+    for (id, from_side) in from_chunks {
+        match to_chunks.remove(&id) {
+            None => diff.added.push(ChunkDiff {
+                id,
+                from: Some(from_side),
+                to: None,
+            }),
+            Some(to_side)
+                if to_side.content_sha256 == from_side.content_sha256
+                    && to_side.kind == from_side.kind =>
+            {
+                diff.unchanged += 1;
+            }
+            Some(to_side) => diff.changed.push(ChunkDiff {
+                id,
+                from: Some(from_side),
+                to: Some(to_side),
+            }),
+        }
+    }
+    diff.removed = to_chunks
+        .into_iter()
+        .map(|(id, to_side)| ChunkDiff {
+            id,
+            from: None,
+            to: Some(to_side),
+        })
+        .collect();
+    Ok(diff)
+}
+
+/// A chunk that disappeared paired with one that appeared, because their embeddings are close.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModifiedChunk {
+    /// Cosine similarity of the two embeddings.
+    pub similarity: f32,
+    /// The chunk as it is in `to`, the older side.
+    pub before: ChunkDiff,
+    /// The chunk as it is in `from`, the newer side.
+    pub after: ChunkDiff,
+}
+
+/// A [`LayerDiff`] whose added and removed ids are paired by embedding similarity, so a chunk
+/// that was rewritten under a new id shows up as modified instead of as an add plus a remove.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SemanticDiff {
+    pub threshold: f32,
+    /// Removed/added pairs at or above `threshold`, most similar first.
+    pub modified: Vec<ModifiedChunk>,
+    /// Everything else, as in [`diff_layers`].
+    pub diff: LayerDiff,
+}
+
+/// Latest embedding of each chunk id in the layer at `path`, L2-normalized.
+fn latest_embeddings(path: &Path) -> anyhow::Result<(u32, BTreeMap<u32, Vec<f32>>)> {
+    let mut out = BTreeMap::new();
+    if !path.exists() {
+        return Ok((0, out));
+    }
+    let file = agentsdb_format::LayerFile::open_lenient(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    let dim = agentsdb_format::schema_of(&file).dim;
+    for chunk in agentsdb_format::read_all_chunks(&file)
+        .with_context(|| format!("read chunks from {}", path.display()))?
+    {
+        let mut embedding = chunk.embedding;
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            for x in &mut embedding {
+                *x /= norm;
+            }
+        }
+        out.insert(chunk.id, embedding);
+    }
+    Ok((dim, out))
+}
+
+/// Like [`diff_layers`], then pairs ids only in `to` with ids only in `from` whose embeddings
+/// have at least `threshold` cosine similarity. Pairs are taken greedily, most similar first,
+/// and each chunk is paired at most once.
+pub fn semantic_diff_layers(
+    from: &Path,
+    to: &Path,
+    threshold: f32,
+) -> anyhow::Result<SemanticDiff> {
+    let mut diff = diff_layers(from, to)?;
+    let (from_dim, from_embeddings) = latest_embeddings(from)?;
+    let (to_dim, to_embeddings) = latest_embeddings(to)?;
+    if from_dim != 0 && to_dim != 0 && from_dim != to_dim {
+        anyhow::bail!(
+            "semantic diff needs layers with the same embedding dim ({} has dim={from_dim}, {} has dim={to_dim})",
+            from.display(),
+            to.display()
+        );
+    }
+
+    let mut candidates = Vec::new();
+    for (ai, added) in diff.added.iter().enumerate() {
+        let Some(a) = from_embeddings.get(&added.id) else {
+            continue;
+        };
+        for (ri, removed) in diff.removed.iter().enumerate() {
+            let Some(r) = to_embeddings.get(&removed.id) else {
+                continue;
+            };
+            let similarity: f32 = a.iter().zip(r).map(|(x, y)| x * y).sum();
+            if similarity >= threshold {
+                candidates.push((similarity, ai, ri));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let mut added_taken = vec![false; diff.added.len()];
+    let mut removed_taken = vec![false; diff.removed.len()];
+    let mut pairs = Vec::new();
+    for (similarity, ai, ri) in candidates {
+        if added_taken[ai] || removed_taken[ri] {
+            continue;
+        }
+        added_taken[ai] = true;
+        removed_taken[ri] = true;
+        pairs.push((similarity, ai, ri));
+    }
+
+    let mut added: Vec<Option<ChunkDiff>> = diff.added.drain(..).map(Some).collect();
+    let mut removed: Vec<Option<ChunkDiff>> = diff.removed.drain(..).map(Some).collect();
+    let mut modified = Vec::with_capacity(pairs.len());
+    for (similarity, ai, ri) in pairs {
+        if let (Some(after), Some(before)) = (added[ai].take(), removed[ri].take()) {
+            modified.push(ModifiedChunk {
+                similarity,
+                before,
+                after,
+            });
+        }
+    }
+    diff.added = added.into_iter().flatten().collect();
+    diff.removed = removed.into_iter().flatten().collect();
+
+    Ok(SemanticDiff {
+        threshold,
+        modified,
+        diff,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{embedded, write_layer};
+
+    #[test]
+    fn semantic_diff_pairs_rewritten_chunks() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let old = dir.path().join("old.db");
+        let new = dir.path().join("new.db");
+        write_layer(
+            &old,
+            vec![
+                embedded(1, "kept", [1.0, 0.0]),
+                embedded(2, "layers are append-only", [0.0, 1.0]),
+                embedded(3, "dropped", [-1.0, 0.0]),
+            ],
+        )
+        .expect("write layer");
+        write_layer(
+            &new,
+            vec![
+                embedded(1, "kept", [1.0, 0.0]),
+                embedded(20, "layers are strictly append-only", [0.1, 1.0]),
+                embedded(30, "brand new", [0.7, -0.7]),
+            ],
+        )
+        .expect("write layer");
+
+        let semantic = semantic_diff_layers(&new, &old, 0.9).expect("diff");
+        assert_eq!(semantic.diff.unchanged, 1);
+        assert_eq!(semantic.modified.len(), 1);
+        assert_eq!(semantic.modified[0].before.id, 2);
+        assert_eq!(semantic.modified[0].after.id, 20);
+        assert!(semantic.modified[0].similarity > 0.99);
+        let added: Vec<u32> = semantic.diff.added.iter().map(|c| c.id).collect();
+        let removed: Vec<u32> = semantic.diff.removed.iter().map(|c| c.id).collect();
+        assert_eq!(added, vec![30]);
+        assert_eq!(removed, vec![3]);
+    }
+}
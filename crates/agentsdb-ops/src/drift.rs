@@ -0,0 +1,294 @@
+//! Embedding drift: rows whose embeddings were computed with another profile than their layer's.
+//!
+//! Each layer records the [`EmbeddingProfile`] its embeddings come from, and searches only accept
+//! layers whose profile matches the query embedder. Promotion copies embeddings between layers
+//! as they are, though, so a layer embedded with one model can end up holding rows embedded with
+//! another of the same dimension, whose cosine scores silently degrade. Promotion tags those rows
+//! with an `embedded-with:<fingerprint>` source ([`EMBEDDED_WITH_SOURCE_PREFIX`]);
+//! [`drift_report`] finds them, along with layers whose profiles disagree, and
+//! [`reembed_drifted`] re-embeds just those rows.
+
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use agentsdb_embeddings::embedder::{EmbedPurpose, Embedder, EmbeddingProfile};
+use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
+use agentsdb_format::{ChunkInput, ChunkSource};
+use agentsdb_query::EMBEDDED_WITH_SOURCE_PREFIX;
+
+/// Embedding profiles of the standard layers of a directory, and the rows that do not match
+/// their layer's.
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    /// No layer differs from the reference profile and no layer holds mixed rows.
+    pub ok: bool,
+    pub dir: String,
+    /// Fingerprint of the profile the other layers are compared with: the base layer's, else the
+    /// first layer's that records one.
+    pub reference: Option<String>,
+    pub layers: Vec<LayerDrift>,
+}
+
+/// One layer of a [`DriftReport`].
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerDrift {
+    /// `base`, `user`, `delta` or `local`.
+    pub layer: String,
+    pub path: String,
+    /// Profile from the layer metadata; `None` for layers written without one.
+    pub profile: Option<EmbeddingProfile>,
+    pub fingerprint: Option<String>,
+    /// Whether the layer's profile differs from the reference profile.
+    pub differs_from_reference: bool,
+    /// Ids of the rows embedded with another profile, by that profile's fingerprint.
+    pub mixed_rows: BTreeMap<String, Vec<u32>>,
+}
+
+impl LayerDrift {
+    pub fn mixed_row_count(&self) -> usize {
+        self.mixed_rows.values().map(Vec::len).sum()
+    }
+}
+
+/// Fingerprint of the profile recorded in layer `metadata`, if it records one.
+pub fn layer_fingerprint(metadata: Option<&[u8]>) -> Option<String> {
+    let metadata = LayerMetadataV1::from_json_bytes(metadata?).ok()?;
+    Some(metadata.embedding_profile.fingerprint())
+}
+
+/// Fingerprint from the `embedded-with:` tag of `chunk`, if it has one.
+pub fn row_fingerprint(chunk: &ChunkInput) -> Option<&str> {
+    chunk.sources.iter().find_map(|s| match s {
+        ChunkSource::SourceString(v) => v.strip_prefix(EMBEDDED_WITH_SOURCE_PREFIX),
+        ChunkSource::ChunkId(_) => None,
+    })
+}
+
+fn is_tag(source: &ChunkSource) -> bool {
+    matches!(source, ChunkSource::SourceString(v) if v.starts_with(EMBEDDED_WITH_SOURCE_PREFIX))
+}
+
+/// Tags `chunk`, moving from a layer with profile fingerprint `from_layer` into one with
+/// `to_layer`, with the profile its embedding was computed with when that is not `to_layer`'s,
+/// and drops the tag when it is. Rows keep their tag when either layer records no profile.
+pub(crate) fn retag(chunk: &mut ChunkInput, from_layer: Option<&str>, to_layer: Option<&str>) {
+    if chunk.kind == agentsdb_query::KIND_TOMBSTONE {
+        return;
+    }
+    let Some(embedded_with) = row_fingerprint(chunk).or(from_layer).map(str::to_string) else {
+        return;
+    };
+    let Some(to_layer) = to_layer else {
+        return;
+    };
+    chunk.sources.retain(|s| !is_tag(s));
+    if embedded_with != to_layer {
+        chunk.sources.push(ChunkSource::SourceString(format!(
+            "{EMBEDDED_WITH_SOURCE_PREFIX}{embedded_with}"
+        )));
+    }
+}
+
+/// Reads the embedding profiles and mixed rows of the standard layers in `dir`.
+pub fn drift_report(dir: &Path) -> anyhow::Result<DriftReport> {
+    let paths = agentsdb_embeddings::config::standard_layer_paths_for_dir(dir);
+    let mut layers = Vec::new();
+    for (layer, path) in [
+        ("base", &paths.base),
+        ("user", &paths.user),
+        ("delta", &paths.delta),
+        ("local", &paths.local),
+    ] {
+        if !path.exists() {
+            continue;
+        }
+        let file = agentsdb_format::LayerFile::open_lenient(path)
+            .with_context(|| format!("open {}", path.display()))?;
+        let profile = match file.layer_metadata_bytes() {
+            Some(bytes) => Some(
+                LayerMetadataV1::from_json_bytes(bytes)
+                    .with_context(|| format!("parse layer metadata of {}", path.display()))?
+                    .embedding_profile,
+            ),
+            None => None,
+        };
+        let mut mixed_rows: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for chunk in agentsdb_format::read_all_chunks(&file)
+            .with_context(|| format!("read chunks from {}", path.display()))?
+        {
+            if chunk.kind == agentsdb_query::KIND_TOMBSTONE {
+                continue;
+            }
+            if let Some(fingerprint) = row_fingerprint(&chunk) {
+                mixed_rows
+                    .entry(fingerprint.to_string())
+                    .or_default()
+                    .push(chunk.id);
+            }
+        }
+        layers.push(LayerDrift {
+            layer: layer.to_string(),
+            path: path.display().to_string(),
+            fingerprint: profile.as_ref().map(EmbeddingProfile::fingerprint),
+            profile,
+            differs_from_reference: false,
+            mixed_rows,
+        });
+    }
+
+    let reference = layers.iter().find_map(|l| l.fingerprint.clone());
+    for layer in &mut layers {
+        layer.differs_from_reference = layer
+            .fingerprint
+            .as_ref()
+            .is_some_and(|f| Some(f) != reference.as_ref());
+    }
+    Ok(DriftReport {
+        ok: layers
+            .iter()
+            .all(|l| !l.differs_from_reference && l.mixed_rows.is_empty()),
+        dir: dir.display().to_string(),
+        reference,
+        layers,
+    })
+}
+
+/// Re-embeds the rows of the layer at `path` tagged as embedded with another profile, with
+/// `embedder`, and drops their tags. The embedder must have the layer's profile.
+///
+/// # Returns
+/// The ids of the re-embedded rows
+pub fn reembed_drifted(
+    path: &Path,
+    embedder: &dyn Embedder,
+    policy: &agentsdb_format::WritePolicy,
+) -> anyhow::Result<Vec<u32>> {
+    policy
+        .check(path)
+        .with_context(|| format!("verify {} is writable", path.display()))?;
+    let file = agentsdb_format::LayerFile::open(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    agentsdb_embeddings::layer_metadata::ensure_layer_metadata_compatible_with_embedder(
+        &file, embedder,
+    )?;
+    let schema = agentsdb_format::schema_of(&file);
+    let metadata = file.layer_metadata_bytes().map(<[u8]>::to_vec);
+    let mut chunks = agentsdb_format::read_all_chunks(&file)
+        .with_context(|| format!("read chunks from {}", path.display()))?;
+    drop(file);
+
+    let drifted: Vec<usize> = chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.kind != agentsdb_query::KIND_TOMBSTONE && row_fingerprint(c).is_some())
+        .map(|(i, _)| i)
+        .collect();
+    if drifted.is_empty() {
+        return Ok(Vec::new());
+    }
+    let inputs: Vec<String> = drifted.iter().map(|&i| chunks[i].content.clone()).collect();
+    let embeddings = embedder
+        .embed_for(EmbedPurpose::Document, &inputs)
+        .with_context(|| format!("embed drifted rows of {}", path.display()))?;
+    if embeddings.len() != drifted.len() {
+        anyhow::bail!(
+            "embedder returned {} embeddings for {} rows",
+            embeddings.len(),
+            drifted.len()
+        );
+    }
+
+    let mut ids = Vec::with_capacity(drifted.len());
+    for (i, embedding) in drifted.into_iter().zip(embeddings) {
+        if embedding.len() != schema.dim as usize {
+            anyhow::bail!(
+                "embedder returned dim {} for a layer of dim {}",
+                embedding.len(),
+                schema.dim
+            );
+        }
+        let chunk = &mut chunks[i];
+        chunk.embedding = embedding;
+        chunk.sources.retain(|s| !is_tag(s));
+        ids.push(chunk.id);
+    }
+    policy
+        .write_layer_atomic(path, &schema, &mut chunks, metadata.as_deref())
+        .with_context(|| format!("rewrite {}", path.display()))?;
+    Ok(ids)
+}
+
+/// `backend/model (dim N)`, for messages.
+pub fn describe_profile(profile: &EmbeddingProfile) -> String {
+    match &profile.model {
+        Some(model) => format!("{}/{model} (dim {})", profile.backend, profile.dim),
+        None => format!("{} (dim {})", profile.backend, profile.dim),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, schema};
+
+    fn profile(model: &str) -> EmbeddingProfile {
+        EmbeddingProfile {
+            backend: "hash".to_string(),
+            model: Some(model.to_string()),
+            revision: None,
+            dim: 2,
+            output_norm: agentsdb_embeddings::embedder::OutputNorm::None,
+        }
+    }
+
+    #[test]
+    fn retag_records_foreign_profiles_only() {
+        let a = profile("a").fingerprint();
+        let b = profile("b").fingerprint();
+        assert_ne!(a, b);
+
+        let mut chunk = chunk(1, "note");
+        retag(&mut chunk, Some(&b), Some(&a));
+        assert_eq!(row_fingerprint(&chunk), Some(b.as_str()));
+        // Promoting it on into another layer keeps the profile it was embedded with...
+        retag(&mut chunk, Some(&a), Some(&a));
+        assert_eq!(row_fingerprint(&chunk), Some(b.as_str()));
+        // ...and drops the tag once it lands in a layer with that profile.
+        retag(&mut chunk, Some(&a), Some(&b));
+        assert_eq!(row_fingerprint(&chunk), None);
+        assert!(chunk.sources.is_empty());
+    }
+
+    #[test]
+    fn report_groups_mixed_rows_by_profile() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let schema = schema();
+        let metadata = |model: &str| LayerMetadataV1::new(profile(model)).to_json_bytes();
+        let b = profile("b").fingerprint();
+
+        let mut user = [chunk(1, "note"), chunk(2, "note"), chunk(3, "note")];
+        retag(&mut user[1], Some(&b), Some(&profile("a").fingerprint()));
+        let user_path = dir.path().join("AGENTS.user.db");
+        agentsdb_format::write_layer_atomic(&user_path, &schema, &mut user, Some(&metadata("a")?))?;
+        let delta_path = dir.path().join("AGENTS.delta.db");
+        agentsdb_format::write_layer_atomic(
+            &delta_path,
+            &schema,
+            &mut [chunk(4, "note")],
+            Some(&metadata("b")?),
+        )?;
+
+        let report = drift_report(dir.path())?;
+        assert!(!report.ok);
+        assert_eq!(report.reference, Some(profile("a").fingerprint()));
+        let [user, delta] = report.layers.as_slice() else {
+            anyhow::bail!("expected user and delta layers, got {:?}", report.layers);
+        };
+        assert_eq!(user.mixed_rows.get(&b), Some(&vec![2]));
+        assert!(!user.differs_from_reference);
+        assert!(delta.differs_from_reference && delta.mixed_rows.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+//! Typed errors for the write, promote and import entry points.
+//!
+//! Every variant has a stable [`code`](WriteError::code) that the servers report to clients
+//! (as `error.data.code` over JSON-RPC and `code` in HTTP error bodies); the message text is for
+//! humans and may change. Failures without a dedicated variant are carried by `Other` and report
+//! `"internal"`.
+
+use std::path::PathBuf;
+
+use agentsdb_core::error::{Error as CoreError, PermissionError, TrustError};
+use agentsdb_embeddings::layer_metadata::ProfileMismatchError;
+use thiserror::Error;
+
+/// Stable code for an error from `agentsdb-core` (permissions, malformed layers, I/O).
+pub const fn core_error_code(err: &CoreError) -> &'static str {
+    match err {
+        CoreError::Permission(e) => permission_error_code(e),
+        CoreError::Format(_) => "invalid_layer",
+        CoreError::Schema(_) => "schema_mismatch",
+        CoreError::Io(_) => "io",
+        CoreError::Trust(_) => "untrusted_layer",
+    }
+}
+
+const fn permission_error_code(err: &PermissionError) -> &'static str {
+    match err {
+        PermissionError::WriteNotPermitted { .. } => "readonly",
+        PermissionError::ScopeNotPermitted { .. } => "scope_mismatch",
+    }
+}
+
+fn did_you_mean(suggestion: Option<&str>) -> String {
+    suggestion
+        .map(|s| format!(" (did you mean {s:?}?)"))
+        .unwrap_or_default()
+}
+
+/// Error from appending chunks to a local or delta layer.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum WriteError {
+    #[error("writes are only allowed for AGENTS.local.db / AGENTS.delta.db (got {path:?})")]
+    NotWritable { path: PathBuf },
+
+    #[error("scope {scope} only allowed for {expected}")]
+    ScopeMismatch { scope: String, expected: String },
+
+    #[error("scope must be 'local' or 'delta' (got {0:?})")]
+    InvalidScope(String),
+
+    #[error("creating a new layer requires dim")]
+    DimRequired,
+
+    #[error("kind {kind:?} is not in the kind taxonomy{}", did_you_mean(.suggestion.as_deref()))]
+    UnknownKind {
+        kind: String,
+        suggestion: Option<String>,
+    },
+
+    #[error("embedding dim mismatch (layer is dim={layer}, options specify dim={configured})")]
+    DimMismatch { layer: usize, configured: usize },
+
+    #[error("rejected by write hook {hook:?}: {reason}")]
+    Rejected { hook: String, reason: String },
+
+    #[error(transparent)]
+    ProfileMismatch(#[from] ProfileMismatchError),
+
+    #[error(transparent)]
+    Core(#[from] CoreError),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl WriteError {
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::NotWritable { .. } => "not_writable",
+            Self::ScopeMismatch { .. } => "scope_mismatch",
+            Self::InvalidScope(_) => "invalid_scope",
+            Self::DimRequired => "dim_required",
+            Self::UnknownKind { .. } => "unknown_kind",
+            Self::DimMismatch { .. } => "dim_mismatch",
+            Self::Rejected { .. } => "policy_violation",
+            Self::ProfileMismatch(_) => "profile_mismatch",
+            Self::Core(e) => core_error_code(e),
+            Self::Other(_) => "internal",
+        }
+    }
+}
+
+/// Error from promoting chunks between layers.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PromoteError {
+    #[error("ids must be non-empty")]
+    EmptyIds,
+
+    #[error("id {id} not found in {path}")]
+    NotFound { id: u32, path: String },
+
+    #[error("schema mismatch between {from} and {to}")]
+    SchemaMismatch { from: String, to: String },
+
+    #[error(transparent)]
+    Core(#[from] CoreError),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl PromoteError {
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::EmptyIds => "invalid_ids",
+            Self::NotFound { .. } => "chunk_not_found",
+            Self::SchemaMismatch { .. } => "schema_mismatch",
+            Self::Core(e) => core_error_code(e),
+            Self::Other(_) => "internal",
+        }
+    }
+}
+
+/// Error from importing exported chunks into a layer.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ImportError {
+    #[error("scope must be local, delta, user, or base (got {0:?})")]
+    InvalidScope(String),
+
+    #[error("scope {scope} expects file named {expected}")]
+    ScopeMismatch { scope: String, expected: String },
+
+    #[error("refusing to write AGENTS.db without allow_base")]
+    BaseNotAllowed,
+
+    #[error("parse import data")]
+    Parse(#[source] anyhow::Error),
+
+    #[error("no chunks found in import")]
+    Empty,
+
+    #[error("chunk {id} has no content and {reason}; cannot import")]
+    MissingContent { id: u32, reason: &'static str },
+
+    #[error("creating a new layer requires dim or input embeddings")]
+    DimRequired,
+
+    #[error("embedding dim mismatch (target dim={target}, options specify dim={configured})")]
+    DimMismatch { target: usize, configured: usize },
+
+    #[error("preserve_ids requires non-zero ids in input")]
+    ZeroId,
+
+    #[error("id {0} already exists in target")]
+    IdExists(u32),
+
+    #[error(transparent)]
+    ProfileMismatch(#[from] ProfileMismatchError),
+
+    #[error(transparent)]
+    Core(#[from] CoreError),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl ImportError {
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidScope(_) => "invalid_scope",
+            Self::ScopeMismatch { .. } => "scope_mismatch",
+            Self::BaseNotAllowed => "base_not_allowed",
+            Self::Parse(_) => "invalid_input",
+            Self::Empty => "empty_import",
+            Self::MissingContent { .. } => "missing_content",
+            Self::DimRequired => "dim_required",
+            Self::DimMismatch { .. } => "dim_mismatch",
+            Self::ZeroId => "invalid_ids",
+            Self::IdExists(_) => "id_exists",
+            Self::ProfileMismatch(_) => "profile_mismatch",
+            Self::Core(e) => core_error_code(e),
+            Self::Other(_) => "internal",
+        }
+    }
+}
+
+/// Stable code of `cause` if it is one of this module's errors (or a core error), without looking
+/// at its sources. `Other` variants have no code.
+pub fn cause_code(cause: &(dyn std::error::Error + 'static)) -> Option<&'static str> {
+    let code = if let Some(e) = cause.downcast_ref::<WriteError>() {
+        e.code()
+    } else if let Some(e) = cause.downcast_ref::<PromoteError>() {
+        e.code()
+    } else if let Some(e) = cause.downcast_ref::<ImportError>() {
+        e.code()
+    } else if let Some(e) = cause.downcast_ref::<CoreError>() {
+        core_error_code(e)
+    } else if let Some(e) = cause.downcast_ref::<PermissionError>() {
+        permission_error_code(e)
+    } else if cause.is::<TrustError>() {
+        "untrusted_layer"
+    } else if cause.is::<ProfileMismatchError>() {
+        "profile_mismatch"
+    } else {
+        return None;
+    };
+    (code != "internal").then_some(code)
+}
+
+/// Stable code of the first typed error in `err`'s chain, or `None` if it has none.
+///
+/// `Other` variants are looked through, so a typed error wrapped by a lower layer still wins.
+pub fn error_code(err: &anyhow::Error) -> Option<&'static str> {
+    err.chain().find_map(cause_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_survive_context_and_other_wrapping() {
+        let err = anyhow::Error::from(WriteError::DimRequired).context("write /api/layer/add");
+        assert_eq!(error_code(&err), Some("dim_required"));
+
+        let inner =
+            anyhow::Error::from(CoreError::Permission(PermissionError::WriteNotPermitted {
+                path: PathBuf::from("AGENTS.db"),
+            }))
+            .context("permission check");
+        let err = anyhow::Error::from(PromoteError::Other(inner));
+        assert_eq!(error_code(&err), Some("readonly"));
+
+        let err = anyhow::anyhow!("something else");
+        assert_eq!(error_code(&err), None);
+    }
+}
@@ -1,20 +1,31 @@
 use anyhow::Context;
 use std::path::Path;
 
+pub mod pgvector;
+#[cfg(feature = "qdrant")]
+pub mod qdrant;
+pub mod v2;
+pub mod vector_store;
+
 use agentsdb_core::export::{
-    ExportBundleV1, ExportChunkV1, ExportLayerSchemaV1, ExportLayerV1, ExportNdjsonRecordV1,
-    ExportSourceV1, ExportToolInfo,
+    ExportBundleV1, ExportBundleV2, ExportChunkV1, ExportLayerProvenanceV2, ExportLayerSchemaV1,
+    ExportLayerV1, ExportLayerV2, ExportNdjsonRecordV2, ExportSourceV1, ExportToolInfo,
+    EXPORT_FORMAT_V1, EXPORT_FORMAT_V2, EXPORT_NDJSON_FORMAT_V2,
 };
 
-use crate::util::{apply_redaction, content_sha256_hex, element_type_str, logical_layer_for_path};
+use crate::util::{
+    apply_redaction, chunk_namespace, content_sha256_hex, element_type_str, logical_layer_for_path,
+    take_redacted_marker,
+};
 
-/// Export a single layer to either JSON or NDJSON format
+/// Export a single layer to JSON, NDJSON or Parquet format
 ///
 /// # Arguments
 /// * `abs_path` - Absolute path to the layer file
 /// * `rel_path` - Relative path/filename for display purposes
-/// * `format` - Either "json" or "ndjson"
+/// * `format` - "json", "ndjson" or "parquet" (needs the `parquet` feature)
 /// * `redact` - Redaction mode: "none", "content", "embeddings", or "all"
+/// * `namespace` - If set, only chunks in this namespace are exported
 /// * `tool_name` - Name of the tool performing the export (e.g., "agentsdb-cli" or "agentsdb-web")
 /// * `tool_version` - Version of the tool
 ///
@@ -25,6 +36,7 @@ pub fn export_layer(
     rel_path: &str,
     format: &str,
     redact: &str,
+    namespace: Option<&str>,
     tool_name: &str,
     tool_version: &str,
 ) -> anyhow::Result<(&'static str, Vec<u8>)> {
@@ -42,7 +54,11 @@ pub fn export_layer(
 
     let chunks = agentsdb_format::read_all_chunks(&file).context("read chunks")?;
     let mut out_chunks = Vec::with_capacity(chunks.len());
-    for c in chunks {
+    for mut c in chunks {
+        if namespace.is_some_and(|ns| chunk_namespace(&c.sources) != Some(ns)) {
+            continue;
+        }
+        let redacted = take_redacted_marker(&mut c.sources);
         let (content, embedding) = apply_redaction(redact, &c.content, &c.embedding);
         let sources = c
             .sources
@@ -54,7 +70,10 @@ pub fn export_layer(
                 }
             })
             .collect();
-        let content_sha256 = content.as_deref().map(content_sha256_hex);
+        // The hash is kept when content is redacted, so importers can still dedupe the chunk. A
+        // chunk that was imported without content exports none, with the hash it came with.
+        let content = content.filter(|_| redacted.is_none());
+        let content_sha256 = redacted.or_else(|| Some(content_sha256_hex(&c.content)));
         out_chunks.push(ExportChunkV1 {
             id: c.id,
             kind: c.kind,
@@ -68,66 +87,111 @@ pub fn export_layer(
         });
     }
 
+    let bundle = ExportBundleV1 {
+        format: EXPORT_FORMAT_V1.to_string(),
+        tool: ExportToolInfo {
+            name: tool_name.to_string(),
+            version: tool_version.to_string(),
+        },
+        layers: vec![ExportLayerV1 {
+            path: rel_path.to_string(),
+            layer: logical_layer_for_path(rel_path).map(|s| s.to_string()),
+            schema,
+            layer_metadata_json,
+            chunks: out_chunks,
+        }],
+    };
+    encode_bundle(bundle, vec![v2::layer_provenance(&file)], format)
+}
+
+/// Serializes `bundle` in `format`. JSON and NDJSON are written as version 2, with
+/// `provenance[i]` recorded for layer `i`; Parquet keeps the version 1 layout.
+fn encode_bundle(
+    bundle: ExportBundleV1,
+    provenance: Vec<ExportLayerProvenanceV2>,
+    format: &str,
+) -> anyhow::Result<(&'static str, Vec<u8>)> {
+    if format == "parquet" {
+        return parquet_body(&bundle);
+    }
+    let bundle = ExportBundleV2 {
+        format: EXPORT_FORMAT_V2.to_string(),
+        tool: bundle.tool,
+        layers: bundle
+            .layers
+            .into_iter()
+            .zip(provenance)
+            .map(|(l, provenance)| ExportLayerV2 {
+                path: l.path,
+                layer: l.layer,
+                schema: l.schema,
+                layer_metadata_json: l.layer_metadata_json,
+                provenance: Some(provenance),
+                chunks: l.chunks.into_iter().map(v2::chunk_to_v2).collect(),
+            })
+            .collect(),
+    };
     match format {
         "json" => {
-            let bundle = ExportBundleV1 {
-                format: "agentsdb.export.v1".to_string(),
-                tool: ExportToolInfo {
-                    name: tool_name.to_string(),
-                    version: tool_version.to_string(),
-                },
-                layers: vec![ExportLayerV1 {
-                    path: rel_path.to_string(),
-                    layer: logical_layer_for_path(rel_path).map(|s| s.to_string()),
-                    schema,
-                    layer_metadata_json,
-                    chunks: out_chunks,
-                }],
-            };
-            Ok((
-                "application/json",
-                serde_json::to_vec_pretty(&bundle).context("serialize JSON")?,
-            ))
+            let bytes = serde_json::to_vec_pretty(&bundle).context("serialize JSON")?;
+            Ok(("application/json", bytes))
         }
         "ndjson" => {
             let mut out = Vec::new();
-            let header = ExportNdjsonRecordV1::Header {
-                format: "agentsdb.export.ndjson.v1".to_string(),
-                tool: ExportToolInfo {
-                    name: tool_name.to_string(),
-                    version: tool_version.to_string(),
-                },
+            let header = ExportNdjsonRecordV2::Header {
+                format: EXPORT_NDJSON_FORMAT_V2.to_string(),
+                tool: bundle.tool,
             };
             out.extend_from_slice(serde_json::to_string(&header)?.as_bytes());
             out.push(b'\n');
-            let layer_rec = ExportNdjsonRecordV1::Layer {
-                path: rel_path.to_string(),
-                layer: logical_layer_for_path(rel_path).map(|s| s.to_string()),
-                schema,
-                layer_metadata_json,
-            };
-            out.extend_from_slice(serde_json::to_string(&layer_rec)?.as_bytes());
-            out.push(b'\n');
-            for c in out_chunks {
-                let rec = ExportNdjsonRecordV1::Chunk {
-                    layer_path: rel_path.to_string(),
-                    chunk: c,
+            for l in bundle.layers {
+                let rec = ExportNdjsonRecordV2::Layer {
+                    path: l.path.clone(),
+                    layer: l.layer,
+                    schema: l.schema,
+                    layer_metadata_json: l.layer_metadata_json,
+                    provenance: l.provenance,
                 };
                 out.extend_from_slice(serde_json::to_string(&rec)?.as_bytes());
                 out.push(b'\n');
+                for c in l.chunks {
+                    let rec = ExportNdjsonRecordV2::Chunk {
+                        layer_path: l.path.clone(),
+                        chunk: c,
+                    };
+                    out.extend_from_slice(serde_json::to_string(&rec)?.as_bytes());
+                    out.push(b'\n');
+                }
             }
             Ok(("application/x-ndjson", out))
         }
-        _ => anyhow::bail!("format must be json or ndjson"),
+        _ => anyhow::bail!("format must be json, ndjson or parquet"),
+    }
+}
+
+/// Encodes a bundle as Parquet (see [`crate::parquet`]).
+fn parquet_body(bundle: &ExportBundleV1) -> anyhow::Result<(&'static str, Vec<u8>)> {
+    #[cfg(feature = "parquet")]
+    {
+        Ok((
+            crate::parquet::CONTENT_TYPE,
+            crate::parquet::write_bundle(bundle)?,
+        ))
+    }
+    #[cfg(not(feature = "parquet"))]
+    {
+        let _ = bundle;
+        anyhow::bail!("Parquet export needs a build with the `parquet` feature")
     }
 }
 
-/// Export multiple layers to a single JSON or NDJSON bundle
+/// Export multiple layers to a single JSON, NDJSON or Parquet bundle
 ///
 /// # Arguments
 /// * `layers_and_paths` - Vector of (abs_path, rel_path, logical_layer) tuples
-/// * `format` - Either "json" or "ndjson"
+/// * `format` - "json", "ndjson" or "parquet" (needs the `parquet` feature)
 /// * `redact` - Redaction mode: "none", "content", "embeddings", or "all"
+/// * `namespace` - If set, only chunks in this namespace are exported
 /// * `tool_name` - Name of the tool performing the export
 /// * `tool_version` - Version of the tool
 ///
@@ -137,10 +201,12 @@ pub fn export_layers(
     layers_and_paths: Vec<(&Path, &str, Option<&str>)>,
     format: &str,
     redact: &str,
+    namespace: Option<&str>,
     tool_name: &str,
     tool_version: &str,
 ) -> anyhow::Result<(&'static str, Vec<u8>)> {
     let mut export_layers = Vec::new();
+    let mut provenance = Vec::new();
 
     for (abs_path, rel_path, logical_layer) in layers_and_paths {
         if !abs_path.exists() {
@@ -161,7 +227,11 @@ pub fn export_layers(
 
         let chunks = agentsdb_format::read_all_chunks(&file).context("read chunks")?;
         let mut out_chunks = Vec::with_capacity(chunks.len());
-        for c in chunks {
+        for mut c in chunks {
+            if namespace.is_some_and(|ns| chunk_namespace(&c.sources) != Some(ns)) {
+                continue;
+            }
+            let redacted = take_redacted_marker(&mut c.sources);
             let (content, embedding) = apply_redaction(redact, &c.content, &c.embedding);
             let sources = c
                 .sources
@@ -173,7 +243,10 @@ pub fn export_layers(
                     }
                 })
                 .collect();
-            let content_sha256 = content.as_deref().map(content_sha256_hex);
+            // The hash is kept when content is redacted, so importers can still dedupe the chunk. A
+            // chunk that was imported without content exports none, with the hash it came with.
+            let content = content.filter(|_| redacted.is_none());
+            let content_sha256 = redacted.or_else(|| Some(content_sha256_hex(&c.content)));
             out_chunks.push(ExportChunkV1 {
                 id: c.id,
                 kind: c.kind,
@@ -187,6 +260,7 @@ pub fn export_layers(
             });
         }
 
+        provenance.push(v2::layer_provenance(&file));
         export_layers.push(ExportLayerV1 {
             path: rel_path.to_string(),
             layer: logical_layer.map(|s| s.to_string()),
@@ -197,47 +271,12 @@ pub fn export_layers(
     }
 
     let bundle = ExportBundleV1 {
-        format: "agentsdb.export.v1".to_string(),
+        format: EXPORT_FORMAT_V1.to_string(),
         tool: ExportToolInfo {
             name: tool_name.to_string(),
             version: tool_version.to_string(),
         },
         layers: export_layers,
     };
-
-    match format {
-        "json" => {
-            let bytes = serde_json::to_vec_pretty(&bundle).context("serialize JSON")?;
-            Ok(("application/json", bytes))
-        }
-        "ndjson" => {
-            let mut out = Vec::new();
-            let header = ExportNdjsonRecordV1::Header {
-                format: "agentsdb.export.ndjson.v1".to_string(),
-                tool: bundle.tool.clone(),
-            };
-            out.extend_from_slice(serde_json::to_string(&header)?.as_bytes());
-            out.push(b'\n');
-            for l in &bundle.layers {
-                let rec = ExportNdjsonRecordV1::Layer {
-                    path: l.path.clone(),
-                    layer: l.layer.clone(),
-                    schema: l.schema.clone(),
-                    layer_metadata_json: l.layer_metadata_json.clone(),
-                };
-                out.extend_from_slice(serde_json::to_string(&rec)?.as_bytes());
-                out.push(b'\n');
-                for c in &l.chunks {
-                    let rec = ExportNdjsonRecordV1::Chunk {
-                        layer_path: l.path.clone(),
-                        chunk: c.clone(),
-                    };
-                    out.extend_from_slice(serde_json::to_string(&rec)?.as_bytes());
-                    out.push(b'\n');
-                }
-            }
-            Ok(("application/x-ndjson", out))
-        }
-        _ => anyhow::bail!("format must be json or ndjson"),
-    }
+    encode_bundle(bundle, provenance, format)
 }
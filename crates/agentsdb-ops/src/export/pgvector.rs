@@ -0,0 +1,174 @@
+//! PostgreSQL + pgvector adapter: writes a `psql` script that creates the table and loads the
+//! records with `COPY`, replacing rows previously exported for the same (layer, chunk id).
+
+use std::io::Write;
+
+use super::vector_store::{VectorRecord, VectorStoreWriter};
+
+pub const DEFAULT_TABLE: &str = "agentsdb_chunks";
+
+/// Table columns, in `COPY` order.
+const COLUMNS: &str = "layer, chunk_id, kind, content, author, confidence, created_at_unix_ms, sources, source_chunk_ids, embedding";
+
+pub struct PgvectorCopyWriter<W: Write> {
+    out: W,
+    table: String,
+}
+
+impl<W: Write> PgvectorCopyWriter<W> {
+    /// `table` is a plain or schema-qualified identifier (`agentsdb_chunks`, `rag.chunks`).
+    pub fn new(out: W, table: &str) -> anyhow::Result<Self> {
+        let valid = table.split('.').count() <= 2
+            && table.split('.').all(|part| {
+                part.chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                    && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+        if !valid {
+            anyhow::bail!(
+                "invalid table name {table:?} (expected [schema.]name of letters, digits and _)"
+            );
+        }
+        Ok(Self {
+            out,
+            table: table.to_string(),
+        })
+    }
+}
+
+impl<W: Write> VectorStoreWriter for PgvectorCopyWriter<W> {
+    fn begin(&mut self, dim: usize) -> anyhow::Result<()> {
+        let table = &self.table;
+        writeln!(
+            self.out,
+            "-- AGENTS.db export for PostgreSQL with pgvector; load with `psql -f`.
+CREATE EXTENSION IF NOT EXISTS vector;
+CREATE TABLE IF NOT EXISTS {table} (
+    layer text NOT NULL,
+    chunk_id bigint NOT NULL,
+    kind text NOT NULL,
+    content text,
+    author text NOT NULL,
+    confidence real NOT NULL,
+    created_at_unix_ms bigint NOT NULL,
+    sources text[] NOT NULL,
+    source_chunk_ids bigint[] NOT NULL,
+    embedding vector({dim}) NOT NULL,
+    PRIMARY KEY (layer, chunk_id)
+);
+BEGIN;
+CREATE TEMP TABLE agentsdb_staging (LIKE {table}) ON COMMIT DROP;
+COPY agentsdb_staging ({COLUMNS}) FROM STDIN;"
+        )?;
+        Ok(())
+    }
+
+    fn write_batch(&mut self, records: &[VectorRecord]) -> anyhow::Result<()> {
+        for r in records {
+            let sources = text_array(&r.sources);
+            let source_chunk_ids = format!(
+                "{{{}}}",
+                r.source_chunk_ids
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            );
+            let embedding = format!(
+                "[{}]",
+                r.embedding
+                    .iter()
+                    .map(f32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            );
+            let fields = [
+                copy_text(r.layer),
+                r.id.to_string(),
+                copy_text(&r.kind),
+                r.content
+                    .as_deref()
+                    .map_or_else(|| "\\N".to_string(), copy_text),
+                copy_text(&r.author),
+                r.confidence.to_string(),
+                r.created_at_unix_ms.to_string(),
+                copy_text(&sources),
+                source_chunk_ids,
+                embedding,
+            ];
+            writeln!(self.out, "{}", fields.join("\t"))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        let table = &self.table;
+        writeln!(
+            self.out,
+            "\\.
+DELETE FROM {table} t USING agentsdb_staging s WHERE t.layer = s.layer AND t.chunk_id = s.chunk_id;
+INSERT INTO {table} ({COLUMNS}) SELECT {COLUMNS} FROM agentsdb_staging;
+COMMIT;"
+        )?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Escapes a value for the `COPY` text format.
+fn copy_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// A PostgreSQL `text[]` literal.
+fn text_array(values: &[String]) -> String {
+    let items: Vec<String> = values
+        .iter()
+        .map(|v| format!("\"{}\"", v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("{{{}}}", items.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_escaped_copy_rows() -> anyhow::Result<()> {
+        let mut out = Vec::new();
+        let mut writer = PgvectorCopyWriter::new(&mut out, "rag.chunks")?;
+        writer.begin(2)?;
+        writer.write_batch(&[VectorRecord {
+            layer: "local",
+            id: 7,
+            kind: "note".to_string(),
+            content: Some("line one\n\tC:\\path".to_string()),
+            author: "human".to_string(),
+            confidence: 0.5,
+            created_at_unix_ms: 42,
+            sources: vec!["docs/\"a\".md:3".to_string()],
+            source_chunk_ids: vec![1, 2],
+            embedding: vec![0.25, -1.0],
+        }])?;
+        writer.finish()?;
+
+        let script = String::from_utf8(out)?;
+        assert!(script.contains("embedding vector(2) NOT NULL"));
+        assert!(script.contains(
+            "local\t7\tnote\tline one\\n\\tC:\\\\path\thuman\t0.5\t42\t{\"docs/\\\\\"a\\\\\".md:3\"}\t{1,2}\t[0.25,-1]\n\\.\n"
+        ));
+        assert!(PgvectorCopyWriter::new(Vec::new(), "chunks; DROP TABLE x").is_err());
+        Ok(())
+    }
+}
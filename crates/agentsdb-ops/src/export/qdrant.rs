@@ -0,0 +1,93 @@
+//! Qdrant adapter: upserts records as points through the REST API.
+//!
+//! Points use [`VectorRecord::point_id`] as id, so re-exporting overwrites. The payload holds
+//! the chunk fields; the collection is created (cosine distance) if it does not exist.
+
+use anyhow::Context;
+use serde_json::json;
+
+use super::vector_store::{VectorRecord, VectorStoreWriter};
+
+/// Env var holding the Qdrant API key, sent as the `api-key` header.
+pub const API_KEY_ENV: &str = "QDRANT_API_KEY";
+
+pub struct QdrantWriter {
+    url: String,
+    collection: String,
+    api_key: Option<String>,
+}
+
+impl QdrantWriter {
+    /// `url` is the REST endpoint, e.g. `http://localhost:6333`.
+    pub fn new(url: &str, collection: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            collection: collection.to_string(),
+            api_key: std::env::var(API_KEY_ENV).ok().filter(|k| !k.is_empty()),
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let request = ureq::request(
+            method,
+            &format!("{}/collections/{}{path}", self.url, self.collection),
+        )
+        .set("Content-Type", "application/json");
+        match &self.api_key {
+            Some(key) => request.set("api-key", key),
+            None => request,
+        }
+    }
+}
+
+impl VectorStoreWriter for QdrantWriter {
+    fn begin(&mut self, dim: usize) -> anyhow::Result<()> {
+        match self.request("GET", "").call() {
+            Ok(response) => {
+                let info: serde_json::Value = serde_json::from_str(&response.into_string()?)
+                    .context("parse collection info")?;
+                let size = info
+                    .pointer("/result/config/params/vectors/size")
+                    .and_then(serde_json::Value::as_u64);
+                if size.is_some_and(|size| size != dim as u64) {
+                    anyhow::bail!(
+                        "Qdrant collection {} has vector size {}, but the layers have dim {dim}",
+                        self.collection,
+                        size.unwrap_or_default()
+                    );
+                }
+                Ok(())
+            }
+            Err(ureq::Error::Status(404, _)) => {
+                self.request("PUT", "")
+                    .send_string(
+                        &json!({ "vectors": { "size": dim, "distance": "Cosine" } }).to_string(),
+                    )
+                    .with_context(|| format!("create Qdrant collection {}", self.collection))?;
+                Ok(())
+            }
+            Err(e) => Err(e).with_context(|| format!("get Qdrant collection {}", self.collection)),
+        }
+    }
+
+    fn write_batch(&mut self, records: &[VectorRecord]) -> anyhow::Result<()> {
+        let points = records
+            .iter()
+            .map(|r| {
+                Ok(json!({
+                    "id": r.point_id(),
+                    "vector": r.embedding,
+                    "payload": serde_json::to_value(r)?,
+                }))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.request("PUT", "/points?wait=true")
+            .send_string(&json!({ "points": points }).to_string())
+            .with_context(|| format!("upsert points into Qdrant collection {}", self.collection))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
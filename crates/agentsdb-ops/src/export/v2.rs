@@ -0,0 +1,285 @@
+//! Conversion between layer sources and the `agentsdb.export.v2` chunk layout.
+//!
+//! Layers keep relationships and metadata as source strings (`supersedes:<id>`,
+//! `namespace:<name>`, ...). Version 2 bundles split those out into
+//! [`ExportRelationshipV2`]s and [`ExportChunkMetadataV2`]; [`chunk_to_v1`] turns them back
+//! into the source strings a layer stores, so importers read both versions the same way.
+
+use agentsdb_core::chunking::PartOf;
+use agentsdb_core::export::{
+    ExportBundleV1, ExportBundleV2, ExportChunkMetadataV2, ExportChunkV1, ExportChunkV2,
+    ExportLayerProvenanceV2, ExportLayerV1, ExportNdjsonRecordV1, ExportNdjsonRecordV2,
+    ExportRelationshipV2, ExportSourceV1,
+};
+use agentsdb_core::language::LANGUAGE_SOURCE_PREFIX;
+use agentsdb_query::{
+    BROKEN_SOURCE_PREFIX, KIND_TOMBSTONE, NAMESPACE_SOURCE_PREFIX, SUPERSEDES_SOURCE_PREFIX,
+};
+
+use crate::util::hex_lower;
+
+/// Hash and chunk count of an exported layer file.
+pub fn layer_provenance(file: &agentsdb_format::LayerFile) -> ExportLayerProvenanceV2 {
+    ExportLayerProvenanceV2 {
+        file_sha256: hex_lower(&agentsdb_embeddings::cache::sha256(file.file_bytes())),
+        chunk_count: file.chunk_count,
+    }
+}
+
+/// Splits the sources of a version 1 chunk into plain sources, relationships and metadata.
+pub fn chunk_to_v2(chunk: ExportChunkV1) -> ExportChunkV2 {
+    let mut sources = Vec::new();
+    let mut relationships = Vec::new();
+    let mut metadata = ExportChunkMetadataV2::default();
+    for source in chunk.sources {
+        let value = match source {
+            ExportSourceV1::ChunkId { id } if chunk.kind == KIND_TOMBSTONE => {
+                relationships.push(ExportRelationshipV2::Retracts { chunk_id: id });
+                continue;
+            }
+            ExportSourceV1::ChunkId { id } => {
+                relationships.push(ExportRelationshipV2::DerivedFrom { chunk_id: id });
+                continue;
+            }
+            ExportSourceV1::SourceString { value } => value,
+        };
+        if let Some(relationship) = relationship_of(&value) {
+            relationships.push(relationship);
+            continue;
+        }
+        match (
+            value.strip_prefix(NAMESPACE_SOURCE_PREFIX),
+            value.strip_prefix(LANGUAGE_SOURCE_PREFIX),
+        ) {
+            (Some(namespace), _) if metadata.namespace.is_none() => {
+                metadata.namespace = Some(namespace.to_string());
+            }
+            (_, Some(language)) if metadata.language.is_none() => {
+                metadata.language = Some(language.to_string());
+            }
+            _ => sources.push(value),
+        }
+    }
+    ExportChunkV2 {
+        id: chunk.id,
+        kind: chunk.kind,
+        content: chunk.content,
+        author: chunk.author,
+        confidence: chunk.confidence,
+        created_at_unix_ms: chunk.created_at_unix_ms,
+        sources,
+        relationships,
+        metadata,
+        embedding: chunk.embedding,
+        content_sha256: chunk.content_sha256,
+    }
+}
+
+/// The source strings a layer stores for a version 2 chunk.
+pub fn chunk_to_v1(chunk: ExportChunkV2) -> ExportChunkV1 {
+    let ExportChunkMetadataV2 {
+        namespace,
+        language,
+        other,
+    } = chunk.metadata;
+    let mut sources: Vec<ExportSourceV1> = chunk
+        .sources
+        .into_iter()
+        .chain(namespace.map(|v| format!("{NAMESPACE_SOURCE_PREFIX}{v}")))
+        .chain(language.map(|v| format!("{LANGUAGE_SOURCE_PREFIX}{v}")))
+        .chain(
+            other
+                .into_iter()
+                .map(|(key, value)| format!("{key}:{value}")),
+        )
+        .map(|value| ExportSourceV1::SourceString { value })
+        .collect();
+    sources.extend(
+        chunk
+            .relationships
+            .iter()
+            .map(|r| match relationship_source(r) {
+                Some(value) => ExportSourceV1::SourceString { value },
+                None => ExportSourceV1::ChunkId { id: r.chunk_id() },
+            }),
+    );
+    ExportChunkV1 {
+        id: chunk.id,
+        kind: chunk.kind,
+        content: chunk.content,
+        author: chunk.author,
+        confidence: chunk.confidence,
+        created_at_unix_ms: chunk.created_at_unix_ms,
+        sources,
+        embedding: chunk.embedding,
+        content_sha256: chunk.content_sha256,
+    }
+}
+
+/// A version 2 bundle in the version 1 layout importers work on. Layer provenance is dropped;
+/// everything a layer can store is kept.
+pub fn bundle_to_v1(bundle: ExportBundleV2) -> ExportBundleV1 {
+    ExportBundleV1 {
+        format: agentsdb_core::export::EXPORT_FORMAT_V1.to_string(),
+        tool: bundle.tool,
+        layers: bundle
+            .layers
+            .into_iter()
+            .map(|layer| ExportLayerV1 {
+                path: layer.path,
+                layer: layer.layer,
+                schema: layer.schema,
+                layer_metadata_json: layer.layer_metadata_json,
+                chunks: layer.chunks.into_iter().map(chunk_to_v1).collect(),
+            })
+            .collect(),
+    }
+}
+
+/// A version 2 NDJSON record in the version 1 layout (layer provenance is dropped).
+pub fn record_to_v1(record: ExportNdjsonRecordV2) -> ExportNdjsonRecordV1 {
+    match record {
+        ExportNdjsonRecordV2::Header { format, tool } => {
+            ExportNdjsonRecordV1::Header { format, tool }
+        }
+        ExportNdjsonRecordV2::Layer {
+            path,
+            layer,
+            schema,
+            layer_metadata_json,
+            provenance: _,
+        } => ExportNdjsonRecordV1::Layer {
+            path,
+            layer,
+            schema,
+            layer_metadata_json,
+        },
+        ExportNdjsonRecordV2::Chunk { layer_path, chunk } => ExportNdjsonRecordV1::Chunk {
+            layer_path,
+            chunk: chunk_to_v1(chunk),
+        },
+    }
+}
+
+/// The relationship a source string records, if it is one written in canonical form (anything
+/// else stays a plain source, so converting back reproduces it exactly).
+fn relationship_of(source: &str) -> Option<ExportRelationshipV2> {
+    let id_after = |prefix: &str| source.strip_prefix(prefix)?.parse::<u32>().ok();
+    let relationship = if let Some(part) = PartOf::parse(source) {
+        ExportRelationshipV2::PartOf {
+            chunk_id: part.first,
+            index: part.index,
+            count: part.count,
+        }
+    } else if let Some(chunk_id) = id_after(SUPERSEDES_SOURCE_PREFIX) {
+        ExportRelationshipV2::Supersedes { chunk_id }
+    } else if let Some(chunk_id) = id_after(BROKEN_SOURCE_PREFIX) {
+        ExportRelationshipV2::BrokenSource { chunk_id }
+    } else {
+        return None;
+    };
+    (relationship_source(&relationship).as_deref() == Some(source)).then_some(relationship)
+}
+
+/// The source string recording `relationship`; `None` for chunk-id relationships.
+fn relationship_source(relationship: &ExportRelationshipV2) -> Option<String> {
+    match *relationship {
+        ExportRelationshipV2::DerivedFrom { .. } | ExportRelationshipV2::Retracts { .. } => None,
+        ExportRelationshipV2::Supersedes { chunk_id } => {
+            Some(format!("{SUPERSEDES_SOURCE_PREFIX}{chunk_id}"))
+        }
+        ExportRelationshipV2::BrokenSource { chunk_id } => {
+            Some(format!("{BROKEN_SOURCE_PREFIX}{chunk_id}"))
+        }
+        ExportRelationshipV2::PartOf {
+            chunk_id,
+            index,
+            count,
+        } => Some(
+            PartOf {
+                first: chunk_id,
+                index,
+                count,
+            }
+            .source(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::export_chunk;
+
+    fn string(value: &str) -> ExportSourceV1 {
+        ExportSourceV1::SourceString {
+            value: value.to_string(),
+        }
+    }
+
+    fn source_strings(chunk: &ExportChunkV1) -> Vec<String> {
+        let mut out: Vec<String> = chunk
+            .sources
+            .iter()
+            .map(|s| match s {
+                ExportSourceV1::ChunkId { id } => format!("#{id}"),
+                ExportSourceV1::SourceString { value } => value.clone(),
+            })
+            .collect();
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn sources_split_into_relationships_and_metadata_and_back() {
+        let original = export_chunk(
+            9,
+            vec![
+                string("README.md:1"),
+                ExportSourceV1::ChunkId { id: 3 },
+                string("supersedes:4"),
+                string("supersedes:07"),
+                string("part-of:5#2/3"),
+                string("broken-source:6"),
+                string("namespace:api"),
+                string("lang:de-AT"),
+            ],
+        );
+        let v2 = chunk_to_v2(original.clone());
+        assert_eq!(v2.sources, ["README.md:1", "supersedes:07"]);
+        assert_eq!(
+            v2.relationships,
+            [
+                ExportRelationshipV2::DerivedFrom { chunk_id: 3 },
+                ExportRelationshipV2::Supersedes { chunk_id: 4 },
+                ExportRelationshipV2::PartOf {
+                    chunk_id: 5,
+                    index: 2,
+                    count: 3
+                },
+                ExportRelationshipV2::BrokenSource { chunk_id: 6 },
+            ]
+        );
+        assert_eq!(v2.metadata.namespace.as_deref(), Some("api"));
+        assert_eq!(v2.metadata.language.as_deref(), Some("de-AT"));
+        assert_eq!(source_strings(&chunk_to_v1(v2)), source_strings(&original));
+
+        let tombstone = chunk_to_v2(ExportChunkV1 {
+            kind: KIND_TOMBSTONE.to_string(),
+            ..export_chunk(9, vec![ExportSourceV1::ChunkId { id: 3 }])
+        });
+        assert_eq!(
+            tombstone.relationships,
+            [ExportRelationshipV2::Retracts { chunk_id: 3 }]
+        );
+    }
+
+    #[test]
+    fn unknown_metadata_becomes_prefixed_sources() {
+        let mut v2 = chunk_to_v2(export_chunk(9, Vec::new()));
+        v2.metadata
+            .other
+            .insert("tag".to_string(), "deploy".to_string());
+        assert_eq!(source_strings(&chunk_to_v1(v2)), ["tag:deploy"]);
+    }
+}
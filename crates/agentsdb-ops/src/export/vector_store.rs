@@ -0,0 +1,253 @@
+//! Export of the visible chunks of a layer set into external vector stores.
+//!
+//! [`export_to_vector_store`] reads what a search would see (latest version of each id, minus
+//! tombstones, retracted chunks, options and `meta.*` records) and hands it in batches to a
+//! [`VectorStoreWriter`]. Adapters: [`super::pgvector`] (a `COPY` script for PostgreSQL with
+//! pgvector) and, with the `qdrant` feature, [`super::qdrant`] (the Qdrant REST API).
+
+use agentsdb_core::types::LayerId;
+use agentsdb_format::{LayerFile, SourceRef};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// One visible chunk, as handed to a [`VectorStoreWriter`].
+#[derive(Debug, Clone, Serialize)]
+pub struct VectorRecord {
+    /// Logical layer: `base`, `user`, `delta` or `local`.
+    pub layer: &'static str,
+    pub id: u32,
+    pub kind: String,
+    /// `None` when exported with content redaction.
+    pub content: Option<String>,
+    pub author: String,
+    pub confidence: f32,
+    pub created_at_unix_ms: u64,
+    /// Free-form provenance strings (`file:line` and the like).
+    pub sources: Vec<String>,
+    /// Chunk ids this chunk derives from.
+    pub source_chunk_ids: Vec<u32>,
+    #[serde(skip)]
+    pub embedding: Vec<f32>,
+}
+
+impl VectorRecord {
+    /// A numeric id unique across layers: the layer rank (base 0, delta 1, user 2, local 3) in
+    /// the high 32 bits, the chunk id in the low ones.
+    pub fn point_id(&self) -> u64 {
+        let rank: u64 = match self.layer {
+            "base" => 0,
+            "delta" => 1,
+            "user" => 2,
+            _ => 3,
+        };
+        rank << 32 | u64::from(self.id)
+    }
+}
+
+/// A destination for [`export_to_vector_store`].
+pub trait VectorStoreWriter {
+    /// Called once, before any records, with the embedding dimension of the layers.
+    fn begin(&mut self, dim: usize) -> anyhow::Result<()>;
+    /// Stores a batch of records. Re-exporting the same chunks should overwrite, not duplicate.
+    fn write_batch(&mut self, records: &[VectorRecord]) -> anyhow::Result<()>;
+    /// Called once after the last batch.
+    fn finish(&mut self) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VectorStoreExportOptions {
+    /// Records per [`VectorStoreWriter::write_batch`] call (0 means the default of 256).
+    pub batch_size: usize,
+    /// Leave chunk content out of the exported records.
+    pub redact_content: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VectorStoreExportReport {
+    pub dim: usize,
+    pub records: usize,
+    pub batches: usize,
+    /// Records per logical layer.
+    pub layers: BTreeMap<&'static str, usize>,
+}
+
+const DEFAULT_BATCH_SIZE: usize = 256;
+
+const fn layer_name(layer_id: LayerId) -> &'static str {
+    match layer_id {
+        LayerId::Base => "base",
+        LayerId::Delta => "delta",
+        LayerId::User => "user",
+        LayerId::Local => "local",
+        LayerId::Session => "session",
+    }
+}
+
+/// Exports the visible chunks of `layers` to `writer`.
+pub fn export_to_vector_store(
+    layers: &agentsdb_query::LayerSet,
+    writer: &mut dyn VectorStoreWriter,
+    options: VectorStoreExportOptions,
+) -> anyhow::Result<VectorStoreExportReport> {
+    let mut opened = Vec::new();
+    for (layer_id, path) in [
+        (LayerId::Local, &layers.local),
+        (LayerId::User, &layers.user),
+        (LayerId::Delta, &layers.delta),
+        (LayerId::Base, &layers.base),
+    ] {
+        if let Some(path) = path {
+            let file = LayerFile::open_lenient(path).with_context(|| format!("open {path}"))?;
+            opened.push((layer_id, file));
+        }
+    }
+    agentsdb_query::validate_schema_compatible(&opened).context("layers are not compatible")?;
+    let dim = opened.first().map_or(0, |(_, f)| f.embedding_dim());
+    let retracted = agentsdb_query::retracted_chunk_ids(&opened)?;
+
+    let batch_size = if options.batch_size == 0 {
+        DEFAULT_BATCH_SIZE
+    } else {
+        options.batch_size
+    };
+    let mut report = VectorStoreExportReport {
+        dim,
+        records: 0,
+        batches: 0,
+        layers: BTreeMap::new(),
+    };
+    writer.begin(dim)?;
+    let mut batch = Vec::with_capacity(batch_size);
+    for (layer_id, file) in &opened {
+        for chunk in file.latest_chunks()? {
+            if chunk.kind == agentsdb_query::KIND_TOMBSTONE
+                || chunk.kind == agentsdb_embeddings::config::KIND_OPTIONS
+                || chunk.kind.starts_with("meta.")
+                || agentsdb_query::is_retracted(&retracted, *layer_id, chunk.id)
+            {
+                continue;
+            }
+            let mut embedding = vec![0.0; dim];
+            file.read_embedding_row_f32(chunk.embedding_row, &mut embedding)?;
+            let mut sources = Vec::new();
+            let mut source_chunk_ids = Vec::new();
+            for source in file.sources_for(chunk.rel_start, chunk.rel_count)? {
+                match source {
+                    SourceRef::String(v) => sources.push(v.to_string()),
+                    SourceRef::ChunkId(id) => source_chunk_ids.push(id),
+                }
+            }
+            batch.push(VectorRecord {
+                layer: layer_name(*layer_id),
+                id: chunk.id,
+                kind: chunk.kind.to_string(),
+                content: (!options.redact_content).then(|| chunk.content.to_string()),
+                author: chunk.author.to_string(),
+                confidence: chunk.confidence,
+                created_at_unix_ms: chunk.created_at_unix_ms,
+                sources,
+                source_chunk_ids,
+                embedding,
+            });
+            *report.layers.entry(layer_name(*layer_id)).or_default() += 1;
+            if batch.len() == batch_size {
+                flush(writer, &mut batch, &mut report)?;
+            }
+        }
+    }
+    flush(writer, &mut batch, &mut report)?;
+    writer.finish()?;
+    Ok(report)
+}
+
+fn flush(
+    writer: &mut dyn VectorStoreWriter,
+    batch: &mut Vec<VectorRecord>,
+    report: &mut VectorStoreExportReport,
+) -> anyhow::Result<()> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    writer.write_batch(batch)?;
+    report.records += batch.len();
+    report.batches += 1;
+    batch.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+    use agentsdb_format::{ChunkInput, ChunkSource};
+
+    #[derive(Default)]
+    struct Collect {
+        dim: usize,
+        batches: Vec<Vec<(u64, Option<String>)>>,
+        finished: bool,
+    }
+
+    impl VectorStoreWriter for Collect {
+        fn begin(&mut self, dim: usize) -> anyhow::Result<()> {
+            self.dim = dim;
+            Ok(())
+        }
+        fn write_batch(&mut self, records: &[VectorRecord]) -> anyhow::Result<()> {
+            self.batches.push(
+                records
+                    .iter()
+                    .map(|r| (r.point_id(), r.content.clone()))
+                    .collect(),
+            );
+            Ok(())
+        }
+        fn finish(&mut self) -> anyhow::Result<()> {
+            self.finished = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn exports_only_visible_chunks_in_batches() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path().join("AGENTS.db");
+        let local = dir.path().join("AGENTS.local.db");
+        write_layer(&base, vec![chunk(1, "kept"), chunk(2, "retracted")])?;
+        let tombstone = ChunkInput {
+            kind: agentsdb_query::KIND_TOMBSTONE.to_string(),
+            sources: vec![ChunkSource::ChunkId(2)],
+            ..chunk(3, "drop 2")
+        };
+        write_layer(&local, vec![chunk(1, "local one"), tombstone])?;
+
+        let layers = agentsdb_query::LayerSet {
+            base: Some(base.display().to_string()),
+            user: None,
+            delta: None,
+            local: Some(local.display().to_string()),
+        };
+        let mut sink = Collect::default();
+        let report = export_to_vector_store(
+            &layers,
+            &mut sink,
+            VectorStoreExportOptions {
+                batch_size: 1,
+                redact_content: false,
+            },
+        )?;
+        assert_eq!(report.records, 2);
+        assert_eq!(report.batches, 2);
+        assert_eq!(sink.dim, 2);
+        assert!(sink.finished);
+        assert_eq!(
+            sink.batches,
+            vec![
+                vec![(3 << 32 | 1, Some("local one".to_string()))],
+                vec![(1, Some("kept".to_string()))],
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+//! Write hooks: checks and rewrites applied to each chunk before it is appended.
+//!
+//! A [`WriteHook`] sees every chunk that [`append_chunk`](crate::write::append_chunk),
+//! [`append_chunks`](crate::write::append_chunks) and the supersede entry points are about to
+//! write, before the kind taxonomy check and before embedding. It may change the chunk, or reject
+//! the write with [`WriteError::Rejected`]. Hooks are registered for the whole process with
+//! [`register`]; the CLI, web and MCP servers register [`PolicyHook`], which enforces the `write`
+//! rules of `AGENTS.policy.json` (see [`WriteRules`]).
+
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use agentsdb_format::{ChunkInput, ChunkSource};
+
+use crate::error::WriteError;
+use crate::policy::{PromotionPolicy, WriteRules};
+use crate::write::NewChunk;
+
+/// The layer a chunk is about to be appended to.
+#[derive(Debug, Clone, Copy)]
+pub struct WriteTarget<'a> {
+    pub path: &'a Path,
+    /// Logical layer (`local`, `delta`, ...) of a standard layer file.
+    pub scope: Option<&'static str>,
+}
+
+impl<'a> WriteTarget<'a> {
+    pub fn of(path: &'a Path) -> Self {
+        let scope = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .and_then(crate::util::logical_layer_for_path);
+        Self { path, scope }
+    }
+}
+
+/// Validates, and may rewrite, chunks before they are appended.
+pub trait WriteHook: Send + Sync {
+    /// Name reported when the hook rejects a write; registering a hook replaces any other with
+    /// the same name.
+    fn name(&self) -> &str;
+
+    /// Checks `chunk` before it is appended to `target`. An error rejects the whole write.
+    fn before_append(
+        &self,
+        target: &WriteTarget<'_>,
+        chunk: &mut NewChunk,
+    ) -> Result<(), WriteError>;
+}
+
+static HOOKS: RwLock<Vec<Arc<dyn WriteHook>>> = RwLock::new(Vec::new());
+
+/// Adds `hook` to the hooks every write runs, replacing a registered hook of the same name.
+pub fn register(hook: Arc<dyn WriteHook>) {
+    let mut hooks = HOOKS.write().unwrap_or_else(|e| e.into_inner());
+    hooks.retain(|h| h.name() != hook.name());
+    hooks.push(hook);
+}
+
+/// The registered hooks, in registration order.
+pub fn registered() -> Vec<Arc<dyn WriteHook>> {
+    HOOKS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Runs the registered hooks on `chunk`, in registration order.
+pub fn run(target: &WriteTarget<'_>, chunk: &mut NewChunk) -> Result<(), WriteError> {
+    run_hooks(&registered(), target, chunk)
+}
+
+/// [`run`] on a chunk in layer form, for writers that build [`ChunkInput`]s themselves. Run it
+/// before embedding: a hook may change the content.
+pub fn run_on_input(target: &WriteTarget<'_>, chunk: &mut ChunkInput) -> Result<(), WriteError> {
+    let mut pending = NewChunk {
+        kind: std::mem::take(&mut chunk.kind),
+        content: std::mem::take(&mut chunk.content),
+        confidence: chunk.confidence,
+        sources: std::mem::take(&mut chunk.sources),
+    };
+    let result = run(target, &mut pending);
+    chunk.kind = pending.kind;
+    chunk.content = pending.content;
+    chunk.confidence = pending.confidence;
+    chunk.sources = pending.sources;
+    result
+}
+
+/// Runs `hooks` on `chunk`, in order; the first rejection stops the write.
+pub fn run_hooks(
+    hooks: &[Arc<dyn WriteHook>],
+    target: &WriteTarget<'_>,
+    chunk: &mut NewChunk,
+) -> Result<(), WriteError> {
+    for hook in hooks {
+        hook.before_append(target, chunk)?;
+    }
+    Ok(())
+}
+
+/// Enforces the [`WriteRules`] of the `AGENTS.policy.json` next to the target layer. Tombstones
+/// are never checked.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PolicyHook;
+
+impl PolicyHook {
+    pub const NAME: &'static str = "policy";
+}
+
+impl WriteHook for PolicyHook {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn before_append(
+        &self,
+        target: &WriteTarget<'_>,
+        chunk: &mut NewChunk,
+    ) -> Result<(), WriteError> {
+        if chunk.kind == agentsdb_query::KIND_TOMBSTONE {
+            return Ok(());
+        }
+        let rules = PromotionPolicy::for_layer(target.path)?.write;
+        check_rules(&rules, target, chunk).map_err(|reason| WriteError::Rejected {
+            hook: Self::NAME.to_string(),
+            reason,
+        })
+    }
+}
+
+fn check_rules(
+    rules: &WriteRules,
+    target: &WriteTarget<'_>,
+    chunk: &NewChunk,
+) -> Result<(), String> {
+    if let Some(max) = rules.max_content_chars {
+        let len = chunk.content.chars().count();
+        if len > max {
+            return Err(format!(
+                "content is {len} characters; at most {max} are allowed"
+            ));
+        }
+    }
+    if let Some(scope) = target.scope {
+        if rules
+            .banned_kinds
+            .get(scope)
+            .is_some_and(|kinds| kinds.contains(&chunk.kind))
+        {
+            return Err(format!(
+                "kind {:?} may not be written to {scope}",
+                chunk.kind
+            ));
+        }
+    }
+    // Namespace, language and identity tags are added before the hooks run; they don't count.
+    let cited = chunk.sources.iter().any(|source| match source {
+        ChunkSource::ChunkId(_) => true,
+        ChunkSource::SourceString(v) => !agentsdb_query::is_reserved_source(v),
+    });
+    if rules.require_sources.contains(&chunk.kind) && !cited {
+        return Err(format!(
+            "kind {:?} requires at least one source",
+            chunk.kind
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::new_chunk;
+
+    #[test]
+    fn policy_hook_enforces_the_write_rules() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("AGENTS.policy.json"),
+            r#"{"write": {"max_content_chars": 10, "banned_kinds": {"delta": ["scratch"]}, "require_sources": ["decision"]}}"#,
+        )?;
+        let local = dir.path().join("AGENTS.local.db");
+        let delta = dir.path().join("AGENTS.delta.db");
+        let hooks: Vec<Arc<dyn WriteHook>> = vec![Arc::new(PolicyHook)];
+        let run =
+            |path: &Path, chunk: &mut NewChunk| run_hooks(&hooks, &WriteTarget::of(path), chunk);
+
+        assert!(run(&local, &mut new_chunk("scratch", "short")).is_ok());
+        let err = run(&delta, &mut new_chunk("scratch", "short")).err();
+        assert_eq!(err.map(|e| e.code()), Some("policy_violation"));
+        assert!(run(&local, &mut new_chunk("note", "far too long for it")).is_err());
+        assert!(run(&local, &mut new_chunk("tombstone", "far too long for it")).is_ok());
+
+        let mut decision = new_chunk("decision", "use sqlite");
+        assert!(run(&local, &mut decision).is_err());
+        decision
+            .sources
+            .push(ChunkSource::SourceString("lang:en".to_string()));
+        assert!(run(&local, &mut decision).is_err());
+        decision
+            .sources
+            .push(ChunkSource::SourceString("ADR-7".to_string()));
+        assert!(run(&local, &mut decision).is_ok());
+
+        // Without a policy file, everything is allowed.
+        let other = tempfile::tempdir()?;
+        let path = other.path().join("AGENTS.delta.db");
+        assert!(run(&path, &mut new_chunk("scratch", "far too long for it")).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn hooks_can_rewrite_chunks() {
+        struct Trim;
+        impl WriteHook for Trim {
+            fn name(&self) -> &str {
+                "trim"
+            }
+            fn before_append(
+                &self,
+                _: &WriteTarget<'_>,
+                chunk: &mut NewChunk,
+            ) -> Result<(), WriteError> {
+                chunk.content = chunk.content.trim().to_string();
+                Ok(())
+            }
+        }
+        let hooks: Vec<Arc<dyn WriteHook>> = vec![Arc::new(Trim)];
+        let mut note = new_chunk("note", "  padded  ");
+        let target = WriteTarget::of(Path::new("AGENTS.local.db"));
+        assert!(run_hooks(&hooks, &target, &mut note).is_ok());
+        assert_eq!(note.content, "padded");
+        assert_eq!(target.scope, Some("local"));
+    }
+}
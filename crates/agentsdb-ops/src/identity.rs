@@ -0,0 +1,131 @@
+//! The identity recorded on what this process writes.
+//!
+//! Chunk authors only tell humans from MCP clients. Front ends resolve the `identity.*` settings
+//! (see [`agentsdb_embeddings::settings::identity`]) at startup and [`install`] the result; from
+//! then on appended chunks carry it as an `identity:<label>` source (added by [`IdentityHook`])
+//! and proposal events as their `identity` field. Servers that authenticate each request
+//! record the signed-in user instead, for the request's duration, with [`scoped`].
+
+use std::cell::RefCell;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use agentsdb_embeddings::settings::Identity;
+use agentsdb_format::ChunkSource;
+use agentsdb_query::IDENTITY_SOURCE_PREFIX;
+
+use crate::error::WriteError;
+use crate::hooks::{WriteHook, WriteTarget};
+use crate::write::NewChunk;
+
+static CURRENT: RwLock<Option<String>> = RwLock::new(None);
+
+thread_local! {
+    static SCOPED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Records `identity` on everything this process writes from now on, and registers
+/// [`IdentityHook`]. An empty identity records nothing.
+pub fn install(identity: &Identity) {
+    *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = identity.label();
+    crate::hooks::register(Arc::new(IdentityHook));
+}
+
+/// Resolves the identity settings for the layers in `dir` and [`install`]s them.
+///
+/// # Returns
+/// The installed identity's label, if it has one
+pub fn install_for_dir(dir: &Path) -> anyhow::Result<Option<String>> {
+    let identity = agentsdb_embeddings::settings::identity(dir)?;
+    install(&identity);
+    Ok(identity.label())
+}
+
+/// Label of the identity this thread records: the [`scoped`] one, else the installed one.
+pub fn current() -> Option<String> {
+    SCOPED
+        .with(|scoped| scoped.borrow().clone())
+        .or_else(|| CURRENT.read().unwrap_or_else(|e| e.into_inner()).clone())
+}
+
+/// Records `identity` on what this thread writes, instead of the installed identity, until the
+/// returned guard is dropped.
+pub fn scoped(identity: &str) -> ScopedIdentity {
+    let previous = SCOPED.with(|scoped| scoped.replace(Some(identity.to_string())));
+    ScopedIdentity { previous }
+}
+
+/// Restores the previous identity when dropped; see [`scoped`].
+#[derive(Debug)]
+#[must_use = "the identity is only recorded while the guard is alive"]
+pub struct ScopedIdentity {
+    previous: Option<String>,
+}
+
+impl Drop for ScopedIdentity {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SCOPED.with(|scoped| *scoped.borrow_mut() = previous);
+    }
+}
+
+fn is_scoped() -> bool {
+    SCOPED.with(|scoped| scoped.borrow().is_some())
+}
+
+/// Adds the installed identity to chunks that do not already name one (imported chunks keep
+/// their writer's). A [`scoped`] identity replaces any identity the chunk names, so an
+/// authenticated user cannot write as someone else.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IdentityHook;
+
+impl IdentityHook {
+    pub const NAME: &'static str = "identity";
+}
+
+impl WriteHook for IdentityHook {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn before_append(
+        &self,
+        _target: &WriteTarget<'_>,
+        chunk: &mut NewChunk,
+    ) -> Result<(), WriteError> {
+        if is_scoped() {
+            chunk.sources.retain(|s| {
+                !matches!(s, ChunkSource::SourceString(v) if v.starts_with(IDENTITY_SOURCE_PREFIX))
+            });
+        } else if crate::util::chunk_identity(&chunk.sources).is_some() {
+            return Ok(());
+        }
+        if let Some(identity) = current() {
+            chunk.sources.push(crate::util::identity_source(&identity));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::new_chunk;
+
+    #[test]
+    fn scoped_identity_replaces_named_identities_until_dropped() {
+        let target = WriteTarget::of(Path::new("AGENTS.delta.db"));
+        let mut chunk = NewChunk {
+            sources: vec![crate::util::identity_source("mallory")],
+            ..new_chunk("note", "deploys run from CI")
+        };
+        {
+            let _user = scoped("ana");
+            assert_eq!(current().as_deref(), Some("ana"));
+            assert!(IdentityHook.before_append(&target, &mut chunk).is_ok());
+        }
+        assert_eq!(crate::util::chunk_identity(&chunk.sources), Some("ana"));
+        assert_eq!(chunk.sources.len(), 1);
+        assert!(!is_scoped());
+    }
+}
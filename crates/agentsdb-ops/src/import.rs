@@ -1,16 +1,29 @@
+#[cfg(feature = "chroma")]
+pub mod chroma;
+#[cfg(any(feature = "sqlite", feature = "chroma"))]
+mod foreign;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
 use anyhow::Context;
 use serde::Serialize;
 use std::collections::HashSet;
 use std::path::Path;
 
 use agentsdb_core::export::{
-    ExportBundleV1, ExportLayerSchemaV1, ExportLayerV1, ExportNdjsonRecordV1,
-    ExportSourceV1, ExportToolInfo,
+    ExportBundleV1, ExportBundleV2, ExportChunkV1, ExportLayerSchemaV1, ExportLayerV1,
+    ExportNdjsonRecordV1, ExportNdjsonRecordV2, ExportSourceV1, ExportToolInfo, EXPORT_FORMAT_V2,
+    EXPORT_NDJSON_FORMAT_V2,
 };
 use agentsdb_embeddings::config::get_immutable_embedding_options;
-use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
+use agentsdb_embeddings::embedder::EmbedPurpose;
+use agentsdb_embeddings::layer_metadata::{carry_kinds, LayerMetadataV1, ProfileMismatchError};
+use agentsdb_embeddings::usage::UsageReport;
+use agentsdb_format::WritePolicy;
+use agentsdb_query::REDACTED_SOURCE_PREFIX;
 
-use crate::util::content_sha256_hex;
+use crate::error::ImportError;
+use crate::util::{content_sha256_hex, redacted_content_sha256};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ImportOutcome {
@@ -22,13 +35,53 @@ pub struct ImportOutcome {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reembedded_to: Option<String>,
     pub reembedded_count: usize,
+    /// Embedding provider usage for chunks embedded during this import (absent if none were).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_usage: Option<UsageReport>,
+    /// Journal id of the import, for `agentsdb undo` (absent for dry runs and empty imports).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<u32>,
+}
+
+/// Leading bytes of every Parquet file.
+const PARQUET_MAGIC: &[u8] = b"PAR1";
+
+/// The format of an export file: `"parquet"`, `"json"` or `"ndjson"`.
+pub fn detect_export_format(input: &[u8]) -> &'static str {
+    if input.starts_with(PARQUET_MAGIC) {
+        "parquet"
+    } else if serde_json::from_slice::<serde::de::IgnoredAny>(input).is_ok() {
+        "json"
+    } else {
+        "ndjson"
+    }
 }
 
-/// Parse an export file into a structured bundle (supports both JSON and NDJSON formats).
+/// Parse an export file into a structured bundle (supports JSON, NDJSON and Parquet formats).
+///
+/// Version 2 JSON and NDJSON exports are returned in the version 1 layout (see
+/// [`crate::export::v2::bundle_to_v1`]), so every importer handles both versions.
 pub fn parse_export_bytes(input: &[u8]) -> anyhow::Result<ExportBundleV1> {
+    if input.starts_with(PARQUET_MAGIC) {
+        #[cfg(feature = "parquet")]
+        return crate::parquet::read_bundle(input);
+        #[cfg(not(feature = "parquet"))]
+        anyhow::bail!("Parquet import needs a build with the `parquet` feature");
+    }
     let s = std::str::from_utf8(input).context("input must be valid UTF-8")?;
     let trimmed = s.trim_start();
     if trimmed.starts_with('{') {
+        #[derive(serde::Deserialize)]
+        struct FormatProbe {
+            #[serde(default)]
+            format: String,
+        }
+        if serde_json::from_str::<FormatProbe>(trimmed).is_ok_and(|p| p.format == EXPORT_FORMAT_V2)
+        {
+            let bundle = serde_json::from_str::<ExportBundleV2>(trimmed)
+                .context("parse agentsdb.export.v2 bundle")?;
+            return Ok(crate::export::v2::bundle_to_v1(bundle));
+        }
         if let Ok(bundle) = serde_json::from_str::<ExportBundleV1>(trimmed) {
             return Ok(bundle);
         }
@@ -41,16 +94,21 @@ pub fn parse_export_bytes(input: &[u8]) -> anyhow::Result<ExportBundleV1> {
     };
     let mut layers: Vec<ExportLayerV1> = Vec::new();
     let mut layer_ix_by_path: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    // Set by the header; without one, each line is tried as version 2 and then version 1.
+    let mut version2: Option<bool> = None;
 
     for (i, line) in s.lines().enumerate() {
         let line = line.trim();
         if line.is_empty() {
             continue;
         }
-        let rec: ExportNdjsonRecordV1 =
-            serde_json::from_str(line).with_context(|| format!("parse NDJSON line {}", i + 1))?;
+        let rec = parse_ndjson_record(line, version2)
+            .with_context(|| format!("parse NDJSON line {}", i + 1))?;
         match rec {
-            ExportNdjsonRecordV1::Header { tool: t, .. } => tool = t,
+            ExportNdjsonRecordV1::Header { format, tool: t } => {
+                version2 = Some(format == EXPORT_NDJSON_FORMAT_V2);
+                tool = t;
+            }
             ExportNdjsonRecordV1::Layer {
                 path,
                 layer,
@@ -112,6 +170,21 @@ pub fn parse_export_bytes(input: &[u8]) -> anyhow::Result<ExportBundleV1> {
     })
 }
 
+/// One NDJSON export record in the version 1 layout; `version2` is what the header said.
+fn parse_ndjson_record(
+    line: &str,
+    version2: Option<bool>,
+) -> serde_json::Result<ExportNdjsonRecordV1> {
+    if version2 == Some(false) {
+        return serde_json::from_str(line);
+    }
+    match serde_json::from_str::<ExportNdjsonRecordV2>(line) {
+        Ok(rec) => Ok(crate::export::v2::record_to_v1(rec)),
+        Err(_) if version2.is_none() => serde_json::from_str(line),
+        Err(err) => Err(err),
+    }
+}
+
 fn sources_to_chunk_sources(sources: Vec<ExportSourceV1>) -> Vec<agentsdb_format::ChunkSource> {
     sources
         .into_iter()
@@ -124,44 +197,46 @@ fn sources_to_chunk_sources(sources: Vec<ExportSourceV1>) -> Vec<agentsdb_format
         .collect()
 }
 
-fn ensure_target_permissions(path: &Path, scope: &str, allow_base: bool) -> anyhow::Result<()> {
-    let file_name = path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or_default();
+/// The content hash of a chunk exported without its content, if it can be imported as an
+/// embedding-only record: it needs the hash and an embedding.
+fn redacted_hash(chunk: &ExportChunkV1) -> Result<String, ImportError> {
+    let missing = |reason| ImportError::MissingContent {
+        id: chunk.id,
+        reason,
+    };
+    let hash = chunk
+        .content_sha256
+        .as_deref()
+        .filter(|h| h.len() == 64 && h.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(|| missing("no valid content_sha256"))?;
+    if chunk.embedding.is_none() {
+        return Err(missing("no embedding"));
+    }
+    Ok(hash.to_ascii_lowercase())
+}
 
-    match scope {
-        "local" => {
-            if file_name != "AGENTS.local.db" {
-                anyhow::bail!("scope local expects file named AGENTS.local.db");
-            }
-            agentsdb_format::ensure_writable_layer_path(path).context("permission check")?;
-        }
-        "delta" => {
-            if file_name != "AGENTS.delta.db" {
-                anyhow::bail!("scope delta expects file named AGENTS.delta.db");
-            }
-            agentsdb_format::ensure_writable_layer_path(path).context("permission check")?;
-        }
-        "user" => {
-            if file_name != "AGENTS.user.db" {
-                anyhow::bail!("scope user expects file named AGENTS.user.db");
-            }
-            agentsdb_format::ensure_writable_layer_path_allow_user(path)
-                .context("permission check")?;
-        }
-        "base" => {
-            if !allow_base {
-                anyhow::bail!("refusing to write AGENTS.db without allow_base");
-            }
-            if file_name != "AGENTS.db" {
-                anyhow::bail!("scope base expects file named AGENTS.db");
-            }
-            agentsdb_format::ensure_writable_layer_path_allow_base(path)
-                .context("permission check")?;
-        }
-        _ => anyhow::bail!("scope must be local, delta, user, or base"),
+fn ensure_target_permissions(
+    path: &Path,
+    scope: &str,
+    allow_base: bool,
+) -> Result<(), ImportError> {
+    if scope == "base" && !allow_base {
+        return Err(ImportError::BaseNotAllowed);
     }
+    let mut policy = WritePolicy::standard().allow_user();
+    if allow_base {
+        policy = policy.allow_base();
+    }
+    let Some(expected) = policy.file_name(scope) else {
+        return Err(ImportError::InvalidScope(scope.to_string()));
+    };
+    if policy.scope_of(path) != Some(scope) {
+        return Err(ImportError::ScopeMismatch {
+            scope: scope.to_string(),
+            expected: expected.to_string(),
+        });
+    }
+    policy.check(path)?;
     Ok(())
 }
 
@@ -170,7 +245,7 @@ fn ensure_target_permissions(path: &Path, scope: &str, allow_base: bool) -> anyh
 /// # Arguments
 /// * `abs_path` - Absolute path to the target layer file
 /// * `scope` - Scope: "local", "delta", "user", or "base"
-/// * `data` - Import data as string (JSON or NDJSON format)
+/// * `data` - Import data (JSON, NDJSON or Parquet format)
 /// * `dry_run` - If true, validate but don't write
 /// * `dedupe` - If true, skip chunks with duplicate content hashes
 /// * `preserve_ids` - If true, preserve chunk IDs from import data
@@ -185,7 +260,7 @@ fn ensure_target_permissions(path: &Path, scope: &str, allow_base: bool) -> anyh
 pub fn import_into_layer(
     abs_path: &Path,
     scope: &str,
-    data: &str,
+    data: &[u8],
     dry_run: bool,
     dedupe: bool,
     preserve_ids: bool,
@@ -193,11 +268,11 @@ pub fn import_into_layer(
     dim: Option<u32>,
     tool_name: &str,
     tool_version: &str,
-) -> anyhow::Result<ImportOutcome> {
+) -> Result<ImportOutcome, ImportError> {
     ensure_target_permissions(abs_path, scope, allow_base)?;
 
     // Parse the full bundle to get layer metadata if available
-    let bundle = parse_export_bytes(data.as_bytes()).context("parse import data")?;
+    let bundle = parse_export_bytes(data).map_err(ImportError::Parse)?;
     let mut imported = Vec::new();
     let mut source_profile: Option<String> = None;
 
@@ -214,16 +289,17 @@ pub fn import_into_layer(
     }
 
     if imported.is_empty() {
-        anyhow::bail!("no chunks found in import");
+        return Err(ImportError::Empty);
     }
 
-    // Validate required fields and normalize hashes
+    // Validate required fields and normalize hashes. A chunk exported without its content is
+    // kept as an embedding-only record, identified by the hash it was exported with.
     for c in &mut imported {
-        if c.content.is_none() {
-            anyhow::bail!("import contains redacted/missing content; cannot import");
-        }
-        let h = content_sha256_hex(c.content.as_deref().unwrap_or_default());
-        c.content_sha256 = Some(h);
+        let hash = match c.content.as_deref() {
+            Some(content) => content_sha256_hex(content),
+            None => redacted_hash(c)?,
+        };
+        c.content_sha256 = Some(hash);
     }
 
     let dir = abs_path.parent().unwrap_or_else(|| Path::new("."));
@@ -235,7 +311,10 @@ pub fn import_into_layer(
         let chunks = agentsdb_format::read_all_chunks(&file).context("read target chunks")?;
         if dedupe {
             for c in &chunks {
-                existing_hashes.insert(content_sha256_hex(&c.content));
+                existing_hashes.insert(match redacted_content_sha256(&c.sources) {
+                    Some(hash) => hash.to_string(),
+                    None => content_sha256_hex(&c.content),
+                });
             }
         }
         for c in &chunks {
@@ -262,7 +341,7 @@ pub fn import_into_layer(
                 break;
             }
         }
-        inferred.context("creating a new layer requires dim or input embeddings")?
+        inferred.ok_or(ImportError::DimRequired)?
     };
 
     // Get the target embedding backend from AGENTS.db (immutable base layer options)
@@ -270,21 +349,23 @@ pub fn import_into_layer(
         .context("get immutable embedding options from AGENTS.db")?;
     let target_backend = target_options.backend.clone();
 
-    let embedder_for_dim = |dim_usize: usize| -> anyhow::Result<
+    let embedder_for_dim = |dim_usize: usize| -> Result<
         Box<dyn agentsdb_embeddings::embedder::Embedder + Send + Sync>,
+        ImportError,
     > {
-        let options = get_immutable_embedding_options(dir)
-            .context("get immutable embedding options")?;
+        let options =
+            get_immutable_embedding_options(dir).context("get immutable embedding options")?;
         if let Some(cfg_dim) = options.dim {
             if cfg_dim != dim_usize {
-                anyhow::bail!(
-                    "embedding dim mismatch (target dim={dim_usize}, options specify dim={cfg_dim})"
-                );
+                return Err(ImportError::DimMismatch {
+                    target: dim_usize,
+                    configured: cfg_dim,
+                });
             }
         }
-        options
+        Ok(options
             .into_embedder(dim_usize)
-            .context("resolve embedder from options")
+            .context("resolve embedder from options")?)
     };
 
     let mut layer_metadata_json: Option<Vec<u8>> = None;
@@ -300,17 +381,16 @@ pub fn import_into_layer(
         for c in &imported {
             let id = c.id;
             if id == 0 {
-                anyhow::bail!("preserve_ids requires non-zero ids in input");
+                return Err(ImportError::ZeroId);
             }
             if existing_ids.contains(&id) {
-                anyhow::bail!("id {id} already exists in target");
+                return Err(ImportError::IdExists(id));
             }
             existing_ids.insert(id);
         }
     }
 
     for c in imported {
-        let content = c.content.as_ref().expect("validated");
         let hash = c.content_sha256.as_deref().unwrap_or_default();
         if dedupe && existing_hashes.contains(hash) {
             skipped += 1;
@@ -345,6 +425,12 @@ pub fn import_into_layer(
 
         let embedding = if needs_reembedding {
             // Re-embed if dimension mismatch or no embedding
+            let Some(content) = c.content.as_ref() else {
+                return Err(ImportError::MissingContent {
+                    id: c.id,
+                    reason: "its embedding does not fit the target layer",
+                });
+            };
             if embedder.is_none() {
                 let e = embedder_for_dim(inferred_dim)?;
                 target_profile = Some(e.profile().backend.clone());
@@ -357,7 +443,7 @@ pub fn import_into_layer(
             }
             let e = embedder.as_ref().expect("embedder");
             reembedded_count += 1;
-            e.embed(&[content.clone()])?
+            e.embed_for(EmbedPurpose::Document, std::slice::from_ref(content))?
                 .into_iter()
                 .next()
                 .unwrap_or_else(|| vec![0.0; inferred_dim])
@@ -369,7 +455,7 @@ pub fn import_into_layer(
         let id = if exists {
             if preserve_ids {
                 if existing_ids.contains(&c.id) {
-                    anyhow::bail!("id {} already exists in target", c.id);
+                    return Err(ImportError::IdExists(c.id));
                 }
                 existing_ids.insert(c.id);
                 c.id
@@ -388,15 +474,21 @@ pub fn import_into_layer(
             assigned
         };
 
+        let mut sources = sources_to_chunk_sources(c.sources);
+        if c.content.is_none() {
+            sources.push(agentsdb_format::ChunkSource::SourceString(format!(
+                "{REDACTED_SOURCE_PREFIX}{hash}"
+            )));
+        }
         prepared.push(agentsdb_format::ChunkInput {
             id,
             kind: c.kind,
-            content: content.clone(),
+            content: c.content.unwrap_or_default(),
             author: c.author,
             confidence: c.confidence,
             created_at_unix_ms: c.created_at_unix_ms,
             embedding,
-            sources: sources_to_chunk_sources(c.sources),
+            sources,
         });
     }
 
@@ -408,6 +500,8 @@ pub fn import_into_layer(
             reembedded_from: source_profile.clone(),
             reembedded_to: target_profile.clone(),
             reembedded_count: 0,
+            embedding_usage: embedder.as_deref().map(|e| UsageReport::from_embedder(e)),
+            operation: None,
         });
     }
 
@@ -420,13 +514,17 @@ pub fn import_into_layer(
         let desired = LayerMetadataV1::from_json_bytes(layer_metadata_json)
             .context("parse desired layer metadata")?;
         if existing.embedding_profile != desired.embedding_profile {
-            anyhow::bail!(
-                "embedder profile mismatch vs target layer metadata (existing={:?}, current={:?})",
-                existing.embedding_profile,
-                desired.embedding_profile
-            );
+            return Err(ProfileMismatchError {
+                layer: abs_path.to_path_buf(),
+                existing: existing.embedding_profile,
+                current: desired.embedding_profile,
+            }
+            .into());
         }
     }
+    let layer_metadata_json = layer_metadata_json
+        .map(|json| carry_kinds(json, existing_meta.as_deref()))
+        .transpose()?;
 
     let prepared_len = prepared.len();
 
@@ -438,23 +536,27 @@ pub fn import_into_layer(
             reembedded_from: source_profile.clone(),
             reembedded_to: target_profile.clone(),
             reembedded_count,
+            embedding_usage: embedder.as_deref().map(|e| UsageReport::from_embedder(e)),
+            operation: None,
         });
     }
 
-    if exists {
+    let appended = prepared.len();
+    let ids = if exists {
         let mut new_chunks = prepared;
         agentsdb_format::append_layer_atomic(
             abs_path,
             &mut new_chunks,
             layer_metadata_json.as_deref(),
         )
-        .context("append")?;
+        .context("append")?
     } else {
         let mut new_chunks = prepared;
         let schema = agentsdb_format::LayerSchema {
             dim: inferred_dim as u32,
             element_type: agentsdb_format::EmbeddingElementType::F32,
             quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
         };
         agentsdb_format::write_layer_atomic(
             abs_path,
@@ -462,8 +564,15 @@ pub fn import_into_layer(
             &mut new_chunks,
             layer_metadata_json.as_deref(),
         )
-        .context("create layer")?;
-    }
+        .context("create layer")?
+    };
+    crate::metrics::record_append(abs_path, appended);
+    let operation = crate::journal::record_operation(
+        crate::journal::OperationKind::Import,
+        abs_path,
+        None,
+        ids,
+    )?;
 
     Ok(ImportOutcome {
         imported: prepared_len,
@@ -472,6 +581,8 @@ pub fn import_into_layer(
         reembedded_from: source_profile,
         reembedded_to: target_profile,
         reembedded_count,
+        embedding_usage: embedder.as_deref().map(|e| UsageReport::from_embedder(e)),
+        operation: Some(operation),
     })
 }
 
@@ -526,7 +637,7 @@ pub fn import_export_bundle_into_dir(
             },
             layers: vec![layer],
         };
-        let data = serde_json::to_string(&single).context("serialize layer bundle")?;
+        let data = serde_json::to_vec(&single).context("serialize layer bundle")?;
 
         let outcome = import_into_layer(
             &abs_path,
@@ -666,4 +777,128 @@ mod tests {
         assert_eq!(by_path.get("AGENTS.delta.db").copied().unwrap_or_default(), 1);
         assert_eq!(by_path.get("AGENTS.local.db").copied().unwrap_or_default(), 1);
     }
+
+    #[test]
+    fn parse_export_bytes_reads_v2_json_and_ndjson() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("AGENTS.local.db");
+        let schema = agentsdb_format::LayerSchema {
+            dim: 4,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let sources = vec![
+            agentsdb_format::ChunkSource::ChunkId(1),
+            agentsdb_format::ChunkSource::SourceString("supersedes:1".to_string()),
+            agentsdb_format::ChunkSource::SourceString("namespace:api".to_string()),
+            agentsdb_format::ChunkSource::SourceString("README.md:3".to_string()),
+        ];
+        let mut chunks = [agentsdb_format::ChunkInput {
+            id: 2,
+            kind: "note".to_string(),
+            content: "use pnpm".to_string(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 1,
+            embedding: vec![0.0; 4],
+            sources: sources.clone(),
+        }];
+        agentsdb_format::write_layer_atomic(&path, &schema, &mut chunks, None)?;
+
+        for format in ["json", "ndjson"] {
+            let (_, bytes) = crate::export::export_layer(
+                &path,
+                "AGENTS.local.db",
+                format,
+                "none",
+                None,
+                "test",
+                "0",
+            )?;
+            let text = String::from_utf8(bytes.clone())?;
+            assert!(text.contains("agentsdb.export"), "{format}: {text}");
+            assert!(text.contains(".v2\""), "{format}: {text}");
+            assert!(text.contains("\"supersedes\""), "{format}: {text}");
+            assert!(text.contains("\"file_sha256\""), "{format}: {text}");
+
+            let parsed = parse_export_bytes(&bytes)?;
+            assert_eq!(parsed.layers.len(), 1);
+            let chunk = &parsed.layers[0].chunks[0];
+            let debug = |sources: &[agentsdb_format::ChunkSource]| {
+                let mut out: Vec<String> = sources.iter().map(|s| format!("{s:?}")).collect();
+                out.sort();
+                out
+            };
+            assert_eq!(
+                debug(&sources_to_chunk_sources(chunk.sources.clone())),
+                debug(&sources),
+                "{format}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn content_redacted_exports_import_as_embedding_only_chunks() -> anyhow::Result<()> {
+        let src = tempfile::tempdir()?;
+        let path = src.path().join("AGENTS.local.db");
+        let schema = agentsdb_format::LayerSchema {
+            dim: 4,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let mut chunks = [agentsdb_format::ChunkInput {
+            id: 1,
+            kind: "note".to_string(),
+            content: "patient records live in vault".to_string(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 1,
+            embedding: vec![0.5, 0.5, 0.0, 0.0],
+            sources: Vec::new(),
+        }];
+        agentsdb_format::write_layer_atomic(&path, &schema, &mut chunks, None)?;
+        let hash = content_sha256_hex(&chunks[0].content);
+        let export = |path: &Path, redact: &str| {
+            crate::export::export_layer(path, "AGENTS.local.db", "json", redact, None, "test", "0")
+                .map(|(_, bytes)| bytes)
+        };
+        let redacted = export(&path, "content")?;
+        let chunk = &parse_export_bytes(&redacted)?.layers[0].chunks[0];
+        assert_eq!(chunk.content, None);
+        assert_eq!(chunk.content_sha256.as_deref(), Some(hash.as_str()));
+
+        let dst = tempfile::tempdir()?;
+        let target = dst.path().join("AGENTS.local.db");
+        let import = |data: &[u8]| {
+            import_into_layer(
+                &target, "local", data, false, true, false, false, None, "t", "0",
+            )
+        };
+        assert_eq!(import(&redacted)?.imported, 1);
+        let file = agentsdb_format::LayerFile::open(&target)?;
+        let stored = &agentsdb_format::read_all_chunks(&file)?[0];
+        assert_eq!(stored.content, "");
+        assert_eq!(stored.embedding, chunks[0].embedding);
+        assert_eq!(
+            redacted_content_sha256(&stored.sources),
+            Some(hash.as_str())
+        );
+        drop(file);
+
+        // Deduped against the redacted record; the full export carries the same hash.
+        assert_eq!(import(&export(&path, "none")?)?.skipped, 1);
+        let chunk = &parse_export_bytes(&export(&target, "none")?)?.layers[0].chunks[0];
+        assert_eq!(chunk.content, None);
+        assert_eq!(chunk.content_sha256.as_deref(), Some(hash.as_str()));
+        assert!(chunk.sources.is_empty());
+
+        assert!(matches!(
+            import(&export(&path, "all")?),
+            Err(ImportError::MissingContent { id: 1, .. })
+        ));
+        Ok(())
+    }
 }
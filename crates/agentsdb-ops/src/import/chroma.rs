@@ -0,0 +1,192 @@
+//! Import from a Chroma collection over the REST API (v2).
+//!
+//! Records are fetched in pages with their documents, metadata and embeddings. The document is
+//! the chunk content; metadata keys are matched as described in [`super::foreign`].
+
+use anyhow::Context;
+use serde_json::{json, Value};
+
+use agentsdb_core::export::{ExportBundleV1, ExportChunkV1};
+
+use super::foreign::{
+    single_layer_bundle, unix_ms, ForeignFields, AUTHOR_FIELDS, CONFIDENCE_FIELDS,
+    CREATED_AT_FIELDS, KIND_FIELDS, SOURCE_FIELDS,
+};
+
+/// Env var holding a Chroma auth token, sent as a bearer token.
+pub const TOKEN_ENV: &str = "CHROMA_TOKEN";
+
+pub const DEFAULT_TENANT: &str = "default_tenant";
+pub const DEFAULT_DATABASE: &str = "default_database";
+
+/// Records fetched per request.
+const PAGE_SIZE: usize = 500;
+
+pub struct ChromaSource {
+    /// Base URL of the collection's database, e.g.
+    /// `http://localhost:8000/api/v2/tenants/default_tenant/databases/default_database`.
+    base: String,
+    collection: String,
+    token: Option<String>,
+}
+
+impl ChromaSource {
+    /// `url` is the server, e.g. `http://localhost:8000`.
+    pub fn new(url: &str, tenant: &str, database: &str, collection: &str) -> Self {
+        Self {
+            base: format!(
+                "{}/api/v2/tenants/{tenant}/databases/{database}",
+                url.trim_end_matches('/')
+            ),
+            collection: collection.to_string(),
+            token: std::env::var(TOKEN_ENV).ok().filter(|t| !t.is_empty()),
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let request = ureq::request(method, &format!("{}/collections/{path}", self.base))
+            .set("Content-Type", "application/json");
+        match &self.token {
+            Some(token) => request.set("Authorization", &format!("Bearer {token}")),
+            None => request,
+        }
+    }
+
+    /// Reads every record of the collection into a one-layer bundle.
+    pub fn read_bundle(&self) -> anyhow::Result<ExportBundleV1> {
+        let info: Value = serde_json::from_str(
+            &self
+                .request("GET", &self.collection)
+                .call()
+                .with_context(|| format!("get Chroma collection {}", self.collection))?
+                .into_string()?,
+        )
+        .context("parse collection info")?;
+        let id = info
+            .get("id")
+            .and_then(Value::as_str)
+            .context("Chroma collection info has no id")?;
+
+        let now_ms = crate::util::now_unix_ms();
+        let mut chunks = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page: Value = serde_json::from_str(
+                &self
+                    .request("POST", &format!("{id}/get"))
+                    .send_string(
+                        &json!({
+                            "limit": PAGE_SIZE,
+                            "offset": offset,
+                            "include": ["documents", "metadatas", "embeddings"],
+                        })
+                        .to_string(),
+                    )
+                    .with_context(|| {
+                        format!("get records of Chroma collection {}", self.collection)
+                    })?
+                    .into_string()?,
+            )
+            .context("parse Chroma records")?;
+            let count = page
+                .get("ids")
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            chunks.extend(page_chunks(&page, &self.collection, now_ms)?);
+            if count < PAGE_SIZE {
+                break;
+            }
+            offset += count;
+        }
+        Ok(single_layer_bundle(
+            format!("chroma:{}", self.collection),
+            chunks,
+        ))
+    }
+}
+
+fn string_field(metadata: Option<&Value>, names: &[&str]) -> Option<String> {
+    let value = names.iter().find_map(|n| metadata?.get(*n))?;
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Null | Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+fn created_at(metadata: Option<&Value>) -> Option<u64> {
+    let value = CREATED_AT_FIELDS.iter().find_map(|n| metadata?.get(*n))?;
+    #[allow(clippy::cast_possible_truncation)]
+    value
+        .as_i64()
+        .or_else(|| value.as_f64().map(|v| v as i64))
+        .or_else(|| value.as_str()?.trim().parse().ok())
+        .and_then(unix_ms)
+}
+
+/// Chunks for one page of a collection `get` response (records without a document are skipped).
+fn page_chunks(page: &Value, collection: &str, now_ms: u64) -> anyhow::Result<Vec<ExportChunkV1>> {
+    let column = |name: &str| page.get(name).and_then(Value::as_array);
+    let ids = column("ids").context("Chroma response has no ids")?;
+    let mut out = Vec::new();
+    for (i, id) in ids.iter().enumerate() {
+        let id = id.as_str().map_or_else(|| id.to_string(), str::to_string);
+        let Some(document) = column("documents")
+            .and_then(|d| d.get(i))
+            .and_then(Value::as_str)
+            .filter(|d| !d.trim().is_empty())
+        else {
+            continue;
+        };
+        let metadata = column("metadatas")
+            .and_then(|m| m.get(i))
+            .filter(|m| m.is_object());
+        let embedding = column("embeddings")
+            .and_then(|e| e.get(i))
+            .filter(|e| !e.is_null())
+            .map(|e| serde_json::from_value::<Vec<f32>>(e.clone()))
+            .transpose()
+            .with_context(|| format!("embedding of Chroma record {id}"))?;
+        let confidence = CONFIDENCE_FIELDS
+            .iter()
+            .find_map(|n| metadata?.get(*n)?.as_f64());
+        let fields = ForeignFields {
+            kind: string_field(metadata, KIND_FIELDS),
+            author: string_field(metadata, AUTHOR_FIELDS),
+            confidence,
+            created_at_unix_ms: created_at(metadata),
+            source: string_field(metadata, SOURCE_FIELDS),
+        };
+        out.push(fields.into_chunk(
+            document.to_string(),
+            embedding,
+            format!("chroma:{collection}/{id}"),
+            now_ms,
+        ));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_records_with_metadata() -> anyhow::Result<()> {
+        let page = json!({
+            "ids": ["a", "b"],
+            "documents": ["Use rustfmt", null],
+            "metadatas": [{"type": "convention", "confidence": 0.9, "timestamp": 1700000000}, null],
+            "embeddings": [[0.1, 0.2, 0.3], [0.0, 0.0, 0.0]],
+        });
+        let chunks = page_chunks(&page, "notes", 7)?;
+        assert_eq!(chunks.len(), 1);
+        let chunk = &chunks[0];
+        assert_eq!(chunk.kind, "convention");
+        assert!((chunk.confidence - 0.9).abs() < f32::EPSILON);
+        assert_eq!(chunk.created_at_unix_ms, 1_700_000_000_000);
+        assert_eq!(chunk.embedding.as_ref().map(Vec::len), Some(3));
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! Field mapping shared by the importers for other memory stores ([`super::sqlite`],
+//! [`super::chroma`]).
+//!
+//! Records are matched by column / metadata key name, first match wins:
+//!
+//! | chunk field | names |
+//! |---|---|
+//! | content (required) | `content`, `text`, `document`, `memory`, `page_content` |
+//! | embedding | `embedding`, `vector` |
+//! | kind | `kind`, `type`, `category` |
+//! | author | `author`, `role` |
+//! | confidence | `confidence` |
+//! | created_at_unix_ms | `created_at`, `timestamp`, `created` |
+//! | sources | `source`, `path`, `url` |
+
+use agentsdb_core::export::{
+    ExportBundleV1, ExportChunkV1, ExportLayerSchemaV1, ExportLayerV1, ExportSourceV1,
+    ExportToolInfo,
+};
+
+pub(crate) const CONTENT_FIELDS: &[&str] =
+    &["content", "text", "document", "memory", "page_content"];
+pub(crate) const EMBEDDING_FIELDS: &[&str] = &["embedding", "vector"];
+pub(crate) const KIND_FIELDS: &[&str] = &["kind", "type", "category"];
+pub(crate) const AUTHOR_FIELDS: &[&str] = &["author", "role"];
+pub(crate) const CONFIDENCE_FIELDS: &[&str] = &["confidence"];
+pub(crate) const CREATED_AT_FIELDS: &[&str] = &["created_at", "timestamp", "created"];
+pub(crate) const SOURCE_FIELDS: &[&str] = &["source", "path", "url"];
+
+/// Kind of imported memories that carry none.
+pub(crate) const DEFAULT_KIND: &str = "note";
+
+/// Timestamps below this are taken as seconds, above as milliseconds (1e11 s is year 5138).
+const SECONDS_LIMIT: i64 = 100_000_000_000;
+
+/// The optional chunk fields of one foreign record.
+#[derive(Debug, Default)]
+pub(crate) struct ForeignFields {
+    pub(crate) kind: Option<String>,
+    pub(crate) author: Option<String>,
+    pub(crate) confidence: Option<f64>,
+    pub(crate) created_at_unix_ms: Option<u64>,
+    pub(crate) source: Option<String>,
+}
+
+impl ForeignFields {
+    /// Builds the chunk; `origin` locates the record in the store (`chroma:notes/abc`) and is
+    /// kept as its first source. Ids are left to the import.
+    pub(crate) fn into_chunk(
+        self,
+        content: String,
+        embedding: Option<Vec<f32>>,
+        origin: String,
+        now_ms: u64,
+    ) -> ExportChunkV1 {
+        let mut sources = vec![ExportSourceV1::SourceString { value: origin }];
+        sources.extend(
+            self.source
+                .filter(|s| !s.is_empty())
+                .map(|value| ExportSourceV1::SourceString { value }),
+        );
+        #[allow(clippy::cast_possible_truncation)]
+        let confidence = self.confidence.map_or(1.0, |c| c.clamp(0.0, 1.0) as f32);
+        ExportChunkV1 {
+            id: 0,
+            kind: self
+                .kind
+                .filter(|k| !k.is_empty())
+                .unwrap_or_else(|| DEFAULT_KIND.to_string()),
+            content: Some(content),
+            author: self
+                .author
+                .filter(|a| !a.is_empty())
+                .unwrap_or_else(|| "human".to_string()),
+            confidence,
+            created_at_unix_ms: self.created_at_unix_ms.unwrap_or(now_ms),
+            sources,
+            embedding,
+            content_sha256: None,
+        }
+    }
+}
+
+/// Milliseconds since the epoch from a timestamp in seconds or milliseconds.
+pub(crate) fn unix_ms(value: i64) -> Option<u64> {
+    let ms = if value < SECONDS_LIMIT {
+        value.checked_mul(1000)?
+    } else {
+        value
+    };
+    u64::try_from(ms).ok()
+}
+
+/// A one-layer bundle for [`super::import_into_layer`]; the schema dim is that of the first
+/// embedding (0 if none), and chunks whose embedding differs from the target dim get re-embedded.
+pub(crate) fn single_layer_bundle(path: String, chunks: Vec<ExportChunkV1>) -> ExportBundleV1 {
+    let dim = chunks
+        .iter()
+        .find_map(|c| c.embedding.as_ref().map(Vec::len))
+        .and_then(|d| u32::try_from(d).ok())
+        .unwrap_or(0);
+    ExportBundleV1 {
+        format: "agentsdb.export.v1".to_string(),
+        tool: ExportToolInfo {
+            name: "agentsdb-ops".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+        layers: vec![ExportLayerV1 {
+            path,
+            layer: None,
+            schema: ExportLayerSchemaV1 {
+                dim,
+                element_type: "f32".to_string(),
+                quant_scale: 1.0,
+            },
+            layer_metadata_json: None,
+            chunks,
+        }],
+    }
+}
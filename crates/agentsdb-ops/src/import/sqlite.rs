@@ -0,0 +1,315 @@
+//! Import from SQLite memory stores.
+//!
+//! Each row of a table (or of a custom `SELECT`) becomes one chunk, with columns matched by
+//! name as described in [`super::foreign`]. Embeddings may be stored as a BLOB of
+//! little-endian f32 values or as JSON array text; timestamps as seconds or milliseconds since
+//! the epoch, or as text SQLite's date functions understand (`2024-05-01 12:00:00`, ISO 8601).
+
+use anyhow::Context;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use std::path::Path;
+
+use agentsdb_core::export::{ExportBundleV1, ExportChunkV1};
+
+use super::foreign::{
+    single_layer_bundle, unix_ms, ForeignFields, AUTHOR_FIELDS, CONFIDENCE_FIELDS, CONTENT_FIELDS,
+    CREATED_AT_FIELDS, EMBEDDING_FIELDS, KIND_FIELDS, SOURCE_FIELDS,
+};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqliteImportOptions<'a> {
+    /// Table to read; found automatically when exactly one table has a content column.
+    pub table: Option<&'a str>,
+    /// A `SELECT` to read instead of a table (column names are matched the same way).
+    pub query: Option<&'a str>,
+}
+
+/// Column positions of the chunk fields in the result set.
+struct Columns {
+    content: usize,
+    embedding: Option<usize>,
+    kind: Option<usize>,
+    author: Option<usize>,
+    confidence: Option<usize>,
+    created_at: Option<usize>,
+    source: Option<usize>,
+    id: Option<usize>,
+}
+
+fn find_column(names: &[String], candidates: &[&str]) -> Option<usize> {
+    candidates
+        .iter()
+        .find_map(|c| names.iter().position(|n| n.eq_ignore_ascii_case(c)))
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// The only table with a content column, or an error naming the choices.
+fn detect_table(conn: &Connection) -> anyhow::Result<String> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )?;
+    let tables = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut candidates = Vec::new();
+    for table in tables {
+        let mut info = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+        let columns = info
+            .query_map([&table], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        if find_column(&columns, CONTENT_FIELDS).is_some() {
+            candidates.push(table);
+        }
+    }
+    match candidates.as_slice() {
+        [table] => Ok(table.clone()),
+        [] => anyhow::bail!(
+            "no table has a content column ({}); pass --table or --query",
+            CONTENT_FIELDS.join(", ")
+        ),
+        _ => anyhow::bail!(
+            "several tables could hold memories ({}); pass --table",
+            candidates.join(", ")
+        ),
+    }
+}
+
+fn text(value: ValueRef<'_>) -> anyhow::Result<Option<String>> {
+    Ok(match value {
+        ValueRef::Null => None,
+        ValueRef::Integer(v) => Some(v.to_string()),
+        ValueRef::Real(v) => Some(v.to_string()),
+        ValueRef::Text(v) | ValueRef::Blob(v) => Some(
+            std::str::from_utf8(v)
+                .context("text column is not valid UTF-8")?
+                .to_string(),
+        ),
+    })
+}
+
+fn embedding(value: ValueRef<'_>) -> anyhow::Result<Option<Vec<f32>>> {
+    match value {
+        ValueRef::Null => Ok(None),
+        ValueRef::Blob(bytes) => {
+            if bytes.len() % 4 != 0 {
+                anyhow::bail!(
+                    "embedding BLOB of {} bytes is not a list of f32",
+                    bytes.len()
+                );
+            }
+            Ok(Some(
+                bytes
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect(),
+            ))
+        }
+        ValueRef::Text(text) => Ok(Some(
+            serde_json::from_slice(text).context("embedding text is not a JSON array")?,
+        )),
+        ValueRef::Integer(_) | ValueRef::Real(_) => {
+            anyhow::bail!("embedding column holds a number, not a vector")
+        }
+    }
+}
+
+fn confidence(value: ValueRef<'_>) -> anyhow::Result<Option<f64>> {
+    #[allow(clippy::cast_precision_loss)]
+    Ok(match value {
+        ValueRef::Null => None,
+        ValueRef::Integer(v) => Some(v as f64),
+        ValueRef::Real(v) => Some(v),
+        ValueRef::Text(_) | ValueRef::Blob(_) => text(value)?
+            .map(|t| t.trim().parse::<f64>())
+            .transpose()
+            .context("confidence is not a number")?,
+    })
+}
+
+fn created_at(conn: &Connection, value: ValueRef<'_>) -> anyhow::Result<Option<u64>> {
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(match value {
+        ValueRef::Null | ValueRef::Blob(_) => None,
+        ValueRef::Integer(v) => unix_ms(v),
+        ValueRef::Real(v) => unix_ms(v as i64),
+        ValueRef::Text(_) => {
+            let text = text(value)?.unwrap_or_default();
+            match text.trim().parse::<i64>() {
+                Ok(v) => unix_ms(v),
+                // Let SQLite parse the date; NULL when it cannot.
+                Err(_) => conn
+                    .query_row(
+                        "SELECT CAST(strftime('%s', ?1) AS INTEGER)",
+                        [&text],
+                        |row| row.get::<_, Option<i64>>(0),
+                    )?
+                    .and_then(unix_ms),
+            }
+        }
+    })
+}
+
+fn optional<T>(
+    row: &rusqlite::Row<'_>,
+    column: Option<usize>,
+    read: impl FnOnce(ValueRef<'_>) -> anyhow::Result<Option<T>>,
+) -> anyhow::Result<Option<T>> {
+    column.map_or(Ok(None), |i| read(row.get_ref(i)?))
+}
+
+fn row_chunk(
+    conn: &Connection,
+    row: &rusqlite::Row<'_>,
+    columns: &Columns,
+    origin: &str,
+    number: usize,
+    now_ms: u64,
+) -> anyhow::Result<Option<ExportChunkV1>> {
+    let Some(content) = text(row.get_ref(columns.content)?)?.filter(|c| !c.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    let key = optional(row, columns.id, text)?.unwrap_or_else(|| number.to_string());
+    let fields = ForeignFields {
+        kind: optional(row, columns.kind, text)?,
+        author: optional(row, columns.author, text)?,
+        confidence: optional(row, columns.confidence, confidence)?,
+        created_at_unix_ms: optional(row, columns.created_at, |v| created_at(conn, v))?,
+        source: optional(row, columns.source, text)?,
+    };
+    let embedding =
+        optional(row, columns.embedding, embedding).with_context(|| format!("{origin}/{key}"))?;
+    Ok(Some(fields.into_chunk(
+        content,
+        embedding,
+        format!("{origin}/{key}"),
+        now_ms,
+    )))
+}
+
+/// Reads the memories of the SQLite database at `path` into a one-layer bundle. Rows without
+/// content are skipped.
+pub fn read_bundle(
+    path: &Path,
+    options: SqliteImportOptions<'_>,
+) -> anyhow::Result<ExportBundleV1> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("open SQLite database {}", path.display()))?;
+    let (sql, label) = match (options.query, options.table) {
+        (Some(query), _) => (query.to_string(), "query".to_string()),
+        (None, Some(table)) => (
+            format!("SELECT * FROM {}", quote_ident(table)),
+            table.to_string(),
+        ),
+        (None, None) => {
+            let table = detect_table(&conn)?;
+            (format!("SELECT * FROM {}", quote_ident(&table)), table)
+        }
+    };
+    let mut stmt = conn
+        .prepare(&sql)
+        .with_context(|| format!("prepare {sql:?}"))?;
+    let names: Vec<String> = stmt
+        .column_names()
+        .iter()
+        .map(|n| (*n).to_string())
+        .collect();
+    let columns = Columns {
+        content: find_column(&names, CONTENT_FIELDS).with_context(|| {
+            format!(
+                "{label} has no content column ({}); alias one in --query",
+                CONTENT_FIELDS.join(", ")
+            )
+        })?,
+        embedding: find_column(&names, EMBEDDING_FIELDS),
+        kind: find_column(&names, KIND_FIELDS),
+        author: find_column(&names, AUTHOR_FIELDS),
+        confidence: find_column(&names, CONFIDENCE_FIELDS),
+        created_at: find_column(&names, CREATED_AT_FIELDS),
+        source: find_column(&names, SOURCE_FIELDS),
+        id: find_column(&names, &["id"]),
+    };
+
+    let file = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().to_string(),
+    );
+    let origin = format!("sqlite:{file}/{label}");
+    let now_ms = crate::util::now_unix_ms();
+    let mut chunks = Vec::new();
+    let mut rows = stmt.query([])?;
+    let mut number = 0usize;
+    while let Some(row) = rows.next()? {
+        number += 1;
+        if let Some(chunk) = row_chunk(&conn, row, &columns, &origin, number, now_ms)? {
+            chunks.push(chunk);
+        }
+    }
+    Ok(single_layer_bundle(path.display().to_string(), chunks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentsdb_core::export::ExportSourceV1;
+
+    #[test]
+    fn maps_memory_rows_to_chunks() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("memory.sqlite");
+        let conn = Connection::open(&path)?;
+        let blob: Vec<u8> = [0.5f32, -1.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT, value TEXT);
+             CREATE TABLE memories (id TEXT, text TEXT, type TEXT, created_at TEXT, embedding BLOB);",
+        )?;
+        conn.execute(
+            "INSERT INTO memories VALUES ('m1', 'Prefers tabs', 'preference', '2024-01-02 00:00:00', ?1)",
+            [&blob],
+        )?;
+        conn.execute(
+            "INSERT INTO memories VALUES ('m2', '', NULL, 1700000000, NULL)",
+            [],
+        )?;
+        drop(conn);
+
+        let bundle = read_bundle(&path, SqliteImportOptions::default())?;
+        let layer = bundle.layers.first().context("one layer")?;
+        assert_eq!(layer.schema.dim, 2);
+        assert_eq!(layer.chunks.len(), 1, "rows without content are skipped");
+        let chunk = &layer.chunks[0];
+        assert_eq!(chunk.kind, "preference");
+        assert_eq!(chunk.content.as_deref(), Some("Prefers tabs"));
+        assert_eq!(chunk.created_at_unix_ms, 1_704_153_600_000);
+        assert_eq!(chunk.embedding.as_deref(), Some(&[0.5, -1.0][..]));
+        assert!(matches!(
+            chunk.sources.first(),
+            Some(ExportSourceV1::SourceString { value }) if value == "sqlite:memory.sqlite/memories/m1"
+        ));
+
+        let queried = read_bundle(
+            &path,
+            SqliteImportOptions {
+                table: None,
+                query: Some("SELECT key AS content FROM settings"),
+            },
+        )?;
+        assert!(queried.layers[0].chunks.is_empty());
+        assert!(read_bundle(
+            &path,
+            SqliteImportOptions {
+                table: Some("settings"),
+                query: None,
+            }
+        )
+        .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+//! Keeps the `.agix` search indexes of the layers a server reads up to date.
+//!
+//! A search only uses a layer's index while it was built from the layer's current bytes; after a
+//! write it scans the layer instead until someone runs `agentsdb index` again. With
+//! `server.maintain_indexes` set, the MCP and web servers [`start_for_layers`] an
+//! [`IndexMaintainer`]: a background thread that builds missing or stale indexes on startup,
+//! then checks the layers every `server.index_poll_secs` and rebuilds the index of any layer that
+//! changed, so searches after a write stay fast without building on the request path.
+
+use anyhow::Context;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use agentsdb_query::{build_layer_index, default_index_path_for_layer};
+
+/// Builds the default index of the layer at `path` unless it is fresh, keeping the options a
+/// stale index was built with.
+///
+/// # Returns
+/// Whether the index was (re)built
+pub fn refresh_index(path: &Path) -> anyhow::Result<bool> {
+    let layer = agentsdb_format::LayerFile::open(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    let index_path = default_index_path_for_layer(path);
+    if agentsdb_query::layer_index_is_fresh(&layer, &index_path)
+        .with_context(|| format!("read index {}", index_path.display()))?
+    {
+        return Ok(false);
+    }
+    // An unreadable index is replaced with a default one.
+    let options = agentsdb_query::index_build_options(&index_path)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    build_layer_index(&layer, &index_path, options)
+        .with_context(|| format!("build index for {}", path.display()))?;
+    Ok(true)
+}
+
+/// Rebuilds the indexes of a set of layers in the background while alive; see the module docs.
+/// Dropping it stops the thread.
+#[derive(Debug)]
+pub struct IndexMaintainer {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl IndexMaintainer {
+    /// Starts maintaining the indexes of `layers`, checking them for changes every `poll`.
+    pub fn start(layers: Vec<PathBuf>, poll: Duration) -> anyhow::Result<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&stop);
+        let thread = std::thread::Builder::new()
+            .name("agentsdb-index".to_string())
+            .spawn(move || maintain(&layers, poll, &flag))
+            .context("spawn index maintenance thread")?;
+        Ok(Self {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for IndexMaintainer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Starts an [`IndexMaintainer`] for `layers` when the `server.maintain_indexes` setting of the
+/// layers in `dir` is set.
+pub fn start_for_layers(
+    dir: &Path,
+    layers: Vec<PathBuf>,
+) -> anyhow::Result<Option<IndexMaintainer>> {
+    let paths = agentsdb_embeddings::config::standard_layer_paths_for_dir(dir);
+    let settings = agentsdb_embeddings::settings::resolve_settings(&paths, None)?.server();
+    if !settings.maintain_indexes || layers.is_empty() {
+        return Ok(None);
+    }
+    let poll = Duration::from_secs(settings.index_poll_secs.max(1));
+    IndexMaintainer::start(layers, poll).map(Some)
+}
+
+/// Size and modification time of `path`, to notice rewrites without reading the file.
+fn stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+fn maintain(layers: &[PathBuf], poll: Duration, stop: &AtomicBool) {
+    let mut seen: HashMap<&Path, Option<(u64, SystemTime)>> = HashMap::new();
+    while !stop.load(Ordering::SeqCst) {
+        for path in layers {
+            let current = stamp(path);
+            if seen.get(path.as_path()) == Some(&current) {
+                continue;
+            }
+            seen.insert(path, current);
+            if current.is_none() {
+                continue;
+            }
+            let started = std::time::Instant::now();
+            match refresh_index(path) {
+                Ok(true) => tracing::info!(
+                    elapsed_ms = started.elapsed().as_millis(),
+                    "rebuilt the index of {}",
+                    path.display()
+                ),
+                Ok(false) => {}
+                // Usually a layer caught mid-write; the next change retries.
+                Err(err) => tracing::warn!("index of {} not rebuilt: {err:#}", path.display()),
+            }
+        }
+        std::thread::park_timeout(poll);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+
+    #[test]
+    fn stale_indexes_are_rebuilt() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("AGENTS.local.db");
+        write_layer(&path, vec![chunk(1, "note")])?;
+
+        assert!(refresh_index(&path)?);
+        assert!(!refresh_index(&path)?);
+        write_layer(&path, vec![chunk(1, "note"), chunk(2, "note")])?;
+        assert!(refresh_index(&path)?);
+
+        // A rebuilt index keeps the options it was built with.
+        let options = agentsdb_query::IndexBuildOptions {
+            store_embeddings_even_if_f32: false,
+            binary_codes: true,
+        };
+        let layer = agentsdb_format::LayerFile::open(&path)?;
+        build_layer_index(&layer, default_index_path_for_layer(&path), options)?;
+        drop(layer);
+        write_layer(&path, vec![chunk(1, "note")])?;
+        assert!(refresh_index(&path)?);
+        assert_eq!(
+            agentsdb_query::index_build_options(default_index_path_for_layer(&path))?,
+            Some(options)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+//! Operation journal: promotions and imports are recorded in a sidecar `AGENTS.journal.json` next
+//! to the layer they wrote, so `agentsdb undo <op-id>` can take them back.
+//!
+//! Undo is append-only like every other edit: the chunks an operation introduced are tombstoned in
+//! the layer it wrote, and chunks a promotion moved out of its source layer are written back there.
+
+use agentsdb_format::{ChunkInput, ChunkSource, LayerFile};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::util::now_unix_ms;
+
+/// Sidecar file name for the operation journal.
+const JOURNAL_FILE: &str = "AGENTS.journal.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Promote,
+    Import,
+}
+
+/// A chunk a promotion moved out of its source layer: its id and author there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MovedChunk {
+    pub id: u32,
+    pub author: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: u32,
+    pub kind: OperationKind,
+    pub created_at_unix_ms: u64,
+    /// Layer the operation wrote, relative to the journal's directory.
+    pub layer: String,
+    /// Layer a promotion moved its chunks out of, relative to the journal's directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Chunk ids the operation introduced in `layer`.
+    pub chunk_ids: Vec<u32>,
+    /// For promotions, the chunk each of `chunk_ids` was in `from`, in the same order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moved: Vec<MovedChunk>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undone_at_unix_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Journal {
+    pub operations: Vec<Operation>,
+}
+
+impl Journal {
+    /// Build the journal file path given the directory holding the layers.
+    pub fn path_for(dir: &Path) -> PathBuf {
+        dir.join(JOURNAL_FILE)
+    }
+
+    /// Load from disk, returning an empty journal if the file doesn't exist. A journal that
+    /// cannot be parsed is an error, so recording an operation never drops the earlier ones.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let path = Self::path_for(dir);
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).with_context(|| format!("parse {}", path.display()))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("read {}", path.display())),
+        }
+    }
+
+    /// Persist to disk.
+    pub fn save(&self, dir: &Path) -> anyhow::Result<()> {
+        let path = Self::path_for(dir);
+        let json = serde_json::to_vec_pretty(self)?;
+        std::fs::write(&path, json).with_context(|| format!("write {}", path.display()))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Operation> {
+        self.operations.iter().find(|op| op.id == id)
+    }
+}
+
+/// Directory whose journal records writes to the layer at `path`.
+fn journal_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new("."))
+}
+
+/// `path` as stored in the journal of `dir`: the file name for layers in `dir`, else the path.
+fn journal_name(dir: &Path, path: &Path) -> String {
+    match path.file_name().and_then(|s| s.to_str()) {
+        Some(name) if path.parent() == Some(dir) => name.to_string(),
+        _ => path.display().to_string(),
+    }
+}
+
+/// Records an operation that wrote `chunk_ids` into the layer at `layer_path` and returns its id.
+/// For promotions, `from` is the source layer and the chunks moved out of it.
+pub fn record_operation(
+    kind: OperationKind,
+    layer_path: &Path,
+    from: Option<(&Path, Vec<MovedChunk>)>,
+    chunk_ids: Vec<u32>,
+) -> anyhow::Result<u32> {
+    let dir = journal_dir(layer_path);
+    let mut journal = Journal::load(dir)?;
+    let id = journal
+        .operations
+        .iter()
+        .map(|op| op.id)
+        .max()
+        .unwrap_or(0)
+        .saturating_add(1);
+    let (from, moved) = match from {
+        Some((path, moved)) => (Some(journal_name(dir, path)), moved),
+        None => (None, Vec::new()),
+    };
+    journal.operations.push(Operation {
+        id,
+        kind,
+        created_at_unix_ms: now_unix_ms(),
+        layer: journal_name(dir, layer_path),
+        from,
+        chunk_ids,
+        moved,
+        undone_at_unix_ms: None,
+    });
+    journal.save(dir)?;
+    Ok(id)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UndoOutcome {
+    pub operation: u32,
+    /// Layer the operation wrote, where its chunks were tombstoned.
+    pub layer: String,
+    /// Chunk ids tombstoned in `layer`.
+    pub retracted: Vec<u32>,
+    /// Layer a promotion's chunks were written back to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restored_to: Option<String>,
+    /// Ids of the chunks written back to `restored_to`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub restored: Vec<u32>,
+}
+
+/// Undoes operation `op_id` of the journal in `dir`: tombstones the chunks it introduced and, for
+/// promotions, writes the moved chunks back to their source layer. Tombstoning chunks in
+/// `AGENTS.db` requires `allow_base`.
+pub fn undo_operation(dir: &Path, op_id: u32, allow_base: bool) -> anyhow::Result<UndoOutcome> {
+    let mut journal = Journal::load(dir)?;
+    let Some(op) = journal.get(op_id).cloned() else {
+        anyhow::bail!(
+            "operation {op_id} not found in {}",
+            Journal::path_for(dir).display()
+        );
+    };
+    if op.undone_at_unix_ms.is_some() {
+        anyhow::bail!("operation {op_id} was already undone");
+    }
+    let layer_path = dir.join(&op.layer);
+    let policy = agentsdb_format::WritePolicy::standard().allow_user();
+    if policy.scope_of(&layer_path) == Some("base") && !allow_base {
+        anyhow::bail!("refusing to write AGENTS.db without allow_base");
+    }
+    let policy = policy.allow_base();
+    policy.check(&layer_path).context("permission check")?;
+
+    let file = LayerFile::open_lenient(&layer_path)
+        .with_context(|| format!("open {}", layer_path.display()))?;
+    let mut latest = BTreeMap::new();
+    for chunk in agentsdb_format::read_all_chunks(&file)? {
+        latest.insert(chunk.id, chunk);
+    }
+    let dim = file.embedding_dim();
+    drop(file);
+
+    let mut outcome = UndoOutcome {
+        operation: op_id,
+        layer: op.layer.clone(),
+        retracted: op
+            .chunk_ids
+            .iter()
+            .copied()
+            .filter(|id| latest.contains_key(id))
+            .collect(),
+        restored_to: None,
+        restored: Vec::new(),
+    };
+    // Restoring is staged before the tombstones, so an interrupted undo leaves the chunks in
+    // both layers rather than in neither.
+    let mut tx = crate::Transaction::new().with_policy(policy);
+    if let Some(from) = &op.from {
+        outcome.restored = restore_moved(&mut tx, &dir.join(from), &op, &latest, &layer_path)?;
+        outcome.restored_to = Some(from.clone());
+    }
+
+    let tombstones: Vec<ChunkInput> = outcome
+        .retracted
+        .iter()
+        .map(|id| ChunkInput {
+            id: 0,
+            kind: agentsdb_query::KIND_TOMBSTONE.to_string(),
+            content: format!("undo of operation {op_id}"),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: now_unix_ms(),
+            embedding: vec![0.0; dim],
+            sources: vec![ChunkSource::ChunkId(*id)],
+        })
+        .collect();
+    if !tombstones.is_empty() {
+        tx.append(&layer_path, tombstones);
+    }
+    tx.commit()
+        .with_context(|| format!("undo operation {op_id}"))?;
+
+    if let Some(entry) = journal.operations.iter_mut().find(|o| o.id == op_id) {
+        entry.undone_at_unix_ms = Some(now_unix_ms());
+    }
+    journal.save(dir)?;
+    Ok(outcome)
+}
+
+/// Stages writing the chunks a promotion moved into `layer_path` back to `from_path`, under their
+/// original ids where those are still free. Ids about to be tombstoned are avoided too, as a
+/// tombstone also retracts its id in lower layers.
+fn restore_moved(
+    tx: &mut crate::Transaction,
+    from_path: &Path,
+    op: &Operation,
+    promoted: &BTreeMap<u32, ChunkInput>,
+    layer_path: &Path,
+) -> anyhow::Result<Vec<u32>> {
+    agentsdb_format::WritePolicy::standard()
+        .allow_user()
+        .check(from_path)
+        .context("permission check")?;
+    let source = if from_path.exists() {
+        Some(
+            LayerFile::open_lenient(from_path)
+                .with_context(|| format!("open {}", from_path.display()))?,
+        )
+    } else {
+        None
+    };
+    let mut taken: HashSet<u32> = op.chunk_ids.iter().copied().collect();
+    if let Some(file) = &source {
+        for chunk in file.chunks() {
+            taken.insert(chunk?.id);
+        }
+    }
+    let mut next_free = 1u32;
+    let mut restored = Vec::new();
+    for (new_id, moved) in op.chunk_ids.iter().zip(&op.moved) {
+        let Some(chunk) = promoted.get(new_id) else {
+            continue;
+        };
+        let id = if taken.contains(&moved.id) {
+            while taken.contains(&next_free) {
+                next_free = next_free.saturating_add(1);
+            }
+            next_free
+        } else {
+            moved.id
+        };
+        taken.insert(id);
+        restored.push(ChunkInput {
+            id,
+            author: moved.author.clone(),
+            ..chunk.clone()
+        });
+    }
+    if restored.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids = restored.iter().map(|c| c.id).collect();
+    if let Some(file) = source {
+        drop(file);
+        tx.append(from_path, restored);
+    } else {
+        let target = LayerFile::open_lenient(layer_path)
+            .with_context(|| format!("open {}", layer_path.display()))?;
+        let schema = agentsdb_format::schema_of(&target);
+        let metadata = target.layer_metadata_bytes().map(<[u8]>::to_vec);
+        drop(target);
+        tx.append_or_create(from_path, &schema, metadata.as_deref(), restored);
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+
+    fn visible_ids(path: &Path) -> anyhow::Result<Vec<u32>> {
+        let layers = [(
+            agentsdb_core::types::LayerId::Delta,
+            LayerFile::open_lenient(path)?,
+        )];
+        let retracted = agentsdb_query::retracted_chunk_ids(&layers)?;
+        let mut out = Vec::new();
+        for c in layers[0].1.chunks() {
+            let c = c?;
+            if c.kind != agentsdb_query::KIND_TOMBSTONE && !retracted.contains_key(&c.id) {
+                out.push(c.id);
+            }
+        }
+        Ok(out)
+    }
+
+    #[test]
+    fn undo_retracts_promoted_chunks_and_restores_the_source() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let local = dir.path().join("AGENTS.local.db");
+        let delta = dir.path().join("AGENTS.delta.db");
+        let by_mcp = ChunkInput {
+            author: "mcp".to_string(),
+            ..chunk(1, "chunk 1")
+        };
+        write_layer(&local, vec![by_mcp, chunk(2, "chunk 2")])?;
+        write_layer(&delta, vec![chunk(7, "chunk 7")])?;
+
+        let outcome = crate::promote::promote_chunks(
+            &local.display().to_string(),
+            &delta.display().to_string(),
+            &[1],
+            false,
+        )?;
+        let op_id = outcome.operation.context("promotion journaled")?;
+        assert_eq!(visible_ids(&local)?, vec![2]);
+        let journal = Journal::load(dir.path())?;
+        let op = journal.get(op_id).context("operation")?;
+        assert_eq!(op.kind, OperationKind::Promote);
+        assert_eq!(op.layer, "AGENTS.delta.db");
+        assert_eq!(op.from.as_deref(), Some("AGENTS.local.db"));
+        assert_eq!(op.chunk_ids, outcome.promoted);
+
+        let undone = undo_operation(dir.path(), op_id, false)?;
+        assert_eq!(undone.retracted, outcome.promoted);
+        assert_eq!(visible_ids(&delta)?, vec![7]);
+        assert_eq!(undone.restored, vec![1]);
+        let restored = agentsdb_format::read_all_chunks(&LayerFile::open_lenient(&local)?)?;
+        assert!(restored.iter().any(|c| c.id == 1 && c.author == "mcp"));
+
+        assert!(undo_operation(dir.path(), op_id, false).is_err());
+        assert!(undo_operation(dir.path(), op_id + 1, false).is_err());
+        Ok(())
+    }
+}
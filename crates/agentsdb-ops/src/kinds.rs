@@ -0,0 +1,278 @@
+//! Kind taxonomy: the kinds a project's chunks are expected to use.
+//!
+//! The taxonomy lives in layer metadata ([`KindTaxonomy`]), usually of `AGENTS.db`, so it is
+//! shared with everyone who has the base layer. Writes look it up across the standard layers of
+//! the target's directory: the kinds of every layer count, and the strictest
+//! [`KindValidation`] wins. Without a taxonomy, any kind is accepted.
+
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+use agentsdb_embeddings::config::get_immutable_embedding_options;
+use agentsdb_embeddings::layer_metadata::{KindTaxonomy, KindValidation, LayerMetadataV1};
+use agentsdb_format::{LayerFile, WritePolicy};
+
+use crate::error::WriteError;
+
+/// Standard layer files searched for a taxonomy, most authoritative first.
+const STANDARD_LAYERS: &[&str] = &[
+    "AGENTS.db",
+    "AGENTS.user.db",
+    "AGENTS.delta.db",
+    "AGENTS.local.db",
+];
+
+/// Prefix of kinds the tools write for their own bookkeeping (e.g. `meta.proposal_event`).
+const META_KIND_PREFIX: &str = "meta.";
+
+/// The taxonomy stored in `file`'s metadata, if any.
+pub fn taxonomy_of(file: &LayerFile) -> anyhow::Result<Option<KindTaxonomy>> {
+    let Some(bytes) = file.layer_metadata_bytes() else {
+        return Ok(None);
+    };
+    Ok(LayerMetadataV1::from_json_bytes(bytes)
+        .with_context(|| format!("parse layer metadata of {}", file.path().display()))?
+        .kinds)
+}
+
+/// The combined taxonomy of the layers at `paths`; missing files are skipped.
+pub fn load_taxonomy<P: AsRef<Path>>(
+    paths: impl IntoIterator<Item = P>,
+) -> anyhow::Result<KindTaxonomy> {
+    let mut combined: Option<KindTaxonomy> = None;
+    for path in paths {
+        let path = path.as_ref();
+        if !path.exists() {
+            continue;
+        }
+        let file = LayerFile::open(path).with_context(|| format!("open {}", path.display()))?;
+        let Some(taxonomy) = taxonomy_of(&file)? else {
+            continue;
+        };
+        let combined = combined.get_or_insert_with(|| KindTaxonomy {
+            validation: KindValidation::Off,
+            kinds: Vec::new(),
+        });
+        combined.validation = combined.validation.max(taxonomy.validation);
+        for kind in taxonomy.kinds {
+            if combined.get(&kind.name).is_none() {
+                combined.kinds.push(kind);
+            }
+        }
+    }
+    Ok(combined.unwrap_or_default())
+}
+
+/// The combined taxonomy of the standard layers in `dir`.
+pub fn load_taxonomy_for_dir(dir: &Path) -> anyhow::Result<KindTaxonomy> {
+    load_taxonomy(STANDARD_LAYERS.iter().map(|name| dir.join(name)))
+}
+
+/// Checks `kind` against `taxonomy`. An unlisted kind is an error under
+/// [`KindValidation::Enforce`]; under [`KindValidation::Warn`] it is accepted and the warning
+/// is returned (and logged). Tombstones, options records and `meta.*` kinds are always accepted.
+pub fn check_kind(taxonomy: &KindTaxonomy, kind: &str) -> Result<Option<String>, WriteError> {
+    if taxonomy.kinds.is_empty()
+        || taxonomy.validation == KindValidation::Off
+        || taxonomy.get(kind).is_some()
+        || is_reserved(kind)
+    {
+        return Ok(None);
+    }
+    let suggestion = suggest(taxonomy, kind);
+    if taxonomy.validation == KindValidation::Enforce {
+        return Err(WriteError::UnknownKind {
+            kind: kind.to_string(),
+            suggestion,
+        });
+    }
+    let warning = WriteError::UnknownKind {
+        kind: kind.to_string(),
+        suggestion,
+    }
+    .to_string();
+    tracing::warn!("{warning}");
+    Ok(Some(warning))
+}
+
+/// [`check_kind`] against the taxonomy of the standard layers next to `path`.
+pub fn check_kind_for_layer(path: &Path, kind: &str) -> Result<Option<String>, WriteError> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    check_kind(&load_taxonomy_for_dir(dir)?, kind)
+}
+
+fn is_reserved(kind: &str) -> bool {
+    kind == agentsdb_query::KIND_TOMBSTONE
+        || kind == agentsdb_embeddings::config::KIND_OPTIONS
+        || kind.starts_with(META_KIND_PREFIX)
+}
+
+/// A listed kind that differs from `kind` only in case or a plural `s`.
+fn suggest(taxonomy: &KindTaxonomy, kind: &str) -> Option<String> {
+    let normalize = |k: &str| {
+        let k = k.trim().to_lowercase();
+        k.strip_suffix('s').map(str::to_string).unwrap_or(k)
+    };
+    let wanted = normalize(kind);
+    taxonomy
+        .kinds
+        .iter()
+        .find(|k| normalize(&k.name) == wanted)
+        .map(|k| k.name.clone())
+}
+
+/// Adds `name` (or updates its description) in the taxonomy stored in the layer at `path`,
+/// optionally setting its validation mode, and rewrites the layer.
+///
+/// A layer without metadata gets metadata for the embedder its directory's options select.
+pub fn add_kind(
+    path: &Path,
+    name: &str,
+    description: &str,
+    validation: Option<KindValidation>,
+    policy: &WritePolicy,
+) -> anyhow::Result<KindTaxonomy> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("kind name must be non-empty");
+    }
+    if is_reserved(name) {
+        anyhow::bail!("kind {name:?} is reserved");
+    }
+    policy.check(path).context("permission check")?;
+
+    let file = LayerFile::open(path).with_context(|| format!("open {}", path.display()))?;
+    let mut metadata = match file.layer_metadata_bytes() {
+        Some(bytes) => LayerMetadataV1::from_json_bytes(bytes)?,
+        None => {
+            let dir = path.parent().unwrap_or_else(|| Path::new("."));
+            let embedder = get_immutable_embedding_options(dir)
+                .context("get immutable embedding options")?
+                .into_embedder(file.embedding_dim())
+                .context("resolve embedder from options")?;
+            LayerMetadataV1::new(embedder.profile().clone())
+                .with_embedder_metadata(embedder.metadata())
+        }
+    };
+    let mut taxonomy = metadata.kinds.take().unwrap_or_default();
+    taxonomy.add(name, description.trim());
+    if let Some(validation) = validation {
+        taxonomy.validation = validation;
+    }
+    let json = metadata
+        .with_kinds(Some(taxonomy.clone()))
+        .to_json_bytes()?;
+
+    let schema = agentsdb_format::schema_of(&file);
+    let mut chunks = agentsdb_format::read_all_chunks(&file).context("read chunks")?;
+    drop(file);
+    policy
+        .write_layer_atomic(path, &schema, &mut chunks, Some(&json))
+        .with_context(|| format!("rewrite {}", path.display()))?;
+    Ok(taxonomy)
+}
+
+/// The taxonomy of each standard layer in `dir` that has one.
+pub fn list_taxonomies(dir: &Path) -> anyhow::Result<Vec<(PathBuf, KindTaxonomy)>> {
+    let mut out = Vec::new();
+    for name in STANDARD_LAYERS {
+        let path = dir.join(name);
+        if !path.exists() {
+            continue;
+        }
+        let file = LayerFile::open(&path).with_context(|| format!("open {}", path.display()))?;
+        if let Some(taxonomy) = taxonomy_of(&file)? {
+            out.push((path, taxonomy));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn taxonomy(validation: KindValidation) -> KindTaxonomy {
+        let mut taxonomy = KindTaxonomy {
+            validation,
+            kinds: Vec::new(),
+        };
+        taxonomy.add("decision", "A choice and why it was made");
+        taxonomy.add("note", "");
+        taxonomy
+    }
+
+    #[test]
+    fn unknown_kinds_warn_or_fail_by_mode() {
+        let warn = taxonomy(KindValidation::Warn);
+        assert_eq!(check_kind(&warn, "decision").ok(), Some(None));
+        assert_eq!(check_kind(&warn, "tombstone").ok(), Some(None));
+        let warning = check_kind(&warn, "Decisions").ok().flatten();
+        assert!(warning.is_some_and(|w| w.contains("did you mean \"decision\"")));
+
+        let enforce = taxonomy(KindValidation::Enforce);
+        let err = check_kind(&enforce, "decisions").err();
+        assert!(matches!(
+            err,
+            Some(WriteError::UnknownKind { ref suggestion, .. })
+                if suggestion.as_deref() == Some("decision")
+        ));
+        assert_eq!(err.map(|e| e.code()), Some("unknown_kind"));
+        assert!(check_kind(&enforce, "meta.proposal_event").is_ok());
+
+        assert_eq!(
+            check_kind(&KindTaxonomy::default(), "anything").ok(),
+            Some(None)
+        );
+        assert_eq!(
+            check_kind(&taxonomy(KindValidation::Off), "anything").ok(),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn kinds_added_to_a_layer_are_enforced_for_its_siblings() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path().join("AGENTS.db");
+        let schema = agentsdb_format::LayerSchema {
+            dim: 4,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let mut chunks = [agentsdb_format::ChunkInput {
+            id: 7,
+            kind: "decision".to_string(),
+            content: "use sqlite".to_string(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 1,
+            embedding: vec![0.0; 4],
+            sources: Vec::new(),
+        }];
+        agentsdb_format::write_layer_atomic(&base, &schema, &mut chunks, None)?;
+
+        let policy = WritePolicy::standard();
+        assert!(add_kind(&base, "decision", "", None, &policy).is_err());
+        let policy = policy.allow_base();
+        add_kind(
+            &base,
+            "decision",
+            "",
+            Some(KindValidation::Enforce),
+            &policy,
+        )?;
+        let taxonomy = add_kind(&base, "decision", "Why we chose", None, &policy)?;
+        assert_eq!(taxonomy.kinds.len(), 1);
+        assert_eq!(taxonomy.kinds[0].description, "Why we chose");
+
+        let file = LayerFile::open(&base)?;
+        assert_eq!(agentsdb_format::read_all_chunks(&file)?[0].id, 7);
+        assert_eq!(list_taxonomies(dir.path())?.len(), 1);
+
+        let local = dir.path().join("AGENTS.local.db");
+        assert!(check_kind_for_layer(&local, "decision").is_ok());
+        assert!(check_kind_for_layer(&local, "Decision").is_err());
+        Ok(())
+    }
+}
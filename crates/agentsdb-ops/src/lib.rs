@@ -1,17 +1,55 @@
+pub mod assemble;
+pub mod check;
 pub mod decay;
+pub mod dedupe;
+pub mod diff;
+pub mod drift;
+pub mod error;
 pub mod export;
+pub mod hooks;
+pub mod identity;
 pub mod import;
+pub mod index_maintenance;
+pub mod journal;
+pub mod kinds;
+pub mod merge;
+pub mod metrics;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod policy;
 pub mod promote;
+pub mod proposals;
+pub mod provenance;
+pub mod query_cache;
+pub mod remote;
 pub mod remove;
+pub mod retention;
+pub mod review;
 pub mod search;
+pub mod show;
+pub mod signing;
+pub mod startup_check;
+pub mod stats;
+pub mod sync;
+#[cfg(test)]
+mod test_support;
+pub mod transaction;
+pub mod usage;
 pub mod util;
+pub mod vcs;
+pub mod workspace;
 pub mod write;
 
 // Re-export commonly used types for convenience
 pub use decay::DecayState;
+pub use diff::{diff_layers, semantic_diff_layers};
+pub use error::{error_code, ImportError, PromoteError, WriteError};
 pub use export::export_layer;
 pub use import::import_into_layer;
 pub use promote::promote_chunks;
 pub use remove::remove_chunk;
 pub use search::{embed_query, search_layers, SearchConfig};
-pub use write::append_chunk;
+pub use transaction::Transaction;
+pub use write::{
+    append_chunk, append_chunks, relink_chunk_sources, retract_chunks, supersede_chunk,
+};
@@ -0,0 +1,372 @@
+//! Three-way, chunk-level merge of layer files, used by `agentsdb mergetool` as a git merge
+//! driver so layers such as `AGENTS.user.db` can be edited on branches.
+
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
+use agentsdb_format::{ChunkInput, ChunkSource, LayerSchema};
+
+use crate::util::content_sha256_hex;
+
+/// Which side wins a conflicting change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Prefer {
+    Ours,
+    Theirs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeConflict {
+    pub id: u32,
+    /// `modify/modify`, `modify/delete`, or `delete/modify` (ours/theirs).
+    pub kind: &'static str,
+    /// Ids of the versions kept in the merged layer (empty when the chunk was deleted).
+    pub kept: Vec<u32>,
+    /// True when `Prefer` settled the conflict.
+    pub resolved: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MergeReport {
+    pub chunks: usize,
+    pub taken_from_theirs: usize,
+    /// Chunks added on both sides under the same id: theirs moved to a fresh id, as (old, new).
+    pub renumbered: Vec<(u32, u32)>,
+    /// Chunks added on both sides with the same kind and content, kept once.
+    pub deduplicated: usize,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeReport {
+    /// True when some conflict was not settled by `Prefer` and needs a human look.
+    pub fn has_unresolved_conflicts(&self) -> bool {
+        self.conflicts.iter().any(|c| !c.resolved)
+    }
+}
+
+/// The latest version of every chunk in one side of the merge.
+struct Side {
+    schema: Option<LayerSchema>,
+    metadata: Option<Vec<u8>>,
+    chunks: BTreeMap<u32, ChunkInput>,
+}
+
+fn read_side(path: &Path) -> anyhow::Result<Side> {
+    // Git hands the driver an empty file as the base when both branches added the layer.
+    let empty = std::fs::metadata(path).map_or(true, |m| m.len() == 0);
+    if empty {
+        return Ok(Side {
+            schema: None,
+            metadata: None,
+            chunks: BTreeMap::new(),
+        });
+    }
+    let file = agentsdb_format::LayerFile::open_lenient(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    let mut chunks = BTreeMap::new();
+    for chunk in agentsdb_format::read_all_chunks(&file)? {
+        chunks.insert(chunk.id, chunk);
+    }
+    Ok(Side {
+        schema: Some(agentsdb_format::schema_of(&file)),
+        metadata: file.layer_metadata_bytes().map(<[u8]>::to_vec),
+        chunks,
+    })
+}
+
+/// Identifies a chunk version by everything but its (derived) embedding.
+fn fingerprint(chunk: &ChunkInput) -> String {
+    content_sha256_hex(&format!(
+        "{}\0{}\0{}\0{}\0{:?}\0{}",
+        chunk.kind,
+        chunk.author,
+        chunk.confidence,
+        chunk.created_at_unix_ms,
+        chunk.sources,
+        chunk.content
+    ))
+}
+
+fn check_compatible(ours: &Side, theirs: &Side, base: &Side) -> anyhow::Result<()> {
+    let schemas = [&ours.schema, &theirs.schema, &base.schema];
+    let mut schemas = schemas.iter().filter_map(|s| s.as_ref());
+    if let Some(first) = schemas.next() {
+        for other in schemas {
+            if other.dim != first.dim || other.element_type != first.element_type {
+                anyhow::bail!(
+                    "layer schemas differ (dim={} {:?} vs dim={} {:?}); re-embed one side first",
+                    first.dim,
+                    first.element_type,
+                    other.dim,
+                    other.element_type
+                );
+            }
+        }
+    }
+    if let (Some(a), Some(b)) = (&ours.metadata, &theirs.metadata) {
+        let a = LayerMetadataV1::from_json_bytes(a).context("parse ours layer metadata")?;
+        let b = LayerMetadataV1::from_json_bytes(b).context("parse theirs layer metadata")?;
+        if a.embedding_profile != b.embedding_profile {
+            anyhow::bail!(
+                "embedder profiles differ (ours={:?}, theirs={:?}); re-embed one side first",
+                a.embedding_profile,
+                b.embedding_profile
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Merge state: the chosen chunks (and whether each came from theirs), plus theirs'
+/// versions that still need an id of their own.
+#[derive(Default)]
+struct Merged {
+    chunks: BTreeMap<u32, (ChunkInput, bool)>,
+    deferred: Vec<ChunkInput>,
+    report: MergeReport,
+}
+
+impl Merged {
+    fn take(&mut self, chunk: Option<&ChunkInput>, from_theirs: bool) {
+        if let Some(chunk) = chunk {
+            if from_theirs {
+                self.report.taken_from_theirs += 1;
+            }
+            self.chunks.insert(chunk.id, (chunk.clone(), from_theirs));
+        }
+    }
+
+    fn conflict(
+        &mut self,
+        id: u32,
+        kind: &'static str,
+        ours: Option<&ChunkInput>,
+        theirs: Option<&ChunkInput>,
+        prefer: Option<Prefer>,
+    ) {
+        match prefer {
+            Some(Prefer::Ours) => self.take(ours, false),
+            Some(Prefer::Theirs) => self.take(theirs, true),
+            None => {
+                // Keep every surviving version: ours at the id, theirs under a fresh one.
+                self.take(ours.or(theirs), ours.is_none());
+                if let (Some(_), Some(theirs)) = (ours, theirs) {
+                    self.deferred.push(theirs.clone());
+                }
+            }
+        }
+        self.report.conflicts.push(MergeConflict {
+            id,
+            kind,
+            kept: Vec::new(),
+            resolved: prefer.is_some(),
+        });
+    }
+}
+
+/// Three-way merges the `ours` and `theirs` layers against their common `base` and
+/// writes the result to `out` (which may be `ours`, as git expects).
+///
+/// Per chunk id, a change on only one side is taken; identical changes merge cleanly.
+/// Chunks both sides added under the same id are both kept (theirs renumbered, with
+/// references from theirs' chunks updated) unless their content matches. Edits to the
+/// same chunk on both sides, or an edit against a deletion, are conflicts: `prefer` picks
+/// a side, otherwise every surviving version is kept and the conflict is reported.
+pub fn merge_layers(
+    base: &Path,
+    ours: &Path,
+    theirs: &Path,
+    out: &Path,
+    prefer: Option<Prefer>,
+) -> anyhow::Result<MergeReport> {
+    let base = read_side(base)?;
+    let ours = read_side(ours)?;
+    let theirs = read_side(theirs)?;
+    check_compatible(&ours, &theirs, &base)?;
+    let Some(schema) = [&ours.schema, &theirs.schema, &base.schema]
+        .into_iter()
+        .find_map(Clone::clone)
+    else {
+        anyhow::bail!("nothing to merge: all three layers are empty");
+    };
+
+    let ids: BTreeSet<u32> = [&base, &ours, &theirs]
+        .iter()
+        .flat_map(|side| side.chunks.keys().copied())
+        .collect();
+    let mut merged = Merged::default();
+    for &id in &ids {
+        let (b, o, t) = (
+            base.chunks.get(&id),
+            ours.chunks.get(&id),
+            theirs.chunks.get(&id),
+        );
+        let (fb, fo, ft) = (b.map(fingerprint), o.map(fingerprint), t.map(fingerprint));
+        if fo == ft || ft == fb {
+            merged.take(o, false);
+        } else if fo == fb {
+            merged.take(t, true);
+        } else {
+            match (b, o, t) {
+                (None, _, Some(t)) => {
+                    merged.take(o, false);
+                    merged.deferred.push(t.clone());
+                }
+                (Some(_), Some(_), Some(_)) => {
+                    merged.conflict(id, "modify/modify", o, t, prefer);
+                }
+                (Some(_), Some(_), None) => merged.conflict(id, "modify/delete", o, t, prefer),
+                _ => merged.conflict(id, "delete/modify", o, t, prefer),
+            }
+        }
+    }
+
+    let mut next_id = ids.last().copied().unwrap_or(0);
+    let mut remap = HashMap::new();
+    let by_content: HashMap<(String, String), u32> = merged
+        .chunks
+        .values()
+        .map(|(c, _)| ((c.kind.clone(), content_sha256_hex(&c.content)), c.id))
+        .collect();
+    for mut chunk in std::mem::take(&mut merged.deferred) {
+        let old = chunk.id;
+        let key = (chunk.kind.clone(), content_sha256_hex(&chunk.content));
+        if let Some(&existing) = by_content.get(&key) {
+            merged.report.deduplicated += 1;
+            remap.insert(old, existing);
+            continue;
+        }
+        next_id = next_id.checked_add(1).context("chunk id space exhausted")?;
+        chunk.id = next_id;
+        remap.insert(old, next_id);
+        merged.report.renumbered.push((old, next_id));
+        merged.report.taken_from_theirs += 1;
+        merged.chunks.insert(next_id, (chunk, true));
+    }
+
+    let mut chunks: Vec<ChunkInput> = merged
+        .chunks
+        .into_values()
+        .map(|(mut chunk, from_theirs)| {
+            if from_theirs {
+                for source in &mut chunk.sources {
+                    if let ChunkSource::ChunkId(id) = source {
+                        *id = remap.get(id).copied().unwrap_or(*id);
+                    }
+                }
+            }
+            chunk
+        })
+        .collect();
+    let mut report = merged.report;
+    for conflict in &mut report.conflicts {
+        let renumbered = remap.get(&conflict.id).copied();
+        conflict.kept = chunks
+            .iter()
+            .map(|c| c.id)
+            .filter(|id| *id == conflict.id || Some(*id) == renumbered)
+            .collect();
+    }
+    report.chunks = chunks.len();
+
+    let metadata = ours.metadata.or(theirs.metadata);
+    agentsdb_format::write_layer_atomic(out, &schema, &mut chunks, metadata.as_deref())
+        .with_context(|| format!("write {}", out.display()))?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+
+    fn contents(path: &Path) -> anyhow::Result<BTreeMap<u32, String>> {
+        let file = agentsdb_format::LayerFile::open(path)?;
+        Ok(agentsdb_format::read_all_chunks(&file)?
+            .into_iter()
+            .map(|c| (c.id, c.content))
+            .collect())
+    }
+
+    #[test]
+    fn merges_appends_from_both_sides_and_renumbers_theirs() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (base, ours, theirs, out) = (
+            dir.path().join("base.db"),
+            dir.path().join("ours.db"),
+            dir.path().join("theirs.db"),
+            dir.path().join("out.db"),
+        );
+        let shared = chunk(1, "shared");
+        write_layer(&base, vec![shared.clone()])?;
+        write_layer(
+            &ours,
+            vec![
+                shared.clone(),
+                chunk(2, "ours note"),
+                chunk(3, "same on both"),
+            ],
+        )?;
+        write_layer(
+            &theirs,
+            vec![
+                shared,
+                chunk(2, "theirs note"),
+                chunk(3, "same on both"),
+                ChunkInput {
+                    sources: vec![ChunkSource::ChunkId(2)],
+                    ..chunk(4, "same on both")
+                },
+            ],
+        )?;
+
+        let report = merge_layers(&base, &ours, &theirs, &out, None)?;
+        assert!(!report.has_unresolved_conflicts());
+        assert_eq!(report.renumbered, vec![(2, 5)]);
+        let merged = contents(&out)?;
+        assert_eq!(merged.len(), 5);
+        assert_eq!(merged.get(&2).map(String::as_str), Some("ours note"));
+        assert_eq!(merged.get(&5).map(String::as_str), Some("theirs note"));
+
+        // Theirs' reference to its chunk 2 follows it to the new id.
+        let file = agentsdb_format::LayerFile::open(&out)?;
+        let four = agentsdb_format::read_all_chunks(&file)?
+            .into_iter()
+            .find(|c| c.id == 4)
+            .context("chunk 4")?;
+        assert!(matches!(four.sources.as_slice(), [ChunkSource::ChunkId(5)]));
+        Ok(())
+    }
+
+    #[test]
+    fn reports_conflicting_edits_and_honours_prefer() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (base, ours, theirs, out) = (
+            dir.path().join("base.db"),
+            dir.path().join("ours.db"),
+            dir.path().join("theirs.db"),
+            dir.path().join("out.db"),
+        );
+        write_layer(&base, vec![chunk(1, "original")])?;
+        write_layer(&ours, vec![chunk(1, "ours edit")])?;
+        write_layer(&theirs, vec![chunk(1, "theirs edit")])?;
+
+        let report = merge_layers(&base, &ours, &theirs, &out, None)?;
+        assert!(report.has_unresolved_conflicts());
+        assert_eq!(report.conflicts[0].kind, "modify/modify");
+        assert_eq!(report.conflicts[0].kept, vec![1, 2]);
+        assert_eq!(contents(&out)?.len(), 2);
+
+        let report = merge_layers(&base, &ours, &theirs, &out, Some(Prefer::Theirs))?;
+        assert!(!report.has_unresolved_conflicts());
+        assert_eq!(
+            contents(&out)?.get(&1).map(String::as_str),
+            Some("theirs edit")
+        );
+        Ok(())
+    }
+}
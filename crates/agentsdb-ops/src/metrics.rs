@@ -0,0 +1,250 @@
+//! Process-wide counters, gauges and histograms, rendered in the Prometheus text format.
+//!
+//! The web server serves [`global`] at `GET /metrics`; the MCP server can write it to a file
+//! for a node-exporter style textfile collector.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// Upper bounds (seconds) of the latency histogram buckets.
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Kind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+            Self::Histogram => "histogram",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Series {
+    Counter(u64),
+    Gauge(f64),
+    /// Cumulative counts per [`LATENCY_BUCKETS`] bound, plus the sum and count.
+    Histogram {
+        buckets: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+#[derive(Debug)]
+struct Family {
+    help: &'static str,
+    kind: Kind,
+    /// Keyed by the rendered label set, e.g. `route="/api/layers",status="200"`.
+    series: BTreeMap<String, Series>,
+}
+
+/// A set of metric families.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    families: Mutex<BTreeMap<&'static str, Family>>,
+}
+
+/// The registry shared by everything in this process.
+pub fn global() -> &'static Metrics {
+    static GLOBAL: OnceLock<Metrics> = OnceLock::new();
+    GLOBAL.get_or_init(Metrics::default)
+}
+
+/// Counts `chunks` appended to the layer at `path` in the global registry.
+pub fn record_append(path: &Path, chunks: usize) {
+    let layer = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    global().inc_counter(
+        "agentsdb_appended_chunks_total",
+        "Chunks appended to layer files.",
+        &[("layer", layer)],
+        chunks as u64,
+    );
+}
+
+impl Metrics {
+    pub fn inc_counter(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        by: u64,
+    ) {
+        self.update(name, help, Kind::Counter, labels, |series| {
+            if let Series::Counter(n) = series {
+                *n += by;
+            }
+        });
+    }
+
+    pub fn set_gauge(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        value: f64,
+    ) {
+        self.update(name, help, Kind::Gauge, labels, |series| {
+            if let Series::Gauge(v) = series {
+                *v = value;
+            }
+        });
+    }
+
+    /// Records one observation (in seconds) in a [`LATENCY_BUCKETS`] histogram.
+    pub fn observe(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        value: f64,
+    ) {
+        self.update(name, help, Kind::Histogram, labels, |series| {
+            if let Series::Histogram {
+                buckets,
+                sum,
+                count,
+            } = series
+            {
+                for (bucket, bound) in buckets.iter_mut().zip(LATENCY_BUCKETS) {
+                    if value <= *bound {
+                        *bucket += 1;
+                    }
+                }
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+
+    fn update(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        kind: Kind,
+        labels: &[(&str, &str)],
+        apply: impl FnOnce(&mut Series),
+    ) {
+        let Ok(mut families) = self.families.lock() else {
+            return;
+        };
+        let family = families.entry(name).or_insert_with(|| Family {
+            help,
+            kind,
+            series: BTreeMap::new(),
+        });
+        if family.kind != kind {
+            return;
+        }
+        let series = family
+            .series
+            .entry(render_labels(labels))
+            .or_insert_with(|| match kind {
+                Kind::Counter => Series::Counter(0),
+                Kind::Gauge => Series::Gauge(0.0),
+                Kind::Histogram => Series::Histogram {
+                    buckets: vec![0; LATENCY_BUCKETS.len()],
+                    sum: 0.0,
+                    count: 0,
+                },
+            });
+        apply(series);
+    }
+
+    /// The registry in the Prometheus text exposition format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let Ok(families) = self.families.lock() else {
+            return out;
+        };
+        for (name, family) in families.iter() {
+            let _ = writeln!(out, "# HELP {name} {}", family.help);
+            let _ = writeln!(out, "# TYPE {name} {}", family.kind.as_str());
+            for (labels, series) in &family.series {
+                match series {
+                    Series::Counter(n) => {
+                        let _ = writeln!(out, "{name}{} {n}", braced(labels, None));
+                    }
+                    Series::Gauge(v) => {
+                        let _ = writeln!(out, "{name}{} {v}", braced(labels, None));
+                    }
+                    Series::Histogram {
+                        buckets,
+                        sum,
+                        count,
+                    } => {
+                        for (n, bound) in buckets.iter().zip(LATENCY_BUCKETS) {
+                            let le = format!("le=\"{bound}\"");
+                            let _ = writeln!(out, "{name}_bucket{} {n}", braced(labels, Some(&le)));
+                        }
+                        let inf = braced(labels, Some("le=\"+Inf\""));
+                        let _ = writeln!(out, "{name}_bucket{inf} {count}");
+                        let _ = writeln!(out, "{name}_sum{} {sum}", braced(labels, None));
+                        let _ = writeln!(out, "{name}_count{} {count}", braced(labels, None));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| {
+            let v = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{k}=\"{v}\"")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn braced(labels: &str, extra: Option<&str>) -> String {
+    match (labels.is_empty(), extra) {
+        (true, None) => String::new(),
+        (true, Some(extra)) => format!("{{{extra}}}"),
+        (false, None) => format!("{{{labels}}}"),
+        (false, Some(extra)) => format!("{{{labels},{extra}}}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters_gauges_and_histograms() {
+        let m = Metrics::default();
+        m.inc_counter("req_total", "Requests.", &[("route", "/a\"b")], 2);
+        m.inc_counter("req_total", "Requests.", &[("route", "/a\"b")], 1);
+        m.set_gauge("size_bytes", "Size.", &[], 42.0);
+        m.observe("latency_seconds", "Latency.", &[("op", "search")], 0.003);
+        m.observe("latency_seconds", "Latency.", &[("op", "search")], 20.0);
+
+        let text = m.render();
+        assert!(text.contains("# TYPE req_total counter\nreq_total{route=\"/a\\\"b\"} 3\n"));
+        assert!(text.contains("size_bytes 42\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"search\",le=\"0.001\"} 0\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"search\",le=\"0.005\"} 1\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"search\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("latency_seconds_count{op=\"search\"} 2\n"));
+    }
+}
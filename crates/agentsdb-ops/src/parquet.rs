@@ -0,0 +1,405 @@
+//! Parquet encoding of export bundles, for analysis in DuckDB, Spark, pandas and the like.
+//!
+//! One row per chunk. Embeddings are a `FixedSizeList<Float32, dim>` column (null when
+//! redacted) and sources a list of `{chunk_id, value}` structs. The tool and per-layer
+//! records of the NDJSON format are kept in the file's key-value metadata, so an export
+//! round-trips; files rewritten by other tools lose that metadata and import with
+//! defaults derived from the rows.
+
+use anyhow::Context;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::basic::Compression;
+use ::parquet::file::properties::WriterProperties;
+use ::parquet::format::KeyValue;
+use arrow_array::builder::{
+    FixedSizeListBuilder, Float32Builder, ListBuilder, StringBuilder, StructBuilder, UInt32Builder,
+};
+use arrow_array::{
+    Array, ArrayRef, FixedSizeListArray, Float32Array, ListArray, RecordBatch, StringArray,
+    StructArray, UInt32Array, UInt64Array,
+};
+use arrow_schema::{DataType, Field, Fields};
+
+use agentsdb_core::export::{
+    ExportBundleV1, ExportChunkV1, ExportLayerSchemaV1, ExportLayerV1, ExportNdjsonRecordV1,
+    ExportSourceV1, ExportToolInfo,
+};
+
+pub const CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+pub const FORMAT: &str = "agentsdb.export.parquet.v1";
+
+/// Key-value metadata keys.
+const HEADER_KEY: &str = "agentsdb.header";
+const LAYERS_KEY: &str = "agentsdb.layers";
+
+fn source_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("chunk_id", DataType::UInt32, true),
+        Field::new("value", DataType::Utf8, true),
+    ])
+}
+
+/// Encodes `bundle` as a Parquet file. All layers must share one embedding dimension.
+pub fn write_bundle(bundle: &ExportBundleV1) -> anyhow::Result<Vec<u8>> {
+    let dim = bundle.layers.first().map_or(0, |l| l.schema.dim);
+    if let Some(other) = bundle.layers.iter().find(|l| l.schema.dim != dim) {
+        anyhow::bail!(
+            "Parquet export needs one embedding dimension, but {} has dim {} and {} has dim {dim}",
+            other.path,
+            other.schema.dim,
+            bundle.layers.first().map_or("", |l| l.path.as_str())
+        );
+    }
+    let batch = chunk_batch(bundle, dim)?;
+
+    let header = ExportNdjsonRecordV1::Header {
+        format: FORMAT.to_string(),
+        tool: bundle.tool.clone(),
+    };
+    let layers: Vec<ExportNdjsonRecordV1> = bundle
+        .layers
+        .iter()
+        .map(|l| ExportNdjsonRecordV1::Layer {
+            path: l.path.clone(),
+            layer: l.layer.clone(),
+            schema: l.schema.clone(),
+            layer_metadata_json: l.layer_metadata_json.clone(),
+        })
+        .collect();
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_key_value_metadata(Some(vec![
+            KeyValue::new(HEADER_KEY.to_string(), serde_json::to_string(&header)?),
+            KeyValue::new(LAYERS_KEY.to_string(), serde_json::to_string(&layers)?),
+        ]))
+        .build();
+
+    let mut out = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut out, batch.schema(), Some(props))
+        .context("create Parquet writer")?;
+    writer.write(&batch).context("write Parquet rows")?;
+    writer.close().context("finish Parquet file")?;
+    Ok(out)
+}
+
+fn chunk_batch(bundle: &ExportBundleV1, dim: u32) -> anyhow::Result<RecordBatch> {
+    let width = i32::try_from(dim).context("embedding dimension too large")?;
+    let mut layer_path = StringBuilder::new();
+    let mut layer = StringBuilder::new();
+    let mut id = UInt32Builder::new();
+    let mut kind = StringBuilder::new();
+    let mut content = StringBuilder::new();
+    let mut content_sha256 = StringBuilder::new();
+    let mut author = StringBuilder::new();
+    let mut confidence = Float32Builder::new();
+    let mut created_at = Vec::new();
+    let mut sources = ListBuilder::new(StructBuilder::from_fields(source_fields(), 0));
+    let mut embedding = FixedSizeListBuilder::new(Float32Builder::new(), width);
+
+    for l in &bundle.layers {
+        for c in &l.chunks {
+            layer_path.append_value(&l.path);
+            layer.append_option(l.layer.as_deref());
+            id.append_value(c.id);
+            kind.append_value(&c.kind);
+            content.append_option(c.content.as_deref());
+            content_sha256.append_option(c.content_sha256.as_deref());
+            author.append_value(&c.author);
+            confidence.append_value(c.confidence);
+            created_at.push(c.created_at_unix_ms);
+            append_sources(&mut sources, &c.sources);
+            match &c.embedding {
+                Some(v) if v.len() == dim as usize => {
+                    embedding.values().append_slice(v);
+                    embedding.append(true);
+                }
+                Some(v) => anyhow::bail!(
+                    "chunk {} in {} has {} embedding values, expected {dim}",
+                    c.id,
+                    l.path,
+                    v.len()
+                ),
+                None => {
+                    embedding.values().append_nulls(dim as usize);
+                    embedding.append(false);
+                }
+            }
+        }
+    }
+
+    RecordBatch::try_from_iter([
+        ("layer_path", Arc::new(layer_path.finish()) as ArrayRef),
+        ("layer", Arc::new(layer.finish())),
+        ("id", Arc::new(id.finish())),
+        ("kind", Arc::new(kind.finish())),
+        ("content", Arc::new(content.finish())),
+        ("content_sha256", Arc::new(content_sha256.finish())),
+        ("author", Arc::new(author.finish())),
+        ("confidence", Arc::new(confidence.finish())),
+        (
+            "created_at_unix_ms",
+            Arc::new(UInt64Array::from(created_at)),
+        ),
+        ("sources", Arc::new(sources.finish())),
+        ("embedding", Arc::new(embedding.finish())),
+    ])
+    .context("build Parquet rows")
+}
+
+fn append_sources(builder: &mut ListBuilder<StructBuilder>, sources: &[ExportSourceV1]) {
+    let entries = builder.values();
+    for s in sources {
+        let (chunk_id, value) = match s {
+            ExportSourceV1::ChunkId { id } => (Some(*id), None),
+            ExportSourceV1::SourceString { value } => (None, Some(value.as_str())),
+        };
+        if let Some(b) = entries.field_builder::<UInt32Builder>(0) {
+            b.append_option(chunk_id);
+        }
+        if let Some(b) = entries.field_builder::<StringBuilder>(1) {
+            b.append_option(value);
+        }
+        entries.append(true);
+    }
+    builder.append(true);
+}
+
+/// Decodes a Parquet file written by [`write_bundle`] (or one with the same columns).
+pub fn read_bundle(bytes: &[u8]) -> anyhow::Result<ExportBundleV1> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::copy_from_slice(bytes))
+        .context("open Parquet file")?;
+    let metadata: HashMap<String, String> = builder
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .into_iter()
+        .flatten()
+        .filter_map(|kv| Some((kv.key.clone(), kv.value.clone()?)))
+        .collect();
+
+    let mut tool = ExportToolInfo {
+        name: "unknown".into(),
+        version: "unknown".into(),
+    };
+    if let Some(header) = metadata.get(HEADER_KEY) {
+        if let ExportNdjsonRecordV1::Header { tool: t, .. } =
+            serde_json::from_str(header).context("parse Parquet header metadata")?
+        {
+            tool = t;
+        }
+    }
+    let mut layers = Vec::new();
+    if let Some(records) = metadata.get(LAYERS_KEY) {
+        let records: Vec<ExportNdjsonRecordV1> =
+            serde_json::from_str(records).context("parse Parquet layer metadata")?;
+        for rec in records {
+            if let ExportNdjsonRecordV1::Layer {
+                path,
+                layer,
+                schema,
+                layer_metadata_json,
+            } = rec
+            {
+                layers.push(ExportLayerV1 {
+                    path,
+                    layer,
+                    schema,
+                    layer_metadata_json,
+                    chunks: Vec::new(),
+                });
+            }
+        }
+    }
+
+    for batch in builder.build().context("read Parquet file")? {
+        read_batch(&batch.context("read Parquet rows")?, &mut layers)?;
+    }
+    Ok(ExportBundleV1 {
+        format: FORMAT.to_string(),
+        tool,
+        layers,
+    })
+}
+
+fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> anyhow::Result<&'a T> {
+    optional_column(batch, name)?.with_context(|| format!("Parquet file has no {name} column"))
+}
+
+fn optional_column<'a, T: 'static>(
+    batch: &'a RecordBatch,
+    name: &str,
+) -> anyhow::Result<Option<&'a T>> {
+    batch
+        .column_by_name(name)
+        .map(|c| {
+            c.as_any().downcast_ref::<T>().with_context(|| {
+                format!(
+                    "Parquet column {name} has unexpected type {}",
+                    c.data_type()
+                )
+            })
+        })
+        .transpose()
+}
+
+fn opt_str(array: &StringArray, row: usize) -> Option<String> {
+    (!array.is_null(row)).then(|| array.value(row).to_string())
+}
+
+fn read_batch(batch: &RecordBatch, layers: &mut Vec<ExportLayerV1>) -> anyhow::Result<()> {
+    let layer_path = column::<StringArray>(batch, "layer_path")?;
+    let layer = optional_column::<StringArray>(batch, "layer")?;
+    let id = column::<UInt32Array>(batch, "id")?;
+    let kind = column::<StringArray>(batch, "kind")?;
+    let content = column::<StringArray>(batch, "content")?;
+    let content_sha256 = optional_column::<StringArray>(batch, "content_sha256")?;
+    let author = column::<StringArray>(batch, "author")?;
+    let confidence = column::<Float32Array>(batch, "confidence")?;
+    let created_at = column::<UInt64Array>(batch, "created_at_unix_ms")?;
+    let sources = optional_column::<ListArray>(batch, "sources")?;
+    let embedding = optional_column::<FixedSizeListArray>(batch, "embedding")?;
+
+    let mut ix_by_path: HashMap<String, usize> = layers
+        .iter()
+        .enumerate()
+        .map(|(ix, l)| (l.path.clone(), ix))
+        .collect();
+    for row in 0..batch.num_rows() {
+        let path = layer_path.value(row);
+        let ix = *ix_by_path.entry(path.to_string()).or_insert_with(|| {
+            // No layer record in the metadata: f32 at the embedding column's width.
+            layers.push(ExportLayerV1 {
+                path: path.to_string(),
+                layer: layer.and_then(|l| opt_str(l, row)),
+                schema: ExportLayerSchemaV1 {
+                    dim: embedding.map_or(0, |e| e.value_length().unsigned_abs()),
+                    element_type: "f32".to_string(),
+                    quant_scale: 1.0,
+                },
+                layer_metadata_json: None,
+                chunks: Vec::new(),
+            });
+            layers.len() - 1
+        });
+        let chunk = ExportChunkV1 {
+            id: id.value(row),
+            kind: kind.value(row).to_string(),
+            content: opt_str(content, row),
+            author: author.value(row).to_string(),
+            confidence: confidence.value(row),
+            created_at_unix_ms: created_at.value(row),
+            sources: sources.map_or(Ok(Vec::new()), |s| read_sources(s, row))?,
+            embedding: embedding.and_then(|e| read_embedding(e, row)),
+            content_sha256: content_sha256.and_then(|h| opt_str(h, row)),
+        };
+        if let Some(l) = layers.get_mut(ix) {
+            l.chunks.push(chunk);
+        }
+    }
+    Ok(())
+}
+
+fn read_sources(sources: &ListArray, row: usize) -> anyhow::Result<Vec<ExportSourceV1>> {
+    if sources.is_null(row) {
+        return Ok(Vec::new());
+    }
+    let entries = sources.value(row);
+    let entries = entries
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .context("Parquet sources must be a list of structs")?;
+    let chunk_id = entries
+        .column_by_name("chunk_id")
+        .and_then(|c| c.as_any().downcast_ref::<UInt32Array>())
+        .context("Parquet sources need a chunk_id field")?;
+    let value = entries
+        .column_by_name("value")
+        .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+        .context("Parquet sources need a value field")?;
+    Ok((0..entries.len())
+        .map(|i| match opt_str(value, i) {
+            Some(value) => ExportSourceV1::SourceString { value },
+            None => ExportSourceV1::ChunkId {
+                id: chunk_id.value(i),
+            },
+        })
+        .collect())
+}
+
+fn read_embedding(embedding: &FixedSizeListArray, row: usize) -> Option<Vec<f32>> {
+    if embedding.is_null(row) {
+        return None;
+    }
+    let values = embedding.value(row);
+    values
+        .as_any()
+        .downcast_ref::<Float32Array>()
+        .map(|v| v.values().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::export_chunk;
+
+    #[test]
+    fn bundle_round_trips_through_parquet() -> anyhow::Result<()> {
+        let bundle = ExportBundleV1 {
+            format: "agentsdb.export.v1".to_string(),
+            tool: ExportToolInfo {
+                name: "test".to_string(),
+                version: "1".to_string(),
+            },
+            layers: vec![ExportLayerV1 {
+                path: "AGENTS.local.db".to_string(),
+                layer: Some("local".to_string()),
+                schema: ExportLayerSchemaV1 {
+                    dim: 2,
+                    element_type: "f32".to_string(),
+                    quant_scale: 1.0,
+                },
+                layer_metadata_json: Some("{\"v\":1}".to_string()),
+                chunks: vec![
+                    ExportChunkV1 {
+                        created_at_unix_ms: 1_700_000_000_001,
+                        embedding: Some(vec![0.25, -1.0]),
+                        ..export_chunk(1, Vec::new())
+                    },
+                    export_chunk(
+                        2,
+                        vec![
+                            ExportSourceV1::ChunkId { id: 1 },
+                            ExportSourceV1::SourceString {
+                                value: "docs/a.md:3".to_string(),
+                            },
+                        ],
+                    ),
+                ],
+            }],
+        };
+
+        let read = read_bundle(&write_bundle(&bundle)?)?;
+        assert_eq!(read.tool.name, "test");
+        assert_eq!(read.layers.len(), 1);
+        let layer = &read.layers[0];
+        assert_eq!(layer.layer.as_deref(), Some("local"));
+        assert_eq!(layer.schema.dim, 2);
+        assert_eq!(layer.layer_metadata_json.as_deref(), Some("{\"v\":1}"));
+        assert_eq!(layer.chunks.len(), 2);
+        assert_eq!(layer.chunks[0].embedding, Some(vec![0.25, -1.0]));
+        assert_eq!(layer.chunks[0].created_at_unix_ms, 1_700_000_000_001);
+        assert_eq!(layer.chunks[1].embedding, None);
+        assert!(matches!(
+            layer.chunks[1].sources.as_slice(),
+            [
+                ExportSourceV1::ChunkId { id: 1 },
+                ExportSourceV1::SourceString { value }
+            ] if value == "docs/a.md:3"
+        ));
+        Ok(())
+    }
+}
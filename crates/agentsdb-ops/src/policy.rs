@@ -0,0 +1,255 @@
+//! Promotion policy: a root-level `AGENTS.policy.json` that gates promotions and proposal
+//! acceptance, and sets the [`WriteRules`] every write must follow.
+//!
+//! ```json
+//! {
+//!   "required_approvals": 2,
+//!   "base_kinds": ["canonical", "decision"],
+//!   "max_confidence_change": 0.25,
+//!   "write": {
+//!     "max_content_chars": 4000,
+//!     "banned_kinds": { "delta": ["scratch"] },
+//!     "require_sources": ["decision"]
+//!   }
+//! }
+//! ```
+//!
+//! Every field is optional; a missing file allows everything.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Sidecar file name for the promotion policy.
+const POLICY_FILE: &str = "AGENTS.policy.json";
+
+/// Proposal event action recording one approval that did not yet complete the acceptance.
+pub const APPROVE_ACTION: &str = "approve";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromotionPolicy {
+    /// Distinct approvers a proposal needs before it is accepted; 0 and 1 both mean a single
+    /// accept is enough.
+    pub required_approvals: u32,
+    /// Kinds that may be promoted into the base layer (`AGENTS.db`); empty allows every kind.
+    pub base_kinds: Vec<String>,
+    /// Largest change in confidence a promoted revision may make to the chunk it supersedes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_confidence_change: Option<f32>,
+    /// Rules for chunks written to any layer in the directory.
+    #[serde(skip_serializing_if = "WriteRules::is_empty")]
+    pub write: WriteRules,
+}
+
+/// Rules every chunk appended next to the policy file must follow, enforced by
+/// [`PolicyHook`](crate::hooks::PolicyHook).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WriteRules {
+    /// Longest content allowed, in characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_content_chars: Option<usize>,
+    /// Kinds that may not be written to a layer, by logical layer (`local`, `delta`, ...).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub banned_kinds: BTreeMap<String, Vec<String>>,
+    /// Kinds whose chunks must cite at least one source other than a reserved tag (see
+    /// [`agentsdb_query::RESERVED_SOURCE_PREFIXES`]).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub require_sources: Vec<String>,
+}
+
+impl WriteRules {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl PromotionPolicy {
+    /// Build the policy file path given the project root directory.
+    pub fn path_for(root: &Path) -> PathBuf {
+        root.join(POLICY_FILE)
+    }
+
+    /// Load from disk, returning the permissive default if the file doesn't exist. Unlike other
+    /// sidecars, a policy that cannot be parsed is an error rather than silently ignored.
+    pub fn load(root: &Path) -> anyhow::Result<Self> {
+        let path = Self::path_for(root);
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).with_context(|| format!("parse {}", path.display()))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("read {}", path.display())),
+        }
+    }
+
+    /// The policy governing promotions into the layer at `to_path` (the one in its directory).
+    pub fn for_layer(to_path: &Path) -> anyhow::Result<Self> {
+        Self::load(to_path.parent().unwrap_or_else(|| Path::new(".")))
+    }
+
+    /// Distinct approvers needed to accept a proposal.
+    pub fn approvals_needed(&self) -> usize {
+        usize::try_from(self.required_approvals.max(1)).unwrap_or(usize::MAX)
+    }
+
+    /// Approvers still missing once `approver` joins `approvers` (distinct names).
+    pub fn missing_approvals(&self, approvers: &[String], approver: &str) -> usize {
+        let mut distinct: Vec<&str> = approvers.iter().map(String::as_str).collect();
+        if !distinct.contains(&approver) {
+            distinct.push(approver);
+        }
+        distinct.sort_unstable();
+        distinct.dedup();
+        self.approvals_needed().saturating_sub(distinct.len())
+    }
+
+    /// Checks chunks about to be promoted from `from_path` into `to_path`: kinds allowed into
+    /// base, and the confidence change of revisions (`supersedes:<id>` sources) against the
+    /// chunk they replace, looked up in the source layer, then the target, then the other
+    /// standard layers next to the target.
+    pub fn check_promotion(
+        &self,
+        from_path: &Path,
+        to_path: &Path,
+        chunks: &[agentsdb_format::ChunkInput],
+    ) -> anyhow::Result<()> {
+        let to_base = to_path.file_name().and_then(|s| s.to_str()) == Some("AGENTS.db");
+        if to_base && !self.base_kinds.is_empty() {
+            if let Some(c) = chunks.iter().find(|c| !self.base_kinds.contains(&c.kind)) {
+                anyhow::bail!(
+                    "policy: kind {:?} of chunk {} may not be promoted to base (allowed: {})",
+                    c.kind,
+                    c.id,
+                    self.base_kinds.join(", ")
+                );
+            }
+        }
+        let Some(max_change) = self.max_confidence_change else {
+            return Ok(());
+        };
+        let revisions: Vec<(&agentsdb_format::ChunkInput, u32)> = chunks
+            .iter()
+            .filter_map(|c| superseded_id(c).map(|old| (c, old)))
+            .collect();
+        if revisions.is_empty() {
+            return Ok(());
+        }
+        let originals = original_confidences(from_path, to_path)?;
+        for (chunk, old) in revisions {
+            let Some(before) = originals.get(&old) else {
+                continue;
+            };
+            let change = (chunk.confidence - before).abs();
+            if change > max_change + f32::EPSILON {
+                anyhow::bail!(
+                    "policy: chunk {} changes the confidence of chunk {old} by {change:.2} ({before:.2} -> {:.2}); at most {max_change} is allowed",
+                    chunk.id,
+                    chunk.confidence
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn superseded_id(chunk: &agentsdb_format::ChunkInput) -> Option<u32> {
+    chunk.sources.iter().find_map(|s| match s {
+        agentsdb_format::ChunkSource::SourceString(v) => v
+            .strip_prefix(agentsdb_query::SUPERSEDES_SOURCE_PREFIX)?
+            .parse()
+            .ok(),
+        agentsdb_format::ChunkSource::ChunkId(_) => None,
+    })
+}
+
+/// Confidence of the latest non-tombstone version of each id, first layer wins.
+fn original_confidences(from_path: &Path, to_path: &Path) -> anyhow::Result<HashMap<u32, f32>> {
+    let dir = to_path.parent().unwrap_or_else(|| Path::new("."));
+    let mut paths = vec![from_path.to_path_buf(), to_path.to_path_buf()];
+    for name in [
+        "AGENTS.local.db",
+        "AGENTS.user.db",
+        "AGENTS.delta.db",
+        "AGENTS.db",
+    ] {
+        let path = dir.join(name);
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    let mut out = HashMap::new();
+    for path in paths.iter().filter(|p| p.exists()) {
+        let file = agentsdb_format::LayerFile::open_lenient(path)
+            .with_context(|| format!("open {}", path.display()))?;
+        let mut layer = HashMap::new();
+        for chunk in file.chunks() {
+            let chunk = chunk?;
+            if chunk.kind != agentsdb_query::KIND_TOMBSTONE {
+                layer.insert(chunk.id, chunk.confidence);
+            }
+        }
+        for (id, confidence) in layer {
+            out.entry(id).or_insert(confidence);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+    use agentsdb_format::{ChunkInput, ChunkSource};
+
+    #[test]
+    fn policy_gates_kinds_confidence_and_approvals() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert_eq!(
+            PromotionPolicy::load(dir.path())?,
+            PromotionPolicy::default()
+        );
+        std::fs::write(
+            PromotionPolicy::path_for(dir.path()),
+            r#"{"required_approvals": 2, "base_kinds": ["canonical"], "max_confidence_change": 0.2}"#,
+        )?;
+        let policy = PromotionPolicy::load(dir.path())?;
+
+        let user = dir.path().join("AGENTS.user.db");
+        let delta = dir.path().join("AGENTS.delta.db");
+        let base = dir.path().join("AGENTS.db");
+        let canonical = |id: u32, confidence: f32, supersedes: Option<u32>| ChunkInput {
+            kind: "canonical".to_string(),
+            confidence,
+            sources: supersedes
+                .map(|old| {
+                    ChunkSource::SourceString(format!(
+                        "{}{old}",
+                        agentsdb_query::SUPERSEDES_SOURCE_PREFIX
+                    ))
+                })
+                .into_iter()
+                .collect(),
+            ..chunk(id, &format!("chunk {id}"))
+        };
+        write_layer(&user, vec![canonical(1, 0.9, None)])?;
+
+        let gentle = [canonical(5, 0.8, Some(1))];
+        policy.check_promotion(&delta, &user, &gentle)?;
+        let drastic = [canonical(5, 0.3, Some(1))];
+        assert!(policy.check_promotion(&delta, &user, &drastic).is_err());
+        let note = [chunk(6, "chunk 6")];
+        policy.check_promotion(&delta, &user, &note)?;
+        assert!(policy.check_promotion(&delta, &base, &note).is_err());
+
+        assert_eq!(policy.missing_approvals(&[], "ana"), 1);
+        assert_eq!(policy.missing_approvals(&["ana".to_string()], "ana"), 1);
+        assert_eq!(policy.missing_approvals(&["ana".to_string()], "bo"), 0);
+
+        std::fs::write(PromotionPolicy::path_for(dir.path()), r#"{"approvals": 2}"#)?;
+        assert!(PromotionPolicy::load(dir.path()).is_err());
+        Ok(())
+    }
+}
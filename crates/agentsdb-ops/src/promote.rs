@@ -1,14 +1,75 @@
 use anyhow::Context;
 use serde::Serialize;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
 
+use crate::error::PromoteError;
+
 #[derive(Debug, Default, Serialize)]
 pub struct PromoteOutcome {
     pub promoted: Vec<u32>,
     pub skipped: Vec<u32>,
+    /// Journal id of the promotion, for `agentsdb undo` (absent if nothing was promoted).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<u32>,
+    /// Id conflicts settled by [`promote_chunks_resolving`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<ResolvedConflict>,
+}
+
+/// How [`promote_chunks_resolving`] settles a chunk whose id the destination already holds with
+/// different content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Keep the destination's chunk; the source chunk is not promoted.
+    Ours,
+    /// Promote the source chunk under a new id that supersedes (and tombstones) the
+    /// destination's chunk.
+    Theirs,
+    /// Promote the source chunk under a new id, next to the destination's chunk.
+    Rename,
+}
+
+impl std::str::FromStr for ConflictStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "ours" => Ok(Self::Ours),
+            "theirs" => Ok(Self::Theirs),
+            "rename" => Ok(Self::Rename),
+            other => anyhow::bail!("unknown strategy {other:?} (expected ours, theirs or rename)"),
+        }
+    }
 }
 
+/// A chunk to promote whose id is taken in the destination by a chunk with different content.
+#[derive(Debug, Clone)]
+pub struct PromoteConflict {
+    pub id: u32,
+    /// The source chunk.
+    pub incoming: agentsdb_format::ChunkInput,
+    /// The destination's visible chunk with the same id.
+    pub existing: agentsdb_format::ChunkInput,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedConflict {
+    pub id: u32,
+    pub strategy: ConflictStrategy,
+    /// Id the source chunk was promoted under (absent for `ours`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub promoted_id: Option<u32>,
+    /// Tombstone retracting the destination's chunk (`theirs` only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tombstone_id: Option<u32>,
+}
+
+/// Picks a [`ConflictStrategy`] per conflict; an error aborts the promotion before anything is
+/// written.
+type Resolver<'a> = &'a mut dyn FnMut(&PromoteConflict) -> anyhow::Result<ConflictStrategy>;
+
 /// Promote chunks from one layer to another
 ///
 /// # Arguments
@@ -17,6 +78,9 @@ pub struct PromoteOutcome {
 /// * `ids` - Chunk IDs to promote
 /// * `_skip_existing` - (Deprecated) No longer used; promoted chunks always receive new auto-assigned IDs
 ///
+/// The chunks must satisfy the [`crate::policy::PromotionPolicy`] of the destination's directory.
+/// The promotion is recorded in the destination directory's [`crate::journal::Journal`].
+///
 /// # Returns
 /// A PromoteOutcome containing lists of promoted and skipped IDs
 pub fn promote_chunks(
@@ -24,12 +88,236 @@ pub fn promote_chunks(
     to_path: &str,
     ids: &[u32],
     _skip_existing: bool,
-) -> anyhow::Result<PromoteOutcome> {
+) -> Result<PromoteOutcome, PromoteError> {
+    promote(from_path, to_path, ids, None)
+}
+
+/// Like [`promote_chunks`], but chunks keep their ids in the destination where those are free.
+///
+/// A chunk whose id the destination already holds with the same kind, content and sources is
+/// skipped. One whose id holds different content is a conflict, settled by `resolve`; a chunk
+/// whose id was retracted in the destination is promoted under a new id. Undoing the promotion
+/// does not bring back chunks superseded with [`ConflictStrategy::Theirs`].
+pub fn promote_chunks_resolving(
+    from_path: &str,
+    to_path: &str,
+    ids: &[u32],
+    mut resolve: impl FnMut(&PromoteConflict) -> anyhow::Result<ConflictStrategy>,
+) -> Result<PromoteOutcome, PromoteError> {
+    promote(from_path, to_path, ids, Some(&mut resolve))
+}
+
+fn same_chunk(a: &agentsdb_format::ChunkInput, b: &agentsdb_format::ChunkInput) -> bool {
+    use agentsdb_format::ChunkSource;
+    a.kind == b.kind
+        && a.content == b.content
+        && a.sources.len() == b.sources.len()
+        && a.sources.iter().zip(&b.sources).all(|pair| match pair {
+            (ChunkSource::ChunkId(x), ChunkSource::ChunkId(y)) => x == y,
+            (ChunkSource::SourceString(x), ChunkSource::SourceString(y)) => x == y,
+            _ => false,
+        })
+}
+
+/// Destination chunks by id, as [`promote_chunks_resolving`] sees them.
+struct Destination {
+    /// Latest non-retracted chunk per requested id.
+    live: BTreeMap<u32, agentsdb_format::ChunkInput>,
+    /// Requested ids stored in the layer, and every id it retracts.
+    taken: HashSet<u32>,
+    /// Fingerprint of the layer's embedding profile, if it records one.
+    fingerprint: Option<String>,
+}
+
+impl Destination {
+    /// Loads the destination chunks with the requested `ids`. Tombstones are read from the chunk
+    /// table, but chunks are only decoded when the layer's chunk id section says it holds one of
+    /// `ids`, so promoting new ids into a large layer does not decode all of it.
+    fn load(file: &agentsdb_format::LayerFile, ids: &[u32]) -> anyhow::Result<Self> {
+        let mut retracted = HashSet::new();
+        for chunk in file.chunks() {
+            let chunk = chunk?;
+            if chunk.kind != agentsdb_query::KIND_TOMBSTONE {
+                continue;
+            }
+            for source in file.sources_for(chunk.rel_start, chunk.rel_count)? {
+                if let agentsdb_format::SourceRef::ChunkId(id) = source {
+                    retracted.insert(id);
+                }
+            }
+        }
+
+        let mut held = HashSet::new();
+        for id in ids {
+            if file.contains_chunk_id(*id)? {
+                held.insert(*id);
+            }
+        }
+        let mut live = BTreeMap::new();
+        if !held.is_empty() {
+            for chunk in agentsdb_format::read_all_chunks(file)? {
+                if held.contains(&chunk.id) && chunk.kind != agentsdb_query::KIND_TOMBSTONE {
+                    live.insert(chunk.id, chunk);
+                }
+            }
+        }
+        live.retain(|id, _| !retracted.contains(id));
+        let mut taken = retracted;
+        taken.extend(held);
+        let fingerprint = crate::drift::layer_fingerprint(file.layer_metadata_bytes());
+        Ok(Self {
+            live,
+            taken,
+            fingerprint,
+        })
+    }
+}
+
+/// What happens to each requested chunk, decided before anything is written.
+struct Plan {
+    promote: Vec<agentsdb_format::ChunkInput>,
+    skipped: Vec<u32>,
+    conflicts: Vec<ResolvedConflict>,
+    /// Destination ids to tombstone ([`ConflictStrategy::Theirs`]).
+    superseded: Vec<u32>,
+    /// Source ids promoted under a new, auto-assigned id.
+    renamed: HashSet<u32>,
+}
+
+impl Plan {
+    /// Every chunk gets a new id in the target layer, so ids never collide.
+    fn new_ids(promote: Vec<agentsdb_format::ChunkInput>) -> Self {
+        Self {
+            renamed: promote.iter().map(|c| c.id).collect(),
+            promote,
+            skipped: Vec::new(),
+            conflicts: Vec::new(),
+            superseded: Vec::new(),
+        }
+    }
+
+    /// Chunks keep their ids where the destination has them free; `resolve` settles conflicts.
+    fn resolve(
+        chunks: Vec<agentsdb_format::ChunkInput>,
+        destination: Option<&Destination>,
+        resolve: Resolver<'_>,
+    ) -> anyhow::Result<Self> {
+        let mut plan = Self {
+            promote: Vec::with_capacity(chunks.len()),
+            skipped: Vec::new(),
+            conflicts: Vec::new(),
+            superseded: Vec::new(),
+            renamed: HashSet::new(),
+        };
+        let mut taken = destination.map(|d| d.taken.clone()).unwrap_or_default();
+        for mut c in chunks {
+            let Some(existing) = destination.and_then(|d| d.live.get(&c.id)) else {
+                // Retracted ids stay taken: the tombstone would hide the promoted chunk.
+                if !taken.insert(c.id) {
+                    plan.renamed.insert(c.id);
+                }
+                plan.promote.push(c);
+                continue;
+            };
+            if same_chunk(existing, &c) {
+                plan.skipped.push(c.id);
+                continue;
+            }
+            let strategy = resolve(&PromoteConflict {
+                id: c.id,
+                incoming: c.clone(),
+                existing: existing.clone(),
+            })?;
+            plan.conflicts.push(ResolvedConflict {
+                id: c.id,
+                strategy,
+                promoted_id: None,
+                tombstone_id: None,
+            });
+            match strategy {
+                ConflictStrategy::Ours => plan.skipped.push(c.id),
+                ConflictStrategy::Theirs => {
+                    plan.superseded.push(c.id);
+                    plan.renamed.insert(c.id);
+                    c.sources.insert(
+                        0,
+                        agentsdb_format::ChunkSource::SourceString(format!(
+                            "{}{}",
+                            agentsdb_query::SUPERSEDES_SOURCE_PREFIX,
+                            c.id
+                        )),
+                    );
+                    plan.promote.push(c);
+                }
+                ConflictStrategy::Rename => {
+                    plan.renamed.insert(c.id);
+                    plan.promote.push(c);
+                }
+            }
+        }
+        Ok(plan)
+    }
+}
+
+/// Tombstone retracting destination chunk `id`, replaced by a chunk promoted from `from_path`.
+fn superseded_tombstone(id: u32, from_path: &str, dim: usize) -> agentsdb_format::ChunkInput {
+    agentsdb_format::ChunkInput {
+        id: 0,
+        kind: agentsdb_query::KIND_TOMBSTONE.to_string(),
+        content: format!("superseded by a chunk promoted from {from_path}"),
+        author: "human".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: crate::util::now_unix_ms(),
+        embedding: vec![0.0; dim],
+        sources: vec![agentsdb_format::ChunkSource::ChunkId(id)],
+    }
+}
+
+/// The id written for `id`, given the ids of a batch and the ids the append assigned to it.
+fn assigned_id(ids: &[u32], assigned: &[u32], id: u32) -> Option<u32> {
+    let index = ids.iter().position(|i| *i == id)?;
+    assigned.get(index).copied()
+}
+
+/// Checks that the destination (if it exists) has the source's schema, and loads its chunks
+/// with the requested `ids`.
+fn open_destination(
+    from_path: &str,
+    to_path: &str,
+    from_schema: &agentsdb_format::LayerSchema,
+    ids: &[u32],
+) -> Result<Option<Destination>, PromoteError> {
+    if !Path::new(to_path).exists() {
+        return Ok(None);
+    }
+    let to_file =
+        agentsdb_format::LayerFile::open(to_path).with_context(|| format!("open {to_path}"))?;
+    let to_schema = agentsdb_format::schema_of(&to_file);
+    if to_schema.dim != from_schema.dim
+        || to_schema.element_type != from_schema.element_type
+        || to_schema.quant_scale.to_bits() != from_schema.quant_scale.to_bits()
+    {
+        return Err(PromoteError::SchemaMismatch {
+            from: from_path.to_string(),
+            to: to_path.to_string(),
+        });
+    }
+    Ok(Destination::load(&to_file, ids).map(Some)?)
+}
+
+#[tracing::instrument(level = "debug", skip(ids, resolve), fields(ids = ids.len()))]
+fn promote(
+    from_path: &str,
+    to_path: &str,
+    ids: &[u32],
+    resolve: Option<Resolver<'_>>,
+) -> Result<PromoteOutcome, PromoteError> {
     if ids.is_empty() {
-        anyhow::bail!("ids must be non-empty");
+        return Err(PromoteError::EmptyIds);
     }
 
-    agentsdb_format::ensure_writable_layer_path_allow_user(to_path).context("permission check")?;
+    let policy = agentsdb_format::WritePolicy::standard().allow_user();
+    policy.check(to_path)?;
 
     let from_file =
         agentsdb_format::LayerFile::open(from_path).with_context(|| format!("open {from_path}"))?;
@@ -38,65 +326,382 @@ pub fn promote_chunks(
     let from_chunks = agentsdb_format::read_all_chunks(&from_file)?;
 
     let by_id: BTreeMap<u32, agentsdb_format::ChunkInput> =
-        from_chunks.into_iter().map(|c| (c.id, c)).collect();
+        from_chunks.iter().map(|c| (c.id, c.clone())).collect();
 
     let to_p = Path::new(to_path);
-    if to_p.exists() {
-        let to_file =
-            agentsdb_format::LayerFile::open(to_path).with_context(|| format!("open {to_path}"))?;
-        let to_schema = agentsdb_format::schema_of(&to_file);
-        if to_schema.dim != from_schema.dim
-            || to_schema.element_type != from_schema.element_type
-            || to_schema.quant_scale.to_bits() != from_schema.quant_scale.to_bits()
-        {
-            anyhow::bail!("schema mismatch between {from_path} and {to_path}");
-        }
-    }
-
-    // Note: We no longer check for ID collisions because promoted chunks
-    // will receive auto-assigned IDs in the target layer (id=0 triggers auto-assignment)
-    let filtered: Vec<u32> = ids.to_vec();
-    let skipped = Vec::new();
+    let destination = open_destination(from_path, to_path, &from_schema, ids)?;
+    // A new layer takes the source layer's metadata, and with it its profile.
+    let from_fingerprint = crate::drift::layer_fingerprint(from_metadata.as_deref());
+    let to_fingerprint = match &destination {
+        Some(destination) => destination.fingerprint.clone(),
+        None => from_fingerprint.clone(),
+    };
+    let promote = ids
+        .iter()
+        .map(|id| {
+            by_id
+                .get(id)
+                .cloned()
+                .ok_or_else(|| PromoteError::NotFound {
+                    id: *id,
+                    path: from_path.to_string(),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    if filtered.is_empty() {
+    let Plan {
+        mut promote,
+        skipped,
+        mut conflicts,
+        superseded,
+        renamed,
+    } = match resolve {
+        Some(resolve) => Plan::resolve(promote, destination.as_ref(), resolve)?,
+        None => Plan::new_ids(promote),
+    };
+    if promote.is_empty() {
         return Ok(PromoteOutcome {
-            promoted: Vec::new(),
             skipped,
+            conflicts,
+            ..PromoteOutcome::default()
         });
     }
 
-    let mut promote = Vec::new();
-    for id in &filtered {
-        let Some(c) = by_id.get(id) else {
-            anyhow::bail!("id {id} not found in {from_path}");
-        };
-        let mut c = c.clone();
-        c.id = 0; // Force auto-assignment of new ID in target layer
+    let moved: Vec<crate::journal::MovedChunk> = promote
+        .iter()
+        .map(|c| crate::journal::MovedChunk {
+            id: c.id,
+            author: c.author.clone(),
+        })
+        .collect();
+    let moved_ids: Vec<u32> = moved.iter().map(|m| m.id).collect();
+    crate::policy::PromotionPolicy::for_layer(to_p)?.check_promotion(
+        Path::new(from_path),
+        to_p,
+        &promote,
+    )?;
+    for c in &mut promote {
+        if renamed.contains(&c.id) {
+            c.id = 0; // Force auto-assignment of new ID in target layer
+        }
         if c.author != "human" {
             c.author = "human".to_string();
         }
-        promote.push(c);
+        crate::drift::retag(c, from_fingerprint.as_deref(), to_fingerprint.as_deref());
     }
+    let promoted_count = promote.len();
+    let dim = from_file.embedding_dim();
+    promote.extend(
+        superseded
+            .iter()
+            .map(|id| superseded_tombstone(*id, from_path, dim)),
+    );
 
-    let assigned_ids = if to_p.exists() {
-        agentsdb_format::append_layer_atomic(to_path, &mut promote, None).context("append")?
-    } else {
+    // Promotion is a move: the promoted chunks leave the source layer in the same transaction,
+    // staged after the append so an interrupted commit leaves a copy rather than nothing.
+    let mut tx = crate::Transaction::new().with_policy(policy.clone());
+    let appended = tx.append_or_create(to_p, &from_schema, from_metadata.as_deref(), promote);
+    if policy.check(from_path).is_ok() {
+        let remaining: Vec<_> = from_chunks
+            .into_iter()
+            .filter(|c| !moved_ids.contains(&c.id))
+            .collect();
+        tx.write(from_path, &from_schema, remaining, from_metadata.as_deref());
+    }
+    drop(from_file);
+    let mut assigned_ids = tx
+        .commit()
+        .context("commit promotion")?
+        .swap_remove(appended);
+    let tombstone_ids = assigned_ids.split_off(promoted_count);
+    for conflict in &mut conflicts {
+        conflict.promoted_id = assigned_id(&moved_ids, &assigned_ids, conflict.id);
+        conflict.tombstone_id = assigned_id(&superseded, &tombstone_ids, conflict.id);
+    }
+
+    let operation = crate::journal::record_operation(
+        crate::journal::OperationKind::Promote,
+        to_p,
+        Some((Path::new(from_path), moved)),
+        assigned_ids.clone(),
+    )?;
+
+    Ok(PromoteOutcome {
+        promoted: assigned_ids,
+        skipped,
+        operation: Some(operation),
+        conflicts,
+    })
+}
+
+/// The base layer with chunks promoted into it, built by [`rebuild_base`].
+#[derive(Debug, Clone)]
+pub struct BaseRebuild {
+    pub schema: agentsdb_format::LayerSchema,
+    pub metadata: Option<Vec<u8>>,
+    /// Every chunk of the rebuilt layer, in id order.
+    pub chunks: Vec<agentsdb_format::ChunkInput>,
+    pub promoted: Vec<u32>,
+    /// Requested ids the base layer already holds unchanged.
+    pub skipped: Vec<u32>,
+}
+
+impl BaseRebuild {
+    /// Writes the rebuilt layer to `path`.
+    pub fn write(&mut self, path: &Path) -> anyhow::Result<()> {
         agentsdb_format::write_layer_atomic(
-            to_path,
-            &from_schema,
-            &mut promote,
-            from_metadata.as_deref(),
+            path,
+            &self.schema,
+            &mut self.chunks,
+            self.metadata.as_deref(),
         )
-        .context("write")?
-    };
+        .with_context(|| format!("write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Builds the base layer at `base_path` with chunks `ids` of `from_path` added, without writing
+/// anything: the base layer is read-only, so the result goes to a new file or a pull request
+/// (see [`crate::vcs`]) for review.
+///
+/// The chunks keep their ids and must satisfy the [`crate::policy::PromotionPolicy`] of the
+/// base layer's directory. An id the base layer holds with identical content is skipped; one it
+/// holds with different content is an error.
+pub fn rebuild_base(
+    base_path: &Path,
+    from_path: &Path,
+    ids: &[u32],
+) -> anyhow::Result<BaseRebuild> {
+    let base_file = agentsdb_format::LayerFile::open(base_path)
+        .with_context(|| format!("open {}", base_path.display()))?;
+    let schema = agentsdb_format::schema_of(&base_file);
+    let metadata = base_file.layer_metadata_bytes().map(|b| b.to_vec());
+    let mut by_id: BTreeMap<u32, agentsdb_format::ChunkInput> =
+        agentsdb_format::read_all_chunks(&base_file)?
+            .into_iter()
+            .map(|c| (c.id, c))
+            .collect();
+
+    let from_file = agentsdb_format::LayerFile::open(from_path)
+        .with_context(|| format!("open {}", from_path.display()))?;
+    let from_schema = agentsdb_format::schema_of(&from_file);
+    if from_schema.dim != schema.dim
+        || from_schema.element_type != schema.element_type
+        || from_schema.quant_scale.to_bits() != schema.quant_scale.to_bits()
+    {
+        return Err(PromoteError::SchemaMismatch {
+            from: from_path.display().to_string(),
+            to: base_path.display().to_string(),
+        }
+        .into());
+    }
+    let from_fingerprint = crate::drift::layer_fingerprint(from_file.layer_metadata_bytes());
+    let base_fingerprint = crate::drift::layer_fingerprint(metadata.as_deref());
+    let from_by_id: BTreeMap<u32, agentsdb_format::ChunkInput> =
+        agentsdb_format::read_all_chunks(&from_file)?
+            .into_iter()
+            .map(|c| (c.id, c))
+            .collect();
+
+    let candidates = ids
+        .iter()
+        .filter_map(|id| from_by_id.get(id).cloned())
+        .collect::<Vec<_>>();
+    let dir = base_path.parent().unwrap_or_else(|| Path::new("."));
+    crate::policy::PromotionPolicy::load(dir)?.check_promotion(
+        from_path,
+        base_path,
+        &candidates,
+    )?;
 
-    // Remove promoted chunks from the source layer so promotion is a move, not a copy.
+    let mut promoted = Vec::new();
+    let mut skipped = Vec::new();
     for id in ids {
-        let _ = crate::remove::remove_chunk(std::path::Path::new(from_path), *id);
+        let Some(c) = from_by_id.get(id) else {
+            return Err(PromoteError::NotFound {
+                id: *id,
+                path: from_path.display().to_string(),
+            }
+            .into());
+        };
+        if c.kind == crate::proposals::PROPOSAL_EVENT_KIND {
+            anyhow::bail!("cannot promote proposal event chunk id {id} into base");
+        }
+        if let Some(existing) = by_id.get(id) {
+            if identical_chunk(existing, c) {
+                skipped.push(*id);
+                continue;
+            }
+            anyhow::bail!(
+                "{} already contains id {id} with different content",
+                base_path.display()
+            );
+        }
+        let mut c = c.clone();
+        if c.author != "human" {
+            c.author = "human".to_string();
+        }
+        crate::drift::retag(
+            &mut c,
+            from_fingerprint.as_deref(),
+            base_fingerprint.as_deref(),
+        );
+        by_id.insert(*id, c);
+        promoted.push(*id);
     }
+    promoted.sort_unstable();
+    promoted.dedup();
+    skipped.sort_unstable();
+    skipped.dedup();
 
-    Ok(PromoteOutcome {
-        promoted: assigned_ids,
+    Ok(BaseRebuild {
+        schema,
+        metadata,
+        chunks: by_id.into_values().collect(),
+        promoted,
         skipped,
     })
 }
+
+/// Whether two chunks are the same record, down to their embeddings.
+fn identical_chunk(a: &agentsdb_format::ChunkInput, b: &agentsdb_format::ChunkInput) -> bool {
+    a.author == b.author
+        && a.confidence.to_bits() == b.confidence.to_bits()
+        && a.created_at_unix_ms == b.created_at_unix_ms
+        && a.embedding.len() == b.embedding.len()
+        && a.embedding
+            .iter()
+            .zip(&b.embedding)
+            .all(|(x, y)| x.to_bits() == y.to_bits())
+        && same_chunk(a, b)
+}
+
+/// Ids of the chunks of the layer at `path` in `namespace` (latest version of each id; tombstones,
+/// options and `meta.*` records excluded), for promoting a namespace as a whole.
+pub fn namespace_chunk_ids(path: &str, namespace: &str) -> anyhow::Result<Vec<u32>> {
+    let file =
+        agentsdb_format::LayerFile::open_lenient(path).with_context(|| format!("open {path}"))?;
+    let mut latest = BTreeMap::new();
+    for chunk in agentsdb_format::read_all_chunks(&file)? {
+        latest.insert(chunk.id, chunk);
+    }
+    Ok(latest
+        .into_values()
+        .filter(|c| {
+            c.kind != agentsdb_query::KIND_TOMBSTONE
+                && c.kind != agentsdb_embeddings::config::KIND_OPTIONS
+                && !c.kind.starts_with("meta.")
+                && crate::util::chunk_namespace(&c.sources) == Some(namespace)
+        })
+        .map(|c| c.id)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+    use agentsdb_format::{ChunkSource, LayerFile};
+
+    #[test]
+    fn resolving_keeps_free_ids_and_settles_conflicts() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let delta = dir.path().join("AGENTS.delta.db");
+        let user = dir.path().join("AGENTS.user.db");
+        write_layer(
+            &delta,
+            vec![
+                chunk(1, "new"),
+                chunk(2, "same"),
+                chunk(3, "ours?"),
+                chunk(4, "theirs"),
+                chunk(5, "renamed"),
+            ],
+        )?;
+        write_layer(
+            &user,
+            vec![
+                chunk(2, "same"),
+                chunk(3, "kept"),
+                chunk(4, "replaced"),
+                chunk(5, "stays"),
+            ],
+        )?;
+
+        let mut asked = Vec::new();
+        let outcome = promote_chunks_resolving(
+            &delta.display().to_string(),
+            &user.display().to_string(),
+            &[1, 2, 3, 4, 5],
+            |conflict| {
+                asked.push(conflict.id);
+                Ok(match conflict.id {
+                    3 => ConflictStrategy::Ours,
+                    4 => ConflictStrategy::Theirs,
+                    _ => ConflictStrategy::Rename,
+                })
+            },
+        )?;
+        assert_eq!(asked, [3, 4, 5]);
+        assert_eq!(outcome.skipped, [2, 3]);
+        assert_eq!(outcome.promoted.len(), 3);
+        assert_eq!(outcome.promoted[0], 1);
+        let strategies: Vec<(u32, ConflictStrategy)> = outcome
+            .conflicts
+            .iter()
+            .map(|c| (c.id, c.strategy))
+            .collect();
+        assert_eq!(
+            strategies,
+            [
+                (3, ConflictStrategy::Ours),
+                (4, ConflictStrategy::Theirs),
+                (5, ConflictStrategy::Rename),
+            ]
+        );
+        let theirs = &outcome.conflicts[1];
+        let new_id = theirs.promoted_id.context("theirs promoted")?;
+        assert_ne!(new_id, 4);
+        assert!(outcome.conflicts[0].promoted_id.is_none());
+
+        let stored = agentsdb_format::read_all_chunks(&LayerFile::open_lenient(&user)?)?;
+        let revision = stored
+            .iter()
+            .find(|c| c.id == new_id)
+            .context("revision stored")?;
+        assert_eq!(revision.content, "theirs");
+        assert!(revision
+            .sources
+            .iter()
+            .any(|s| matches!(s, ChunkSource::SourceString(v) if v == "supersedes:4")));
+        let tombstone_id = theirs.tombstone_id.context("tombstone")?;
+        assert!(stored.iter().any(|c| c.id == tombstone_id
+            && c.kind == agentsdb_query::KIND_TOMBSTONE
+            && matches!(c.sources.as_slice(), [ChunkSource::ChunkId(4)])));
+
+        // Only the promoted chunks left the source.
+        let remaining = Destination::load(&LayerFile::open_lenient(&delta)?, &[1, 2, 3, 4, 5])?;
+        let remaining: Vec<u32> = remaining.live.into_keys().collect();
+        assert_eq!(remaining, [2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn resolver_error_aborts_before_writing() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let delta = dir.path().join("AGENTS.delta.db");
+        let user = dir.path().join("AGENTS.user.db");
+        write_layer(&delta, vec![chunk(1, "a")])?;
+        write_layer(&user, vec![chunk(1, "b")])?;
+        let result = promote_chunks_resolving(
+            &delta.display().to_string(),
+            &user.display().to_string(),
+            &[1],
+            |_| anyhow::bail!("aborted"),
+        );
+        assert!(result.is_err());
+        let stored = agentsdb_format::read_all_chunks(&LayerFile::open_lenient(&user)?)?;
+        assert_eq!(stored.len(), 1);
+        Ok(())
+    }
+}
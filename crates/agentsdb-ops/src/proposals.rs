@@ -0,0 +1,311 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::util::now_unix_ms;
+
+/// Kind of the chunks recording proposal lifecycle events (propose/accept/reject).
+pub const PROPOSAL_EVENT_KIND: &str = "meta.proposal_event";
+
+/// A proposal event, deserialized from a `meta.proposal_event` chunk.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProposalEvent {
+    #[serde(default)]
+    pub action: Option<String>, // propose | approve | accept | reject
+    #[serde(default)]
+    pub proposal_id: Option<u32>, // for approve/accept/reject
+    pub context_id: u32,
+    #[serde(default)]
+    pub from_path: Option<String>,
+    #[serde(default)]
+    pub to_path: Option<String>,
+    #[serde(default)]
+    pub created_at_unix_ms: Option<u64>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub why: Option<String>,
+    #[serde(default)]
+    pub what: Option<String>,
+    #[serde(default, rename = "where")]
+    pub where_: Option<String>,
+    #[serde(default)]
+    pub actor: Option<String>,
+    /// Label of the writer's identity (see [`crate::identity`]), if one was set.
+    #[serde(default)]
+    pub identity: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub outcome: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+/// Current state of a proposal, folded from its events. `proposal_id` is the id of the
+/// propose event chunk.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposalState {
+    pub proposal_id: u32,
+    pub context_id: u32,
+    pub from_path: String,
+    pub to_path: String,
+    pub status: ProposalStatus,
+    pub created_at_unix_ms: Option<u64>,
+    pub title: Option<String>,
+    pub why: Option<String>,
+    pub what: Option<String>,
+    #[serde(rename = "where")]
+    pub where_: Option<String>,
+    /// Identity recorded on the propose event.
+    pub proposed_by_identity: Option<String>,
+    pub decided_at_unix_ms: Option<u64>,
+    pub decided_by: Option<String>,
+    /// Identity recorded on the accept or reject event.
+    pub decided_by_identity: Option<String>,
+    pub decision_reason: Option<String>,
+    pub decision_outcome: Option<String>,
+    /// Distinct actors that approved the proposal, in order (see
+    /// [`crate::policy::PromotionPolicy::required_approvals`]).
+    pub approvers: Vec<String>,
+}
+
+/// Reads proposal events from `path` in append order. A missing layer has no events.
+pub fn read_proposal_events(path: &Path) -> anyhow::Result<Vec<(u32, ProposalEvent)>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = agentsdb_format::LayerFile::open(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    let mut out = Vec::new();
+    for chunk in file.chunks() {
+        let chunk = chunk?;
+        if chunk.kind != PROPOSAL_EVENT_KIND {
+            continue;
+        }
+        let ev: ProposalEvent = serde_json::from_str(chunk.content)
+            .with_context(|| format!("parse proposal event chunk {}", chunk.id))?;
+        out.push((chunk.id, ev));
+    }
+    Ok(out)
+}
+
+fn apply_event(map: &mut BTreeMap<u32, ProposalState>, event_id: u32, ev: ProposalEvent) {
+    let action = ev.action.as_deref().unwrap_or("propose");
+    match action {
+        "propose" => {
+            map.insert(
+                event_id,
+                ProposalState {
+                    proposal_id: event_id,
+                    context_id: ev.context_id,
+                    from_path: ev
+                        .from_path
+                        .unwrap_or_else(|| "AGENTS.delta.db".to_string()),
+                    to_path: ev.to_path.unwrap_or_else(|| "AGENTS.user.db".to_string()),
+                    status: ProposalStatus::Pending,
+                    created_at_unix_ms: ev.created_at_unix_ms,
+                    title: ev.title,
+                    why: ev.why,
+                    what: ev.what,
+                    where_: ev.where_,
+                    proposed_by_identity: ev.identity,
+                    decided_at_unix_ms: None,
+                    decided_by: None,
+                    decided_by_identity: None,
+                    decision_reason: None,
+                    decision_outcome: None,
+                    approvers: Vec::new(),
+                },
+            );
+        }
+        crate::policy::APPROVE_ACTION | "accept" | "reject" => {
+            let Some(proposal_id) = ev.proposal_id else {
+                return;
+            };
+            let Some(state) = map.get_mut(&proposal_id) else {
+                return;
+            };
+            if action != "reject" {
+                if let Some(actor) = &ev.actor {
+                    if !state.approvers.contains(actor) {
+                        state.approvers.push(actor.clone());
+                    }
+                }
+            }
+            if action != crate::policy::APPROVE_ACTION {
+                state.status = if action == "accept" {
+                    ProposalStatus::Accepted
+                } else {
+                    ProposalStatus::Rejected
+                };
+                state.decided_at_unix_ms = ev.created_at_unix_ms;
+                state.decided_by = ev.actor;
+                state.decided_by_identity = ev.identity;
+                state.decision_reason = ev.reason;
+                state.decision_outcome = ev.outcome;
+            }
+        }
+        _other => {}
+    }
+}
+
+/// Folds the proposal events stored in `path` into per-proposal state, keyed by proposal id.
+pub fn load_proposal_states(path: &Path) -> anyhow::Result<BTreeMap<u32, ProposalState>> {
+    let mut map = BTreeMap::new();
+    for (event_id, ev) in read_proposal_events(path)? {
+        apply_event(&mut map, event_id, ev);
+    }
+    Ok(map)
+}
+
+/// What a propose event records about the chunk put up for promotion.
+#[derive(Debug, Clone, Default)]
+pub struct NewProposal {
+    /// Id of the proposed chunk in the layer the event is appended to.
+    pub context_id: u32,
+    pub title: Option<String>,
+    pub why: Option<String>,
+    pub what: Option<String>,
+    pub where_: Option<String>,
+}
+
+/// Appends a propose event for `proposal.context_id` to the layer at `path`, which must hold
+/// that chunk. `to_label` names the destination layer (e.g. `AGENTS.user.db`).
+///
+/// # Returns
+/// The id of the appended event chunk, which is the proposal id
+#[tracing::instrument(level = "debug", skip_all, fields(path = %path.display(), context_id = proposal.context_id, actor = actor))]
+pub fn append_propose_event(
+    path: &Path,
+    to_label: &str,
+    proposal: &NewProposal,
+    actor: &str,
+) -> anyhow::Result<u32> {
+    let file = agentsdb_format::LayerFile::open(path)
+        .with_context(|| format!("open layer {}", path.display()))?;
+    let chunks = agentsdb_format::read_all_chunks(&file).context("read chunks")?;
+    let Some(src) = chunks.into_iter().find(|c| c.id == proposal.context_id) else {
+        anyhow::bail!(
+            "context_id {} not found in layer {}",
+            proposal.context_id,
+            path.display()
+        );
+    };
+
+    let from_label = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("AGENTS.delta.db");
+    let now_ms = now_unix_ms();
+    let record = serde_json::json!({
+        "action": "propose",
+        "context_id": proposal.context_id,
+        "from_path": from_label,
+        "to_path": to_label,
+        "created_at_unix_ms": now_ms,
+        "actor": actor,
+        "identity": crate::identity::current(),
+        "title": proposal.title,
+        "why": proposal.why,
+        "what": proposal.what,
+        "where": proposal.where_,
+    });
+
+    let mut chunk = agentsdb_format::ChunkInput {
+        id: 0,
+        kind: PROPOSAL_EVENT_KIND.to_string(),
+        content: serde_json::to_string(&record).context("serialize proposal record")?,
+        author: if actor == "mcp" { "mcp" } else { "human" }.to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: now_ms,
+        embedding: src.embedding,
+        sources: vec![agentsdb_format::ChunkSource::ChunkId(proposal.context_id)],
+    };
+    let ids = agentsdb_format::append_layer_atomic(path, std::slice::from_mut(&mut chunk), None)
+        .context("append proposal event")?;
+    crate::metrics::record_append(path, ids.len());
+    Ok(ids[0])
+}
+
+/// Appends an approve/accept/reject event for `proposal_id` to the proposal events layer at `path`.
+///
+/// # Returns
+/// The id of the appended event chunk
+#[tracing::instrument(level = "debug", skip(path, state), fields(proposal_id = state.proposal_id))]
+pub fn append_decision_event(
+    path: &Path,
+    action: &str,
+    state: &ProposalState,
+    actor: &str,
+    outcome: Option<&str>,
+    reason: Option<&str>,
+) -> anyhow::Result<u32> {
+    let now_ms = now_unix_ms();
+    let record = serde_json::json!({
+        "action": action,
+        "proposal_id": state.proposal_id,
+        "context_id": state.context_id,
+        "created_at_unix_ms": now_ms,
+        "actor": actor,
+        "identity": crate::identity::current(),
+        "outcome": outcome,
+        "reason": reason,
+    });
+
+    let file = agentsdb_format::LayerFile::open(path)
+        .with_context(|| format!("open proposal events layer {}", path.display()))?;
+    let mut chunk = agentsdb_format::ChunkInput {
+        id: 0,
+        kind: PROPOSAL_EVENT_KIND.to_string(),
+        content: serde_json::to_string(&record).context("serialize decision event")?,
+        author: if actor == "mcp" { "mcp" } else { "human" }.to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: now_ms,
+        embedding: vec![0.0; file.embedding_dim()],
+        sources: vec![agentsdb_format::ChunkSource::ChunkId(state.context_id)],
+    };
+    let ids = agentsdb_format::append_layer_atomic(path, std::slice::from_mut(&mut chunk), None)
+        .context("append decision event")?;
+    Ok(ids[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_fold_into_latest_state() {
+        let mut map = BTreeMap::new();
+        let ev = |json: serde_json::Value| -> ProposalEvent {
+            serde_json::from_value(json).expect("event")
+        };
+        apply_event(
+            &mut map,
+            7,
+            ev(serde_json::json!({ "context_id": 3, "title": "t" })),
+        );
+        apply_event(
+            &mut map,
+            8,
+            ev(
+                serde_json::json!({ "action": "reject", "proposal_id": 7, "context_id": 3, "actor": "mcp", "identity": "ci/triage", "reason": "dup" }),
+            ),
+        );
+        let state = map.get(&7).expect("proposal 7");
+        assert_eq!(state.status, ProposalStatus::Rejected);
+        assert_eq!(state.from_path, "AGENTS.delta.db");
+        assert_eq!(state.to_path, "AGENTS.user.db");
+        assert_eq!(state.decided_by.as_deref(), Some("mcp"));
+        assert_eq!(state.decided_by_identity.as_deref(), Some("ci/triage"));
+        assert_eq!(state.proposed_by_identity, None);
+        assert_eq!(state.decision_reason.as_deref(), Some("dup"));
+    }
+}
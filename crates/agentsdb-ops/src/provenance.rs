@@ -0,0 +1,414 @@
+//! Provenance checks for `agentsdb verify-provenance`: chunk-id sources that resolve to no chunk
+//! in the layer stack, that cite a retracted chunk, or that form a cycle.
+
+use agentsdb_core::types::LayerId;
+use agentsdb_format::{LayerFile, SourceRef};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Passes of [`fix_provenance`]: each relinked chunk is itself retired by a revision, so
+/// chunks citing it get relinked on the next pass.
+const MAX_FIX_PASSES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvenanceProblem {
+    /// No chunk in the layer stack has the cited id.
+    Dangling,
+    /// Every chunk with the cited id is retracted.
+    Retracted,
+    /// Following chunk-id sources from the cited chunk leads back to the citing one.
+    Cycle,
+}
+
+/// One broken chunk-id source of a visible chunk.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvenanceIssue {
+    /// Path of the layer holding the citing chunk, as given in the [`agentsdb_query::LayerSet`].
+    pub layer: String,
+    pub id: u32,
+    /// The cited chunk id.
+    pub source_id: u32,
+    pub problem: ProvenanceProblem,
+    /// For a retracted source: the visible revision that superseded it, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub successor: Option<u32>,
+    /// For a cycle: the ids along it, starting at the cited one and ending at the citing one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cycle: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvenanceReport {
+    /// Number of visible chunks checked.
+    pub scanned: usize,
+    /// Number of chunk-id sources checked.
+    pub references: usize,
+    pub issues: Vec<ProvenanceIssue>,
+}
+
+/// A chunk that [`fix_provenance`] replaced with a relinked revision.
+#[derive(Debug, Clone, Serialize)]
+pub struct RelinkedChunk {
+    pub layer: String,
+    pub id: u32,
+    pub revision: u32,
+    /// `(old, new)` chunk-id sources; `new` is `None` when the link was dropped and recorded
+    /// as a `broken-source:<old>` string instead.
+    pub relinks: Vec<(u32, Option<u32>)>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProvenanceFix {
+    pub relinked: Vec<RelinkedChunk>,
+    /// `(layer, id, reason)` of broken chunks left in place.
+    pub skipped: Vec<(String, u32, String)>,
+}
+
+/// A visible chunk with chunk-id sources.
+struct Citer {
+    layer_id: LayerId,
+    id: u32,
+    targets: Vec<u32>,
+}
+
+/// What [`verify_provenance`] needs from the layers.
+struct Scan {
+    /// Layers holding each id, tombstones aside.
+    present: HashMap<u32, Vec<LayerId>>,
+    citers: Vec<Citer>,
+    /// Visible revisions by the id they supersede.
+    revisions: HashMap<u32, u32>,
+    scanned: usize,
+}
+
+fn scan(
+    opened: &[(LayerId, LayerFile)],
+    retracted: &HashMap<u32, LayerId>,
+) -> anyhow::Result<Scan> {
+    let mut out = Scan {
+        present: HashMap::new(),
+        citers: Vec::new(),
+        revisions: HashMap::new(),
+        scanned: 0,
+    };
+    for (layer_id, file) in opened {
+        for chunk in file.latest_chunks()? {
+            if chunk.kind != agentsdb_query::KIND_TOMBSTONE {
+                out.present.entry(chunk.id).or_default().push(*layer_id);
+            }
+            if chunk.kind == agentsdb_query::KIND_TOMBSTONE
+                || chunk.kind == agentsdb_embeddings::config::KIND_OPTIONS
+                || chunk.kind.starts_with("meta.")
+                || agentsdb_query::is_retracted(retracted, *layer_id, chunk.id)
+            {
+                continue;
+            }
+            out.scanned += 1;
+            let mut targets = Vec::new();
+            for source in file.sources_for(chunk.rel_start, chunk.rel_count)? {
+                match source {
+                    SourceRef::ChunkId(id) => targets.push(id),
+                    SourceRef::String(v) => {
+                        if let Some(old) = v
+                            .strip_prefix(agentsdb_query::SUPERSEDES_SOURCE_PREFIX)
+                            .and_then(|id| id.parse().ok())
+                        {
+                            out.revisions.insert(old, chunk.id);
+                        }
+                    }
+                }
+            }
+            if !targets.is_empty() {
+                out.citers.push(Citer {
+                    layer_id: *layer_id,
+                    id: chunk.id,
+                    targets,
+                });
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Checks the chunk-id sources of every visible chunk of `layers` (not retracted, not
+/// tombstones, options or `meta.*` records).
+pub fn verify_provenance(layers: &agentsdb_query::LayerSet) -> anyhow::Result<ProvenanceReport> {
+    let mut opened = Vec::new();
+    let mut paths = BTreeMap::new();
+    for (layer_id, path) in [
+        (LayerId::Local, &layers.local),
+        (LayerId::User, &layers.user),
+        (LayerId::Delta, &layers.delta),
+        (LayerId::Base, &layers.base),
+    ] {
+        if let Some(path) = path {
+            let file = LayerFile::open_lenient(path).with_context(|| format!("open {path}"))?;
+            opened.push((layer_id, file));
+            paths.insert(layer_id, path.clone());
+        }
+    }
+    let retracted = agentsdb_query::retracted_chunk_ids(&opened)?;
+    let Scan {
+        present,
+        citers,
+        revisions,
+        scanned,
+    } = scan(&opened, &retracted)?;
+
+    let is_visible = |id: u32| {
+        present.get(&id).is_some_and(|layers| {
+            layers
+                .iter()
+                .any(|l| !agentsdb_query::is_retracted(&retracted, *l, id))
+        })
+    };
+    let mut issues = Vec::new();
+    let mut references = 0;
+    for citer in &citers {
+        let layer = paths.get(&citer.layer_id).cloned().unwrap_or_default();
+        for &target in &citer.targets {
+            references += 1;
+            let (problem, successor) = if !present.contains_key(&target) {
+                (ProvenanceProblem::Dangling, None)
+            } else if !is_visible(target) {
+                (
+                    ProvenanceProblem::Retracted,
+                    successor(target, &revisions, is_visible),
+                )
+            } else {
+                continue;
+            };
+            issues.push(ProvenanceIssue {
+                layer: layer.clone(),
+                id: citer.id,
+                source_id: target,
+                problem,
+                successor,
+                cycle: Vec::new(),
+            });
+        }
+    }
+    for (citer, target, cycle) in find_cycles(&citers) {
+        issues.push(ProvenanceIssue {
+            layer: paths.get(&citer.layer_id).cloned().unwrap_or_default(),
+            id: citer.id,
+            source_id: target,
+            problem: ProvenanceProblem::Cycle,
+            successor: None,
+            cycle,
+        });
+    }
+    Ok(ProvenanceReport {
+        scanned,
+        references,
+        issues,
+    })
+}
+
+/// The visible end of the chain of revisions superseding `id`.
+fn successor(
+    mut id: u32,
+    revisions: &HashMap<u32, u32>,
+    is_visible: impl Fn(u32) -> bool,
+) -> Option<u32> {
+    let mut seen = HashSet::new();
+    while let Some(&next) = revisions.get(&id) {
+        if !seen.insert(next) {
+            return None;
+        }
+        if is_visible(next) {
+            return Some(next);
+        }
+        id = next;
+    }
+    None
+}
+
+/// One back edge per cycle of the chunk-id graph: the citing chunk, the cited id, and the ids
+/// along the cycle from the cited one to the citing one.
+fn find_cycles(citers: &[Citer]) -> Vec<(&Citer, u32, Vec<u32>)> {
+    let mut edges: BTreeMap<u32, Vec<(u32, &Citer)>> = BTreeMap::new();
+    for citer in citers {
+        for &target in &citer.targets {
+            edges.entry(citer.id).or_default().push((target, citer));
+        }
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        OnPath,
+        Done,
+    }
+    let mut state: HashMap<u32, State> = HashMap::new();
+    let mut out = Vec::new();
+    for &start in edges.keys() {
+        if state.contains_key(&start) {
+            continue;
+        }
+        // Iterative DFS: the current path, with the next edge to follow from each node.
+        let mut path: Vec<(u32, usize)> = vec![(start, 0)];
+        state.insert(start, State::OnPath);
+        while let Some((node, next_edge)) = path.last_mut() {
+            let node = *node;
+            let Some(&(target, citer)) = edges.get(&node).and_then(|e| e.get(*next_edge)) else {
+                state.insert(node, State::Done);
+                path.pop();
+                continue;
+            };
+            *next_edge += 1;
+            match state.get(&target) {
+                Some(State::OnPath) => {
+                    let from = path.iter().position(|(n, _)| *n == target).unwrap_or(0);
+                    let cycle = path.iter().skip(from).map(|(n, _)| *n).collect();
+                    out.push((citer, target, cycle));
+                }
+                Some(State::Done) => {}
+                None => {
+                    state.insert(target, State::OnPath);
+                    path.push((target, 0));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Scope accepted by [`crate::relink_chunk_sources`] for the layer at `path`, if it is writable.
+pub(crate) fn writable_scope(path: &str) -> Option<&'static str> {
+    match Path::new(path).file_name().and_then(|s| s.to_str()) {
+        Some("AGENTS.local.db") => Some("local"),
+        Some("AGENTS.delta.db") => Some("delta"),
+        _ => None,
+    }
+}
+
+/// Replaces each broken chunk in a writable layer (local or delta) with a revision whose
+/// chunk-id sources point at the successor of a retracted source, or are dropped and recorded
+/// as `broken-source:<id>` otherwise. Runs until nothing is left to fix; the returned report
+/// holds the issues that remain.
+pub fn fix_provenance(
+    layers: &agentsdb_query::LayerSet,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<(ProvenanceFix, ProvenanceReport)> {
+    let mut fix = ProvenanceFix::default();
+    let mut report = verify_provenance(layers)?;
+    for _ in 0..MAX_FIX_PASSES {
+        let mut by_chunk: BTreeMap<(&str, u32), Vec<(u32, Option<u32>)>> = BTreeMap::new();
+        for issue in &report.issues {
+            let relinks = by_chunk.entry((&issue.layer, issue.id)).or_default();
+            if !relinks.iter().any(|(old, _)| *old == issue.source_id) {
+                relinks.push((issue.source_id, issue.successor));
+            }
+        }
+        let mut relinked_any = false;
+        for ((layer, id), relinks) in by_chunk {
+            if writable_scope(layer).is_none() {
+                if !fix.skipped.iter().any(|(l, i, _)| l == layer && *i == id) {
+                    fix.skipped.push((
+                        layer.to_string(),
+                        id,
+                        "not in a writable layer (local or delta)".to_string(),
+                    ));
+                }
+                continue;
+            }
+            let path = Path::new(layer);
+            let outcome = crate::relink_chunk_sources(
+                path,
+                path,
+                id,
+                &relinks,
+                "provenance relinked by verify-provenance --fix",
+                tool_name,
+                tool_version,
+            )
+            .with_context(|| format!("relink sources of {layer}:{id}"))?;
+            fix.relinked.push(RelinkedChunk {
+                layer: layer.to_string(),
+                id,
+                revision: outcome.id,
+                relinks,
+            });
+            relinked_any = true;
+        }
+        if !relinked_any {
+            break;
+        }
+        report = verify_provenance(layers)?;
+    }
+    Ok((fix, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+    use agentsdb_format::{ChunkInput, ChunkSource};
+
+    #[test]
+    fn flags_dangling_retracted_and_cyclic_sources_and_fixes_them() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path().join("AGENTS.db");
+        let local = dir.path().join("AGENTS.local.db");
+        let cites = |id: u32, sources: Vec<ChunkSource>| ChunkInput {
+            sources,
+            ..chunk(id, &format!("chunk {id}"))
+        };
+        write_layer(&base, vec![cites(1, Vec::new()), cites(2, Vec::new())])?;
+        let tombstone = ChunkInput {
+            kind: agentsdb_query::KIND_TOMBSTONE.to_string(),
+            ..cites(13, vec![ChunkSource::ChunkId(2)])
+        };
+        write_layer(
+            &local,
+            vec![
+                // Cites a missing chunk, a retracted one and a live one.
+                cites(
+                    10,
+                    vec![
+                        ChunkSource::ChunkId(99),
+                        ChunkSource::ChunkId(2),
+                        ChunkSource::ChunkId(1),
+                    ],
+                ),
+                cites(11, vec![ChunkSource::ChunkId(12)]),
+                cites(12, vec![ChunkSource::ChunkId(11)]),
+                tombstone,
+            ],
+        )?;
+        let layers = agentsdb_query::LayerSet {
+            base: Some(base.display().to_string()),
+            user: None,
+            delta: None,
+            local: Some(local.display().to_string()),
+        };
+
+        let report = verify_provenance(&layers)?;
+        assert_eq!(report.scanned, 4);
+        assert_eq!(report.references, 5);
+        let found: Vec<(u32, u32, ProvenanceProblem)> = report
+            .issues
+            .iter()
+            .map(|i| (i.id, i.source_id, i.problem))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (10, 99, ProvenanceProblem::Dangling),
+                (10, 2, ProvenanceProblem::Retracted),
+                (12, 11, ProvenanceProblem::Cycle),
+            ]
+        );
+        assert_eq!(report.issues[2].cycle, [11, 12]);
+
+        let (fix, remaining) = fix_provenance(&layers, "test", "0")?;
+        assert!(remaining.issues.is_empty(), "{:?}", remaining.issues);
+        assert_eq!(fix.relinked[0].relinks, [(99, None), (2, None)]);
+        assert!(fix.skipped.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+//! In-memory cache of query embeddings for long-running servers.
+//!
+//! Agents repeat the same searches, and with a provider backend every query embedding is a paid
+//! request. The MCP and web servers [`install_for_dir`] a cache sized by the
+//! `server.query_cache_size` and `server.query_cache_ttl_secs` settings; searches then embed
+//! queries through [`embed_query_cached`], which keys entries by the embedder's profile
+//! fingerprint and the query text, so a changed model never reuses a stale vector. Hits and
+//! misses are counted in [`crate::metrics::global`]. Until a cache is installed nothing is
+//! cached, so one-shot CLI searches behave as before.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use agentsdb_embeddings::embedder::{EmbedPurpose, Embedder};
+
+static CACHE: Mutex<Option<QueryCache>> = Mutex::new(None);
+
+/// Query embeddings by profile fingerprint and query text. Entries expire after a time to live;
+/// when full, the least recently used one is evicted.
+#[derive(Debug)]
+pub struct QueryCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<(String, String), Entry>,
+    /// Bumped on every hit and insert; the entry with the lowest `used` is the least recent.
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    embedding: Vec<f32>,
+    stored_at: Instant,
+    used: u64,
+}
+
+impl QueryCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            entries: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The embedding stored for `query` under profile `fingerprint`, unless it expired by `now`.
+    pub fn get(&mut self, fingerprint: &str, query: &str, now: Instant) -> Option<Vec<f32>> {
+        let key = (fingerprint.to_string(), query.to_string());
+        let entry = self.entries.get_mut(&key)?;
+        if now.saturating_duration_since(entry.stored_at) > self.ttl {
+            self.entries.remove(&key);
+            return None;
+        }
+        self.tick += 1;
+        entry.used = self.tick;
+        Some(entry.embedding.clone())
+    }
+
+    /// Stores `embedding` for `query` under profile `fingerprint`, making room first when full:
+    /// expired entries go, then the least recently used one.
+    pub fn insert(&mut self, fingerprint: &str, query: &str, embedding: Vec<f32>, now: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = (fingerprint.to_string(), query.to_string());
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let ttl = self.ttl;
+            self.entries
+                .retain(|_, e| now.saturating_duration_since(e.stored_at) <= ttl);
+            if self.entries.len() >= self.capacity {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, e)| e.used)
+                    .map(|(k, _)| k.clone());
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(
+            key,
+            Entry {
+                embedding,
+                stored_at: now,
+                used: self.tick,
+            },
+        );
+    }
+}
+
+fn lock() -> MutexGuard<'static, Option<QueryCache>> {
+    CACHE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Caches up to `capacity` query embeddings for this process, each reused for `ttl`. A
+/// capacity of 0 removes the cache.
+pub fn install(capacity: usize, ttl: Duration) {
+    *lock() = (capacity > 0).then(|| QueryCache::new(capacity, ttl));
+}
+
+/// Resolves the `server.query_cache_*` settings for the layers in `dir` and [`install`]s a
+/// cache accordingly.
+pub fn install_for_dir(dir: &Path) -> anyhow::Result<()> {
+    let paths = agentsdb_embeddings::config::standard_layer_paths_for_dir(dir);
+    let settings = agentsdb_embeddings::settings::resolve_settings(&paths, None)?.server();
+    install(
+        settings.query_cache_size,
+        Duration::from_secs(settings.query_cache_ttl_secs),
+    );
+    Ok(())
+}
+
+/// Embeds the search query `query` with `embedder`, reusing the embedding of an earlier call
+/// with the same profile and text while the installed cache holds it.
+pub fn embed_query_cached(embedder: &dyn Embedder, query: &str) -> anyhow::Result<Vec<f32>> {
+    let fingerprint = embedder.profile().fingerprint();
+    let cached = lock()
+        .as_mut()
+        .map(|cache| cache.get(&fingerprint, query, Instant::now()));
+    let metrics = crate::metrics::global();
+    match cached {
+        None => return embed(embedder, query),
+        Some(Some(embedding)) => {
+            metrics.inc_counter(
+                "agentsdb_query_embedding_cache_hits_total",
+                "Search queries whose embedding came from the query embedding cache.",
+                &[],
+                1,
+            );
+            return Ok(embedding);
+        }
+        Some(None) => metrics.inc_counter(
+            "agentsdb_query_embedding_cache_misses_total",
+            "Search queries embedded because the query embedding cache did not hold them.",
+            &[],
+            1,
+        ),
+    }
+
+    let embedding = embed(embedder, query)?;
+    if let Some(cache) = lock().as_mut() {
+        cache.insert(&fingerprint, query, embedding.clone(), Instant::now());
+        // The cache holds far fewer than 2^52 entries, so the f64 conversion is exact.
+        #[allow(clippy::cast_precision_loss)]
+        metrics.set_gauge(
+            "agentsdb_query_embedding_cache_entries",
+            "Query embeddings held by the query embedding cache.",
+            &[],
+            cache.len() as f64,
+        );
+    }
+    Ok(embedding)
+}
+
+fn embed(embedder: &dyn Embedder, query: &str) -> anyhow::Result<Vec<f32>> {
+    let out = embedder.embed_for(EmbedPurpose::Query, &[query.to_string()])?;
+    Ok(out
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| vec![0.0; embedder.profile().dim]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used_and_expired_entries() {
+        let start = Instant::now();
+        let mut cache = QueryCache::new(2, Duration::from_secs(60));
+        cache.insert("a", "deploy", vec![1.0], start);
+        cache.insert("a", "release", vec![2.0], start);
+        // The same text under another profile is a different entry.
+        assert_eq!(cache.get("b", "deploy", start), None);
+        assert_eq!(cache.get("a", "deploy", start), Some(vec![1.0]));
+
+        cache.insert("a", "rollback", vec![3.0], start);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("a", "release", start), None);
+        assert_eq!(cache.get("a", "deploy", start), Some(vec![1.0]));
+
+        let later = start + Duration::from_secs(61);
+        assert_eq!(cache.get("a", "rollback", later), None);
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,317 @@
+//! Read-only layers fetched over HTTP(S).
+//!
+//! A base or user layer path may be an `https://` URL, e.g. an organisation-wide base layer on
+//! an artifact server. [`resolve_remote_layers`] downloads it into the layer cache
+//! (`$AGENTSDB_LAYER_CACHE`, default `agentsdb/layers` in the user's cache directory) and points
+//! the [`LayerSet`] at the cached copy. A cached copy is revalidated with its `ETag` each time,
+//! and used as is when the server cannot be reached.
+//!
+//! A `#sha256=<hex>` fragment pins the layer file's hash; a pinned copy already in the cache is
+//! used without asking the server. A fetched layer that carries a signature must verify; trust
+//! in the signing key is checked on open, as for local layers. Requests carry
+//! `$AGENTSDB_LAYER_TOKEN`, if set, as a bearer token.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "sync")]
+use agentsdb_embeddings::signing::SignatureStatus;
+use agentsdb_query::LayerSet;
+
+use crate::util::hex_lower;
+#[cfg(feature = "sync")]
+use crate::util::now_unix_ms;
+
+/// Environment variable naming the layer cache directory.
+pub const LAYER_CACHE_ENV: &str = "AGENTSDB_LAYER_CACHE";
+
+/// Environment variable holding a bearer token for the servers remote layers are fetched from.
+/// Separate from the sync token, which only goes to sync remotes.
+pub const LAYER_TOKEN_ENV: &str = "AGENTSDB_LAYER_TOKEN";
+
+/// Whether a layer path names a remote layer. Only HTTPS URLs do.
+pub fn is_remote(path: &str) -> bool {
+    path.starts_with("https://")
+}
+
+/// Where fetched layers are cached: `$AGENTSDB_LAYER_CACHE`, else `agentsdb/layers` in
+/// `$XDG_CACHE_HOME` (`%LOCALAPPDATA%` on Windows, `~/.cache` otherwise).
+pub fn cache_dir() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(LAYER_CACHE_ENV) {
+        return Ok(PathBuf::from(dir));
+    }
+    let layers = |dir: PathBuf| dir.join("agentsdb").join("layers");
+    if let Some(dir) = std::env::var_os("XDG_CACHE_HOME") {
+        return Ok(layers(PathBuf::from(dir)));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = std::env::var_os("LOCALAPPDATA") {
+            return Ok(layers(PathBuf::from(dir)));
+        }
+    }
+    if let Some(home) = std::env::var_os("HOME") {
+        return Ok(layers(PathBuf::from(home).join(".cache")));
+    }
+    anyhow::bail!("unable to determine the layer cache dir (set {LAYER_CACHE_ENV} or HOME)")
+}
+
+/// Replaces remote base and user layer paths in `layers` with their cached copies, fetching
+/// them first. Delta and local layers are written to, so they cannot be remote.
+pub fn resolve_remote_layers(layers: &mut LayerSet) -> anyhow::Result<()> {
+    for (layer, path) in [("delta", &layers.delta), ("local", &layers.local)] {
+        if let Some(url) = path.as_deref().filter(|p| is_remote(p)) {
+            anyhow::bail!("the {layer} layer is written to and cannot be a URL ({url})");
+        }
+    }
+    let paths = [&layers.base, &layers.user, &layers.delta, &layers.local];
+    for path in paths.into_iter().flatten() {
+        if path.starts_with("http://") {
+            anyhow::bail!("remote layers must be fetched over https ({path})");
+        }
+    }
+    let remote = |path: &Option<String>| path.as_deref().is_some_and(is_remote);
+    if !remote(&layers.base) && !remote(&layers.user) {
+        return Ok(());
+    }
+    let dir = cache_dir()?;
+    for path in [&mut layers.base, &mut layers.user] {
+        let Some(url) = path.as_deref().filter(|p| is_remote(p)) else {
+            continue;
+        };
+        let cached = fetch_layer_into(&dir, url)?;
+        *path = Some(cached.to_string_lossy().into_owned());
+    }
+    Ok(())
+}
+
+/// What the cache remembers about a fetched layer (`<key>.json` next to `<key>.db`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedLayer {
+    url: String,
+    #[serde(default)]
+    etag: Option<String>,
+    sha256: String,
+    fetched_at_unix_ms: u64,
+}
+
+/// Outcome of a conditional download.
+#[cfg(feature = "sync")]
+enum Fetched {
+    NotModified,
+    Body { etag: Option<String> },
+}
+
+/// The cache key of `url`.
+fn cache_key(url: &str) -> String {
+    hex_lower(&agentsdb_embeddings::cache::sha256(url.as_bytes()))
+}
+
+/// The cache entry `key` in `dir`: the layer path, its metadata path, and the metadata when
+/// the layer itself is present.
+fn cache_entry(dir: &Path, key: &str) -> (PathBuf, PathBuf, Option<CachedLayer>) {
+    let path = dir.join(format!("{key}.db"));
+    let meta_path = dir.join(format!("{key}.json"));
+    let cached = std::fs::read(&meta_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<CachedLayer>(&bytes).ok())
+        .filter(|_| path.exists());
+    (path, meta_path, cached)
+}
+
+/// `path`, the cached copy of `url`, unless it does not match the pin.
+fn use_cached(
+    url: &str,
+    path: &Path,
+    pinned: Option<&str>,
+    cached: &CachedLayer,
+) -> anyhow::Result<PathBuf> {
+    if pinned.is_some_and(|pin| pin != cached.sha256) {
+        anyhow::bail!("cached copy of {url} does not match the pinned sha256");
+    }
+    Ok(path.to_path_buf())
+}
+
+/// Without the `sync` feature nothing is fetched, so only a cached copy can be used.
+#[cfg(not(feature = "sync"))]
+fn fetch_layer_into(dir: &Path, url: &str) -> anyhow::Result<PathBuf> {
+    let (url, pinned) = split_pin(url)?;
+    let (path, _, cached) = cache_entry(dir, &cache_key(url));
+    let Some(cached) = cached else {
+        anyhow::bail!("remote layer {url} needs a build with the `sync` feature");
+    };
+    if pinned != Some(cached.sha256.as_str()) {
+        tracing::warn!("cannot revalidate {url} without the `sync` feature, using the cached copy");
+    }
+    use_cached(url, &path, pinned, &cached)
+}
+
+/// Brings the cached copy of the layer at `url` up to date in `dir` and returns its path.
+#[cfg(feature = "sync")]
+fn fetch_layer_into(dir: &Path, url: &str) -> anyhow::Result<PathBuf> {
+    let (url, pinned) = split_pin(url)?;
+    std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    let key = cache_key(url);
+    let (path, meta_path, cached) = cache_entry(dir, &key);
+
+    let use_cached = |cached: &CachedLayer| use_cached(url, &path, pinned, cached);
+    if let Some(cached) = cached
+        .as_ref()
+        .filter(|c| pinned == Some(c.sha256.as_str()))
+    {
+        return use_cached(cached);
+    }
+
+    let part = dir.join(format!(".{key}.part"));
+    let etag = cached.as_ref().and_then(|c| c.etag.as_deref());
+    let fetched = match download(url, etag, &part) {
+        Ok(fetched) => fetched,
+        Err(err) => {
+            let _ = std::fs::remove_file(&part);
+            let Some(cached) = &cached else {
+                return Err(err.context(format!("fetch {url}")));
+            };
+            tracing::warn!("fetch {url} failed, using the cached copy: {err:#}");
+            return use_cached(cached);
+        }
+    };
+    let etag = match (fetched, &cached) {
+        (Fetched::NotModified, Some(cached)) => return use_cached(cached),
+        (Fetched::NotModified, None) => {
+            anyhow::bail!("{url} answered 304 Not Modified without a cached copy")
+        }
+        (Fetched::Body { etag }, _) => etag,
+    };
+
+    let sha256 = match verify_download(url, &part, pinned) {
+        Ok(sha256) => sha256,
+        Err(err) => {
+            let _ = std::fs::remove_file(&part);
+            return Err(err);
+        }
+    };
+    std::fs::rename(&part, &path).with_context(|| format!("replace {}", path.display()))?;
+    let meta = CachedLayer {
+        url: url.to_string(),
+        etag,
+        sha256,
+        fetched_at_unix_ms: now_unix_ms(),
+    };
+    std::fs::write(&meta_path, serde_json::to_vec_pretty(&meta)?)
+        .with_context(|| format!("write {}", meta_path.display()))?;
+    agentsdb_query::LayerPool::global().invalidate(&path);
+    Ok(path)
+}
+
+/// Splits a `#sha256=<hex>` pin off `url`.
+fn split_pin(url: &str) -> anyhow::Result<(&str, Option<&str>)> {
+    let Some((url, fragment)) = url.split_once('#') else {
+        return Ok((url, None));
+    };
+    let pin = fragment
+        .strip_prefix("sha256=")
+        .filter(|hex| {
+            hex.len() == 64 && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        })
+        .with_context(|| {
+            format!("unsupported fragment #{fragment} in {url} (expected #sha256=<lowercase hex>)")
+        })?;
+    Ok((url, Some(pin)))
+}
+
+/// Checks a downloaded layer against `pinned` and its own signature, and returns its hash.
+#[cfg(feature = "sync")]
+fn verify_download(url: &str, part: &Path, pinned: Option<&str>) -> anyhow::Result<String> {
+    let bytes = std::fs::read(part).with_context(|| format!("read {}", part.display()))?;
+    let sha256 = hex_lower(&agentsdb_embeddings::cache::sha256(&bytes));
+    if pinned.is_some_and(|pin| pin != sha256) {
+        anyhow::bail!("{url} does not match the pinned sha256 (got {sha256})");
+    }
+    let file = agentsdb_format::LayerFile::open(part)
+        .with_context(|| format!("{url} is not a valid layer"))?;
+    if let SignatureStatus::Invalid { reason, .. } =
+        agentsdb_embeddings::signing::verify_layer(&file)
+    {
+        anyhow::bail!("{url} carries an invalid signature: {reason}");
+    }
+    Ok(sha256)
+}
+
+/// `GET`s `url` into `dest`, sending `If-None-Match` when a cached copy has an `ETag`.
+#[cfg(feature = "sync")]
+fn download(url: &str, etag: Option<&str>, dest: &Path) -> anyhow::Result<Fetched> {
+    let mut request = ureq::get(url);
+    if let Some(etag) = etag {
+        request = request.set("If-None-Match", etag);
+    }
+    if let Some(token) = std::env::var(LAYER_TOKEN_ENV)
+        .ok()
+        .filter(|t| !t.is_empty())
+    {
+        request = request.set("Authorization", &format!("Bearer {token}"));
+    }
+    let response = request.call()?;
+    if response.status() == 304 {
+        return Ok(Fetched::NotModified);
+    }
+    let etag = response.header("ETag").map(str::to_string);
+    let mut out =
+        std::fs::File::create(dest).with_context(|| format!("create {}", dest.display()))?;
+    std::io::copy(&mut response.into_reader(), &mut out)
+        .with_context(|| format!("download {url}"))?;
+    Ok(Fetched::Body { etag })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_cached_layers_are_used_without_fetching() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        // Nothing listens on port 1, so every fetch fails.
+        let url = "https://127.0.0.1:1/AGENTS.db";
+        let key = cache_key(url);
+        let schema = agentsdb_format::LayerSchema {
+            dim: 4,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let layer = dir.path().join(format!("{key}.db"));
+        agentsdb_format::write_layer_atomic(&layer, &schema, &mut [], None)?;
+        let sha256 = hex_lower(&agentsdb_embeddings::cache::sha256(&std::fs::read(&layer)?));
+        let meta = CachedLayer {
+            url: url.to_string(),
+            etag: Some("\"v1\"".to_string()),
+            sha256: sha256.clone(),
+            fetched_at_unix_ms: 1,
+        };
+        std::fs::write(
+            dir.path().join(format!("{key}.json")),
+            serde_json::to_vec(&meta)?,
+        )?;
+
+        let pinned = format!("{url}#sha256={sha256}");
+        assert_eq!(fetch_layer_into(dir.path(), &pinned)?, layer);
+        // Unreachable server: fall back to the cached copy.
+        assert_eq!(fetch_layer_into(dir.path(), url)?, layer);
+        let wrong = format!("{url}#sha256={}", "0".repeat(64));
+        assert!(fetch_layer_into(dir.path(), &wrong).is_err());
+        assert!(fetch_layer_into(dir.path(), &format!("{url}#md5=abc")).is_err());
+        // No cached copy and no server.
+        assert!(fetch_layer_into(dir.path(), "https://127.0.0.1:1/other.db").is_err());
+
+        let mut layers = LayerSet {
+            base: None,
+            user: None,
+            delta: None,
+            local: Some(url.to_string()),
+        };
+        assert!(resolve_remote_layers(&mut layers).is_err());
+        layers.local = None;
+        layers.base = Some("http://127.0.0.1:1/AGENTS.db".to_string());
+        assert!(resolve_remote_layers(&mut layers).is_err());
+        Ok(())
+    }
+}
@@ -10,7 +10,9 @@ use std::path::Path;
 /// # Returns
 /// Ok(true) if chunk was found and removed, Ok(false) if chunk was not found
 pub fn remove_chunk(path: &Path, id: u32) -> anyhow::Result<bool> {
-    agentsdb_format::ensure_writable_layer_path_allow_user(path.to_str().unwrap_or_default())
+    agentsdb_format::WritePolicy::standard()
+        .allow_user()
+        .check(path)
         .context("permission check")?;
 
     // Open the file
@@ -0,0 +1,269 @@
+//! Retention policy for the local layer, which grows with every note agents write.
+//!
+//! The `retention.*` settings cap the local layer by chunk count, age and approximate size.
+//! [`apply_retention`] enforces them in two steps, so nothing disappears without a trace:
+//!
+//! 1. Chunks over a limit, oldest first, are retracted with a tombstone whose content is
+//!    [`RETENTION_REASON`]. They drop out of search at once but stay in the file.
+//! 2. Once such a tombstone is older than `retention.gc_after_days`, the tombstone and the chunk
+//!    it retracts are removed from the file.
+//!
+//! `agentsdb compact --apply-policy` runs it; `serve` and `web` also run it on startup when
+//! `retention.on_startup` is set.
+
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+use agentsdb_embeddings::settings::RetentionSettings;
+use agentsdb_format::{ChunkInput, ChunkSource, EmbeddingElementType};
+
+/// Content of the tombstones the retention policy writes; only these are garbage-collected.
+pub const RETENTION_REASON: &str = "retention policy";
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Result of [`apply_retention`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionOutcome {
+    /// Chunks retracted by this run.
+    pub retracted: Vec<u32>,
+    /// Chunks removed from the file, with their tombstones, by this run.
+    pub collected: Vec<u32>,
+    /// Chunks the layer holds for search afterwards.
+    pub kept: usize,
+}
+
+/// Enforces `policy` on the local layer at `path` as of `now_unix_ms`; see the module docs.
+/// Tombstones are written by `tool_name`/`tool_version`.
+pub fn apply_retention(
+    path: &Path,
+    policy: &RetentionSettings,
+    now_unix_ms: u64,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<RetentionOutcome> {
+    let mut outcome = RetentionOutcome::default();
+    if !path.exists() {
+        return Ok(outcome);
+    }
+    agentsdb_format::WritePolicy::standard()
+        .check_scope("local", path)
+        .context("the retention policy only applies to the local layer")?;
+
+    let file = agentsdb_format::LayerFile::open_lenient(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    let schema = agentsdb_format::schema_of(&file);
+    let metadata = file.layer_metadata_bytes().map(<[u8]>::to_vec);
+    let mut chunks = agentsdb_format::read_all_chunks(&file)
+        .with_context(|| format!("read chunks from {}", path.display()))?;
+    drop(file);
+
+    let gc_cutoff = now_unix_ms.saturating_sub(policy.gc_after_days.saturating_mul(DAY_MS));
+    let mut collect = HashSet::new();
+    for chunk in &chunks {
+        if is_retention_tombstone(chunk) && chunk.created_at_unix_ms <= gc_cutoff {
+            collect.insert(chunk.id);
+            collect.extend(retracted_ids(chunk));
+        }
+    }
+    if !collect.is_empty() {
+        outcome.collected = chunks
+            .iter()
+            .filter(|c| collect.contains(&c.id) && !is_retention_tombstone(c))
+            .map(|c| c.id)
+            .collect();
+        chunks.retain(|c| !collect.contains(&c.id));
+        agentsdb_format::WritePolicy::standard()
+            .write_layer_atomic(path, &schema, &mut chunks, metadata.as_deref())
+            .with_context(|| format!("rewrite {}", path.display()))?;
+    }
+
+    let retracted: HashSet<u32> = chunks
+        .iter()
+        .filter(|c| c.kind == agentsdb_query::KIND_TOMBSTONE)
+        .flat_map(retracted_ids)
+        .collect();
+    let mut live: Vec<&ChunkInput> = chunks
+        .iter()
+        .filter(|c| is_content(c) && !retracted.contains(&c.id))
+        .collect();
+    // Oldest first; ids break ties so runs are reproducible.
+    live.sort_by_key(|c| (c.created_at_unix_ms, c.id));
+
+    let element_bytes = match schema.element_type {
+        EmbeddingElementType::F32 => 4,
+        EmbeddingElementType::I8 => 1,
+    };
+    let size = |c: &ChunkInput| approximate_bytes(c, schema.dim, element_bytes);
+    let mut total_bytes: u64 = live.iter().map(|c| size(c)).sum();
+    let mut expired = 0;
+    for (index, chunk) in live.iter().enumerate() {
+        let remaining = live.len() - index;
+        let too_old = policy.max_age_days.is_some_and(|days| {
+            now_unix_ms.saturating_sub(chunk.created_at_unix_ms) > days.saturating_mul(DAY_MS)
+        });
+        let too_many = policy.max_chunks.is_some_and(|max| remaining > max);
+        let too_big = policy.max_bytes.is_some_and(|max| total_bytes > max);
+        if !(too_old || too_many || too_big) {
+            break;
+        }
+        total_bytes -= size(chunk);
+        expired += 1;
+    }
+    outcome.retracted = live.iter().take(expired).map(|c| c.id).collect();
+    outcome.kept = live.len() - expired;
+
+    if !outcome.retracted.is_empty() {
+        crate::retract_chunks(
+            path,
+            path,
+            "local",
+            &outcome.retracted,
+            RETENTION_REASON,
+            tool_name,
+            tool_version,
+        )
+        .with_context(|| format!("retract expired chunks in {}", path.display()))?;
+    }
+    Ok(outcome)
+}
+
+/// Applies the retention policy of the layers next to the local layer at `local` when
+/// `retention.on_startup` is set; `None` when it is not, or no limit is set.
+pub fn apply_on_startup(
+    local: &Path,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<Option<RetentionOutcome>> {
+    let dir = local.parent().unwrap_or_else(|| Path::new("."));
+    let policy = agentsdb_embeddings::settings::retention(dir).context("resolve retention")?;
+    if !policy.on_startup || !policy.has_limits() {
+        return Ok(None);
+    }
+    let now = crate::util::now_unix_ms();
+    apply_retention(local, &policy, now, tool_name, tool_version).map(Some)
+}
+
+fn is_retention_tombstone(chunk: &ChunkInput) -> bool {
+    chunk.kind == agentsdb_query::KIND_TOMBSTONE && chunk.content == RETENTION_REASON
+}
+
+fn retracted_ids(tombstone: &ChunkInput) -> impl Iterator<Item = u32> + '_ {
+    tombstone.sources.iter().filter_map(|s| match s {
+        ChunkSource::ChunkId(id) => Some(*id),
+        ChunkSource::SourceString(_) => None,
+    })
+}
+
+/// Chunks the policy counts: not tombstones, options or `meta.*` records.
+fn is_content(chunk: &ChunkInput) -> bool {
+    chunk.kind != agentsdb_query::KIND_TOMBSTONE
+        && chunk.kind != agentsdb_embeddings::config::KIND_OPTIONS
+        && !chunk.kind.starts_with("meta.")
+}
+
+/// Bytes a chunk's content, sources and embedding take up in the file, roughly.
+fn approximate_bytes(chunk: &ChunkInput, dim: u32, element_bytes: u64) -> u64 {
+    let sources: usize = chunk
+        .sources
+        .iter()
+        .map(|s| match s {
+            ChunkSource::ChunkId(_) => 4,
+            ChunkSource::SourceString(v) => v.len(),
+        })
+        .sum();
+    let text = chunk.kind.len() + chunk.author.len() + chunk.content.len() + sources;
+    u64::try_from(text)
+        .unwrap_or(u64::MAX)
+        .saturating_add(u64::from(dim) * element_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+
+    fn visible(path: &Path) -> anyhow::Result<Vec<u32>> {
+        let layers = [(
+            agentsdb_core::types::LayerId::Local,
+            agentsdb_format::LayerFile::open_lenient(path)?,
+        )];
+        let retracted = agentsdb_query::retracted_chunk_ids(&layers)?;
+        let mut out = Vec::new();
+        for c in layers[0].1.chunks() {
+            let c = c?;
+            if c.kind != agentsdb_query::KIND_TOMBSTONE && !retracted.contains_key(&c.id) {
+                out.push(c.id);
+            }
+        }
+        Ok(out)
+    }
+
+    fn apply(
+        path: &Path,
+        policy: &RetentionSettings,
+        now: u64,
+    ) -> anyhow::Result<RetentionOutcome> {
+        apply_retention(path, policy, now, "agentsdb-test", "0")
+    }
+
+    #[test]
+    fn expired_chunks_are_tombstoned_then_collected() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let local = dir.path().join("AGENTS.local.db");
+        let aged = |id, created_at_unix_ms| ChunkInput {
+            created_at_unix_ms,
+            ..chunk(id, "note")
+        };
+        let now = 100 * DAY_MS;
+        write_layer(
+            &local,
+            vec![
+                aged(1, now - 40 * DAY_MS),
+                aged(2, now - 3 * DAY_MS),
+                aged(3, now - 2 * DAY_MS),
+                aged(4, now - DAY_MS),
+            ],
+        )?;
+        let policy = RetentionSettings {
+            max_chunks: Some(2),
+            max_age_days: Some(30),
+            max_bytes: None,
+            gc_after_days: 7,
+            on_startup: false,
+        };
+
+        let outcome = apply(&local, &policy, now)?;
+        assert_eq!(outcome.retracted, [1, 2]);
+        assert!(outcome.collected.is_empty());
+        assert_eq!(outcome.kept, 2);
+        assert_eq!(visible(&local)?, [3, 4]);
+
+        // Within the grace period nothing more happens.
+        let outcome = apply(&local, &policy, now + DAY_MS)?;
+        assert!(outcome.retracted.is_empty() && outcome.collected.is_empty());
+
+        let later = crate::util::now_unix_ms() + 8 * DAY_MS;
+        let relaxed = RetentionSettings {
+            max_age_days: None,
+            ..policy
+        };
+        let outcome = apply(&local, &relaxed, later)?;
+        let mut collected = outcome.collected;
+        collected.sort_unstable();
+        assert_eq!(collected, [1, 2]);
+        let file = agentsdb_format::LayerFile::open(&local)?;
+        let ids: Vec<u32> = agentsdb_format::read_all_chunks(&file)?
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, [3, 4]);
+
+        let delta = dir.path().join("AGENTS.delta.db");
+        write_layer(&delta, vec![chunk(1, "note")])?;
+        assert!(apply(&delta, &policy, now).is_err());
+        Ok(())
+    }
+}
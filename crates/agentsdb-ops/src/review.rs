@@ -0,0 +1,266 @@
+//! Confidence decay and re-validation for `agentsdb review`: chunks that have not been revised
+//! for a while lose confidence, and those that drop below a threshold are queued for a human to
+//! confirm (restoring their confidence) or remove.
+
+use agentsdb_core::types::LayerId;
+use agentsdb_format::LayerFile;
+use anyhow::Context;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::util::{now_unix_ms, truncate_preview};
+
+/// Characters of content shown per queued chunk.
+const PREVIEW_CHARS: usize = 200;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// A visible chunk whose confidence is below the review threshold.
+#[derive(Debug, Clone, Serialize)]
+pub struct StaleChunk {
+    /// Path of the layer holding the chunk, as given in the [`agentsdb_query::LayerSet`].
+    pub layer: String,
+    pub id: u32,
+    pub kind: String,
+    pub confidence: f32,
+    pub created_at_unix_ms: u64,
+    pub content_preview: String,
+    /// Whether the chunk can be re-validated in place (it lives in a local or delta layer).
+    pub writable: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DecayOptions {
+    /// Chunks created (or last revised) longer ago than this lose confidence.
+    pub max_age_ms: u64,
+    /// Multiplier applied to the confidence of each such chunk, in `0..=1`.
+    pub factor: f32,
+}
+
+impl DecayOptions {
+    /// Options for chunks older than `days`.
+    pub const fn from_days(days: u64, factor: f32) -> Self {
+        Self {
+            max_age_ms: days.saturating_mul(DAY_MS),
+            factor,
+        }
+    }
+}
+
+/// A chunk that [`decay_confidence`] replaced with a lower-confidence revision.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecayedChunk {
+    pub layer: String,
+    pub id: u32,
+    pub revision: u32,
+    pub from: f32,
+    pub to: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DecayReport {
+    /// Number of visible chunks checked.
+    pub scanned: usize,
+    pub decayed: Vec<DecayedChunk>,
+    /// `(layer, id, reason)` of old chunks left in place.
+    pub skipped: Vec<(String, u32, String)>,
+}
+
+/// A visible chunk of the layer stack (not retracted, not a tombstone, options or `meta.*`
+/// record), latest version per id.
+pub(crate) struct Visible {
+    pub(crate) layer_id: LayerId,
+    pub(crate) layer: String,
+    pub(crate) id: u32,
+    pub(crate) kind: String,
+    pub(crate) confidence: f32,
+    pub(crate) created_at_unix_ms: u64,
+    pub(crate) content: String,
+}
+
+pub(crate) fn visible_chunks(layers: &agentsdb_query::LayerSet) -> anyhow::Result<Vec<Visible>> {
+    let mut opened = Vec::new();
+    let mut paths = Vec::new();
+    for (layer_id, path) in [
+        (LayerId::Local, &layers.local),
+        (LayerId::User, &layers.user),
+        (LayerId::Delta, &layers.delta),
+        (LayerId::Base, &layers.base),
+    ] {
+        if let Some(path) = path {
+            let file = LayerFile::open_lenient(path).with_context(|| format!("open {path}"))?;
+            opened.push((layer_id, file));
+            paths.push(path);
+        }
+    }
+    let retracted = agentsdb_query::retracted_chunk_ids(&opened)?;
+    let mut out = Vec::new();
+    for ((layer_id, file), path) in opened.iter().zip(paths) {
+        for chunk in file.latest_chunks()? {
+            if chunk.kind == agentsdb_query::KIND_TOMBSTONE
+                || chunk.kind == agentsdb_embeddings::config::KIND_OPTIONS
+                || chunk.kind.starts_with("meta.")
+                || agentsdb_query::is_retracted(&retracted, *layer_id, chunk.id)
+            {
+                continue;
+            }
+            out.push(Visible {
+                layer_id: *layer_id,
+                layer: path.clone(),
+                id: chunk.id,
+                kind: chunk.kind.to_string(),
+                confidence: chunk.confidence,
+                created_at_unix_ms: chunk.created_at_unix_ms,
+                content: chunk.content.to_string(),
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Visible chunks of `layers` with confidence below `threshold`, oldest first.
+pub fn stale_queue(
+    layers: &agentsdb_query::LayerSet,
+    threshold: f32,
+) -> anyhow::Result<Vec<StaleChunk>> {
+    let mut out: Vec<StaleChunk> = visible_chunks(layers)?
+        .into_iter()
+        .filter(|c| c.confidence < threshold)
+        .map(|c| StaleChunk {
+            writable: crate::provenance::writable_scope(&c.layer).is_some(),
+            content_preview: truncate_preview(&c.content, PREVIEW_CHARS),
+            layer: c.layer,
+            id: c.id,
+            kind: c.kind,
+            confidence: c.confidence,
+            created_at_unix_ms: c.created_at_unix_ms,
+        })
+        .collect();
+    out.sort_by_key(|c| (c.created_at_unix_ms, c.id));
+    Ok(out)
+}
+
+/// Replaces each visible chunk in a writable layer (local or delta) that is older than
+/// `options.max_age_ms` with a revision whose confidence is scaled by `options.factor`. The
+/// revision is dated now, so a chunk decays at most once per `max_age_ms`.
+pub fn decay_confidence(
+    layers: &agentsdb_query::LayerSet,
+    options: DecayOptions,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<DecayReport> {
+    if !(0.0..=1.0).contains(&options.factor) {
+        anyhow::bail!("decay factor must be within 0..=1");
+    }
+    let cutoff = now_unix_ms().saturating_sub(options.max_age_ms);
+    let chunks = visible_chunks(layers)?;
+    let mut report = DecayReport {
+        scanned: chunks.len(),
+        ..DecayReport::default()
+    };
+    for chunk in chunks {
+        if chunk.created_at_unix_ms >= cutoff || chunk.confidence <= 0.0 {
+            continue;
+        }
+        if crate::provenance::writable_scope(&chunk.layer).is_none() {
+            report.skipped.push((
+                chunk.layer,
+                chunk.id,
+                "not in a writable layer (local or delta)".to_string(),
+            ));
+            continue;
+        }
+        let to = chunk.confidence * options.factor;
+        let path = Path::new(&chunk.layer);
+        let outcome = crate::write::revise_confidence(
+            path,
+            path,
+            chunk.id,
+            to,
+            "confidence decayed by review --decay",
+            tool_name,
+            tool_version,
+        )
+        .with_context(|| format!("decay {}:{}", chunk.layer, chunk.id))?;
+        report.decayed.push(DecayedChunk {
+            layer: chunk.layer,
+            id: chunk.id,
+            revision: outcome.id,
+            from: chunk.confidence,
+            to,
+        });
+    }
+    Ok(report)
+}
+
+/// Marks chunk `id` of the writable layer at `path` as re-validated by a human: it is replaced
+/// with a revision (dated now) carrying `confidence`.
+pub fn revalidate_chunk(
+    path: &Path,
+    id: u32,
+    confidence: f32,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<crate::write::SupersedeOutcome> {
+    if !(0.0..=1.0).contains(&confidence) {
+        anyhow::bail!("confidence must be within 0..=1");
+    }
+    crate::write::revise_confidence(
+        path,
+        path,
+        id,
+        confidence,
+        "re-validated by review",
+        tool_name,
+        tool_version,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+    use agentsdb_format::ChunkInput;
+
+    #[test]
+    fn decays_old_chunks_and_queues_them_until_revalidated() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path().join("AGENTS.db");
+        let local = dir.path().join("AGENTS.local.db");
+        let now = now_unix_ms();
+        let aged = |id: u32, confidence: f32, created_at_unix_ms: u64| ChunkInput {
+            confidence,
+            created_at_unix_ms,
+            ..chunk(id, &format!("chunk {id}"))
+        };
+        write_layer(&base, vec![aged(1, 0.9, 0)])?;
+        write_layer(&local, vec![aged(10, 0.6, 0), aged(11, 0.6, now)])?;
+        let layers = agentsdb_query::LayerSet {
+            base: Some(base.display().to_string()),
+            user: None,
+            delta: None,
+            local: Some(local.display().to_string()),
+        };
+
+        assert!(stale_queue(&layers, 0.5)?.is_empty());
+        let report = decay_confidence(&layers, DecayOptions::from_days(30, 0.5), "test", "0")?;
+        assert_eq!(report.decayed.len(), 1, "only the old local chunk decays");
+        assert_eq!(report.decayed[0].id, 10);
+        assert_eq!(report.skipped.len(), 1, "the old base chunk is read-only");
+
+        let queue = stale_queue(&layers, 0.5)?;
+        assert_eq!(queue.len(), 1);
+        let revision = queue[0].id;
+        assert_eq!(revision, report.decayed[0].revision);
+        assert!((queue[0].confidence - 0.3).abs() < 1e-6);
+        assert!(queue[0].writable);
+
+        // The revision is dated now, so a second pass leaves it alone.
+        let again = decay_confidence(&layers, DecayOptions::from_days(30, 0.5), "test", "0")?;
+        assert!(again.decayed.is_empty());
+
+        revalidate_chunk(&local, revision, 1.0, "test", "0")?;
+        assert!(stale_queue(&layers, 0.5)?.is_empty());
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::Context;
 use agentsdb_core::types::{SearchFilters, SearchResult};
 use agentsdb_embeddings::layer_metadata::ensure_layer_metadata_compatible_with_embedder;
-use agentsdb_query::{LayerSet, SearchMode, SearchOptions, SearchQuery};
+use agentsdb_query::{LayerPool, LayerSet, SearchMode, SearchOptions, SearchQuery};
 
 /// Configuration for a search operation
 #[derive(Debug, Clone)]
@@ -14,10 +14,20 @@ pub struct SearchConfig {
     pub k: usize,
     /// Filter by chunk kinds (empty = no filter)
     pub kinds: Vec<String>,
+    /// Only chunks in this namespace (None = every namespace)
+    pub namespace: Option<String>,
+    /// Only chunks in this language, e.g. `de` (None = every language)
+    pub language: Option<String>,
     /// Whether to use ANN index if available
     pub use_index: bool,
     /// Search mode: semantic only or hybrid (lexical + semantic)
     pub mode: SearchMode,
+    /// Reuse the results of an identical search over unchanged layers
+    /// (see [`agentsdb_query::ResultCache`])
+    pub cache_results: bool,
+    /// Reuse the selection pass cached on disk by an earlier process for the same layer
+    /// contents (see [`agentsdb_query::SearchOptions::disk_cache`])
+    pub disk_cache: bool,
 }
 
 /// Perform a search across opened layers
@@ -30,6 +40,7 @@ pub struct SearchConfig {
 /// 5. Validates layer metadata vs embedder
 /// 6. Executes search via agentsdb_query
 /// 7. Returns ranked results
+#[tracing::instrument(level = "debug", skip_all, fields(k = config.k, mode = ?config.mode))]
 pub fn search_layers(
     layers: &LayerSet,
     config: SearchConfig,
@@ -43,15 +54,6 @@ pub fn search_layers(
         _ => {}
     }
 
-    // Open layers
-    let opened = layers.open().context("open layers")?;
-    if opened.is_empty() {
-        anyhow::bail!("no layers provided");
-    }
-
-    // Get dimension from first layer
-    let dim = opened[0].1.embedding_dim();
-
     // Get directory from base layer path (or first available layer)
     // All layers should be in the same directory, and we need this to read immutable options from AGENTS.db
     let dir = layers
@@ -63,6 +65,18 @@ pub fn search_layers(
         .and_then(|p| std::path::Path::new(p).parent())
         .unwrap_or_else(|| std::path::Path::new("."));
 
+    // Open layers, checking signatures the trust settings require
+    let trust = agentsdb_embeddings::settings::trust_policy(dir).context("read trust settings")?;
+    let opened = layers
+        .open_trusted(LayerPool::global(), &trust)
+        .context("open layers")?;
+    if opened.is_empty() {
+        anyhow::bail!("no layers provided");
+    }
+
+    // Get dimension from first layer
+    let dim = opened[0].1.embedding_dim();
+
     // Get immutable embedding options from base layer only
     let options = agentsdb_embeddings::config::get_immutable_embedding_options(dir)
         .context("get immutable embedding options")?;
@@ -102,9 +116,8 @@ pub fn search_layers(
                 }
             }
 
-            // Embed the query
-            let out = embedder.embed(&[q.clone()])?;
-            out.into_iter().next().unwrap_or_else(|| vec![0.0; dim])
+            // Embed the query, or reuse a cached embedding of it
+            crate::query_cache::embed_query_cached(embedder.as_ref(), q)?
         }
         (None, Some(vec)) => {
             // Use pre-computed vector
@@ -126,6 +139,9 @@ pub fn search_layers(
         k: config.k,
         filters: SearchFilters {
             kinds: config.kinds,
+            namespace: config.namespace,
+            language: config.language,
+            min_confidence: None,
         },
         query_text: config.query.clone(),
     };
@@ -137,9 +153,12 @@ pub fn search_layers(
         SearchOptions {
             use_index: config.use_index,
             mode: config.mode,
+            cache_results: config.cache_results,
+            disk_cache: config.disk_cache,
         },
     )
     .context("search")?;
+    tracing::debug!(results = results.len(), "search done");
 
     Ok(results)
 }
@@ -154,7 +173,9 @@ pub fn embed_query(layers: &LayerSet, query: &str) -> anyhow::Result<Vec<f32>> {
     }
 
     // Open layers
-    let opened = layers.open().context("open layers")?;
+    let opened = layers
+        .open_pooled(LayerPool::global())
+        .context("open layers")?;
     if opened.is_empty() {
         anyhow::bail!("no layers provided");
     }
@@ -202,7 +223,6 @@ pub fn embed_query(layers: &LayerSet, query: &str) -> anyhow::Result<Vec<f32>> {
         }
     }
 
-    // Embed the query
-    let out = embedder.embed(&[query.to_string()])?;
-    Ok(out.into_iter().next().unwrap_or_else(|| vec![0.0; dim]))
+    // Embed the query, or reuse a cached embedding of it
+    crate::query_cache::embed_query_cached(embedder.as_ref(), query)
 }
@@ -0,0 +1,388 @@
+//! Chunk details for `agentsdb show`: every copy of an id across the layer stack, which one
+//! wins precedence, and where its chunk-id sources lead.
+
+use agentsdb_core::types::LayerId;
+use agentsdb_format::{LayerFile, SourceRef};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Deepest chain of chunk-id sources the provenance trace follows.
+const MAX_TRACE_DEPTH: usize = 8;
+
+/// How a copy of a chunk fares in the layer stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyStatus {
+    /// Returned by searches and reads.
+    Visible,
+    /// Shadowed by the copy in a higher-precedence layer.
+    Hidden,
+    /// Hidden by a tombstone in this or a higher-precedence layer.
+    Retracted,
+}
+
+/// The latest version of a chunk id in one layer.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkCopy {
+    /// Logical layer name (`local`, `user`, `delta` or `base`).
+    pub layer: String,
+    pub path: String,
+    pub status: CopyStatus,
+    pub kind: String,
+    pub author: String,
+    /// Writer's identity, from the copy's `identity:<label>` source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    pub confidence: f32,
+    pub created_at_unix_ms: u64,
+    /// L2 norm of the stored embedding.
+    pub embedding_norm: f32,
+    pub sources: Vec<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceProblem {
+    /// No layer holds the cited id.
+    Missing,
+    /// Every copy of the cited id is retracted.
+    Retracted,
+    /// The id was already visited higher up the trace.
+    Cycle,
+}
+
+/// One node of the provenance trace, in depth-first order.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceNode {
+    pub depth: usize,
+    pub id: u32,
+    /// Layer of the copy the trace followed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_preview: Option<String>,
+    /// String sources (files, URLs, ...) of the copy.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub problem: Option<TraceProblem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkReport {
+    pub id: u32,
+    /// Layer whose copy wins precedence (absent when every copy is retracted).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winner: Option<String>,
+    /// Layer holding the tombstone that retracts the id, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retracted_by: Option<String>,
+    /// Visible revision that supersedes this chunk (`supersedes:<id>` source), if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<u32>,
+    /// Copies by layer precedence, highest first.
+    pub copies: Vec<ChunkCopy>,
+    pub trace: Vec<TraceNode>,
+}
+
+fn layer_name(layer: LayerId) -> String {
+    format!("{layer:?}").to_ascii_lowercase()
+}
+
+fn embedding_norm(file: &LayerFile, row: u32) -> anyhow::Result<f32> {
+    let mut buf = vec![0.0; file.embedding_dim()];
+    file.read_embedding_row_f32(row, &mut buf)?;
+    Ok(buf.iter().map(|v| v * v).sum::<f32>().sqrt())
+}
+
+/// A copy as found in one layer, before its status is known.
+struct Found {
+    layer: LayerId,
+    copy: ChunkCopy,
+    chunk_sources: Vec<u32>,
+}
+
+/// Opened layers of a [`agentsdb_query::LayerSet`], with retractions resolved.
+struct Stack {
+    opened: Vec<(LayerId, LayerFile)>,
+    paths: Vec<String>,
+    retracted: HashMap<u32, LayerId>,
+}
+
+impl Stack {
+    fn open(layers: &agentsdb_query::LayerSet) -> anyhow::Result<Self> {
+        let mut opened = Vec::new();
+        let mut paths = Vec::new();
+        for (layer_id, path) in [
+            (LayerId::Local, &layers.local),
+            (LayerId::User, &layers.user),
+            (LayerId::Delta, &layers.delta),
+            (LayerId::Base, &layers.base),
+        ] {
+            if let Some(path) = path {
+                let file = LayerFile::open_lenient(path).with_context(|| format!("open {path}"))?;
+                opened.push((layer_id, file));
+                paths.push(path.clone());
+            }
+        }
+        let retracted = agentsdb_query::retracted_chunk_ids(&opened)?;
+        Ok(Self {
+            opened,
+            paths,
+            retracted,
+        })
+    }
+
+    /// Latest non-tombstone version of `id` in each layer, highest precedence first.
+    fn copies(&self, id: u32) -> anyhow::Result<Vec<Found>> {
+        let mut out = Vec::new();
+        for ((layer, file), path) in self.opened.iter().zip(&self.paths) {
+            if file.has_chunk_ids_section() && !file.contains_chunk_id(id)? {
+                continue;
+            }
+            let mut latest = None;
+            for chunk in file.chunks() {
+                let chunk = chunk?;
+                if chunk.id == id && chunk.kind != agentsdb_query::KIND_TOMBSTONE {
+                    latest = Some(chunk);
+                }
+            }
+            let Some(chunk) = latest else {
+                continue;
+            };
+            let mut sources = Vec::new();
+            let mut chunk_sources = Vec::new();
+            for source in file.sources_for(chunk.rel_start, chunk.rel_count)? {
+                match source {
+                    SourceRef::ChunkId(v) => {
+                        chunk_sources.push(v);
+                        sources.push(format!("chunk:{v}"));
+                    }
+                    SourceRef::String(v) => sources.push(v.to_string()),
+                }
+            }
+            let status = if agentsdb_query::is_retracted(&self.retracted, *layer, id) {
+                CopyStatus::Retracted
+            } else if out
+                .iter()
+                .any(|f: &Found| f.copy.status == CopyStatus::Visible)
+            {
+                CopyStatus::Hidden
+            } else {
+                CopyStatus::Visible
+            };
+            out.push(Found {
+                layer: *layer,
+                copy: ChunkCopy {
+                    layer: layer_name(*layer),
+                    path: path.clone(),
+                    status,
+                    kind: chunk.kind.to_string(),
+                    author: chunk.author.to_string(),
+                    identity: sources.iter().find_map(|s| {
+                        s.strip_prefix(agentsdb_query::IDENTITY_SOURCE_PREFIX)
+                            .map(str::to_string)
+                    }),
+                    confidence: chunk.confidence,
+                    created_at_unix_ms: chunk.created_at_unix_ms,
+                    embedding_norm: embedding_norm(file, chunk.embedding_row)?,
+                    sources,
+                    content: chunk.content.to_string(),
+                },
+                chunk_sources,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Visible chunk whose `supersedes:<id>` source names `id`.
+    fn superseded_by(&self, id: u32) -> anyhow::Result<Option<u32>> {
+        let marker = format!("{}{id}", agentsdb_query::SUPERSEDES_SOURCE_PREFIX);
+        for (layer, file) in &self.opened {
+            for chunk in file.latest_chunks()? {
+                if chunk.kind == agentsdb_query::KIND_TOMBSTONE
+                    || agentsdb_query::is_retracted(&self.retracted, *layer, chunk.id)
+                {
+                    continue;
+                }
+                let sources = file.sources_for(chunk.rel_start, chunk.rel_count)?;
+                if sources.contains(&SourceRef::String(&marker)) {
+                    return Ok(Some(chunk.id));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Depth-first walk of chunk-id sources from `id`, following the winning copy of each id
+    /// (or the highest-precedence retracted one).
+    fn trace(&self, id: u32) -> anyhow::Result<Vec<TraceNode>> {
+        let mut out = Vec::new();
+        let mut on_path: Vec<u32> = Vec::new();
+        let mut stack = vec![(id, 0usize)];
+        let mut visited = HashSet::new();
+        while let Some((id, depth)) = stack.pop() {
+            on_path.truncate(depth);
+            let mut node = TraceNode {
+                depth,
+                id,
+                layer: None,
+                kind: None,
+                content_preview: None,
+                sources: Vec::new(),
+                problem: None,
+            };
+            if on_path.contains(&id) {
+                node.problem = Some(TraceProblem::Cycle);
+                out.push(node);
+                continue;
+            }
+            let copies = self.copies(id)?;
+            let followed = copies
+                .iter()
+                .find(|f| f.copy.status == CopyStatus::Visible)
+                .or_else(|| copies.first());
+            let Some(found) = followed else {
+                node.problem = Some(TraceProblem::Missing);
+                out.push(node);
+                continue;
+            };
+            if found.copy.status == CopyStatus::Retracted {
+                node.problem = Some(TraceProblem::Retracted);
+            }
+            node.layer = Some(layer_name(found.layer));
+            node.kind = Some(found.copy.kind.clone());
+            node.content_preview = Some(crate::util::truncate_preview(&found.copy.content, 80));
+            node.sources = found
+                .copy
+                .sources
+                .iter()
+                .filter(|s| !s.starts_with("chunk:"))
+                .cloned()
+                .collect();
+            out.push(node);
+            on_path.push(id);
+            // Shared ancestors are listed once, under the first chunk citing them.
+            if depth + 1 >= MAX_TRACE_DEPTH || !visited.insert(id) {
+                continue;
+            }
+            for source in found.chunk_sources.iter().rev() {
+                stack.push((*source, depth + 1));
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Every copy of chunk `id` in `layers`, its precedence and retraction status, and its
+/// provenance trace. Fails if no layer holds the id.
+pub fn show_chunk(layers: &agentsdb_query::LayerSet, id: u32) -> anyhow::Result<ChunkReport> {
+    let stack = Stack::open(layers)?;
+    let copies = stack.copies(id)?;
+    if copies.is_empty() {
+        anyhow::bail!("chunk {id} not found in any layer");
+    }
+    Ok(ChunkReport {
+        id,
+        winner: copies
+            .iter()
+            .find(|f| f.copy.status == CopyStatus::Visible)
+            .map(|f| layer_name(f.layer)),
+        retracted_by: stack.retracted.get(&id).map(|l| layer_name(*l)),
+        superseded_by: stack.superseded_by(id)?,
+        trace: stack.trace(id)?,
+        copies: copies.into_iter().map(|f| f.copy).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+    use agentsdb_format::{ChunkInput, ChunkSource};
+
+    #[test]
+    fn reports_copies_precedence_and_trace() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        // Norm 5, so the reported embedding norm is easy to check.
+        let cites = |id: u32, content: &str, sources: Vec<ChunkSource>| ChunkInput {
+            embedding: vec![3.0, 4.0],
+            sources,
+            ..chunk(id, content)
+        };
+        let base = dir.path().join("AGENTS.db");
+        let local = dir.path().join("AGENTS.local.db");
+        write_layer(
+            &base,
+            vec![
+                cites(
+                    1,
+                    "root",
+                    vec![ChunkSource::SourceString("README.md:1".into())],
+                ),
+                cites(2, "base copy", vec![ChunkSource::ChunkId(1)]),
+                cites(3, "loop", vec![ChunkSource::ChunkId(3)]),
+            ],
+        )?;
+        write_layer(
+            &local,
+            vec![
+                cites(
+                    2,
+                    "local copy",
+                    vec![ChunkSource::ChunkId(1), ChunkSource::ChunkId(9)],
+                ),
+                ChunkInput {
+                    kind: agentsdb_query::KIND_TOMBSTONE.to_string(),
+                    ..cites(4, "gone", vec![ChunkSource::ChunkId(3)])
+                },
+            ],
+        )?;
+        let layers = agentsdb_query::LayerSet {
+            base: Some(base.display().to_string()),
+            user: None,
+            delta: None,
+            local: Some(local.display().to_string()),
+        };
+
+        let report = show_chunk(&layers, 2)?;
+        assert_eq!(report.winner.as_deref(), Some("local"));
+        let statuses: Vec<(&str, CopyStatus)> = report
+            .copies
+            .iter()
+            .map(|c| (c.layer.as_str(), c.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [("local", CopyStatus::Visible), ("base", CopyStatus::Hidden)]
+        );
+        assert!((report.copies[0].embedding_norm - 5.0).abs() < 1e-6);
+        let trace: Vec<(usize, u32, Option<TraceProblem>)> = report
+            .trace
+            .iter()
+            .map(|n| (n.depth, n.id, n.problem))
+            .collect();
+        assert_eq!(
+            trace,
+            [
+                (0, 2, None),
+                (1, 1, None),
+                (1, 9, Some(TraceProblem::Missing))
+            ]
+        );
+        assert_eq!(report.trace[1].sources, ["README.md:1"]);
+
+        let retracted = show_chunk(&layers, 3)?;
+        assert!(retracted.winner.is_none());
+        assert_eq!(retracted.retracted_by.as_deref(), Some("local"));
+        assert_eq!(retracted.copies[0].status, CopyStatus::Retracted);
+        assert_eq!(retracted.trace[1].problem, Some(TraceProblem::Cycle));
+
+        assert!(show_chunk(&layers, 42).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+//! Signing layers and checking their signatures.
+//!
+//! See [`agentsdb_embeddings::signing`] for what a signature covers and how readers decide which
+//! layers to trust.
+
+use anyhow::Context;
+use serde::Serialize;
+use std::path::Path;
+
+use agentsdb_embeddings::config::get_immutable_embedding_options;
+use agentsdb_embeddings::layer_metadata::{LayerMetadataV1, LayerSignature};
+use agentsdb_embeddings::signing::{
+    content_sha256_of, verify_layer, LayerSigningKey, SignatureStatus, TrustPolicy,
+};
+use agentsdb_format::{LayerFile, WritePolicy};
+use agentsdb_query::LayerSet;
+
+/// Signs the layer at `path` with `key` and rewrites it with the signature in its metadata.
+///
+/// A layer without metadata gets metadata for the embedder its directory's options select. Any
+/// earlier signature is replaced.
+pub fn sign_layer(
+    path: &Path,
+    key: &LayerSigningKey,
+    policy: &WritePolicy,
+) -> anyhow::Result<LayerSignature> {
+    policy.check(path).context("permission check")?;
+
+    let file = LayerFile::open(path).with_context(|| format!("open {}", path.display()))?;
+    let metadata = match file.layer_metadata_bytes() {
+        Some(bytes) => LayerMetadataV1::from_json_bytes(bytes)?,
+        None => {
+            let dir = path.parent().unwrap_or_else(|| Path::new("."));
+            let embedder = get_immutable_embedding_options(dir)
+                .context("get immutable embedding options")?
+                .into_embedder(file.embedding_dim())
+                .context("resolve embedder from options")?;
+            LayerMetadataV1::new(embedder.profile().clone())
+                .with_embedder_metadata(embedder.metadata())
+        }
+    };
+    let schema = agentsdb_format::schema_of(&file);
+    let mut chunks = agentsdb_format::read_all_chunks(&file).context("read chunks")?;
+    drop(file);
+
+    let metadata = metadata.with_signature(None);
+    let digest = content_sha256_of(&schema, Some(&metadata), &chunks)?;
+    let signature = key.sign_digest(&digest);
+    let json = metadata
+        .with_signature(Some(signature.clone()))
+        .to_json_bytes()?;
+    policy
+        .write_layer_atomic(path, &schema, &mut chunks, Some(&json))
+        .with_context(|| format!("rewrite {}", path.display()))?;
+
+    // The writer may normalize what it was given; make sure the signature covers what landed.
+    let file = LayerFile::open(path).with_context(|| format!("open {}", path.display()))?;
+    match verify_layer(&file) {
+        SignatureStatus::Valid { .. } => Ok(signature),
+        other => anyhow::bail!("signed layer {} does not verify: {other:?}", path.display()),
+    }
+}
+
+/// The signature check of one layer, as reported by `agentsdb verify --signature`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureReport {
+    pub layer: &'static str,
+    pub path: String,
+    #[serde(flatten)]
+    pub status: SignatureStatus,
+    /// Whether the layer is validly signed by a key `trust` lists.
+    pub trusted: bool,
+    /// Whether readers refuse to open the layer under the trust settings.
+    pub refused: bool,
+}
+
+/// Checks the signature of every layer in `layers` against `trust`.
+pub fn verify_signatures(
+    layers: &LayerSet,
+    trust: &TrustPolicy,
+) -> anyhow::Result<Vec<SignatureReport>> {
+    let mut out = Vec::new();
+    for (layer, path) in [
+        ("base", &layers.base),
+        ("user", &layers.user),
+        ("delta", &layers.delta),
+        ("local", &layers.local),
+    ] {
+        let Some(path) = path.as_deref() else {
+            continue;
+        };
+        let file = LayerFile::open(path).with_context(|| format!("open {path}"))?;
+        let status = verify_layer(&file);
+        let trusted = matches!(
+            &status,
+            SignatureStatus::Valid { public_key } if trust.trusts_key(public_key)
+        );
+        out.push(SignatureReport {
+            layer,
+            path: path.to_string(),
+            status,
+            trusted,
+            refused: !trusted
+                && trust.covers(layer)
+                && trust.mode == agentsdb_embeddings::signing::TrustMode::Enforce,
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentsdb_embeddings::signing::TrustMode;
+
+    #[test]
+    fn signed_layers_verify_until_they_change() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path().join("AGENTS.db");
+        let schema = agentsdb_format::LayerSchema {
+            dim: 4,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let mut chunks = vec![agentsdb_format::ChunkInput {
+            id: 1,
+            kind: "note".to_string(),
+            content: "use sqlite".to_string(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 1,
+            embedding: vec![0.0; 4],
+            sources: Vec::new(),
+        }];
+        agentsdb_format::write_layer_atomic(&base, &schema, &mut chunks, None)?;
+
+        let key = LayerSigningKey::generate()?;
+        assert!(sign_layer(&base, &key, &WritePolicy::standard()).is_err());
+        let policy = WritePolicy::standard().allow_base();
+        let signature = sign_layer(&base, &key, &policy)?;
+        assert_eq!(signature.public_key, key.public_key());
+
+        let layers = LayerSet {
+            base: Some(base.display().to_string()),
+            user: None,
+            delta: None,
+            local: None,
+        };
+        let mut trust = TrustPolicy {
+            mode: TrustMode::Enforce,
+            public_keys: Vec::new(),
+            layers: vec!["base".to_string()],
+        };
+        let report = &verify_signatures(&layers, &trust)?[0];
+        assert!(matches!(report.status, SignatureStatus::Valid { .. }));
+        assert!(!report.trusted && report.refused);
+
+        trust.public_keys.push(key.public_key());
+        let report = &verify_signatures(&layers, &trust)?[0];
+        assert!(report.trusted && !report.refused);
+
+        chunks[0].content = "use postgres".to_string();
+        let file = LayerFile::open(&base)?;
+        let json = file.layer_metadata_bytes().map(<[u8]>::to_vec);
+        drop(file);
+        agentsdb_format::write_layer_atomic(&base, &schema, &mut chunks, json.as_deref())?;
+        let report = &verify_signatures(&layers, &trust)?[0];
+        assert!(matches!(report.status, SignatureStatus::Invalid { .. }));
+        assert!(report.refused);
+        Ok(())
+    }
+}
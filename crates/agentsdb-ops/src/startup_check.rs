@@ -0,0 +1,69 @@
+//! Timed startup self-checks, shared by `agentsdb serve --check` and `agentsdb web --check`.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// A single timed step of a startup self-check.
+#[derive(Debug, Serialize)]
+pub struct CheckStep {
+    pub step: &'static str,
+    pub ok: bool,
+    pub elapsed_ms: f64,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub detail: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The effective configuration and timing for each initialization step of a server.
+#[derive(Debug, Serialize)]
+pub struct CheckReport {
+    pub server: &'static str,
+    pub version: &'static str,
+    pub ok: bool,
+    pub total_ms: f64,
+    pub steps: Vec<CheckStep>,
+}
+
+impl CheckReport {
+    /// An empty, passing report for `server` (`mcp`, `web`).
+    pub const fn new(server: &'static str) -> Self {
+        Self {
+            server,
+            version: env!("CARGO_PKG_VERSION"),
+            ok: true,
+            total_ms: 0.0,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Runs `f` as a named step, unless an earlier step already failed.
+    pub fn step<T>(
+        &mut self,
+        name: &'static str,
+        f: impl FnOnce() -> anyhow::Result<(T, Value)>,
+    ) -> Option<T> {
+        if !self.ok {
+            return None;
+        }
+        let start = std::time::Instant::now();
+        let res = f();
+        let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+        self.total_ms += elapsed_ms;
+        let (out, detail, error) = match res {
+            Ok((out, detail)) => (Some(out), detail, None),
+            Err(e) => {
+                self.ok = false;
+                (None, Value::Null, Some(format!("{e:#}")))
+            }
+        };
+        self.steps.push(CheckStep {
+            step: name,
+            ok: error.is_none(),
+            elapsed_ms,
+            detail,
+            error,
+        });
+        out
+    }
+}
@@ -0,0 +1,297 @@
+//! Per-layer statistics for memory hygiene reviews, used by `agentsdb stats`.
+
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::util::{content_sha256_hex, now_unix_ms};
+
+/// Upper bounds of the confidence histogram buckets; the last bucket also holds 1.0.
+const CONFIDENCE_BUCKETS: [f32; 5] = [0.2, 0.4, 0.6, 0.8, 1.0];
+
+/// Age histogram buckets as (label, upper bound in days).
+const AGE_BUCKETS: [(&str, u64); 5] = [
+    ("1d", 1),
+    ("7d", 7),
+    ("30d", 30),
+    ("90d", 90),
+    ("365d", 365),
+];
+
+const MS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Summary {
+    pub min: f32,
+    pub mean: f32,
+    pub max: f32,
+}
+
+/// A histogram bucket: values up to `le` (exclusive, except for the last bucket).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bucket {
+    pub le: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayerStats {
+    pub path: String,
+    pub chunk_count: u64,
+    /// Chunks not hidden by a tombstone in this layer (tombstones themselves excluded).
+    pub live_chunks: u64,
+    pub tombstones: u64,
+    pub by_kind: BTreeMap<String, u64>,
+    pub by_author: BTreeMap<String, u64>,
+    pub confidence: Option<Summary>,
+    pub confidence_histogram: Vec<Bucket>,
+    /// Chunk age by `created_at_unix_ms`; chunks without a timestamp count as `unknown`.
+    pub age_histogram: Vec<Bucket>,
+    pub embedding_dim: usize,
+    pub embedding_norm: Option<Summary>,
+    pub zero_embeddings: u64,
+    /// Live chunks whose content hash matches an earlier live chunk in this layer.
+    pub exact_duplicates: u64,
+    pub file_length_bytes: u64,
+    pub content_bytes: u64,
+    pub embedding_bytes: u64,
+    /// Bytes that are neither content nor embeddings: headers, string tables, metadata.
+    pub overhead_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsReport {
+    pub layers: Vec<LayerStats>,
+    /// Live chunks whose content also appears in another of the layers.
+    pub cross_layer_duplicates: u64,
+}
+
+struct Running {
+    min: f32,
+    max: f32,
+    sum: f64,
+    n: u64,
+}
+
+impl Running {
+    const fn new() -> Self {
+        Self {
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            sum: 0.0,
+            n: 0,
+        }
+    }
+
+    fn push(&mut self, v: f32) {
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+        self.sum += f64::from(v);
+        self.n += 1;
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn summary(&self) -> Option<Summary> {
+        (self.n > 0).then(|| Summary {
+            min: self.min,
+            // Means of f32 values fit back into f32; precision loss on the count is harmless.
+            mean: (self.sum / self.n as f64) as f32,
+            max: self.max,
+        })
+    }
+}
+
+/// Statistics for the layer at `path`, plus the content hashes of its live chunks.
+fn layer_stats(path: &Path, now_ms: u64) -> anyhow::Result<(LayerStats, HashSet<String>)> {
+    let file = agentsdb_format::LayerFile::open_lenient(path)
+        .with_context(|| format!("open {}", path.display()))?;
+
+    let mut retracted = HashSet::new();
+    for chunk in file.chunks() {
+        let chunk = chunk?;
+        if chunk.kind == agentsdb_query::KIND_TOMBSTONE {
+            for source in file.sources_for(chunk.rel_start, chunk.rel_count)? {
+                if let agentsdb_format::SourceRef::ChunkId(id) = source {
+                    retracted.insert(id);
+                }
+            }
+        }
+    }
+
+    let mut stats = LayerStats {
+        path: path.display().to_string(),
+        chunk_count: file.chunk_count,
+        live_chunks: 0,
+        tombstones: 0,
+        by_kind: BTreeMap::new(),
+        by_author: BTreeMap::new(),
+        confidence: None,
+        confidence_histogram: Vec::new(),
+        age_histogram: Vec::new(),
+        embedding_dim: file.embedding_dim(),
+        embedding_norm: None,
+        zero_embeddings: 0,
+        exact_duplicates: 0,
+        file_length_bytes: file.header.file_length_bytes,
+        content_bytes: 0,
+        embedding_bytes: file.embedding_matrix.data_length,
+        overhead_bytes: 0,
+    };
+    let mut confidence = Running::new();
+    let mut norms = Running::new();
+    let mut confidence_counts = [0u64; CONFIDENCE_BUCKETS.len()];
+    let mut age_counts = [0u64; AGE_BUCKETS.len() + 2];
+    let mut hashes = HashSet::new();
+    let mut row = vec![0.0f32; file.embedding_dim()];
+
+    for chunk in file.chunks() {
+        let chunk = chunk?;
+        stats.content_bytes += chunk.content.len() as u64;
+        *stats.by_kind.entry(chunk.kind.to_string()).or_default() += 1;
+        *stats.by_author.entry(chunk.author.to_string()).or_default() += 1;
+        if chunk.kind == agentsdb_query::KIND_TOMBSTONE {
+            stats.tombstones += 1;
+            continue;
+        }
+        if retracted.contains(&chunk.id) {
+            continue;
+        }
+        stats.live_chunks += 1;
+
+        confidence.push(chunk.confidence);
+        let bucket = CONFIDENCE_BUCKETS
+            .iter()
+            .position(|le| chunk.confidence < *le)
+            .unwrap_or(CONFIDENCE_BUCKETS.len() - 1);
+        confidence_counts[bucket] += 1;
+
+        let age_bucket = if chunk.created_at_unix_ms == 0 {
+            AGE_BUCKETS.len() + 1
+        } else {
+            let age_days = now_ms.saturating_sub(chunk.created_at_unix_ms) / MS_PER_DAY;
+            AGE_BUCKETS
+                .iter()
+                .position(|(_, days)| age_days < *days)
+                .unwrap_or(AGE_BUCKETS.len())
+        };
+        age_counts[age_bucket] += 1;
+
+        file.read_embedding_row_f32(chunk.embedding_row, &mut row)?;
+        let norm = row.iter().map(|x| x * x).sum::<f32>().sqrt();
+        norms.push(norm);
+        if norm == 0.0 {
+            stats.zero_embeddings += 1;
+        }
+
+        if !hashes.insert(content_sha256_hex(chunk.content)) {
+            stats.exact_duplicates += 1;
+        }
+    }
+
+    stats.confidence = confidence.summary();
+    stats.embedding_norm = norms.summary();
+    stats.confidence_histogram = CONFIDENCE_BUCKETS
+        .iter()
+        .zip(confidence_counts)
+        .map(|(le, count)| Bucket {
+            le: le.to_string(),
+            count,
+        })
+        .collect();
+    stats.age_histogram = AGE_BUCKETS
+        .iter()
+        .map(|(label, _)| (*label).to_string())
+        .chain(["older".to_string(), "unknown".to_string()])
+        .zip(age_counts)
+        .map(|(le, count)| Bucket { le, count })
+        .collect();
+    stats.overhead_bytes = stats
+        .file_length_bytes
+        .saturating_sub(stats.content_bytes + stats.embedding_bytes);
+    Ok((stats, hashes))
+}
+
+/// Statistics for each layer in `paths` (missing files are skipped).
+pub fn collect_stats(paths: &[String]) -> anyhow::Result<StatsReport> {
+    let now_ms = now_unix_ms();
+    let mut layers = Vec::new();
+    let mut seen_in: HashMap<String, usize> = HashMap::new();
+    for path in paths {
+        let path = Path::new(path);
+        if !path.exists() {
+            continue;
+        }
+        let (stats, hashes) = layer_stats(path, now_ms)?;
+        for hash in hashes {
+            *seen_in.entry(hash).or_default() += 1;
+        }
+        layers.push(stats);
+    }
+    let cross_layer_duplicates = seen_in
+        .values()
+        .filter(|n| **n > 1)
+        .map(|n| *n as u64 - 1)
+        .sum();
+    Ok(StatsReport {
+        layers,
+        cross_layer_duplicates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_live_chunks_duplicates_and_histograms() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("AGENTS.local.db");
+        let schema = agentsdb_format::LayerSchema {
+            dim: 2,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let chunk = |id: u32, kind: &str, content: &str, confidence: f32, sources| {
+            agentsdb_format::ChunkInput {
+                id,
+                kind: kind.to_string(),
+                content: content.to_string(),
+                author: "human".to_string(),
+                confidence,
+                created_at_unix_ms: 0,
+                embedding: vec![3.0, 4.0],
+                sources,
+            }
+        };
+        let mut chunks = [
+            chunk(1, "note", "same", 0.1, Vec::new()),
+            chunk(2, "note", "same", 0.9, Vec::new()),
+            chunk(3, "canonical", "gone", 1.0, Vec::new()),
+            chunk(
+                4,
+                agentsdb_query::KIND_TOMBSTONE,
+                "retracted",
+                1.0,
+                vec![agentsdb_format::ChunkSource::ChunkId(3)],
+            ),
+        ];
+        agentsdb_format::write_layer_atomic(&path, &schema, &mut chunks, None).expect("write");
+
+        let report = collect_stats(&[path.display().to_string()]).expect("stats");
+        let stats = &report.layers[0];
+        assert_eq!(stats.chunk_count, 4);
+        assert_eq!(stats.live_chunks, 2);
+        assert_eq!(stats.tombstones, 1);
+        assert_eq!(stats.by_kind.get("note"), Some(&2));
+        assert_eq!(stats.exact_duplicates, 1);
+        assert_eq!(stats.confidence_histogram[0].count, 1);
+        assert_eq!(stats.confidence_histogram[4].count, 1);
+        assert_eq!(stats.age_histogram.last().map(|b| b.count), Some(2));
+        assert_eq!(stats.embedding_norm.as_ref().map(|n| n.mean), Some(5.0));
+        assert_eq!(stats.embedding_bytes, 4 * 2 * 4);
+        assert!(stats.overhead_bytes > 0);
+        assert_eq!(report.cross_layer_duplicates, 0);
+    }
+}
@@ -0,0 +1,470 @@
+//! Push/pull of layer files to remote storage, used by `agentsdb sync`.
+//!
+//! A remote holds content-addressed blobs (`blobs/<sha256>.db`) and one manifest per layer
+//! file (`<file>.manifest.json`) naming the current blob and the hash it replaced. The
+//! `AGENTS.sync.json` sidecar next to the layers remembers the hash each layer had when it
+//! was last synced: that common parent tells a fast-forward from a conflict.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::util::{hex_lower, now_unix_ms};
+
+#[cfg(feature = "sync")]
+mod http;
+#[cfg(feature = "sync")]
+mod s3;
+
+/// Sidecar file name for sync state.
+const SYNC_FILE: &str = "AGENTS.sync.json";
+
+const MANIFEST_VERSION: u32 = 1;
+
+/// The manifest a remote keeps for one layer file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteManifest {
+    pub version: u32,
+    pub file: String,
+    pub sha256: String,
+    pub size: u64,
+    /// Hash of the version this push replaced (`None` for the first push).
+    pub parent: Option<String>,
+    pub pushed_at_unix_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerSyncState {
+    pub remote: String,
+    /// Hash the local layer and the remote agreed on at the last push or pull.
+    pub synced_sha256: String,
+    pub synced_at_unix_ms: u64,
+}
+
+/// Persisted sync state: layer name (`user`, `delta`, ...) to its last sync.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    pub layers: BTreeMap<String, LayerSyncState>,
+}
+
+impl SyncState {
+    /// Build the sidecar file path given the directory holding the layers.
+    pub fn path_for(dir: &Path) -> PathBuf {
+        dir.join(SYNC_FILE)
+    }
+
+    /// Load from disk, returning the default state if the file doesn't exist.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let path = Self::path_for(dir);
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).with_context(|| format!("parse {}", path.display()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("read {}", path.display())),
+        }
+    }
+
+    /// Persist to disk.
+    pub fn save(&self, dir: &Path) -> anyhow::Result<()> {
+        let path = Self::path_for(dir);
+        std::fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("write {}", path.display()))
+    }
+
+    fn record(&mut self, layer: &str, remote: &str, sha256: &str) {
+        self.layers.insert(
+            layer.to_string(),
+            LayerSyncState {
+                remote: remote.to_string(),
+                synced_sha256: sha256.to_string(),
+                synced_at_unix_ms: now_unix_ms(),
+            },
+        );
+    }
+
+    /// The last synced hash for `layer`, if it was synced with `remote`.
+    fn parent(&self, layer: &str, remote: &str) -> Option<String> {
+        self.layers
+            .get(layer)
+            .filter(|s| s.remote == remote)
+            .map(|s| s.synced_sha256.clone())
+    }
+}
+
+/// Object storage a layer can be synced with. Keys are `/`-separated paths under the
+/// remote's root.
+pub trait Remote {
+    /// Reads a whole object, or `None` if it does not exist.
+    fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>>;
+    fn exists(&self, key: &str) -> anyhow::Result<bool>;
+    fn put(&self, key: &str, bytes: &[u8]) -> anyhow::Result<()>;
+    /// Downloads an object into `dest`. When `dest` already holds the start of the object
+    /// (an interrupted earlier download), only the rest is fetched.
+    fn download(&self, key: &str, dest: &Path) -> anyhow::Result<u64>;
+}
+
+/// A directory (local path or `file://` URL), e.g. a mounted network share.
+struct DirRemote {
+    root: PathBuf,
+}
+
+impl Remote for DirRemote {
+    fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let path = self.root.join(key);
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("read {}", path.display())),
+        }
+    }
+
+    fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        Ok(self.root.join(key).is_file())
+    }
+
+    fn put(&self, key: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("create {}", parent.display()))?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes).with_context(|| format!("write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("rename to {}", path.display()))
+    }
+
+    fn download(&self, key: &str, dest: &Path) -> anyhow::Result<u64> {
+        let src_path = self.root.join(key);
+        let mut src = std::fs::File::open(&src_path)
+            .with_context(|| format!("open {}", src_path.display()))?;
+        let total = src.metadata()?.len();
+        let mut out = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dest)
+            .with_context(|| format!("open {}", dest.display()))?;
+        let mut have = out.metadata()?.len();
+        if have > total {
+            out.set_len(0)?;
+            have = 0;
+        }
+        src.seek(SeekFrom::Start(have))?;
+        Ok(std::io::copy(&mut src, &mut out)?)
+    }
+}
+
+/// Opens the remote a URL names: `s3://bucket/prefix` (S3 or S3-compatible storage),
+/// `https://...` (plain HTTPS or WebDAV), or a local directory / `file://` URL.
+pub fn open_remote(url: &str) -> anyhow::Result<Box<dyn Remote>> {
+    if url.starts_with("s3://") || url.starts_with("http://") || url.starts_with("https://") {
+        #[cfg(feature = "sync")]
+        {
+            if url.starts_with("s3://") {
+                return Ok(Box::new(s3::S3Remote::from_url(url)?));
+            }
+            return Ok(Box::new(http::HttpRemote::from_url(url)));
+        }
+        #[cfg(not(feature = "sync"))]
+        anyhow::bail!("remote {url} needs a build with the `sync` feature");
+    }
+    let root = url.strip_prefix("file://").unwrap_or(url);
+    Ok(Box::new(DirRemote {
+        root: PathBuf::from(root),
+    }))
+}
+
+/// File name of a standard layer (`base`, `user`, `delta`, `local`).
+pub fn layer_file_name(layer: &str) -> anyhow::Result<&'static str> {
+    match layer {
+        "base" => Ok("AGENTS.db"),
+        "user" => Ok("AGENTS.user.db"),
+        "delta" => Ok("AGENTS.delta.db"),
+        "local" => Ok("AGENTS.local.db"),
+        other => anyhow::bail!("unknown layer {other:?} (expected base, user, delta, or local)"),
+    }
+}
+
+fn manifest_key(file: &str) -> String {
+    format!("{file}.manifest.json")
+}
+
+fn blob_key(sha256: &str) -> String {
+    format!("blobs/{sha256}.db")
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex_lower(&agentsdb_embeddings::cache::sha256(bytes))
+}
+
+fn short(sha256: &str) -> &str {
+    sha256.get(..12).unwrap_or(sha256)
+}
+
+fn read_manifest(remote: &dyn Remote, file: &str) -> anyhow::Result<Option<RemoteManifest>> {
+    remote
+        .get(&manifest_key(file))?
+        .map(|bytes| serde_json::from_slice(&bytes).context("parse remote manifest"))
+        .transpose()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+    UpToDate,
+    Pushed,
+    Pulled,
+    Merged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncOutcome {
+    pub layer: String,
+    pub remote: String,
+    pub action: SyncAction,
+    pub sha256: String,
+    pub bytes_transferred: u64,
+    /// Conflicting chunks a `--merge` pull kept in both versions (see `merge::merge_layers`).
+    pub unresolved_conflicts: usize,
+}
+
+/// The remote to use: the one given, else the one `layer` was last synced with.
+fn resolve_remote(state: &SyncState, layer: &str, remote: Option<&str>) -> anyhow::Result<String> {
+    remote
+        .map(str::to_string)
+        .or_else(|| state.layers.get(layer).map(|s| s.remote.clone()))
+        .with_context(|| format!("no remote given and {layer} has not been synced before"))
+}
+
+/// Uploads the `layer` file in `dir`. Refuses (unless `force`) when the remote moved on
+/// since this copy last synced with it.
+pub fn push(
+    dir: &Path,
+    layer: &str,
+    remote: Option<&str>,
+    force: bool,
+) -> anyhow::Result<SyncOutcome> {
+    let file = layer_file_name(layer)?;
+    let mut state = SyncState::load(dir)?;
+    let url = resolve_remote(&state, layer, remote)?;
+    let remote = open_remote(&url)?;
+
+    let path = dir.join(file);
+    let bytes = std::fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+    let local = sha256_hex(&bytes);
+    let manifest = read_manifest(remote.as_ref(), file)?;
+    let mut outcome = SyncOutcome {
+        layer: layer.to_string(),
+        remote: url.clone(),
+        action: SyncAction::UpToDate,
+        sha256: local.clone(),
+        bytes_transferred: 0,
+        unresolved_conflicts: 0,
+    };
+
+    if let Some(m) = &manifest {
+        if m.sha256 == local {
+            state.record(layer, &url, &local);
+            state.save(dir)?;
+            return Ok(outcome);
+        }
+        let parent = state.parent(layer, &url);
+        if !force && parent.as_deref() != Some(m.sha256.as_str()) {
+            anyhow::bail!(
+                "remote {file} changed since the last sync (remote {}, last synced {}); pull first (`agentsdb sync pull {layer} --merge`) or push with --force",
+                short(&m.sha256),
+                parent.as_deref().map_or("never", short)
+            );
+        }
+    }
+
+    // Blobs are content-addressed, so a push interrupted after the upload resumes here.
+    let blob = blob_key(&local);
+    if !remote.exists(&blob)? {
+        remote.put(&blob, &bytes)?;
+        outcome.bytes_transferred = bytes.len() as u64;
+    }
+    let next = RemoteManifest {
+        version: MANIFEST_VERSION,
+        file: file.to_string(),
+        sha256: local.clone(),
+        size: bytes.len() as u64,
+        parent: manifest.map(|m| m.sha256),
+        pushed_at_unix_ms: now_unix_ms(),
+    };
+    remote.put(&manifest_key(file), &serde_json::to_vec_pretty(&next)?)?;
+
+    state.record(layer, &url, &local);
+    state.save(dir)?;
+    outcome.action = SyncAction::Pushed;
+    Ok(outcome)
+}
+
+/// Downloads the blob with hash `sha256` next to `dest_dir`, resuming a partial download,
+/// and verifies it.
+fn fetch_blob(
+    remote: &dyn Remote,
+    dest_dir: &Path,
+    file: &str,
+    sha256: &str,
+) -> anyhow::Result<(PathBuf, u64)> {
+    let part = dest_dir.join(format!(".{file}.{sha256}.part"));
+    let transferred = remote.download(&blob_key(sha256), &part)?;
+    let bytes = std::fs::read(&part).with_context(|| format!("read {}", part.display()))?;
+    if sha256_hex(&bytes) != sha256 {
+        let _ = std::fs::remove_file(&part);
+        anyhow::bail!(
+            "downloaded {file} does not match its hash {}",
+            short(sha256)
+        );
+    }
+    agentsdb_format::LayerFile::open_lenient(&part)
+        .with_context(|| format!("downloaded {file} is not a valid layer"))?;
+    Ok((part, transferred))
+}
+
+/// Downloads the remote version of the `layer` file into `dir`. When the local file also
+/// changed since the last sync, this fails unless `merge` (three-way merge against the
+/// last synced version) or `force` (overwrite the local changes) is set.
+pub fn pull(
+    dir: &Path,
+    layer: &str,
+    remote: Option<&str>,
+    force: bool,
+    merge: bool,
+) -> anyhow::Result<SyncOutcome> {
+    let file = layer_file_name(layer)?;
+    let mut state = SyncState::load(dir)?;
+    let url = resolve_remote(&state, layer, remote)?;
+    let remote = open_remote(&url)?;
+    let manifest = read_manifest(remote.as_ref(), file)?
+        .with_context(|| format!("remote {url} has no {file}"))?;
+
+    let path = dir.join(file);
+    let local = match std::fs::read(&path) {
+        Ok(bytes) => Some(sha256_hex(&bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+    };
+    let mut outcome = SyncOutcome {
+        layer: layer.to_string(),
+        remote: url.clone(),
+        action: SyncAction::UpToDate,
+        sha256: manifest.sha256.clone(),
+        bytes_transferred: 0,
+        unresolved_conflicts: 0,
+    };
+    if local.as_deref() == Some(manifest.sha256.as_str()) {
+        state.record(layer, &url, &manifest.sha256);
+        state.save(dir)?;
+        return Ok(outcome);
+    }
+
+    let parent = state.parent(layer, &url);
+    let local_changed = local.is_some() && local != parent;
+    if local_changed && !force && !merge {
+        anyhow::bail!(
+            "{file} changed locally and on the remote since the last sync (local {}, remote {}); pull with --merge, or --force to discard the local changes",
+            local.as_deref().map_or("none", short),
+            short(&manifest.sha256)
+        );
+    }
+
+    let (theirs, transferred) = fetch_blob(remote.as_ref(), dir, file, &manifest.sha256)?;
+    outcome.bytes_transferred = transferred;
+    if local_changed && !force {
+        // Without a recorded parent the merge base is empty: everything is an addition.
+        let base = match &parent {
+            Some(p) => {
+                let (base, n) = fetch_blob(remote.as_ref(), dir, file, p)?;
+                outcome.bytes_transferred += n;
+                base
+            }
+            None => dir.join(format!(".{file}.empty-base")),
+        };
+        let report = crate::merge::merge_layers(&base, &path, &theirs, &path, None);
+        let _ = std::fs::remove_file(&base);
+        let _ = std::fs::remove_file(&theirs);
+        outcome.unresolved_conflicts = report?.conflicts.iter().filter(|c| !c.resolved).count();
+        outcome.action = SyncAction::Merged;
+    } else {
+        std::fs::rename(&theirs, &path).with_context(|| format!("replace {}", path.display()))?;
+        outcome.action = SyncAction::Pulled;
+    }
+
+    state.record(layer, &url, &manifest.sha256);
+    state.save(dir)?;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+
+    #[test]
+    fn push_pull_detects_conflicts_and_merges() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let (alice, bob, remote) = (
+            tmp.path().join("alice"),
+            tmp.path().join("bob"),
+            tmp.path().join("remote"),
+        );
+        std::fs::create_dir_all(&alice)?;
+        std::fs::create_dir_all(&bob)?;
+        let remote_url = remote.display().to_string();
+        let remote_url = Some(remote_url.as_str());
+
+        write_layer(&alice.join("AGENTS.user.db"), vec![chunk(1, "shared")])?;
+        assert_eq!(
+            push(&alice, "user", remote_url, false)?.action,
+            SyncAction::Pushed
+        );
+        assert_eq!(
+            pull(&bob, "user", remote_url, false, false)?.action,
+            SyncAction::Pulled
+        );
+        // The remote is remembered after the first sync.
+        assert_eq!(
+            pull(&bob, "user", None, false, false)?.action,
+            SyncAction::UpToDate
+        );
+
+        write_layer(
+            &alice.join("AGENTS.user.db"),
+            vec![chunk(1, "shared"), chunk(2, "alice")],
+        )?;
+        write_layer(
+            &bob.join("AGENTS.user.db"),
+            vec![chunk(1, "shared"), chunk(3, "bob")],
+        )?;
+        push(&alice, "user", None, false)?;
+        assert!(push(&bob, "user", None, false).is_err());
+        assert!(pull(&bob, "user", None, false, false).is_err());
+
+        let merged = pull(&bob, "user", None, false, true)?;
+        assert_eq!(merged.action, SyncAction::Merged);
+        assert_eq!(merged.unresolved_conflicts, 0);
+        assert_eq!(push(&bob, "user", None, false)?.action, SyncAction::Pushed);
+        pull(&alice, "user", None, false, false)?;
+        let file = agentsdb_format::LayerFile::open(alice.join("AGENTS.user.db"))?;
+        assert_eq!(agentsdb_format::read_all_chunks(&file)?.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn dir_remote_download_resumes_partial_files() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let remote = DirRemote {
+            root: tmp.path().join("remote"),
+        };
+        remote.put("blobs/x.db", b"0123456789")?;
+        let dest = tmp.path().join("x.part");
+        std::fs::write(&dest, b"0123")?;
+        assert_eq!(remote.download("blobs/x.db", &dest)?, 6);
+        assert_eq!(std::fs::read(&dest)?, b"0123456789");
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Plain HTTPS / WebDAV remote: objects are `GET`/`PUT`/`HEAD` under a base URL.
+
+use anyhow::Context;
+use std::io::Read;
+use std::path::Path;
+
+use super::Remote;
+
+/// Env var holding a bearer token sent as `Authorization: Bearer <token>`.
+const TOKEN_ENV: &str = "AGENTSDB_SYNC_TOKEN";
+
+pub(super) struct HttpRemote {
+    base: String,
+    token: Option<String>,
+}
+
+impl HttpRemote {
+    pub(super) fn from_url(url: &str) -> Self {
+        Self {
+            base: url.trim_end_matches('/').to_string(),
+            token: std::env::var(TOKEN_ENV).ok().filter(|t| !t.is_empty()),
+        }
+    }
+
+    fn request(&self, method: &str, key: &str) -> ureq::Request {
+        let request = ureq::request(method, &format!("{}/{key}", self.base));
+        match &self.token {
+            Some(token) => request.set("Authorization", &format!("Bearer {token}")),
+            None => request,
+        }
+    }
+
+    /// Creates the WebDAV collections above `key` (servers refuse a `PUT` into a missing one).
+    fn make_collections(&self, key: &str) -> anyhow::Result<()> {
+        let mut prefix = String::new();
+        for segment in key
+            .split('/')
+            .rev()
+            .skip(1)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            prefix.push_str(segment);
+            prefix.push('/');
+            match self.request("MKCOL", &prefix).call() {
+                // 405: the collection already exists.
+                Ok(_) | Err(ureq::Error::Status(405, _)) => {}
+                Err(e) => return Err(e).with_context(|| format!("MKCOL {prefix}")),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes a (possibly ranged) download response to `dest`, which already holds `have` bytes.
+pub(super) fn write_download(
+    response: ureq::Response,
+    dest: &Path,
+    have: u64,
+) -> anyhow::Result<u64> {
+    // 206 continues the partial file; a full 200 response replaces it.
+    let append = response.status() == 206 && have > 0;
+    let mut out = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(dest)
+        .with_context(|| format!("open {}", dest.display()))?;
+    Ok(std::io::copy(&mut response.into_reader(), &mut out)?)
+}
+
+/// Bytes already downloaded into `dest`.
+pub(super) fn partial_len(dest: &Path) -> u64 {
+    std::fs::metadata(dest).map_or(0, |m| m.len())
+}
+
+impl Remote for HttpRemote {
+    fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match self.request("GET", key).call() {
+            Ok(response) => {
+                let mut bytes = Vec::new();
+                response.into_reader().read_to_end(&mut bytes)?;
+                Ok(Some(bytes))
+            }
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("GET {key}")),
+        }
+    }
+
+    fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        match self.request("HEAD", key).call() {
+            Ok(_) => Ok(true),
+            Err(ureq::Error::Status(404, _)) => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("HEAD {key}")),
+        }
+    }
+
+    fn put(&self, key: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        match self.request("PUT", key).send_bytes(bytes) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(409, _)) => {
+                self.make_collections(key)?;
+                self.request("PUT", key)
+                    .send_bytes(bytes)
+                    .with_context(|| format!("PUT {key}"))?;
+                Ok(())
+            }
+            Err(e) => Err(e).with_context(|| format!("PUT {key}")),
+        }
+    }
+
+    fn download(&self, key: &str, dest: &Path) -> anyhow::Result<u64> {
+        let have = partial_len(dest);
+        let mut request = self.request("GET", key);
+        if have > 0 {
+            request = request.set("Range", &format!("bytes={have}-"));
+        }
+        match request.call() {
+            Ok(response) => write_download(response, dest, have),
+            // 416: the partial file is already complete.
+            Err(ureq::Error::Status(416, _)) if have > 0 => Ok(0),
+            Err(e) => Err(e).with_context(|| format!("GET {key}")),
+        }
+    }
+}
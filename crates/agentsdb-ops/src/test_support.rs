@@ -0,0 +1,71 @@
+//! Fixtures shared by the unit tests of this crate.
+
+use agentsdb_core::export::{ExportChunkV1, ExportSourceV1};
+use agentsdb_format::{ChunkInput, EmbeddingElementType, EmbeddingLayout, LayerSchema};
+use std::path::Path;
+
+use crate::write::NewChunk;
+
+/// The schema of every fixture layer: two f32 dimensions, row-major.
+pub(crate) fn schema() -> LayerSchema {
+    LayerSchema {
+        dim: 2,
+        element_type: EmbeddingElementType::F32,
+        quant_scale: 1.0,
+        layout: EmbeddingLayout::RowMajor,
+    }
+}
+
+/// A human note with full confidence and embedding `[1, 0]`; tests set any other field with
+/// struct update syntax.
+pub(crate) fn chunk(id: u32, content: &str) -> ChunkInput {
+    ChunkInput {
+        id,
+        kind: "note".to_string(),
+        content: content.to_string(),
+        author: "human".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: 0,
+        embedding: vec![1.0, 0.0],
+        sources: Vec::new(),
+    }
+}
+
+/// [`chunk`] with its embedding set to `embedding`.
+pub(crate) fn embedded(id: u32, content: &str, embedding: [f32; 2]) -> ChunkInput {
+    ChunkInput {
+        embedding: embedding.to_vec(),
+        ..chunk(id, content)
+    }
+}
+
+/// Writes `chunks` to a new layer at `path` with [`schema`].
+pub(crate) fn write_layer(path: &Path, mut chunks: Vec<ChunkInput>) -> anyhow::Result<()> {
+    agentsdb_format::write_layer_atomic(path, &schema(), &mut chunks, None)?;
+    Ok(())
+}
+
+/// A chunk as a write hook receives it, with full confidence and no sources.
+pub(crate) fn new_chunk(kind: &str, content: &str) -> NewChunk {
+    NewChunk {
+        kind: kind.to_string(),
+        content: content.to_string(),
+        confidence: 1.0,
+        sources: Vec::new(),
+    }
+}
+
+/// The export form of a human note with id `id` and no embedding.
+pub(crate) fn export_chunk(id: u32, sources: Vec<ExportSourceV1>) -> ExportChunkV1 {
+    ExportChunkV1 {
+        id,
+        kind: "note".to_string(),
+        content: Some(format!("chunk {id}")),
+        author: "human".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: 0,
+        sources,
+        embedding: None,
+        content_sha256: None,
+    }
+}
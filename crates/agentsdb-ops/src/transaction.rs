@@ -0,0 +1,377 @@
+//! Writes to several layers that land together or not at all.
+//!
+//! A [`Transaction`] stages appends and rewrites, then [`commit`](Transaction::commit) encodes
+//! every layer, writes each to a synced temp file, and only then renames the temp files over
+//! their layers, in the order they were staged. If a rename fails, the layers already replaced
+//! are restored from hard-linked backups and the remaining temp files are removed.
+//!
+//! A crash between two renames can still leave the earlier layers replaced, so stage the layer
+//! that gains data before the one that loses it: a promotion stages the append to the user layer
+//! first and the rewrite of delta second, so an interrupted commit duplicates a chunk rather than
+//! dropping it.
+
+use anyhow::Context;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use agentsdb_format::{ChunkInput, LayerSchema, WritePolicy};
+
+#[derive(Debug)]
+enum Change {
+    /// Append to the layer; create it with this schema and metadata if it does not exist.
+    Append {
+        chunks: Vec<ChunkInput>,
+        create: Option<(LayerSchema, Option<Vec<u8>>)>,
+    },
+    /// Replace the layer's contents.
+    Write {
+        schema: LayerSchema,
+        chunks: Vec<ChunkInput>,
+        metadata: Option<Vec<u8>>,
+    },
+}
+
+#[derive(Debug)]
+struct Staged {
+    path: PathBuf,
+    change: Change,
+}
+
+impl Staged {
+    fn prepare(&mut self) -> anyhow::Result<(Vec<u8>, Vec<u32>)> {
+        let path = &self.path;
+        let prepared = match &mut self.change {
+            Change::Append {
+                chunks,
+                create: Some((schema, metadata)),
+            } if !path.exists() => {
+                agentsdb_format::prepare_write_layer(schema, chunks, metadata.as_deref())
+            }
+            Change::Append { chunks, .. } => {
+                agentsdb_format::prepare_append_layer(path, chunks, None)
+            }
+            Change::Write {
+                schema,
+                chunks,
+                metadata,
+            } => agentsdb_format::prepare_write_layer(schema, chunks, metadata.as_deref()),
+        };
+        prepared.with_context(|| format!("prepare {}", path.display()))
+    }
+
+    const fn is_append(&self) -> bool {
+        matches!(self.change, Change::Append { .. })
+    }
+}
+
+/// Changes to several layers, applied by [`commit`](Self::commit).
+///
+/// ```no_run
+/// use agentsdb_format::WritePolicy;
+/// use agentsdb_ops::Transaction;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let delta = agentsdb_format::LayerFile::open("AGENTS.delta.db")?;
+/// let schema = agentsdb_format::schema_of(&delta);
+/// let (moved, kept): (Vec<_>, Vec<_>) = agentsdb_format::read_all_chunks(&delta)?
+///     .into_iter()
+///     .partition(|c| c.id == 42);
+/// drop(delta);
+///
+/// let mut tx = Transaction::new().with_policy(WritePolicy::standard().allow_user());
+/// let user = tx.append_or_create("AGENTS.user.db", &schema, None, moved);
+/// tx.write("AGENTS.delta.db", &schema, kept, None);
+/// let ids = tx.commit()?;
+/// println!("moved to the user layer as {:?}", ids[user]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Transaction {
+    policy: WritePolicy,
+    staged: Vec<Staged>,
+}
+
+impl Transaction {
+    /// An empty transaction checked against [`WritePolicy::standard`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks every staged layer against `policy` instead.
+    #[must_use]
+    pub fn with_policy(mut self, policy: WritePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Stages appending `chunks` (ids of 0 are assigned) to the existing layer at `path`.
+    ///
+    /// # Returns
+    /// The index of this change's ids in the result of [`commit`](Self::commit)
+    pub fn append(&mut self, path: impl Into<PathBuf>, chunks: Vec<ChunkInput>) -> usize {
+        self.stage(
+            path.into(),
+            Change::Append {
+                chunks,
+                create: None,
+            },
+        )
+    }
+
+    /// Like [`append`](Self::append), but creates the layer with `schema` and `metadata` if it
+    /// does not exist when the transaction commits.
+    pub fn append_or_create(
+        &mut self,
+        path: impl Into<PathBuf>,
+        schema: &LayerSchema,
+        metadata: Option<&[u8]>,
+        chunks: Vec<ChunkInput>,
+    ) -> usize {
+        self.stage(
+            path.into(),
+            Change::Append {
+                chunks,
+                create: Some((schema.clone(), metadata.map(<[u8]>::to_vec))),
+            },
+        )
+    }
+
+    /// Stages replacing the layer at `path` with `chunks`.
+    ///
+    /// # Returns
+    /// The index of this change's ids in the result of [`commit`](Self::commit)
+    pub fn write(
+        &mut self,
+        path: impl Into<PathBuf>,
+        schema: &LayerSchema,
+        chunks: Vec<ChunkInput>,
+        metadata: Option<&[u8]>,
+    ) -> usize {
+        self.stage(
+            path.into(),
+            Change::Write {
+                schema: schema.clone(),
+                chunks,
+                metadata: metadata.map(<[u8]>::to_vec),
+            },
+        )
+    }
+
+    fn stage(&mut self, path: PathBuf, change: Change) -> usize {
+        self.staged.push(Staged { path, change });
+        self.staged.len() - 1
+    }
+
+    /// Whether nothing has been staged.
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Applies every staged change, or none of them.
+    ///
+    /// Each layer may be staged once. Nothing on disk changes until every layer has been
+    /// encoded and written to a temp file, so permission, schema and I/O errors up to that point
+    /// leave the layers as they were.
+    ///
+    /// # Returns
+    /// The ids written by each change, in staging order
+    #[tracing::instrument(level = "debug", skip_all, fields(layers = self.staged.len()))]
+    pub fn commit(self) -> anyhow::Result<Vec<Vec<u32>>> {
+        let Self { policy, mut staged } = self;
+        let mut seen = HashSet::new();
+        for s in &staged {
+            policy
+                .check(&s.path)
+                .with_context(|| format!("permission check for {}", s.path.display()))?;
+            if !seen.insert(s.path.as_path()) {
+                anyhow::bail!("{} is staged more than once", s.path.display());
+            }
+        }
+
+        let mut prepared = Vec::with_capacity(staged.len());
+        for s in &mut staged {
+            prepared.push(s.prepare()?);
+        }
+
+        let mut temps = Vec::with_capacity(staged.len());
+        for (s, (bytes, _)) in staged.iter().zip(&prepared) {
+            match agentsdb_format::stage_layer_bytes(&s.path, bytes) {
+                Ok(tmp) => temps.push(tmp),
+                Err(err) => {
+                    remove_all(&temps);
+                    return Err(err).with_context(|| format!("stage {}", s.path.display()));
+                }
+            }
+        }
+
+        let mut applied: Vec<(&Path, Option<PathBuf>)> = Vec::with_capacity(staged.len());
+        for (i, (s, tmp)) in staged.iter().zip(&temps).enumerate() {
+            match replace(&s.path, tmp) {
+                Ok(backup) => applied.push((&s.path, backup)),
+                Err(err) => {
+                    remove_all(&temps[i..]);
+                    roll_back(&applied);
+                    return Err(err).with_context(|| {
+                        format!("replace {} (transaction rolled back)", s.path.display())
+                    });
+                }
+            }
+        }
+
+        for backup in applied.iter().filter_map(|(_, backup)| backup.as_ref()) {
+            let _ = std::fs::remove_file(backup);
+        }
+        for (s, (_, ids)) in staged.iter().zip(&prepared) {
+            if s.is_append() {
+                crate::metrics::record_append(&s.path, ids.len());
+            }
+        }
+        Ok(prepared.into_iter().map(|(_, ids)| ids).collect())
+    }
+}
+
+/// Renames `tmp` over `path`, keeping the old file (if any) as a backup.
+///
+/// # Returns
+/// The backup's path, or `None` if `path` did not exist
+fn replace(path: &Path, tmp: &Path) -> anyhow::Result<Option<PathBuf>> {
+    let backup = if path.exists() {
+        Some(backup(path).with_context(|| format!("back up {}", path.display()))?)
+    } else {
+        None
+    };
+    if let Err(err) = std::fs::rename(tmp, path) {
+        if let Some(backup) = &backup {
+            let _ = std::fs::remove_file(backup);
+        }
+        return Err(err.into());
+    }
+    Ok(backup)
+}
+
+/// Hard-links (or, where links are unsupported, copies) `path` to a fresh name next to it.
+fn backup(path: &Path) -> std::io::Result<PathBuf> {
+    let base = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("AGENTS.db");
+    let mut i = 0u32;
+    loop {
+        let name = if i == 0 {
+            format!("{base}.txn-backup")
+        } else {
+            format!("{base}.txn-backup.{i}")
+        };
+        let backup = path.with_file_name(name);
+        match std::fs::hard_link(path, &backup) {
+            Ok(()) => return Ok(backup),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                i = i.saturating_add(1);
+            }
+            Err(_) => {
+                std::fs::copy(path, &backup)?;
+                return Ok(backup);
+            }
+        }
+    }
+}
+
+/// Undoes `applied` renames, newest first.
+fn roll_back(applied: &[(&Path, Option<PathBuf>)]) {
+    for (path, backup) in applied.iter().rev() {
+        let restored = match backup {
+            Some(backup) => std::fs::rename(backup, path),
+            None => std::fs::remove_file(path),
+        };
+        if let Err(err) = restored {
+            tracing::warn!(
+                layer = %path.display(),
+                backup = ?backup,
+                error = %err,
+                "could not roll back layer"
+            );
+        }
+    }
+}
+
+fn remove_all(paths: &[PathBuf]) {
+    for path in paths {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, schema};
+    use agentsdb_format::LayerFile;
+
+    fn contents(path: &Path) -> anyhow::Result<Vec<String>> {
+        let file = LayerFile::open(path)?;
+        Ok(agentsdb_format::read_all_chunks(&file)?
+            .into_iter()
+            .map(|c| c.content)
+            .collect())
+    }
+
+    fn leftovers(dir: &Path) -> anyhow::Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if name.contains(".tmp") || name.contains(".txn-backup") {
+                names.push(name);
+            }
+        }
+        Ok(names)
+    }
+
+    #[test]
+    fn commit_applies_every_change() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let delta = dir.path().join("AGENTS.delta.db");
+        let user = dir.path().join("AGENTS.user.db");
+        agentsdb_format::write_layer_atomic(
+            &delta,
+            &schema(),
+            &mut [chunk(1, "a"), chunk(2, "b")],
+            None,
+        )?;
+
+        let mut tx = Transaction::new().with_policy(WritePolicy::standard().allow_user());
+        let promoted = tx.append_or_create(&user, &schema(), None, vec![chunk(1, "a")]);
+        tx.write(&delta, &schema(), vec![chunk(2, "b")], None);
+        let ids = tx.commit()?;
+
+        assert_eq!(ids[promoted], vec![1]);
+        assert_eq!(contents(&user)?, vec!["a"]);
+        assert_eq!(contents(&delta)?, vec!["b"]);
+        assert!(leftovers(dir.path())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn failed_rename_rolls_back_earlier_layers() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let local = dir.path().join("AGENTS.local.db");
+        agentsdb_format::write_layer_atomic(&local, &schema(), &mut [chunk(1, "a")], None)?;
+        // A directory in the place of the second layer: staging succeeds, replacing it fails.
+        let blocked = dir.path().join("AGENTS.delta.db");
+        std::fs::create_dir(&blocked)?;
+        std::fs::write(blocked.join("keep"), b"")?;
+
+        let mut tx = Transaction::new();
+        tx.append(&local, vec![chunk(2, "b")]);
+        tx.write(&blocked, &schema(), vec![chunk(3, "c")], None);
+        let err = tx.commit().expect_err("replacing a directory fails");
+
+        assert!(format!("{err:#}").contains("rolled back"));
+        assert_eq!(contents(&local)?, vec!["a"]);
+        assert!(leftovers(dir.path())?.is_empty());
+
+        let mut tx = Transaction::new();
+        tx.append(dir.path().join("AGENTS.db"), vec![chunk(4, "d")]);
+        assert!(tx.commit().is_err(), "base layer is protected by default");
+        Ok(())
+    }
+}
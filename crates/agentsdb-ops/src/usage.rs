@@ -0,0 +1,387 @@
+//! Usage analytics for `agentsdb top`, aggregated from the MCP server's audit log
+//! (`serve --audit-log`). Writes are always logged; searches and chunk reads only when the
+//! server also runs with `--audit-reads`.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::review::visible_chunks;
+use crate::util::truncate_preview;
+
+/// Characters of content shown per listed chunk.
+const PREVIEW_CHARS: usize = 120;
+
+/// Tools whose audit records are retrievals rather than writes.
+const READ_TOOLS: [&str; 3] = ["agents_search", "agents.search", "agents_context_get"];
+
+/// Words too common to be worth reporting as query terms.
+const STOP_WORDS: &[&str] = &[
+    "about", "and", "are", "can", "does", "for", "from", "have", "how", "into", "not", "that",
+    "the", "there", "this", "use", "was", "what", "when", "where", "which", "who", "why", "with",
+    "you",
+];
+
+/// One line of the audit log; fields the analytics do not use are ignored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditRecord {
+    pub ts_unix_ms: u64,
+    pub tool: String,
+    #[serde(default)]
+    pub actor: String,
+    #[serde(default)]
+    pub ok: bool,
+    /// Query text of a logged search.
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Chunks returned by a logged search or read.
+    #[serde(default)]
+    pub retrieved: Vec<RetrievedChunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct RetrievedChunk {
+    /// Logical layer name (`local`, `user`, `delta`, `base` or `session`).
+    pub layer: String,
+    pub id: u32,
+}
+
+impl AuditRecord {
+    fn is_read(&self) -> bool {
+        READ_TOOLS.contains(&self.tool.as_str())
+    }
+}
+
+/// Reads an ndjson audit log. Returns the records and the number of lines that could not be
+/// parsed (e.g. a line cut short by a crash), which are skipped.
+pub fn read_audit_log(path: &Path) -> anyhow::Result<(Vec<AuditRecord>, usize)> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let mut records = Vec::new();
+    let mut malformed = 0;
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            Err(_) => malformed += 1,
+        }
+    }
+    Ok((records, malformed))
+}
+
+/// Granularity of the write-volume buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsagePeriod {
+    Day,
+    /// Weeks starting on Monday, labelled with that Monday's date.
+    Week,
+    /// Labelled `YYYY-MM`.
+    Month,
+}
+
+impl std::str::FromStr for UsagePeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            other => anyhow::bail!("unknown period {other:?} (expected day, week or month)"),
+        }
+    }
+}
+
+impl UsagePeriod {
+    /// UTC bucket label for a timestamp.
+    pub fn bucket(self, ts_unix_ms: u64) -> String {
+        let days = i64::try_from(ts_unix_ms / 86_400_000).unwrap_or(i64::MAX);
+        match self {
+            Self::Day => {
+                let (y, m, d) = civil_from_days(days);
+                format!("{y:04}-{m:02}-{d:02}")
+            }
+            Self::Week => {
+                // 1970-01-01 was a Thursday.
+                let (y, m, d) = civil_from_days(days - (days + 3).rem_euclid(7));
+                format!("{y:04}-{m:02}-{d:02}")
+            }
+            Self::Month => {
+                let (y, m, _) = civil_from_days(days);
+                format!("{y:04}-{m:02}")
+            }
+        }
+    }
+}
+
+/// Gregorian date of a day count since 1970-01-01 (Howard Hinnant's `civil_from_days`).
+const fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct UsageOptions {
+    /// Entries per list.
+    pub limit: usize,
+    pub period: UsagePeriod,
+}
+
+/// A chunk with how often it was retrieved.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkUsage {
+    /// Logical layer name, as recorded in the audit log.
+    pub layer: String,
+    pub id: u32,
+    pub retrievals: usize,
+    /// Chunk details, when the chunk is still visible in the layers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at_unix_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_preview: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TermCount {
+    pub term: String,
+    pub count: usize,
+}
+
+/// Successful writes by one actor (the MCP client) in one period.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorWrites {
+    pub period: String,
+    pub actor: String,
+    pub writes: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageReport {
+    pub records: usize,
+    pub reads: usize,
+    pub writes: usize,
+    /// Most retrieved chunks, most often first.
+    pub most_retrieved: Vec<ChunkUsage>,
+    /// Visible chunks no logged retrieval returned, oldest first. Empty when the log has no
+    /// retrievals at all, since then nothing can be told apart.
+    pub never_retrieved: Vec<ChunkUsage>,
+    /// Number of never-retrieved chunks before `limit` was applied.
+    pub never_retrieved_total: usize,
+    pub top_terms: Vec<TermCount>,
+    /// Write volume per period and actor, oldest period first.
+    pub writes_by_author: Vec<AuthorWrites>,
+}
+
+fn query_terms(query: &str) -> impl Iterator<Item = String> + '_ {
+    query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map(str::to_lowercase)
+        .filter(|t| t.chars().count() >= 3)
+        .filter(|t| !t.chars().all(|c| c.is_ascii_digit()))
+        .filter(|t| !STOP_WORDS.contains(&t.as_str()))
+}
+
+/// Sorts `counts` by count (descending), then key, and keeps the first `limit`.
+fn top_counts<K: Ord>(counts: HashMap<K, usize>, limit: usize) -> Vec<(K, usize)> {
+    let mut counts: Vec<(K, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(limit);
+    counts
+}
+
+/// Aggregates `records` against the visible chunks of `layers`.
+pub fn usage_report(
+    records: &[AuditRecord],
+    layers: &agentsdb_query::LayerSet,
+    options: UsageOptions,
+) -> anyhow::Result<UsageReport> {
+    let mut retrievals: HashMap<(String, u32), usize> = HashMap::new();
+    let mut terms: HashMap<String, usize> = HashMap::new();
+    let mut writes: BTreeMap<(String, String), usize> = BTreeMap::new();
+    let mut report = UsageReport {
+        records: records.len(),
+        ..UsageReport::default()
+    };
+    for record in records {
+        if record.is_read() {
+            report.reads += 1;
+            // A chunk counts once per call, however many times the call returned it.
+            let hits: HashSet<&RetrievedChunk> = record.retrieved.iter().collect();
+            for hit in hits {
+                *retrievals.entry((hit.layer.clone(), hit.id)).or_default() += 1;
+            }
+            for term in record.query.as_deref().into_iter().flat_map(query_terms) {
+                *terms.entry(term).or_default() += 1;
+            }
+        } else if record.ok {
+            report.writes += 1;
+            let period = options.period.bucket(record.ts_unix_ms);
+            *writes.entry((period, record.actor.clone())).or_default() += 1;
+        }
+    }
+
+    let visible: HashMap<(String, u32), _> = visible_chunks(layers)?
+        .into_iter()
+        .map(|c| ((format!("{:?}", c.layer_id).to_ascii_lowercase(), c.id), c))
+        .collect();
+    let usage = |(layer, id): (String, u32), retrievals: usize| {
+        let chunk = visible.get(&(layer.clone(), id));
+        ChunkUsage {
+            kind: chunk.map(|c| c.kind.clone()),
+            created_at_unix_ms: chunk.map(|c| c.created_at_unix_ms),
+            content_preview: chunk.map(|c| truncate_preview(&c.content, PREVIEW_CHARS)),
+            layer,
+            id,
+            retrievals,
+        }
+    };
+
+    if report.reads > 0 {
+        let mut never: Vec<&(String, u32)> = visible
+            .keys()
+            .filter(|key| !retrievals.contains_key(*key))
+            .collect();
+        never.sort_by_key(|key| {
+            let created = visible.get(*key).map_or(0, |c| c.created_at_unix_ms);
+            (created, key.1, key.0.clone())
+        });
+        report.never_retrieved_total = never.len();
+        report.never_retrieved = never
+            .into_iter()
+            .take(options.limit)
+            .map(|key| usage(key.clone(), 0))
+            .collect();
+    }
+    report.most_retrieved = top_counts(retrievals, options.limit)
+        .into_iter()
+        .map(|(key, count)| usage(key, count))
+        .collect();
+    report.top_terms = top_counts(terms, options.limit)
+        .into_iter()
+        .map(|(term, count)| TermCount { term, count })
+        .collect();
+    report.writes_by_author = writes
+        .into_iter()
+        .map(|((period, actor), writes)| AuthorWrites {
+            period,
+            actor,
+            writes,
+        })
+        .collect();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+
+    fn record(ts_unix_ms: u64, tool: &str, query: Option<&str>, hits: &[u32]) -> AuditRecord {
+        AuditRecord {
+            ts_unix_ms,
+            tool: tool.to_string(),
+            actor: "agent/1.0".to_string(),
+            ok: true,
+            query: query.map(str::to_string),
+            retrieved: hits
+                .iter()
+                .map(|&id| RetrievedChunk {
+                    layer: "base".to_string(),
+                    id,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn buckets_dates_by_day_week_and_month() {
+        // 2024-02-29T12:00:00Z, a Thursday.
+        let ts = 1_709_208_000_000;
+        assert_eq!(UsagePeriod::Day.bucket(ts), "2024-02-29");
+        assert_eq!(UsagePeriod::Week.bucket(ts), "2024-02-26");
+        assert_eq!(UsagePeriod::Month.bucket(ts), "2024-02");
+        assert_eq!(UsagePeriod::Day.bucket(0), "1970-01-01");
+        assert_eq!(UsagePeriod::Week.bucket(0), "1969-12-29");
+    }
+
+    #[test]
+    fn report_ranks_chunks_terms_and_writers() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path().join("AGENTS.db");
+        let chunks = (1..=3)
+            .map(|id| agentsdb_format::ChunkInput {
+                created_at_unix_ms: u64::from(10 - id),
+                ..chunk(id, &format!("chunk {id}"))
+            })
+            .collect();
+        write_layer(&base, chunks)?;
+        let layers = agentsdb_query::LayerSet {
+            base: Some(base.display().to_string()),
+            user: None,
+            delta: None,
+            local: None,
+        };
+
+        let day = 86_400_000;
+        let records = [
+            record(
+                0,
+                "agents_search",
+                Some("How are releases tagged?"),
+                &[1, 1, 2],
+            ),
+            record(1, "agents_search", Some("release tags"), &[1]),
+            record(2, "agents_context_get", None, &[9]),
+            record(3, "agents_context_write", None, &[]),
+            record(day, "agents_context_write", None, &[]),
+        ];
+        let options = UsageOptions {
+            limit: 10,
+            period: UsagePeriod::Day,
+        };
+        let report = usage_report(&records, &layers, options)?;
+        assert_eq!((report.records, report.reads, report.writes), (5, 3, 2));
+
+        let most: Vec<(u32, usize)> = report
+            .most_retrieved
+            .iter()
+            .map(|c| (c.id, c.retrievals))
+            .collect();
+        assert_eq!(most, [(1, 2), (2, 1), (9, 1)]);
+        assert_eq!(report.most_retrieved[0].kind.as_deref(), Some("note"));
+        assert!(report.most_retrieved[2].kind.is_none());
+
+        assert_eq!(report.never_retrieved_total, 1);
+        assert_eq!(report.never_retrieved[0].id, 3);
+
+        let terms: Vec<(&str, usize)> = report
+            .top_terms
+            .iter()
+            .map(|t| (t.term.as_str(), t.count))
+            .collect();
+        assert_eq!(
+            terms,
+            [("release", 1), ("releases", 1), ("tagged", 1), ("tags", 1)]
+        );
+
+        let writes: Vec<(&str, usize)> = report
+            .writes_by_author
+            .iter()
+            .map(|w| (w.period.as_str(), w.writes))
+            .collect();
+        assert_eq!(writes, [("1970-01-01", 1), ("1970-01-02", 1)]);
+
+        let only_writes = usage_report(&records[3..], &layers, options)?;
+        assert!(only_writes.never_retrieved.is_empty());
+        Ok(())
+    }
+}
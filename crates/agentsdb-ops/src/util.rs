@@ -70,3 +70,96 @@ pub fn truncate_preview(s: &str, max_chars: usize) -> String {
     }
     out
 }
+
+/// The `namespace:<name>` source string placing a chunk in `namespace`.
+pub fn namespace_source(namespace: &str) -> anyhow::Result<agentsdb_format::ChunkSource> {
+    let namespace = namespace.trim();
+    if namespace.is_empty() || namespace.chars().any(char::is_whitespace) {
+        anyhow::bail!("namespace must be non-empty and contain no whitespace");
+    }
+    Ok(agentsdb_format::ChunkSource::SourceString(format!(
+        "{}{namespace}",
+        agentsdb_query::NAMESPACE_SOURCE_PREFIX
+    )))
+}
+
+/// Namespace of a chunk with `sources` (see [`agentsdb_query::namespace_of`]).
+pub fn chunk_namespace(sources: &[agentsdb_format::ChunkSource]) -> Option<&str> {
+    sources.iter().find_map(|s| match s {
+        agentsdb_format::ChunkSource::SourceString(v) => {
+            v.strip_prefix(agentsdb_query::NAMESPACE_SOURCE_PREFIX)
+        }
+        agentsdb_format::ChunkSource::ChunkId(_) => None,
+    })
+}
+
+/// Content hash of a chunk imported without its content, from its `redacted:<sha256>` source
+/// string (see [`agentsdb_query::REDACTED_SOURCE_PREFIX`]).
+pub fn redacted_content_sha256(sources: &[agentsdb_format::ChunkSource]) -> Option<&str> {
+    sources.iter().find_map(|s| match s {
+        agentsdb_format::ChunkSource::SourceString(v) => {
+            v.strip_prefix(agentsdb_query::REDACTED_SOURCE_PREFIX)
+        }
+        agentsdb_format::ChunkSource::ChunkId(_) => None,
+    })
+}
+
+/// Removes the `redacted:<sha256>` source string from `sources`, returning its hash.
+pub fn take_redacted_marker(sources: &mut Vec<agentsdb_format::ChunkSource>) -> Option<String> {
+    let hash = redacted_content_sha256(sources)?.to_string();
+    sources.retain(|s| {
+        !matches!(s, agentsdb_format::ChunkSource::SourceString(v)
+            if v.starts_with(agentsdb_query::REDACTED_SOURCE_PREFIX))
+    });
+    Some(hash)
+}
+
+/// The `lang:<tag>` source string recording the language of a chunk with `content`. `language`
+/// is a language tag, or `auto` to detect it from `content` (`None` if it cannot be told).
+pub fn language_source(
+    language: &str,
+    content: &str,
+) -> anyhow::Result<Option<agentsdb_format::ChunkSource>> {
+    let tag = if language.trim().eq_ignore_ascii_case("auto") {
+        match agentsdb_core::language::detect(content) {
+            Some(tag) => tag.to_string(),
+            None => return Ok(None),
+        }
+    } else {
+        agentsdb_core::language::normalize_tag(language).ok_or_else(|| {
+            anyhow::anyhow!("language must be a language tag such as \"en\" or \"de-AT\", or \"auto\" (got {language:?})")
+        })?
+    };
+    Ok(Some(agentsdb_format::ChunkSource::SourceString(format!(
+        "{}{tag}",
+        agentsdb_core::language::LANGUAGE_SOURCE_PREFIX
+    ))))
+}
+
+/// Language tag of a chunk with `sources` (see [`agentsdb_query::language_of`]).
+pub fn chunk_language(sources: &[agentsdb_format::ChunkSource]) -> Option<&str> {
+    sources.iter().find_map(|s| match s {
+        agentsdb_format::ChunkSource::SourceString(v) => {
+            v.strip_prefix(agentsdb_core::language::LANGUAGE_SOURCE_PREFIX)
+        }
+        agentsdb_format::ChunkSource::ChunkId(_) => None,
+    })
+}
+
+/// The `identity:<label>` source string recording `identity` as the writer of a chunk.
+pub fn identity_source(identity: &str) -> agentsdb_format::ChunkSource {
+    agentsdb_format::ChunkSource::SourceString(format!(
+        "{}{identity}",
+        agentsdb_query::IDENTITY_SOURCE_PREFIX
+    ))
+}
+
+/// Identity of whoever wrote a chunk with `sources` (see [`agentsdb_query::identity_of`]).
+pub fn chunk_identity(sources: &[agentsdb_format::ChunkSource]) -> Option<&str> {
+    sources.iter().find_map(|s| match s {
+        agentsdb_format::ChunkSource::SourceString(v) => {
+            v.strip_prefix(agentsdb_query::IDENTITY_SOURCE_PREFIX)
+        }
+        agentsdb_format::ChunkSource::ChunkId(_) => None,
+    })
+}
@@ -0,0 +1,435 @@
+//! Promotions into the base layer opened as pull requests.
+//!
+//! `AGENTS.db` is committed with the code it describes, so changing it should go through code
+//! review. [`promote_via_pr`] rebuilds the base layer with the promoted chunks (see
+//! [`rebuild_base`](crate::promote::rebuild_base)), commits it to a new branch through a
+//! [`VcsProvider`] and opens a pull request (a merge request on GitLab) whose body summarises
+//! the chunks it adds. Nothing is written locally.
+//!
+//! [`provider_for_dir`] picks the provider and repository from the `origin` remote of the git
+//! repository holding the layers; `AGENTSDB_PR_PROVIDER` (`github` or `gitlab`),
+//! `AGENTSDB_PR_REPO` (`owner/name`) and `AGENTSDB_PR_API_URL` override what it detects. The
+//! token comes from `GITHUB_TOKEN` (or `GH_TOKEN`) and `GITLAB_TOKEN`.
+
+use anyhow::Context;
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+use crate::diff::LayerDiff;
+use crate::util::{now_unix_ms, truncate_preview};
+
+#[cfg(feature = "pr")]
+mod github;
+#[cfg(feature = "pr")]
+mod gitlab;
+
+/// Environment variable naming the provider (`github` or `gitlab`).
+pub const PROVIDER_ENV: &str = "AGENTSDB_PR_PROVIDER";
+/// Environment variable naming the repository (`owner/name`, or a GitLab project path).
+pub const REPO_ENV: &str = "AGENTSDB_PR_REPO";
+/// Environment variable naming the provider's API base URL.
+pub const API_URL_ENV: &str = "AGENTSDB_PR_API_URL";
+/// Environment variable naming the branch pull requests target (default: the repository's
+/// default branch).
+pub const BASE_BRANCH_ENV: &str = "AGENTSDB_PR_BASE";
+
+/// A file committed by a [`PullRequest`].
+#[derive(Debug, Clone)]
+pub struct PullRequestFile {
+    /// Path in the repository, `/`-separated.
+    pub path: String,
+    pub contents: Vec<u8>,
+}
+
+/// A branch to create, and the pull request to open from it.
+#[derive(Debug, Clone)]
+pub struct PullRequest {
+    pub branch: String,
+    /// Branch to merge into; `None` for the repository's default branch.
+    pub base: Option<String>,
+    pub title: String,
+    /// Markdown body.
+    pub body: String,
+    pub files: Vec<PullRequestFile>,
+}
+
+/// A pull request a [`VcsProvider`] opened.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenedPullRequest {
+    pub provider: String,
+    pub url: String,
+    pub branch: String,
+    pub base: String,
+}
+
+/// A code host that can open pull requests.
+pub trait VcsProvider {
+    /// Name shown to users, e.g. `github`.
+    fn name(&self) -> &str;
+
+    /// Creates `request.branch` from the base branch, commits `request.files` to it in one
+    /// commit and opens the pull request.
+    fn open_pull_request(&self, request: &PullRequest) -> anyhow::Result<OpenedPullRequest>;
+}
+
+/// Outcome of [`promote_via_pr`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PrPromotion {
+    pub promoted: Vec<u32>,
+    pub skipped: Vec<u32>,
+    /// Absent when every requested chunk was already in the base layer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<OpenedPullRequest>,
+}
+
+/// Opens a pull request adding chunks `ids` of `from_path` to the base layer at `base_path`.
+///
+/// The source layer is left as is: the chunks only reach the base layer when the pull request
+/// is merged.
+pub fn promote_via_pr(
+    provider: &dyn VcsProvider,
+    from_path: &Path,
+    base_path: &Path,
+    ids: &[u32],
+) -> anyhow::Result<PrPromotion> {
+    let mut rebuilt = crate::promote::rebuild_base(base_path, from_path, ids)?;
+    if rebuilt.promoted.is_empty() {
+        return Ok(PrPromotion {
+            promoted: rebuilt.promoted,
+            skipped: rebuilt.skipped,
+            pull_request: None,
+        });
+    }
+
+    // Written next to the base layer only to diff it and read it back.
+    let name = base_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("AGENTS.db");
+    let staged = base_path.with_file_name(format!(".{name}.pr"));
+    rebuilt.write(&staged)?;
+    let staged_result = crate::diff::diff_layers(&staged, base_path).and_then(|diff| {
+        let contents =
+            std::fs::read(&staged).with_context(|| format!("read {}", staged.display()))?;
+        Ok((diff, contents))
+    });
+    let _ = std::fs::remove_file(&staged);
+    let (diff, contents) = staged_result?;
+
+    let path = repo_path(base_path)?;
+    let from = from_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("the source layer");
+    let request = PullRequest {
+        branch: format!("agentsdb/promote-{}", now_unix_ms()),
+        base: std::env::var(BASE_BRANCH_ENV)
+            .ok()
+            .filter(|b| !b.is_empty()),
+        title: format!("Promote {} chunks into {path}", rebuilt.promoted.len()),
+        body: summary(&diff, from, &path, &rebuilt.skipped),
+        files: vec![PullRequestFile { path, contents }],
+    };
+    let opened = provider.open_pull_request(&request)?;
+    Ok(PrPromotion {
+        promoted: rebuilt.promoted,
+        skipped: rebuilt.skipped,
+        pull_request: Some(opened),
+    })
+}
+
+/// Markdown body listing what the pull request changes in the layer at `path`.
+fn summary(diff: &LayerDiff, from: &str, path: &str, skipped: &[u32]) -> String {
+    let mut out = format!(
+        "Promotes {} chunks from `{from}` into `{path}`.\n\n| id | kind | content |\n|---:|---|---|\n",
+        diff.added.len()
+    );
+    for chunk in &diff.added {
+        let Some(side) = &chunk.from else {
+            continue;
+        };
+        let content = truncate_preview(&side.content, 160)
+            .replace('|', "\\|")
+            .replace('\n', " ");
+        out.push_str(&format!("| {} | `{}` | {content} |\n", chunk.id, side.kind));
+    }
+    if !skipped.is_empty() {
+        let ids: Vec<String> = skipped.iter().map(u32::to_string).collect();
+        out.push_str(&format!("\nAlready in `{path}`: {}.\n", ids.join(", ")));
+    }
+    out.push_str(&format!(
+        "\n{} chunks of `{path}` are unchanged.\n",
+        diff.unchanged
+    ));
+    out
+}
+
+/// `path` relative to the root of the git repository holding it, `/`-separated; its file name
+/// when it is not in a repository.
+fn repo_path(path: &Path) -> anyhow::Result<String> {
+    let path =
+        std::fs::canonicalize(path).with_context(|| format!("canonicalize {}", path.display()))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let relative = git(dir, &["rev-parse", "--show-toplevel"])
+        .ok()
+        .and_then(|top| std::fs::canonicalize(top.trim()).ok())
+        .and_then(|top| path.strip_prefix(top).ok().map(Path::to_path_buf));
+    let relative = relative
+        .or_else(|| path.file_name().map(Into::into))
+        .context("layer path has no file name")?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Ok(parts.join("/"))
+}
+
+fn git(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// A code host and repository, as detected by [`detect_repository`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Repository {
+    provider: String,
+    host: String,
+    /// `owner/name`, or a GitLab project path with subgroups.
+    path: String,
+}
+
+/// Splits a git remote URL (`https://host/owner/name.git`, `git@host:owner/name.git` or
+/// `ssh://git@host/owner/name`) into its host and repository path.
+fn parse_remote(url: &str) -> Option<(String, String)> {
+    let url = url.trim();
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        None => url.split_once(':')?,
+    };
+    let (host, path) = rest;
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_string();
+    let path = path.trim_matches('/').trim_end_matches(".git").to_string();
+    (!host.is_empty() && path.contains('/')).then_some((host, path))
+}
+
+/// The provider and repository for the layers in `dir`, from the overrides or the `origin`
+/// remote.
+fn detect_repository(dir: &Path) -> anyhow::Result<Repository> {
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let remote = git(dir, &["remote", "get-url", "origin"])
+        .ok()
+        .and_then(|url| parse_remote(&url));
+    let host = remote.as_ref().map(|(host, _)| host.clone());
+    let provider = match (env(PROVIDER_ENV), host.as_deref()) {
+        (Some(provider), _) => provider,
+        (None, Some(host)) if host.contains("github") => "github".to_string(),
+        (None, Some(host)) if host.contains("gitlab") => "gitlab".to_string(),
+        (None, Some(host)) => anyhow::bail!(
+            "cannot tell which code host {host} is (set {PROVIDER_ENV} to github or gitlab)"
+        ),
+        (None, None) => anyhow::bail!(
+            "no `origin` remote in {} (set {PROVIDER_ENV} and {REPO_ENV})",
+            dir.display()
+        ),
+    };
+    let path = env(REPO_ENV)
+        .or_else(|| remote.map(|(_, path)| path))
+        .with_context(|| format!("cannot tell the repository (set {REPO_ENV})"))?;
+    let host = host.unwrap_or_else(|| format!("{provider}.com"));
+    Ok(Repository {
+        provider,
+        host,
+        path,
+    })
+}
+
+/// The provider for the repository holding the layers in `dir`; see the module docs.
+pub fn provider_for_dir(dir: &Path) -> anyhow::Result<Box<dyn VcsProvider>> {
+    let repository = detect_repository(dir)?;
+    provider_for(&repository)
+}
+
+#[cfg(feature = "pr")]
+fn provider_for(repository: &Repository) -> anyhow::Result<Box<dyn VcsProvider>> {
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    match repository.provider.as_str() {
+        "github" => {
+            let token = env("GITHUB_TOKEN")
+                .or_else(|| env("GH_TOKEN"))
+                .context("opening a GitHub pull request needs GITHUB_TOKEN or GH_TOKEN")?;
+            let api = env(API_URL_ENV).unwrap_or_else(|| match repository.host.as_str() {
+                "github.com" => "https://api.github.com".to_string(),
+                host => format!("https://{host}/api/v3"),
+            });
+            Ok(Box::new(github::GitHub::new(&api, &repository.path, token)))
+        }
+        "gitlab" => {
+            let token =
+                env("GITLAB_TOKEN").context("opening a GitLab merge request needs GITLAB_TOKEN")?;
+            let api =
+                env(API_URL_ENV).unwrap_or_else(|| format!("https://{}/api/v4", repository.host));
+            Ok(Box::new(gitlab::GitLab::new(&api, &repository.path, token)))
+        }
+        other => anyhow::bail!("unknown {PROVIDER_ENV} {other:?} (expected github or gitlab)"),
+    }
+}
+
+#[cfg(not(feature = "pr"))]
+fn provider_for(repository: &Repository) -> anyhow::Result<Box<dyn VcsProvider>> {
+    anyhow::bail!(
+        "opening {} pull requests needs a build with the `pr` feature",
+        repository.provider
+    )
+}
+
+/// Standard base64 (with padding), as the providers' file APIs expect.
+#[cfg_attr(not(feature = "pr"), allow(dead_code))]
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let b0 = group[0];
+        let b1 = group.get(1).copied().unwrap_or(0);
+        let b2 = group.get(2).copied().unwrap_or(0);
+        let sextets = [
+            b0 >> 2,
+            ((b0 & 0x03) << 4) | (b1 >> 4),
+            ((b1 & 0x0f) << 2) | (b2 >> 6),
+            b2 & 0x3f,
+        ];
+        for (i, sextet) in sextets.into_iter().enumerate() {
+            if i <= group.len() {
+                out.push(char::from(ALPHABET[usize::from(sextet)]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Percent-encodes `s` for a URL path or query, keeping `/` when `keep_slash`.
+#[cfg_attr(not(feature = "pr"), allow(dead_code))]
+fn url_encode(s: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric()
+            || matches!(b, b'-' | b'_' | b'.' | b'~')
+            || (keep_slash && b == b'/')
+        {
+            out.push(char::from(b));
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, write_layer};
+    use agentsdb_format::ChunkInput;
+    use std::cell::RefCell;
+
+    struct Recorder(RefCell<Vec<PullRequest>>);
+
+    impl VcsProvider for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn open_pull_request(&self, request: &PullRequest) -> anyhow::Result<OpenedPullRequest> {
+            self.0.borrow_mut().push(request.clone());
+            Ok(OpenedPullRequest {
+                provider: self.name().to_string(),
+                url: "https://example.com/pr/1".to_string(),
+                branch: request.branch.clone(),
+                base: "main".to_string(),
+            })
+        }
+    }
+
+    #[test]
+    fn promotion_pull_requests_carry_the_rebuilt_base_and_a_summary() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let by_mcp = |id: u32, content: &str| ChunkInput {
+            author: "mcp".to_string(),
+            ..chunk(id, content)
+        };
+        let base = dir.path().join("AGENTS.db");
+        let delta = dir.path().join("AGENTS.delta.db");
+        write_layer(&base, vec![by_mcp(1, "kept")])?;
+        write_layer(
+            &delta,
+            vec![by_mcp(1, "kept"), by_mcp(2, "deploys | run from CI")],
+        )?;
+        let base_before = std::fs::read(&base)?;
+
+        let recorder = Recorder(RefCell::new(Vec::new()));
+        let out = promote_via_pr(&recorder, &delta, &base, &[1, 2])?;
+        assert_eq!(out.promoted, [2]);
+        assert_eq!(out.skipped, [1]);
+        assert!(out.pull_request.is_some());
+        assert_eq!(std::fs::read(&base)?, base_before);
+
+        let requests = recorder.0.borrow().clone();
+        let [request] = requests.as_slice() else {
+            anyhow::bail!("expected one pull request, got {}", requests.len());
+        };
+        assert!(request
+            .body
+            .contains("| 2 | `note` | deploys \\| run from CI |"));
+        let [file] = request.files.as_slice() else {
+            anyhow::bail!("expected one file");
+        };
+        assert!(file.path.ends_with("AGENTS.db"));
+        let staged = dir.path().join("rebuilt.db");
+        std::fs::write(&staged, &file.contents)?;
+        let stored = agentsdb_format::read_all_chunks(&agentsdb_format::LayerFile::open(&staged)?)?;
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[1].author, "human");
+
+        // Nothing new to promote: no pull request.
+        let again = promote_via_pr(&recorder, &delta, &base, &[1])?;
+        assert!(again.pull_request.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn remotes_and_encodings() {
+        let github = Some(("github.com".to_string(), "acme/app".to_string()));
+        assert_eq!(parse_remote("https://github.com/acme/app.git"), github);
+        assert_eq!(parse_remote("git@github.com:acme/app.git\n"), github);
+        assert_eq!(parse_remote("ssh://git@github.com:22/acme/app"), github);
+        assert_eq!(
+            parse_remote("https://gitlab.example.com/group/sub/app"),
+            Some((
+                "gitlab.example.com".to_string(),
+                "group/sub/app".to_string()
+            ))
+        );
+        assert_eq!(parse_remote("/srv/git/app"), None);
+
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(url_encode("group/sub app", false), "group%2Fsub%20app");
+        assert_eq!(url_encode("docs/AGENTS.db", true), "docs/AGENTS.db");
+    }
+}
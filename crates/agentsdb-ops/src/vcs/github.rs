@@ -0,0 +1,137 @@
+//! GitHub (and GitHub Enterprise) through the REST API.
+
+use anyhow::Context;
+use serde_json::{json, Value};
+
+use super::{base64, url_encode, OpenedPullRequest, PullRequest, VcsProvider};
+
+pub(super) struct GitHub {
+    api: String,
+    /// `owner/name`.
+    repo: String,
+    token: String,
+}
+
+impl GitHub {
+    pub(super) fn new(api: &str, repo: &str, token: String) -> Self {
+        Self {
+            api: api.trim_end_matches('/').to_string(),
+            repo: repo.to_string(),
+            token,
+        }
+    }
+
+    /// A request for `suffix` under the repository's API URL.
+    fn request(&self, method: &str, suffix: &str) -> ureq::Request {
+        ureq::request(method, &format!("{}/repos/{}{suffix}", self.api, self.repo))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .set("Accept", "application/vnd.github+json")
+            .set("User-Agent", "agentsdb")
+            .set("Content-Type", "application/json")
+    }
+
+    fn json(response: ureq::Response) -> anyhow::Result<Value> {
+        serde_json::from_str(&response.into_string()?).context("parse GitHub response")
+    }
+
+    fn default_branch(&self) -> anyhow::Result<String> {
+        let repo = Self::json(
+            self.request("GET", "")
+                .call()
+                .with_context(|| format!("get GitHub repository {}", self.repo))?,
+        )?;
+        repo.get("default_branch")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .context("GitHub repository has no default branch")
+    }
+
+    /// Blob sha of `path` on `branch`, which updating an existing file must name.
+    fn file_sha(&self, path: &str, branch: &str) -> anyhow::Result<Option<String>> {
+        let suffix = format!(
+            "/contents/{}?ref={}",
+            url_encode(path, true),
+            url_encode(branch, false)
+        );
+        match self.request("GET", &suffix).call() {
+            Ok(response) => Ok(Self::json(response)?
+                .get("sha")
+                .and_then(Value::as_str)
+                .map(str::to_string)),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("get {path} on GitHub")),
+        }
+    }
+}
+
+impl VcsProvider for GitHub {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn open_pull_request(&self, request: &PullRequest) -> anyhow::Result<OpenedPullRequest> {
+        let base = match &request.base {
+            Some(base) => base.clone(),
+            None => self.default_branch()?,
+        };
+        let head = Self::json(
+            self.request(
+                "GET",
+                &format!("/git/ref/heads/{}", url_encode(&base, true)),
+            )
+            .call()
+            .with_context(|| format!("get branch {base} on GitHub"))?,
+        )?;
+        let sha = head
+            .pointer("/object/sha")
+            .and_then(Value::as_str)
+            .with_context(|| format!("GitHub branch {base} has no commit"))?;
+        self.request("POST", "/git/refs")
+            .send_string(
+                &json!({ "ref": format!("refs/heads/{}", request.branch), "sha": sha }).to_string(),
+            )
+            .with_context(|| format!("create branch {} on GitHub", request.branch))?;
+
+        // The contents API commits one file at a time; a promotion changes a single layer.
+        for file in &request.files {
+            let mut body = json!({
+                "message": request.title,
+                "content": base64(&file.contents),
+                "branch": request.branch,
+            });
+            if let Some(sha) = self.file_sha(&file.path, &request.branch)? {
+                body["sha"] = Value::String(sha);
+            }
+            self.request(
+                "PUT",
+                &format!("/contents/{}", url_encode(&file.path, true)),
+            )
+            .send_string(&body.to_string())
+            .with_context(|| format!("commit {} on GitHub", file.path))?;
+        }
+
+        let pull = Self::json(
+            self.request("POST", "/pulls")
+                .send_string(
+                    &json!({
+                        "title": request.title,
+                        "head": request.branch,
+                        "base": base,
+                        "body": request.body,
+                    })
+                    .to_string(),
+                )
+                .context("open GitHub pull request")?,
+        )?;
+        let url = pull
+            .get("html_url")
+            .and_then(Value::as_str)
+            .context("GitHub pull request has no URL")?;
+        Ok(OpenedPullRequest {
+            provider: self.name().to_string(),
+            url: url.to_string(),
+            branch: request.branch.clone(),
+            base,
+        })
+    }
+}
@@ -0,0 +1,130 @@
+//! GitLab (gitlab.com or self-managed) through the REST API.
+
+use anyhow::Context;
+use serde_json::{json, Value};
+
+use super::{base64, url_encode, OpenedPullRequest, PullRequest, VcsProvider};
+
+pub(super) struct GitLab {
+    api: String,
+    /// Project path, percent-encoded as a single path segment.
+    project: String,
+    token: String,
+}
+
+impl GitLab {
+    pub(super) fn new(api: &str, project: &str, token: String) -> Self {
+        Self {
+            api: api.trim_end_matches('/').to_string(),
+            project: url_encode(project, false),
+            token,
+        }
+    }
+
+    /// A request for `suffix` under the project's API URL.
+    fn request(&self, method: &str, suffix: &str) -> ureq::Request {
+        ureq::request(
+            method,
+            &format!("{}/projects/{}{suffix}", self.api, self.project),
+        )
+        .set("PRIVATE-TOKEN", &self.token)
+        .set("Content-Type", "application/json")
+    }
+
+    fn json(response: ureq::Response) -> anyhow::Result<Value> {
+        serde_json::from_str(&response.into_string()?).context("parse GitLab response")
+    }
+
+    fn default_branch(&self) -> anyhow::Result<String> {
+        let project = Self::json(
+            self.request("GET", "")
+                .call()
+                .context("get GitLab project")?,
+        )?;
+        project
+            .get("default_branch")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .context("GitLab project has no default branch")
+    }
+
+    fn file_exists(&self, path: &str, branch: &str) -> anyhow::Result<bool> {
+        let suffix = format!(
+            "/repository/files/{}?ref={}",
+            url_encode(path, false),
+            url_encode(branch, false)
+        );
+        match self.request("HEAD", &suffix).call() {
+            Ok(_) => Ok(true),
+            Err(ureq::Error::Status(404, _)) => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("get {path} on GitLab")),
+        }
+    }
+}
+
+impl VcsProvider for GitLab {
+    fn name(&self) -> &str {
+        "gitlab"
+    }
+
+    fn open_pull_request(&self, request: &PullRequest) -> anyhow::Result<OpenedPullRequest> {
+        let base = match &request.base {
+            Some(base) => base.clone(),
+            None => self.default_branch()?,
+        };
+        // One commit creates the branch from `base` with every file.
+        let actions = request
+            .files
+            .iter()
+            .map(|file| {
+                let action = if self.file_exists(&file.path, &base)? {
+                    "update"
+                } else {
+                    "create"
+                };
+                Ok(json!({
+                    "action": action,
+                    "file_path": file.path,
+                    "content": base64(&file.contents),
+                    "encoding": "base64",
+                }))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.request("POST", "/repository/commits")
+            .send_string(
+                &json!({
+                    "branch": request.branch,
+                    "start_branch": base,
+                    "commit_message": request.title,
+                    "actions": actions,
+                })
+                .to_string(),
+            )
+            .with_context(|| format!("commit to branch {} on GitLab", request.branch))?;
+
+        let merge_request = Self::json(
+            self.request("POST", "/merge_requests")
+                .send_string(
+                    &json!({
+                        "source_branch": request.branch,
+                        "target_branch": base,
+                        "title": request.title,
+                        "description": request.body,
+                        "remove_source_branch": true,
+                    })
+                    .to_string(),
+                )
+                .context("open GitLab merge request")?,
+        )?;
+        let url = merge_request
+            .get("web_url")
+            .and_then(Value::as_str)
+            .context("GitLab merge request has no URL")?;
+        Ok(OpenedPullRequest {
+            provider: self.name().to_string(),
+            url: url.to_string(),
+            branch: request.branch.clone(),
+            base,
+        })
+    }
+}
@@ -0,0 +1,262 @@
+//! A registry of project roots searched together.
+//!
+//! `agentsdb workspace add/remove/list` maintain the registry, a JSON file in the user's config
+//! directory (`AGENTSDB_WORKSPACE` names another file). `agentsdb search --workspace` and the MCP
+//! server's `agents_workspace_search` run one search per registered project and merge the hits,
+//! each attributed to its project.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use agentsdb_core::types::SearchResult;
+use agentsdb_query::LayerSet;
+
+use crate::search::{search_layers, SearchConfig};
+
+/// Environment variable naming the registry file, overriding the default location.
+pub const WORKSPACE_ENV: &str = "AGENTSDB_WORKSPACE";
+
+/// One registered project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceProject {
+    pub name: String,
+    /// Canonical path of the directory holding the project's standard layers.
+    pub root: PathBuf,
+}
+
+/// The registered projects, in registration order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Workspace {
+    #[serde(default)]
+    pub projects: Vec<WorkspaceProject>,
+}
+
+/// Where the registry lives: `$AGENTSDB_WORKSPACE`, else `agentsdb/workspace.json` in
+/// `$XDG_CONFIG_HOME` (`%APPDATA%` on Windows, `~/.config` otherwise).
+pub fn registry_path() -> anyhow::Result<PathBuf> {
+    if let Some(path) = std::env::var_os(WORKSPACE_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+    let file = |dir: PathBuf| dir.join("agentsdb").join("workspace.json");
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Ok(file(PathBuf::from(dir)));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = std::env::var_os("APPDATA") {
+            return Ok(file(PathBuf::from(dir)));
+        }
+    }
+    if let Some(home) = std::env::var_os("HOME") {
+        return Ok(file(PathBuf::from(home).join(".config")));
+    }
+    anyhow::bail!("unable to determine the workspace registry path (set {WORKSPACE_ENV} or HOME)")
+}
+
+impl Workspace {
+    /// Reads the registry at `path`; a missing file is an empty workspace.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let bytes = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        serde_json::from_slice(&bytes).with_context(|| format!("parse {}", path.display()))
+    }
+
+    /// Writes the registry to `path`, creating its directory.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        }
+        let json = serde_json::to_vec_pretty(self).context("serialize workspace")?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).with_context(|| format!("write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("replace {}", path.display()))
+    }
+
+    /// Registers the project at `root` as `name` (default: the directory's name). Registering a
+    /// root again renames it.
+    pub fn add(&mut self, root: &Path, name: Option<&str>) -> anyhow::Result<WorkspaceProject> {
+        let root = std::fs::canonicalize(root)
+            .with_context(|| format!("canonicalize {}", root.display()))?;
+        if !root.join("AGENTS.db").exists() {
+            anyhow::bail!("{} has no AGENTS.db", root.display());
+        }
+        let name = match name {
+            Some(name) => name.trim().to_string(),
+            None => root
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("project")
+                .to_string(),
+        };
+        if name.is_empty() || name.chars().any(|c| c.is_whitespace() || c == '/') {
+            anyhow::bail!("project name {name:?} must be non-empty, without whitespace or '/'");
+        }
+        if let Some(other) = self
+            .projects
+            .iter()
+            .find(|p| p.name == name && p.root != root)
+        {
+            anyhow::bail!(
+                "project name {name:?} is already used by {} (pass --name)",
+                other.root.display()
+            );
+        }
+        let project = WorkspaceProject { name, root };
+        match self.projects.iter_mut().find(|p| p.root == project.root) {
+            Some(existing) => existing.name.clone_from(&project.name),
+            None => self.projects.push(project.clone()),
+        }
+        Ok(project)
+    }
+
+    /// Unregisters the project named `project`, or registered at that path.
+    pub fn remove(&mut self, project: &str) -> Option<WorkspaceProject> {
+        let root = std::fs::canonicalize(project).ok();
+        let index = self
+            .projects
+            .iter()
+            .position(|p| p.name == project || root.as_deref() == Some(p.root.as_path()))?;
+        Some(self.projects.remove(index))
+    }
+}
+
+/// The standard layers present in `root`.
+pub fn layers_in(root: &Path) -> LayerSet {
+    let paths = agentsdb_embeddings::config::standard_layer_paths_for_dir(root);
+    let existing = |path: PathBuf| path.exists().then(|| path.to_string_lossy().into_owned());
+    LayerSet {
+        base: existing(paths.base),
+        user: existing(paths.user),
+        delta: existing(paths.delta),
+        local: existing(paths.local),
+    }
+}
+
+/// A search hit, attributed to the project it came from.
+#[derive(Debug, Clone)]
+pub struct WorkspaceHit {
+    pub project: String,
+    pub root: PathBuf,
+    pub result: SearchResult,
+}
+
+/// A project a workspace search could not search.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectError {
+    pub project: String,
+    pub error: String,
+}
+
+/// Result of [`search_workspace`].
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceSearch {
+    /// The best `k` hits across all projects, best first.
+    pub hits: Vec<WorkspaceHit>,
+    /// Projects that were skipped, e.g. because their layers are gone or use another embedding
+    /// dimension than `query_vec`.
+    pub errors: Vec<ProjectError>,
+}
+
+/// Searches every project in `workspace` with `config` and keeps the best `config.k` hits.
+///
+/// Each project embeds the query with its own embedder, so hybrid and semantic scores from
+/// different projects are only roughly comparable.
+pub fn search_workspace(workspace: &Workspace, config: &SearchConfig) -> WorkspaceSearch {
+    let mut out = WorkspaceSearch::default();
+    for project in &workspace.projects {
+        let searched = search_layers(&layers_in(&project.root), config.clone())
+            .with_context(|| format!("search {}", project.root.display()));
+        match searched {
+            Ok(results) => out
+                .hits
+                .extend(results.into_iter().map(|result| WorkspaceHit {
+                    project: project.name.clone(),
+                    root: project.root.clone(),
+                    result,
+                })),
+            Err(err) => out.errors.push(ProjectError {
+                project: project.name.clone(),
+                error: format!("{err:#}"),
+            }),
+        }
+    }
+    out.hits
+        .sort_by(|a, b| b.result.score.total_cmp(&a.result.score));
+    out.hits.truncate(config.k);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(dir: &Path) -> anyhow::Result<()> {
+        let schema = agentsdb_format::LayerSchema {
+            dim: 8,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let mut chunks = vec![agentsdb_format::ChunkInput {
+            id: 1,
+            kind: "note".to_string(),
+            content: format!("deploy steps for {}", dir.display()),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: 1,
+            embedding: vec![0.5; 8],
+            sources: Vec::new(),
+        }];
+        agentsdb_format::write_layer_atomic(dir.join("AGENTS.db"), &schema, &mut chunks, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn registry_round_trips_and_search_attributes_projects() -> anyhow::Result<()> {
+        let a = tempfile::tempdir()?;
+        let b = tempfile::tempdir()?;
+        project(a.path())?;
+        project(b.path())?;
+        let registry = a.path().join("config").join("workspace.json");
+
+        let mut workspace = Workspace::load(&registry)?;
+        assert!(workspace.projects.is_empty());
+        workspace.add(a.path(), Some("alpha"))?;
+        workspace.add(b.path(), Some("beta"))?;
+        assert!(workspace.add(a.path(), Some("beta")).is_err());
+        workspace.add(a.path(), Some("gamma"))?;
+        workspace.save(&registry)?;
+
+        let mut workspace = Workspace::load(&registry)?;
+        let names: Vec<&str> = workspace.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["gamma", "beta"]);
+
+        let config = SearchConfig {
+            query: None,
+            query_vec: Some(vec![0.5; 8]),
+            k: 10,
+            kinds: Vec::new(),
+            namespace: None,
+            language: None,
+            use_index: false,
+            mode: agentsdb_query::SearchMode::Semantic,
+            cache_results: false,
+            disk_cache: false,
+        };
+        let found = search_workspace(&workspace, &config);
+        assert!(found.errors.is_empty(), "{:?}", found.errors);
+        let mut projects: Vec<&str> = found.hits.iter().map(|h| h.project.as_str()).collect();
+        projects.sort_unstable();
+        assert_eq!(projects, ["beta", "gamma"]);
+
+        assert!(workspace.remove("gamma").is_some());
+        assert!(workspace.remove("gamma").is_none());
+        std::fs::remove_file(b.path().join("AGENTS.db"))?;
+        let found = search_workspace(&workspace, &config);
+        assert!(found.hits.is_empty());
+        assert_eq!(found.errors.len(), 1);
+        Ok(())
+    }
+}
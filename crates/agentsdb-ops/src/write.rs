@@ -2,9 +2,13 @@ use anyhow::Context;
 use std::path::Path;
 
 use agentsdb_embeddings::config::get_immutable_embedding_options;
+use agentsdb_embeddings::embedder::EmbedPurpose;
 use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
-use agentsdb_format::{ChunkInput, ChunkSource, LayerFile};
+use agentsdb_embeddings::layer_metadata::ProfileMismatchError;
+use agentsdb_format::{ChunkInput, ChunkSource, LayerFile, WritePolicy};
 
+use crate::error::WriteError;
+use crate::hooks::WriteTarget;
 use crate::util::now_unix_ms;
 
 /// Append a chunk to a layer file (local or delta)
@@ -25,6 +29,7 @@ use crate::util::now_unix_ms;
 /// # Returns
 /// The assigned chunk ID
 #[allow(clippy::too_many_arguments)]
+#[tracing::instrument(level = "debug", skip_all, fields(path = %path.display(), scope = scope, kind = kind))]
 pub fn append_chunk(
     path: &Path,
     scope: &str,
@@ -37,39 +42,63 @@ pub fn append_chunk(
     source_chunks: &[u32],
     tool_name: &str,
     tool_version: &str,
-) -> anyhow::Result<u32> {
-    let file_name = path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or_default();
-    if !matches!(file_name, "AGENTS.local.db" | "AGENTS.delta.db") {
-        anyhow::bail!("writes are only allowed for AGENTS.local.db / AGENTS.delta.db");
+) -> Result<u32, WriteError> {
+    let policy = WritePolicy::standard();
+    if !policy
+        .scope_of(path)
+        .is_some_and(|s| policy.is_writable_scope(s))
+    {
+        return Err(WriteError::NotWritable {
+            path: path.to_path_buf(),
+        });
     }
-    if scope == "local" && file_name != "AGENTS.local.db" {
-        anyhow::bail!("scope local only allowed for AGENTS.local.db");
-    }
-    if scope == "delta" && file_name != "AGENTS.delta.db" {
-        anyhow::bail!("scope delta only allowed for AGENTS.delta.db");
+    if let Some(expected) = policy.writable_file_name(scope) {
+        if policy.scope_of(path) != Some(scope) {
+            return Err(WriteError::ScopeMismatch {
+                scope: scope.to_string(),
+                expected: expected.to_string(),
+            });
+        }
     }
+    let mut chunk = NewChunk {
+        kind: kind.to_string(),
+        content: content.to_string(),
+        confidence,
+        sources: sources
+            .iter()
+            .map(|s| ChunkSource::SourceString(s.clone()))
+            .chain(source_chunks.iter().map(|id| ChunkSource::ChunkId(*id)))
+            .collect(),
+    };
+    crate::hooks::run(&WriteTarget::of(path), &mut chunk)?;
+    let NewChunk {
+        kind,
+        content,
+        confidence,
+        sources,
+    } = chunk;
+    crate::kinds::check_kind_for_layer(path, &kind)?;
 
     let exists = path.exists();
     let dir = path.parent().unwrap_or_else(|| Path::new("."));
 
-    let embedder_for_dim = |dim_usize: usize| -> anyhow::Result<
+    let embedder_for_dim = |dim_usize: usize| -> Result<
         Box<dyn agentsdb_embeddings::embedder::Embedder + Send + Sync>,
+        WriteError,
     > {
-        let options = get_immutable_embedding_options(dir)
-            .context("get immutable embedding options")?;
+        let options =
+            get_immutable_embedding_options(dir).context("get immutable embedding options")?;
         if let Some(cfg_dim) = options.dim {
             if cfg_dim != dim_usize {
-                anyhow::bail!(
-                    "embedding dim mismatch (layer is dim={dim_usize}, options specify dim={cfg_dim})"
-                );
+                return Err(WriteError::DimMismatch {
+                    layer: dim_usize,
+                    configured: cfg_dim,
+                });
             }
         }
-        options
+        Ok(options
             .into_embedder(dim_usize)
-            .context("resolve embedder from options")
+            .context("resolve embedder from options")?)
     };
 
     if exists {
@@ -79,17 +108,17 @@ pub fn append_chunk(
 
         let mut chunk = ChunkInput {
             id: id.unwrap_or(0), // 0 = auto-assign
-            kind: kind.to_string(),
+            kind,
             author: "human".to_string(),
             confidence,
             created_at_unix_ms: now_unix_ms(),
-            content: content.to_string(),
+            content,
             embedding: Vec::new(),
-            sources: Vec::new(),
+            sources,
         };
         let embedder = embedder_for_dim(dim_usize)?;
         chunk.embedding = embedder
-            .embed(&[chunk.content.clone()])?
+            .embed_for(EmbedPurpose::Document, &[chunk.content.clone()])?
             .into_iter()
             .next()
             .unwrap_or_else(|| vec![0.0; dim_usize]);
@@ -100,23 +129,17 @@ pub fn append_chunk(
             .to_json_bytes()
             .context("serialize layer metadata")?;
 
-        for s in sources.iter() {
-            chunk.sources.push(ChunkSource::SourceString(s.to_string()));
-        }
-        for cid in source_chunks.iter() {
-            chunk.sources.push(ChunkSource::ChunkId(*cid));
-        }
-
         let mut new_chunks = vec![chunk];
         let assigned = if let Some(existing) = file.layer_metadata_bytes() {
             let existing = LayerMetadataV1::from_json_bytes(existing)
                 .context("parse existing layer metadata")?;
             if existing.embedding_profile != *embedder.profile() {
-                anyhow::bail!(
-                    "embedder profile mismatch vs existing layer metadata (existing={:?}, current={:?})",
-                    existing.embedding_profile,
-                    embedder.profile()
-                );
+                return Err(ProfileMismatchError {
+                    layer: path.to_path_buf(),
+                    existing: existing.embedding_profile,
+                    current: embedder.profile().clone(),
+                }
+                .into());
             }
             agentsdb_format::append_layer_atomic(path, &mut new_chunks, None)
                 .context("append chunk")?
@@ -124,24 +147,25 @@ pub fn append_chunk(
             agentsdb_format::append_layer_atomic(path, &mut new_chunks, Some(&layer_metadata_json))
                 .context("append chunk")?
         };
+        crate::metrics::record_append(path, 1);
         Ok(*assigned.first().unwrap_or(&0))
     } else {
-        let dim = dim.context("creating a new layer requires dim")?;
+        let dim = dim.ok_or(WriteError::DimRequired)?;
         let assigned = id.unwrap_or(1);
         let mut chunk = ChunkInput {
             id: assigned,
-            kind: kind.to_string(),
+            kind,
             author: "human".to_string(),
             confidence,
             created_at_unix_ms: now_unix_ms(),
-            content: content.to_string(),
+            content,
             embedding: Vec::new(),
-            sources: Vec::new(),
+            sources,
         };
         let dim_usize = dim as usize;
         let embedder = embedder_for_dim(dim_usize)?;
         chunk.embedding = embedder
-            .embed(&[chunk.content.clone()])?
+            .embed_for(EmbedPurpose::Document, &[chunk.content.clone()])?
             .into_iter()
             .next()
             .unwrap_or_else(|| vec![0.0; dim_usize]);
@@ -152,13 +176,6 @@ pub fn append_chunk(
             .to_json_bytes()
             .context("serialize layer metadata")?;
 
-        for s in sources.iter() {
-            chunk.sources.push(ChunkSource::SourceString(s.to_string()));
-        }
-        for cid in source_chunks.iter() {
-            chunk.sources.push(ChunkSource::ChunkId(*cid));
-        }
-
         if chunk.id == 0 {
             chunk.id = 1;
         }
@@ -166,10 +183,506 @@ pub fn append_chunk(
             dim,
             element_type: agentsdb_format::EmbeddingElementType::F32,
             quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
         };
         let mut chunks = [chunk];
         agentsdb_format::write_layer_atomic(path, &schema, &mut chunks, Some(&layer_metadata_json))
             .context("create layer")?;
+        crate::metrics::record_append(path, 1);
         Ok(assigned)
     }
 }
+
+/// Ids written by [`supersede_chunk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct SupersedeOutcome {
+    /// Id of the revised chunk.
+    pub id: u32,
+    /// Id of the chunk that was replaced.
+    pub superseded_id: u32,
+    /// Id of the tombstone retracting the replaced chunk.
+    pub tombstone_id: u32,
+}
+
+/// Resolves the embedder for the layer at `path`, checks it against the layer's stored metadata
+/// (if any), and returns it with the metadata to record for it.
+fn checked_embedder(
+    path: &Path,
+    dim: usize,
+    existing_metadata: Option<&[u8]>,
+    tool_name: &str,
+    tool_version: &str,
+) -> Result<
+    (
+        Box<dyn agentsdb_embeddings::embedder::Embedder + Send + Sync>,
+        Vec<u8>,
+    ),
+    WriteError,
+> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let options =
+        get_immutable_embedding_options(dir).context("get immutable embedding options")?;
+    if let Some(cfg_dim) = options.dim {
+        if cfg_dim != dim {
+            return Err(WriteError::DimMismatch {
+                layer: dim,
+                configured: cfg_dim,
+            });
+        }
+    }
+    let embedder = options
+        .into_embedder(dim)
+        .context("resolve embedder from options")?;
+    if let Some(existing) = existing_metadata {
+        let existing =
+            LayerMetadataV1::from_json_bytes(existing).context("parse existing layer metadata")?;
+        if existing.embedding_profile != *embedder.profile() {
+            return Err(ProfileMismatchError {
+                layer: path.to_path_buf(),
+                existing: existing.embedding_profile,
+                current: embedder.profile().clone(),
+            }
+            .into());
+        }
+    }
+    let layer_metadata_json = LayerMetadataV1::new(embedder.profile().clone())
+        .with_embedder_metadata(embedder.metadata())
+        .with_tool(tool_name, tool_version)
+        .to_json_bytes()
+        .context("serialize layer metadata")?;
+    Ok((embedder, layer_metadata_json))
+}
+
+/// Precedence of a standard layer file (lower wins), used to check that a tombstone written
+/// to one layer can retract a chunk stored in another.
+fn layer_rank(path: &Path) -> u8 {
+    match path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+    {
+        "AGENTS.local.db" => 0,
+        "AGENTS.user.db" => 1,
+        "AGENTS.delta.db" => 2,
+        _ => 3,
+    }
+}
+
+/// Replace chunk `id` (stored in `source_path`) with a revised version written to `path`
+/// (local or delta).
+///
+/// The revision and a tombstone for the original are appended in one atomic write. The revision
+/// keeps the original's kind, confidence and sources unless overridden, adds `extra_sources`,
+/// and records a `supersedes:<id>` source.
+///
+/// # Arguments
+/// * `source_path` - Layer the original chunk is read from
+/// * `path` - Layer to write to; must not have lower precedence than `source_path`
+/// * `reason` - Tombstone content (defaults to "superseded by a revised chunk")
+#[allow(clippy::too_many_arguments)]
+pub fn supersede_chunk(
+    source_path: &Path,
+    path: &Path,
+    id: u32,
+    kind: Option<&str>,
+    content: &str,
+    confidence: Option<f32>,
+    extra_sources: &[String],
+    reason: Option<&str>,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<SupersedeOutcome> {
+    if content.trim().is_empty() {
+        anyhow::bail!("content must be non-empty");
+    }
+    let revision = Revision {
+        kind,
+        content: Some(content),
+        confidence,
+        reason,
+    };
+    supersede_with(
+        source_path,
+        path,
+        id,
+        &revision,
+        |sources| {
+            for extra in extra_sources {
+                if !sources
+                    .iter()
+                    .any(|s| matches!(s, ChunkSource::SourceString(v) if v == extra))
+                {
+                    sources.push(ChunkSource::SourceString(extra.clone()));
+                }
+            }
+        },
+        tool_name,
+        tool_version,
+    )
+}
+
+/// Replace chunk `id` (stored in `source_path`) with a revision written to `path` whose
+/// chunk-id sources are relinked: each `(old, Some(new))` in `relinks` points the `old`
+/// reference at `new`; each `(old, None)` drops it and records `broken-source:<old>` instead.
+/// Content, kind and confidence are kept. See [`supersede_chunk`] for how the original is retired.
+pub fn relink_chunk_sources(
+    source_path: &Path,
+    path: &Path,
+    id: u32,
+    relinks: &[(u32, Option<u32>)],
+    reason: &str,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<SupersedeOutcome> {
+    let revision = Revision {
+        kind: None,
+        content: None,
+        confidence: None,
+        reason: Some(reason),
+    };
+    supersede_with(
+        source_path,
+        path,
+        id,
+        &revision,
+        |sources| {
+            let mut relinked = Vec::with_capacity(sources.len());
+            for source in sources.drain(..) {
+                let relink = match &source {
+                    ChunkSource::ChunkId(old) => relinks.iter().find(|(o, _)| o == old),
+                    ChunkSource::SourceString(_) => None,
+                };
+                let source = match relink {
+                    Some((_, Some(new))) => ChunkSource::ChunkId(*new),
+                    Some((old, None)) => ChunkSource::SourceString(format!(
+                        "{}{old}",
+                        agentsdb_query::BROKEN_SOURCE_PREFIX
+                    )),
+                    None => source,
+                };
+                let duplicate = relinked.iter().any(|s| match (s, &source) {
+                    (ChunkSource::ChunkId(a), ChunkSource::ChunkId(b)) => a == b,
+                    (ChunkSource::SourceString(a), ChunkSource::SourceString(b)) => a == b,
+                    _ => false,
+                });
+                if !duplicate {
+                    relinked.push(source);
+                }
+            }
+            *sources = relinked;
+        },
+        tool_name,
+        tool_version,
+    )
+}
+
+/// Replace chunk `id` (stored in `source_path`) with a revision written to `path` that only
+/// changes its confidence. See [`supersede_chunk`] for how the original is retired.
+pub fn revise_confidence(
+    source_path: &Path,
+    path: &Path,
+    id: u32,
+    confidence: f32,
+    reason: &str,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<SupersedeOutcome> {
+    let revision = Revision {
+        kind: None,
+        content: None,
+        confidence: Some(confidence),
+        reason: Some(reason),
+    };
+    supersede_with(
+        source_path,
+        path,
+        id,
+        &revision,
+        |_| {},
+        tool_name,
+        tool_version,
+    )
+}
+
+/// Overrides for the revision written by [`supersede_with`]; `None` keeps the original's value.
+struct Revision<'a> {
+    kind: Option<&'a str>,
+    content: Option<&'a str>,
+    confidence: Option<f32>,
+    reason: Option<&'a str>,
+}
+
+fn supersede_with(
+    source_path: &Path,
+    path: &Path,
+    id: u32,
+    revision: &Revision<'_>,
+    edit_sources: impl FnOnce(&mut Vec<ChunkSource>),
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<SupersedeOutcome> {
+    WritePolicy::standard()
+        .check(path)
+        .context("permission check")?;
+    if layer_rank(path) > layer_rank(source_path) {
+        anyhow::bail!(
+            "cannot supersede a chunk from {} by writing to {}: the tombstone would not hide it",
+            source_path.display(),
+            path.display()
+        );
+    }
+    if let Some(kind) = revision.kind {
+        crate::kinds::check_kind_for_layer(path, kind)?;
+    }
+
+    let source =
+        LayerFile::open(source_path).with_context(|| format!("open {}", source_path.display()))?;
+    let original = agentsdb_format::read_all_chunks(&source)
+        .with_context(|| format!("read chunks from {}", source_path.display()))?
+        .into_iter()
+        .rev()
+        .find(|c| c.id == id && c.kind != agentsdb_query::KIND_TOMBSTONE)
+        .with_context(|| format!("chunk {id} not found in {}", source_path.display()))?;
+
+    let mut sources = vec![ChunkSource::SourceString(format!(
+        "{}{id}",
+        agentsdb_query::SUPERSEDES_SOURCE_PREFIX
+    ))];
+    // The revision is written by whoever revises it, not the original's writer.
+    sources.extend(original.sources.into_iter().filter(|s| {
+        !matches!(s, ChunkSource::SourceString(v)
+            if v.starts_with(agentsdb_query::IDENTITY_SOURCE_PREFIX))
+    }));
+    edit_sources(&mut sources);
+    let reason = revision
+        .reason
+        .filter(|r| !r.trim().is_empty())
+        .unwrap_or("superseded by a revised chunk");
+    let mut revised = NewChunk {
+        kind: revision.kind.unwrap_or(&original.kind).to_string(),
+        content: revision.content.unwrap_or(&original.content).to_string(),
+        confidence: revision.confidence.unwrap_or(original.confidence),
+        sources,
+    };
+    crate::hooks::run(&WriteTarget::of(path), &mut revised)?;
+
+    let target = if path.exists() {
+        Some(LayerFile::open(path).with_context(|| format!("open for append {}", path.display()))?)
+    } else {
+        None
+    };
+    let dim = target
+        .as_ref()
+        .map_or_else(|| source.embedding_dim(), LayerFile::embedding_dim);
+    let existing_metadata = target.as_ref().and_then(LayerFile::layer_metadata_bytes);
+    let (embedder, layer_metadata_json) =
+        checked_embedder(path, dim, existing_metadata, tool_name, tool_version)?;
+
+    let texts = [revised.content.clone(), reason.to_string()];
+    let mut embeddings = embedder
+        .embed_for(EmbedPurpose::Document, &texts)?
+        .into_iter();
+    let now = now_unix_ms();
+    let mut chunks = vec![
+        ChunkInput {
+            id: 0,
+            kind: revised.kind,
+            content: revised.content,
+            author: "human".to_string(),
+            confidence: revised.confidence,
+            created_at_unix_ms: now,
+            embedding: embeddings.next().unwrap_or_else(|| vec![0.0; dim]),
+            sources: revised.sources,
+        },
+        ChunkInput {
+            id: 0,
+            kind: agentsdb_query::KIND_TOMBSTONE.to_string(),
+            content: reason.to_string(),
+            author: "human".to_string(),
+            confidence: 1.0,
+            created_at_unix_ms: now,
+            embedding: embeddings.next().unwrap_or_else(|| vec![0.0; dim]),
+            sources: vec![ChunkSource::ChunkId(id)],
+        },
+    ];
+
+    let ids = if target.is_some() {
+        let metadata = existing_metadata
+            .is_none()
+            .then_some(layer_metadata_json.as_slice());
+        agentsdb_format::append_layer_atomic(path, &mut chunks, metadata)
+            .context("append revision")?
+    } else {
+        chunks[0].id = 1;
+        chunks[1].id = 2;
+        let schema = agentsdb_format::schema_of(&source);
+        agentsdb_format::write_layer_atomic(path, &schema, &mut chunks, Some(&layer_metadata_json))
+            .context("create layer")?;
+        vec![1, 2]
+    };
+    crate::metrics::record_append(path, ids.len());
+    Ok(SupersedeOutcome {
+        id: ids.first().copied().unwrap_or(0),
+        superseded_id: id,
+        tombstone_id: ids.get(1).copied().unwrap_or(0),
+    })
+}
+
+/// A chunk to append with [`append_chunks`]; ids are assigned on append.
+#[derive(Debug, Clone)]
+pub struct NewChunk {
+    pub kind: String,
+    pub content: String,
+    pub confidence: f32,
+    pub sources: Vec<ChunkSource>,
+}
+
+/// Append several chunks to a layer file (local or delta) in one atomic write.
+///
+/// # Arguments
+/// * `dim` - Embedding dimension (required only if creating a new layer)
+///
+/// # Returns
+/// The assigned chunk IDs, in input order
+#[tracing::instrument(level = "debug", skip_all, fields(path = %path.display(), scope = scope, chunks = chunks.len()))]
+pub fn append_chunks(
+    path: &Path,
+    scope: &str,
+    mut chunks: Vec<NewChunk>,
+    dim: Option<u32>,
+    tool_name: &str,
+    tool_version: &str,
+) -> Result<Vec<u32>, WriteError> {
+    let policy = WritePolicy::standard();
+    let Some(expected) = policy.writable_file_name(scope) else {
+        return Err(WriteError::InvalidScope(scope.to_string()));
+    };
+    if policy.scope_of(path) != Some(scope) {
+        return Err(WriteError::ScopeMismatch {
+            scope: scope.to_string(),
+            expected: expected.to_string(),
+        });
+    }
+    if chunks.is_empty() {
+        return Ok(Vec::new());
+    }
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let taxonomy = crate::kinds::load_taxonomy_for_dir(dir)?;
+    for chunk in &mut chunks {
+        crate::hooks::run(&WriteTarget::of(path), chunk)?;
+        crate::kinds::check_kind(&taxonomy, &chunk.kind)?;
+    }
+
+    let target = if path.exists() {
+        Some(LayerFile::open(path).with_context(|| format!("open for append {}", path.display()))?)
+    } else {
+        None
+    };
+    let dim = match &target {
+        Some(file) => file.embedding_dim(),
+        None => dim.ok_or(WriteError::DimRequired)? as usize,
+    };
+    let existing_metadata = target.as_ref().and_then(LayerFile::layer_metadata_bytes);
+    let (embedder, layer_metadata_json) =
+        checked_embedder(path, dim, existing_metadata, tool_name, tool_version)?;
+
+    let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+    let mut embeddings = embedder
+        .embed_for(EmbedPurpose::Document, &texts)?
+        .into_iter();
+    let now = now_unix_ms();
+    let mut inputs: Vec<ChunkInput> = chunks
+        .into_iter()
+        .map(|c| ChunkInput {
+            id: 0,
+            kind: c.kind,
+            content: c.content,
+            author: "human".to_string(),
+            confidence: c.confidence,
+            created_at_unix_ms: now,
+            embedding: embeddings.next().unwrap_or_else(|| vec![0.0; dim]),
+            sources: c.sources,
+        })
+        .collect();
+
+    let ids = if target.is_some() {
+        let metadata = existing_metadata
+            .is_none()
+            .then_some(layer_metadata_json.as_slice());
+        agentsdb_format::append_layer_atomic(path, &mut inputs, metadata)
+            .context("append chunks")?
+    } else {
+        let ids: Vec<u32> = (1..).take(inputs.len()).collect();
+        for (chunk, id) in inputs.iter_mut().zip(&ids) {
+            chunk.id = *id;
+        }
+        let schema = agentsdb_format::LayerSchema {
+            dim: u32::try_from(dim).context("dim out of range")?,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        agentsdb_format::write_layer_atomic(path, &schema, &mut inputs, Some(&layer_metadata_json))
+            .context("create layer")?;
+        ids
+    };
+    crate::metrics::record_append(path, ids.len());
+    Ok(ids)
+}
+
+/// Retract chunks `ids` (stored in `source_path`) by appending one tombstone per id to `path`
+/// (local or delta) in a single atomic write.
+///
+/// # Returns
+/// The tombstone chunk IDs, in input order
+pub fn retract_chunks(
+    source_path: &Path,
+    path: &Path,
+    scope: &str,
+    ids: &[u32],
+    reason: &str,
+    tool_name: &str,
+    tool_version: &str,
+) -> anyhow::Result<Vec<u32>> {
+    if ids.is_empty() {
+        anyhow::bail!("ids must be non-empty");
+    }
+    if reason.trim().is_empty() {
+        anyhow::bail!("reason must be non-empty");
+    }
+    if layer_rank(path) > layer_rank(source_path) {
+        anyhow::bail!(
+            "cannot retract chunks from {} by writing to {}: the tombstones would not hide them",
+            source_path.display(),
+            path.display()
+        );
+    }
+    let source = LayerFile::open_lenient(source_path)
+        .with_context(|| format!("open {}", source_path.display()))?;
+    let mut present = std::collections::HashSet::new();
+    for chunk in source.chunks() {
+        present.insert(chunk.context("read chunk")?.id);
+    }
+    if let Some(missing) = ids.iter().find(|id| !present.contains(id)) {
+        anyhow::bail!("chunk {missing} not found in {}", source_path.display());
+    }
+
+    let tombstones = ids
+        .iter()
+        .map(|id| NewChunk {
+            kind: agentsdb_query::KIND_TOMBSTONE.to_string(),
+            content: reason.to_string(),
+            confidence: 1.0,
+            sources: vec![ChunkSource::ChunkId(*id)],
+        })
+        .collect();
+    let dim = u32::try_from(source.embedding_dim()).context("dim out of range")?;
+    Ok(append_chunks(
+        path,
+        scope,
+        tombstones,
+        Some(dim),
+        tool_name,
+        tool_version,
+    )?)
+}
@@ -0,0 +1,238 @@
+//! Search and selection benchmarks.
+//!
+//! `cargo bench -p agentsdb-query` covers 1k and 100k chunk layers; set
+//! `AGENTSDB_BENCH_LARGE=1` to add 1M (about 256 MB of f32 embeddings on disk).
+#![allow(clippy::expect_used)]
+
+use std::path::Path;
+
+use agentsdb_core::types::{LayerId, SearchFilters};
+use agentsdb_format::{
+    ChunkInput, ChunkSource, EmbeddingElementType, EmbeddingLayout, LayerFile, LayerSchema,
+};
+use agentsdb_query::{
+    build_layer_index, default_index_path_for_layer, search_layers_with_options,
+    selected_chunk_count, IndexBuildOptions, SearchMode, SearchOptions, SearchQuery,
+    SELECTION_CACHE_DIR,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+const DIM: usize = 64;
+const K: usize = 10;
+
+fn sizes() -> Vec<usize> {
+    let mut sizes = vec![1_000, 100_000];
+    if std::env::var_os("AGENTSDB_BENCH_LARGE").is_some() {
+        sizes.push(1_000_000);
+    }
+    sizes
+}
+
+/// Deterministic pseudo-random unit-ish vector for row `seed`.
+fn embedding(seed: u64) -> Vec<f32> {
+    let mut state = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+    (0..DIM)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            let unit = f32::from(u16::try_from(state >> 48).unwrap_or(0)) / 65_536.0;
+            unit - 0.5
+        })
+        .collect()
+}
+
+fn chunks(ids: std::ops::Range<u32>, topic: &str) -> Vec<ChunkInput> {
+    ids.map(|id| ChunkInput {
+        id,
+        kind: "note".to_string(),
+        content: format!("{topic} chunk {id} about subsystem {}", id % 97),
+        author: "human".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: u64::from(id),
+        embedding: embedding(u64::from(id)),
+        sources: vec![ChunkSource::SourceString(format!("src/{}.rs:1", id % 97))],
+    })
+    .collect()
+}
+
+fn schema(element_type: EmbeddingElementType, layout: EmbeddingLayout) -> LayerSchema {
+    LayerSchema {
+        dim: u32::try_from(DIM).unwrap_or(u32::MAX),
+        element_type,
+        quant_scale: match element_type {
+            EmbeddingElementType::F32 => 1.0,
+            EmbeddingElementType::I8 => 1.0 / 254.0,
+        },
+        layout,
+    }
+}
+
+fn write_layer(
+    path: &Path,
+    element_type: EmbeddingElementType,
+    layout: EmbeddingLayout,
+    mut chunks: Vec<ChunkInput>,
+) {
+    agentsdb_format::write_layer_atomic(path, &schema(element_type, layout), &mut chunks, None)
+        .expect("write layer");
+}
+
+fn open(path: &Path) -> LayerFile {
+    LayerFile::open(path).expect("open layer")
+}
+
+fn query() -> SearchQuery {
+    SearchQuery {
+        embedding: embedding(u64::MAX),
+        k: K,
+        filters: SearchFilters::default(),
+        query_text: None,
+    }
+}
+
+/// Brute-force vs. indexed semantic search over one layer, for f32 and i8 embeddings and for a
+/// blocked f32 matrix.
+fn bench_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    let dir = tempfile::tempdir().expect("tempdir");
+
+    for n in sizes() {
+        let count = u32::try_from(n).unwrap_or(u32::MAX);
+        for (label, element_type, layout) in [
+            ("f32", EmbeddingElementType::F32, EmbeddingLayout::RowMajor),
+            ("i8", EmbeddingElementType::I8, EmbeddingLayout::RowMajor),
+            (
+                "f32-blocked",
+                EmbeddingElementType::F32,
+                EmbeddingLayout::Blocked,
+            ),
+        ] {
+            let path = dir.path().join(format!("{label}-{n}.db"));
+            write_layer(&path, element_type, layout, chunks(1..count + 1, "base"));
+            let layer = open(&path);
+            build_layer_index(
+                &layer,
+                default_index_path_for_layer(&path),
+                IndexBuildOptions {
+                    store_embeddings_even_if_f32: false,
+                    binary_codes: false,
+                },
+            )
+            .expect("build index");
+            let layers = vec![(LayerId::Base, layer)];
+            let q = query();
+            group.throughput(Throughput::Elements(n as u64));
+
+            for (mode, use_index) in [("brute", false), ("indexed", true)] {
+                let options = SearchOptions {
+                    use_index,
+                    mode: SearchMode::Semantic,
+                    ..SearchOptions::default()
+                };
+                group.bench_with_input(
+                    BenchmarkId::new(format!("{mode}/{label}"), n),
+                    &q,
+                    |b, q| b.iter(|| search_layers_with_options(&layers, q, options)),
+                );
+            }
+        }
+    }
+    group.finish();
+}
+
+/// Precedence selection across four layers where the upper layers override a tenth of the
+/// base layer's ids, with and without query text for the lexical tiers.
+fn bench_selection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("selection");
+    group.sample_size(10);
+    let dir = tempfile::tempdir().expect("tempdir");
+
+    for n in sizes() {
+        let count = u32::try_from(n).unwrap_or(u32::MAX);
+        let tenth = (count / 10).max(1);
+        let layers: Vec<(LayerId, LayerFile)> = [
+            (LayerId::Local, "local", 1..tenth + 1),
+            (LayerId::User, "user", tenth..2 * tenth + 1),
+            (LayerId::Delta, "delta", 2 * tenth..3 * tenth + 1),
+            (LayerId::Base, "base", 1..count + 1),
+        ]
+        .into_iter()
+        .map(|(id, name, ids)| {
+            let path = dir.path().join(format!("{name}-{n}.db"));
+            write_layer(
+                &path,
+                EmbeddingElementType::F32,
+                EmbeddingLayout::RowMajor,
+                chunks(ids, name),
+            );
+            (id, open(&path))
+        })
+        .collect();
+        group.throughput(Throughput::Elements(n as u64));
+
+        group.bench_function(BenchmarkId::new("semantic", n), |b| {
+            b.iter(|| selected_chunk_count(&layers, None));
+        });
+        group.bench_function(BenchmarkId::new("hybrid", n), |b| {
+            b.iter(|| selected_chunk_count(&layers, Some("subsystem 42")));
+        });
+    }
+    group.finish();
+}
+
+/// A search with the on-disk selection cache when the entry is missing (scan and write it) and
+/// when it is there (map and read it), over a base layer with a tenth of its ids overridden.
+fn bench_disk_cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("disk_cache");
+    group.sample_size(10);
+
+    for n in sizes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let count = u32::try_from(n).unwrap_or(u32::MAX);
+        let tenth = (count / 10).max(1);
+        let layers: Vec<(LayerId, LayerFile)> = [
+            (LayerId::Local, "AGENTS.local.db", 1..tenth + 1),
+            (LayerId::Base, "AGENTS.db", 1..count + 1),
+        ]
+        .into_iter()
+        .map(|(id, name, ids)| {
+            let path = dir.path().join(name);
+            write_layer(
+                &path,
+                EmbeddingElementType::F32,
+                EmbeddingLayout::RowMajor,
+                chunks(ids, name),
+            );
+            (id, open(&path))
+        })
+        .collect();
+        let cache_dir = dir.path().join(SELECTION_CACHE_DIR);
+        let q = query();
+        let options = SearchOptions {
+            mode: SearchMode::Semantic,
+            disk_cache: true,
+            ..SearchOptions::default()
+        };
+        group.throughput(Throughput::Elements(n as u64));
+
+        group.bench_with_input(BenchmarkId::new("miss", n), &q, |b, q| {
+            b.iter_batched(
+                || {
+                    let _ = std::fs::remove_dir_all(&cache_dir);
+                },
+                |()| search_layers_with_options(&layers, q, options),
+                BatchSize::PerIteration,
+            );
+        });
+        search_layers_with_options(&layers, &q, options).expect("warm the cache");
+        group.bench_with_input(BenchmarkId::new("hit", n), &q, |b, q| {
+            b.iter(|| search_layers_with_options(&layers, q, options));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_search, bench_selection, bench_disk_cache);
+criterion_main!(benches);
@@ -9,10 +9,20 @@ use std::path::{Path, PathBuf};
 
 const MAGIC_AGIX: u32 = 0x5849_4741; // 'A' 'G' 'I' 'X'
 
-#[derive(Debug, Clone, Copy)]
+const FLAG_EMBEDDINGS: u32 = 1;
+const FLAG_BINARY_CODES: u32 = 2;
+
+/// Header length of version 1.0 indexes; 1.1 appends the binary code range.
+const HEADER_LEN_V1_0: u64 = 104;
+const HEADER_LEN_V1_1: u64 = 120;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct IndexBuildOptions {
     /// Store decoded f32 embeddings even for f32 layers (default false).
     pub store_embeddings_even_if_f32: bool,
+    /// Store a 1-bit code per row (see [`binary_code`]), so searches rank rows by Hamming
+    /// distance first and score only the best candidates exactly (default false).
+    pub binary_codes: bool,
 }
 
 #[derive(Debug)]
@@ -24,10 +34,12 @@ pub struct LayerIndex {
     element_type: EmbeddingElementType,
     quant_scale_bits: u32,
     has_embeddings: bool,
+    has_binary_codes: bool,
     norms_offset: u64,
     norms_len: u64,
     embeds_offset: u64,
     embeds_len: u64,
+    codes_offset: u64,
 }
 
 impl LayerIndex {
@@ -49,7 +61,8 @@ impl LayerIndex {
             return Ok(None);
         }
 
-        let has_embeddings = (hdr.flags & 1) != 0;
+        let has_embeddings = (hdr.flags & FLAG_EMBEDDINGS) != 0;
+        let has_binary_codes = (hdr.flags & FLAG_BINARY_CODES) != 0;
         let element_type = match hdr.element_type {
             1 => EmbeddingElementType::F32,
             2 => EmbeddingElementType::I8,
@@ -72,13 +85,42 @@ impl LayerIndex {
             element_type,
             quant_scale_bits: hdr.quant_scale_bits,
             has_embeddings,
+            has_binary_codes,
             norms_offset: hdr.norms_offset,
             norms_len: hdr.norms_len,
             embeds_offset: hdr.embeds_offset,
             embeds_len: hdr.embeds_len,
+            codes_offset: hdr.codes_offset,
         }))
     }
 
+    pub const fn has_binary_codes(&self) -> bool {
+        self.has_binary_codes
+    }
+
+    /// The 1-bit code of `embedding_row`, if the index stores codes.
+    pub fn binary_code(&self, embedding_row: u32) -> Result<Option<&[u8]>, Error> {
+        if embedding_row == 0 || embedding_row as u64 > self.row_count {
+            return Err(FormatError::InvalidEmbeddingRow {
+                embedding_row,
+                row_count: self.row_count,
+            }
+            .into());
+        }
+        if !self.has_binary_codes {
+            return Ok(None);
+        }
+        let code_len = binary_code_len(self.dim) as u64;
+        let start = self.codes_offset + (embedding_row as u64 - 1) * code_len;
+        let code = self
+            .mmap
+            .get(start as usize..(start + code_len) as usize)
+            .ok_or(FormatError::InvalidRange {
+                field: "AGIX.binary_codes range",
+            })?;
+        Ok(Some(code))
+    }
+
     pub fn row_f32_and_norm(&self, embedding_row: u32) -> Result<(f32, Option<&[f32]>), Error> {
         if embedding_row == 0 || embedding_row as u64 > self.row_count {
             return Err(FormatError::InvalidEmbeddingRow {
@@ -106,6 +148,14 @@ impl LayerIndex {
         })?;
         Ok((row_norm, Some(&embeds[start..end])))
     }
+
+    /// Whether this index was built for `layer`'s embedding schema and row count.
+    fn matches_layer(&self, layer: &LayerFile) -> bool {
+        self.dim == layer.embedding_matrix.dim
+            && self.element_type == layer.embedding_matrix.element_type
+            && self.quant_scale_bits == layer.embedding_matrix.quant_scale.to_bits()
+            && self.row_count == layer.embedding_matrix.row_count
+    }
 }
 
 #[derive(Debug)]
@@ -129,16 +179,7 @@ impl IndexLookup {
             let layer_sha = sha256(layer.file_bytes());
             if let Some(index) = LayerIndex::open(idx_path, layer_sha)? {
                 // Index must match schema; otherwise treat as stale/missing.
-                if index.dim != layer.embedding_matrix.dim {
-                    continue;
-                }
-                if index.element_type != layer.embedding_matrix.element_type {
-                    continue;
-                }
-                if index.quant_scale_bits != layer.embedding_matrix.quant_scale.to_bits() {
-                    continue;
-                }
-                if index.row_count != layer.embedding_matrix.row_count {
+                if !index.matches_layer(layer) {
                     continue;
                 }
                 by_layer.insert(*id, index);
@@ -157,6 +198,64 @@ pub fn default_index_path_for_layer(layer_path: impl AsRef<Path>) -> PathBuf {
     PathBuf::from(format!("{}.agix", layer_path.display()))
 }
 
+/// Whether the index at `index_path` exists and is usable for `layer` (built from the
+/// current layer bytes with a matching schema). A missing index is not fresh.
+pub fn layer_index_is_fresh(
+    layer: &LayerFile,
+    index_path: impl AsRef<Path>,
+) -> Result<bool, Error> {
+    let layer_sha = sha256(layer.file_bytes());
+    Ok(LayerIndex::open(index_path, layer_sha)?.is_some_and(|index| index.matches_layer(layer)))
+}
+
+/// The options the index at `index_path` was built with, whether or not it is still fresh, so
+/// a rebuild can keep them. `None` when there is no index.
+pub fn index_build_options(
+    index_path: impl AsRef<Path>,
+) -> Result<Option<IndexBuildOptions>, Error> {
+    let bytes = match std::fs::read(index_path.as_ref()) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let hdr = parse_header(&bytes)?;
+    Ok(Some(IndexBuildOptions {
+        // I8 layers always store decoded embeddings.
+        store_embeddings_even_if_f32: hdr.element_type == 1 && (hdr.flags & FLAG_EMBEDDINGS) != 0,
+        binary_codes: (hdr.flags & FLAG_BINARY_CODES) != 0,
+    }))
+}
+
+/// Bytes in the binary code of a `dim`-dimensional embedding.
+pub fn binary_code_len(dim: u32) -> usize {
+    dim.div_ceil(8) as usize
+}
+
+/// 1-bit code of `embedding`: bit `d` (least significant first within each byte) is set when
+/// value `d` is positive. The fewer bits two codes differ in, the closer their directions.
+pub fn binary_code(embedding: &[f32]) -> Vec<u8> {
+    let mut code = vec![0u8; embedding.len().div_ceil(8)];
+    for (d, v) in embedding.iter().enumerate() {
+        if *v > 0.0 {
+            code[d / 8] |= 1 << (d % 8);
+        }
+    }
+    code
+}
+
+/// Cosine similarity of two `dim`-dimensional embeddings estimated from their binary codes:
+/// the angle between random-hyperplane codes is proportional to their Hamming distance.
+pub fn binary_code_similarity(a: &[u8], b: &[u8], dim: u32) -> f32 {
+    if dim == 0 {
+        return 0.0;
+    }
+    let distance: u32 = a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum();
+    // Only an estimate to rank candidates, so f32 precision is plenty.
+    #[allow(clippy::cast_possible_truncation)]
+    let similarity = (std::f64::consts::PI * f64::from(distance) / f64::from(dim)).cos() as f32;
+    similarity
+}
+
 pub fn build_layer_index(
     layer: &LayerFile,
     out_path: impl AsRef<Path>,
@@ -180,9 +279,18 @@ pub fn build_layer_index(
         Vec::new()
     };
 
+    let mut codes: Vec<u8> = if opts.binary_codes {
+        Vec::with_capacity((row_count as usize) * binary_code_len(dim))
+    } else {
+        Vec::new()
+    };
+
     let mut tmp = vec![0.0f32; dim as usize];
     for row in 1..=row_count {
         layer.read_embedding_row_f32(row as u32, &mut tmp)?;
+        if opts.binary_codes {
+            codes.extend_from_slice(&binary_code(&tmp));
+        }
         let mut sum = 0.0f32;
         for v in &tmp {
             sum += v * v;
@@ -194,8 +302,19 @@ pub fn build_layer_index(
         }
     }
 
-    let flags: u32 = if store_embeddings { 1 } else { 0 };
-    let header_len: u64 = 104;
+    let mut flags: u32 = 0;
+    if store_embeddings {
+        flags |= FLAG_EMBEDDINGS;
+    }
+    if opts.binary_codes {
+        flags |= FLAG_BINARY_CODES;
+    }
+    // Indexes without codes stay at version 1.0, so older readers keep using them.
+    let header_len = if opts.binary_codes {
+        HEADER_LEN_V1_1
+    } else {
+        HEADER_LEN_V1_0
+    };
     let norms_offset = header_len;
     let norms_len = (row_count as u64)
         .checked_mul(4)
@@ -218,16 +337,23 @@ pub fn build_layer_index(
         0
     };
 
-    let mut buf = Vec::with_capacity((header_len + norms_len + embeds_len).try_into().map_err(
-        |_| FormatError::InvalidRange {
+    let codes_offset = embeds_offset
+        .checked_add(embeds_len)
+        .ok_or(FormatError::InvalidRange {
+            field: "AGIX.codes_offset",
+        })?;
+    let codes_len = codes.len() as u64;
+
+    let mut buf = Vec::with_capacity((codes_offset + codes_len).try_into().map_err(|_| {
+        FormatError::InvalidRange {
             field: "AGIX.buffer",
-        },
-    )?);
+        }
+    })?);
 
     // Header
     push_u32(&mut buf, MAGIC_AGIX);
     push_u16(&mut buf, 1);
-    push_u16(&mut buf, 0);
+    push_u16(&mut buf, u16::from(opts.binary_codes));
     push_u32(&mut buf, dim);
     push_u32(&mut buf, 0);
     push_u64(&mut buf, row_count);
@@ -246,6 +372,10 @@ pub fn build_layer_index(
     push_u64(&mut buf, norms_len);
     push_u64(&mut buf, embeds_offset);
     push_u64(&mut buf, embeds_len);
+    if opts.binary_codes {
+        push_u64(&mut buf, codes_offset);
+        push_u64(&mut buf, codes_len);
+    }
     debug_assert_eq!(buf.len() as u64, header_len);
 
     // Norms
@@ -260,11 +390,14 @@ pub fn build_layer_index(
         }
     }
 
+    // Binary codes (optional)
+    buf.extend_from_slice(&codes);
+
     write_atomic(out_path, &buf)?;
     Ok(())
 }
 
-fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), Error> {
     let parent = path.parent().unwrap_or_else(|| Path::new("."));
     std::fs::create_dir_all(parent)?;
     let mut tmp = parent.to_path_buf();
@@ -296,6 +429,9 @@ struct IndexHeaderV1 {
     norms_len: u64,
     embeds_offset: u64,
     embeds_len: u64,
+    /// Zero in version 1.0 indexes.
+    codes_offset: u64,
+    codes_len: u64,
 }
 
 fn parse_header(bytes: &[u8]) -> Result<IndexHeaderV1, Error> {
@@ -310,7 +446,7 @@ fn parse_header(bytes: &[u8]) -> Result<IndexHeaderV1, Error> {
     }
     let major = read_u16(bytes, &mut off)?;
     let minor = read_u16(bytes, &mut off)?;
-    if major != 1 || minor != 0 {
+    if major != 1 || minor > 1 {
         return Err(FormatError::UnsupportedVersion { major, minor }.into());
     }
     let dim = read_u32(bytes, &mut off)?;
@@ -331,6 +467,11 @@ fn parse_header(bytes: &[u8]) -> Result<IndexHeaderV1, Error> {
     let norms_len = read_u64(bytes, &mut off)?;
     let embeds_offset = read_u64(bytes, &mut off)?;
     let embeds_len = read_u64(bytes, &mut off)?;
+    let (codes_offset, codes_len) = if minor >= 1 {
+        (read_u64(bytes, &mut off)?, read_u64(bytes, &mut off)?)
+    } else {
+        (0, 0)
+    };
     Ok(IndexHeaderV1 {
         dim,
         row_count,
@@ -342,6 +483,8 @@ fn parse_header(bytes: &[u8]) -> Result<IndexHeaderV1, Error> {
         norms_len,
         embeds_offset,
         embeds_len,
+        codes_offset,
+        codes_len,
     })
 }
 
@@ -374,7 +517,7 @@ fn validate_ranges(bytes: &[u8], hdr: &IndexHeaderV1) -> Result<(), Error> {
         .into());
     }
 
-    let has_embeddings = (hdr.flags & 1) != 0;
+    let has_embeddings = (hdr.flags & FLAG_EMBEDDINGS) != 0;
     if has_embeddings {
         let embeds_end =
             hdr.embeds_offset
@@ -410,6 +553,40 @@ fn validate_ranges(bytes: &[u8], hdr: &IndexHeaderV1) -> Result<(), Error> {
         .into());
     }
 
+    if (hdr.flags & FLAG_BINARY_CODES) != 0 {
+        let codes_end =
+            hdr.codes_offset
+                .checked_add(hdr.codes_len)
+                .ok_or(FormatError::InvalidRange {
+                    field: "AGIX.binary_codes",
+                })?;
+        if codes_end > file_len {
+            return Err(FormatError::InvalidRange {
+                field: "AGIX.binary_codes",
+            }
+            .into());
+        }
+        let expected_codes_len = hdr
+            .row_count
+            .checked_mul(binary_code_len(hdr.dim) as u64)
+            .ok_or(FormatError::InvalidRange {
+                field: "AGIX.expected_codes_len",
+            })?;
+        if hdr.codes_len != expected_codes_len {
+            return Err(FormatError::InvalidValue {
+                field: "AGIX.codes_len",
+                reason: "unexpected binary codes length",
+            }
+            .into());
+        }
+    } else if hdr.codes_len != 0 {
+        return Err(FormatError::InvalidValue {
+            field: "AGIX.codes_len",
+            reason: "must be 0 when binary codes are not present",
+        }
+        .into());
+    }
+
     Ok(())
 }
 
@@ -465,7 +642,7 @@ fn embeds_slice<'a>(bytes: &'a [u8], off: u64, len: u64) -> Result<&'a [f32], Er
     Ok(body)
 }
 
-fn read_u16(bytes: &[u8], off: &mut usize) -> Result<u16, Error> {
+pub(crate) fn read_u16(bytes: &[u8], off: &mut usize) -> Result<u16, Error> {
     let start = *off;
     let end = start + 2;
     let slice = bytes.get(start..end).ok_or(FormatError::Truncated {
@@ -476,7 +653,7 @@ fn read_u16(bytes: &[u8], off: &mut usize) -> Result<u16, Error> {
     Ok(u16::from_le_bytes([slice[0], slice[1]]))
 }
 
-fn read_u32(bytes: &[u8], off: &mut usize) -> Result<u32, Error> {
+pub(crate) fn read_u32(bytes: &[u8], off: &mut usize) -> Result<u32, Error> {
     let start = *off;
     let end = start + 4;
     let slice = bytes.get(start..end).ok_or(FormatError::Truncated {
@@ -487,7 +664,7 @@ fn read_u32(bytes: &[u8], off: &mut usize) -> Result<u32, Error> {
     Ok(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
 }
 
-fn read_u64(bytes: &[u8], off: &mut usize) -> Result<u64, Error> {
+pub(crate) fn read_u64(bytes: &[u8], off: &mut usize) -> Result<u64, Error> {
     let start = *off;
     let end = start + 8;
     let slice = bytes.get(start..end).ok_or(FormatError::Truncated {
@@ -500,7 +677,7 @@ fn read_u64(bytes: &[u8], off: &mut usize) -> Result<u64, Error> {
     ]))
 }
 
-fn read_bytes_32(bytes: &[u8], off: &mut usize) -> Result<[u8; 32], Error> {
+pub(crate) fn read_bytes_32(bytes: &[u8], off: &mut usize) -> Result<[u8; 32], Error> {
     let start = *off;
     let end = start + 32;
     let slice = bytes.get(start..end).ok_or(FormatError::Truncated {
@@ -513,15 +690,15 @@ fn read_bytes_32(bytes: &[u8], off: &mut usize) -> Result<[u8; 32], Error> {
     Ok(out)
 }
 
-fn push_u16(buf: &mut Vec<u8>, v: u16) {
+pub(crate) fn push_u16(buf: &mut Vec<u8>, v: u16) {
     buf.extend_from_slice(&v.to_le_bytes());
 }
 
-fn push_u32(buf: &mut Vec<u8>, v: u32) {
+pub(crate) fn push_u32(buf: &mut Vec<u8>, v: u32) {
     buf.extend_from_slice(&v.to_le_bytes());
 }
 
-fn push_u64(buf: &mut Vec<u8>, v: u64) {
+pub(crate) fn push_u64(buf: &mut Vec<u8>, v: u64) {
     buf.extend_from_slice(&v.to_le_bytes());
 }
 
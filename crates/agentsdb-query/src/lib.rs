@@ -3,11 +3,106 @@ use agentsdb_core::types::{
     Author, Chunk, ChunkId, LayerId, ProvenanceRef, SearchFilters, SearchResult,
 };
 use agentsdb_embeddings::config::KIND_OPTIONS;
-use agentsdb_format::{LayerFile, SourceRef};
+use agentsdb_embeddings::signing::TrustPolicy;
+use agentsdb_format::{EmbeddingLayout, LayerFile, SourceRef};
 use std::collections::{HashMap, HashSet};
 
 mod index;
-pub use index::{build_layer_index, default_index_path_for_layer, IndexBuildOptions, IndexLookup};
+mod pool;
+mod result_cache;
+mod selection_cache;
+#[cfg(test)]
+mod test_support;
+pub use index::{
+    binary_code, binary_code_len, binary_code_similarity, build_layer_index,
+    default_index_path_for_layer, index_build_options, layer_index_is_fresh, IndexBuildOptions,
+    IndexLookup,
+};
+pub use pool::LayerPool;
+pub use result_cache::{ResultCache, ResultCacheStats};
+pub use selection_cache::CACHE_DIR as SELECTION_CACHE_DIR;
+
+/// Kind of the record appended to retract a chunk. Its `ChunkId` sources name the retracted
+/// chunks and its content holds the reason.
+pub const KIND_TOMBSTONE: &str = "tombstone";
+
+/// Source string prefix (followed by a chunk id) marking a chunk as the revision of another.
+pub const SUPERSEDES_SOURCE_PREFIX: &str = "supersedes:";
+
+/// Source string prefix (followed by a chunk id) recording a chunk-id source that no longer
+/// resolves and was unlinked by `agentsdb verify-provenance --fix`.
+pub const BROKEN_SOURCE_PREFIX: &str = "broken-source:";
+
+/// When a search ranks rows by binary code, this many candidates per requested result are
+/// rescored exactly before the top `k` are returned.
+pub const BINARY_RESCORE_FACTOR: usize = 10;
+
+/// Source string prefix (followed by a name) placing a chunk in a namespace, e.g. one package of
+/// a monorepo sharing a layer with the others.
+pub const NAMESPACE_SOURCE_PREFIX: &str = "namespace:";
+
+/// Source string prefix (followed by a SHA-256 as hex) marking a chunk imported from a
+/// content-redacted export: it has an embedding, so search finds it, but its content is empty.
+/// The hash is that of the content it was exported without.
+pub const REDACTED_SOURCE_PREFIX: &str = "redacted:";
+
+/// Source string prefix (followed by `Name <email>` or a machine actor id) recording who wrote a
+/// chunk, from the writer's `identity.*` settings. The author field only tells humans from MCP.
+pub const IDENTITY_SOURCE_PREFIX: &str = "identity:";
+
+/// Source string prefix (followed by an [`EmbeddingProfile::fingerprint`]) recording that a
+/// chunk's embedding was computed with another profile than its layer's, e.g. one promoted from a
+/// layer embedded with a different model of the same dimension. Its scores against queries
+/// embedded with the layer's profile are meaningless until it is re-embedded.
+///
+/// [`EmbeddingProfile::fingerprint`]: agentsdb_embeddings::embedder::EmbeddingProfile::fingerprint
+pub const EMBEDDED_WITH_SOURCE_PREFIX: &str = "embedded-with:";
+
+/// Prefixes of the source strings the tools add to record something about a chunk itself (its
+/// namespace, language, writer, revisions, ...) rather than where its content came from.
+pub const RESERVED_SOURCE_PREFIXES: &[&str] = &[
+    SUPERSEDES_SOURCE_PREFIX,
+    BROKEN_SOURCE_PREFIX,
+    NAMESPACE_SOURCE_PREFIX,
+    REDACTED_SOURCE_PREFIX,
+    IDENTITY_SOURCE_PREFIX,
+    EMBEDDED_WITH_SOURCE_PREFIX,
+    agentsdb_core::language::LANGUAGE_SOURCE_PREFIX,
+    agentsdb_core::chunking::PART_OF_SOURCE_PREFIX,
+];
+
+/// Whether source string `source` starts with one of the [`RESERVED_SOURCE_PREFIXES`], so it is
+/// not provenance.
+pub fn is_reserved_source(source: &str) -> bool {
+    RESERVED_SOURCE_PREFIXES
+        .iter()
+        .any(|prefix| source.starts_with(prefix))
+}
+
+/// Namespace of a chunk with `sources`, from its first `namespace:<name>` source string.
+pub fn namespace_of<'a>(sources: &[SourceRef<'a>]) -> Option<&'a str> {
+    sources.iter().find_map(|s| match s {
+        SourceRef::String(v) => v.strip_prefix(NAMESPACE_SOURCE_PREFIX),
+        SourceRef::ChunkId(_) => None,
+    })
+}
+
+/// Identity of whoever wrote a chunk with `sources`, from its first `identity:<label>` source
+/// string.
+pub fn identity_of<'a>(sources: &[SourceRef<'a>]) -> Option<&'a str> {
+    sources.iter().find_map(|s| match s {
+        SourceRef::String(v) => v.strip_prefix(IDENTITY_SOURCE_PREFIX),
+        SourceRef::ChunkId(_) => None,
+    })
+}
+
+/// Language tag of a chunk with `sources`, from its first `lang:<tag>` source string.
+pub fn language_of<'a>(sources: &[SourceRef<'a>]) -> Option<&'a str> {
+    sources.iter().find_map(|s| match s {
+        SourceRef::String(v) => v.strip_prefix(agentsdb_core::language::LANGUAGE_SOURCE_PREFIX),
+        SourceRef::ChunkId(_) => None,
+    })
+}
 
 #[derive(Debug, Clone)]
 pub struct SearchQuery {
@@ -38,6 +133,12 @@ pub struct SearchOptions {
     pub use_index: bool,
     /// Search mode: semantic only or hybrid (lexical + semantic)
     pub mode: SearchMode,
+    /// Reuse the results of an identical earlier search over the same layer generations from
+    /// [`ResultCache::global`], and store new results there.
+    pub cache_results: bool,
+    /// Reuse the selection pass another process stored under [`SELECTION_CACHE_DIR`] next to
+    /// the layers for the same layer files, and store it there when missing.
+    pub disk_cache: bool,
 }
 
 impl Default for SearchOptions {
@@ -45,6 +146,8 @@ impl Default for SearchOptions {
         Self {
             use_index: false,
             mode: SearchMode::default(),
+            cache_results: false,
+            disk_cache: false,
         }
     }
 }
@@ -59,6 +162,44 @@ pub struct LayerSet {
 
 impl LayerSet {
     pub fn open(&self) -> Result<Vec<(LayerId, LayerFile)>, Error> {
+        self.open_with(|p| LayerFile::open(p))
+    }
+
+    /// Like [`open`](Self::open), reusing the handles cached in `pool`.
+    pub fn open_pooled(&self, pool: &LayerPool) -> Result<Vec<(LayerId, LayerFile)>, Error> {
+        self.open_with(|path| pool.get(path))
+    }
+
+    /// Like [`open_pooled`](Self::open_pooled), first checking the signature of every layer
+    /// `trust` covers: an untrusted layer fails the open under [`TrustMode::Enforce`] and is
+    /// logged under [`TrustMode::Warn`].
+    ///
+    /// [`TrustMode::Enforce`]: agentsdb_embeddings::signing::TrustMode::Enforce
+    /// [`TrustMode::Warn`]: agentsdb_embeddings::signing::TrustMode::Warn
+    pub fn open_trusted(
+        &self,
+        pool: &LayerPool,
+        trust: &TrustPolicy,
+    ) -> Result<Vec<(LayerId, LayerFile)>, Error> {
+        for (layer, path) in [
+            ("local", &self.local),
+            ("user", &self.user),
+            ("delta", &self.delta),
+            ("base", &self.base),
+        ] {
+            let Some(path) = path.as_deref().filter(|_| trust.covers(layer)) else {
+                continue;
+            };
+            let status = pool.signature_status(path)?;
+            trust.check(layer, std::path::Path::new(path), &status)?;
+        }
+        self.open_pooled(pool)
+    }
+
+    fn open_with(
+        &self,
+        open: impl Fn(&str) -> Result<LayerFile, Error>,
+    ) -> Result<Vec<(LayerId, LayerFile)>, Error> {
         let mut layers = Vec::new();
         for (layer_id, path) in [
             (LayerId::Local, &self.local),
@@ -67,7 +208,7 @@ impl LayerSet {
             (LayerId::Base, &self.base),
         ] {
             if let Some(path) = path {
-                layers.push((layer_id, LayerFile::open(path)?));
+                layers.push((layer_id, open(path)?));
             }
         }
         validate_schema_compatible(&layers)?;
@@ -86,6 +227,24 @@ pub fn search_layers_with_options(
     layers: &[(LayerId, LayerFile)],
     query: &SearchQuery,
     options: SearchOptions,
+) -> Result<Vec<SearchResult>, Error> {
+    if !options.cache_results {
+        return search_uncached(layers, query, options);
+    }
+    let cache = ResultCache::global();
+    let key = result_cache::cache_key(layers, query, options);
+    if let Some(results) = cache.get(&key) {
+        return Ok(results);
+    }
+    let results = search_uncached(layers, query, options)?;
+    cache.insert(key, results.clone());
+    Ok(results)
+}
+
+fn search_uncached(
+    layers: &[(LayerId, LayerFile)],
+    query: &SearchQuery,
+    options: SearchOptions,
 ) -> Result<Vec<SearchResult>, Error> {
     if query.k == 0 {
         return Err(FormatError::InvalidValue {
@@ -107,102 +266,42 @@ pub fn search_layers_with_options(
     // append-only updates within a layer.
     // In hybrid mode with query_text, lexical tier comparison allows better matches from
     // lower-precedence layers to surface.
-    let selection = compute_selection(layers, query.query_text.as_deref())?;
-
-    let kind_filter: Option<HashSet<&str>> = if query.filters.kinds.is_empty() {
-        None
+    let (selection, retracted) = if options.disk_cache {
+        selection_cache::load_or_build(layers, query.query_text.as_deref())?
     } else {
-        Some(query.filters.kinds.iter().map(|s| s.as_str()).collect())
+        (
+            compute_selection(layers, query.query_text.as_deref())?,
+            retracted_chunk_ids(layers)?,
+        )
     };
 
-    let query_norm = l2_norm(&query.embedding);
-    let mut tmp = vec![0.0f32; dim];
-    let mut hits: Vec<(SearchResult, u32)> = Vec::new(); // (result, priority_tier)
-
+    let filter = ChunkFilter::new(&query.filters);
     let layers_by_id: HashMap<LayerId, &LayerFile> =
         layers.iter().map(|(id, f)| (*id, f)).collect();
-
-    let index_lookup = if options.use_index {
-        IndexLookup::open_for_layers(layers)?
-    } else {
-        IndexLookup::empty()
-    };
-
-    let use_hybrid = options.mode == SearchMode::Hybrid && query.query_text.is_some();
-
-    for (chunk_id, selected) in selection.selected.iter() {
-        let layer = layers_by_id
-            .get(&selected.layer)
-            .ok_or(SchemaError::Mismatch(
-                "selected layer missing from layer set",
-            ))?;
+    let mut scorer = SemanticScorer::new(layers, &query.embedding, options.use_index)?;
+    let hybrid_text = query
+        .query_text
+        .as_deref()
+        .filter(|_| options.mode == SearchMode::Hybrid);
+    let mut hits: Vec<Hit> = Vec::new();
+
+    for (chunk_id, selected) in &selection.selected {
+        let layer = layer_by_id(&layers_by_id, selected.layer)?;
         let chunk = selected.chunk;
-
-        if let Some(kinds) = &kind_filter {
-            if !kinds.contains(chunk.kind) {
-                continue;
-            }
-        } else if chunk.kind == KIND_OPTIONS || chunk.kind.starts_with("meta.") {
+        if is_retracted(&retracted, selected.layer, chunk.id) || !filter.admits_record(&chunk) {
+            continue;
+        }
+        let sources = layer.sources_for(chunk.rel_start, chunk.rel_count)?;
+        if !filter.admits_sources(&sources) {
             continue;
         }
 
-        // Compute semantic similarity score
-        let semantic_score = if let Some(index) = index_lookup.index_for(selected.layer) {
-            let (row_norm, row_opt) = index.row_f32_and_norm(chunk.embedding_row)?;
-            match row_opt {
-                Some(row) => {
-                    cosine_similarity_row_norm(&query.embedding, query_norm, row, row_norm)
-                }
-                None => {
-                    layer.read_embedding_row_f32(chunk.embedding_row, &mut tmp)?;
-                    cosine_similarity_row_norm(&query.embedding, query_norm, &tmp, row_norm)
-                }
-            }
-        } else {
-            layer.read_embedding_row_f32(chunk.embedding_row, &mut tmp)?;
-            cosine_similarity(&query.embedding, query_norm, &tmp)
-        };
-
-        let sources = layer
-            .sources_for(chunk.rel_start, chunk.rel_count)?
-            .into_iter()
-            .map(|s| match s {
-                SourceRef::ChunkId(id) => ProvenanceRef::ChunkId(ChunkId(id)),
-                SourceRef::String(v) => ProvenanceRef::SourceString(v.to_string()),
-            })
-            .collect();
-
-        let out_chunk = Chunk {
-            id: ChunkId(chunk.id),
-            kind: chunk.kind.to_string(),
-            content: chunk.content.to_string(),
-            author: match chunk.author {
-                "human" => Author::Human,
-                "mcp" => Author::Mcp,
-                _other => {
-                    return Err(FormatError::InvalidValue {
-                        field: "ChunkRecord.author_str_id",
-                        reason: "must resolve to 'human' or 'mcp'",
-                    }
-                    .into());
-                }
-            },
-            confidence: chunk.confidence,
-            created_at_unix_ms: chunk.created_at_unix_ms,
-            sources,
-        };
-
-        // Compute final score based on mode
-        let (final_score, priority_tier) = if use_hybrid {
-            if let Some(ref query_text) = query.query_text {
-                let lexical_match = compute_lexical_match(query_text, &out_chunk.content);
-                let (tier, score) = compute_hybrid_score(lexical_match, semantic_score);
-                (score, tier)
-            } else {
-                (semantic_score, 6) // Fallback to pure semantic
-            }
-        } else {
-            (semantic_score, 6) // Pure semantic mode
+        let (semantic_score, approximate) = scorer.score(selected.layer, layer, &chunk)?;
+        let out_chunk = result_chunk(&chunk, sources)?;
+        let lexical = hybrid_text.map(|text| compute_lexical_match(text, &out_chunk.content));
+        let (priority_tier, final_score) = match lexical {
+            Some(lexical_match) => compute_hybrid_score(lexical_match, semantic_score),
+            None => (6, semantic_score), // Pure semantic mode
         };
 
         hits.push((
@@ -217,9 +316,238 @@ pub fn search_layers_with_options(
                     .unwrap_or_default(),
             },
             priority_tier,
+            approximate.then_some(ApproximateHit {
+                embedding_row: chunk.embedding_row,
+                lexical,
+            }),
         ));
     }
 
+    sort_hits(&mut hits);
+    rescore_approximate_hits(&mut hits, &layers_by_id, &mut scorer, query.k)?;
+
+    // Extract results and truncate
+    let results: Vec<SearchResult> = hits.into_iter().map(|(r, _, _)| r).take(query.k).collect();
+    Ok(results)
+}
+
+/// A search hit: the result, its priority tier, and the row to rescore exactly when the score
+/// came from a binary code.
+type Hit = (SearchResult, u32, Option<ApproximateHit>);
+
+fn layer_by_id<'a>(
+    layers_by_id: &HashMap<LayerId, &'a LayerFile>,
+    layer: LayerId,
+) -> Result<&'a LayerFile, Error> {
+    layers_by_id
+        .get(&layer)
+        .copied()
+        .ok_or_else(|| SchemaError::Mismatch("selected layer missing from layer set").into())
+}
+
+/// The [`SearchFilters`] of a query, checked against each selected chunk.
+struct ChunkFilter<'q> {
+    filters: &'q SearchFilters,
+    kinds: Option<HashSet<&'q str>>,
+}
+
+impl<'q> ChunkFilter<'q> {
+    fn new(filters: &'q SearchFilters) -> Self {
+        let kinds =
+            (!filters.kinds.is_empty()).then(|| filters.kinds.iter().map(String::as_str).collect());
+        Self { filters, kinds }
+    }
+
+    /// Whether the kind and confidence of `chunk` pass. Without a kind filter, options,
+    /// tombstones and `meta.*` records never do.
+    fn admits_record(&self, chunk: &agentsdb_format::ChunkView<'_>) -> bool {
+        let kind_admitted = match &self.kinds {
+            Some(kinds) => kinds.contains(chunk.kind),
+            None => {
+                chunk.kind != KIND_OPTIONS
+                    && chunk.kind != KIND_TOMBSTONE
+                    && !chunk.kind.starts_with("meta.")
+            }
+        };
+        kind_admitted
+            && !self
+                .filters
+                .min_confidence
+                .is_some_and(|min| chunk.confidence < min)
+    }
+
+    /// Whether the namespace and language tags among a chunk's `sources` pass.
+    fn admits_sources(&self, sources: &[SourceRef<'_>]) -> bool {
+        if let Some(namespace) = &self.filters.namespace {
+            if namespace_of(sources) != Some(namespace.as_str()) {
+                return false;
+            }
+        }
+        match &self.filters.language {
+            Some(language) => language_of(sources)
+                .is_some_and(|tag| agentsdb_core::language::matches(tag, language)),
+            None => true,
+        }
+    }
+}
+
+/// Cosine similarity of chunk embeddings to the query, read from whatever is at hand: a binary
+/// code or stored row in a sidecar index, a pass over a blocked matrix, or the layer's own row.
+struct SemanticScorer<'q> {
+    query: &'q [f32],
+    query_norm: f32,
+    query_code: Vec<u8>,
+    index_lookup: IndexLookup,
+    /// `(dot, row norm)` of every row of the blocked layers without an index.
+    scanned: HashMap<LayerId, Vec<(f32, f32)>>,
+    row: Vec<f32>,
+}
+
+impl<'q> SemanticScorer<'q> {
+    fn new(
+        layers: &[(LayerId, LayerFile)],
+        query: &'q [f32],
+        use_index: bool,
+    ) -> Result<Self, Error> {
+        let index_lookup = if use_index {
+            IndexLookup::open_for_layers(layers)?
+        } else {
+            IndexLookup::empty()
+        };
+
+        // Blocked matrices are scored in one sequential pass rather than row by row.
+        let mut scanned: HashMap<LayerId, Vec<(f32, f32)>> = HashMap::new();
+        for (id, layer) in layers {
+            if layer.embedding_matrix.layout == EmbeddingLayout::Blocked
+                && index_lookup.index_for(*id).is_none()
+            {
+                scanned.insert(*id, layer.dot_and_norm_rows(query)?);
+            }
+        }
+
+        Ok(Self {
+            query,
+            query_norm: l2_norm(query),
+            query_code: binary_code(query),
+            index_lookup,
+            scanned,
+            row: vec![0.0f32; query.len()],
+        })
+    }
+
+    /// The similarity of `chunk` of `layer` to the query, and whether it is only an estimate
+    /// from a binary code (see [`Self::exact`]).
+    fn score(
+        &mut self,
+        layer_id: LayerId,
+        layer: &LayerFile,
+        chunk: &agentsdb_format::ChunkView<'_>,
+    ) -> Result<(f32, bool), Error> {
+        let index = self.index_lookup.index_for(layer_id);
+        if let Some(code) = index
+            .map(|index| index.binary_code(chunk.embedding_row))
+            .transpose()?
+            .flatten()
+        {
+            let score = binary_code_similarity(code, &self.query_code, layer.embedding_matrix.dim);
+            return Ok((score, true));
+        }
+        let score = if let Some(index) = index {
+            let (row_norm, row_opt) = index.row_f32_and_norm(chunk.embedding_row)?;
+            let row = if let Some(row) = row_opt {
+                row
+            } else {
+                layer.read_embedding_row_f32(chunk.embedding_row, &mut self.row)?;
+                &self.row
+            };
+            cosine_similarity_row_norm(self.query, self.query_norm, row, row_norm)
+        } else if let Some((dot, row_norm)) = self.scanned.get(&layer_id).and_then(|rows| {
+            (chunk.embedding_row as usize)
+                .checked_sub(1)
+                .and_then(|i| rows.get(i))
+        }) {
+            cosine_from_dot(*dot, self.query_norm, *row_norm)
+        } else {
+            self.exact(layer, chunk.embedding_row)?
+        };
+        Ok((score, false))
+    }
+
+    /// The similarity of row `embedding_row` of `layer` to the query, from the row itself.
+    fn exact(&mut self, layer: &LayerFile, embedding_row: u32) -> Result<f32, Error> {
+        layer.read_embedding_row_f32(embedding_row, &mut self.row)?;
+        Ok(cosine_similarity(self.query, self.query_norm, &self.row))
+    }
+}
+
+/// Binary codes only rank candidates: rescores the best few of sorted `hits` exactly, then
+/// ranks them again.
+fn rescore_approximate_hits(
+    hits: &mut Vec<Hit>,
+    layers_by_id: &HashMap<LayerId, &LayerFile>,
+    scorer: &mut SemanticScorer<'_>,
+    k: usize,
+) -> Result<(), Error> {
+    if !hits.iter().any(|(_, _, approx)| approx.is_some()) {
+        return Ok(());
+    }
+    hits.truncate(k.saturating_mul(BINARY_RESCORE_FACTOR));
+    for (result, tier, approx) in hits.iter_mut() {
+        let Some(approx) = approx.take() else {
+            continue;
+        };
+        let layer = layer_by_id(layers_by_id, result.layer)?;
+        let semantic_score = scorer.exact(layer, approx.embedding_row)?;
+        (*tier, result.score) = match approx.lexical {
+            Some(lexical_match) => compute_hybrid_score(lexical_match, semantic_score),
+            None => (6, semantic_score),
+        };
+    }
+    sort_hits(hits);
+    Ok(())
+}
+
+/// `chunk` as a search result returns it, with `sources` resolved.
+fn result_chunk(
+    chunk: &agentsdb_format::ChunkView<'_>,
+    sources: Vec<SourceRef<'_>>,
+) -> Result<Chunk, Error> {
+    let sources = sources
+        .into_iter()
+        .map(|s| match s {
+            SourceRef::ChunkId(id) => ProvenanceRef::ChunkId(ChunkId(id)),
+            SourceRef::String(v) => ProvenanceRef::SourceString(v.to_string()),
+        })
+        .collect();
+
+    Ok(Chunk {
+        id: ChunkId(chunk.id),
+        kind: chunk.kind.to_string(),
+        content: chunk.content.to_string(),
+        author: match chunk.author {
+            "human" => Author::Human,
+            "mcp" => Author::Mcp,
+            _other => {
+                return Err(FormatError::InvalidValue {
+                    field: "ChunkRecord.author_str_id",
+                    reason: "must resolve to 'human' or 'mcp'",
+                }
+                .into());
+            }
+        },
+        confidence: chunk.confidence,
+        created_at_unix_ms: chunk.created_at_unix_ms,
+        sources,
+    })
+}
+
+/// A hit scored from its binary code, kept so it can be rescored exactly.
+struct ApproximateHit {
+    embedding_row: u32,
+    lexical: Option<LexicalMatch>,
+}
+
+fn sort_hits(hits: &mut [Hit]) {
     // Sort by priority tier first, then by score within tier
     hits.sort_by(|a, b| {
         a.1.cmp(&b.1) // Priority tier (lower is better)
@@ -230,13 +558,40 @@ pub fn search_layers_with_options(
             .then_with(|| a.0.chunk.id.cmp(&b.0.chunk.id))
             .then_with(|| a.0.layer.cmp(&b.0.layer))
     });
+}
 
-    // Extract results and truncate
-    let results: Vec<SearchResult> = hits.into_iter().map(|(r, _)| r).take(query.k).collect();
-    Ok(results)
+/// Chunk ids retracted by tombstones, mapped to the highest-precedence layer holding a
+/// tombstone for them. A tombstone retracts the id in its own layer and every lower one.
+pub fn retracted_chunk_ids(
+    layers: &[(LayerId, LayerFile)],
+) -> Result<HashMap<u32, LayerId>, Error> {
+    let mut out: HashMap<u32, LayerId> = HashMap::new();
+    for (layer_id, layer) in layers {
+        for chunk in layer.chunks() {
+            let chunk = chunk?;
+            if chunk.kind != KIND_TOMBSTONE {
+                continue;
+            }
+            for source in layer.sources_for(chunk.rel_start, chunk.rel_count)? {
+                if let SourceRef::ChunkId(id) = source {
+                    out.entry(id)
+                        .and_modify(|l| *l = (*l).min(*layer_id))
+                        .or_insert(*layer_id);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Whether chunk `id` as stored in `layer` is retracted (see [`retracted_chunk_ids`]).
+pub fn is_retracted(retracted: &HashMap<u32, LayerId>, layer: LayerId, id: u32) -> bool {
+    // `LayerId` orders by precedence, highest first.
+    retracted.get(&id).is_some_and(|by| *by <= layer)
 }
 
-fn validate_schema_compatible(layers: &[(LayerId, LayerFile)]) -> Result<(), Error> {
+/// Checks that all layers share the embedding dim, element type, and quantization scale.
+pub fn validate_schema_compatible(layers: &[(LayerId, LayerFile)]) -> Result<(), Error> {
     if layers.len() <= 1 {
         return Ok(());
     }
@@ -287,7 +642,7 @@ fn compute_selection<'a>(
     let mut selected: HashMap<ChunkId, SelectedChunk<'_>> = HashMap::new();
     let mut hidden_by: HashMap<ChunkId, Vec<LayerId>> = HashMap::new();
 
-    for (layer_id, layer) in layers {
+    for (index, (layer_id, layer)) in layers.iter().enumerate() {
         let mut last_by_id: HashMap<ChunkId, agentsdb_format::ChunkView<'_>> = HashMap::new();
 
         for chunk_res in layer.chunks() {
@@ -296,9 +651,25 @@ fn compute_selection<'a>(
         }
 
         for (id, chunk) in last_by_id {
-            // Read chunk content for lexical tier computation
-            let content = chunk.content.to_string();
-            let new_tier = get_lexical_tier(query_text, &content);
+            // Tiers only decide between versions of an id held by several layers, so skip the
+            // lexical match for ids no other layer holds. Files without a chunk id section would
+            // need a scan per id, so their ids always count as shared.
+            let mut shared = false;
+            if query_text.is_some() {
+                for (other, (_, file)) in layers.iter().enumerate() {
+                    if other != index
+                        && (!file.has_chunk_ids_section() || file.contains_chunk_id(id.get())?)
+                    {
+                        shared = true;
+                        break;
+                    }
+                }
+            }
+            let new_tier = if shared {
+                get_lexical_tier(query_text, chunk.content)
+            } else {
+                get_lexical_tier(None, chunk.content)
+            };
 
             if let Some(existing) = selected.get(&id) {
                 // Chunk ID already exists in higher-priority layer
@@ -342,6 +713,16 @@ fn compute_selection<'a>(
     })
 }
 
+/// Number of chunk ids visible across `layers` after precedence: the selection pass every search
+/// starts with. Exposed for the benchmarks.
+#[doc(hidden)]
+pub fn selected_chunk_count(
+    layers: &[(LayerId, LayerFile)],
+    query_text: Option<&str>,
+) -> Result<usize, Error> {
+    Ok(compute_selection(layers, query_text)?.selected.len())
+}
+
 fn score_for_sort(v: f32) -> f32 {
     if v.is_finite() {
         v
@@ -387,6 +768,14 @@ fn cosine_similarity_row_norm(query: &[f32], query_norm: f32, row: &[f32], row_n
     dot / (query_norm * row_norm)
 }
 
+fn cosine_from_dot(dot: f32, query_norm: f32, row_norm: f32) -> f32 {
+    if query_norm == 0.0 || row_norm == 0.0 {
+        0.0
+    } else {
+        dot / (query_norm * row_norm)
+    }
+}
+
 /// Extract title from chunk content (first markdown heading or first line)
 fn extract_title(content: &str) -> &str {
     let trimmed = content.trim();
@@ -511,6 +900,7 @@ fn compute_hybrid_score(lexical_match: LexicalMatch, semantic_score: f32) -> (u3
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{chunk, schema, write_layer};
     use agentsdb_format::EmbeddingElementType;
     use std::path::PathBuf;
 
@@ -766,6 +1156,74 @@ mod tests {
         assert_eq!(local_1.hidden_layers, vec![LayerId::Base]);
     }
 
+    #[test]
+    fn tombstone_retracts_chunk_in_own_and_lower_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = agentsdb_format::LayerSchema {
+            dim: 2,
+            element_type: EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        let chunk = |id: u32, kind: &str, sources: Vec<agentsdb_format::ChunkSource>| {
+            agentsdb_format::ChunkInput {
+                id,
+                kind: kind.to_string(),
+                content: format!("chunk {id}"),
+                author: "human".to_string(),
+                confidence: 1.0,
+                created_at_unix_ms: 0,
+                embedding: vec![1.0, 0.0],
+                sources,
+            }
+        };
+        let base_path = dir.path().join("AGENTS.db");
+        let delta_path = dir.path().join("AGENTS.delta.db");
+        let local_path = dir.path().join("AGENTS.local.db");
+        agentsdb_format::write_layer_atomic(
+            &base_path,
+            &schema,
+            &mut [chunk(1, "note", Vec::new()), chunk(2, "note", Vec::new())],
+            None,
+        )
+        .unwrap();
+        agentsdb_format::write_layer_atomic(
+            &local_path,
+            &schema,
+            &mut [chunk(2, "note", Vec::new())],
+            None,
+        )
+        .unwrap();
+        let retract = |id| vec![agentsdb_format::ChunkSource::ChunkId(id)];
+        agentsdb_format::write_layer_atomic(
+            &delta_path,
+            &schema,
+            &mut [
+                chunk(10, KIND_TOMBSTONE, retract(1)),
+                chunk(11, KIND_TOMBSTONE, retract(2)),
+            ],
+            None,
+        )
+        .unwrap();
+
+        let layers = vec![
+            (LayerId::Local, LayerFile::open(&local_path).unwrap()),
+            (LayerId::Delta, LayerFile::open(&delta_path).unwrap()),
+            (LayerId::Base, LayerFile::open(&base_path).unwrap()),
+        ];
+        let q = SearchQuery {
+            embedding: vec![1.0, 0.0],
+            k: 10,
+            filters: SearchFilters::default(),
+            query_text: None,
+        };
+        let res = search_layers(&layers, &q).unwrap();
+
+        // Base id=1 is retracted; local id=2 sits above the delta tombstone and stays.
+        let ids: Vec<(LayerId, u32)> = res.iter().map(|r| (r.layer, r.chunk.id.get())).collect();
+        assert_eq!(ids, vec![(LayerId::Local, 2)]);
+    }
+
     #[test]
     fn search_with_index_matches_bruteforce() {
         let data = build_layer_two_chunks_f32(false);
@@ -785,6 +1243,7 @@ mod tests {
             &index_path,
             IndexBuildOptions {
                 store_embeddings_even_if_f32: false,
+                binary_codes: false,
             },
         )
         .unwrap();
@@ -798,9 +1257,9 @@ mod tests {
         };
 
         let brute =
-            search_layers_with_options(&layers, &q, SearchOptions { use_index: false, mode: SearchMode::Semantic }).unwrap();
+            search_layers_with_options(&layers, &q, SearchOptions { use_index: false, mode: SearchMode::Semantic, ..SearchOptions::default() }).unwrap();
         let indexed =
-            search_layers_with_options(&layers, &q, SearchOptions { use_index: true, mode: SearchMode::Semantic }).unwrap();
+            search_layers_with_options(&layers, &q, SearchOptions { use_index: true, mode: SearchMode::Semantic, ..SearchOptions::default() }).unwrap();
 
         assert_eq!(brute.len(), indexed.len());
         for (a, b) in brute.iter().zip(indexed.iter()) {
@@ -810,4 +1269,306 @@ mod tests {
             assert_eq!(a.chunk.content, b.chunk.content);
         }
     }
+
+    #[test]
+    fn blocked_layout_scores_like_row_major() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunks: Vec<agentsdb_format::ChunkInput> = (1u8..=20)
+            .map(|id| {
+                let embedding = vec![f32::from(id), f32::from(20 - id), 1.0];
+                chunk(u32::from(id), &format!("chunk {id}"), embedding)
+            })
+            .collect();
+        let search = |layout: EmbeddingLayout| {
+            let path = dir.path().join(format!("{}.db", layout.name()));
+            let schema = agentsdb_format::LayerSchema {
+                layout,
+                ..schema(3)
+            };
+            write_layer(&path, &schema, chunks.clone()).unwrap();
+            let layers = vec![(LayerId::Base, LayerFile::open(&path).unwrap())];
+            let q = SearchQuery {
+                embedding: vec![1.0, 0.5, 0.0],
+                k: 5,
+                filters: SearchFilters::default(),
+                query_text: None,
+            };
+            search_layers(&layers, &q)
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.chunk.id.get(), r.score.to_bits()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            search(EmbeddingLayout::Blocked),
+            search(EmbeddingLayout::RowMajor)
+        );
+    }
+
+    #[test]
+    fn binary_index_rescores_candidates_exactly() {
+        let dir = tempfile::tempdir().unwrap();
+        let layer_path = dir.path().join("AGENTS.db");
+        let chunks: Vec<agentsdb_format::ChunkInput> = (1u8..=40)
+            .map(|id| {
+                let embedding = vec![
+                    f32::from(id) - 20.5,
+                    20.5 - f32::from(id) * 0.5,
+                    f32::from(id % 3) - 1.0,
+                    1.0,
+                ];
+                chunk(u32::from(id), &format!("chunk {id}"), embedding)
+            })
+            .collect();
+        write_layer(&layer_path, &schema(4), chunks).unwrap();
+        let layer = LayerFile::open(&layer_path).unwrap();
+        let index_path = default_index_path_for_layer(&layer_path);
+        build_layer_index(
+            &layer,
+            &index_path,
+            IndexBuildOptions {
+                store_embeddings_even_if_f32: false,
+                binary_codes: true,
+            },
+        )
+        .unwrap();
+        assert!(layer_index_is_fresh(&layer, &index_path).unwrap());
+        assert_eq!(
+            index_build_options(&index_path).unwrap(),
+            Some(IndexBuildOptions {
+                store_embeddings_even_if_f32: false,
+                binary_codes: true,
+            })
+        );
+
+        let layers = vec![(LayerId::Base, layer)];
+        let q = SearchQuery {
+            embedding: vec![1.0, -0.25, 0.5, 0.1],
+            k: 3,
+            filters: SearchFilters::default(),
+            query_text: None,
+        };
+        let search = |use_index: bool| {
+            let options = SearchOptions {
+                use_index,
+                mode: SearchMode::Semantic,
+                ..SearchOptions::default()
+            };
+            search_layers_with_options(&layers, &q, options)
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.chunk.id.get(), r.score.to_bits()))
+                .collect::<Vec<_>>()
+        };
+
+        // Candidates come from Hamming distance, but returned scores are exact cosines.
+        assert_eq!(search(true), search(false));
+    }
+
+    #[test]
+    fn hybrid_selection_compares_tiers_of_shared_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let local_path = dir.path().join("AGENTS.local.db");
+        let base_path = dir.path().join("AGENTS.db");
+        let note = vec![1.0, 0.0];
+        write_layer(
+            &local_path,
+            &schema(2),
+            vec![chunk(1, "unrelated note", note.clone())],
+        )
+        .unwrap();
+        write_layer(
+            &base_path,
+            &schema(2),
+            vec![
+                chunk(1, "# Deploy guide", note.clone()),
+                chunk(2, "# Release checklist", note),
+            ],
+        )
+        .unwrap();
+
+        let layers = vec![
+            (LayerId::Local, LayerFile::open(&local_path).unwrap()),
+            (LayerId::Base, LayerFile::open(&base_path).unwrap()),
+        ];
+        assert!(layers.iter().all(|(_, f)| f.has_chunk_ids_section()));
+        let selection = compute_selection(&layers, Some("deploy guide")).unwrap();
+
+        // Id 1 is in both layers, and the base version matches the query better.
+        let shared = &selection.selected[&ChunkId(1)];
+        assert_eq!(shared.layer, LayerId::Base);
+        assert_eq!(selection.hidden_by[&ChunkId(1)], vec![LayerId::Local]);
+        assert_eq!(selection.selected[&ChunkId(2)].layer, LayerId::Base);
+    }
+
+    #[test]
+    fn disk_cached_selection_matches_a_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let local_path = dir.path().join("AGENTS.local.db");
+        let base_path = dir.path().join("AGENTS.db");
+        let note = vec![1.0, 0.0];
+        write_layer(
+            &local_path,
+            &schema(2),
+            vec![
+                chunk(1, "unrelated note", note.clone()),
+                chunk(3, "# Deploy steps", note.clone()),
+            ],
+        )
+        .unwrap();
+        write_layer(
+            &base_path,
+            &schema(2),
+            vec![
+                chunk(1, "# Deploy guide", note.clone()),
+                chunk(2, "# Release checklist", note.clone()),
+            ],
+        )
+        .unwrap();
+
+        let open = || {
+            vec![
+                (LayerId::Local, LayerFile::open(&local_path).unwrap()),
+                (LayerId::Base, LayerFile::open(&base_path).unwrap()),
+            ]
+        };
+        let q = SearchQuery {
+            embedding: vec![1.0, 0.0],
+            k: 10,
+            filters: SearchFilters::default(),
+            query_text: Some("deploy guide".to_string()),
+        };
+        let cached = SearchOptions {
+            disk_cache: true,
+            ..SearchOptions::default()
+        };
+        let found = |layers: &[(LayerId, LayerFile)], options: SearchOptions| {
+            search_layers_with_options(layers, &q, options)
+                .unwrap()
+                .iter()
+                .map(|r| (r.layer, r.chunk.id.get(), r.hidden_layers.clone()))
+                .collect::<Vec<_>>()
+        };
+        let expected = found(&open(), SearchOptions::default());
+        assert!(expected.contains(&(LayerId::Base, 1, vec![LayerId::Local])));
+
+        let cache_dir = dir.path().join(SELECTION_CACHE_DIR);
+        let entries = || {
+            let mut names: Vec<_> = std::fs::read_dir(&cache_dir)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().into_string().unwrap())
+                .filter(|n| n.ends_with(".agsel"))
+                .collect();
+            names.sort();
+            names
+        };
+        // The first search writes the entry, a later process reads it.
+        assert_eq!(found(&open(), cached), expected);
+        let written = entries();
+        assert_eq!(written.len(), 1);
+        assert_eq!(found(&open(), cached), expected);
+
+        // A damaged entry is rebuilt.
+        std::fs::write(cache_dir.join(&written[0]), b"AGSL").unwrap();
+        assert_eq!(found(&open(), cached), expected);
+        let rebuilt = std::fs::metadata(cache_dir.join(&written[0])).unwrap();
+        assert!(rebuilt.len() > 4);
+
+        // Changed layer contents get an entry of their own.
+        write_layer(
+            &local_path,
+            &schema(2),
+            vec![chunk(3, "# Deploy steps", note)],
+        )
+        .unwrap();
+        let layers = open();
+        assert_eq!(
+            found(&layers, cached),
+            found(&layers, SearchOptions::default())
+        );
+        assert_eq!(entries().len(), 2);
+    }
+
+    #[test]
+    fn cached_results_follow_layer_generations() {
+        let data = build_layer_two_chunks_f32(false);
+        let dir = tempfile::tempdir().unwrap();
+        let layer_path = dir.path().join("AGENTS.db");
+        std::fs::write(&layer_path, &data).unwrap();
+
+        let layers = vec![(LayerId::Base, LayerFile::open(&layer_path).unwrap())];
+        let q = SearchQuery {
+            embedding: vec![1.0, 0.0],
+            k: 10,
+            filters: SearchFilters::default(),
+            query_text: None,
+        };
+        let options = SearchOptions {
+            cache_results: true,
+            ..SearchOptions::default()
+        };
+        let key = result_cache::cache_key(&layers, &q, options);
+        let cache = ResultCache::global();
+        assert!(cache.get(&key).is_none());
+
+        let found = search_layers_with_options(&layers, &q, options).unwrap();
+        let cached = cache.get(&key).expect("results should be cached");
+        let ids = |results: &[SearchResult]| -> Vec<u32> {
+            results.iter().map(|r| r.chunk.id.get()).collect()
+        };
+        assert_eq!(ids(&found), ids(&cached));
+
+        // Clones share the generation; a reopened file (the pool reopens changed layers) does not.
+        let cloned = vec![(LayerId::Base, layers[0].1.clone())];
+        assert_eq!(result_cache::cache_key(&cloned, &q, options), key);
+        let reopened = vec![(LayerId::Base, LayerFile::open(&layer_path).unwrap())];
+        assert!(cache
+            .get(&result_cache::cache_key(&reopened, &q, options))
+            .is_none());
+
+        // Searches through an index are keyed apart, and by the index they would read.
+        let indexed = SearchOptions {
+            use_index: true,
+            ..options
+        };
+        let unindexed_key = result_cache::cache_key(&layers, &q, indexed);
+        assert_ne!(unindexed_key, key);
+        build_layer_index(
+            &layers[0].1,
+            default_index_path_for_layer(&layer_path),
+            IndexBuildOptions {
+                store_embeddings_even_if_f32: false,
+                binary_codes: true,
+            },
+        )
+        .unwrap();
+        assert_ne!(result_cache::cache_key(&layers, &q, indexed), unindexed_key);
+    }
+
+    #[test]
+    fn index_goes_stale_when_layer_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let layer_path = dir.path().join("AGENTS.db");
+        let index_path = default_index_path_for_layer(&layer_path);
+        std::fs::write(&layer_path, build_layer_two_chunks_f32(false)).unwrap();
+
+        let layer = LayerFile::open(&layer_path).unwrap();
+        assert!(!layer_index_is_fresh(&layer, &index_path).unwrap());
+        build_layer_index(
+            &layer,
+            &index_path,
+            IndexBuildOptions {
+                store_embeddings_even_if_f32: false,
+                binary_codes: false,
+            },
+        )
+        .unwrap();
+        assert!(layer_index_is_fresh(&layer, &index_path).unwrap());
+        drop(layer);
+
+        std::fs::write(&layer_path, build_layer_two_chunks_f32(true)).unwrap();
+        let layer = LayerFile::open(&layer_path).unwrap();
+        assert!(!layer_index_is_fresh(&layer, &index_path).unwrap());
+    }
 }
@@ -0,0 +1,175 @@
+use agentsdb_core::error::Error;
+use agentsdb_embeddings::signing::{verify_layer, SignatureStatus};
+use agentsdb_format::{FileStamp, LayerFile};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Open layer files keyed by path, shared across threads.
+///
+/// [`get`](Self::get) stats the file and hands out a clone of the cached handle (which shares
+/// its mapping) while the file is unchanged, and reopens it otherwise. Servers that search the
+/// same layers on every request use [`LayerPool::global`] instead of [`LayerFile::open`].
+/// Signature checks are cached the same way, see [`signature_status`](Self::signature_status).
+#[derive(Debug, Default)]
+pub struct LayerPool {
+    entries: Mutex<HashMap<PathBuf, (FileStamp, LayerFile)>>,
+    signatures: Mutex<HashMap<PathBuf, (FileStamp, SignatureStatus)>>,
+}
+
+impl LayerPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The pool shared by everything in this process.
+    pub fn global() -> &'static Self {
+        static GLOBAL: OnceLock<LayerPool> = OnceLock::new();
+        GLOBAL.get_or_init(Self::default)
+    }
+
+    /// The layer at `path`, as [`LayerFile::open`] would return it.
+    pub fn get(&self, path: impl AsRef<Path>) -> Result<LayerFile, Error> {
+        let path = path.as_ref();
+        let stamp = match FileStamp::of(path) {
+            Ok(stamp) => stamp,
+            Err(err) => {
+                self.invalidate(path);
+                return Err(err.into());
+            }
+        };
+        let Ok(mut entries) = self.entries.lock() else {
+            return LayerFile::open(path);
+        };
+        if let Some((_, file)) = entries.get(path).filter(|(cached, _)| *cached == stamp) {
+            return Ok(file.clone());
+        }
+        let file = LayerFile::open(path)?;
+        entries.insert(path.to_path_buf(), (file.stamp(), file.clone()));
+        Ok(file)
+    }
+
+    /// [`verify_layer`] for the layer at `path`, checked once per version of the file.
+    pub fn signature_status(&self, path: impl AsRef<Path>) -> Result<SignatureStatus, Error> {
+        let path = path.as_ref();
+        let stamp = FileStamp::of(path)?;
+        if let Some((_, status)) = self
+            .signatures
+            .lock()
+            .ok()
+            .and_then(|signatures| signatures.get(path).cloned())
+            .filter(|(cached, _)| *cached == stamp)
+        {
+            return Ok(status);
+        }
+        let status = verify_layer(&self.get(path)?);
+        if let Ok(mut signatures) = self.signatures.lock() {
+            signatures.insert(path.to_path_buf(), (stamp, status.clone()));
+        }
+        Ok(status)
+    }
+
+    /// Drops the cached handle for `path`, if any.
+    pub fn invalidate(&self, path: impl AsRef<Path>) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(path.as_ref());
+        }
+        if let Ok(mut signatures) = self.signatures.lock() {
+            signatures.remove(path.as_ref());
+        }
+    }
+
+    /// Drops every cached handle.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+        if let Ok(mut signatures) = self.signatures.lock() {
+            signatures.clear();
+        }
+    }
+
+    /// Number of cached handles.
+    pub fn len(&self) -> usize {
+        self.entries.lock().map_or(0, |entries| entries.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chunk, schema, write_layer};
+
+    fn write(path: &Path, ids: &[u32]) -> Result<(), Error> {
+        let chunks = ids
+            .iter()
+            .map(|&id| chunk(id, &format!("chunk {id}"), vec![0.0; 2]))
+            .collect();
+        write_layer(path, &schema(2), chunks)
+    }
+
+    #[test]
+    fn reopens_layers_replaced_on_disk() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("AGENTS.local.db");
+        write(&path, &[1])?;
+
+        let pool = LayerPool::new();
+        let first = pool.get(&path)?;
+        assert_eq!(first.chunk_count, 1);
+        assert_eq!(
+            pool.get(&path)?.file_bytes().as_ptr(),
+            first.file_bytes().as_ptr(),
+            "unchanged layer is served from the cache"
+        );
+
+        write(&path, &[1, 2])?;
+        assert_eq!(pool.get(&path)?.chunk_count, 2);
+        assert_eq!(
+            first.chunk_count, 1,
+            "handed-out handles keep their mapping"
+        );
+
+        std::fs::remove_file(&path)?;
+        assert!(pool.get(&path).is_err());
+        assert!(pool.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn trusted_opens_check_signatures() -> Result<(), Error> {
+        use agentsdb_embeddings::signing::{TrustMode, TrustPolicy};
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("AGENTS.db");
+        write(&path, &[1])?;
+        let pool = LayerPool::new();
+        assert_eq!(pool.signature_status(&path)?, SignatureStatus::Unsigned);
+
+        let set = crate::LayerSet {
+            base: Some(path.display().to_string()),
+            user: None,
+            delta: None,
+            local: None,
+        };
+        let mut trust = TrustPolicy {
+            mode: TrustMode::Enforce,
+            public_keys: Vec::new(),
+            layers: vec!["base".to_string()],
+        };
+        assert!(matches!(
+            set.open_trusted(&pool, &trust),
+            Err(Error::Trust(_))
+        ));
+        trust.mode = TrustMode::Warn;
+        assert_eq!(set.open_trusted(&pool, &trust)?.len(), 1);
+        trust.layers = vec!["local".to_string()];
+        trust.mode = TrustMode::Enforce;
+        assert_eq!(set.open_trusted(&pool, &trust)?.len(), 1);
+        Ok(())
+    }
+}
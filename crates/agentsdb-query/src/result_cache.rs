@@ -0,0 +1,215 @@
+//! In-process cache of search results, for callers that run the same searches over and over
+//! (a dashboard polling a fixed query, for instance).
+//!
+//! Searches with [`SearchOptions::cache_results`] set consult [`ResultCache::global`] before
+//! scoring anything. Entries are keyed by the [`LayerFile::generation`] of every searched layer
+//! plus a hash of the query, so a layer that changed (and was reopened, as [`crate::LayerPool`]
+//! does on the next lookup) never serves stale results: its older entries just stop matching and
+//! age out in least-recently-used order. Searches that use sidecar indexes also hash the size and
+//! modification time of each layer's index, since building or dropping one changes the results.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::UNIX_EPOCH;
+
+use agentsdb_core::types::{LayerId, SearchResult};
+use agentsdb_format::LayerFile;
+
+use crate::index::default_index_path_for_layer;
+use crate::{SearchMode, SearchOptions, SearchQuery};
+
+/// Entries [`ResultCache::global`] holds until [`ResultCache::set_capacity`] changes it.
+pub const DEFAULT_CAPACITY: usize = 128;
+
+/// Layer generations searched, and the SHA-256 of the query and index state.
+type Key = (Vec<(LayerId, u64)>, [u8; 32]);
+
+/// Search results by layer generations and query; see the module docs.
+#[derive(Debug)]
+pub struct ResultCache {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    capacity: usize,
+    entries: HashMap<Key, (Vec<SearchResult>, u64)>,
+    /// Bumped on every hit and insert; the entry with the lowest tick is the least recent.
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+/// Counters of a [`ResultCache`], see [`ResultCache::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResultCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl ResultCache {
+    /// A cache of up to `capacity` result lists; 0 caches nothing.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                capacity,
+                ..Inner::default()
+            }),
+        }
+    }
+
+    /// The cache searches with [`SearchOptions::cache_results`] use.
+    pub fn global() -> &'static Self {
+        static GLOBAL: OnceLock<ResultCache> = OnceLock::new();
+        GLOBAL.get_or_init(|| Self::new(DEFAULT_CAPACITY))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Changes how many result lists the cache holds, evicting the least recent ones if needed.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.lock();
+        inner.capacity = capacity;
+        while inner.entries.len() > capacity {
+            inner.evict_least_recent();
+        }
+    }
+
+    /// Drops every entry.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    pub fn stats(&self) -> ResultCacheStats {
+        let inner = self.lock();
+        ResultCacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            entries: inner.entries.len(),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &Key) -> Option<Vec<SearchResult>> {
+        let mut inner = self.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let found = inner.entries.get_mut(key).map(|(results, used)| {
+            *used = tick;
+            results.clone()
+        });
+        if found.is_some() {
+            inner.hits += 1;
+        } else {
+            inner.misses += 1;
+        }
+        found
+    }
+
+    pub(crate) fn insert(&self, key: Key, results: Vec<SearchResult>) {
+        let mut inner = self.lock();
+        if inner.capacity == 0 {
+            return;
+        }
+        while inner.entries.len() >= inner.capacity && !inner.entries.contains_key(&key) {
+            inner.evict_least_recent();
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.entries.insert(key, (results, tick));
+    }
+}
+
+impl Inner {
+    fn evict_least_recent(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, (_, used))| *used)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+/// The cache key of searching `layers` for `query` with `options`.
+pub(crate) fn cache_key(
+    layers: &[(LayerId, LayerFile)],
+    query: &SearchQuery,
+    options: SearchOptions,
+) -> Key {
+    let generations = layers
+        .iter()
+        .map(|(id, file)| (*id, file.generation()))
+        .collect();
+
+    // Every field is length-prefixed or tagged, so different queries never serialize alike.
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(query.embedding.len() as u64).to_le_bytes());
+    for v in &query.embedding {
+        buf.extend_from_slice(&v.to_bits().to_le_bytes());
+    }
+    buf.extend_from_slice(&(query.k as u64).to_le_bytes());
+    buf.extend_from_slice(&(query.filters.kinds.len() as u64).to_le_bytes());
+    for kind in &query.filters.kinds {
+        push_str(&mut buf, Some(kind));
+    }
+    push_str(&mut buf, query.filters.namespace.as_deref());
+    push_str(&mut buf, query.filters.language.as_deref());
+    match query.filters.min_confidence {
+        Some(min) => {
+            buf.push(1);
+            buf.extend_from_slice(&min.to_bits().to_le_bytes());
+        }
+        None => buf.push(0),
+    }
+    push_str(&mut buf, query.query_text.as_deref());
+    buf.push(match options.mode {
+        SearchMode::Semantic => 0,
+        SearchMode::Hybrid => 1,
+    });
+    // Binary codes in an index make scores approximate, so results depend on which indexes
+    // exist, and indexes are rebuilt without touching their layer.
+    buf.push(u8::from(options.use_index));
+    if options.use_index {
+        for (_, file) in layers {
+            push_index_stamp(&mut buf, &default_index_path_for_layer(file.path()));
+        }
+    }
+    (generations, agentsdb_embeddings::cache::sha256(&buf))
+}
+
+/// The size and modification time of the index at `path`, or a marker that there is none.
+fn push_index_stamp(buf: &mut Vec<u8>, path: &Path) {
+    let stamp = std::fs::metadata(path).ok().map(|meta| {
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos());
+        (meta.len(), modified)
+    });
+    match stamp {
+        Some((len, modified)) => {
+            buf.push(1);
+            buf.extend_from_slice(&len.to_le_bytes());
+            buf.extend_from_slice(&modified.to_le_bytes());
+        }
+        None => buf.push(0),
+    }
+}
+
+fn push_str(buf: &mut Vec<u8>, s: Option<&str>) {
+    match s {
+        Some(s) => {
+            buf.push(1);
+            buf.extend_from_slice(&(s.len() as u64).to_le_bytes());
+            buf.extend_from_slice(s.as_bytes());
+        }
+        None => buf.push(0),
+    }
+}
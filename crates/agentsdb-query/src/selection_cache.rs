@@ -0,0 +1,324 @@
+//! On-disk cache of the selection pass, for one-shot searches that run again and again (a
+//! script calling `agentsdb search --disk-cache` in a loop, for instance).
+//!
+//! Every search first decides which record of each chunk id is visible across the layers and
+//! which ids tombstones retract. Servers keep their layers open and cache whole results in
+//! [`crate::ResultCache`], but each CLI process would redo that pass. With
+//! [`SearchOptions::disk_cache`](crate::SearchOptions::disk_cache) set, the latest record of
+//! every id in every layer and the retracted ids are stored under `.agentsdb/cache/` next to the
+//! layers, keyed by the path and [`FileStamp`] (size, mtime and inode) of each searched layer,
+//! and memory-mapped by later processes. Stamping a layer costs one `fstat` at open, where
+//! hashing it would read the whole file on every search. Writers replace layers by renaming a
+//! new file into place, so a changed layer gets another key and a stale entry is never read; the
+//! newest
+//! [`KEEP_ENTRIES`] entries are kept. An unreadable entry is rebuilt, and a directory that
+//! cannot be written to just goes without the cache.
+
+use agentsdb_core::error::{Error, FormatError};
+use agentsdb_core::types::{ChunkId, LayerId};
+use agentsdb_embeddings::cache::sha256;
+use agentsdb_format::{FileStamp, LayerFile};
+use memmap2::Mmap;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::index::{
+    push_u16, push_u32, push_u64, read_bytes_32, read_u16, read_u32, read_u64, write_atomic,
+};
+use crate::{get_lexical_tier, retracted_chunk_ids, SelectedChunk, Selection};
+
+const MAGIC_AGSL: u32 = 0x4C53_4741; // 'A' 'G' 'S' 'L'
+
+const HEADER_LEN: usize = 56;
+const CANDIDATE_LEN: usize = 16;
+const RETRACTED_LEN: usize = 8;
+
+/// Cache directory, relative to the directory of the searched layers.
+pub const CACHE_DIR: &str = ".agentsdb/cache";
+
+/// Selection cache entries kept in [`CACHE_DIR`]; older ones are removed when a new one is
+/// written.
+const KEEP_ENTRIES: usize = 8;
+
+/// The latest record of chunk `id` in one layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Candidate {
+    id: u32,
+    layer: LayerId,
+    record: u64,
+}
+
+/// What the selection pass reads from the layers: candidates sorted by id and, per id, in the
+/// order of the searched layers, plus the ids retracted by tombstones.
+#[derive(Debug, Default, PartialEq)]
+struct Entry {
+    candidates: Vec<Candidate>,
+    retracted: HashMap<u32, LayerId>,
+}
+
+/// The selection and retracted ids of searching `layers` for `query_text`, from the entry
+/// cached for the layers' stamps or from a scan that is then cached.
+pub(crate) fn load_or_build<'a>(
+    layers: &'a [(LayerId, LayerFile)],
+    query_text: Option<&str>,
+) -> Result<(Selection<'a>, HashMap<u32, LayerId>), Error> {
+    let key = cache_key(layers);
+    let dir = cache_dir(layers);
+    let path = dir.join(format!("selection-{}.agsel", hex(&key)));
+
+    if let Some(entry) = Entry::open(&path, key, layers) {
+        if let Ok(selection) = entry.select(layers, query_text) {
+            return Ok((selection, entry.retracted));
+        }
+    }
+
+    let entry = Entry::scan(layers)?;
+    // A read-only checkout still searches, just without the cache.
+    if write_atomic(&path, &entry.to_bytes(key)).is_ok() {
+        let _ = std::fs::write(dir.join(".gitignore"), "*\n");
+        prune(&dir);
+    }
+    let selection = entry.select(layers, query_text)?;
+    Ok((selection, entry.retracted))
+}
+
+impl Entry {
+    fn scan(layers: &[(LayerId, LayerFile)]) -> Result<Self, Error> {
+        let mut candidates = Vec::new();
+        for (layer, file) in layers {
+            let mut latest: HashMap<u32, u64> = HashMap::new();
+            for record in 0..file.chunk_count {
+                latest.insert(file.chunk_at(record)?.id, record);
+            }
+            candidates.extend(latest.into_iter().map(|(id, record)| Candidate {
+                id,
+                layer: *layer,
+                record,
+            }));
+        }
+        // Stable, so the candidates of an id stay in the order of the searched layers.
+        candidates.sort_by_key(|c| c.id);
+        Ok(Self {
+            candidates,
+            retracted: retracted_chunk_ids(layers)?,
+        })
+    }
+
+    /// Picks the visible record of every id exactly like `compute_selection`: the first layer
+    /// holding it wins unless a later one matches `query_text` in a better lexical tier.
+    fn select<'a>(
+        &self,
+        layers: &'a [(LayerId, LayerFile)],
+        query_text: Option<&str>,
+    ) -> Result<Selection<'a>, Error> {
+        let files: HashMap<LayerId, &'a LayerFile> =
+            layers.iter().map(|(id, f)| (*id, f)).collect();
+        let mut selected: HashMap<ChunkId, SelectedChunk<'a>> = HashMap::new();
+        let mut hidden_by: HashMap<ChunkId, Vec<LayerId>> = HashMap::new();
+
+        for group in self.candidates.chunk_by(|a, b| a.id == b.id) {
+            // Tiers only decide between the layers of a shared id.
+            let query_text = query_text.filter(|_| group.len() > 1);
+            for candidate in group {
+                let Some(file) = files.get(&candidate.layer).copied() else {
+                    return Err(FormatError::InvalidValue {
+                        field: "AGSL.candidate.layer",
+                        reason: "not a searched layer",
+                    }
+                    .into());
+                };
+                let chunk = file.chunk_at(candidate.record)?;
+                if chunk.id != candidate.id {
+                    return Err(FormatError::InvalidValue {
+                        field: "AGSL.candidate.record",
+                        reason: "holds another chunk id",
+                    }
+                    .into());
+                }
+                let id = ChunkId(candidate.id);
+                let lexical_tier = get_lexical_tier(query_text, chunk.content);
+                let better = match selected.get(&id) {
+                    None => true,
+                    Some(existing) if lexical_tier < existing.lexical_tier => {
+                        hidden_by.entry(id).or_default().push(existing.layer);
+                        true
+                    }
+                    Some(_) => {
+                        hidden_by.entry(id).or_default().push(candidate.layer);
+                        false
+                    }
+                };
+                if better {
+                    selected.insert(
+                        id,
+                        SelectedChunk {
+                            layer: candidate.layer,
+                            chunk,
+                            lexical_tier,
+                        },
+                    );
+                }
+            }
+        }
+
+        Ok(Selection {
+            selected,
+            hidden_by,
+        })
+    }
+
+    /// The entry at `path`, if it exists, was written for `key` and only names `layers`.
+    fn open(path: &Path, key: [u8; 32], layers: &[(LayerId, LayerFile)]) -> Option<Self> {
+        let file = File::open(path).ok()?;
+        let mmap = unsafe { Mmap::map(&file).ok()? };
+        Self::parse(&mmap, key, layers).ok().flatten()
+    }
+
+    fn parse(
+        bytes: &[u8],
+        key: [u8; 32],
+        layers: &[(LayerId, LayerFile)],
+    ) -> Result<Option<Self>, Error> {
+        let mut off = 0usize;
+        if read_u32(bytes, &mut off)? != MAGIC_AGSL {
+            return Ok(None);
+        }
+        let major = read_u16(bytes, &mut off)?;
+        let minor = read_u16(bytes, &mut off)?;
+        if major != 1 || minor != 0 || read_bytes_32(bytes, &mut off)? != key {
+            return Ok(None);
+        }
+        let (Ok(candidate_count), Ok(retracted_count)) = (
+            usize::try_from(read_u64(bytes, &mut off)?),
+            usize::try_from(read_u64(bytes, &mut off)?),
+        ) else {
+            return Ok(None);
+        };
+        let expected_len = candidate_count
+            .checked_mul(CANDIDATE_LEN)
+            .zip(retracted_count.checked_mul(RETRACTED_LEN))
+            .and_then(|(c, r)| c.checked_add(r)?.checked_add(HEADER_LEN));
+        if expected_len != Some(bytes.len()) {
+            return Ok(None);
+        }
+        let layer_of = |code: u32| {
+            layers
+                .iter()
+                .map(|(id, _)| *id)
+                .find(|id| layer_code(*id) == code)
+        };
+
+        let mut entry = Self::default();
+        for _ in 0..candidate_count {
+            let id = read_u32(bytes, &mut off)?;
+            let Some(layer) = layer_of(read_u32(bytes, &mut off)?) else {
+                return Ok(None);
+            };
+            let record = read_u64(bytes, &mut off)?;
+            entry.candidates.push(Candidate { id, layer, record });
+        }
+        if !entry.candidates.is_sorted_by_key(|c| c.id) {
+            return Ok(None);
+        }
+        for _ in 0..retracted_count {
+            let id = read_u32(bytes, &mut off)?;
+            let Some(layer) = layer_of(read_u32(bytes, &mut off)?) else {
+                return Ok(None);
+            };
+            entry.retracted.insert(id, layer);
+        }
+        Ok(Some(entry))
+    }
+
+    fn to_bytes(&self, key: [u8; 32]) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(
+            HEADER_LEN
+                + self.candidates.len() * CANDIDATE_LEN
+                + self.retracted.len() * RETRACTED_LEN,
+        );
+        push_u32(&mut buf, MAGIC_AGSL);
+        push_u16(&mut buf, 1);
+        push_u16(&mut buf, 0);
+        buf.extend_from_slice(&key);
+        push_u64(&mut buf, self.candidates.len() as u64);
+        push_u64(&mut buf, self.retracted.len() as u64);
+        debug_assert_eq!(buf.len(), HEADER_LEN);
+        for c in &self.candidates {
+            push_u32(&mut buf, c.id);
+            push_u32(&mut buf, layer_code(c.layer));
+            push_u64(&mut buf, c.record);
+        }
+        let mut retracted: Vec<(u32, LayerId)> =
+            self.retracted.iter().map(|(id, l)| (*id, *l)).collect();
+        retracted.sort_unstable();
+        for (id, layer) in retracted {
+            push_u32(&mut buf, id);
+            push_u32(&mut buf, layer_code(layer));
+        }
+        buf
+    }
+}
+
+/// SHA-256 of the searched layers, in order, with their paths and [`FileStamp`]s.
+fn cache_key(layers: &[(LayerId, LayerFile)]) -> [u8; 32] {
+    let mut buf = Vec::new();
+    for (id, file) in layers {
+        let path = file.path().as_os_str().as_encoded_bytes();
+        let FileStamp {
+            len,
+            modified,
+            inode,
+        } = file.stamp();
+        let modified = modified
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos());
+        buf.extend_from_slice(&layer_code(*id).to_le_bytes());
+        buf.extend_from_slice(&(path.len() as u64).to_le_bytes());
+        buf.extend_from_slice(path);
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(&modified.to_le_bytes());
+        buf.extend_from_slice(&inode.to_le_bytes());
+    }
+    sha256(&buf)
+}
+
+/// [`CACHE_DIR`] in the directory of the first searched layer.
+fn cache_dir(layers: &[(LayerId, LayerFile)]) -> PathBuf {
+    let dir = layers
+        .first()
+        .and_then(|(_, f)| f.path().parent())
+        .unwrap_or_else(|| Path::new(""));
+    dir.join(CACHE_DIR)
+}
+
+const fn layer_code(layer: LayerId) -> u32 {
+    match layer {
+        LayerId::Session => 0,
+        LayerId::Local => 1,
+        LayerId::User => 2,
+        LayerId::Delta => 3,
+        LayerId::Base => 4,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Removes all but the [`KEEP_ENTRIES`] most recently written entries in `dir`.
+fn prune(dir: &Path) {
+    let Ok(read) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<(std::time::SystemTime, PathBuf)> = read
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "agsel"))
+        .filter_map(|p| Some((std::fs::metadata(&p).ok()?.modified().ok()?, p)))
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.0));
+    for (_, path) in entries.into_iter().skip(KEEP_ENTRIES) {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,39 @@
+//! Fixtures shared by the unit tests of this crate.
+
+use agentsdb_core::error::Error;
+use agentsdb_format::{ChunkInput, EmbeddingElementType, EmbeddingLayout, LayerSchema};
+use std::path::Path;
+
+/// A row-major f32 schema with `dim` dimensions; tests set the layout with struct update syntax.
+pub(crate) fn schema(dim: u32) -> LayerSchema {
+    LayerSchema {
+        dim,
+        element_type: EmbeddingElementType::F32,
+        quant_scale: 1.0,
+        layout: EmbeddingLayout::RowMajor,
+    }
+}
+
+/// A human note with full confidence, `embedding` and no sources.
+pub(crate) fn chunk(id: u32, content: &str, embedding: Vec<f32>) -> ChunkInput {
+    ChunkInput {
+        id,
+        kind: "note".to_string(),
+        content: content.to_string(),
+        author: "human".to_string(),
+        confidence: 1.0,
+        created_at_unix_ms: 0,
+        embedding,
+        sources: Vec::new(),
+    }
+}
+
+/// Writes `chunks` to a new layer at `path`.
+pub(crate) fn write_layer(
+    path: &Path,
+    schema: &LayerSchema,
+    mut chunks: Vec<ChunkInput>,
+) -> Result<(), Error> {
+    agentsdb_format::write_layer_atomic(path, schema, &mut chunks, None)?;
+    Ok(())
+}
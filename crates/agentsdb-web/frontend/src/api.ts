@@ -1,17 +1,30 @@
 import type {
   ListedLayer,
+  ListedRoot,
   LayerMeta,
   ChunksResponse,
   ChunkFull,
+  ChunkHistory,
   VersionResponse,
   ProposalRow,
   PromoteResponse,
+  PullRequestPromoteResponse,
   AddChunkRequest,
+  UpdateChunkRequest,
+  UpdateChunkResponse,
   ProposeRequest,
   ImportRequest,
   ImportResponse,
+  UploadLayerResponse,
   SearchRequest,
   SearchResponse,
+  LayerChangedEvent,
+  LayerDiff,
+  BatchRequest,
+  ChunkQuery,
+  IndexStatus,
+  StaleQueueResponse,
+  DecayReport,
 } from './types';
 
 class ApiError extends Error {
@@ -21,8 +34,55 @@ class ApiError extends Error {
   }
 }
 
+const TOKEN_KEY = 'agentsdb.authToken';
+
+// `agentsdb web --auth-token` prints a URL with `#token=...`; keep it and drop it from the address bar.
+function authToken(): string | null {
+  const match = window.location.hash.match(/token=([^&]+)/);
+  if (match) {
+    localStorage.setItem(TOKEN_KEY, decodeURIComponent(match[1]));
+    history.replaceState(null, '', window.location.pathname + window.location.search);
+  }
+  return localStorage.getItem(TOKEN_KEY);
+}
+
+function withAuth(options?: RequestInit): RequestInit {
+  const token = authToken();
+  if (!token) {
+    return options ?? {};
+  }
+  const headers = new Headers(options?.headers);
+  headers.set('authorization', `Bearer ${token}`);
+  return { ...options, headers };
+}
+
+// Root picked in the UI when the server was started with several `--root`s; null means the first.
+let currentRoot: string | null = null;
+
+// Routes `/api/...` to the selected root's `/api/roots/<name>/...`.
+function scoped(path: string): string {
+  if (!currentRoot || !path.startsWith('/api/') || path === '/api/roots') {
+    return path;
+  }
+  return `/api/roots/${encodeURIComponent(currentRoot)}/${path.slice('/api/'.length)}`;
+}
+
+async function authFetch(path: string, options?: RequestInit): Promise<Response> {
+  path = scoped(path);
+  const response = await fetch(path, withAuth(options));
+  if (response.status !== 401) {
+    return response;
+  }
+  const token = window.prompt('This server requires an access token:');
+  if (!token) {
+    return response;
+  }
+  localStorage.setItem(TOKEN_KEY, token.trim());
+  return fetch(path, withAuth(options));
+}
+
 async function request<T>(path: string, options?: RequestInit): Promise<T> {
-  const response = await fetch(path, options);
+  const response = await authFetch(path, options);
 
   if (!response.ok) {
     const text = await response.text();
@@ -38,6 +98,14 @@ async function request<T>(path: string, options?: RequestInit): Promise<T> {
 }
 
 export const api = {
+  async getRoots(): Promise<ListedRoot[]> {
+    return request<ListedRoot[]>('/api/roots');
+  },
+
+  setRoot(name: string | null) {
+    currentRoot = name;
+  },
+
   async getVersion(): Promise<VersionResponse> {
     return request<VersionResponse>('/api/version');
   },
@@ -50,12 +118,24 @@ export const api = {
     return request<LayerMeta>(`/api/layer/meta?path=${encodeURIComponent(path)}`);
   },
 
+  async buildIndex(
+    path: string,
+    storeEmbeddingsF32 = false
+  ): Promise<{ ok: boolean; path: string; index: IndexStatus }> {
+    return request('/api/index/build', {
+      method: 'POST',
+      headers: { 'content-type': 'application/json' },
+      body: JSON.stringify({ path, store_embeddings_f32: storeEmbeddingsF32 }),
+    });
+  },
+
   async getChunks(
     path: string,
     offset: number,
     limit: number,
     includeRemoved: boolean,
-    kind: string
+    kind: string,
+    query?: ChunkQuery
   ): Promise<ChunksResponse> {
     const params = new URLSearchParams({
       path,
@@ -64,6 +144,15 @@ export const api = {
       include_removed: includeRemoved ? '1' : '0',
       kind,
     });
+    if (query) {
+      if (query.q) params.set('q', query.q);
+      if (query.q && query.regex) params.set('regex', '1');
+      if (query.author) params.set('author', query.author);
+      if (query.namespace) params.set('namespace', query.namespace);
+      if (query.min_confidence) params.set('min_confidence', query.min_confidence);
+      if (query.sort !== 'id') params.set('sort', query.sort);
+      if (query.order) params.set('order', query.order);
+    }
     return request<ChunksResponse>(`/api/layer/chunks?${params}`);
   },
 
@@ -71,6 +160,12 @@ export const api = {
     return request<ChunkFull>(`/api/layer/chunk?path=${encodeURIComponent(path)}&id=${id}`);
   },
 
+  async getChunkHistory(path: string, id: number): Promise<ChunkHistory> {
+    return request<ChunkHistory>(
+      `/api/layer/chunk/history?path=${encodeURIComponent(path)}&id=${id}`
+    );
+  },
+
   async addChunk(data: AddChunkRequest): Promise<{ ok: boolean; path: string; id: number }> {
     return request('/api/layer/add', {
       method: 'POST',
@@ -79,6 +174,22 @@ export const api = {
     });
   },
 
+  async updateChunk(data: UpdateChunkRequest): Promise<UpdateChunkResponse> {
+    return request('/api/layer/update', {
+      method: 'POST',
+      headers: { 'content-type': 'application/json' },
+      body: JSON.stringify(data),
+    });
+  },
+
+  async batch(data: BatchRequest): Promise<{ ok: boolean; action: string; result: unknown }> {
+    return request('/api/layer/batch', {
+      method: 'POST',
+      headers: { 'content-type': 'application/json' },
+      body: JSON.stringify(data),
+    });
+  },
+
   async removeChunk(path: string, id: number): Promise<{ ok: boolean; removed: boolean }> {
     return request('/api/layer/remove', {
       method: 'POST',
@@ -89,7 +200,7 @@ export const api = {
 
   async exportLayer(path: string, format: string, redact: string): Promise<Blob> {
     const params = new URLSearchParams({ path, format, redact });
-    const response = await fetch(`/api/export?${params}`);
+    const response = await authFetch(`/api/export?${params}`);
     if (!response.ok) {
       throw new ApiError(await response.text(), response.status);
     }
@@ -104,6 +215,15 @@ export const api = {
     });
   },
 
+  async uploadLayer(file: Blob, path: string, overwrite: boolean): Promise<UploadLayerResponse> {
+    const params = new URLSearchParams({ path, overwrite: overwrite ? '1' : '0' });
+    return request(`/api/layer/upload?${params}`, {
+      method: 'POST',
+      headers: { 'content-type': 'application/octet-stream' },
+      body: file,
+    });
+  },
+
   async getProposals(includeAll: boolean = false): Promise<ProposalRow[]> {
     const params = new URLSearchParams();
     if (includeAll) {
@@ -122,12 +242,13 @@ export const api = {
 
   async acceptProposals(
     ids: number[],
-    skipExisting: boolean
+    skipExisting: boolean,
+    reason?: string
   ): Promise<PromoteResponse> {
     return request('/api/proposals/accept', {
       method: 'POST',
       headers: { 'content-type': 'application/json' },
-      body: JSON.stringify({ ids, skip_existing: skipExisting }),
+      body: JSON.stringify({ ids, skip_existing: skipExisting, reason }),
     });
   },
 
@@ -142,6 +263,36 @@ export const api = {
     });
   },
 
+  async getStaleChunks(threshold?: number): Promise<StaleQueueResponse> {
+    const params = new URLSearchParams();
+    if (threshold !== undefined) {
+      params.set('threshold', String(threshold));
+    }
+    return request<StaleQueueResponse>(
+      `/api/review/stale${params.toString() ? '?' + params : ''}`
+    );
+  },
+
+  async decayConfidence(maxAgeDays: number, factor: number): Promise<DecayReport> {
+    return request('/api/review/decay', {
+      method: 'POST',
+      headers: { 'content-type': 'application/json' },
+      body: JSON.stringify({ max_age_days: maxAgeDays, factor }),
+    });
+  },
+
+  async confirmChunk(
+    path: string,
+    id: number,
+    confidence: number
+  ): Promise<{ ok: boolean; path: string; id: number; superseded_id: number }> {
+    return request('/api/review/confirm', {
+      method: 'POST',
+      headers: { 'content-type': 'application/json' },
+      body: JSON.stringify({ path, id, confidence }),
+    });
+  },
+
   async promoteBatch(
     fromPath: string,
     toPath: string,
@@ -155,6 +306,20 @@ export const api = {
     });
   },
 
+  // Opens a pull request adding AGENTS.delta.db chunks to AGENTS.db.
+  async promoteViaPullRequest(ids: number[]): Promise<PullRequestPromoteResponse> {
+    return request('/api/promote/pr', {
+      method: 'POST',
+      headers: { 'content-type': 'application/json' },
+      body: JSON.stringify({ ids }),
+    });
+  },
+
+  async diffLayers(from: string, to: string): Promise<LayerDiff> {
+    const params = new URLSearchParams({ from, to });
+    return request<LayerDiff>(`/api/diff?${params}`);
+  },
+
   async searchChunks(data: SearchRequest): Promise<SearchResponse> {
     return request('/api/search', {
       method: 'POST',
@@ -162,6 +327,46 @@ export const api = {
       body: JSON.stringify(data),
     });
   },
+
+  // Streams `/api/events` with fetch rather than EventSource so the auth header is sent.
+  // Reconnects after a delay if the stream drops; call the returned function to stop.
+  subscribeLayerEvents(onChange: (event: LayerChangedEvent) => void): () => void {
+    const controller = new AbortController();
+    const connect = async () => {
+      try {
+        const response = await authFetch('/api/events', { signal: controller.signal });
+        if (!response.ok || !response.body) {
+          throw new ApiError(response.statusText, response.status);
+        }
+        const reader = response.body.pipeThrough(new TextDecoderStream()).getReader();
+        let buffer = '';
+        for (;;) {
+          const { value, done } = await reader.read();
+          if (done) {
+            break;
+          }
+          buffer += value;
+          const messages = buffer.split('\n\n');
+          buffer = messages.pop() ?? '';
+          for (const message of messages) {
+            const data = message
+              .split('\n')
+              .find((line) => line.startsWith('data: '));
+            if (data) {
+              onChange(JSON.parse(data.slice('data: '.length)) as LayerChangedEvent);
+            }
+          }
+        }
+      } catch {
+        // Fall through to reconnect unless we were stopped.
+      }
+      if (!controller.signal.aborted) {
+        setTimeout(connect, 3000);
+      }
+    };
+    connect();
+    return () => controller.abort();
+  },
 };
 
 export { ApiError };
@@ -6,19 +6,26 @@ import { ChunkViewer } from './components/ChunkViewer';
 import { AddChunkPanel } from './components/AddChunkPanel';
 import { EditChunkModal } from './components/EditChunkModal';
 import { ProposalsPanel } from './components/ProposalsPanel';
+import { ProposalQueue } from './components/ProposalQueue';
+import { ReviewQueue } from './components/ReviewQueue';
 import { ProposalDetailsModal } from './components/ProposalDetailsModal';
 import { ExportImportPanel } from './components/ExportImportPanel';
 import { PromoteModal } from './components/PromoteModal';
 import { ProposeModal } from './components/ProposeModal';
+import { LayerDiffModal } from './components/LayerDiffModal';
 import type {
   ListedLayer,
+  ListedRoot,
   LayerMeta,
   ChunkSummary,
+  ChunkQuery,
   ProposalRow,
   ChunkFull,
   AddChunkRequest,
   ImportRequest,
   ProposeRequest,
+  UpdateChunkRequest,
+  LayerChangedEvent,
 } from './types';
 import { api } from './api';
 
@@ -41,6 +48,8 @@ function useErrorHandler(setError: (error: string | null) => void) {
 
 export function App() {
   // Layer state
+  const [roots, setRoots] = useState<ListedRoot[]>([]);
+  const [selectedRoot, setSelectedRoot] = useState<string | null>(null);
   const [layers, setLayers] = useState<ListedLayer[]>([]);
   const [selectedLayer, setSelectedLayer] = useState<string>('');
   const [layerMeta, setLayerMeta] = useState<LayerMeta | null>(null);
@@ -51,6 +60,15 @@ export function App() {
   const [offset, setOffset] = useState(0);
   const [limit] = useState(100);
   const [kindFilter, setKindFilter] = useState('');
+  const [chunkQuery, setChunkQuery] = useState<ChunkQuery>({
+    q: '',
+    regex: false,
+    author: '',
+    namespace: '',
+    min_confidence: '',
+    sort: 'id',
+    order: '',
+  });
   const [includeRemoved, setIncludeRemoved] = useState(false);
 
   // Proposals state
@@ -59,12 +77,22 @@ export function App() {
   // UI state
   const [viewingChunk, setViewingChunk] = useState<ChunkFull | null>(null);
   const [editingChunk, setEditingChunk] = useState<ChunkFull | null>(null);
+  // Layer the viewed or edited chunk was loaded from
+  const [chunkLayer, setChunkLayer] = useState('');
   const [promotingChunk, setPromotingChunk] = useState<ChunkFull | null>(null);
   const [proposingChunk, setProposingChunk] = useState<ChunkFull | null>(null);
   const [viewingProposal, setViewingProposal] = useState<ProposalRow | null>(null);
+  const [showProposalQueue, setShowProposalQueue] = useState(false);
+  const [showReviewQueue, setShowReviewQueue] = useState(false);
   const [showAddPanel, setShowAddPanel] = useState(false);
   const [showExportImport, setShowExportImport] = useState(false);
   const [showMetadata, setShowMetadata] = useState(false);
+  // Set when the server was started with --read-only; write controls are hidden.
+  const [readOnly, setReadOnly] = useState(false);
+  const [showDiff, setShowDiff] = useState(false);
+
+  // Last change reported by the server's layer event stream
+  const [layerEvent, setLayerEvent] = useState<LayerChangedEvent | null>(null);
 
   // Loading and error state
   const [loading, setLoading] = useState(false);
@@ -111,7 +139,8 @@ export function App() {
         offset,
         limit,
         includeRemoved,
-        kindFilter
+        kindFilter,
+        chunkQuery
       );
       setChunks(response.items);
       setTotal(response.total);
@@ -127,11 +156,23 @@ export function App() {
   };
 
   // Event handlers
+  const handleRootChange = async (name: string) => {
+    api.setRoot(name);
+    setSelectedRoot(name);
+    await withErrorHandling(async () => {
+      const layersList = await api.getLayers();
+      setLayers(layersList);
+      setSelectedLayer(layersList[0]?.path ?? '');
+    });
+    await refreshProposals();
+  };
+
   const handleViewChunk = async (chunk: ChunkSummary) => {
     await withErrorHandling(async () => {
       // Use chunk's layer if available (from search results), otherwise use selected layer
       const layerToUse = chunk.layer || selectedLayer;
       const full = await api.getChunk(layerToUse, chunk.id);
+      setChunkLayer(layerToUse);
       setViewingChunk(full);
     });
   };
@@ -141,6 +182,7 @@ export function App() {
       // Use chunk's layer if available (from search results), otherwise use selected layer
       const layerToUse = chunk.layer || selectedLayer;
       const full = await api.getChunk(layerToUse, chunk.id);
+      setChunkLayer(layerToUse);
       setEditingChunk(full);
       setViewingChunk(null);
     });
@@ -158,9 +200,9 @@ export function App() {
     }
   };
 
-  const handleEditSubmit = async (data: AddChunkRequest) => {
+  const handleEditSubmit = async (data: Omit<UpdateChunkRequest, 'path'>) => {
     try {
-      await api.addChunk(data);
+      await api.updateChunk({ ...data, path: chunkLayer || selectedLayer });
       await loadChunks();
       await refreshLayers();
       setEditingChunk(null);
@@ -192,10 +234,21 @@ export function App() {
     setPromotingChunk(chunk);
   };
 
-  const handlePromoteSubmit = async (toPath: string, skipExisting: boolean) => {
+  const handlePromoteSubmit = async (toPath: string, skipExisting: boolean, viaPr: boolean) => {
     if (!promotingChunk) return;
 
     try {
+      if (viaPr) {
+        const result = await api.promoteViaPullRequest([promotingChunk.id]);
+        setPromotingChunk(null);
+        setError(null);
+        alert(
+          result.pull_request
+            ? `Opened ${result.pull_request.url}`
+            : `Chunk ${promotingChunk.id} is already in AGENTS.db`
+        );
+        return;
+      }
       await api.promoteBatch(selectedLayer, toPath, [promotingChunk.id], skipExisting);
       await loadChunks();
       await refreshLayers();
@@ -207,6 +260,28 @@ export function App() {
     }
   };
 
+  const handleBatch = async (action: 'retract' | 'promote', ids: number[]) => {
+    if (action === 'retract') {
+      const reason = prompt(`Retract ${ids.length} chunk(s)? Reason:`, 'retracted from the web UI');
+      if (reason === null) return;
+      await withErrorHandling(() =>
+        api.batch({
+          action,
+          ids,
+          path: selectedLayer,
+          scope: selectedLayer === 'AGENTS.local.db' ? 'local' : 'delta',
+          reason,
+        })
+      );
+    } else {
+      await withErrorHandling(() =>
+        api.batch({ action, ids, path: selectedLayer, to_path: 'AGENTS.user.db', skip_existing: true })
+      );
+    }
+    await loadChunks();
+    await refreshLayers();
+  };
+
   const handleRemoveChunk = async (chunk: ChunkFull | ChunkSummary) => {
     // Use chunk's layer if available (from search results), otherwise use selected layer
     const layerToUse = 'layer' in chunk && chunk.layer ? chunk.layer : selectedLayer;
@@ -231,14 +306,18 @@ export function App() {
     }
   };
 
-  const handleAcceptProposals = async (ids: number[], skipExisting: boolean) => {
+  const handleAcceptProposals = async (ids: number[], skipExisting: boolean, reason?: string) => {
     try {
-      const result = await api.acceptProposals(ids, skipExisting);
+      const result = await api.acceptProposals(ids, skipExisting, reason);
       await refreshProposals();
       await loadChunks();
       await refreshLayers();
       setError(null);
-      alert(`Promoted: ${result.promoted.length}, Skipped: ${result.skipped.length}`);
+      const awaiting = result.awaiting_approval?.length ?? 0;
+      alert(
+        `Promoted: ${result.promoted.length}, Skipped: ${result.skipped.length}` +
+          (awaiting > 0 ? `, Awaiting more approvals: ${awaiting}` : '')
+      );
     } catch (err) {
       setError(err instanceof Error ? err.message : String(err));
       throw err;
@@ -285,31 +364,74 @@ export function App() {
     }
   };
 
+  const handleUpload = async (file: File, path: string, overwrite: boolean) => {
+    const result = await api.uploadLayer(file, path, overwrite);
+    await refreshLayers();
+    setError(null);
+    return result;
+  };
+
   // Effects
   useEffect(() => {
     refreshLayers();
     refreshProposals();
+    api
+      .getVersion()
+      .then((res) => setReadOnly(res.read_only))
+      .catch(() => setReadOnly(false));
+    api
+      .getRoots()
+      .then(setRoots)
+      .catch(() => setRoots([]));
   }, []);
 
+  useEffect(() => api.subscribeLayerEvents(setLayerEvent), [selectedRoot]);
+
+  useEffect(() => {
+    if (!layerEvent) {
+      return;
+    }
+    refreshLayers();
+    refreshProposals();
+    if (layerEvent.path === selectedLayer && layerEvent.exists) {
+      refreshMeta();
+      loadChunks();
+    }
+  }, [layerEvent]);
+
   useEffect(() => {
     if (selectedLayer) {
       refreshMeta();
       setOffset(0);
     }
-  }, [selectedLayer]);
+  }, [selectedRoot, selectedLayer]);
 
   useEffect(() => {
     loadChunks();
-  }, [selectedLayer, offset, limit, kindFilter, includeRemoved]);
+  }, [selectedRoot, selectedLayer, offset, limit, kindFilter, includeRemoved, chunkQuery]);
 
   return (
     <div class="min-h-screen flex flex-col">
       <Header
+        roots={roots}
+        selectedRoot={selectedRoot ?? roots[0]?.name ?? ''}
+        onRootChange={handleRootChange}
         layers={layers}
         selectedLayer={selectedLayer}
         onLayerChange={setSelectedLayer}
         onShowMetadata={() => setShowMetadata(true)}
         onShowExportImport={() => setShowExportImport(true)}
+        onShowDiff={() => setShowDiff(true)}
+        onShowProposals={() => {
+          setShowReviewQueue(false);
+          setShowProposalQueue(true);
+        }}
+        pendingProposals={proposals.filter((p) => p.status === 'pending').length}
+        onShowReview={() => {
+          setShowProposalQueue(false);
+          setShowReviewQueue(true);
+        }}
+        readOnly={readOnly}
       />
 
       <main class="container mx-auto p-4 flex-1">
@@ -322,63 +444,94 @@ export function App() {
           </div>
         )}
 
-        {showAddPanel && (
-          <AddChunkPanel
-            embeddingDim={layerMeta?.embedding_dim}
-            onSubmit={handleAddChunk}
-            onCancel={() => setShowAddPanel(false)}
-          />
-        )}
-
-        <ChunkList
-          chunks={chunks}
-          total={total}
-          offset={offset}
-          limit={limit}
-          loading={loading}
-          selectedLayer={selectedLayer}
-          kindFilter={kindFilter}
-          includeRemoved={includeRemoved}
-          layerMeta={layerMeta}
-          onViewChunk={handleViewChunk}
-          onEditChunk={handleEditChunk}
-          onRemoveChunk={handleRemoveChunk}
-          onPageChange={setOffset}
-          onKindFilterChange={setKindFilter}
-          onIncludeRemovedChange={setIncludeRemoved}
-          onLoad={loadChunks}
-          onAdd={() => setShowAddPanel(!showAddPanel)}
-        />
-
-        {proposals.length > 0 && (
-          <ProposalsPanel
+        {showProposalQueue ? (
+          <ProposalQueue
             proposals={proposals}
-            onAccept={handleAcceptProposals}
-            onReject={handleRejectProposals}
-            onViewDetails={setViewingProposal}
+            onAccept={readOnly ? undefined : handleAcceptProposals}
+            onReject={readOnly ? undefined : handleRejectProposals}
             onRefresh={refreshProposals}
+            onClose={() => setShowProposalQueue(false)}
+          />
+        ) : showReviewQueue ? (
+          <ReviewQueue
+            readOnly={readOnly}
+            onChanged={() => {
+              loadChunks();
+              refreshLayers();
+            }}
+            onClose={() => setShowReviewQueue(false)}
           />
+        ) : (
+          <>
+            {showAddPanel && !readOnly && (
+              <AddChunkPanel
+                embeddingDim={layerMeta?.embedding_dim}
+                onSubmit={handleAddChunk}
+                onCancel={() => setShowAddPanel(false)}
+              />
+            )}
+
+            <ChunkList
+              chunks={chunks}
+              total={total}
+              offset={offset}
+              limit={limit}
+              loading={loading}
+              selectedLayer={selectedLayer}
+              kindFilter={kindFilter}
+              includeRemoved={includeRemoved}
+              layerMeta={layerMeta}
+              onViewChunk={handleViewChunk}
+              onEditChunk={readOnly ? undefined : handleEditChunk}
+              onRemoveChunk={readOnly ? undefined : handleRemoveChunk}
+              onBatch={readOnly ? undefined : handleBatch}
+              onPageChange={setOffset}
+              onKindFilterChange={setKindFilter}
+              chunkQuery={chunkQuery}
+              onChunkQueryChange={(q) => {
+                setChunkQuery(q);
+                setOffset(0);
+              }}
+              onIncludeRemovedChange={setIncludeRemoved}
+              onLoad={loadChunks}
+              onAdd={readOnly ? undefined : () => setShowAddPanel(!showAddPanel)}
+            />
+
+            {proposals.length > 0 && (
+              <ProposalsPanel
+                proposals={proposals}
+                onAccept={readOnly ? undefined : handleAcceptProposals}
+                onReject={readOnly ? undefined : handleRejectProposals}
+                onViewDetails={setViewingProposal}
+                onRefresh={refreshProposals}
+              />
+            )}
+          </>
         )}
       </main>
 
       {viewingChunk && (
         <ChunkViewer
           chunk={viewingChunk}
+          layer={chunkLayer}
           onClose={() => setViewingChunk(null)}
-          onPropose={handlePropose}
-          onPromote={handlePromote}
-          onRemove={handleRemoveChunk}
-          onEdit={(chunk) => {
-            setViewingChunk(null);
-            setEditingChunk(chunk);
-          }}
+          onPropose={readOnly ? undefined : handlePropose}
+          onPromote={readOnly ? undefined : handlePromote}
+          onRemove={readOnly ? undefined : handleRemoveChunk}
+          onEdit={
+            readOnly
+              ? undefined
+              : (chunk) => {
+                  setViewingChunk(null);
+                  setEditingChunk(chunk);
+                }
+          }
         />
       )}
 
       {editingChunk && (
         <EditChunkModal
           chunk={editingChunk}
-          embeddingDim={layerMeta?.embedding_dim}
           onSubmit={handleEditSubmit}
           onClose={() => setEditingChunk(null)}
         />
@@ -412,6 +565,14 @@ export function App() {
       {showMetadata && (
         <LayerMetadataModal
           layerMeta={layerMeta}
+          onBuildIndex={
+            readOnly
+              ? undefined
+              : async (storeEmbeddingsF32) => {
+                  await withErrorHandling(() => api.buildIndex(selectedLayer, storeEmbeddingsF32));
+                  await refreshMeta();
+                }
+          }
           onClose={() => setShowMetadata(false)}
         />
       )}
@@ -421,10 +582,13 @@ export function App() {
           selectedLayer={selectedLayer}
           embeddingDim={layerMeta?.embedding_dim}
           onExport={handleExport}
-          onImport={handleImport}
+          onImport={readOnly ? undefined : handleImport}
+          onUpload={readOnly ? undefined : handleUpload}
           onClose={() => setShowExportImport(false)}
         />
       )}
+
+      {showDiff && <LayerDiffModal layers={layers} onClose={() => setShowDiff(false)} />}
     </div>
   );
 }
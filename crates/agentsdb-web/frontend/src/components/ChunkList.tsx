@@ -1,5 +1,5 @@
 import { useState, useMemo, useEffect } from 'preact/hooks';
-import type { ChunkSummary, SearchResultJson, LayerMeta } from '../types';
+import type { ChunkSummary, ChunkQuery, SearchResultJson, LayerMeta } from '../types';
 import { api } from '../api';
 
 interface ChunkListProps {
@@ -13,13 +13,16 @@ interface ChunkListProps {
   includeRemoved?: boolean;
   layerMeta?: LayerMeta | null;
   onViewChunk: (chunk: ChunkSummary) => void;
-  onEditChunk: (chunk: ChunkSummary) => void;
+  onEditChunk?: (chunk: ChunkSummary) => void;
   onRemoveChunk?: (chunk: ChunkSummary) => void;
   onPageChange: (newOffset: number) => void;
   onKindFilterChange?: (kind: string) => void;
+  chunkQuery?: ChunkQuery;
+  onChunkQueryChange?: (query: ChunkQuery) => void;
   onIncludeRemovedChange?: (include: boolean) => void;
   onLoad?: () => void;
   onAdd?: () => void;
+  onBatch?: (action: 'retract' | 'promote', ids: number[]) => Promise<void>;
 }
 
 export function ChunkList({
@@ -37,15 +40,22 @@ export function ChunkList({
   onRemoveChunk,
   onPageChange,
   onKindFilterChange,
+  chunkQuery,
+  onChunkQueryChange,
   onIncludeRemovedChange,
   onLoad,
   onAdd,
+  onBatch,
 }: ChunkListProps) {
+  const [selectedIds, setSelectedIds] = useState<Set<number>>(new Set());
+  const [batchRunning, setBatchRunning] = useState(false);
   const [searchQuery, setSearchQuery] = useState('');
   const [searchMode, setSearchMode] = useState<'filter' | 'search'>('search');
   const [searchResults, setSearchResults] = useState<SearchResultJson[]>([]);
   const [searchLoading, setSearchLoading] = useState(false);
   const [searchError, setSearchError] = useState<string | null>(null);
+  // Content filter is applied on Enter so each keystroke doesn't refetch the layer.
+  const [contentFilter, setContentFilter] = useState(chunkQuery?.q ?? '');
 
   // Client-side filtering for filter mode
   const filteredChunks = useMemo(() => {
@@ -124,6 +134,34 @@ export function ChunkList({
 
   const isSearchActive = searchMode === 'search' && searchQuery.trim();
   const isLoading = loading || (searchLoading && searchMode === 'search');
+  // Search results span layers, so batch actions only apply to the plain layer listing.
+  const selectable = !!onBatch && !isSearchActive;
+  const canPromote = selectedLayer === 'AGENTS.delta.db' || selectedLayer === 'AGENTS.local.db';
+
+  useEffect(() => {
+    setSelectedIds(new Set());
+  }, [selectedLayer, chunks, isSearchActive]);
+
+  const toggleSelected = (id: number) => {
+    const next = new Set(selectedIds);
+    if (next.has(id)) {
+      next.delete(id);
+    } else {
+      next.add(id);
+    }
+    setSelectedIds(next);
+  };
+
+  const runBatch = async (action: 'retract' | 'promote') => {
+    if (!onBatch || selectedIds.size === 0) return;
+    setBatchRunning(true);
+    try {
+      await onBatch(action, Array.from(selectedIds));
+      setSelectedIds(new Set());
+    } finally {
+      setBatchRunning(false);
+    }
+  };
 
   return (
     <div class="card bg-base-200 shadow-xl mb-4">
@@ -231,6 +269,106 @@ export function ChunkList({
             </div>
           </div>
 
+          {/* Server-side filters (filter mode only) */}
+          {searchMode === 'filter' && chunkQuery && onChunkQueryChange && (
+            <div class="flex flex-wrap items-center gap-3">
+              <input
+                type="text"
+                placeholder="Content contains... (Enter to apply)"
+                class="input input-bordered input-sm w-64"
+                value={contentFilter}
+                onInput={(e) => setContentFilter((e.target as HTMLInputElement).value)}
+                onKeyPress={(e) => {
+                  if (e.key === 'Enter') {
+                    onChunkQueryChange({ ...chunkQuery, q: contentFilter });
+                  }
+                }}
+              />
+              <label class="label cursor-pointer gap-1 p-0">
+                <input
+                  type="checkbox"
+                  class="checkbox checkbox-sm"
+                  checked={chunkQuery.regex}
+                  onChange={(e) =>
+                    onChunkQueryChange({
+                      ...chunkQuery,
+                      q: contentFilter,
+                      regex: (e.target as HTMLInputElement).checked,
+                    })
+                  }
+                />
+                <span class="label-text text-sm">Regex</span>
+              </label>
+              <input
+                type="text"
+                placeholder="Author"
+                class="input input-bordered input-sm w-28"
+                value={chunkQuery.author}
+                onChange={(e) =>
+                  onChunkQueryChange({ ...chunkQuery, author: (e.target as HTMLInputElement).value })
+                }
+              />
+              <input
+                type="text"
+                placeholder="Namespace"
+                class="input input-bordered input-sm w-28"
+                value={chunkQuery.namespace}
+                onChange={(e) =>
+                  onChunkQueryChange({
+                    ...chunkQuery,
+                    namespace: (e.target as HTMLInputElement).value,
+                  })
+                }
+              />
+              <input
+                type="number"
+                min="0"
+                max="1"
+                step="0.05"
+                placeholder="Min conf."
+                class="input input-bordered input-sm w-28"
+                value={chunkQuery.min_confidence}
+                onChange={(e) =>
+                  onChunkQueryChange({
+                    ...chunkQuery,
+                    min_confidence: (e.target as HTMLInputElement).value,
+                  })
+                }
+              />
+              <div class="flex items-center gap-2">
+                <span class="text-sm font-semibold">Sort:</span>
+                <select
+                  class="select select-bordered select-sm"
+                  value={chunkQuery.sort}
+                  onChange={(e) =>
+                    onChunkQueryChange({
+                      ...chunkQuery,
+                      sort: (e.target as HTMLSelectElement).value as ChunkQuery['sort'],
+                    })
+                  }
+                >
+                  <option value="id">ID</option>
+                  <option value="created_at">Created</option>
+                  <option value="confidence">Confidence</option>
+                </select>
+                <select
+                  class="select select-bordered select-sm"
+                  value={chunkQuery.order}
+                  onChange={(e) =>
+                    onChunkQueryChange({
+                      ...chunkQuery,
+                      order: (e.target as HTMLSelectElement).value as ChunkQuery['order'],
+                    })
+                  }
+                >
+                  <option value="">Default order</option>
+                  <option value="asc">Ascending</option>
+                  <option value="desc">Descending</option>
+                </select>
+              </div>
+            </div>
+          )}
+
           {/* Search Input */}
           <div class="form-control w-full">
             <div class="flex items-center gap-0">
@@ -299,6 +437,33 @@ export function ChunkList({
             )}
           </div>
 
+          {selectable && selectedIds.size > 0 && (
+            <div class="flex items-center gap-2">
+              <span class="text-sm">{selectedIds.size} selected</span>
+              <button
+                class="btn btn-sm btn-error btn-outline"
+                onClick={() => runBatch('retract')}
+                disabled={batchRunning}
+                title="Append one tombstone per selected chunk"
+              >
+                Retract ({selectedIds.size})
+              </button>
+              {canPromote && (
+                <button
+                  class="btn btn-sm btn-success btn-outline"
+                  onClick={() => runBatch('promote')}
+                  disabled={batchRunning}
+                  title="Copy the selected chunks to AGENTS.user.db"
+                >
+                  Promote to user ({selectedIds.size})
+                </button>
+              )}
+              <button class="btn btn-sm btn-ghost" onClick={() => setSelectedIds(new Set())} disabled={batchRunning}>
+                Clear
+              </button>
+            </div>
+          )}
+
           {/* Pagination (only for filter mode) */}
           {searchMode === 'filter' && (
             <div class="flex justify-between items-center">
@@ -337,6 +502,22 @@ export function ChunkList({
             <table class="table table-zebra">
               <thead>
                 <tr>
+                  {selectable && (
+                    <th>
+                      <input
+                        type="checkbox"
+                        class="checkbox checkbox-sm"
+                        checked={displayChunks.length > 0 && displayChunks.every((c) => selectedIds.has(c.id))}
+                        onChange={(e) =>
+                          setSelectedIds(
+                            (e.target as HTMLInputElement).checked
+                              ? new Set(displayChunks.filter((c) => !c.removed).map((c) => c.id))
+                              : new Set()
+                          )
+                        }
+                      />
+                    </th>
+                  )}
                   <th>ID</th>
                   <th>Kind</th>
                   <th>Conf</th>
@@ -348,7 +529,10 @@ export function ChunkList({
               <tbody>
                 {displayChunks.length === 0 ? (
                   <tr>
-                    <td colSpan={searchMode === 'search' && isSearchActive ? 6 : 5} class="text-center py-8">
+                    <td
+                      colSpan={(searchMode === 'search' && isSearchActive ? 6 : 5) + (selectable ? 1 : 0)}
+                      class="text-center py-8"
+                    >
                       {searchQuery
                         ? searchMode === 'search'
                           ? 'No chunks match your semantic search'
@@ -361,12 +545,34 @@ export function ChunkList({
                     const searchResult = searchMode === 'search' && isSearchActive ? searchResults[idx] : null;
                     return (
                       <tr key={chunk.id}>
+                        {selectable && (
+                          <td>
+                            <input
+                              type="checkbox"
+                              class="checkbox checkbox-sm"
+                              checked={selectedIds.has(chunk.id)}
+                              disabled={chunk.removed}
+                              onChange={() => toggleSelected(chunk.id)}
+                            />
+                          </td>
+                        )}
                         <td class="mono">
                           {chunk.id}
                           {chunk.removed && <span class="badge badge-error ml-2">removed</span>}
                         </td>
                         <td>
                           <span class="badge">{chunk.kind}</span>
+                          {chunk.namespace && (
+                            <span class="badge badge-outline ml-1">{chunk.namespace}</span>
+                          )}
+                          {chunk.language && (
+                            <span class="badge badge-ghost ml-1">{chunk.language}</span>
+                          )}
+                          {chunk.identity && (
+                            <div class="text-xs opacity-70 mt-1" title="Written by">
+                              {chunk.identity}
+                            </div>
+                          )}
                         </td>
                         <td class="mono">{chunk.confidence.toFixed(2)}</td>
                         {searchMode === 'search' && isSearchActive && searchResult && (
@@ -386,13 +592,15 @@ export function ChunkList({
                             </button>
                             {!chunk.removed && (
                               <>
-                                <button
-                                  class="btn btn-ghost btn-xs"
-                                  onClick={() => onEditChunk(chunk)}
-                                  title="Edit chunk"
-                                >
-                                  Edit
-                                </button>
+                                {onEditChunk && (
+                                  <button
+                                    class="btn btn-ghost btn-xs"
+                                    onClick={() => onEditChunk(chunk)}
+                                    title="Edit chunk"
+                                  >
+                                    Edit
+                                  </button>
+                                )}
                                 {onRemoveChunk && (
                                   <button
                                     class="btn btn-ghost btn-xs text-error"
@@ -1,9 +1,28 @@
-import { useState } from 'preact/hooks';
-import type { ChunkFull } from '../types';
+import { useEffect, useState } from 'preact/hooks';
+import { api } from '../api';
+import type { ChunkFull, ChunkHistory, HistoryRelation } from '../types';
 import { renderMarkdown } from '../utils/markdown';
 
+const RELATION_LABELS: Record<HistoryRelation, string> = {
+  version: 'this chunk',
+  earlier_revision: 'earlier revision',
+  revision: 'revision',
+  tombstone: 'tombstone',
+  proposal_event: 'proposal',
+};
+
+const RELATION_BADGES: Record<HistoryRelation, string> = {
+  version: 'badge-primary',
+  earlier_revision: 'badge-ghost',
+  revision: 'badge-secondary',
+  tombstone: 'badge-error',
+  proposal_event: 'badge-accent',
+};
+
 interface ChunkViewerProps {
   chunk: ChunkFull | null;
+  // Layer the chunk was read from; its history is reconstructed from the same layer.
+  layer?: string;
   onClose: () => void;
   onPropose?: (chunk: ChunkFull) => void;
   onPromote?: (chunk: ChunkFull) => void;
@@ -11,13 +30,42 @@ interface ChunkViewerProps {
   onEdit?: (chunk: ChunkFull) => void;
 }
 
-export function ChunkViewer({ chunk, onClose, onPropose, onEdit, onPromote, onRemove }: ChunkViewerProps) {
+export function ChunkViewer({
+  chunk,
+  layer,
+  onClose,
+  onPropose,
+  onEdit,
+  onPromote,
+  onRemove,
+}: ChunkViewerProps) {
   const [showRaw, setShowRaw] = useState(false);
   const [isMaximized, setIsMaximized] = useState(false);
+  const [showHistory, setShowHistory] = useState(false);
+  const [history, setHistory] = useState<ChunkHistory | null>(null);
+  const [historyError, setHistoryError] = useState<string | null>(null);
+
+  useEffect(() => {
+    setHistory(null);
+    setHistoryError(null);
+    if (!showHistory || !chunk || !layer) return;
+    let cancelled = false;
+    api
+      .getChunkHistory(layer, chunk.id)
+      .then((h) => !cancelled && setHistory(h))
+      .catch((err) => !cancelled && setHistoryError(err instanceof Error ? err.message : String(err)));
+    return () => {
+      cancelled = true;
+    };
+  }, [showHistory, chunk?.id, layer]);
 
   if (!chunk) return null;
 
   const renderedContent = showRaw ? chunk.content : renderMarkdown(chunk.content);
+  // Who wrote the chunk, from its `identity:<label>` source (the author only tells human from mcp).
+  const identity = chunk.sources
+    .find((s) => s.startsWith('identity:'))
+    ?.slice('identity:'.length);
   const createdDate = chunk.created_at_unix_ms
     ? new Date(chunk.created_at_unix_ms).toLocaleString()
     : 'Unknown';
@@ -58,6 +106,7 @@ export function ChunkViewer({ chunk, onClose, onPropose, onEdit, onPromote, onRe
                   <circle cx="12" cy="7" r="4" />
                 </svg>
                 <span class="mono">{chunk.author}</span>
+                {identity && <span class="mono">({identity})</span>}
               </span>
               <span class="flex items-center gap-1">
                 <svg class="h-4 w-4" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2">
@@ -152,6 +201,64 @@ export function ChunkViewer({ chunk, onClose, onPropose, onEdit, onPromote, onRe
           )}
         </div>
 
+        {layer && (
+          <div class="mt-4">
+            <button class="btn btn-xs btn-ghost" onClick={() => setShowHistory(!showHistory)}>
+              {showHistory ? 'Hide history' : 'Show history'}
+            </button>
+            {showHistory && (
+              <div class="mt-2">
+                {historyError ? (
+                  <div class="alert alert-warning text-sm">{historyError}</div>
+                ) : !history ? (
+                  <span class="loading loading-spinner loading-sm"></span>
+                ) : (
+                  <ul class="timeline timeline-vertical timeline-compact">
+                    {history.entries.map((entry, idx) => (
+                      <li key={`${entry.layer}:${entry.chunk.id}`}>
+                        {idx > 0 && <hr />}
+                        <div class="timeline-start text-xs mono opacity-70">
+                          {new Date(entry.chunk.created_at_unix_ms).toLocaleString()}
+                        </div>
+                        <div class="timeline-middle">
+                          <span class={`badge badge-xs ${RELATION_BADGES[entry.relation]}`}></span>
+                        </div>
+                        <div class="timeline-end timeline-box text-sm w-full">
+                          <div class="flex flex-wrap gap-2 items-center mb-1">
+                            <span class={`badge badge-sm ${RELATION_BADGES[entry.relation]}`}>
+                              {entry.relation === 'proposal_event' && entry.action
+                                ? `${entry.action} #${entry.proposal_id}`
+                                : RELATION_LABELS[entry.relation]}
+                            </span>
+                            <span class="mono text-xs">
+                              {entry.layer}:{entry.chunk.id}
+                            </span>
+                            {entry.chunk.id === history.current_id &&
+                              entry.relation !== 'proposal_event' && (
+                                <span class="badge badge-sm badge-success">current</span>
+                              )}
+                            {entry.supersedes !== undefined && (
+                              <span class="text-xs opacity-70">supersedes {entry.supersedes}</span>
+                            )}
+                            {entry.retracts !== undefined && (
+                              <span class="text-xs opacity-70">retracts {entry.retracts}</span>
+                            )}
+                            <span class="text-xs opacity-70">by {entry.chunk.author}</span>
+                          </div>
+                          <pre class="whitespace-pre-wrap text-xs max-h-32 overflow-y-auto">
+                            {entry.chunk.content}
+                          </pre>
+                        </div>
+                        {idx < history.entries.length - 1 && <hr />}
+                      </li>
+                    ))}
+                  </ul>
+                )}
+              </div>
+            )}
+          </div>
+        )}
+
         <div class="modal-action mt-4">
           <div class="flex gap-2 flex-wrap w-full justify-between">
             <div class="flex gap-2">
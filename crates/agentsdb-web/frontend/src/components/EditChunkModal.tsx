@@ -1,24 +1,19 @@
 import { useState, useEffect } from 'preact/hooks';
-import type { ChunkFull, AddChunkRequest } from '../types';
+import type { ChunkFull, UpdateChunkRequest } from '../types';
 
 interface EditChunkModalProps {
   chunk: ChunkFull | null;
-  embeddingDim?: number;
-  onSubmit: (data: AddChunkRequest) => Promise<void>;
+  onSubmit: (data: Omit<UpdateChunkRequest, 'path'>) => Promise<void>;
   onClose: () => void;
 }
 
-export function EditChunkModal({
-  chunk,
-  embeddingDim = 128,
-  onSubmit,
-  onClose,
-}: EditChunkModalProps) {
+export function EditChunkModal({ chunk, onSubmit, onClose }: EditChunkModalProps) {
   const [scope, setScope] = useState<'local' | 'delta'>('delta');
   const [kind, setKind] = useState('');
   const [content, setContent] = useState('');
   const [confidence, setConfidence] = useState(0.8);
   const [sources, setSources] = useState('');
+  const [reason, setReason] = useState('');
   const [submitting, setSubmitting] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [isMaximized, setIsMaximized] = useState(false);
@@ -28,7 +23,8 @@ export function EditChunkModal({
       setKind(chunk.kind);
       setContent(chunk.content);
       setConfidence(chunk.confidence);
-      setSources((chunk.sources || []).join(', '));
+      setSources('');
+      setReason('');
       setError(null);
     }
   }, [chunk]);
@@ -49,14 +45,14 @@ export function EditChunkModal({
       .map((s) => s.trim())
       .filter(Boolean);
 
-    const data: AddChunkRequest = {
+    const data: Omit<UpdateChunkRequest, 'path'> = {
       scope,
       id: chunk.id,
       kind,
       content: content.trim(),
       confidence,
-      dim: embeddingDim,
       sources: sourcesArray.length > 0 ? sourcesArray : undefined,
+      reason: reason.trim() || undefined,
     };
 
     try {
@@ -170,7 +166,7 @@ export function EditChunkModal({
 
           <div class="form-control mt-4">
             <label class="label">
-              <span class="label-text">Sources</span>
+              <span class="label-text">Additional sources</span>
             </label>
             <input
               type="text"
@@ -180,6 +176,25 @@ export function EditChunkModal({
               placeholder="file.rs:42, doc.md:10 (comma-separated)"
               disabled={submitting}
             />
+            <label class="label">
+              <span class="label-text-alt">
+                The revision keeps the original's sources and records that it supersedes id={chunk.id}.
+              </span>
+            </label>
+          </div>
+
+          <div class="form-control mt-4">
+            <label class="label">
+              <span class="label-text">Reason</span>
+            </label>
+            <input
+              type="text"
+              class="input input-bordered"
+              value={reason}
+              onInput={(e) => setReason((e.target as HTMLInputElement).value)}
+              placeholder="superseded by a revised chunk"
+              disabled={submitting}
+            />
           </div>
 
           <div class="modal-action">
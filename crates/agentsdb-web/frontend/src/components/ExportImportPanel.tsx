@@ -1,11 +1,13 @@
 import { useState } from 'preact/hooks';
-import type { ImportRequest, ImportResponse } from '../types';
+import type { ImportRequest, ImportResponse, UploadLayerResponse } from '../types';
 
 interface ExportImportPanelProps {
   selectedLayer: string;
   embeddingDim?: number;
   onExport: (path: string, format: string, redact: string) => Promise<void>;
-  onImport: (data: ImportRequest) => Promise<ImportResponse>;
+  // Omitted on a read-only server, which leaves only the export tab.
+  onImport?: (data: ImportRequest) => Promise<ImportResponse>;
+  onUpload?: (file: File, path: string, overwrite: boolean) => Promise<UploadLayerResponse>;
   onClose: () => void;
 }
 
@@ -14,9 +16,10 @@ export function ExportImportPanel({
   embeddingDim = 128,
   onExport,
   onImport,
+  onUpload,
   onClose,
 }: ExportImportPanelProps) {
-  const [activeTab, setActiveTab] = useState<'export' | 'import'>('export');
+  const [activeTab, setActiveTab] = useState<'export' | 'import' | 'upload'>('export');
 
   // Export state
   const [exportFormat, setExportFormat] = useState('json');
@@ -34,6 +37,12 @@ export function ExportImportPanel({
   const [error, setError] = useState<string | null>(null);
   const [importResult, setImportResult] = useState<string | null>(null);
 
+  // Upload state
+  const [uploadFile, setUploadFile] = useState<File | null>(null);
+  const [uploadPath, setUploadPath] = useState('');
+  const [uploadOverwrite, setUploadOverwrite] = useState(false);
+  const [uploading, setUploading] = useState(false);
+
   const handleExport = async () => {
     setError(null);
     try {
@@ -69,9 +78,12 @@ export function ExportImportPanel({
 
     try {
       setImporting(true);
+      if (!onImport) return;
       const result = await onImport(request);
+      const usage = result.embedding_usage;
       setImportResult(
-        `${result.dry_run ? 'Dry run: ' : ''}Imported ${result.imported} chunks, skipped ${result.skipped}`
+        `${result.dry_run ? 'Dry run: ' : ''}Imported ${result.imported} chunks, skipped ${result.skipped}` +
+          (usage ? ` (embedded ${usage.inputs} via ${usage.backend}, ${usage.input_tokens} tokens)` : '')
       );
       if (!result.dry_run) {
         setImportData('');
@@ -83,6 +95,29 @@ export function ExportImportPanel({
     }
   };
 
+  const handleUpload = async (e: Event) => {
+    e.preventDefault();
+    setError(null);
+    setImportResult(null);
+
+    if (!uploadFile || !onUpload) {
+      setError('Choose a .db file to upload');
+      return;
+    }
+
+    try {
+      setUploading(true);
+      const result = await onUpload(uploadFile, uploadPath.trim() || uploadFile.name, uploadOverwrite);
+      setImportResult(`Uploaded ${result.layer.path} (${result.layer.chunk_count} chunks)`);
+      setUploadFile(null);
+      setUploadPath('');
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setUploading(false);
+    }
+  };
+
   return (
     <dialog class="modal modal-open">
       <div class="modal-box max-w-3xl">
@@ -112,12 +147,22 @@ export function ExportImportPanel({
           >
             Export
           </button>
-          <button
-            class={`tab ${activeTab === 'import' ? 'tab-active' : ''}`}
-            onClick={() => setActiveTab('import')}
-          >
-            Import
-          </button>
+          {onImport && (
+            <button
+              class={`tab ${activeTab === 'import' ? 'tab-active' : ''}`}
+              onClick={() => setActiveTab('import')}
+            >
+              Import
+            </button>
+          )}
+          {onUpload && (
+            <button
+              class={`tab ${activeTab === 'upload' ? 'tab-active' : ''}`}
+              onClick={() => setActiveTab('upload')}
+            >
+              Upload .db
+            </button>
+          )}
         </div>
 
         {activeTab === 'export' ? (
@@ -177,6 +222,62 @@ export function ExportImportPanel({
               {exporting ? <span class="loading loading-spinner"></span> : 'Export Layer'}
             </button>
           </div>
+        ) : activeTab === 'upload' ? (
+          <form onSubmit={handleUpload} class="space-y-4">
+            <div class="form-control">
+              <label class="label">
+                <span class="label-text">Layer file *</span>
+              </label>
+              <input
+                type="file"
+                accept=".db"
+                class="file-input file-input-bordered"
+                onChange={(e) => {
+                  const file = (e.target as HTMLInputElement).files?.[0] ?? null;
+                  setUploadFile(file);
+                  if (file && !uploadPath) setUploadPath(file.name);
+                }}
+                disabled={uploading}
+                required
+              />
+            </div>
+
+            <div class="form-control">
+              <label class="label">
+                <span class="label-text">Save as</span>
+              </label>
+              <input
+                type="text"
+                class="input input-bordered mono"
+                value={uploadPath}
+                onInput={(e) => setUploadPath((e.target as HTMLInputElement).value)}
+                placeholder="AGENTS.local.db"
+                disabled={uploading}
+              />
+            </div>
+
+            <label class="label cursor-pointer gap-2 justify-start">
+              <input
+                type="checkbox"
+                class="checkbox checkbox-sm"
+                checked={uploadOverwrite}
+                onChange={(e) => setUploadOverwrite((e.target as HTMLInputElement).checked)}
+                disabled={uploading}
+              />
+              <span class="label-text">Replace an existing layer with this name</span>
+            </label>
+
+            <div class="alert alert-info">
+              <span class="text-sm">
+                The file is checked before it is saved: it must be a valid layer with the same
+                embedding dimension and profile as the layers already in this root.
+              </span>
+            </div>
+
+            <button type="submit" class="btn btn-primary" disabled={uploading}>
+              {uploading ? <span class="loading loading-spinner"></span> : 'Upload Layer'}
+            </button>
+          </form>
         ) : (
           <form onSubmit={handleImport} class="space-y-4">
             <div class="grid grid-cols-1 md:grid-cols-2 gap-4">
@@ -1,21 +1,37 @@
 import { useEffect, useState } from 'preact/hooks';
 import { api } from '../api';
-import type { ListedLayer } from '../types';
+import type { ListedLayer, ListedRoot } from '../types';
 
 interface HeaderProps {
+  roots: ListedRoot[];
+  selectedRoot: string;
+  onRootChange: (name: string) => void;
   layers: ListedLayer[];
   selectedLayer: string;
   onLayerChange: (path: string) => void;
   onShowMetadata: () => void;
   onShowExportImport: () => void;
+  onShowDiff: () => void;
+  onShowProposals: () => void;
+  pendingProposals: number;
+  onShowReview: () => void;
+  readOnly?: boolean;
 }
 
 export function Header({
+  roots,
+  selectedRoot,
+  onRootChange,
   layers,
   selectedLayer,
   onLayerChange,
   onShowMetadata,
-  onShowExportImport
+  onShowExportImport,
+  onShowDiff,
+  onShowProposals,
+  pendingProposals,
+  onShowReview,
+  readOnly = false
 }: HeaderProps) {
   const [version, setVersion] = useState<string>('…');
   const [theme, setTheme] = useState<'light' | 'dark'>('light');
@@ -59,12 +75,31 @@ export function Header({
               <span class="badge badge-sm mono" title="Web UI version">
                 {version}
               </span>
+              {readOnly && (
+                <span class="badge badge-sm badge-warning ml-1" title="Changes are disabled on this server">
+                  read-only
+                </span>
+              )}
             </div>
           </div>
         </div>
       </div>
       <div class="flex-none gap-2">
         <div class="flex items-center gap-2">
+          {roots.length > 1 && (
+            <select
+              class="select select-bordered select-sm max-w-xs"
+              value={selectedRoot}
+              onChange={(e) => onRootChange((e.target as HTMLSelectElement).value)}
+              title="Select project root"
+            >
+              {roots.map((root) => (
+                <option key={root.name} value={root.name} title={root.path}>
+                  {root.name}
+                </option>
+              ))}
+            </select>
+          )}
           <select
             class="select select-bordered select-sm max-w-xs"
             value={selectedLayer}
@@ -119,6 +154,67 @@ export function Header({
               <line x1="12" y1="15" x2="12" y2="3" />
             </svg>
           </button>
+          <button
+            onClick={onShowDiff}
+            class="btn btn-ghost btn-sm btn-circle"
+            title="Compare layers"
+            aria-label="Compare layers"
+            disabled={layers.length < 2}
+          >
+            <svg
+              class="h-5 w-5"
+              viewBox="0 0 24 24"
+              fill="none"
+              stroke="currentColor"
+              stroke-width="2"
+              stroke-linecap="round"
+              stroke-linejoin="round"
+            >
+              <rect x="3" y="3" width="7" height="18" rx="1" />
+              <rect x="14" y="3" width="7" height="18" rx="1" />
+            </svg>
+          </button>
+          <button
+            onClick={onShowProposals}
+            class="btn btn-ghost btn-sm gap-1"
+            title="Review proposals"
+            aria-label="Review proposals"
+          >
+            <svg
+              class="h-5 w-5"
+              viewBox="0 0 24 24"
+              fill="none"
+              stroke="currentColor"
+              stroke-width="2"
+              stroke-linecap="round"
+              stroke-linejoin="round"
+            >
+              <path d="M9 11l3 3L22 4" />
+              <path d="M21 12v7a2 2 0 0 1-2 2H5a2 2 0 0 1-2-2V5a2 2 0 0 1 2-2h11" />
+            </svg>
+            {pendingProposals > 0 && (
+              <span class="badge badge-sm badge-warning">{pendingProposals}</span>
+            )}
+          </button>
+          <button
+            onClick={onShowReview}
+            class="btn btn-ghost btn-sm btn-circle"
+            title="Review stale chunks"
+            aria-label="Review stale chunks"
+          >
+            <svg
+              class="h-5 w-5"
+              viewBox="0 0 24 24"
+              fill="none"
+              stroke="currentColor"
+              stroke-width="2"
+              stroke-linecap="round"
+              stroke-linejoin="round"
+            >
+              <circle cx="12" cy="12" r="10" />
+              <polyline points="12 6 12 12 16 14" />
+            </svg>
+          </button>
           <a
             href="https://github.com/krazyjakee/AGENTS.db"
             target="_blank"
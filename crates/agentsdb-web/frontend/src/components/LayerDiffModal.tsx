@@ -0,0 +1,136 @@
+import { useState } from 'preact/hooks';
+import { api } from '../api';
+import type { ListedLayer, LayerDiff, ChunkDiff, DiffSide } from '../types';
+
+interface LayerDiffModalProps {
+  layers: ListedLayer[];
+  onClose: () => void;
+}
+
+function defaultLayer(layers: ListedLayer[], name: string, fallback: number): string {
+  return layers.find((l) => l.path === name)?.path ?? layers[fallback]?.path ?? '';
+}
+
+function Side({ side, tone }: { side: DiffSide | null; tone: string }) {
+  if (!side) {
+    return <div class="rounded-lg p-3 bg-base-200 opacity-50 text-sm italic">absent</div>;
+  }
+  return (
+    <div class={`rounded-lg p-3 ${tone}`}>
+      <div class="flex gap-2 mb-2 text-xs">
+        <span class="badge badge-sm">{side.kind}</span>
+        <span class="badge badge-sm badge-ghost">conf {side.confidence.toFixed(2)}</span>
+        <span class="mono opacity-60" title={side.content_sha256}>
+          {side.content_sha256.slice(0, 12)}
+        </span>
+      </div>
+      <pre class="whitespace-pre-wrap text-sm mono">{side.content}</pre>
+    </div>
+  );
+}
+
+function Section({ title, badge, items }: { title: string; badge: string; items: ChunkDiff[] }) {
+  if (items.length === 0) {
+    return null;
+  }
+  return (
+    <div class="mt-4">
+      <h4 class="font-semibold mb-2">
+        {title} <span class={`badge ${badge}`}>{items.length}</span>
+      </h4>
+      <div class="space-y-3">
+        {items.map((item) => (
+          <div key={item.id}>
+            <div class="mono text-xs opacity-70 mb-1">id={item.id}</div>
+            <div class="grid grid-cols-2 gap-2">
+              <Side side={item.from} tone="bg-success/10" />
+              <Side side={item.to} tone="bg-error/10" />
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
+
+export function LayerDiffModal({ layers, onClose }: LayerDiffModalProps) {
+  const [from, setFrom] = useState(defaultLayer(layers, 'AGENTS.delta.db', 0));
+  const [to, setTo] = useState(defaultLayer(layers, 'AGENTS.user.db', 1));
+  const [diff, setDiff] = useState<LayerDiff | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const compare = async () => {
+    setLoading(true);
+    setError(null);
+    try {
+      setDiff(await api.diffLayers(from, to));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const layerSelect = (value: string, onChange: (v: string) => void) => (
+    <select
+      class="select select-bordered select-sm"
+      value={value}
+      onChange={(e) => onChange((e.target as HTMLSelectElement).value)}
+    >
+      {layers.map((layer) => (
+        <option key={layer.path} value={layer.path}>
+          {layer.path}
+        </option>
+      ))}
+    </select>
+  );
+
+  return (
+    <div class="modal modal-open">
+      <div class="modal-box max-w-6xl">
+        <button
+          onClick={onClose}
+          class="btn btn-sm btn-circle btn-ghost absolute right-2 top-2"
+          aria-label="Close"
+        >
+          ✕
+        </button>
+
+        <h3 class="font-bold text-2xl mb-4">Compare Layers</h3>
+        <div class="flex items-center gap-2 flex-wrap">
+          {layerSelect(from, setFrom)}
+          <span>→</span>
+          {layerSelect(to, setTo)}
+          <button class="btn btn-primary btn-sm" onClick={compare} disabled={loading || !from || !to}>
+            {loading ? <span class="loading loading-spinner loading-sm"></span> : 'Compare'}
+          </button>
+        </div>
+        <div class="text-xs opacity-70 mt-2">
+          Chunks are matched by id and compared by content hash. Left is the source layer, right the target.
+        </div>
+
+        {error && (
+          <div class="alert alert-error mt-4">
+            <span>{error}</span>
+          </div>
+        )}
+
+        {diff && (
+          <div class="max-h-[60vh] overflow-y-auto">
+            <div class="flex gap-2 mt-4">
+              <span class="badge badge-success">{diff.added.length} added</span>
+              <span class="badge badge-warning">{diff.changed.length} changed</span>
+              <span class="badge badge-error">{diff.removed.length} only in target</span>
+              <span class="badge badge-ghost">{diff.unchanged} unchanged</span>
+            </div>
+            <Section title="Added" badge="badge-success" items={diff.added} />
+            <Section title="Changed" badge="badge-warning" items={diff.changed} />
+            <Section title="Only in target" badge="badge-error" items={diff.removed} />
+          </div>
+        )}
+      </div>
+      <div class="modal-backdrop" onClick={onClose}></div>
+    </div>
+  );
+}
@@ -1,13 +1,29 @@
+import { useState } from 'preact/hooks';
 import type { LayerMeta } from '../types';
 
 interface LayerMetadataModalProps {
   layerMeta: LayerMeta | null;
+  onBuildIndex?: (storeEmbeddingsF32: boolean) => Promise<void>;
   onClose: () => void;
 }
 
-export function LayerMetadataModal({ layerMeta, onClose }: LayerMetadataModalProps) {
+export function LayerMetadataModal({ layerMeta, onBuildIndex, onClose }: LayerMetadataModalProps) {
+  const [building, setBuilding] = useState(false);
+  const [storeEmbeddingsF32, setStoreEmbeddingsF32] = useState(false);
+
   if (!layerMeta) return null;
 
+  const index = layerMeta.index;
+  const buildIndex = async () => {
+    if (!onBuildIndex) return;
+    setBuilding(true);
+    try {
+      await onBuildIndex(storeEmbeddingsF32);
+    } finally {
+      setBuilding(false);
+    }
+  };
+
   return (
     <div class="modal modal-open">
       <div class="modal-box max-w-3xl">
@@ -57,6 +73,41 @@ export function LayerMetadataModal({ layerMeta, onClose }: LayerMetadataModalPro
             </div>
           </div>
 
+          {index && (
+            <div class="stat bg-base-200 rounded-lg p-4">
+              <div class="stat-title text-xs">Search Index</div>
+              <div class="flex gap-2 items-center mt-2">
+                <span class="mono text-sm">{index.path}</span>
+                {!index.exists ? (
+                  <span class="badge badge-ghost">missing</span>
+                ) : index.fresh ? (
+                  <span class="badge badge-success">fresh</span>
+                ) : (
+                  <span class="badge badge-warning">stale</span>
+                )}
+                {index.size_bytes !== null && (
+                  <span class="text-xs opacity-70">{Math.round(index.size_bytes / 1024)} KiB</span>
+                )}
+              </div>
+              {onBuildIndex && (
+                <div class="flex gap-3 items-center mt-3">
+                  <button onClick={buildIndex} class="btn btn-sm btn-primary" disabled={building}>
+                    {building ? 'Building...' : index.exists ? 'Rebuild index' : 'Build index'}
+                  </button>
+                  <label class="label cursor-pointer gap-1 p-0">
+                    <input
+                      type="checkbox"
+                      class="checkbox checkbox-sm"
+                      checked={storeEmbeddingsF32}
+                      onChange={(e) => setStoreEmbeddingsF32((e.target as HTMLInputElement).checked)}
+                    />
+                    <span class="label-text text-xs">Store decoded f32 embeddings</span>
+                  </label>
+                </div>
+              )}
+            </div>
+          )}
+
           <div class="bg-base-200 rounded-lg p-4">
             <div class="text-sm font-semibold mb-3">Chunk Kinds</div>
             <div class="flex flex-wrap gap-2">
@@ -4,7 +4,7 @@ import type { ChunkFull } from '../types';
 interface PromoteModalProps {
   chunk: ChunkFull | null;
   selectedLayer: string;
-  onPromote: (toPath: string, skipExisting: boolean) => Promise<void>;
+  onPromote: (toPath: string, skipExisting: boolean, viaPr: boolean) => Promise<void>;
   onClose: () => void;
 }
 
@@ -16,6 +16,7 @@ export function PromoteModal({
 }: PromoteModalProps) {
   const [toPath, setToPath] = useState('');
   const [skipExisting, setSkipExisting] = useState(true);
+  const [viaPr, setViaPr] = useState(true);
   const [submitting, setSubmitting] = useState(false);
   const [error, setError] = useState<string | null>(null);
 
@@ -31,6 +32,8 @@ export function PromoteModal({
 
   if (!chunk) return null;
 
+  const toBase = toPath.trim() === 'AGENTS.db';
+
   const handleSubmit = async (e: Event) => {
     e.preventDefault();
     setError(null);
@@ -47,7 +50,7 @@ export function PromoteModal({
 
     try {
       setSubmitting(true);
-      await onPromote(toPath, skipExisting);
+      await onPromote(toPath, skipExisting, toBase && viaPr);
       onClose();
     } catch (err) {
       setError(err instanceof Error ? err.message : String(err));
@@ -153,6 +156,26 @@ export function PromoteModal({
             </label>
           </div>
 
+          {toBase && (
+            <div class="form-control mt-4">
+              <label class="cursor-pointer label justify-start gap-2">
+                <input
+                  type="checkbox"
+                  class="checkbox"
+                  checked={viaPr}
+                  onChange={(e) => setViaPr((e.target as HTMLInputElement).checked)}
+                  disabled={submitting}
+                />
+                <span class="label-text">Open a pull request</span>
+              </label>
+              <label class="label">
+                <span class="label-text-alt text-base-content/70">
+                  Commits the rebuilt AGENTS.db to a new branch on GitHub or GitLab instead of writing AGENTS.db.new
+                </span>
+              </label>
+            </div>
+          )}
+
           <div class="divider"></div>
 
           <div class="alert alert-info">
@@ -120,6 +120,13 @@ export function ProposalDetailsModal({ proposal, onClose }: ProposalDetailsModal
             <div class="text-sm mono">{createdDate}</div>
           </div>
 
+          {proposal.proposed_by_identity && (
+            <div>
+              <div class="font-semibold text-sm mb-1">Proposed By</div>
+              <div class="text-sm mono">{proposal.proposed_by_identity}</div>
+            </div>
+          )}
+
           {decidedDate && (
             <>
               <div>
@@ -129,7 +136,10 @@ export function ProposalDetailsModal({ proposal, onClose }: ProposalDetailsModal
               {proposal.decided_by && (
                 <div>
                   <div class="font-semibold text-sm mb-1">Decided By</div>
-                  <div class="text-sm mono">{proposal.decided_by}</div>
+                  <div class="text-sm mono">
+                    {proposal.decided_by}
+                    {proposal.decided_by_identity && ` (${proposal.decided_by_identity})`}
+                  </div>
                 </div>
               )}
               {proposal.decision_reason && (
@@ -0,0 +1,236 @@
+import { useEffect, useState } from 'preact/hooks';
+import { api } from '../api';
+import type { ChunkFull, ProposalRow } from '../types';
+
+interface ProposalQueueProps {
+  proposals: ProposalRow[];
+  // Omitted on a read-only server, which leaves the queue browsable only.
+  onAccept?: (ids: number[], skipExisting: boolean, reason?: string) => Promise<void>;
+  onReject?: (ids: number[], reason?: string) => Promise<void>;
+  onRefresh: () => void;
+  onClose: () => void;
+}
+
+export function ProposalQueue({
+  proposals,
+  onAccept,
+  onReject,
+  onRefresh,
+  onClose,
+}: ProposalQueueProps) {
+  const pending = proposals.filter((p) => p.status === 'pending');
+  const [selectedId, setSelectedId] = useState<number | null>(null);
+  const [sourceChunk, setSourceChunk] = useState<ChunkFull | null>(null);
+  const [sourceError, setSourceError] = useState<string | null>(null);
+  const [reason, setReason] = useState('');
+  const [skipExisting, setSkipExisting] = useState(true);
+  const [submitting, setSubmitting] = useState(false);
+
+  const selected =
+    pending.find((p) => p.proposal_id === selectedId) ?? (pending.length > 0 ? pending[0] : null);
+
+  useEffect(() => {
+    setSourceChunk(null);
+    setSourceError(null);
+    setReason('');
+    if (!selected) return;
+    if (!selected.exists_in_source) {
+      setSourceError(`Chunk ${selected.context_id} is no longer in ${selected.from_path}.`);
+      return;
+    }
+    let cancelled = false;
+    api
+      .getChunk(selected.from_path, selected.context_id)
+      .then((chunk) => !cancelled && setSourceChunk(chunk))
+      .catch((err) => !cancelled && setSourceError(err instanceof Error ? err.message : String(err)));
+    return () => {
+      cancelled = true;
+    };
+  }, [selected?.proposal_id]);
+
+  const decide = async (action: 'accept' | 'reject') => {
+    if (!selected) return;
+    const next = pending[pending.indexOf(selected) + 1] ?? null;
+    try {
+      setSubmitting(true);
+      if (action === 'accept') {
+        await onAccept?.([selected.proposal_id], skipExisting, reason.trim() || undefined);
+      } else {
+        await onReject?.([selected.proposal_id], reason.trim() || undefined);
+      }
+      setSelectedId(next ? next.proposal_id : null);
+    } finally {
+      setSubmitting(false);
+    }
+  };
+
+  return (
+    <div class="card bg-base-200 shadow-xl">
+      <div class="card-body">
+        <div class="flex justify-between items-center mb-4">
+          <div>
+            <h2 class="card-title">Proposal Queue</h2>
+            <div class="text-sm text-base-content/70 mt-1">{pending.length} pending</div>
+          </div>
+          <div class="flex gap-2">
+            <button class="btn btn-sm btn-ghost" onClick={onRefresh} disabled={submitting}>
+              Refresh
+            </button>
+            <button class="btn btn-sm" onClick={onClose}>
+              Back to chunks
+            </button>
+          </div>
+        </div>
+
+        {pending.length === 0 ? (
+          <div class="text-sm text-base-content/70">No pending proposals.</div>
+        ) : (
+          <div class="grid grid-cols-1 lg:grid-cols-3 gap-4">
+            <ul class="menu bg-base-100 rounded-box p-2 lg:col-span-1 max-h-[70vh] overflow-y-auto flex-nowrap">
+              {pending.map((p) => (
+                <li key={p.proposal_id}>
+                  <a
+                    class={selected?.proposal_id === p.proposal_id ? 'active' : ''}
+                    onClick={() => setSelectedId(p.proposal_id)}
+                  >
+                    <div class="flex flex-col gap-1 min-w-0">
+                      <span class="font-semibold truncate">
+                        <span class="mono">#{p.proposal_id}</span> {p.title || '(no title)'}
+                      </span>
+                      <span class="mono text-xs opacity-70">
+                        {p.from_path} → {p.to_path}
+                      </span>
+                      {p.source_preview && (
+                        <span class="text-xs opacity-70 truncate">{p.source_preview}</span>
+                      )}
+                    </div>
+                  </a>
+                </li>
+              ))}
+            </ul>
+
+            {selected && (
+              <div class="lg:col-span-2 space-y-4">
+                <div class="flex flex-wrap items-center gap-2">
+                  <h3 class="font-bold text-lg">
+                    Proposal <span class="mono">#{selected.proposal_id}</span>
+                  </h3>
+                  <span class="badge badge-outline mono">{selected.from_path}</span>
+                  <span>→</span>
+                  <span class="badge badge-primary mono">{selected.to_path}</span>
+                </div>
+
+                {selected.title && <div class="text-sm">{selected.title}</div>}
+                {selected.approvers.length > 0 && (
+                  <div class="text-sm text-base-content/70">
+                    Approved by {selected.approvers.join(', ')}
+                  </div>
+                )}
+                {selected.why && (
+                  <div>
+                    <div class="font-semibold text-sm mb-1">Why</div>
+                    <div class="text-sm bg-base-300 p-3 rounded whitespace-pre-wrap">{selected.why}</div>
+                  </div>
+                )}
+                {selected.what && (
+                  <div>
+                    <div class="font-semibold text-sm mb-1">What</div>
+                    <div class="text-sm bg-base-300 p-3 rounded whitespace-pre-wrap">{selected.what}</div>
+                  </div>
+                )}
+                {selected.where && (
+                  <div>
+                    <div class="font-semibold text-sm mb-1">Where</div>
+                    <div class="text-sm mono bg-base-300 p-2 rounded">{selected.where}</div>
+                  </div>
+                )}
+
+                <div>
+                  <div class="font-semibold text-sm mb-1">
+                    Proposed chunk <span class="mono">{selected.context_id}</span>
+                  </div>
+                  {sourceError ? (
+                    <div class="alert alert-warning text-sm">{sourceError}</div>
+                  ) : !sourceChunk ? (
+                    <span class="loading loading-spinner loading-sm"></span>
+                  ) : (
+                    <div class="space-y-2">
+                      <div class="flex flex-wrap gap-2 text-xs">
+                        <span class="badge badge-sm">{sourceChunk.kind}</span>
+                        <span class="badge badge-sm badge-ghost">author: {sourceChunk.author}</span>
+                        <span class="badge badge-sm badge-ghost">
+                          confidence: {sourceChunk.confidence.toFixed(2)}
+                        </span>
+                        <span class="badge badge-sm badge-ghost">
+                          {new Date(sourceChunk.created_at_unix_ms).toLocaleString()}
+                        </span>
+                      </div>
+                      <pre class="text-sm bg-base-300 p-3 rounded whitespace-pre-wrap max-h-80 overflow-y-auto">
+                        {sourceChunk.content}
+                      </pre>
+                      <div>
+                        <div class="font-semibold text-xs mb-1">Provenance</div>
+                        {sourceChunk.sources.length === 0 ? (
+                          <div class="text-xs text-base-content/70">No sources recorded.</div>
+                        ) : (
+                          <ul class="text-xs mono list-disc list-inside">
+                            {sourceChunk.sources.map((s) => (
+                              <li key={s}>{s}</li>
+                            ))}
+                          </ul>
+                        )}
+                      </div>
+                    </div>
+                  )}
+                  {selected.exists_in_target && (
+                    <div class="alert alert-info text-sm mt-2">
+                      A chunk with this id already exists in {selected.to_path}.
+                    </div>
+                  )}
+                </div>
+
+                {onAccept && onReject && (
+                  <div class="space-y-2">
+                    <textarea
+                      class="textarea textarea-bordered w-full text-sm"
+                      placeholder="Reason (optional, stored with the decision)"
+                      value={reason}
+                      onInput={(e) => setReason((e.target as HTMLTextAreaElement).value)}
+                      disabled={submitting}
+                    />
+                    <div class="flex flex-wrap items-center gap-2">
+                      <button
+                        class="btn btn-sm btn-success"
+                        onClick={() => decide('accept')}
+                        disabled={submitting}
+                      >
+                        {submitting ? <span class="loading loading-spinner loading-xs"></span> : 'Accept'}
+                      </button>
+                      <button
+                        class="btn btn-sm btn-error"
+                        onClick={() => decide('reject')}
+                        disabled={submitting}
+                      >
+                        Reject
+                      </button>
+                      <label class="label cursor-pointer gap-2">
+                        <span class="label-text text-xs">Skip existing</span>
+                        <input
+                          type="checkbox"
+                          class="checkbox checkbox-xs"
+                          checked={skipExisting}
+                          onChange={(e) => setSkipExisting((e.target as HTMLInputElement).checked)}
+                          disabled={submitting}
+                        />
+                      </label>
+                    </div>
+                  </div>
+                )}
+              </div>
+            )}
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
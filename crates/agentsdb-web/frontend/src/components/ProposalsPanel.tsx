@@ -4,8 +4,9 @@ import type { ProposalRow } from '../types';
 interface ProposalsPanelProps {
   proposals: ProposalRow[];
   loading?: boolean;
-  onAccept: (ids: number[], skipExisting: boolean) => Promise<void>;
-  onReject: (ids: number[], reason?: string) => Promise<void>;
+  // Omitted on a read-only server, which hides the review controls.
+  onAccept?: (ids: number[], skipExisting: boolean) => Promise<void>;
+  onReject?: (ids: number[], reason?: string) => Promise<void>;
   onViewDetails: (proposal: ProposalRow) => void;
   onRefresh: () => void;
 }
@@ -48,7 +49,7 @@ export function ProposalsPanel({
     if (selectedIds.size === 0) return;
     try {
       setSubmitting(true);
-      await onAccept(Array.from(selectedIds), skipExisting);
+      await onAccept?.(Array.from(selectedIds), skipExisting);
       setSelectedIds(new Set());
     } finally {
       setSubmitting(false);
@@ -60,7 +61,7 @@ export function ProposalsPanel({
     const reason = prompt('Reason for rejection (optional):');
     try {
       setSubmitting(true);
-      await onReject(Array.from(selectedIds), reason || undefined);
+      await onReject?.(Array.from(selectedIds), reason || undefined);
       setSelectedIds(new Set());
     } finally {
       setSubmitting(false);
@@ -115,36 +116,40 @@ export function ProposalsPanel({
           <button class="btn btn-sm" onClick={clearSelection} disabled={submitting}>
             Clear
           </button>
-          <div class="divider divider-horizontal"></div>
-          <button
-            class="btn btn-sm btn-success"
-            onClick={handleAccept}
-            disabled={selectedIds.size === 0 || submitting}
-          >
-            {submitting ? (
-              <span class="loading loading-spinner loading-xs"></span>
-            ) : (
-              `Accept (${selectedIds.size})`
-            )}
-          </button>
-          <button
-            class="btn btn-sm btn-error"
-            onClick={handleReject}
-            disabled={selectedIds.size === 0 || submitting}
-          >
-            Reject ({selectedIds.size})
-          </button>
-          <div class="divider divider-horizontal"></div>
-          <label class="label cursor-pointer gap-2">
-            <span class="label-text text-xs">Skip existing</span>
-            <input
-              type="checkbox"
-              class="checkbox checkbox-xs"
-              checked={skipExisting}
-              onChange={(e) => setSkipExisting((e.target as HTMLInputElement).checked)}
-              disabled={submitting}
-            />
-          </label>
+          {onAccept && onReject && (
+            <>
+              <div class="divider divider-horizontal"></div>
+              <button
+                class="btn btn-sm btn-success"
+                onClick={handleAccept}
+                disabled={selectedIds.size === 0 || submitting}
+              >
+                {submitting ? (
+                  <span class="loading loading-spinner loading-xs"></span>
+                ) : (
+                  `Accept (${selectedIds.size})`
+                )}
+              </button>
+              <button
+                class="btn btn-sm btn-error"
+                onClick={handleReject}
+                disabled={selectedIds.size === 0 || submitting}
+              >
+                Reject ({selectedIds.size})
+              </button>
+              <div class="divider divider-horizontal"></div>
+              <label class="label cursor-pointer gap-2">
+                <span class="label-text text-xs">Skip existing</span>
+                <input
+                  type="checkbox"
+                  class="checkbox checkbox-xs"
+                  checked={skipExisting}
+                  onChange={(e) => setSkipExisting((e.target as HTMLInputElement).checked)}
+                  disabled={submitting}
+                />
+              </label>
+            </>
+          )}
           <label class="label cursor-pointer gap-2">
             <span class="label-text text-xs">Show all</span>
             <input
@@ -0,0 +1,217 @@
+import { useEffect, useState } from 'preact/hooks';
+import { api } from '../api';
+import type { StaleChunk } from '../types';
+
+interface ReviewQueueProps {
+  // Hides the decay, confirm and remove controls on a read-only server.
+  readOnly: boolean;
+  // Called after a write so the chunk list can reload.
+  onChanged: () => void;
+  onClose: () => void;
+}
+
+export function ReviewQueue({ readOnly, onChanged, onClose }: ReviewQueueProps) {
+  const [threshold, setThreshold] = useState(0.5);
+  const [chunks, setChunks] = useState<StaleChunk[]>([]);
+  const [selectedKey, setSelectedKey] = useState<string | null>(null);
+  const [confidence, setConfidence] = useState(1);
+  const [maxAgeDays, setMaxAgeDays] = useState(90);
+  const [factor, setFactor] = useState(0.8);
+  const [busy, setBusy] = useState(false);
+  const [message, setMessage] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const keyOf = (c: StaleChunk) => `${c.layer}:${c.id}`;
+  const selected =
+    chunks.find((c) => keyOf(c) === selectedKey) ?? (chunks.length > 0 ? chunks[0] : null);
+
+  const refresh = async () => {
+    try {
+      const res = await api.getStaleChunks(threshold);
+      setChunks(res.chunks);
+      setError(null);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  useEffect(() => {
+    refresh();
+  }, [threshold]);
+
+  const run = async (action: () => Promise<string>) => {
+    try {
+      setBusy(true);
+      setMessage(await action());
+      setError(null);
+      await refresh();
+      onChanged();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const decay = () =>
+    run(async () => {
+      const report = await api.decayConfidence(maxAgeDays, factor);
+      return `Decayed ${report.decayed.length} of ${report.scanned} chunks (${report.skipped.length} read-only skipped).`;
+    });
+
+  const decide = (action: 'confirm' | 'remove') => {
+    if (!selected) return;
+    const next = chunks[chunks.indexOf(selected) + 1] ?? null;
+    setSelectedKey(next ? keyOf(next) : null);
+    return run(async () => {
+      if (action === 'confirm') {
+        const res = await api.confirmChunk(selected.layer, selected.id, confidence);
+        return `Confirmed ${selected.layer}:${selected.id} as ${res.id}.`;
+      }
+      await api.removeChunk(selected.layer, selected.id);
+      return `Removed ${selected.layer}:${selected.id}.`;
+    });
+  };
+
+  return (
+    <div class="card bg-base-200 shadow-xl">
+      <div class="card-body">
+        <div class="flex justify-between items-center mb-4">
+          <div>
+            <h2 class="card-title">Review Queue</h2>
+            <div class="text-sm text-base-content/70 mt-1">
+              {chunks.length} chunks below confidence{' '}
+              <input
+                type="number"
+                class="input input-bordered input-xs w-20"
+                min={0}
+                max={1}
+                step={0.05}
+                value={threshold}
+                onChange={(e) => setThreshold(Number((e.target as HTMLInputElement).value))}
+              />
+            </div>
+          </div>
+          <div class="flex gap-2">
+            <button class="btn btn-sm btn-ghost" onClick={refresh} disabled={busy}>
+              Refresh
+            </button>
+            <button class="btn btn-sm" onClick={onClose}>
+              Back to chunks
+            </button>
+          </div>
+        </div>
+
+        {!readOnly && (
+          <div class="flex flex-wrap items-center gap-2 mb-4 text-sm">
+            <span>Decay chunks older than</span>
+            <input
+              type="number"
+              class="input input-bordered input-xs w-20"
+              min={1}
+              value={maxAgeDays}
+              onChange={(e) => setMaxAgeDays(Number((e.target as HTMLInputElement).value))}
+              disabled={busy}
+            />
+            <span>days by ×</span>
+            <input
+              type="number"
+              class="input input-bordered input-xs w-20"
+              min={0}
+              max={1}
+              step={0.05}
+              value={factor}
+              onChange={(e) => setFactor(Number((e.target as HTMLInputElement).value))}
+              disabled={busy}
+            />
+            <button class="btn btn-xs btn-warning" onClick={decay} disabled={busy}>
+              Decay
+            </button>
+          </div>
+        )}
+
+        {message && <div class="alert alert-success mb-4 text-sm">{message}</div>}
+        {error && <div class="alert alert-error mb-4 text-sm">{error}</div>}
+
+        {chunks.length === 0 ? (
+          <div class="text-sm text-base-content/70">Nothing needs review.</div>
+        ) : (
+          <div class="grid grid-cols-1 lg:grid-cols-3 gap-4">
+            <ul class="menu bg-base-100 rounded-box p-2 lg:col-span-1 max-h-[70vh] overflow-y-auto flex-nowrap">
+              {chunks.map((c) => (
+                <li key={keyOf(c)}>
+                  <a
+                    class={selected && keyOf(selected) === keyOf(c) ? 'active' : ''}
+                    onClick={() => setSelectedKey(keyOf(c))}
+                  >
+                    <div class="flex flex-col gap-1 min-w-0">
+                      <span class="font-semibold truncate">
+                        <span class="mono">#{c.id}</span> {c.kind}{' '}
+                        <span class="badge badge-sm badge-warning">{c.confidence.toFixed(2)}</span>
+                      </span>
+                      <span class="mono text-xs opacity-70">{c.layer}</span>
+                      <span class="text-xs opacity-70 truncate">{c.content_preview}</span>
+                    </div>
+                  </a>
+                </li>
+              ))}
+            </ul>
+
+            {selected && (
+              <div class="lg:col-span-2 space-y-4">
+                <div class="flex flex-wrap items-center gap-2">
+                  <h3 class="font-bold text-lg">
+                    Chunk <span class="mono">#{selected.id}</span>
+                  </h3>
+                  <span class="badge badge-outline mono">{selected.layer}</span>
+                  <span class="badge badge-outline">{selected.kind}</span>
+                  <span class="text-sm opacity-70">
+                    last revised {new Date(selected.created_at_unix_ms).toLocaleString()}
+                  </span>
+                </div>
+                <pre class="bg-base-100 rounded-box p-3 whitespace-pre-wrap text-sm">
+                  {selected.content_preview}
+                </pre>
+                {readOnly ? null : selected.writable ? (
+                  <div class="flex flex-wrap items-center gap-2">
+                    <button
+                      class="btn btn-sm btn-success"
+                      onClick={() => decide('confirm')}
+                      disabled={busy}
+                    >
+                      {busy ? <span class="loading loading-spinner loading-xs"></span> : 'Still valid'}
+                    </button>
+                    <label class="label gap-2">
+                      <span class="label-text text-xs">at confidence</span>
+                      <input
+                        type="number"
+                        class="input input-bordered input-xs w-20"
+                        min={0}
+                        max={1}
+                        step={0.05}
+                        value={confidence}
+                        onChange={(e) => setConfidence(Number((e.target as HTMLInputElement).value))}
+                        disabled={busy}
+                      />
+                    </label>
+                    <button
+                      class="btn btn-sm btn-error"
+                      onClick={() => decide('remove')}
+                      disabled={busy}
+                    >
+                      Remove
+                    </button>
+                  </div>
+                ) : (
+                  <div class="text-sm text-base-content/70">
+                    {selected.layer} is read-only; promote a revision to change this chunk.
+                  </div>
+                )}
+              </div>
+            )}
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -1,3 +1,8 @@
+export interface ListedRoot {
+  name: string;
+  path: string;
+}
+
 export interface ListedLayer {
   path: string;
   chunk_count: number;
@@ -17,6 +22,15 @@ export interface LayerMeta {
   confidence_min: number;
   confidence_max: number;
   confidence_avg: number;
+  index?: IndexStatus;
+}
+
+/** Status of a layer's `.agix` sidecar search index. */
+export interface IndexStatus {
+  path: string;
+  exists: boolean;
+  fresh: boolean;
+  size_bytes: number | null;
 }
 
 export interface ChunkSummary {
@@ -26,11 +40,30 @@ export interface ChunkSummary {
   confidence: number;
   created_at_unix_ms: number;
   source_count: number;
+  namespace?: string;
+  language?: string;
+  identity?: string;
   removed: boolean;
   content_preview: string;
   layer?: string; // Optional: set when chunk comes from search results across layers
 }
 
+/** Server-side filter and sort options for `/api/layer/chunks`. */
+export interface ChunkQuery {
+  q: string;
+  regex: boolean;
+  author: string;
+  namespace: string;
+  min_confidence: string;
+  sort: 'id' | 'created_at' | 'confidence';
+  order: '' | 'asc' | 'desc';
+}
+
+export interface LayerChangedEvent {
+  path: string;
+  exists: boolean;
+}
+
 export interface ChunkFull {
   id: number;
   kind: string;
@@ -42,6 +75,30 @@ export interface ChunkFull {
   removed: boolean;
 }
 
+export type HistoryRelation =
+  | 'version'
+  | 'earlier_revision'
+  | 'revision'
+  | 'tombstone'
+  | 'proposal_event';
+
+export interface HistoryEntry {
+  relation: HistoryRelation;
+  layer: string;
+  chunk: ChunkFull;
+  supersedes?: number;
+  retracts?: number;
+  action?: string;
+  proposal_id?: number;
+}
+
+export interface ChunkHistory {
+  path: string;
+  id: number;
+  current_id: number | null;
+  entries: HistoryEntry[];
+}
+
 export interface ChunksResponse {
   total: number;
   offset: number;
@@ -51,6 +108,7 @@ export interface ChunksResponse {
 
 export interface VersionResponse {
   version: string;
+  read_only: boolean;
 }
 
 export type ProposalStatus = 'pending' | 'accepted' | 'rejected';
@@ -70,10 +128,44 @@ export interface ProposalRow {
   exists_in_user: boolean;
   exists_in_source: boolean;
   exists_in_target: boolean;
+  source_preview: string | null;
+  proposed_by_identity?: string;
   decided_at_unix_ms: number | null;
   decided_by: string | null;
+  decided_by_identity?: string;
   decision_reason: string | null;
   decision_outcome: string | null;
+  // Distinct approvers so far; AGENTS.policy.json may require several before an accept lands.
+  approvers: string[];
+}
+
+export interface StaleChunk {
+  layer: string;
+  id: number;
+  kind: string;
+  confidence: number;
+  created_at_unix_ms: number;
+  content_preview: string;
+  writable: boolean;
+}
+
+export interface StaleQueueResponse {
+  threshold: number;
+  chunks: StaleChunk[];
+}
+
+export interface DecayedChunk {
+  layer: string;
+  id: number;
+  revision: number;
+  from: number;
+  to: number;
+}
+
+export interface DecayReport {
+  scanned: number;
+  decayed: DecayedChunk[];
+  skipped: [string, number, string][];
 }
 
 export interface PromoteResponse {
@@ -81,6 +173,21 @@ export interface PromoteResponse {
   promoted: number[];
   skipped: number[];
   out_path?: string;
+  // Proposals approved but still short of the policy's required approvals.
+  awaiting_approval?: number[];
+}
+
+export interface PullRequestPromoteResponse {
+  ok: boolean;
+  promoted: number[];
+  skipped: number[];
+  // Absent when every chunk was already in AGENTS.db.
+  pull_request?: {
+    provider: string;
+    url: string;
+    branch: string;
+    base: string;
+  };
 }
 
 export interface AddChunkRequest {
@@ -94,6 +201,57 @@ export interface AddChunkRequest {
   source_chunks?: number[];
 }
 
+export interface UpdateChunkRequest {
+  path: string; // layer holding the chunk being replaced
+  id: number;
+  scope: string;
+  content: string;
+  kind?: string;
+  confidence?: number;
+  sources?: string[];
+  reason?: string;
+}
+
+export interface UpdateChunkResponse {
+  ok: boolean;
+  path: string;
+  id: number;
+  superseded_id: number;
+  tombstone_id: number;
+}
+
+export interface DiffSide {
+  kind: string;
+  content: string;
+  content_sha256: string;
+  confidence: number;
+  author: string;
+  created_at_unix_ms: number;
+}
+
+export interface ChunkDiff {
+  id: number;
+  from: DiffSide | null;
+  to: DiffSide | null;
+}
+
+export interface LayerDiff {
+  added: ChunkDiff[];
+  removed: ChunkDiff[];
+  changed: ChunkDiff[];
+  unchanged: number;
+}
+
+export interface BatchRequest {
+  action: 'accept' | 'reject' | 'promote' | 'retract';
+  ids: number[];
+  path?: string;
+  to_path?: string;
+  scope?: string;
+  reason?: string;
+  skip_existing?: boolean;
+}
+
 export interface ProposeRequest {
   context_id: number;
   from_path?: string;
@@ -114,6 +272,18 @@ export interface ImportRequest {
   preserve_ids?: boolean;
   allow_base?: boolean;
   dim?: number;
+  price_per_million_tokens?: number;
+}
+
+export interface EmbeddingUsage {
+  backend: string;
+  model?: string;
+  requests: number;
+  inputs: number;
+  cached_inputs: number;
+  input_tokens: number;
+  price_per_million_tokens?: number;
+  estimated_cost?: number;
 }
 
 export interface ImportResponse {
@@ -122,6 +292,12 @@ export interface ImportResponse {
   imported: number;
   skipped: number;
   dry_run: boolean;
+  embedding_usage?: EmbeddingUsage | null;
+}
+
+export interface UploadLayerResponse {
+  ok: boolean;
+  layer: ListedLayer;
 }
 
 export interface SearchRequest {
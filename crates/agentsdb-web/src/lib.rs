@@ -1,18 +1,41 @@
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
+use std::net::{SocketAddr, TcpListener, TcpStream};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 use agentsdb_format::LayerFile;
 use include_dir::{include_dir, Dir};
 
+pub use agentsdb_ops::startup_check::{CheckReport, CheckStep};
+
 const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+/// Body limit for `POST /api/layer/upload`, which carries a whole layer file.
+const MAX_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
 const PROPOSAL_EVENT_KIND: &str = "meta.proposal_event";
 const PROPOSAL_EVENT_LAYER: &str = "AGENTS.delta.db";
+/// Approver recorded for proposal accepts that do not name one.
+const DEFAULT_APPROVER: &str = "web";
+/// How often `/api/events` checks the layer files under root for changes.
+const EVENTS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Idle polls between `/api/events` keep-alive comments.
+const EVENTS_KEEPALIVE_POLLS: u32 = 15;
+/// Requests served on one keep-alive connection before it is closed.
+const MAX_KEEP_ALIVE_REQUESTS: u32 = 100;
+/// JSON bodies smaller than this are sent uncompressed.
+const COMPRESS_MIN_BYTES: usize = 1024;
+/// How long a connection may sit idle between requests.
+const KEEP_ALIVE_IDLE: Duration = Duration::from_secs(10);
+/// How often idle loops (accept, keep-alive waits) check for shutdown.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Confidence below which `/api/review/stale` queues a chunk when no threshold is given.
+const DEFAULT_REVIEW_THRESHOLD: f32 = 0.5;
 
 const LOGO_PNG: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/logo.png"));
 
@@ -22,43 +45,403 @@ const LOGO_PNG: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/ass
 // ensuring the web UI is always available without needing the source files.
 static FRONTEND_DIST: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/dist");
 
-pub fn serve(root: &str, bind: &str) -> anyhow::Result<()> {
-    let root = std::fs::canonicalize(root).with_context(|| format!("canonicalize root {root}"))?;
+/// Access control for the `/api` routes.
+#[derive(Debug, Clone, Default)]
+pub struct WebAuth {
+    /// Token required as `Authorization: Bearer <token>`. `None` leaves the API open.
+    pub token: Option<String>,
+    /// Let GET requests through without a token, so only changes need one.
+    pub anonymous_read: bool,
+    /// Reject every request that changes data, for everyone.
+    pub read_only: bool,
+    /// Team members, each with their own token. Changes made with a member's token are
+    /// attributed to them (see [`agentsdb_ops::identity::scoped`]).
+    pub users: Vec<WebUser>,
+    /// With `users`, let members accept or reject their own proposals.
+    pub allow_self_review: bool,
+}
+
+/// A team member of a server started with [`WebAuth::users`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebUser {
+    pub name: String,
+    pub token: String,
+}
+
+/// Reads team members from a JSON file holding an array of `{"name", "token"}` objects.
+pub fn read_users_file(path: &Path) -> anyhow::Result<Vec<WebUser>> {
+    let bytes = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    let users: Vec<WebUser> =
+        serde_json::from_slice(&bytes).with_context(|| format!("parse {}", path.display()))?;
+    let mut names = HashSet::new();
+    for user in &users {
+        if user.name.trim().is_empty() || user.token.len() < 16 {
+            anyhow::bail!(
+                "{}: every user needs a name and a token of at least 16 characters",
+                path.display()
+            );
+        }
+        if !names.insert(user.name.as_str()) {
+            anyhow::bail!("{}: user {:?} is listed twice", path.display(), user.name);
+        }
+    }
+    Ok(users)
+}
+
+impl WebAuth {
+    fn allows(&self, req: &Request) -> bool {
+        if self.token.is_none() && self.users.is_empty() {
+            return true;
+        }
+        let guarded = req.path.starts_with("/api/") || req.path == "/metrics";
+        if !guarded || (self.anonymous_read && req.method == "GET") {
+            return true;
+        }
+        let Some(given) = bearer_token(req) else {
+            return false;
+        };
+        self.token
+            .as_deref()
+            .is_some_and(|token| constant_time_eq(given.as_bytes(), token.as_bytes()))
+            || self.user(req).is_some()
+    }
+
+    /// The team member whose token `req` carries.
+    fn user(&self, req: &Request) -> Option<&str> {
+        let given = bearer_token(req)?;
+        self.users
+            .iter()
+            .find(|u| constant_time_eq(given.as_bytes(), u.token.as_bytes()))
+            .map(|u| u.name.as_str())
+    }
+
+    /// Whether proposals must be decided by someone other than their proposer.
+    fn separates_review(&self) -> bool {
+        !self.users.is_empty() && !self.allow_self_review
+    }
+
+    /// Whether `req` is refused because the server is read-only. Only searches may POST.
+    fn forbids(&self, req: &Request) -> bool {
+        self.read_only && req.method != "GET" && req.path != "/api/search"
+    }
+}
+
+fn bearer_token(req: &Request) -> Option<&str> {
+    req.authorization
+        .as_deref()
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Runs the server on the current thread. Use [`start`] to be able to shut it down.
+pub fn serve(root: &str, bind: &str, auth: WebAuth) -> anyhow::Result<()> {
+    start(root, bind, auth)?.wait()
+}
+
+/// Binds and starts the server on a background thread.
+pub fn start(root: &str, bind: &str, auth: WebAuth) -> anyhow::Result<ServerHandle> {
+    start_roots(&[root], bind, auth)
+}
+
+/// Enforces the retention policy of the local layer in `root` when `retention.on_startup` is
+/// set. Failures are logged, not fatal.
+fn apply_retention_on_startup(root: &Path) {
+    let local = agentsdb_embeddings::config::standard_layer_paths_for_dir(root).local;
+    let applied = agentsdb_ops::retention::apply_on_startup(
+        &local,
+        "agentsdb-web",
+        env!("CARGO_PKG_VERSION"),
+    );
+    match applied {
+        Ok(Some(outcome)) => tracing::info!(
+            retracted = outcome.retracted.len(),
+            collected = outcome.collected.len(),
+            kept = outcome.kept,
+            "applied the retention policy to {}",
+            local.display()
+        ),
+        Ok(None) => {}
+        Err(err) => tracing::warn!(
+            "retention policy not applied to {}: {err:#}",
+            local.display()
+        ),
+    }
+}
+
+/// Binds and starts a server for several project roots on a background thread.
+///
+/// Each entry is a directory, optionally prefixed with `NAME=`; without a name the directory's
+/// base name is used. Every root's API is served under `/api/roots/<name>/`, and the plain
+/// `/api/` routes address the first root.
+// `auth` is taken by value like in `start`; every root gets its own copy.
+#[allow(clippy::needless_pass_by_value)]
+pub fn start_roots<S: AsRef<str>>(
+    roots: &[S],
+    bind: &str,
+    auth: WebAuth,
+) -> anyhow::Result<ServerHandle> {
+    let stop = Arc::new(AtomicBool::new(false));
+    agentsdb_ops::hooks::register(Arc::new(agentsdb_ops::hooks::PolicyHook));
+    let mut entries = Vec::with_capacity(roots.len());
+    for spec in roots {
+        let (name, root) = parse_root_spec(spec.as_ref())?;
+        let mut state = ServerState::new(root);
+        state.auth = auth.clone();
+        state.stop = Arc::clone(&stop);
+        entries.push((name, state));
+    }
+    // Web writes are the operator's; their identity comes from the first root, and so do the
+    // query cache settings.
+    if let Some((_, first)) = entries.first() {
+        agentsdb_ops::identity::install_for_dir(&first.root).context("resolve identity")?;
+        agentsdb_ops::query_cache::install_for_dir(&first.root)
+            .context("resolve query cache settings")?;
+    }
+    if !auth.read_only {
+        for (_, state) in &entries {
+            apply_retention_on_startup(&state.root);
+        }
+    }
+    let mut indexes = Vec::new();
+    for (_, state) in &entries {
+        let paths = agentsdb_embeddings::config::standard_layer_paths_for_dir(&state.root);
+        let layers = vec![paths.base, paths.user, paths.delta, paths.local];
+        indexes.extend(
+            agentsdb_ops::index_maintenance::start_for_layers(&state.root, layers)
+                .context("start index maintenance")?,
+        );
+    }
+    let roots = Roots::new(entries)?;
     let listener = TcpListener::bind(bind).with_context(|| format!("bind {bind}"))?;
-    println!("Web: http://{bind}/ (root: {})", root.display());
+    let local_addr = listener.local_addr().context("read bound address")?;
+    // Polled so the accept loop notices shutdown without a wake-up connection.
+    listener
+        .set_nonblocking(true)
+        .context("set listener non-blocking")?;
+    let listed = roots.describe();
+    match auth.token.as_deref() {
+        // The UI picks the token up from the URL fragment, which is never sent to the server.
+        Some(token) => println!("Web: http://{bind}/#token={token} ({listed})"),
+        None if !auth.users.is_empty() => println!(
+            "Web: http://{bind}/#token=<your token> ({listed}; {} users)",
+            auth.users.len()
+        ),
+        None => println!("Web: http://{bind}/ ({listed})"),
+    }
+
+    let roots = Arc::new(roots);
+    let thread = std::thread::spawn(move || accept_loop(&listener, &roots));
+    Ok(ServerHandle {
+        local_addr,
+        stop,
+        thread,
+        _indexes: indexes,
+    })
+}
 
-    let state = Arc::new(Mutex::new(ServerState::new(root)));
+/// A running server started with [`start`].
+pub struct ServerHandle {
+    local_addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: std::thread::JoinHandle<anyhow::Result<()>>,
+    /// Index maintenance for the roots that enable it; stopped when the handle is dropped.
+    _indexes: Vec<agentsdb_ops::index_maintenance::IndexMaintainer>,
+}
+
+impl ServerHandle {
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// A cloneable trigger that can stop the server from another thread.
+    pub fn shutdown_trigger(&self) -> ShutdownTrigger {
+        ShutdownTrigger(Arc::clone(&self.stop))
+    }
+
+    /// Stops accepting connections, waits for in-flight requests, and flushes state.
+    pub fn shutdown(self) -> anyhow::Result<()> {
+        self.stop.store(true, Ordering::SeqCst);
+        self.wait()
+    }
+
+    /// Blocks until the server has been shut down and drained.
+    pub fn wait(self) -> anyhow::Result<()> {
+        self.thread
+            .join()
+            .map_err(|_| anyhow::anyhow!("web server thread panicked"))?
+    }
+}
+
+/// Requests shutdown of a running server.
+#[derive(Debug, Clone)]
+pub struct ShutdownTrigger(Arc<AtomicBool>);
 
-    for stream in listener.incoming() {
-        let state = Arc::clone(&state);
-        let mut stream = match stream {
-            Ok(s) => s,
-            Err(err) => {
-                eprintln!("accept failed: {err}");
-                continue;
+impl ShutdownTrigger {
+    pub fn shutdown(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Accepts connections until shutdown, then waits for open connections to finish their
+/// current request and saves each root's decay state.
+fn accept_loop(listener: &TcpListener, roots: &Arc<Roots>) -> anyhow::Result<()> {
+    let mut connections: Vec<std::thread::JoinHandle<()>> = Vec::new();
+    while !roots.stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                connections.retain(|c| !c.is_finished());
+                let roots = Arc::clone(roots);
+                connections.push(std::thread::spawn(move || serve_connection(stream, &roots)));
             }
-        };
-        std::thread::spawn(move || {
-            let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
-            let _ = stream.set_write_timeout(Some(Duration::from_secs(10)));
-            if let Err(err) = handle_conn(&mut stream, &state) {
-                let _ = write_response(
-                    &mut stream,
-                    500,
-                    "text/plain; charset=utf-8",
-                    format!("internal error: {err}\n").as_bytes(),
-                );
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(SHUTDOWN_POLL_INTERVAL);
             }
-        });
+            Err(err) => tracing::warn!("accept failed: {err}"),
+        }
     }
 
+    for connection in connections {
+        let _ = connection.join();
+    }
+    for (_, state) in &roots.entries {
+        let st = state
+            .lock()
+            .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+        st.decay.save(&st.root).context("save decay state")?;
+    }
     Ok(())
 }
 
+/// Serves requests on one connection until the client closes it, asks for `Connection: close`,
+/// goes idle, an error response is sent, or the server shuts down.
+fn serve_connection(stream: TcpStream, roots: &Roots) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(10)));
+    let mut conn = HttpConn::new(stream);
+    while wait_for_request(&conn, &roots.stop) {
+        let _span = tracing::debug_span!("http_request", request = conn.requests + 1).entered();
+        let started = Instant::now();
+        conn.route = None;
+        if let Err(err) = handle_conn(&mut conn, roots) {
+            tracing::error!("request failed: {err:#}");
+            conn.keep_alive = false;
+            let _ = write_error(&mut conn, &err);
+        }
+        conn.record_request(started.elapsed());
+        if !conn.keep_alive {
+            break;
+        }
+    }
+}
+
+/// Waits until the client sends data. Returns false when it closes the connection, stays idle
+/// for `KEEP_ALIVE_IDLE`, or the server is shutting down.
+fn wait_for_request(conn: &HttpConn, stop: &AtomicBool) -> bool {
+    let _ = conn.set_read_timeout(Some(SHUTDOWN_POLL_INTERVAL));
+    let deadline = Instant::now() + KEEP_ALIVE_IDLE;
+    let mut byte = [0u8; 1];
+    let ready = loop {
+        if stop.load(Ordering::SeqCst) {
+            break false;
+        }
+        match conn.peek(&mut byte) {
+            Ok(n) => break n > 0,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                if Instant::now() >= deadline {
+                    break false;
+                }
+            }
+            Err(_) => break false,
+        }
+    };
+    let _ = conn.set_read_timeout(Some(Duration::from_secs(10)));
+    ready
+}
+
+/// Performs the same initialization as `serve` (resolve root, bind, open layers, build caches,
+/// resolve the embedder, load decay state) and reports it instead of serving.
+pub fn check(root: &str, bind: &str) -> CheckReport {
+    let mut report = CheckReport::new("web");
+
+    let Some(root) = report.step("resolve_root", || {
+        let root =
+            std::fs::canonicalize(root).with_context(|| format!("canonicalize root {root}"))?;
+        let detail = serde_json::json!({ "root": root.to_string_lossy() });
+        Ok((root, detail))
+    }) else {
+        return report;
+    };
+
+    report.step("bind", || {
+        let listener = TcpListener::bind(bind).with_context(|| format!("bind {bind}"))?;
+        let local_addr = listener.local_addr().context("read bound address")?;
+        let detail = serde_json::json!({ "bind": bind, "local_addr": local_addr.to_string() });
+        Ok(((), detail))
+    });
+
+    let Some(mut state) = report.step("load_state", || {
+        let state = ServerState::new(root.clone());
+        let detail = serde_json::json!({
+            "decay_path": agentsdb_ops::DecayState::path_for(&root).to_string_lossy(),
+            "decay_ttl_ms": state.decay.ttl_ms,
+            "decay_tracked_chunks": state.decay.accessed.len(),
+        });
+        Ok((state, detail))
+    }) else {
+        return report;
+    };
+
+    let Some(listed) = report.step("list_layers", || {
+        let listed = list_layers(&root)?;
+        let detail = serde_json::to_value(&listed)?;
+        Ok((listed, detail))
+    }) else {
+        return report;
+    };
+
+    report.step("build_caches", || {
+        let mut metas = Vec::with_capacity(listed.len());
+        for layer in &listed {
+            let cache = get_or_build_cache(&mut state, &layer.path)?;
+            metas.push(cache.meta);
+        }
+        Ok(((), serde_json::to_value(metas)?))
+    });
+
+    report.step("resolve_embedder", || {
+        let options = agentsdb_embeddings::config::get_immutable_embedding_options(&root)
+            .context("get immutable embedding options")?;
+        let dim = infer_dim_for_root(&root)? as usize;
+        let cache_enabled = options.cache_enabled;
+        let embedder = options
+            .into_embedder(dim)
+            .context("resolve embedder from options")?;
+        let detail = serde_json::json!({
+            "profile": embedder.profile(),
+            "cache_enabled": cache_enabled,
+        });
+        Ok(((), detail))
+    });
+
+    report
+}
+
 struct ServerState {
     root: PathBuf,
     cache: HashMap<String, LayerCache>,
     decay: agentsdb_ops::DecayState,
+    auth: WebAuth,
+    /// Set when the server is shutting down.
+    stop: Arc<AtomicBool>,
 }
 
 impl ServerState {
@@ -68,8 +451,157 @@ impl ServerState {
             root,
             cache: HashMap::new(),
             decay,
+            auth: WebAuth::default(),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+/// The project roots served by one server, in command-line order.
+struct Roots {
+    entries: Vec<(String, Arc<Mutex<ServerState>>)>,
+    /// Shared by every root's state.
+    stop: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Serialize)]
+struct ListedRoot {
+    name: String,
+    path: String,
+}
+
+impl Roots {
+    fn new(entries: Vec<(String, ServerState)>) -> anyhow::Result<Self> {
+        let stop = entries
+            .first()
+            .map(|(_, st)| Arc::clone(&st.stop))
+            .context("at least one root is required")?;
+        let mut seen = HashSet::new();
+        for (name, _) in &entries {
+            if !seen.insert(name.as_str()) {
+                anyhow::bail!("duplicate root name {name:?}; name roots with NAME=DIR");
+            }
+        }
+        Ok(Self {
+            entries: entries
+                .into_iter()
+                .map(|(name, st)| (name, Arc::new(Mutex::new(st))))
+                .collect(),
+            stop,
+        })
+    }
+
+    /// The root that unscoped `/api/` routes address. `new` rejects an empty list.
+    fn first(&self) -> &Arc<Mutex<ServerState>> {
+        &self.entries[0].1
+    }
+
+    /// Picks the root a request addresses. `/api/roots/<name>/<rest>` is rewritten to
+    /// `/api/<rest>` for the named root; every other path goes to the first root. Returns
+    /// `None` for an unknown root name.
+    fn route(&self, req: &mut Request) -> Option<&Arc<Mutex<ServerState>>> {
+        let Some(scoped) = req.path.strip_prefix("/api/roots/") else {
+            return Some(self.first());
+        };
+        let (name, rest) = scoped.split_once('/').unwrap_or((scoped, ""));
+        let state = self
+            .entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, st)| st)?;
+        req.path = format!("/api/{rest}");
+        Some(state)
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<ListedRoot>> {
+        self.entries
+            .iter()
+            .map(|(name, state)| {
+                let st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+                Ok(ListedRoot {
+                    name: name.clone(),
+                    path: st.root.display().to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Startup banner text, e.g. `root: /src/app` or `roots: app=/src/app, lib=/src/lib`.
+    fn describe(&self) -> String {
+        let listed = self.list().unwrap_or_default();
+        if let [only] = listed.as_slice() {
+            return format!("root: {}", only.path);
+        }
+        let all: Vec<String> = listed
+            .iter()
+            .map(|r| format!("{}={}", r.name, r.path))
+            .collect();
+        format!("roots: {}", all.join(", "))
+    }
+}
+
+/// The process metrics plus the current size of every layer under each root.
+// Layer sizes stay far below 2^52, so the f64 conversion is exact.
+#[allow(clippy::cast_precision_loss)]
+fn render_metrics(roots: &Roots) -> anyhow::Result<String> {
+    let layers = agentsdb_ops::metrics::Metrics::default();
+    for (name, state) in &roots.entries {
+        let root = state
+            .lock()
+            .map_err(|_| anyhow::anyhow!("poisoned mutex"))?
+            .root
+            .clone();
+        for layer in list_layers(&root)? {
+            let labels = [("root", name.as_str()), ("layer", layer.path.as_str())];
+            layers.set_gauge(
+                "agentsdb_layer_size_bytes",
+                "Size of each layer file.",
+                &labels,
+                layer.file_length_bytes as f64,
+            );
+            layers.set_gauge(
+                "agentsdb_layer_chunks",
+                "Chunk records in each layer file.",
+                &labels,
+                layer.chunk_count as f64,
+            );
         }
     }
+    let mut text = agentsdb_ops::metrics::global().render();
+    text.push_str(&layers.render());
+    Ok(text)
+}
+
+/// Splits a `[NAME=]DIR` root argument and canonicalizes the directory. Names are limited to
+/// characters that are safe in a URL path segment.
+fn parse_root_spec(spec: &str) -> anyhow::Result<(String, PathBuf)> {
+    let (name, dir) = match spec.split_once('=') {
+        Some((name, dir)) if !name.is_empty() && !name.contains(['/', '\\']) => (Some(name), dir),
+        _ => (None, spec),
+    };
+    let root = std::fs::canonicalize(dir).with_context(|| format!("canonicalize root {dir}"))?;
+    let name = name.map_or_else(
+        || {
+            root.file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("root")
+                .to_string()
+        },
+        str::to_string,
+    );
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    Ok((name, root))
 }
 
 #[derive(Clone)]
@@ -78,7 +610,10 @@ struct LayerCache {
     file_length_bytes: u64,
     modified_unix_ms: u64,
     meta: LayerMeta,
-    summaries: Vec<ChunkSummary>,
+    /// Shared, so handing the cache to a request does not copy the listing.
+    summaries: Arc<[ChunkSummary]>,
+    /// Compact JSON of each entry of `summaries`, serialized once when the cache is built.
+    summary_json: Arc<[Box<str>]>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -94,6 +629,17 @@ struct LayerMeta {
     confidence_min: f32,
     confidence_max: f32,
     confidence_avg: f32,
+    /// Sidecar index status; filled per request since the index changes independently.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<IndexStatus>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct IndexStatus {
+    path: String,
+    exists: bool,
+    fresh: bool,
+    size_bytes: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -104,7 +650,19 @@ struct ChunkSummary {
     confidence: f32,
     created_at_unix_ms: u64,
     source_count: usize,
+    /// Namespace from the chunk's `namespace:<name>` source, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    /// Language tag from the chunk's `lang:<tag>` source, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    /// Writer's identity from the chunk's `identity:<label>` source, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    identity: Option<String>,
     content_preview: String,
+    /// Full content, kept for `q` filtering but not sent with listings.
+    #[serde(skip)]
+    content: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -118,6 +676,37 @@ struct ChunkFull {
     content: String,
 }
 
+/// One step in the history of a chunk, see [`chunk_history`].
+#[derive(Debug, Clone, Serialize)]
+struct HistoryEntry {
+    /// `version` (the id itself), `earlier_revision` / `revision` (its supersedes chain),
+    /// `tombstone`, or `proposal_event`.
+    relation: &'static str,
+    /// Layer holding `chunk`; proposal events live in the proposal event layer.
+    layer: String,
+    chunk: ChunkFull,
+    /// For revisions, the id the chunk supersedes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supersedes: Option<u32>,
+    /// For tombstones, the id they retract.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retracts: Option<u32>,
+    /// For proposal events, `propose`, `accept` or `reject`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proposal_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChunkHistory {
+    path: String,
+    id: u32,
+    /// The newest revision in the chain that has not been retracted, if any.
+    current_id: Option<u32>,
+    entries: Vec<HistoryEntry>,
+}
+
 fn serve_static_file(path: &str) -> anyhow::Result<(&'static str, Vec<u8>)> {
     let path = path.trim_start_matches('/');
 
@@ -149,10 +738,109 @@ fn serve_static_file(path: &str) -> anyhow::Result<(&'static str, Vec<u8>)> {
     Ok((content_type, content))
 }
 
-fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyhow::Result<()> {
-    let req = read_request(stream).context("read request")?;
+fn handle_conn(stream: &mut HttpConn, roots: &Roots) -> anyhow::Result<()> {
+    let Some(mut req) = read_request(stream).context("read request")? else {
+        stream.keep_alive = false;
+        return Ok(());
+    };
+    // Unknown roots are reported only after the auth check, against the first root's settings.
+    let routed = roots.route(&mut req);
+    let state = routed.unwrap_or_else(|| roots.first());
+    let (allowed, forbidden, stopping, user) = {
+        let st = state
+            .lock()
+            .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+        (
+            st.auth.allows(&req),
+            st.auth.forbids(&req),
+            st.stop.load(Ordering::SeqCst),
+            st.auth.user(&req).map(str::to_string),
+        )
+    };
+    // Everything this request writes is recorded as the signed-in team member's.
+    let _identity = user.as_deref().map(agentsdb_ops::identity::scoped);
+    stream.begin(&req, stopping);
+    if !allowed || forbidden || routed.is_none() {
+        // Refused without reading the body, so the connection cannot be reused.
+        stream.keep_alive = false;
+    }
+    if !allowed {
+        return write_response(
+            stream,
+            401,
+            "text/plain; charset=utf-8",
+            b"unauthorized: missing or invalid bearer token\n",
+        )
+        .context("write 401");
+    }
+    if forbidden {
+        return write_response(
+            stream,
+            403,
+            "text/plain; charset=utf-8",
+            b"forbidden: the server is read-only\n",
+        )
+        .context("write 403");
+    }
+    if routed.is_none() {
+        return write_response(
+            stream,
+            404,
+            "text/plain; charset=utf-8",
+            b"not found: unknown root\n",
+        )
+        .context("write 404");
+    }
+    read_body(stream, &mut req)?;
 
     match (req.method.as_str(), req.path.as_str()) {
+        ("GET", "/metrics") => {
+            let body = render_metrics(roots)?;
+            write_response(
+                stream,
+                200,
+                "text/plain; version=0.0.4; charset=utf-8",
+                body.as_bytes(),
+            )
+            .context("write /metrics")
+        }
+        ("POST", "/api/layer/upload") => {
+            let (file_name, bytes) = match req.content_type.as_deref() {
+                Some(ct) if ct.starts_with("multipart/form-data") => {
+                    let (file_name, bytes) = multipart_file(ct, &req.body)?;
+                    (req.query.get("path").cloned().or(file_name), bytes)
+                }
+                _ => (req.query.get("path").cloned(), req.body.as_slice()),
+            };
+            let file_name = file_name.context("missing query param: path")?;
+            let overwrite = req.query.get("overwrite").is_some_and(|v| v == "1");
+            // Only the final rename waits for the root's lock, so reads on this root keep
+            // being served while a large upload is written and parsed.
+            let root = state
+                .lock()
+                .map_err(|_| anyhow::anyhow!("poisoned mutex"))?
+                .root
+                .clone();
+            let staged = stage_upload(&root, &file_name, bytes, overwrite)?;
+            let uploaded = {
+                let mut st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+                let uploaded = staged.land()?;
+                st.cache.remove(&file_name);
+                uploaded
+            };
+            let body = serde_json::to_vec_pretty(&serde_json::json!({
+                "ok": true,
+                "layer": uploaded,
+            }))?;
+            write_response(stream, 200, "application/json", &body)
+                .context("write /api/layer/upload")
+        }
+        ("GET", "/api/roots") => {
+            let body = serde_json::to_vec_pretty(&roots.list()?)?;
+            write_response(stream, 200, "application/json", &body).context("write /api/roots")
+        }
         ("GET", "/") => {
             let (content_type, body) = serve_static_file("index.html").context("serve index.html")?;
             write_response(stream, 200, content_type, &body).context("write index")
@@ -177,6 +865,18 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
         ("GET", "/favicon.ico") => {
             write_response(stream, 200, "image/png", LOGO_PNG).context("write /favicon.ico")
         }
+        ("GET", "/api/events") => {
+            let (root, stop) = {
+                let st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+                (st.root.clone(), Arc::clone(&st.stop))
+            };
+            // The stream ends when the client goes away (a write error) or on shutdown.
+            stream.keep_alive = false;
+            let _ = stream_layer_events(stream, &root, &stop);
+            Ok(())
+        }
         ("GET", "/api/layers") => {
             let layers = {
                 let st = state.lock().expect("poisoned mutex");
@@ -191,10 +891,23 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
                 .get("path")
                 .context("missing query param: path")?
                 .to_string();
-            let meta = {
-                let mut st = state.lock().expect("poisoned mutex");
+            let abs_path = {
+                let st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+                resolve_layer_path(&st.root, &layer)?
+            };
+            if stream.set_layer_etag(&abs_path, &req.query) {
+                return write_response(stream, 304, "application/json", b"")
+                    .context("write /api/layer/meta");
+            }
+            let mut meta = {
+                let mut st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
                 get_or_build_cache(&mut st, &layer)?.meta
             };
+            meta.index = Some(index_status(&abs_path)?);
             let body = serde_json::to_vec_pretty(&meta)?;
             write_response(stream, 200, "application/json", &body).context("write /api/layer/meta")
         }
@@ -219,41 +932,26 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
                 .get("include_removed")
                 .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
                 .unwrap_or(false);
-            let kind_filter = req.query.get("kind").map(|s| s.as_str()).unwrap_or("");
-
-            let (items, total) = {
-                let mut st = state.lock().expect("poisoned mutex");
-                let cache = get_or_build_cache(&mut st, &layer)?;
-                let filtered: Vec<ChunkSummary> = cache
-                    .summaries
-                    .iter()
-                    .filter(|c| kind_filter.is_empty() || c.kind == kind_filter)
-                    .cloned()
-                    .collect();
-                let total = filtered.len();
-                let end = offset.saturating_add(limit).min(total);
-                let page = if offset >= total {
-                    Vec::new()
-                } else {
-                    filtered[offset..end].to_vec()
-                };
-                (page, total)
+            let filter = ChunkFilter::from_query(&req.query)?;
+            let abs_path = {
+                let st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+                resolve_layer_path(&st.root, &layer)?
             };
-
-            #[derive(Serialize)]
-            struct Out {
-                total: usize,
-                offset: usize,
-                limit: usize,
-                items: Vec<ChunkSummary>,
+            if stream.set_layer_etag(&abs_path, &req.query) {
+                return write_response(stream, 304, "application/json", b"")
+                    .context("write /api/layer/chunks");
             }
-            let out = Out {
-                total,
-                offset,
-                limit,
-                items,
+
+            let cache = {
+                let mut st = state.lock().expect("poisoned mutex");
+                get_or_build_cache(&mut st, &layer)?
             };
-            let body = serde_json::to_vec_pretty(&out)?;
+            let order = filter.apply(&cache.summaries);
+            let total = order.len();
+            let page = &order[offset.min(total)..offset.saturating_add(limit).min(total)];
+            let body = chunk_page_json(total, offset, limit, &cache.summary_json, page);
             write_response(stream, 200, "application/json", &body)
                 .context("write /api/layer/chunks")
         }
@@ -261,10 +959,17 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
             #[derive(Serialize)]
             struct Out {
                 version: &'static str,
+                read_only: bool,
             }
 
+            let read_only = state
+                .lock()
+                .map_err(|_| anyhow::anyhow!("poisoned mutex"))?
+                .auth
+                .read_only;
             let out = Out {
                 version: env!("CARGO_PKG_VERSION"),
+                read_only,
             };
             let body = serde_json::to_vec_pretty(&out)?;
             write_response(stream, 200, "application/json", &body).context("write /api/version")
@@ -295,13 +1000,48 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
             let body = serde_json::to_vec_pretty(&chunk)?;
             write_response(stream, 200, "application/json", &body).context("write /api/layer/chunk")
         }
-        ("POST", "/api/search") => {
-            let input: SearchInput =
-                serde_json::from_slice(&req.body).context("parse JSON body for search")?;
+        ("GET", "/api/layer/chunk/history") => {
+            let layer = req
+                .query
+                .get("path")
+                .context("missing query param: path")?
+                .to_string();
+            let id: u32 = req
+                .query
+                .get("id")
+                .context("missing query param: id")?
+                .parse()
+                .context("invalid id")?;
+
+            let history = {
+                let mut st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+                let abs_path = get_or_build_cache(&mut st, &layer)?.abs_path;
+                chunk_history(&st.root, &layer, &abs_path, id)?
+            };
+
+            let body = serde_json::to_vec_pretty(&history)?;
+            write_response(stream, 200, "application/json", &body)
+                .context("write /api/layer/chunk/history")
+        }
+        ("GET" | "POST", "/api/search") => {
+            let input: SearchInput = if req.method == "GET" {
+                search_input_from_query(&req.query)?
+            } else {
+                serde_json::from_slice(&req.body).context("parse JSON body for search")?
+            };
+            let started = Instant::now();
             let results = {
                 let mut st = state.lock().expect("poisoned mutex");
                 perform_search(&mut st, input)?
             };
+            agentsdb_ops::metrics::global().observe(
+                "agentsdb_search_duration_seconds",
+                "Time spent in semantic searches.",
+                &[("server", "web")],
+                started.elapsed().as_secs_f64(),
+            );
             let body = serde_json::to_vec_pretty(&results)?;
             write_response(stream, 200, "application/json", &body).context("write /api/search")
         }
@@ -347,6 +1087,52 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
             let body = serde_json::to_vec_pretty(&out)?;
             write_response(stream, 200, "application/json", &body).context("write add response")
         }
+        ("POST", "/api/layer/update") => {
+            let input: UpdateInput =
+                serde_json::from_slice(&req.body).context("parse JSON body for update")?;
+            let (outcome, path) = {
+                let mut st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+                let layer_filename = match input.scope.as_str() {
+                    "local" => "AGENTS.local.db",
+                    "delta" => "AGENTS.delta.db",
+                    _ => anyhow::bail!("scope must be 'local' or 'delta'"),
+                };
+                let source_path = resolve_layer_path(&st.root, &input.path)?;
+                let abs_path = resolve_layer_path(&st.root, layer_filename)?;
+                let outcome = agentsdb_ops::supersede_chunk(
+                    &source_path,
+                    &abs_path,
+                    input.id,
+                    input.kind.as_deref(),
+                    &input.content,
+                    input.confidence,
+                    &input.sources,
+                    input.reason.as_deref(),
+                    "agentsdb-web",
+                    env!("CARGO_PKG_VERSION"),
+                )?;
+
+                st.cache.remove(layer_filename);
+                (outcome, layer_filename.to_string())
+            };
+
+            #[derive(Serialize)]
+            struct Out {
+                ok: bool,
+                path: String,
+                #[serde(flatten)]
+                outcome: agentsdb_ops::write::SupersedeOutcome,
+            }
+            let out = Out {
+                ok: true,
+                path,
+                outcome,
+            };
+            let body = serde_json::to_vec_pretty(&out)?;
+            write_response(stream, 200, "application/json", &body).context("write update response")
+        }
         ("POST", "/api/layer/remove") => {
             let input: RemoveInput =
                 serde_json::from_slice(&req.body).context("parse JSON body for remove")?;
@@ -389,10 +1175,15 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
                 .get("redact")
                 .map(String::as_str)
                 .unwrap_or("none");
+            let namespace = req
+                .query
+                .get("namespace")
+                .map(String::as_str)
+                .filter(|v| !v.is_empty());
             let (content_type, body) = {
                 let st = state.lock().expect("poisoned mutex");
                 let abs_path = resolve_layer_path(&st.root, &rel_path)?;
-                export_layer(abs_path.as_path(), &rel_path, format, redact)?
+                export_layer(abs_path.as_path(), &rel_path, format, redact, namespace)?
             };
             write_response(stream, 200, content_type, &body).context("write /api/export")
         }
@@ -400,7 +1191,7 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
             let input: ImportInput =
                 serde_json::from_slice(&req.body).context("parse JSON body for import")?;
             let path = input.path.clone();
-            let (imported, skipped, dry_run) = {
+            let out = {
                 let mut st = state.lock().expect("poisoned mutex");
                 let abs_path = resolve_layer_path(&st.root, &input.path)?;
                 let out = import_into_layer(
@@ -414,17 +1205,21 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
                     input.allow_base.unwrap_or(false),
                     input.dim,
                 )?;
-                if !out.2 {
+                if !out.dry_run {
                     st.cache.remove(&input.path);
                 }
                 out
             };
+            let embedding_usage = out
+                .embedding_usage
+                .map(|u| u.with_price(input.price_per_million_tokens));
             let body = serde_json::to_vec_pretty(&serde_json::json!({
                 "ok": true,
                 "path": path,
-                "imported": imported,
-                "skipped": skipped,
-                "dry_run": dry_run
+                "imported": out.imported,
+                "skipped": out.skipped,
+                "dry_run": out.dry_run,
+                "embedding_usage": embedding_usage
             }))?;
             write_response(stream, 200, "application/json", &body).context("write /api/import")
         }
@@ -470,13 +1265,53 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
                 serde_json::from_slice(&req.body).context("parse JSON body for accept")?;
             let out = {
                 let mut st = state.lock().expect("poisoned mutex");
-                accept_proposals(&mut st, &input.proposal_ids, input.skip_existing)?
+                accept_proposals(
+                    &mut st,
+                    &input.proposal_ids,
+                    input.skip_existing,
+                    input.reason.as_deref(),
+                    user.as_deref()
+                        .or(input.approver.as_deref())
+                        .unwrap_or(DEFAULT_APPROVER),
+                )?
             };
             let body = serde_json::to_vec_pretty(&out)?;
             write_response(stream, 200, "application/json", &body)
                 .context("write /api/proposals/accept")
         }
-        ("POST", "/api/promote") => {
+        ("POST", "/api/index/build") => {
+            let input: IndexBuildInput =
+                serde_json::from_slice(&req.body).context("parse JSON body for index build")?;
+            let abs_path = {
+                let st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+                resolve_layer_path(&st.root, &input.path)?
+            };
+            let index = build_index(&abs_path, &input)?;
+            let body = serde_json::to_vec_pretty(&serde_json::json!({
+                "ok": true,
+                "path": input.path,
+                "index": index,
+            }))?;
+            write_response(stream, 200, "application/json", &body).context("write /api/index/build")
+        }
+        ("POST", "/api/layer/batch") => {
+            let mut input: BatchInput =
+                serde_json::from_slice(&req.body).context("parse JSON body for batch")?;
+            if user.is_some() {
+                input.approver.clone_from(&user);
+            }
+            let out = {
+                let mut st = state
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+                run_batch(&mut st, &input)?
+            };
+            let body = serde_json::to_vec_pretty(&out)?;
+            write_response(stream, 200, "application/json", &body).context("write /api/layer/batch")
+        }
+        ("POST", "/api/promote") => {
             let input: PromoteInput =
                 serde_json::from_slice(&req.body).context("parse JSON body for promote")?;
             let out = {
@@ -503,6 +1338,42 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
             write_response(stream, 200, "application/json", &body)
                 .context("write /api/promote/batch")
         }
+        ("POST", "/api/promote/pr") => {
+            let input: PromotePrInput =
+                serde_json::from_slice(&req.body).context("parse JSON body for promote pr")?;
+            let (root, (base_path, delta_path)) = {
+                let st = state.lock().expect("poisoned mutex");
+                (st.root.clone(), base_promotion_paths(&st)?)
+            };
+            // Talks to the code host: do not hold the state lock meanwhile.
+            let provider = agentsdb_ops::vcs::provider_for_dir(&root)?;
+            let promotion = agentsdb_ops::vcs::promote_via_pr(
+                provider.as_ref(),
+                &delta_path,
+                &base_path,
+                &input.ids,
+            )?;
+            let body = serde_json::to_vec_pretty(&PromotePrOut {
+                ok: true,
+                promotion,
+            })?;
+            write_response(stream, 200, "application/json", &body).context("write /api/promote/pr")
+        }
+        ("GET", "/api/diff") => {
+            let root = state
+                .lock()
+                .map_err(|_| anyhow::anyhow!("poisoned mutex"))?
+                .root
+                .clone();
+            let from = req.query.get("from").context("missing query param: from")?;
+            let to = req.query.get("to").context("missing query param: to")?;
+            let diff = agentsdb_ops::diff_layers(
+                &resolve_layer_path(&root, from)?,
+                &resolve_layer_path(&root, to)?,
+            )?;
+            let body = serde_json::to_vec_pretty(&diff)?;
+            write_response(stream, 200, "application/json", &body).context("write /api/diff")
+        }
         ("GET", "/api/decay") => {
             let st = state.lock().expect("poisoned mutex");
             let body = serde_json::to_vec_pretty(&st.decay)?;
@@ -539,6 +1410,77 @@ fn handle_conn(stream: &mut TcpStream, state: &Arc<Mutex<ServerState>>) -> anyho
             write_response(stream, 200, "application/json", &body)
                 .context("write /api/decay/touch")
         }
+        ("GET", "/api/review/stale") => {
+            let threshold = match req.query.get("threshold") {
+                Some(v) => v.parse::<f32>().context("threshold must be a number")?,
+                None => DEFAULT_REVIEW_THRESHOLD,
+            };
+            let root = state
+                .lock()
+                .map_err(|_| anyhow::anyhow!("poisoned mutex"))?
+                .root
+                .clone();
+            let mut stale = agentsdb_ops::review::stale_queue(
+                &discover_standard_layers_in_root(&root),
+                threshold,
+            )?;
+            for chunk in &mut stale {
+                chunk.layer = layer_file_name(&chunk.layer);
+            }
+            let body = serde_json::to_vec_pretty(&serde_json::json!({
+                "threshold": threshold,
+                "chunks": stale,
+            }))?;
+            write_response(stream, 200, "application/json", &body)
+                .context("write /api/review/stale")
+        }
+        ("POST", "/api/review/decay") => {
+            let input: ReviewDecayInput =
+                serde_json::from_slice(&req.body).context("parse JSON body for review decay")?;
+            let mut st = state
+                .lock()
+                .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+            let mut report = agentsdb_ops::review::decay_confidence(
+                &discover_standard_layers_in_root(&st.root),
+                agentsdb_ops::review::DecayOptions::from_days(input.max_age_days, input.factor),
+                "agentsdb-web",
+                env!("CARGO_PKG_VERSION"),
+            )?;
+            for chunk in &mut report.decayed {
+                chunk.layer = layer_file_name(&chunk.layer);
+                st.cache.remove(&chunk.layer);
+            }
+            for (layer, _, _) in &mut report.skipped {
+                *layer = layer_file_name(layer);
+            }
+            let body = serde_json::to_vec_pretty(&report)?;
+            write_response(stream, 200, "application/json", &body)
+                .context("write /api/review/decay")
+        }
+        ("POST", "/api/review/confirm") => {
+            let input: ReviewConfirmInput =
+                serde_json::from_slice(&req.body).context("parse JSON body for review confirm")?;
+            let mut st = state
+                .lock()
+                .map_err(|_| anyhow::anyhow!("poisoned mutex"))?;
+            let abs_path = resolve_layer_path(&st.root, &input.path)?;
+            let outcome = agentsdb_ops::review::revalidate_chunk(
+                &abs_path,
+                input.id,
+                input.confidence,
+                "agentsdb-web",
+                env!("CARGO_PKG_VERSION"),
+            )?;
+            st.cache.remove(&input.path);
+            let body = serde_json::to_vec_pretty(&serde_json::json!({
+                "ok": true,
+                "path": input.path,
+                "id": outcome.id,
+                "superseded_id": outcome.superseded_id,
+            }))?;
+            write_response(stream, 200, "application/json", &body)
+                .context("write /api/review/confirm")
+        }
         _ => write_response(stream, 404, "text/plain; charset=utf-8", b"not found\n")
             .context("write 404"),
     }
@@ -549,16 +1491,222 @@ struct Request {
     method: String,
     path: String,
     query: HashMap<String, String>,
+    authorization: Option<String>,
+    /// Whether the client wants the connection kept open (HTTP/1.1 unless `Connection: close`).
+    keep_alive: bool,
+    accept_encoding: Option<ContentEncoding>,
+    if_none_match: Option<String>,
+    content_type: Option<String>,
+    content_length: usize,
+    /// The body; only complete after [`read_body`].
     body: Vec<u8>,
 }
 
-fn read_request(stream: &mut TcpStream) -> anyhow::Result<Request> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContentEncoding {
+    Gzip,
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// Picks gzip, then deflate, from an `Accept-Encoding` header (entries with `q=0` excluded).
+    fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let accepted: Vec<&str> = accept_encoding
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';').map(str::trim);
+                let name = parts.next()?;
+                let refused = parts.any(|p| {
+                    p.strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .is_some_and(|q| q <= 0.0)
+                });
+                (!refused).then_some(name)
+            })
+            .collect();
+        let has = |name: &str| accepted.iter().any(|a| a.eq_ignore_ascii_case(name));
+        if has("gzip") {
+            Some(Self::Gzip)
+        } else if has("deflate") {
+            Some(Self::Deflate)
+        } else {
+            None
+        }
+    }
+
+    const fn header_value(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        }
+    }
+
+    fn encode(self, body: &[u8]) -> std::io::Result<Vec<u8>> {
+        let level = flate2::Compression::fast();
+        match self {
+            Self::Gzip => {
+                let mut enc = flate2::write::GzEncoder::new(Vec::new(), level);
+                enc.write_all(body)?;
+                enc.finish()
+            }
+            Self::Deflate => {
+                let mut enc = flate2::write::ZlibEncoder::new(Vec::new(), level);
+                enc.write_all(body)?;
+                enc.finish()
+            }
+        }
+    }
+}
+
+/// A client connection plus the per-request state that shapes responses written to it.
+struct HttpConn {
+    stream: TcpStream,
+    requests: u32,
+    keep_alive: bool,
+    encoding: Option<ContentEncoding>,
+    if_none_match: Option<String>,
+    /// Validator sent with the current response, set by handlers that support conditional GETs.
+    etag: Option<String>,
+    /// Method and route label of the current request, for the request metrics.
+    route: Option<(String, String)>,
+    /// Status of the last response written.
+    status: u16,
+}
+
+impl HttpConn {
+    const fn new(stream: TcpStream) -> Self {
+        Self {
+            stream,
+            requests: 0,
+            keep_alive: false,
+            encoding: None,
+            if_none_match: None,
+            etag: None,
+            route: None,
+            status: 0,
+        }
+    }
+
+    /// Resets the per-request state from the headers of `req`.
+    fn begin(&mut self, req: &Request, stopping: bool) {
+        self.requests += 1;
+        self.keep_alive = req.keep_alive && !stopping && self.requests < MAX_KEEP_ALIVE_REQUESTS;
+        self.encoding = req.accept_encoding;
+        self.if_none_match = req.if_none_match.clone();
+        self.etag = None;
+        // Static files share one label so arbitrary paths do not create new series.
+        let route = if req.path.starts_with("/api/") || req.path == "/metrics" {
+            req.path.clone()
+        } else {
+            "static".to_string()
+        };
+        self.route = Some((req.method.clone(), route));
+    }
+
+    /// Counts the request that was just answered and records how long it took.
+    fn record_request(&self, elapsed: Duration) {
+        let Some((method, route)) = &self.route else {
+            return;
+        };
+        let route = if self.status == 404 {
+            "unmatched"
+        } else {
+            route
+        };
+        let metrics = agentsdb_ops::metrics::global();
+        metrics.inc_counter(
+            "agentsdb_http_requests_total",
+            "HTTP requests answered by the web server.",
+            &[
+                ("method", method),
+                ("route", route),
+                ("status", &self.status.to_string()),
+            ],
+            1,
+        );
+        metrics.observe(
+            "agentsdb_http_request_duration_seconds",
+            "Time spent answering HTTP requests.",
+            &[("route", route)],
+            elapsed.as_secs_f64(),
+        );
+        tracing::info!(
+            method = %method,
+            route,
+            status = self.status,
+            latency_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            "http request"
+        );
+    }
+
+    /// Sets an ETag derived from the layer (and its index sidecar) size and mtime plus the
+    /// query, and reports whether the client's `If-None-Match` already has it.
+    fn set_layer_etag(&mut self, layer_path: &Path, query: &HashMap<String, String>) -> bool {
+        let Some(etag) = layer_etag(layer_path, query) else {
+            return false;
+        };
+        let fresh = self
+            .if_none_match
+            .as_deref()
+            .is_some_and(|v| v.split(',').map(str::trim).any(|t| t == "*" || t == etag));
+        self.etag = Some(etag);
+        fresh
+    }
+}
+
+impl std::ops::Deref for HttpConn {
+    type Target = TcpStream;
+
+    fn deref(&self) -> &TcpStream {
+        &self.stream
+    }
+}
+
+impl std::ops::DerefMut for HttpConn {
+    fn deref_mut(&mut self) -> &mut TcpStream {
+        &mut self.stream
+    }
+}
+
+fn layer_etag(layer_path: &Path, query: &HashMap<String, String>) -> Option<String> {
+    let stamp = |p: &Path| {
+        std::fs::metadata(p)
+            .ok()
+            .map(|m| (m.len(), m.modified().ok()))
+    };
+    let mut hasher = DefaultHasher::new();
+    stamp(layer_path)?.hash(&mut hasher);
+    stamp(&agentsdb_query::default_index_path_for_layer(layer_path)).hash(&mut hasher);
+    let mut pairs: Vec<_> = query.iter().collect();
+    pairs.sort();
+    pairs.hash(&mut hasher);
+    Some(format!("W/\"{:016x}\"", hasher.finish()))
+}
+
+/// Reads one request. Returns `None` when the client closes (or idles out) a kept-alive
+/// connection before sending anything.
+fn read_request(stream: &mut TcpStream) -> anyhow::Result<Option<Request>> {
     let mut buf = Vec::new();
     let mut tmp = [0u8; 4096];
     let header_end;
     loop {
-        let n = stream.read(&mut tmp).context("read socket")?;
+        let n = match stream.read(&mut tmp) {
+            Ok(n) => n,
+            Err(err)
+                if buf.is_empty()
+                    && matches!(
+                        err.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+            {
+                return Ok(None);
+            }
+            Err(err) => return Err(err).context("read socket"),
+        };
         if n == 0 {
+            if buf.is_empty() {
+                return Ok(None);
+            }
             anyhow::bail!("unexpected EOF");
         }
         buf.extend_from_slice(&tmp[..n]);
@@ -579,8 +1727,13 @@ fn read_request(stream: &mut TcpStream) -> anyhow::Result<Request> {
     let method = parts.next().context("missing method")?.to_string();
     let raw_path = parts.next().context("missing path")?.to_string();
     let (path, query) = split_path_query(&raw_path);
+    let mut keep_alive = parts.next() == Some("HTTP/1.1");
 
     let mut content_length: usize = 0;
+    let mut authorization = None;
+    let mut accept_encoding = None;
+    let mut if_none_match = None;
+    let mut content_type = None;
     for line in lines {
         if line.is_empty() {
             break;
@@ -590,53 +1743,158 @@ fn read_request(stream: &mut TcpStream) -> anyhow::Result<Request> {
         };
         if k.trim().eq_ignore_ascii_case("content-length") {
             content_length = v.trim().parse().context("invalid content-length int")?;
+        } else if k.trim().eq_ignore_ascii_case("authorization") {
+            authorization = Some(v.trim().to_string());
+        } else if k.trim().eq_ignore_ascii_case("connection") {
+            let v = v.trim();
+            if v.eq_ignore_ascii_case("close") {
+                keep_alive = false;
+            } else if v.eq_ignore_ascii_case("keep-alive") {
+                keep_alive = true;
+            }
+        } else if k.trim().eq_ignore_ascii_case("accept-encoding") {
+            accept_encoding = ContentEncoding::negotiate(v);
+        } else if k.trim().eq_ignore_ascii_case("if-none-match") {
+            if_none_match = Some(v.trim().to_string());
+        } else if k.trim().eq_ignore_ascii_case("content-type") {
+            content_type = Some(v.trim().to_string());
         }
     }
-    if content_length > MAX_BODY_BYTES {
+    let max_body = if path.ends_with("/layer/upload") {
+        MAX_UPLOAD_BYTES
+    } else {
+        MAX_BODY_BYTES
+    };
+    if content_length > max_body {
         anyhow::bail!("body too large");
     }
+    let mut body = buf[header_end..].to_vec();
+    body.truncate(content_length);
+
+    Ok(Some(Request {
+        method,
+        path,
+        query,
+        authorization,
+        keep_alive,
+        accept_encoding,
+        if_none_match,
+        content_type,
+        content_length,
+        body,
+    }))
+}
 
-    let mut body = Vec::new();
-    body.extend_from_slice(&buf[header_end..]);
-    while body.len() < content_length {
+/// Reads the rest of `req`'s body, up to its `Content-Length` (already checked against the
+/// body limit). Only called once the request passed the auth checks, so unauthenticated
+/// clients cannot make the server buffer an upload-sized body.
+fn read_body(stream: &mut TcpStream, req: &mut Request) -> anyhow::Result<()> {
+    let mut tmp = [0u8; 4096];
+    while req.body.len() < req.content_length {
         let n = stream.read(&mut tmp).context("read body")?;
         if n == 0 {
             anyhow::bail!("unexpected EOF reading body");
         }
-        body.extend_from_slice(&tmp[..n]);
-        if body.len() > MAX_BODY_BYTES {
-            anyhow::bail!("body too large");
-        }
+        req.body.extend_from_slice(&tmp[..n]);
     }
-    body.truncate(content_length);
+    req.body.truncate(req.content_length);
+    Ok(())
+}
 
-    Ok(Request {
-        method,
-        path,
-        query,
-        body,
-    })
+/// HTTP status for a stable error code from [`agentsdb_ops::error_code`].
+fn status_for_error_code(code: &str) -> u16 {
+    match code {
+        "readonly" | "not_writable" | "base_not_allowed" | "policy_violation" => 403,
+        "chunk_not_found" => 404,
+        "id_exists" | "schema_mismatch" | "profile_mismatch" | "dim_mismatch" => 409,
+        "io" | "invalid_layer" => 500,
+        _ => 400,
+    }
+}
+
+/// Answers a failed request. Errors with a stable code get a JSON body `{"error", "code"}` and a
+/// matching status; anything else is a plain-text 500.
+fn write_error(stream: &mut HttpConn, err: &anyhow::Error) -> anyhow::Result<()> {
+    let Some(code) = agentsdb_ops::error_code(err) else {
+        return write_response(
+            stream,
+            500,
+            "text/plain; charset=utf-8",
+            format!("internal error: {err}\n").as_bytes(),
+        );
+    };
+    let body = serde_json::to_vec(&serde_json::json!({
+        "error": format!("{err:#}"),
+        "code": code,
+    }))?;
+    write_response(
+        stream,
+        status_for_error_code(code),
+        "application/json",
+        &body,
+    )
 }
 
+/// Writes a response, compressing JSON bodies when the client accepts it. A 304 is sent
+/// without a body, with the ETag set by the handler.
 fn write_response(
-    stream: &mut TcpStream,
+    stream: &mut HttpConn,
     status: u16,
     content_type: &str,
     body: &[u8],
 ) -> anyhow::Result<()> {
+    stream.status = status;
     let status_line = match status {
         200 => "HTTP/1.1 200 OK",
+        304 => "HTTP/1.1 304 Not Modified",
         400 => "HTTP/1.1 400 Bad Request",
+        401 => "HTTP/1.1 401 Unauthorized",
+        403 => "HTTP/1.1 403 Forbidden",
         404 => "HTTP/1.1 404 Not Found",
+        409 => "HTTP/1.1 409 Conflict",
         500 => "HTTP/1.1 500 Internal Server Error",
         _ => "HTTP/1.1 200 OK",
     };
+    let mut headers = String::new();
+    if status == 401 {
+        headers.push_str("WWW-Authenticate: Bearer realm=\"agentsdb\"\r\n");
+    }
+    // Responses with a validator may be cached but must be revalidated.
+    match &stream.etag {
+        Some(etag) if status == 200 || status == 304 => {
+            headers.push_str(&format!("ETag: {etag}\r\nCache-Control: no-cache\r\n"));
+        }
+        _ => headers.push_str("Cache-Control: no-store\r\n"),
+    }
+    let is_json = content_type.starts_with("application/json");
+    if is_json {
+        headers.push_str("Vary: Accept-Encoding\r\n");
+    }
+    let encoded;
+    let body = match stream.encoding {
+        Some(encoding) if is_json && status != 304 && body.len() >= COMPRESS_MIN_BYTES => {
+            encoded = encoding.encode(body)?;
+            headers.push_str(&format!(
+                "Content-Encoding: {}\r\n",
+                encoding.header_value()
+            ));
+            encoded.as_slice()
+        }
+        _ if status == 304 => &[],
+        _ => body,
+    };
+    let connection = if stream.keep_alive {
+        "keep-alive"
+    } else {
+        "close"
+    };
     write!(
-        stream,
-        "{status_line}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        stream.stream,
+        "{status_line}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n{headers}Connection: {connection}\r\n\r\n",
         body.len()
     )?;
     stream.write_all(body)?;
+    stream.flush()?;
     Ok(())
 }
 
@@ -705,6 +1963,184 @@ struct SearchInput {
     k: Option<usize>,
     #[serde(default)]
     kinds: Option<Vec<String>>,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkSort {
+    Id,
+    CreatedAt,
+    Confidence,
+}
+
+/// Filter and sort options for `GET /api/layer/chunks`.
+#[derive(Debug)]
+struct ChunkFilter {
+    kind: Option<String>,
+    author: Option<String>,
+    namespace: Option<String>,
+    min_confidence: Option<f32>,
+    /// Case-insensitive substring, or a regex when `regex=1`.
+    content: Option<ContentMatch>,
+    sort: ChunkSort,
+    descending: bool,
+}
+
+#[derive(Debug)]
+enum ContentMatch {
+    Substring(String),
+    Regex(regex::Regex),
+}
+
+impl ChunkFilter {
+    /// Parses `kind`, `author`, `namespace`, `min_confidence`, `q`, `regex`, `sort` and `order`.
+    ///
+    /// `sort` is one of `id` (default, ascending), `created_at` or `confidence` (both
+    /// newest/highest first); `order=asc|desc` overrides the direction.
+    fn from_query(query: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let non_empty = |key: &str| query.get(key).filter(|v| !v.trim().is_empty());
+        let min_confidence = non_empty("min_confidence")
+            .map(|v| v.trim().parse::<f32>().context("invalid min_confidence"))
+            .transpose()?;
+        let use_regex = query
+            .get("regex")
+            .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+        let content = non_empty("q")
+            .map(|q| -> anyhow::Result<ContentMatch> {
+                if use_regex {
+                    let re = regex::Regex::new(q).with_context(|| format!("invalid regex: {q}"))?;
+                    Ok(ContentMatch::Regex(re))
+                } else {
+                    Ok(ContentMatch::Substring(q.to_lowercase()))
+                }
+            })
+            .transpose()?;
+        let sort = match non_empty("sort").map(String::as_str) {
+            None | Some("id") => ChunkSort::Id,
+            Some("created_at") => ChunkSort::CreatedAt,
+            Some("confidence") => ChunkSort::Confidence,
+            Some(other) => {
+                anyhow::bail!("invalid sort {other:?} (expected id, created_at or confidence)")
+            }
+        };
+        let descending = match non_empty("order").map(String::as_str) {
+            None => sort != ChunkSort::Id,
+            Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => anyhow::bail!("invalid order {other:?} (expected asc or desc)"),
+        };
+        Ok(Self {
+            kind: non_empty("kind").cloned(),
+            author: non_empty("author").cloned(),
+            namespace: non_empty("namespace").cloned(),
+            min_confidence,
+            content,
+            sort,
+            descending,
+        })
+    }
+
+    fn matches(&self, c: &ChunkSummary) -> bool {
+        if self.kind.as_ref().is_some_and(|k| &c.kind != k) {
+            return false;
+        }
+        if self.author.as_ref().is_some_and(|a| &c.author != a) {
+            return false;
+        }
+        if self.namespace.is_some() && c.namespace != self.namespace {
+            return false;
+        }
+        if self.min_confidence.is_some_and(|m| c.confidence < m) {
+            return false;
+        }
+        match &self.content {
+            None => true,
+            Some(ContentMatch::Substring(q)) => c.content.to_lowercase().contains(q.as_str()),
+            Some(ContentMatch::Regex(re)) => re.is_match(&c.content),
+        }
+    }
+
+    /// Indices of the matching summaries, in listing order.
+    fn apply(&self, summaries: &[ChunkSummary]) -> Vec<usize> {
+        let mut out: Vec<usize> = (0..summaries.len())
+            .filter(|&i| self.matches(&summaries[i]))
+            .collect();
+        // The cache is in append order, so a stable sort keeps ties in layer order.
+        out.sort_by(|&a, &b| {
+            let (a, b) = (&summaries[a], &summaries[b]);
+            let ord = match self.sort {
+                ChunkSort::Id => a.id.cmp(&b.id),
+                ChunkSort::CreatedAt => a.created_at_unix_ms.cmp(&b.created_at_unix_ms),
+                ChunkSort::Confidence => a.confidence.total_cmp(&b.confidence),
+            };
+            if self.descending {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+        out
+    }
+}
+
+/// Body of a `GET /api/layer/chunks` page, `{"total":…,"offset":…,"limit":…,"items":[…]}`,
+/// assembled from the cached per-chunk JSON of the `page` indices into one buffer.
+fn chunk_page_json(
+    total: usize,
+    offset: usize,
+    limit: usize,
+    summary_json: &[Box<str>],
+    page: &[usize],
+) -> Vec<u8> {
+    let head = format!(r#"{{"total":{total},"offset":{offset},"limit":{limit},"items":["#);
+    let items_len: usize = page.iter().map(|&i| summary_json[i].len() + 1).sum();
+    let mut body = Vec::with_capacity(head.len() + items_len + 2);
+    body.extend_from_slice(head.as_bytes());
+    for (n, &i) in page.iter().enumerate() {
+        if n > 0 {
+            body.push(b',');
+        }
+        body.extend_from_slice(summary_json[i].as_bytes());
+    }
+    body.extend_from_slice(b"]}");
+    body
+}
+
+/// Builds a search from
+/// `GET /api/search?q=...&k=...&layers=a.db,b.db&kind=note,decision&namespace=...&language=...`.
+fn search_input_from_query(query: &HashMap<String, String>) -> anyhow::Result<SearchInput> {
+    let list = |key: &str| -> Vec<String> {
+        query
+            .get(key)
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let q = query.get("q").context("missing query param: q")?;
+    if q.trim().is_empty() {
+        anyhow::bail!("query param q must be non-empty");
+    }
+    let k = query
+        .get("k")
+        .map(|v| v.parse::<usize>().context("invalid k"))
+        .transpose()?;
+    let kinds = list("kind");
+    Ok(SearchInput {
+        query: q.clone(),
+        layers: list("layers"),
+        k,
+        kinds: (!kinds.is_empty()).then_some(kinds),
+        namespace: query.get("namespace").filter(|v| !v.is_empty()).cloned(),
+        language: query.get("language").filter(|v| !v.is_empty()).cloned(),
+    })
 }
 
 #[derive(Debug, Serialize)]
@@ -743,19 +2179,153 @@ struct AddInput {
     source_chunks: Vec<u32>,
 }
 
+#[derive(Debug, Deserialize)]
+struct UpdateInput {
+    path: String, // layer holding the chunk being replaced
+    id: u32,
+    scope: String, // local|delta
+    content: String,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    confidence: Option<f32>,
+    #[serde(default)]
+    sources: Vec<String>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewDecayInput {
+    #[serde(default = "default_review_max_age_days")]
+    max_age_days: u64,
+    #[serde(default = "default_review_factor")]
+    factor: f32,
+}
+
+const fn default_review_max_age_days() -> u64 {
+    90
+}
+
+const fn default_review_factor() -> f32 {
+    0.8
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewConfirmInput {
+    path: String, // layer holding the chunk (local|delta)
+    id: u32,
+    #[serde(default = "default_review_confidence")]
+    confidence: f32,
+}
+
+const fn default_review_confidence() -> f32 {
+    1.0
+}
+
+/// File name of a layer path returned by `agentsdb_ops::review`, as the UI addresses layers.
+fn layer_file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map_or_else(|| path.to_string(), |n| n.to_string_lossy().to_string())
+}
+
 #[derive(Debug, Deserialize)]
 struct RemoveInput {
     path: String,
     id: u32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 struct ListedLayer {
     path: String,
     chunk_count: u64,
     file_length_bytes: u64,
 }
 
+/// Length and modification time of each `.db` file directly under `root`, keyed by file name.
+fn layer_stamps(root: &Path) -> BTreeMap<String, (u64, u64)> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("db"))
+        .filter_map(|e| {
+            let meta = e.metadata().ok().filter(std::fs::Metadata::is_file)?;
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX));
+            Some((
+                e.file_name().to_string_lossy().into_owned(),
+                (meta.len(), modified),
+            ))
+        })
+        .collect()
+}
+
+/// Layers that were created, modified or removed between two `layer_stamps` snapshots, with
+/// whether each one exists now.
+fn changed_layers(
+    before: &BTreeMap<String, (u64, u64)>,
+    after: &BTreeMap<String, (u64, u64)>,
+) -> Vec<(String, bool)> {
+    let mut out: Vec<(String, bool)> = after
+        .iter()
+        .filter(|(path, stamp)| before.get(*path) != Some(*stamp))
+        .map(|(path, _)| (path.clone(), true))
+        .collect();
+    out.extend(
+        before
+            .keys()
+            .filter(|path| !after.contains_key(*path))
+            .map(|path| (path.clone(), false)),
+    );
+    out
+}
+
+/// Serves `GET /api/events`: a server-sent event stream with one `layer` event
+/// (`{"path": "AGENTS.local.db", "exists": true}`) per layer file that changes under `root`.
+fn stream_layer_events(
+    stream: &mut TcpStream,
+    root: &Path,
+    stop: &AtomicBool,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n"
+    )?;
+    stream.write_all(b": connected\n\n")?;
+    stream.flush()?;
+
+    let mut last = layer_stamps(root);
+    let mut idle = 0;
+    while !stop.load(Ordering::SeqCst) {
+        std::thread::sleep(EVENTS_POLL_INTERVAL);
+        let now = layer_stamps(root);
+        let changed = changed_layers(&last, &now);
+        if changed.is_empty() {
+            idle += 1;
+            if idle >= EVENTS_KEEPALIVE_POLLS {
+                idle = 0;
+                stream.write_all(b": keep-alive\n\n")?;
+                stream.flush()?;
+            }
+            continue;
+        }
+        idle = 0;
+        for (path, exists) in changed {
+            let data = serde_json::json!({ "path": path, "exists": exists });
+            write!(stream, "event: layer\ndata: {data}\n\n")?;
+        }
+        stream.flush()?;
+        last = now;
+    }
+    Ok(())
+}
+
 fn list_layers(root: &Path) -> anyhow::Result<Vec<ListedLayer>> {
     let mut out = Vec::new();
     for entry in std::fs::read_dir(root).with_context(|| format!("read dir {}", root.display()))? {
@@ -789,40 +2359,175 @@ fn list_layers(root: &Path) -> anyhow::Result<Vec<ListedLayer>> {
     Ok(out)
 }
 
-fn perform_search(state: &mut ServerState, input: SearchInput) -> anyhow::Result<SearchOutput> {
-    use agentsdb_ops::{search_layers, SearchConfig};
-    use agentsdb_query::LayerSet;
+/// Validates `bytes` as a layer file and places it under `root` as `file_name`.
+///
+/// Like every other web write, the target must be writable under the standard
+/// [`agentsdb_format::WritePolicy`], so the base and user layers cannot be replaced. Existing
+/// layers are only replaced when `overwrite` is set, and never when they are signed. The
+/// upload is written to a temporary file next to the target and opened there first; it must
+/// parse completely and use the same embedding dimension and profile as the layers already
+/// under `root`.
+#[cfg(test)]
+fn upload_layer(
+    root: &Path,
+    file_name: &str,
+    bytes: &[u8],
+    overwrite: bool,
+) -> anyhow::Result<ListedLayer> {
+    stage_upload(root, file_name, bytes, overwrite)?.land()
+}
 
-    // Build LayerSet from input.layers, or auto-discover if empty
-    let layer_set = if input.layers.is_empty() {
-        // Auto-discover standard layers in the root directory
-        discover_standard_layers_in_root(&state.root)
-    } else {
-        let mut layer_set = LayerSet {
-            base: None,
-            user: None,
-            delta: None,
-            local: None,
-        };
-        for path in &input.layers {
-            let abs_path = resolve_layer_path(&state.root, path)?;
+/// An upload written and validated next to its target, waiting to be renamed into place.
+///
+/// Staging does the slow part of an upload (writing and parsing up to [`MAX_UPLOAD_BYTES`])
+/// without the root's state lock; [`StagedUpload::land`] is the only step that needs it. The
+/// temporary file is removed if the upload is dropped without landing.
+struct StagedUpload {
+    tmp: PathBuf,
+    abs: PathBuf,
+    file_name: String,
+    overwrite: bool,
+    listed: ListedLayer,
+}
 
-            // Determine layer type from filename
-            let layer_name = abs_path
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("");
+impl StagedUpload {
+    /// Re-checks the target, since another request may have written it since staging, and
+    /// renames the upload over it.
+    fn land(self) -> anyhow::Result<ListedLayer> {
+        check_upload_target(&self.abs, &self.file_name, self.overwrite)?;
+        std::fs::rename(&self.tmp, &self.abs)
+            .with_context(|| format!("rename into {}", self.abs.display()))?;
+        Ok(self.listed.clone())
+    }
+}
 
-            if layer_name == "AGENTS.local.db" {
-                layer_set.local = Some(abs_path.to_string_lossy().to_string());
-            } else if layer_name == "AGENTS.user.db" {
-                layer_set.user = Some(abs_path.to_string_lossy().to_string());
-            } else if layer_name == "AGENTS.delta.db" {
-                layer_set.delta = Some(abs_path.to_string_lossy().to_string());
-            } else if layer_name == "AGENTS.db" {
-                layer_set.base = Some(abs_path.to_string_lossy().to_string());
-            } else {
-                // For custom layer names, try to infer from the path or default to base
+impl Drop for StagedUpload {
+    fn drop(&mut self) {
+        // Already renamed away once the upload has landed.
+        let _ = std::fs::remove_file(&self.tmp);
+    }
+}
+
+/// Writes `bytes` to a temporary file next to `file_name` under `root` and validates it,
+/// without touching the target itself. See [`upload_layer`] for the checks.
+fn stage_upload(
+    root: &Path,
+    file_name: &str,
+    bytes: &[u8],
+    overwrite: bool,
+) -> anyhow::Result<StagedUpload> {
+    let abs = resolve_layer_path(root, file_name)?;
+    check_upload_target(&abs, file_name, overwrite)?;
+    let tmp = agentsdb_format::stage_layer_bytes(&abs, bytes)
+        .with_context(|| format!("write upload for {file_name}"))?;
+    let listed = match validate_upload(root, file_name, &tmp) {
+        Ok(listed) => listed,
+        Err(err) => {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(err);
+        }
+    };
+    Ok(StagedUpload {
+        tmp,
+        abs,
+        file_name: file_name.to_string(),
+        overwrite,
+        listed,
+    })
+}
+
+fn check_upload_target(abs: &Path, file_name: &str, overwrite: bool) -> anyhow::Result<()> {
+    agentsdb_format::WritePolicy::standard().check(abs)?;
+    if abs.exists() {
+        if !overwrite {
+            anyhow::bail!("{file_name} already exists (pass overwrite=1 to replace it)");
+        }
+        let existing = agentsdb_format::LayerFile::open_lenient(abs)
+            .with_context(|| format!("open {file_name}"))?;
+        if agentsdb_embeddings::signing::verify_layer(&existing)
+            != agentsdb_embeddings::signing::SignatureStatus::Unsigned
+        {
+            anyhow::bail!("{file_name} is signed and cannot be replaced by an upload");
+        }
+    }
+    Ok(())
+}
+
+fn validate_upload(root: &Path, file_name: &str, tmp: &Path) -> anyhow::Result<ListedLayer> {
+    use agentsdb_embeddings::layer_metadata::LayerMetadataV1;
+
+    let file = agentsdb_format::LayerFile::open_lenient(tmp).context("invalid layer file")?;
+    for chunk in file.chunks() {
+        chunk.context("invalid layer file")?;
+    }
+    let profile = |f: &agentsdb_format::LayerFile| {
+        f.layer_metadata_bytes()
+            .and_then(|b| LayerMetadataV1::from_json_bytes(b).ok())
+            .map(|m| m.embedding_profile)
+    };
+    let uploaded_profile = profile(&file);
+    for layer in list_layers(root)? {
+        if layer.path == file_name {
+            continue;
+        }
+        let Ok(other) = agentsdb_format::LayerFile::open_lenient(root.join(&layer.path)) else {
+            continue;
+        };
+        if other.embedding_dim() != file.embedding_dim() {
+            anyhow::bail!(
+                "embedding dimension {} does not match {} ({})",
+                file.embedding_dim(),
+                layer.path,
+                other.embedding_dim()
+            );
+        }
+        if let (Some(ours), Some(theirs)) = (&uploaded_profile, profile(&other)) {
+            if *ours != theirs {
+                anyhow::bail!("embedding profile does not match {}", layer.path);
+            }
+        }
+    }
+    Ok(ListedLayer {
+        path: file_name.to_string(),
+        chunk_count: file.chunk_count,
+        file_length_bytes: file.header.file_length_bytes,
+    })
+}
+
+fn perform_search(state: &mut ServerState, input: SearchInput) -> anyhow::Result<SearchOutput> {
+    use agentsdb_ops::{search_layers, SearchConfig};
+    use agentsdb_query::LayerSet;
+
+    // Build LayerSet from input.layers, or auto-discover if empty
+    let layer_set = if input.layers.is_empty() {
+        // Auto-discover standard layers in the root directory
+        discover_standard_layers_in_root(&state.root)
+    } else {
+        let mut layer_set = LayerSet {
+            base: None,
+            user: None,
+            delta: None,
+            local: None,
+        };
+        for path in &input.layers {
+            let abs_path = resolve_layer_path(&state.root, path)?;
+
+            // Determine layer type from filename
+            let layer_name = abs_path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("");
+
+            if layer_name == "AGENTS.local.db" {
+                layer_set.local = Some(abs_path.to_string_lossy().to_string());
+            } else if layer_name == "AGENTS.user.db" {
+                layer_set.user = Some(abs_path.to_string_lossy().to_string());
+            } else if layer_name == "AGENTS.delta.db" {
+                layer_set.delta = Some(abs_path.to_string_lossy().to_string());
+            } else if layer_name == "AGENTS.db" {
+                layer_set.base = Some(abs_path.to_string_lossy().to_string());
+            } else {
+                // For custom layer names, try to infer from the path or default to base
                 layer_set.base = Some(abs_path.to_string_lossy().to_string());
             }
         }
@@ -835,14 +2540,21 @@ fn perform_search(state: &mut ServerState, input: SearchInput) -> anyhow::Result
         query_vec: None,
         k: input.k.unwrap_or(10),
         kinds: input.kinds.unwrap_or_default(),
-        use_index: false,
+        namespace: input.namespace,
+        language: input.language,
+        use_index: true,
         mode: agentsdb_query::SearchMode::Hybrid,
+        // The UI and dashboards repeat the same searches; unchanged layers reuse the results.
+        cache_results: true,
+        disk_cache: false,
     };
 
     let results = search_layers(&layer_set, config)?;
 
     // Get embedding dimension from first opened layer
-    let opened = layer_set.open().context("open layers for dimension")?;
+    let opened = layer_set
+        .open_pooled(agentsdb_query::LayerPool::global())
+        .context("open layers for dimension")?;
     let query_embedding_dim = if !opened.is_empty() {
         opened[0].1.embedding_dim()
     } else {
@@ -908,6 +2620,7 @@ fn layer_id_to_filename(layer: agentsdb_core::types::LayerId) -> &'static str {
         LayerId::User => "AGENTS.user.db",
         LayerId::Delta => "AGENTS.delta.db",
         LayerId::Base => "AGENTS.db",
+        LayerId::Session => "AGENTS.session.db",
     }
 }
 
@@ -990,6 +2703,12 @@ fn get_or_build_cache(st: &mut ServerState, file_name: &str) -> anyhow::Result<L
         None => true,
     };
     if needs_rebuild {
+        agentsdb_ops::metrics::global().inc_counter(
+            "agentsdb_layer_cache_rebuilds_total",
+            "Layer summaries re-read after the layer file changed.",
+            &[("layer", file_name)],
+            1,
+        );
         let cache = build_cache(file_name.to_string(), abs)?;
         st.cache.insert(file_name.to_string(), cache);
     }
@@ -1004,23 +2723,10 @@ fn build_cache(path_label: String, abs_path: PathBuf) -> anyhow::Result<LayerCac
     let file =
         LayerFile::open(&abs_path).with_context(|| format!("open {}", abs_path.display()))?;
     let modified_ms = modified_unix_ms(&abs_path)?;
-    let mut kinds: BTreeMap<String, u64> = BTreeMap::new();
     let mut summaries = Vec::with_capacity(file.chunk_count as usize);
 
-    let mut conf_min = 1.0f32;
-    let mut conf_max = 0.0f32;
-    let mut conf_sum = 0.0f64;
-    let mut conf_n = 0u64;
-
     for chunk in file.chunks() {
         let chunk = chunk?;
-        *kinds.entry(chunk.kind.to_string()).or_insert(0) += 1;
-
-        conf_min = conf_min.min(chunk.confidence);
-        conf_max = conf_max.max(chunk.confidence);
-        conf_sum += chunk.confidence as f64;
-        conf_n += 1;
-
         let sources = file.sources_for(chunk.rel_start, chunk.rel_count)?;
         let source_count = sources.len();
         let content_preview = truncate_preview(chunk.content, 240);
@@ -1032,15 +2738,21 @@ fn build_cache(path_label: String, abs_path: PathBuf) -> anyhow::Result<LayerCac
             confidence: chunk.confidence,
             created_at_unix_ms: chunk.created_at_unix_ms,
             source_count,
+            namespace: agentsdb_query::namespace_of(&sources).map(str::to_string),
+            language: agentsdb_query::language_of(&sources).map(str::to_string),
+            identity: agentsdb_query::identity_of(&sources).map(str::to_string),
             content_preview,
+            content: chunk.content.to_string(),
         });
     }
 
-    let confidence_avg = if conf_n == 0 {
-        0.0
-    } else {
-        (conf_sum / (conf_n as f64)) as f32
-    };
+    let summary_json = summaries
+        .iter()
+        .map(|s| serde_json::to_string(s).map(String::into_boxed_str))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Stored by the writer; older files are scanned.
+    let stats = agentsdb_format::ChunkStats::of(&file)?;
 
     // Extract embedding backend from layer metadata, or fall back to options chunks
     let embedding_backend = file.layer_metadata_bytes()
@@ -1097,10 +2809,11 @@ fn build_cache(path_label: String, abs_path: PathBuf) -> anyhow::Result<LayerCac
         embedding_element_type: format!("{:?}", file.embedding_matrix.element_type).to_lowercase(),
         embedding_backend,
         relationship_count: file.relationship_count,
-        kinds,
-        confidence_min: if conf_n == 0 { 0.0 } else { conf_min },
-        confidence_max: if conf_n == 0 { 0.0 } else { conf_max },
-        confidence_avg,
+        confidence_avg: stats.confidence_mean(),
+        kinds: stats.by_kind,
+        confidence_min: stats.confidence_min,
+        confidence_max: stats.confidence_max,
+        index: None,
     };
 
     Ok(LayerCache {
@@ -1108,7 +2821,8 @@ fn build_cache(path_label: String, abs_path: PathBuf) -> anyhow::Result<LayerCac
         file_length_bytes: file.header.file_length_bytes,
         modified_unix_ms: modified_ms,
         meta,
-        summaries,
+        summaries: summaries.into(),
+        summary_json: summary_json.into(),
     })
 }
 
@@ -1146,6 +2860,154 @@ fn read_chunk_full(path: &Path, id: u32) -> anyhow::Result<ChunkFull> {
     anyhow::bail!("chunk id {id} not found");
 }
 
+/// A chunk read for [`chunk_history`], with the links it records.
+struct HistoryChunk {
+    chunk: ChunkFull,
+    /// The id named by a `supersedes:<id>` source (revisions only).
+    supersedes: Option<u32>,
+    /// The id a tombstone retracts.
+    retracts: Option<u32>,
+}
+
+fn read_history_chunks(abs_path: &Path) -> anyhow::Result<Vec<HistoryChunk>> {
+    use agentsdb_format::SourceRef;
+
+    let file = LayerFile::open_lenient(abs_path)
+        .with_context(|| format!("open {}", abs_path.display()))?;
+    let mut out = Vec::new();
+    for chunk in file.chunks() {
+        let chunk = chunk?;
+        let refs = file.sources_for(chunk.rel_start, chunk.rel_count)?;
+        let tombstone = chunk.kind == agentsdb_query::KIND_TOMBSTONE;
+        let supersedes = refs.iter().find_map(|s| match s {
+            SourceRef::String(v) if !tombstone => v
+                .strip_prefix(agentsdb_query::SUPERSEDES_SOURCE_PREFIX)
+                .and_then(|n| n.parse().ok()),
+            SourceRef::String(_) | SourceRef::ChunkId(_) => None,
+        });
+        let retracts = refs.iter().find_map(|s| match s {
+            SourceRef::ChunkId(target) if tombstone => Some(*target),
+            SourceRef::ChunkId(_) | SourceRef::String(_) => None,
+        });
+        out.push(HistoryChunk {
+            chunk: ChunkFull {
+                id: chunk.id,
+                kind: chunk.kind.to_string(),
+                author: chunk.author.to_string(),
+                confidence: chunk.confidence,
+                created_at_unix_ms: chunk.created_at_unix_ms,
+                sources: refs.iter().map(|s| format!("{s:?}")).collect(),
+                content: chunk.content.to_string(),
+            },
+            supersedes,
+            retracts,
+        });
+    }
+    Ok(out)
+}
+
+/// Reconstructs how chunk `id` of `layer` evolved: every appended version of the id, the
+/// revisions linked to it through `supersedes:<id>` sources (in both directions), the
+/// tombstones retracting any of them, and the proposal events about them. Entries are
+/// ordered by creation time.
+fn chunk_history(
+    root: &Path,
+    layer: &str,
+    abs_path: &Path,
+    id: u32,
+) -> anyhow::Result<ChunkHistory> {
+    let stored = read_history_chunks(abs_path)?;
+    if !stored.iter().any(|s| s.chunk.id == id) {
+        anyhow::bail!("chunk id {id} not found");
+    }
+
+    // Walk the supersedes links out from `id` until no new ids turn up.
+    let mut earlier = BTreeSet::new();
+    let mut later = BTreeSet::new();
+    let mut frontier = vec![id];
+    while let Some(next) = frontier.pop() {
+        for s in &stored {
+            if s.chunk.id == next {
+                if let Some(prev) = s.supersedes.filter(|p| *p != id && earlier.insert(*p)) {
+                    frontier.push(prev);
+                }
+            }
+        }
+    }
+    frontier.push(id);
+    while let Some(next) = frontier.pop() {
+        for s in &stored {
+            if s.supersedes == Some(next) && s.chunk.id != id && later.insert(s.chunk.id) {
+                frontier.push(s.chunk.id);
+            }
+        }
+    }
+    let in_chain = |n: u32| n == id || earlier.contains(&n) || later.contains(&n);
+
+    let mut entries = Vec::new();
+    let mut retracted = BTreeSet::new();
+    for s in stored {
+        let relation = match s.retracts {
+            Some(target) if in_chain(target) => {
+                retracted.insert(target);
+                "tombstone"
+            }
+            Some(_) => continue,
+            None if s.chunk.id == id => "version",
+            None if earlier.contains(&s.chunk.id) => "earlier_revision",
+            None if later.contains(&s.chunk.id) => "revision",
+            None => continue,
+        };
+        entries.push(HistoryEntry {
+            relation,
+            layer: layer.to_string(),
+            chunk: s.chunk,
+            supersedes: s.supersedes,
+            retracts: s.retracts,
+            action: None,
+            proposal_id: None,
+        });
+    }
+    let current_id = entries
+        .iter()
+        .filter(|e| matches!(e.relation, "version" | "revision"))
+        .map(|e| e.chunk.id)
+        .filter(|n| !retracted.contains(n))
+        .max();
+
+    let mut proposals = BTreeSet::new();
+    for (event_id, ev) in read_proposal_events_from_layer(root)? {
+        let action = ev.action.clone().unwrap_or_else(|| "propose".to_string());
+        let relevant = if action == "propose" {
+            let from = ev.from_path.as_deref().unwrap_or(PROPOSAL_EVENT_LAYER);
+            from == layer && in_chain(ev.context_id) && proposals.insert(event_id)
+        } else {
+            ev.proposal_id.is_some_and(|p| proposals.contains(&p))
+        };
+        if !relevant {
+            continue;
+        }
+        let event_layer = root.join(PROPOSAL_EVENT_LAYER);
+        entries.push(HistoryEntry {
+            relation: "proposal_event",
+            layer: PROPOSAL_EVENT_LAYER.to_string(),
+            chunk: read_chunk_full(&event_layer, event_id)?,
+            supersedes: None,
+            retracts: None,
+            proposal_id: Some(ev.proposal_id.unwrap_or(event_id)),
+            action: Some(action),
+        });
+    }
+    entries.sort_by_key(|e| e.chunk.created_at_unix_ms);
+
+    Ok(ChunkHistory {
+        path: layer.to_string(),
+        id,
+        current_id,
+        entries,
+    })
+}
+
 #[allow(clippy::too_many_arguments)]
 fn append_chunk(
     path: &Path,
@@ -1157,7 +3019,7 @@ fn append_chunk(
     dim: Option<u32>,
     sources: &[String],
     source_chunks: &[u32],
-) -> anyhow::Result<u32> {
+) -> Result<u32, agentsdb_ops::WriteError> {
     agentsdb_ops::write::append_chunk(
         path,
         scope,
@@ -1178,12 +3040,14 @@ fn export_layer(
     rel_path: &str,
     format: &str,
     redact: &str,
+    namespace: Option<&str>,
 ) -> anyhow::Result<(&'static str, Vec<u8>)> {
     agentsdb_ops::export::export_layer(
         abs_path,
         rel_path,
         format,
         redact,
+        namespace,
         "agentsdb-web",
         env!("CARGO_PKG_VERSION"),
     )
@@ -1199,11 +3063,11 @@ fn import_into_layer(
     preserve_ids: bool,
     allow_base: bool,
     dim: Option<u32>,
-) -> anyhow::Result<(usize, usize, bool)> {
-    let outcome = agentsdb_ops::import::import_into_layer(
+) -> Result<agentsdb_ops::import::ImportOutcome, agentsdb_ops::ImportError> {
+    agentsdb_ops::import::import_into_layer(
         abs_path,
         scope,
-        data,
+        data.as_bytes(),
         dry_run,
         dedupe,
         preserve_ids,
@@ -1211,8 +3075,7 @@ fn import_into_layer(
         dim,
         "agentsdb-web",
         env!("CARGO_PKG_VERSION"),
-    )?;
-    Ok((outcome.imported, outcome.skipped, outcome.dry_run))
+    )
 }
 
 #[derive(Debug, Deserialize)]
@@ -1232,6 +3095,9 @@ struct ImportInput {
     allow_base: Option<bool>,
     #[serde(default)]
     dim: Option<u32>,
+    /// Optional price per million input tokens for the usage report's cost estimate.
+    #[serde(default)]
+    price_per_million_tokens: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1265,6 +3131,11 @@ struct AcceptInput {
     proposal_ids: Vec<u32>,
     #[serde(default)]
     skip_existing: bool,
+    #[serde(default)]
+    reason: Option<String>,
+    /// Name recorded as the approver (defaults to `web`; team members are recorded by name).
+    #[serde(default)]
+    approver: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1283,6 +3154,19 @@ struct PromoteBatchInput {
     skip_existing: bool,
 }
 
+/// Chunks of `AGENTS.delta.db` to add to `AGENTS.db` through a pull request.
+#[derive(Debug, Deserialize)]
+struct PromotePrInput {
+    ids: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PromotePrOut {
+    ok: bool,
+    #[serde(flatten)]
+    promotion: agentsdb_ops::vcs::PrPromotion,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "snake_case")]
 enum ProposalStatus {
@@ -1308,18 +3192,27 @@ struct ProposalRow {
     exists_in_user: bool,
     exists_in_source: bool,
     exists_in_target: bool,
+    /// Preview of the proposed chunk, if it is still in the source layer.
+    source_preview: Option<String>,
+    /// Identity recorded on the propose event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proposed_by_identity: Option<String>,
     decided_at_unix_ms: Option<u64>,
     decided_by: Option<String>,
+    /// Identity recorded on the accept or reject event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decided_by_identity: Option<String>,
     decision_reason: Option<String>,
     decision_outcome: Option<String>,
+    approvers: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 struct ProposalEvent {
     #[serde(default)]
-    action: Option<String>, // propose | accept | reject
+    action: Option<String>, // propose | approve | accept | reject
     #[serde(default)]
-    proposal_id: Option<u32>, // for accept/reject
+    proposal_id: Option<u32>, // for approve/accept/reject
     context_id: u32,
     #[serde(default)]
     from_path: Option<String>,
@@ -1340,6 +3233,8 @@ struct ProposalEvent {
     #[serde(default)]
     actor: Option<String>,
     #[serde(default)]
+    identity: Option<String>,
+    #[serde(default)]
     reason: Option<String>,
     #[serde(default)]
     outcome: Option<String>,
@@ -1357,10 +3252,13 @@ struct ProposalState {
     why: Option<String>,
     what: Option<String>,
     where_: Option<String>,
+    proposed_by_identity: Option<String>,
     decided_at_unix_ms: Option<u64>,
     decided_by: Option<String>,
+    decided_by_identity: Option<String>,
     decision_reason: Option<String>,
     decision_outcome: Option<String>,
+    approvers: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -1372,6 +3270,16 @@ struct PromoteOut {
     out_path: Option<String>,
 }
 
+/// Result of accepting proposals: the promotion, plus the proposals that were only approved
+/// because the promotion policy needs more approvers.
+#[derive(Debug, Clone, Serialize)]
+struct AcceptOut {
+    #[serde(flatten)]
+    promote: PromoteOut,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    awaiting_approval: Vec<u32>,
+}
+
 fn apply_proposal_event(map: &mut BTreeMap<u32, ProposalState>, event_id: u32, ev: ProposalEvent) {
     let action = ev.action.as_deref().unwrap_or("propose");
     match action {
@@ -1393,18 +3301,31 @@ fn apply_proposal_event(map: &mut BTreeMap<u32, ProposalState>, event_id: u32, e
                     why: ev.why,
                     what: ev.what,
                     where_: ev.where_,
+                    proposed_by_identity: ev.identity,
                     decided_at_unix_ms: None,
                     decided_by: None,
+                    decided_by_identity: None,
                     decision_reason: None,
                     decision_outcome: None,
+                    approvers: Vec::new(),
                 },
             );
         }
-        "accept" | "reject" => {
+        agentsdb_ops::policy::APPROVE_ACTION | "accept" | "reject" => {
             let Some(proposal_id) = ev.proposal_id else {
                 return;
             };
-            if let Some(s) = map.get_mut(&proposal_id) {
+            let Some(s) = map.get_mut(&proposal_id) else {
+                return;
+            };
+            if action != "reject" {
+                if let Some(actor) = &ev.actor {
+                    if !s.approvers.contains(actor) {
+                        s.approvers.push(actor.clone());
+                    }
+                }
+            }
+            if action != agentsdb_ops::policy::APPROVE_ACTION {
                 s.status = if action == "accept" {
                     ProposalStatus::Accepted
                 } else {
@@ -1412,6 +3333,7 @@ fn apply_proposal_event(map: &mut BTreeMap<u32, ProposalState>, event_id: u32, e
                 };
                 s.decided_at_unix_ms = ev.created_at_unix_ms;
                 s.decided_by = ev.actor;
+                s.decided_by_identity = ev.identity;
                 s.decision_reason = ev.reason;
                 s.decision_outcome = ev.outcome;
             }
@@ -1461,26 +3383,47 @@ fn append_proposal_event_chunk(
     record: serde_json::Value,
     context_id: u32,
 ) -> anyhow::Result<u32> {
+    let ids = append_proposal_event_chunks(st, vec![(record, context_id)])?;
+    ids.first().copied().context("append proposal event chunk")
+}
+
+/// Appends several proposal events (record, context id) in one atomic write.
+fn append_proposal_event_chunks(
+    st: &mut ServerState,
+    records: Vec<(serde_json::Value, u32)>,
+) -> anyhow::Result<Vec<u32>> {
     let path = st.root.join(PROPOSAL_EVENT_LAYER);
     let dim = if path.exists() {
         None
     } else {
         Some(infer_dim_for_root(&st.root).context("infer dim for proposal layer")?)
     };
-    let id = append_chunk(
+    let identity = agentsdb_ops::identity::current();
+    let chunks = records
+        .into_iter()
+        .map(|(mut record, context_id)| {
+            if let (Some(identity), Some(fields)) = (&identity, record.as_object_mut()) {
+                fields.insert("identity".to_string(), identity.clone().into());
+            }
+            Ok(agentsdb_ops::write::NewChunk {
+                kind: PROPOSAL_EVENT_KIND.to_string(),
+                content: serde_json::to_string(&record).context("serialize proposal record")?,
+                confidence: 1.0,
+                sources: vec![agentsdb_format::ChunkSource::ChunkId(context_id)],
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let ids = agentsdb_ops::append_chunks(
         &path,
         "delta",
-        None,
-        PROPOSAL_EVENT_KIND,
-        &serde_json::to_string(&record).context("serialize proposal record")?,
-        1.0,
+        chunks,
         dim,
-        &[],
-        &[context_id],
+        "agentsdb-web",
+        env!("CARGO_PKG_VERSION"),
     )
-    .context("append proposal event chunk")?;
+    .context("append proposal event chunks")?;
     st.cache.remove(PROPOSAL_EVENT_LAYER);
-    Ok(id)
+    Ok(ids)
 }
 
 fn load_proposal_states(st: &mut ServerState) -> anyhow::Result<BTreeMap<u32, ProposalState>> {
@@ -1494,7 +3437,8 @@ fn load_proposal_states(st: &mut ServerState) -> anyhow::Result<BTreeMap<u32, Pr
 
 fn list_proposals(st: &mut ServerState, include_all: bool) -> anyhow::Result<Vec<ProposalRow>> {
     let states = load_proposal_states(st)?;
-    let mut layer_ids: HashMap<String, HashSet<u32>> = HashMap::new();
+    // Chunk id -> content preview, per standard layer.
+    let mut layer_ids: HashMap<String, HashMap<u32, String>> = HashMap::new();
     for file in [
         "AGENTS.local.db",
         "AGENTS.user.db",
@@ -1505,20 +3449,25 @@ fn list_proposals(st: &mut ServerState, include_all: bool) -> anyhow::Result<Vec
             let cache = get_or_build_cache(st, file)?;
             layer_ids.insert(
                 file.to_string(),
-                cache.summaries.iter().map(|c| c.id).collect(),
+                cache
+                    .summaries
+                    .iter()
+                    .map(|c| (c.id, c.content_preview.clone()))
+                    .collect(),
             );
         } else {
-            layer_ids.insert(file.to_string(), HashSet::new());
+            layer_ids.insert(file.to_string(), HashMap::new());
         }
     }
 
+    let no_chunks = HashMap::new();
     let mut out = Vec::new();
     for s in states.values() {
         if !include_all && !matches!(s.status, ProposalStatus::Pending) {
             continue;
         }
-        let from_ids = layer_ids.get(&s.from_path).cloned().unwrap_or_default();
-        let to_ids = layer_ids.get(&s.to_path).cloned().unwrap_or_default();
+        let from_ids = layer_ids.get(&s.from_path).unwrap_or(&no_chunks);
+        let to_ids = layer_ids.get(&s.to_path).unwrap_or(&no_chunks);
         out.push(ProposalRow {
             proposal_id: s.proposal_id,
             context_id: s.context_id,
@@ -1532,18 +3481,22 @@ fn list_proposals(st: &mut ServerState, include_all: bool) -> anyhow::Result<Vec
             where_: s.where_.clone(),
             exists_in_delta: layer_ids
                 .get("AGENTS.delta.db")
-                .map(|ids| ids.contains(&s.context_id))
+                .map(|ids| ids.contains_key(&s.context_id))
                 .unwrap_or(false),
             exists_in_user: layer_ids
                 .get("AGENTS.user.db")
-                .map(|ids| ids.contains(&s.context_id))
+                .map(|ids| ids.contains_key(&s.context_id))
                 .unwrap_or(false),
-            exists_in_source: from_ids.contains(&s.context_id),
-            exists_in_target: to_ids.contains(&s.context_id),
+            exists_in_source: from_ids.contains_key(&s.context_id),
+            exists_in_target: to_ids.contains_key(&s.context_id),
+            source_preview: from_ids.get(&s.context_id).cloned(),
+            proposed_by_identity: s.proposed_by_identity.clone(),
             decided_at_unix_ms: s.decided_at_unix_ms,
             decided_by: s.decided_by.clone(),
+            decided_by_identity: s.decided_by_identity.clone(),
             decision_reason: s.decision_reason.clone(),
             decision_outcome: s.decision_outcome.clone(),
+            approvers: s.approvers.clone(),
         });
     }
     Ok(out)
@@ -1595,6 +3548,174 @@ fn record_proposal(st: &mut ServerState, input: ProposeInput) -> anyhow::Result<
     Ok(id)
 }
 
+/// The file name and contents of the first file part of a `multipart/form-data` body.
+fn multipart_file<'a>(
+    content_type: &str,
+    body: &'a [u8],
+) -> anyhow::Result<(Option<String>, &'a [u8])> {
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack.windows(needle.len()).position(|w| w == needle)
+    }
+    let boundary = content_type
+        .split(';')
+        .find_map(|p| p.trim().strip_prefix("boundary="))
+        .map(|b| b.trim_matches('"'))
+        .context("multipart body without boundary")?;
+    let delimiter = format!("--{boundary}");
+    let terminator = format!("\r\n--{boundary}");
+    let mut rest = body;
+    while let Some(start) = find(rest, delimiter.as_bytes()) {
+        let part = &rest[start + delimiter.len()..];
+        if part.starts_with(b"--") {
+            break;
+        }
+        let headers_end = find(part, b"\r\n\r\n").context("malformed multipart part")?;
+        let headers = String::from_utf8_lossy(&part[..headers_end]);
+        let content = &part[headers_end + 4..];
+        let end = find(content, terminator.as_bytes()).context("unterminated multipart part")?;
+        let file_name = headers
+            .lines()
+            .filter(|l| l.to_ascii_lowercase().starts_with("content-disposition:"))
+            .flat_map(|l| l.split(';'))
+            .find_map(|p| p.trim().strip_prefix("filename="))
+            .map(|f| f.trim_matches('"').to_string());
+        if file_name.is_some() {
+            return Ok((file_name, &content[..end]));
+        }
+        rest = &content[end..];
+    }
+    anyhow::bail!("multipart body has no file part")
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexBuildInput {
+    path: String,
+    #[serde(default)]
+    store_embeddings_f32: bool,
+    #[serde(default)]
+    binary_codes: bool,
+}
+
+/// Reports whether the default `.agix` sidecar for `layer_path` exists and matches the layer.
+fn index_status(layer_path: &Path) -> anyhow::Result<IndexStatus> {
+    let index_path = agentsdb_query::default_index_path_for_layer(layer_path);
+    let size_bytes = std::fs::metadata(&index_path).ok().map(|m| m.len());
+    let fresh = match size_bytes {
+        Some(_) => {
+            let layer = LayerFile::open_lenient(layer_path)
+                .with_context(|| format!("open {}", layer_path.display()))?;
+            agentsdb_query::layer_index_is_fresh(&layer, &index_path)
+                .with_context(|| format!("read index {}", index_path.display()))?
+        }
+        None => false,
+    };
+    Ok(IndexStatus {
+        path: index_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        exists: size_bytes.is_some(),
+        fresh,
+        size_bytes,
+    })
+}
+
+/// Builds (or rebuilds) the default sidecar index for `layer_path`.
+fn build_index(layer_path: &Path, input: &IndexBuildInput) -> anyhow::Result<IndexStatus> {
+    let layer = LayerFile::open_lenient(layer_path)
+        .with_context(|| format!("open {}", layer_path.display()))?;
+    let index_path = agentsdb_query::default_index_path_for_layer(layer_path);
+    agentsdb_query::build_layer_index(
+        &layer,
+        &index_path,
+        agentsdb_query::IndexBuildOptions {
+            store_embeddings_even_if_f32: input.store_embeddings_f32,
+            binary_codes: input.binary_codes,
+        },
+    )
+    .with_context(|| format!("build index for {}", layer_path.display()))?;
+    drop(layer);
+    index_status(layer_path)
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchInput {
+    action: String, // accept | reject | promote | retract
+    ids: Vec<u32>,  // proposal ids for accept/reject, chunk ids otherwise
+    #[serde(default)]
+    path: Option<String>, // layer holding the chunks (promote, retract)
+    #[serde(default)]
+    to_path: Option<String>, // promote target
+    #[serde(default)]
+    scope: Option<String>, // retract target: local | delta
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    approver: Option<String>, // accept
+    #[serde(default)]
+    skip_existing: bool,
+}
+
+/// Runs one batch action over `input.ids`, writing at most one atomic append per target layer.
+fn run_batch(st: &mut ServerState, input: &BatchInput) -> anyhow::Result<serde_json::Value> {
+    if input.ids.is_empty() {
+        anyhow::bail!("ids must be non-empty");
+    }
+    let path = || input.path.as_deref().context("path is required");
+    let result = match input.action.as_str() {
+        "accept" => serde_json::to_value(accept_proposals(
+            st,
+            &input.ids,
+            input.skip_existing,
+            input.reason.as_deref(),
+            input.approver.as_deref().unwrap_or(DEFAULT_APPROVER),
+        )?)?,
+        "reject" => {
+            reject_proposals(st, &input.ids, input.reason.as_deref())?;
+            serde_json::json!({ "rejected": input.ids })
+        }
+        "promote" => {
+            let to_path = input.to_path.as_deref().context("to_path is required")?;
+            serde_json::to_value(promote_layers(
+                st,
+                path()?,
+                to_path,
+                &input.ids,
+                input.skip_existing,
+            )?)?
+        }
+        "retract" => {
+            let scope = input.scope.as_deref().unwrap_or("delta");
+            let layer_filename = match scope {
+                "local" => "AGENTS.local.db",
+                "delta" => "AGENTS.delta.db",
+                _ => anyhow::bail!("scope must be 'local' or 'delta'"),
+            };
+            let source_path = resolve_layer_path(&st.root, path()?)?;
+            let abs_path = resolve_layer_path(&st.root, layer_filename)?;
+            let tombstone_ids = agentsdb_ops::retract_chunks(
+                &source_path,
+                &abs_path,
+                scope,
+                &input.ids,
+                input
+                    .reason
+                    .as_deref()
+                    .unwrap_or("retracted from the web UI"),
+                "agentsdb-web",
+                env!("CARGO_PKG_VERSION"),
+            )?;
+            st.cache.remove(layer_filename);
+            serde_json::json!({ "path": layer_filename, "retracted": input.ids, "tombstone_ids": tombstone_ids })
+        }
+        other => anyhow::bail!(
+            "unknown batch action {other:?} (expected accept, reject, promote or retract)"
+        ),
+    };
+    Ok(serde_json::json!({ "ok": true, "action": input.action, "result": result }))
+}
+
 fn reject_proposals(
     st: &mut ServerState,
     proposal_ids: &[u32],
@@ -1612,6 +3733,8 @@ fn reject_proposals(
             anyhow::bail!("proposal {id} is not pending");
         }
     }
+    check_reviewer(&st.auth, &states, proposal_ids)?;
+    let mut records = Vec::with_capacity(proposal_ids.len());
     for id in proposal_ids {
         let s = states.get(id).context("proposal missing")?;
         let record = serde_json::json!({
@@ -1623,7 +3746,38 @@ fn reject_proposals(
             "outcome": "rejected",
             "reason": reason,
         });
-        append_proposal_event_chunk(st, record, s.context_id).context("append reject event")?;
+        records.push((record, s.context_id));
+    }
+    append_proposal_event_chunks(st, records).context("append reject events")?;
+    Ok(())
+}
+
+/// Refuses to let a team member decide their own proposals, unless `auth` allows self-review.
+/// The reviewer is the request's [scoped](agentsdb_ops::identity::scoped) identity.
+fn check_reviewer(
+    auth: &WebAuth,
+    states: &BTreeMap<u32, ProposalState>,
+    proposal_ids: &[u32],
+) -> anyhow::Result<()> {
+    if !auth.separates_review() {
+        return Ok(());
+    }
+    let Some(reviewer) = agentsdb_ops::identity::current() else {
+        return Ok(());
+    };
+    for id in proposal_ids {
+        let Some(s) = states.get(id) else {
+            continue;
+        };
+        if s.proposed_by_identity.as_deref() == Some(reviewer.as_str()) {
+            return Err(agentsdb_ops::WriteError::Rejected {
+                hook: "review".to_string(),
+                reason: format!(
+                    "proposal {id} was proposed by {reviewer}; another user must decide it"
+                ),
+            }
+            .into());
+        }
     }
     Ok(())
 }
@@ -1632,7 +3786,9 @@ fn accept_proposals(
     st: &mut ServerState,
     proposal_ids: &[u32],
     skip_existing: bool,
-) -> anyhow::Result<PromoteOut> {
+    reason: Option<&str>,
+    approver: &str,
+) -> anyhow::Result<AcceptOut> {
     if proposal_ids.is_empty() {
         anyhow::bail!("ids must be non-empty");
     }
@@ -1656,12 +3812,20 @@ fn accept_proposals(
             anyhow::bail!("proposal {id} flow is not permitted");
         }
     }
+    check_reviewer(&st.auth, &states, proposal_ids)?;
 
-    let out = promote_from_to(st, &states, proposal_ids, skip_existing)?;
+    let awaiting_approval = record_approvals(st, &states, proposal_ids, approver)?;
+    let ready: Vec<u32> = proposal_ids
+        .iter()
+        .copied()
+        .filter(|id| !awaiting_approval.contains(id))
+        .collect();
+    let out = promote_from_to(st, &states, &ready, skip_existing)?;
     let promoted: HashSet<u32> = out.promoted.iter().copied().collect();
     let skipped: HashSet<u32> = out.skipped.iter().copied().collect();
 
-    for id in proposal_ids {
+    let mut records = Vec::with_capacity(ready.len());
+    for id in &ready {
         let s = states.get(id).context("proposal missing")?;
         let outcome = if promoted.contains(&s.context_id) {
             "promoted"
@@ -1675,14 +3839,55 @@ fn accept_proposals(
             "proposal_id": id,
             "context_id": s.context_id,
             "created_at_unix_ms": agentsdb_ops::util::now_unix_ms(),
-            "actor": "web",
+            "actor": approver,
             "outcome": outcome,
+            "reason": reason,
             "out_path": out.out_path.clone(),
         });
-        append_proposal_event_chunk(st, record, s.context_id).context("append accept event")?;
+        records.push((record, s.context_id));
+    }
+    if !records.is_empty() {
+        append_proposal_event_chunks(st, records).context("append accept events")?;
     }
 
-    Ok(out)
+    Ok(AcceptOut {
+        promote: out,
+        awaiting_approval,
+    })
+}
+
+/// Records an approval by `approver` for each proposal that the root's promotion policy says
+/// still needs more approvers, and returns their ids; the rest can be accepted now.
+fn record_approvals(
+    st: &mut ServerState,
+    states: &BTreeMap<u32, ProposalState>,
+    proposal_ids: &[u32],
+    approver: &str,
+) -> anyhow::Result<Vec<u32>> {
+    let policy = agentsdb_ops::policy::PromotionPolicy::load(&st.root)?;
+    let mut awaiting = Vec::new();
+    let mut records = Vec::new();
+    for id in proposal_ids {
+        let s = states.get(id).context("proposal missing")?;
+        if policy.missing_approvals(&s.approvers, approver) == 0 {
+            continue;
+        }
+        records.push((
+            serde_json::json!({
+                "action": agentsdb_ops::policy::APPROVE_ACTION,
+                "proposal_id": id,
+                "context_id": s.context_id,
+                "created_at_unix_ms": agentsdb_ops::util::now_unix_ms(),
+                "actor": approver,
+            }),
+            s.context_id,
+        ));
+        awaiting.push(*id);
+    }
+    if !records.is_empty() {
+        append_proposal_event_chunks(st, records).context("append approve events")?;
+    }
+    Ok(awaiting)
 }
 
 fn promote_from_to(
@@ -1768,7 +3973,7 @@ fn promote_layers(
     skip_existing: bool,
 ) -> anyhow::Result<PromoteOut> {
     if to_path == "AGENTS.db" {
-        return promote_delta_to_base_new(st, ids, skip_existing);
+        return promote_delta_to_base_new(st, ids);
     }
 
     let from_abs = resolve_layer_path(&st.root, from_path)?;
@@ -1793,145 +3998,41 @@ fn promote_layers(
     })
 }
 
-fn chunks_equal(a: &agentsdb_format::ChunkInput, b: &agentsdb_format::ChunkInput) -> bool {
-    a.id == b.id
-        && a.kind == b.kind
-        && a.content == b.content
-        && a.author == b.author
-        && a.confidence.to_bits() == b.confidence.to_bits()
-        && a.created_at_unix_ms == b.created_at_unix_ms
-        && a.embedding.len() == b.embedding.len()
-        && a.embedding
-            .iter()
-            .zip(b.embedding.iter())
-            .all(|(x, y)| x.to_bits() == y.to_bits())
-        && sources_equal(&a.sources, &b.sources)
-}
-
-fn sources_equal(a: &[agentsdb_format::ChunkSource], b: &[agentsdb_format::ChunkSource]) -> bool {
-    if a.len() != b.len() {
-        return false;
-    }
-    for (x, y) in a.iter().zip(b.iter()) {
-        match (x, y) {
-            (
-                agentsdb_format::ChunkSource::ChunkId(ax),
-                agentsdb_format::ChunkSource::ChunkId(by),
-            ) => {
-                if ax != by {
-                    return false;
-                }
-            }
-            (
-                agentsdb_format::ChunkSource::SourceString(ax),
-                agentsdb_format::ChunkSource::SourceString(by),
-            ) => {
-                if ax != by {
-                    return false;
-                }
-            }
-            _ => return false,
-        }
-    }
-    true
-}
-
-fn promote_delta_to_base_new(
-    st: &mut ServerState,
-    ids: &[u32],
-    skip_existing: bool,
-) -> anyhow::Result<PromoteOut> {
-    let base_path = st.root.join("AGENTS.db");
-    let delta_path = st.root.join("AGENTS.delta.db");
-    if !base_path.exists() {
-        anyhow::bail!("AGENTS.db not found under root");
-    }
-    if !delta_path.exists() {
-        anyhow::bail!("AGENTS.delta.db not found under root");
-    }
-
-    let base_file = agentsdb_format::LayerFile::open(&base_path)
-        .with_context(|| format!("open {}", base_path.display()))?;
-    let base_schema = agentsdb_format::schema_of(&base_file);
-    let base_metadata = base_file.layer_metadata_bytes().map(|b| b.to_vec());
-    let mut by_id: BTreeMap<u32, agentsdb_format::ChunkInput> =
-        agentsdb_format::read_all_chunks(&base_file)?
-            .into_iter()
-            .map(|c| (c.id, c))
-            .collect();
-
-    let delta_file = agentsdb_format::LayerFile::open(&delta_path)
-        .with_context(|| format!("open {}", delta_path.display()))?;
-    let delta_schema = agentsdb_format::schema_of(&delta_file);
-    if delta_schema.dim != base_schema.dim
-        || delta_schema.element_type != base_schema.element_type
-        || delta_schema.quant_scale.to_bits() != base_schema.quant_scale.to_bits()
-    {
-        anyhow::bail!("schema mismatch between AGENTS.delta.db and AGENTS.db");
-    }
-    let delta_by_id: HashMap<u32, agentsdb_format::ChunkInput> =
-        agentsdb_format::read_all_chunks(&delta_file)?
-            .into_iter()
-            .map(|c| (c.id, c))
-            .collect();
-
-    let mut promoted = Vec::new();
-    let mut skipped = Vec::new();
-
-    for id in ids {
-        let Some(c) = delta_by_id.get(id) else {
-            anyhow::bail!("chunk id {id} not found in AGENTS.delta.db");
-        };
-        if c.kind == PROPOSAL_EVENT_KIND {
-            anyhow::bail!("cannot promote proposal event chunk id {id} into base");
-        }
-        if let Some(existing) = by_id.get(id) {
-            if chunks_equal(existing, c) {
-                skipped.push(*id);
-                continue;
-            }
-            if skip_existing {
-                anyhow::bail!(
-                    "base already contains id {id} with different content (cannot skip conflicts)"
-                );
-            }
-            anyhow::bail!("base already contains id {id} with different content");
-        }
-        let mut c = c.clone();
-        if c.author != "human" {
-            c.author = "human".to_string();
-        }
-        by_id.insert(*id, c);
-        promoted.push(*id);
-    }
-
-    promoted.sort_unstable();
-    promoted.dedup();
-    skipped.sort_unstable();
-    skipped.dedup();
-    if promoted.is_empty() {
+fn promote_delta_to_base_new(st: &mut ServerState, ids: &[u32]) -> anyhow::Result<PromoteOut> {
+    let (base_path, delta_path) = base_promotion_paths(st)?;
+    let mut rebuilt = agentsdb_ops::promote::rebuild_base(&base_path, &delta_path, ids)?;
+    if rebuilt.promoted.is_empty() {
         return Ok(PromoteOut {
             ok: true,
-            promoted,
-            skipped,
+            promoted: rebuilt.promoted,
+            skipped: rebuilt.skipped,
             out_path: None,
         });
     }
 
     let out_path = st.root.join("AGENTS.db.new");
-    let mut chunks: Vec<agentsdb_format::ChunkInput> = by_id.into_values().collect();
-    chunks.sort_by_key(|c| c.id);
-    agentsdb_format::write_layer_atomic(&out_path, &base_schema, &mut chunks, base_metadata.as_deref())
-        .context("write AGENTS.db.new")?;
+    rebuilt.write(&out_path)?;
 
     Ok(PromoteOut {
         ok: true,
-        promoted,
-        skipped,
+        promoted: rebuilt.promoted,
+        skipped: rebuilt.skipped,
         out_path: Some(out_path.to_string_lossy().into_owned()),
     })
 }
 
+fn base_promotion_paths(st: &ServerState) -> anyhow::Result<(PathBuf, PathBuf)> {
+    let base_path = st.root.join("AGENTS.db");
+    let delta_path = st.root.join("AGENTS.delta.db");
+    if !base_path.exists() {
+        anyhow::bail!("AGENTS.db not found under root");
+    }
+    if !delta_path.exists() {
+        anyhow::bail!("AGENTS.delta.db not found under root");
+    }
+    Ok((base_path, delta_path))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1943,6 +4044,7 @@ mod tests {
             dim,
             element_type: agentsdb_format::EmbeddingElementType::F32,
             quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
         };
         let profile = EmbeddingProfile {
             backend: "hash".to_string(),
@@ -2014,6 +4116,165 @@ mod tests {
         assert!(root.join("AGENTS.user.db").exists());
     }
 
+    #[test]
+    fn check_reports_every_startup_step() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        write_layer_with_custom_profile(&dir.path().join("AGENTS.db"), 8, OutputNorm::None);
+
+        let report = check(&dir.path().to_string_lossy(), "127.0.0.1:0");
+        assert!(report.ok, "{report:?}");
+        let steps: Vec<&str> = report.steps.iter().map(|s| s.step).collect();
+        assert_eq!(
+            steps,
+            [
+                "resolve_root",
+                "bind",
+                "load_state",
+                "list_layers",
+                "build_caches",
+                "resolve_embedder"
+            ]
+        );
+    }
+
+    #[test]
+    fn proposal_rows_preview_source_and_keep_accept_reason() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        let delta = root.join("AGENTS.delta.db");
+        write_layer_with_custom_profile(&delta, 8, OutputNorm::None);
+        let _ = append_chunk(
+            &delta,
+            "delta",
+            Some(9),
+            "note",
+            "promote me",
+            0.9,
+            None,
+            &[],
+            &[],
+        )
+        .expect("append delta chunk");
+
+        let mut st = ServerState::new(root.to_path_buf());
+        let input: ProposeInput = serde_json::from_value(serde_json::json!({
+            "context_id": 9,
+            "title": "share the note",
+        }))
+        .expect("propose input");
+        let proposal_id = record_proposal(&mut st, input).expect("propose");
+
+        let rows = list_proposals(&mut st, false).expect("list");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].source_preview.as_deref(), Some("promote me"));
+
+        accept_proposals(&mut st, &[proposal_id], false, Some("reviewed"), "web").expect("accept");
+        assert!(list_proposals(&mut st, false).expect("list").is_empty());
+        let rows = list_proposals(&mut st, true).expect("list all");
+        assert!(matches!(rows[0].status, ProposalStatus::Accepted));
+        assert_eq!(rows[0].decision_reason.as_deref(), Some("reviewed"));
+    }
+
+    #[test]
+    fn accept_waits_for_the_policy_approvals() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        let delta = root.join("AGENTS.delta.db");
+        write_layer_with_custom_profile(&delta, 8, OutputNorm::None);
+        let _ = append_chunk(
+            &delta,
+            "delta",
+            Some(9),
+            "note",
+            "needs two reviewers",
+            0.9,
+            None,
+            &[],
+            &[],
+        )
+        .expect("append delta chunk");
+        std::fs::write(
+            agentsdb_ops::policy::PromotionPolicy::path_for(root),
+            r#"{"required_approvals": 2}"#,
+        )
+        .expect("write policy");
+
+        let mut st = ServerState::new(root.to_path_buf());
+        let input: ProposeInput =
+            serde_json::from_value(serde_json::json!({ "context_id": 9 })).expect("propose input");
+        let proposal_id = record_proposal(&mut st, input).expect("propose");
+
+        for _ in 0..2 {
+            let out =
+                accept_proposals(&mut st, &[proposal_id], false, None, "ana").expect("approve");
+            assert_eq!(out.awaiting_approval, [proposal_id]);
+            assert!(out.promote.promoted.is_empty());
+        }
+        let rows = list_proposals(&mut st, false).expect("list");
+        assert_eq!(rows[0].approvers, ["ana"]);
+
+        let out = accept_proposals(&mut st, &[proposal_id], false, None, "bo").expect("accept");
+        assert!(out.awaiting_approval.is_empty());
+        assert_eq!(out.promote.promoted.len(), 1);
+        let rows = list_proposals(&mut st, true).expect("list all");
+        assert!(matches!(rows[0].status, ProposalStatus::Accepted));
+        assert_eq!(rows[0].decided_by.as_deref(), Some("bo"));
+    }
+
+    #[test]
+    fn team_members_cannot_decide_their_own_proposals() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        let delta = root.join("AGENTS.delta.db");
+        write_layer_with_custom_profile(&delta, 8, OutputNorm::None);
+        let _ = append_chunk(
+            &delta,
+            "delta",
+            Some(9),
+            "note",
+            "reviewed by someone else",
+            0.9,
+            None,
+            &[],
+            &[],
+        )
+        .expect("append delta chunk");
+
+        let mut st = ServerState::new(root.to_path_buf());
+        st.auth.users = vec![
+            WebUser {
+                name: "ana".to_string(),
+                token: "ana-token-0123456789".to_string(),
+            },
+            WebUser {
+                name: "bo".to_string(),
+                token: "bo-token-0123456789".to_string(),
+            },
+        ];
+        let proposal_id = {
+            let _ana = agentsdb_ops::identity::scoped("ana");
+            let input: ProposeInput =
+                serde_json::from_value(serde_json::json!({ "context_id": 9 }))
+                    .expect("propose input");
+            let proposal_id = record_proposal(&mut st, input).expect("propose");
+            let err = accept_proposals(&mut st, &[proposal_id], false, None, "ana")
+                .err()
+                .expect("self-review is refused");
+            assert_eq!(agentsdb_ops::error_code(&err), Some("policy_violation"));
+            assert!(reject_proposals(&mut st, &[proposal_id], None).is_err());
+            proposal_id
+        };
+        let rows = list_proposals(&mut st, false).expect("list");
+        assert_eq!(rows[0].proposed_by_identity.as_deref(), Some("ana"));
+
+        {
+            let _bo = agentsdb_ops::identity::scoped("bo");
+            accept_proposals(&mut st, &[proposal_id], false, None, "bo").expect("accept");
+        }
+        let rows = list_proposals(&mut st, true).expect("list all");
+        assert_eq!(rows[0].decided_by_identity.as_deref(), Some("bo"));
+    }
+
     #[test]
     fn web_proposal_states_ignore_missing_layer() {
         let dir = tempfile::tempdir().expect("tempdir");
@@ -2022,6 +4283,492 @@ mod tests {
         assert!(states.is_empty());
     }
 
+    #[test]
+    fn auth_token_guards_api_routes() {
+        let req = |method: &str, path: &str, authorization: Option<&str>| Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: HashMap::new(),
+            authorization: authorization.map(str::to_string),
+            keep_alive: false,
+            accept_encoding: None,
+            if_none_match: None,
+            content_type: None,
+            content_length: 0,
+            body: Vec::new(),
+        };
+        let mut auth = WebAuth {
+            token: Some("s3cret".to_string()),
+            anonymous_read: false,
+            read_only: false,
+            users: Vec::new(),
+            allow_self_review: false,
+        };
+        assert!(auth.allows(&req("GET", "/", None)));
+        assert!(!auth.allows(&req("GET", "/api/layers", None)));
+        assert!(!auth.allows(&req("GET", "/api/layers", Some("Bearer wrong"))));
+        assert!(!auth.allows(&req("GET", "/api/layers", Some("Basic s3cret"))));
+        assert!(auth.allows(&req("GET", "/api/layers", Some("Bearer s3cret"))));
+        assert!(!auth.allows(&req("POST", "/api/layer/add", None)));
+
+        auth.anonymous_read = true;
+        assert!(auth.allows(&req("GET", "/api/layers", None)));
+        assert!(!auth.allows(&req("POST", "/api/layer/add", None)));
+        assert!(auth.allows(&req("POST", "/api/layer/add", Some("Bearer s3cret"))));
+
+        assert!(WebAuth::default().allows(&req("POST", "/api/layer/add", None)));
+
+        let team = WebAuth {
+            users: vec![WebUser {
+                name: "ana".to_string(),
+                token: "ana-token-0123456789".to_string(),
+            }],
+            ..WebAuth::default()
+        };
+        let ana = req(
+            "POST",
+            "/api/layer/add",
+            Some("Bearer ana-token-0123456789"),
+        );
+        assert!(team.allows(&ana));
+        assert_eq!(team.user(&ana), Some("ana"));
+        assert!(!team.allows(&req("POST", "/api/layer/add", Some("Bearer s3cret"))));
+        assert_eq!(
+            auth.user(&req("GET", "/api/layers", Some("Bearer s3cret"))),
+            None
+        );
+    }
+
+    #[test]
+    fn read_only_refuses_everything_but_reads_and_search() {
+        let req = |method: &str, path: &str| Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: HashMap::new(),
+            authorization: None,
+            keep_alive: false,
+            accept_encoding: None,
+            if_none_match: None,
+            content_type: None,
+            content_length: 0,
+            body: Vec::new(),
+        };
+        let auth = WebAuth {
+            read_only: true,
+            ..WebAuth::default()
+        };
+        assert!(!auth.forbids(&req("GET", "/api/layer/chunks")));
+        assert!(!auth.forbids(&req("POST", "/api/search")));
+        for path in [
+            "/api/layer/add",
+            "/api/layer/remove",
+            "/api/import",
+            "/api/promote",
+            "/api/proposals/accept",
+            "/api/index/build",
+        ] {
+            assert!(auth.forbids(&req("POST", path)), "{path}");
+        }
+        assert!(!WebAuth::default().forbids(&req("POST", "/api/layer/add")));
+    }
+
+    #[test]
+    fn connection_is_kept_alive_and_answers_conditional_gets() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        write_layer_with_custom_profile(&dir.path().join("AGENTS.db"), 8, OutputNorm::None);
+        let roots = Roots::new(vec![(
+            "root".to_string(),
+            ServerState::new(dir.path().to_path_buf()),
+        )])
+        .expect("roots");
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            serve_connection(stream, &roots);
+        });
+
+        let mut client = TcpStream::connect(addr).expect("connect");
+        let mut send = |extra: &str| -> (String, Vec<u8>) {
+            write!(
+                client,
+                "GET /api/layer/meta?path=AGENTS.db HTTP/1.1\r\nHost: x\r\n{extra}\r\n"
+            )
+            .expect("send");
+            let mut buf = Vec::new();
+            let mut byte = [0u8; 1];
+            while find_header_end(&buf).is_none() {
+                client.read_exact(&mut byte).expect("read header");
+                buf.push(byte[0]);
+            }
+            let head = String::from_utf8(buf).expect("utf-8 head");
+            let len: usize = head
+                .lines()
+                .find_map(|l| l.strip_prefix("Content-Length: "))
+                .and_then(|v| v.trim().parse().ok())
+                .expect("content-length");
+            let mut body = vec![0u8; len];
+            client.read_exact(&mut body).expect("read body");
+            (head, body)
+        };
+
+        let (head, body) = send("");
+        assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+        assert!(head.contains("Connection: keep-alive"));
+        let etag = head
+            .lines()
+            .find_map(|l| l.strip_prefix("ETag: "))
+            .expect("etag")
+            .to_string();
+        let meta: serde_json::Value = serde_json::from_slice(&body).expect("meta json");
+        assert_eq!(meta["chunk_count"], 1);
+
+        // Same connection: the unchanged layer revalidates to a bodyless 304.
+        let (head, body) = send(&format!("If-None-Match: {etag}\r\nConnection: close\r\n"));
+        assert!(head.starts_with("HTTP/1.1 304"), "{head}");
+        assert!(head.contains("Connection: close"));
+        assert!(body.is_empty());
+        server.join().expect("server thread");
+    }
+
+    #[test]
+    fn roots_are_served_under_their_names() {
+        let a = tempfile::tempdir().expect("tempdir");
+        let b = tempfile::tempdir().expect("tempdir");
+        write_layer_with_custom_profile(&a.path().join("AGENTS.db"), 8, OutputNorm::None);
+        write_layer_with_custom_profile(&b.path().join("AGENTS.local.db"), 8, OutputNorm::None);
+        let specs = [
+            format!("app={}", a.path().display()),
+            format!("lib={}", b.path().display()),
+        ];
+        let server = start_roots(&specs, "127.0.0.1:0", WebAuth::default()).expect("start");
+        let get = |path: &str| -> String {
+            let mut client = TcpStream::connect(server.local_addr()).expect("connect");
+            write!(
+                client,
+                "GET {path} HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n"
+            )
+            .expect("send");
+            let mut out = String::new();
+            client.read_to_string(&mut out).expect("read");
+            out
+        };
+
+        let listed = get("/api/roots");
+        assert!(listed.contains("\"name\": \"app\"") && listed.contains("\"name\": \"lib\""));
+        assert!(get("/api/layers").contains("\"AGENTS.db\""));
+        let lib = get("/api/roots/lib/layers");
+        assert!(lib.contains("\"AGENTS.local.db\"") && !lib.contains("\"AGENTS.db\""));
+        assert!(get("/api/roots/nope/layers").starts_with("HTTP/1.1 404"));
+        server.shutdown().expect("shutdown");
+
+        assert!(Roots::new(vec![
+            ("x".to_string(), ServerState::new(a.path().to_path_buf())),
+            ("x".to_string(), ServerState::new(b.path().to_path_buf())),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn typed_write_errors_map_to_stable_status_and_code() {
+        let root = tempfile::tempdir().expect("tempdir");
+        write_layer_with_custom_profile(&root.path().join("AGENTS.local.db"), 8, OutputNorm::L2);
+        let specs = [root.path().display().to_string()];
+        let server = start_roots(&specs, "127.0.0.1:0", WebAuth::default()).expect("start");
+        let post = |path: &str, body: &str| -> String {
+            let mut client = TcpStream::connect(server.local_addr()).expect("connect");
+            write!(
+                client,
+                "POST {path} HTTP/1.1\r\nHost: x\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            )
+            .expect("send");
+            let mut out = String::new();
+            client.read_to_string(&mut out).expect("read");
+            out
+        };
+
+        let out = post(
+            "/api/layer/add",
+            r#"{"scope":"local","kind":"note","content":"hello","confidence":1.0}"#,
+        );
+        assert!(out.starts_with("HTTP/1.1 409"), "{out}");
+        assert!(out.contains(r#""code":"profile_mismatch""#), "{out}");
+
+        let out = post(
+            "/api/layer/add",
+            r#"{"scope":"delta","kind":"note","content":"hello","confidence":1.0}"#,
+        );
+        assert!(out.starts_with("HTTP/1.1 400"), "{out}");
+        assert!(out.contains(r#""code":"dim_required""#), "{out}");
+        server.shutdown().expect("shutdown");
+    }
+
+    #[test]
+    fn uploaded_layers_are_validated_before_landing_under_root() {
+        let root = tempfile::tempdir().expect("tempdir");
+        let src = tempfile::tempdir().expect("tempdir");
+        write_layer_with_custom_profile(&root.path().join("AGENTS.db"), 8, OutputNorm::None);
+        write_layer_with_custom_profile(&src.path().join("ok.db"), 8, OutputNorm::None);
+        write_layer_with_custom_profile(&src.path().join("wide.db"), 16, OutputNorm::None);
+        let ok = std::fs::read(src.path().join("ok.db")).expect("read");
+        let wide = std::fs::read(src.path().join("wide.db")).expect("read");
+
+        let listed = upload_layer(root.path(), "AGENTS.local.db", &ok, false).expect("upload");
+        assert_eq!(listed.chunk_count, 1);
+        assert!(root.path().join("AGENTS.local.db").exists());
+        assert!(upload_layer(root.path(), "AGENTS.local.db", &ok, false).is_err());
+        upload_layer(root.path(), "AGENTS.local.db", &ok, true).expect("overwrite");
+        let err = upload_layer(root.path(), "AGENTS.db", &ok, true).expect_err("base");
+        assert_eq!(agentsdb_ops::error_code(&err), Some("readonly"));
+
+        assert!(upload_layer(root.path(), "AGENTS.delta.db", b"not a layer", false).is_err());
+        let err = upload_layer(root.path(), "AGENTS.delta.db", &wide, false).expect_err("dim");
+        assert!(err.to_string().contains("embedding dimension"), "{err}");
+        assert!(!root.path().join("AGENTS.delta.db").exists());
+        assert!(!root.path().join("AGENTS.delta.db.tmp").exists());
+
+        // Two uploads can stage side by side; whichever lands second re-checks the target.
+        let first = stage_upload(root.path(), "AGENTS.delta.db", &ok, false).expect("stage");
+        let second = stage_upload(root.path(), "AGENTS.delta.db", &ok, false).expect("stage");
+        assert!(!root.path().join("AGENTS.delta.db").exists());
+        first.land().expect("land");
+        let tmp = second.tmp.clone();
+        assert!(second.land().is_err());
+        assert!(!tmp.exists());
+
+        let mut body =
+            b"--xyz\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.db\"\r\n\r\n"
+                .to_vec();
+        body.extend_from_slice(&ok);
+        body.extend_from_slice(b"\r\n--xyz--\r\n");
+        let (name, bytes) =
+            multipart_file("multipart/form-data; boundary=xyz", &body).expect("part");
+        assert_eq!(name.as_deref(), Some("a.db"));
+        assert_eq!(bytes, ok.as_slice());
+    }
+
+    #[test]
+    fn metrics_count_requests_and_report_layer_sizes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        write_layer_with_custom_profile(&dir.path().join("AGENTS.db"), 8, OutputNorm::None);
+        let spec = format!("proj={}", dir.path().display());
+        let server = start_roots(&[spec], "127.0.0.1:0", WebAuth::default()).expect("start");
+        let get = |path: &str| -> String {
+            let mut client = TcpStream::connect(server.local_addr()).expect("connect");
+            write!(
+                client,
+                "GET {path} HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n"
+            )
+            .expect("send");
+            let mut out = String::new();
+            client.read_to_string(&mut out).expect("read");
+            out
+        };
+
+        get("/api/layers");
+        get("/no/such/asset");
+        let text = get("/metrics");
+        assert!(
+            text.contains("Content-Type: text/plain; version=0.0.4"),
+            "{text}"
+        );
+        assert!(text.contains(
+            "agentsdb_http_requests_total{method=\"GET\",route=\"/api/layers\",status=\"200\"}"
+        ));
+        assert!(text.contains("route=\"unmatched\",status=\"404\"}"));
+        assert!(text.contains("# TYPE agentsdb_http_request_duration_seconds histogram"));
+        assert!(text.contains("agentsdb_layer_chunks{root=\"proj\",layer=\"AGENTS.db\"} 1\n"));
+        server.shutdown().expect("shutdown");
+    }
+
+    #[test]
+    fn shutdown_drains_connections_and_returns() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        write_layer_with_custom_profile(&dir.path().join("AGENTS.db"), 8, OutputNorm::None);
+        let root = dir.path().to_string_lossy().into_owned();
+        let server = start(&root, "127.0.0.1:0", WebAuth::default()).expect("start");
+        let addr = server.local_addr();
+
+        // An open event stream and an idle keep-alive connection must not block shutdown.
+        let mut events = TcpStream::connect(addr).expect("connect events");
+        write!(events, "GET /api/events HTTP/1.1\r\nHost: x\r\n\r\n").expect("send");
+        let mut head = [0u8; 15];
+        events.read_exact(&mut head).expect("read events head");
+        assert_eq!(&head, b"HTTP/1.1 200 OK");
+        let _idle = TcpStream::connect(addr).expect("connect idle");
+
+        let started = Instant::now();
+        server.shutdown().expect("shutdown");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(agentsdb_ops::DecayState::path_for(dir.path()).exists());
+        assert!(TcpStream::connect(addr).is_err());
+    }
+
+    #[test]
+    fn json_bodies_are_compressed_when_accepted() {
+        assert_eq!(
+            ContentEncoding::negotiate("gzip, deflate, br"),
+            Some(ContentEncoding::Gzip)
+        );
+        assert_eq!(
+            ContentEncoding::negotiate("gzip;q=0, deflate"),
+            Some(ContentEncoding::Deflate)
+        );
+        assert_eq!(ContentEncoding::negotiate("br, identity"), None);
+
+        let body = serde_json::to_vec(&vec!["chunk"; 500]).expect("json");
+        let gz = ContentEncoding::Gzip.encode(&body).expect("gzip");
+        assert!(gz.len() < body.len());
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(gz.as_slice())
+            .read_to_end(&mut decoded)
+            .expect("gunzip");
+        assert_eq!(decoded, body);
+    }
+
+    #[test]
+    fn search_query_params_parse_into_search_input() {
+        let query: HashMap<String, String> = [
+            ("q", "release process"),
+            ("k", "3"),
+            ("layers", "AGENTS.db, AGENTS.local.db"),
+            ("kind", "note"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let input = search_input_from_query(&query).expect("parse");
+        assert_eq!(input.query, "release process");
+        assert_eq!(input.k, Some(3));
+        assert_eq!(input.layers, ["AGENTS.db", "AGENTS.local.db"]);
+        assert_eq!(input.kinds, Some(vec!["note".to_string()]));
+
+        assert!(search_input_from_query(&HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn chunk_filter_matches_content_author_and_confidence_and_sorts() {
+        let summary =
+            |id: u32, author: &str, confidence: f32, created: u64, content: &str| ChunkSummary {
+                id,
+                kind: "note".to_string(),
+                author: author.to_string(),
+                confidence,
+                created_at_unix_ms: created,
+                source_count: 0,
+                namespace: (author == "human").then(|| "app".to_string()),
+                language: None,
+                identity: None,
+                content_preview: String::new(),
+                content: content.to_string(),
+            };
+        let summaries = vec![
+            summary(1, "human", 0.9, 300, "Release checklist"),
+            summary(2, "mcp", 0.4, 100, "release notes draft"),
+            summary(3, "human", 0.6, 200, "Unrelated"),
+        ];
+        let parse = |pairs: &[(&str, &str)]| {
+            let query: HashMap<String, String> = pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            ChunkFilter::from_query(&query)
+        };
+        let filter = |pairs: &[(&str, &str)]| parse(pairs).expect("filter");
+        let ids = |order: Vec<usize>| order.iter().map(|&i| summaries[i].id).collect::<Vec<_>>();
+
+        assert_eq!(ids(filter(&[("q", "RELEASE")]).apply(&summaries)), [1, 2]);
+        assert_eq!(
+            ids(filter(&[("q", "^release"), ("regex", "1")]).apply(&summaries)),
+            [2]
+        );
+        assert_eq!(
+            ids(filter(&[("author", "human"), ("min_confidence", "0.7")]).apply(&summaries)),
+            [1]
+        );
+        assert_eq!(
+            ids(filter(&[("sort", "created_at")]).apply(&summaries)),
+            [1, 3, 2]
+        );
+        assert_eq!(
+            ids(filter(&[("sort", "confidence"), ("order", "asc")]).apply(&summaries)),
+            [2, 3, 1]
+        );
+
+        assert_eq!(
+            ids(filter(&[("namespace", "app")]).apply(&summaries)),
+            [1, 3]
+        );
+
+        assert!(parse(&[("sort", "kind")]).is_err());
+        assert!(parse(&[("q", "("), ("regex", "1")]).is_err());
+    }
+
+    #[test]
+    fn chunk_page_json_splices_cached_items() {
+        let summary = |id: u32| ChunkSummary {
+            id,
+            kind: "note".to_string(),
+            author: "human".to_string(),
+            confidence: 0.5,
+            created_at_unix_ms: 7,
+            source_count: 1,
+            namespace: None,
+            language: Some("en".to_string()),
+            identity: None,
+            content_preview: format!("chunk \"{id}\""),
+            content: String::new(),
+        };
+        let summaries = [summary(1), summary(2), summary(3)];
+        let json: Vec<Box<str>> = summaries
+            .iter()
+            .map(|s| serde_json::to_string(s).expect("json").into_boxed_str())
+            .collect();
+
+        let body = chunk_page_json(3, 1, 5, &json, &[2, 0]);
+        let parsed: serde_json::Value = serde_json::from_slice(&body).expect("valid JSON");
+        assert_eq!(
+            parsed,
+            serde_json::json!({
+                "total": 3,
+                "offset": 1,
+                "limit": 5,
+                "items": [summaries[2], summaries[0]],
+            })
+        );
+
+        let empty = chunk_page_json(0, 0, 100, &json, &[]);
+        assert_eq!(empty, br#"{"total":0,"offset":0,"limit":100,"items":[]}"#);
+    }
+
+    #[test]
+    fn changed_layers_reports_created_modified_and_removed_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("AGENTS.db"), b"a").expect("write");
+        std::fs::write(dir.path().join("AGENTS.local.db"), b"a").expect("write");
+        std::fs::write(dir.path().join("notes.txt"), b"a").expect("write");
+        let before = layer_stamps(dir.path());
+        assert_eq!(before.len(), 2);
+
+        std::fs::write(dir.path().join("AGENTS.local.db"), b"ab").expect("write");
+        std::fs::remove_file(dir.path().join("AGENTS.db")).expect("remove");
+        std::fs::write(dir.path().join("AGENTS.delta.db"), b"a").expect("write");
+        let after = layer_stamps(dir.path());
+
+        let mut changed = changed_layers(&before, &after);
+        changed.sort();
+        assert_eq!(
+            changed,
+            [
+                ("AGENTS.db".to_string(), false),
+                ("AGENTS.delta.db".to_string(), true),
+                ("AGENTS.local.db".to_string(), true),
+            ]
+        );
+        assert!(changed_layers(&after, &after).is_empty());
+    }
+
     #[test]
     fn frontend_is_embedded() {
         // Verify that the frontend dist folder is embedded at compile time
@@ -2077,6 +4824,231 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_supersedes_chunk_with_revision_and_tombstone() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let base_path = dir.path().join("AGENTS.db");
+        let local_path = dir.path().join("AGENTS.local.db");
+        let delta_path = dir.path().join("AGENTS.delta.db");
+        write_layer_with_custom_profile(&base_path, 8, OutputNorm::None);
+        write_layer_with_custom_profile(&local_path, 8, OutputNorm::None);
+
+        let supersede = |source: &Path, target: &Path| {
+            agentsdb_ops::supersede_chunk(
+                source,
+                target,
+                1,
+                None,
+                "revised seed",
+                Some(0.5),
+                &["docs/seed.md".to_string()],
+                None,
+                "agentsdb-web",
+                "test",
+            )
+        };
+        // A delta tombstone cannot hide a chunk in the higher-precedence local layer.
+        assert!(supersede(&local_path, &delta_path).is_err());
+
+        let outcome = supersede(&base_path, &delta_path).expect("supersede");
+        assert_eq!(outcome.superseded_id, 1);
+        let file = agentsdb_format::LayerFile::open(&delta_path).expect("open delta");
+        let chunks = agentsdb_format::read_all_chunks(&file).expect("read delta");
+        let revised = chunks
+            .iter()
+            .find(|c| c.id == outcome.id)
+            .expect("revision");
+        assert_eq!(revised.kind, "note");
+        assert_eq!(revised.content, "revised seed");
+        assert!((revised.confidence - 0.5).abs() < f32::EPSILON);
+        assert!(revised.sources.iter().any(
+            |s| matches!(s, agentsdb_format::ChunkSource::SourceString(v) if v == "supersedes:1")
+        ));
+        let tombstone = chunks
+            .iter()
+            .find(|c| c.id == outcome.tombstone_id)
+            .expect("tombstone");
+        assert_eq!(tombstone.kind, agentsdb_query::KIND_TOMBSTONE);
+        assert!(matches!(
+            tombstone.sources.as_slice(),
+            [agentsdb_format::ChunkSource::ChunkId(1)]
+        ));
+    }
+
+    #[test]
+    fn chunk_history_follows_supersedes_chain_and_tombstones() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let local_path = dir.path().join("AGENTS.local.db");
+        write_layer_with_custom_profile(&local_path, 8, OutputNorm::None);
+        let supersede = |id: u32, content: &str| {
+            agentsdb_ops::supersede_chunk(
+                &local_path,
+                &local_path,
+                id,
+                None,
+                content,
+                None,
+                &[],
+                None,
+                "agentsdb-web",
+                "test",
+            )
+            .expect("supersede")
+        };
+        let first = supersede(1, "second draft");
+        let second = supersede(first.id, "third draft");
+
+        let history =
+            chunk_history(dir.path(), "AGENTS.local.db", &local_path, 1).expect("history");
+        let relations: Vec<(&str, u32)> = history
+            .entries
+            .iter()
+            .map(|e| (e.relation, e.chunk.id))
+            .collect();
+        assert_eq!(
+            relations,
+            [
+                ("version", 1),
+                ("revision", first.id),
+                ("tombstone", first.tombstone_id),
+                ("revision", second.id),
+                ("tombstone", second.tombstone_id),
+            ]
+        );
+        assert_eq!(history.current_id, Some(second.id));
+
+        let latest =
+            chunk_history(dir.path(), "AGENTS.local.db", &local_path, second.id).expect("history");
+        assert!(latest
+            .entries
+            .iter()
+            .any(|e| e.relation == "earlier_revision" && e.chunk.id == 1));
+        assert_eq!(latest.current_id, Some(second.id));
+        assert!(chunk_history(dir.path(), "AGENTS.local.db", &local_path, 99).is_err());
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_chunks() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let write = |name: &str, chunks: &[(u32, &str)]| {
+            let schema = agentsdb_format::LayerSchema {
+                dim: 4,
+                element_type: agentsdb_format::EmbeddingElementType::F32,
+                quant_scale: 1.0,
+                layout: agentsdb_format::EmbeddingLayout::RowMajor,
+            };
+            let mut chunks: Vec<_> = chunks
+                .iter()
+                .map(|(id, content)| agentsdb_format::ChunkInput {
+                    id: *id,
+                    kind: "note".to_string(),
+                    content: (*content).to_string(),
+                    author: "human".to_string(),
+                    confidence: 1.0,
+                    created_at_unix_ms: 0,
+                    embedding: vec![0.0; 4],
+                    sources: Vec::new(),
+                })
+                .collect();
+            agentsdb_format::write_layer_atomic(&dir.path().join(name), &schema, &mut chunks, None)
+                .expect("write layer");
+        };
+        write(
+            "AGENTS.delta.db",
+            &[(1, "same"), (2, "draft"), (2, "revised"), (3, "new")],
+        );
+        write("AGENTS.user.db", &[(1, "same"), (2, "draft"), (4, "old")]);
+
+        let diff = agentsdb_ops::diff_layers(
+            &dir.path().join("AGENTS.delta.db"),
+            &dir.path().join("AGENTS.user.db"),
+        )
+        .expect("diff");
+        let ids = |v: &[agentsdb_ops::diff::ChunkDiff]| v.iter().map(|c| c.id).collect::<Vec<_>>();
+        assert_eq!(ids(&diff.added), [3]);
+        assert_eq!(ids(&diff.removed), [4]);
+        assert_eq!(ids(&diff.changed), [2]);
+        assert_eq!(diff.unchanged, 1);
+        let changed = &diff.changed[0];
+        assert_eq!(
+            changed.from.as_ref().map(|s| s.content.as_str()),
+            Some("revised")
+        );
+        assert_eq!(
+            changed.to.as_ref().map(|s| s.content.as_str()),
+            Some("draft")
+        );
+
+        let missing = agentsdb_ops::diff_layers(
+            &dir.path().join("AGENTS.delta.db"),
+            &dir.path().join("AGENTS.local.db"),
+        )
+        .expect("diff against missing layer");
+        assert_eq!(ids(&missing.added), [1, 2, 3]);
+    }
+
+    #[test]
+    fn index_build_reports_fresh_then_stale_after_layer_change() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let layer_path = dir.path().join("AGENTS.db");
+        write_layer_with_custom_profile(&layer_path, 8, OutputNorm::None);
+
+        let status = index_status(&layer_path).expect("status");
+        assert!(!status.exists && !status.fresh);
+        assert_eq!(status.path, "AGENTS.db.agix");
+
+        let input = IndexBuildInput {
+            path: "AGENTS.db".to_string(),
+            store_embeddings_f32: false,
+            binary_codes: false,
+        };
+        let status = build_index(&layer_path, &input).expect("build");
+        assert!(status.exists && status.fresh);
+        assert!(status.size_bytes.unwrap_or(0) > 0);
+
+        write_layer_with_custom_profile(&layer_path, 4, OutputNorm::None);
+        let status = index_status(&layer_path).expect("status");
+        assert!(status.exists && !status.fresh);
+    }
+
+    #[test]
+    fn batch_retract_appends_all_tombstones_at_once() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let base_path = dir.path().join("AGENTS.db");
+        write_layer_with_custom_profile(&base_path, 8, OutputNorm::None);
+        let mut st = ServerState::new(dir.path().to_path_buf());
+        let batch = |json: serde_json::Value| -> BatchInput {
+            serde_json::from_value(json).expect("batch input")
+        };
+
+        assert!(run_batch(
+            &mut st,
+            &batch(serde_json::json!({ "action": "retract", "path": "AGENTS.db", "ids": [1, 9] }))
+        )
+        .is_err());
+        assert!(!dir.path().join("AGENTS.delta.db").exists());
+        assert!(run_batch(
+            &mut st,
+            &batch(serde_json::json!({ "action": "archive", "path": "AGENTS.db", "ids": [1] }))
+        )
+        .is_err());
+
+        let out = run_batch(
+            &mut st,
+            &batch(serde_json::json!({
+                "action": "retract", "path": "AGENTS.db", "ids": [1], "reason": "outdated"
+            })),
+        )
+        .expect("retract");
+        assert_eq!(out["result"]["path"], "AGENTS.delta.db");
+        let file = agentsdb_format::LayerFile::open(dir.path().join("AGENTS.delta.db"))
+            .expect("open delta");
+        let chunks = agentsdb_format::read_all_chunks(&file).expect("read delta");
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].kind, agentsdb_query::KIND_TOMBSTONE);
+        assert_eq!(chunks[0].content, "outdated");
+    }
+
     #[test]
     fn add_chunk_with_delta_scope_writes_to_delta_db() {
         let dir = tempfile::tempdir().expect("tempdir");
@@ -0,0 +1,29 @@
+//! Searches the project in the current directory, then records a note and proposes it.
+//!
+//! ```text
+//! cargo run -p agentsdb --example remember -- "how are releases cut?" "Releases are tagged from main."
+//! ```
+
+use agentsdb::{Entry, Proposal, Query, Scope, Store};
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let question = args
+        .next()
+        .unwrap_or_else(|| "how are releases cut?".to_string());
+
+    let store = Store::open(".")?;
+    for hit in store.search(Query::new(question).k(5))? {
+        println!(
+            "[{}] #{} {:.3} {}",
+            hit.layer, hit.id, hit.score, hit.content
+        );
+    }
+
+    if let Some(note) = args.next() {
+        let id = store.write(Scope::Delta, &Entry::new("note", note))?;
+        let proposal = store.propose(&Proposal::new(id).why("answered a question"))?;
+        println!("wrote #{id} to delta; proposal #{proposal}");
+    }
+    Ok(())
+}
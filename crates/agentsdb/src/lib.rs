@@ -0,0 +1,529 @@
+//! Open, search and write the AGENTS.db layers of a project from Rust.
+//!
+//! [`Store`] wraps the standard layers of one directory (`AGENTS.db`, `AGENTS.user.db`,
+//! `AGENTS.delta.db`, `AGENTS.local.db`) and exposes the everyday operations the CLI and MCP
+//! server are built from. The types here are the crate's stable surface; the `agentsdb-*` crates
+//! underneath may change between minor versions.
+//!
+//! ```no_run
+//! use agentsdb::{Entry, Proposal, Query, Scope, Store};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let store = Store::open(".")?;
+//!
+//! for hit in store.search(Query::new("how are releases cut?").k(3))? {
+//!     println!("[{}] {:.3} {}", hit.layer, hit.score, hit.content);
+//! }
+//!
+//! let id = store.write(Scope::Delta, &Entry::new("note", "Releases are tagged from main."))?;
+//! store.propose(&Proposal::new(id).title("Release process"))?;
+//! store.promote(&[id])?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Context;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use agentsdb_core::types::{Author, LayerId, ProvenanceRef};
+use agentsdb_embeddings::config::{standard_layer_paths_for_dir, StandardLayerPaths};
+use agentsdb_embeddings::settings::{default_config_file, resolve_settings};
+use agentsdb_ops::proposals::{append_propose_event, NewProposal};
+use agentsdb_ops::{append_chunk, promote_chunks, search_layers, SearchConfig};
+use agentsdb_query::{LayerSet, SearchMode};
+
+pub use agentsdb_format::WritePolicy;
+
+const TOOL_NAME: &str = "agentsdb";
+
+/// A layer of a [`Store`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Layer {
+    /// `AGENTS.db`, the canonical base layer.
+    Base,
+    /// `AGENTS.user.db`, accepted knowledge.
+    User,
+    /// `AGENTS.delta.db`, proposals awaiting review.
+    Delta,
+    /// `AGENTS.local.db`, notes that never leave the machine.
+    Local,
+}
+
+impl Layer {
+    /// File name of the layer inside a project directory.
+    pub const fn file_name(self) -> &'static str {
+        match self {
+            Self::Base => "AGENTS.db",
+            Self::User => "AGENTS.user.db",
+            Self::Delta => "AGENTS.delta.db",
+            Self::Local => "AGENTS.local.db",
+        }
+    }
+
+    const fn from_id(id: LayerId) -> Self {
+        match id {
+            LayerId::Base => Self::Base,
+            LayerId::User => Self::User,
+            LayerId::Delta => Self::Delta,
+            LayerId::Local | LayerId::Session => Self::Local,
+        }
+    }
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Base => "base",
+            Self::User => "user",
+            Self::Delta => "delta",
+            Self::Local => "local",
+        })
+    }
+}
+
+/// A layer [`Store::write`] may append to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    Local,
+    Delta,
+}
+
+impl Scope {
+    const fn layer(self) -> Layer {
+        match self {
+            Self::Local => Layer::Local,
+            Self::Delta => Layer::Delta,
+        }
+    }
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Delta => "delta",
+        }
+    }
+}
+
+/// Where a chunk came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Another chunk, by id.
+    Chunk(u32),
+    /// A free-form reference, e.g. `src/lib.rs:42`.
+    Text(String),
+}
+
+/// A search request for [`Store::search`]. Unset fields fall back to the project's `search.*`
+/// settings.
+#[derive(Debug, Clone)]
+pub struct Query {
+    text: String,
+    k: Option<usize>,
+    kinds: Vec<String>,
+    namespace: Option<String>,
+    language: Option<String>,
+    semantic: Option<bool>,
+}
+
+impl Query {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            k: None,
+            kinds: Vec::new(),
+            namespace: None,
+            language: None,
+            semantic: None,
+        }
+    }
+
+    /// Number of results to return.
+    pub const fn k(mut self, k: usize) -> Self {
+        self.k = Some(k);
+        self
+    }
+
+    /// Only return chunks of this kind; may be given more than once.
+    pub fn kind(mut self, kind: impl Into<String>) -> Self {
+        self.kinds.push(kind.into());
+        self
+    }
+
+    /// Only return chunks in this namespace.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Only return chunks in this language; `de` also matches `de-AT`.
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Rank by embedding similarity only, without the lexical half of hybrid search.
+    pub const fn semantic(mut self) -> Self {
+        self.semantic = Some(true);
+        self
+    }
+}
+
+impl From<&str> for Query {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for Query {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+/// A chunk returned by [`Store::search`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Hit {
+    pub id: u32,
+    pub layer: Layer,
+    pub score: f32,
+    pub kind: String,
+    pub content: String,
+    /// `human` or `mcp`.
+    pub author: String,
+    pub confidence: f32,
+    pub created_at_unix_ms: u64,
+    pub sources: Vec<Source>,
+}
+
+/// A chunk for [`Store::write`].
+#[derive(Debug, Clone)]
+pub struct Entry {
+    kind: String,
+    content: String,
+    confidence: f32,
+    sources: Vec<String>,
+    source_chunks: Vec<u32>,
+}
+
+impl Entry {
+    pub fn new(kind: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            content: content.into(),
+            confidence: 1.0,
+            sources: Vec::new(),
+            source_chunks: Vec::new(),
+        }
+    }
+
+    /// Confidence between 0.0 and 1.0 (default 1.0).
+    pub const fn confidence(mut self, confidence: f32) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Records where the content came from, e.g. `src/lib.rs:42`.
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.sources.push(source.into());
+        self
+    }
+
+    /// Records a chunk the content was derived from.
+    pub fn derived_from(mut self, id: u32) -> Self {
+        self.source_chunks.push(id);
+        self
+    }
+}
+
+/// A request to promote a delta chunk to the user layer, for [`Store::propose`].
+#[derive(Debug, Clone)]
+pub struct Proposal {
+    inner: NewProposal,
+}
+
+impl Proposal {
+    /// Proposes the delta chunk `context_id`.
+    pub fn new(context_id: u32) -> Self {
+        Self {
+            inner: NewProposal {
+                context_id,
+                ..NewProposal::default()
+            },
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.inner.title = Some(title.into());
+        self
+    }
+
+    /// Why the chunk should be accepted.
+    pub fn why(mut self, why: impl Into<String>) -> Self {
+        self.inner.why = Some(why.into());
+        self
+    }
+
+    /// What the chunk changes.
+    pub fn what(mut self, what: impl Into<String>) -> Self {
+        self.inner.what = Some(what.into());
+        self
+    }
+
+    /// Where the change applies, e.g. a path or component.
+    pub fn location(mut self, location: impl Into<String>) -> Self {
+        self.inner.where_ = Some(location.into());
+        self
+    }
+}
+
+/// Result of [`Store::promote`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Promotion {
+    /// Delta ids copied to the user layer.
+    pub promoted: Vec<u32>,
+    /// Delta ids left out, e.g. because the user layer already holds them.
+    pub skipped: Vec<u32>,
+}
+
+/// The standard layers of a project directory.
+#[derive(Debug, Clone)]
+pub struct Store {
+    root: PathBuf,
+    paths: StandardLayerPaths,
+    policy: WritePolicy,
+}
+
+impl Store {
+    /// Opens the layers in `root`. Missing layer files are fine; writes create them.
+    pub fn open(root: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let root = root.as_ref();
+        if !root.is_dir() {
+            anyhow::bail!("{} is not a directory", root.display());
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            paths: standard_layer_paths_for_dir(root),
+            policy: WritePolicy::standard().allow_user(),
+        })
+    }
+
+    /// Replaces the policy the store checks before writing a layer. The default allows the
+    /// local, delta and user layers and protects `AGENTS.db`.
+    #[must_use]
+    pub fn with_write_policy(mut self, policy: WritePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Directory the store was opened in.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of `layer`, whether or not the file exists.
+    pub fn layer_path(&self, layer: Layer) -> &Path {
+        match layer {
+            Layer::Base => &self.paths.base,
+            Layer::User => &self.paths.user,
+            Layer::Delta => &self.paths.delta,
+            Layer::Local => &self.paths.local,
+        }
+    }
+
+    fn existing(&self, layer: Layer) -> Option<String> {
+        let path = self.layer_path(layer);
+        path.exists().then(|| path.display().to_string())
+    }
+
+    fn layers(&self) -> LayerSet {
+        LayerSet {
+            base: self.existing(Layer::Base),
+            user: self.existing(Layer::User),
+            delta: self.existing(Layer::Delta),
+            local: self.existing(Layer::Local),
+        }
+    }
+
+    /// Searches every existing layer; a chunk in a higher layer hides the same id below it.
+    pub fn search(&self, query: impl Into<Query>) -> anyhow::Result<Vec<Hit>> {
+        let query = query.into();
+        let layers = self.layers();
+        if [&layers.base, &layers.user, &layers.delta, &layers.local]
+            .iter()
+            .all(|p| p.is_none())
+        {
+            anyhow::bail!("no AGENTS.db layers in {}", self.root.display());
+        }
+
+        let config_file = default_config_file(&self.root);
+        let defaults = resolve_settings(&self.paths, config_file.as_deref())
+            .context("resolve settings")?
+            .search();
+        let semantic = query
+            .semantic
+            .unwrap_or_else(|| defaults.mode.eq_ignore_ascii_case("semantic"));
+        let config = SearchConfig {
+            query: Some(query.text),
+            query_vec: None,
+            k: query.k.unwrap_or(defaults.k),
+            kinds: query.kinds,
+            namespace: query.namespace,
+            language: query.language,
+            use_index: defaults.use_index,
+            mode: if semantic {
+                SearchMode::Semantic
+            } else {
+                SearchMode::Hybrid
+            },
+            cache_results: false,
+            disk_cache: defaults.disk_cache,
+        };
+
+        let results = search_layers(&layers, config).context("search")?;
+        Ok(results
+            .into_iter()
+            .map(|r| Hit {
+                id: r.chunk.id.get(),
+                layer: Layer::from_id(r.layer),
+                score: r.score,
+                kind: r.chunk.kind,
+                content: r.chunk.content,
+                author: match r.chunk.author {
+                    Author::Human => "human",
+                    Author::Mcp => "mcp",
+                }
+                .to_string(),
+                confidence: r.chunk.confidence,
+                created_at_unix_ms: r.chunk.created_at_unix_ms,
+                sources: r
+                    .chunk
+                    .sources
+                    .into_iter()
+                    .map(|s| match s {
+                        ProvenanceRef::ChunkId(id) => Source::Chunk(id.get()),
+                        ProvenanceRef::SourceString(s) => Source::Text(s),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// Appends `entry` to the `scope` layer, creating it with the other layers' embedding
+    /// dimension if needed.
+    ///
+    /// # Returns
+    /// The id of the new chunk
+    pub fn write(&self, scope: Scope, entry: &Entry) -> anyhow::Result<u32> {
+        let dim = self
+            .layers()
+            .open()
+            .ok()
+            .and_then(|opened| opened.first().map(|(_, f)| f.embedding_dim()))
+            .and_then(|d| u32::try_from(d).ok());
+        let path = self.layer_path(scope.layer());
+        self.policy.check(path).context("permission check")?;
+        Ok(append_chunk(
+            path,
+            scope.as_str(),
+            None,
+            &entry.kind,
+            &entry.content,
+            entry.confidence,
+            dim,
+            &entry.sources,
+            &entry.source_chunks,
+            TOOL_NAME,
+            env!("CARGO_PKG_VERSION"),
+        )?)
+    }
+
+    /// Records a proposal to promote a delta chunk to the user layer, for review with
+    /// `agentsdb proposals`.
+    ///
+    /// # Returns
+    /// The proposal id
+    pub fn propose(&self, proposal: &Proposal) -> anyhow::Result<u32> {
+        let delta = self.layer_path(Layer::Delta);
+        if !delta.exists() {
+            anyhow::bail!("delta layer file not found at {}", delta.display());
+        }
+        self.policy.check(delta).context("permission check")?;
+        append_propose_event(delta, Layer::User.file_name(), &proposal.inner, "human")
+    }
+
+    /// Copies delta chunks to the user layer, subject to the project's promotion policy.
+    pub fn promote(&self, ids: &[u32]) -> anyhow::Result<Promotion> {
+        self.policy
+            .check(self.layer_path(Layer::User))
+            .context("permission check")?;
+        let outcome = promote_chunks(
+            &self.layer_path(Layer::Delta).display().to_string(),
+            &self.layer_path(Layer::User).display().to_string(),
+            ids,
+            false,
+        )?;
+        Ok(Promotion {
+            promoted: outcome.promoted,
+            skipped: outcome.skipped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_propose_promote_and_search() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let schema = agentsdb_format::LayerSchema {
+            dim: 8,
+            element_type: agentsdb_format::EmbeddingElementType::F32,
+            quant_scale: 1.0,
+            layout: agentsdb_format::EmbeddingLayout::RowMajor,
+        };
+        agentsdb_format::write_layer_atomic(&dir.path().join("AGENTS.db"), &schema, &mut [], None)?;
+
+        let store = Store::open(dir.path())?;
+        let id = store.write(
+            Scope::Delta,
+            &Entry::new("note", "Releases are tagged from main.").source("RELEASING.md"),
+        )?;
+        let proposal = store.propose(&Proposal::new(id).title("Release process"))?;
+        assert_ne!(proposal, id);
+        let states = agentsdb_ops::proposals::load_proposal_states(store.layer_path(Layer::Delta))?;
+        assert_eq!(
+            states.get(&proposal).map(|s| s.context_id),
+            Some(id),
+            "proposal recorded against the written chunk"
+        );
+
+        let promoted = store.promote(&[id])?.promoted;
+        assert_eq!(promoted.len(), 1);
+
+        let hits = store.search(Query::new("releases tagged").kind("note").k(5))?;
+        let hit = hits
+            .iter()
+            .find(|h| h.layer == Layer::User && h.id == promoted[0])
+            .expect("promoted chunk is searchable in the user layer");
+        assert_eq!(hit.content, "Releases are tagged from main.");
+        assert_eq!(hit.sources, vec![Source::Text("RELEASING.md".to_string())]);
+        Ok(())
+    }
+
+    #[test]
+    fn write_policy_guards_store_writes() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = Store::open(dir.path())?.with_write_policy(WritePolicy::read_only());
+        let err = store
+            .write(Scope::Local, &Entry::new("note", "blocked").confidence(0.5))
+            .expect_err("read-only policy refuses writes");
+        assert!(format!("{err:#}").contains("permission check"));
+        assert!(!store.layer_path(Layer::Local).exists());
+        Ok(())
+    }
+}